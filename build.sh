@@ -3,18 +3,34 @@
 # Script Name: build.sh
 # Description: Downloads required model files and builds the Python SDK wheel package.
 # Usage: Run this script inside the distiller-cm5-sdk directory.
-#        To include Whisper model download, run: ./build.sh.sh --whisper
+#        To include Whisper model download, run: ./build.sh --whisper
+#        For a minimal display/LED-only wheel without the ASR/TTS model and
+#        binary downloads (notably the GPL-licensed espeak-ng bundled with
+#        Piper), run: ./build.sh --minimal
+#        To build only the single-file recovery UI bundle
+#        (dist/distiller-recovery.pyz), run: ./build.sh --recovery
 
 set -e
 
 # Parse arguments
 INCLUDE_WHISPER=false
+MINIMAL=false
+RECOVERY=false
 for arg in "$@"; do
     if [ "$arg" == "--whisper" ]; then
         INCLUDE_WHISPER=true
+    elif [ "$arg" == "--minimal" ]; then
+        MINIMAL=true
+    elif [ "$arg" == "--recovery" ]; then
+        RECOVERY=true
     fi
 done
 
+if [ "$MINIMAL" = true ] && [ "$INCLUDE_WHISPER" = true ]; then
+    echo "[ERROR] --minimal and --whisper cannot be combined"
+    exit 1
+fi
+
 # Helper function to create directory if it does not exist
 make_dir_if_not_exists() {
     if [ ! -d "$1" ]; then
@@ -23,6 +39,57 @@ make_dir_if_not_exists() {
     fi
 }
 
+# Recovery UI bundle: a single executable zip of the pure-Python SDK modules it
+# uses. Runs on the python display backend, so no compiled library is needed.
+build_recovery_bundle() {
+    STAGE_DIR="build/recovery"
+    SDK_DIR="src/distiller_cm5_sdk"
+    EINK_DIR="$STAGE_DIR/distiller_cm5_sdk/hardware/eink"
+
+    rm -rf "$STAGE_DIR"
+    make_dir_if_not_exists "$EINK_DIR/backends"
+    make_dir_if_not_exists "dist"
+
+    cp "$SDK_DIR/__init__.py" "$STAGE_DIR/distiller_cm5_sdk/"
+    touch "$STAGE_DIR/distiller_cm5_sdk/hardware/__init__.py"
+    cp "$SDK_DIR"/hardware/eink/{__init__,client,config,daemon,display,font,ghosting,graphics,group,idle,imagecache,imaging,layout,markdown,panels,patterns,pngcodec,qr,registers,reload,textstream,thermal,usage}.py "$EINK_DIR/"
+    cp "$SDK_DIR"/hardware/eink/backends/*.py "$EINK_DIR/backends/"
+    cp -r "$SDK_DIR/device" "$SDK_DIR/diagnostics" "$SDK_DIR/recovery" "$STAGE_DIR/distiller_cm5_sdk/"
+    find "$STAGE_DIR" -name "_*_test.py" -delete
+    find "$STAGE_DIR" -name "__pycache__" -type d -prune -exec rm -rf {} +
+
+    cat > "$STAGE_DIR/__main__.py" <<'PYEOF'
+import sys
+from distiller_cm5_sdk.recovery.__main__ import main
+sys.exit(main())
+PYEOF
+
+    python3 -m zipapp "$STAGE_DIR" -p "/usr/bin/env python3" -o "dist/distiller-recovery.pyz"
+
+    # Import every module from the bundle alone, so a module missing from the
+    # lists above fails the build instead of the recovery UI on a device
+    BUNDLE="$(pwd)/dist/distiller-recovery.pyz"
+    (cd / && python3 - "$BUNDLE" <<'PYEOF'
+import importlib
+import sys
+import zipfile
+
+bundle = sys.argv[1]
+sys.path.insert(0, bundle)
+for name in sorted(zipfile.ZipFile(bundle).namelist()):
+    if name.startswith("distiller_cm5_sdk/") and name.endswith(".py"):
+        module = name[:-3].replace("/", ".")
+        importlib.import_module(module[:-len(".__init__")] if module.endswith(".__init__") else module)
+PYEOF
+    ) || { echo "[ERROR] The recovery bundle is missing modules it imports"; exit 1; }
+    echo "[INFO] Recovery bundle written to dist/distiller-recovery.pyz"
+}
+
+if [ "$RECOVERY" = true ]; then
+    build_recovery_bundle
+    exit 0
+fi
+
 # Helper function to download a file if it does not already exist
 download_if_not_exists() {
     local url="$1"
@@ -55,61 +122,69 @@ else
     echo "[INFO] Skipping Whisper model download (use --whisper to enable)"
 fi
 
-# Parakeet model files
-PARAKEET_DIR="src/distiller_cm5_sdk/parakeet/models"
-make_dir_if_not_exists "$PARAKEET_DIR"
-
-download_if_not_exists "https://huggingface.co/tommy1900/Parakeet-onnx/resolve/main/encoder.onnx" "$PARAKEET_DIR/encoder.onnx"
-download_if_not_exists "https://huggingface.co/tommy1900/Parakeet-onnx/resolve/main/decoder.onnx" "$PARAKEET_DIR/decoder.onnx"
-download_if_not_exists "https://huggingface.co/tommy1900/Parakeet-onnx/resolve/main/joiner.onnx" "$PARAKEET_DIR/joiner.onnx"
-download_if_not_exists "https://huggingface.co/tommy1900/Parakeet-onnx/resolve/main/tokens.txt" "$PARAKEET_DIR/tokens.txt"
-download_if_not_exists "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/silero_vad.onnx" "$PARAKEET_DIR/silero_vad.onnx"
-
-# Piper files
-PIPER_MODEL_DIR="src/distiller_cm5_sdk/piper/models"
-PIPER_EXE_FILE_DIR="src/distiller_cm5_sdk/piper/piper"
-PIPER_EXE_FILE_UNZIP_DIR="src/distiller_cm5_sdk/piper"
-PIPER_TAR="src/distiller_cm5_sdk/piper/piper_arm64.tar.gz"
-make_dir_if_not_exists "$PIPER_MODEL_DIR"
-make_dir_if_not_exists "$PIPER_EXE_FILE_DIR"
-
-# Check for Piper executable files
-PIPER_REQUIRED_FILES=(
-    "libespeak-ng.so.1"
-    "libespeak-ng.so.1.1.51"
-    "libonnxruntime.so.1.14.1"
-    "libpiper_phonemize.so.1"
-    "libpiper_phonemize.so.1.1.0"
-    "libtashkeel_model.ort"
-    "piper"
-)
-
-piper_needs_download=false
-for file in "${PIPER_REQUIRED_FILES[@]}"; do
-    if [ ! -f "$PIPER_EXE_FILE_DIR/$file" ]; then
-        piper_needs_download=true
-        break
+download_speech_models() {
+    # Parakeet model files
+    PARAKEET_DIR="src/distiller_cm5_sdk/parakeet/models"
+    make_dir_if_not_exists "$PARAKEET_DIR"
+
+    download_if_not_exists "https://huggingface.co/tommy1900/Parakeet-onnx/resolve/main/encoder.onnx" "$PARAKEET_DIR/encoder.onnx"
+    download_if_not_exists "https://huggingface.co/tommy1900/Parakeet-onnx/resolve/main/decoder.onnx" "$PARAKEET_DIR/decoder.onnx"
+    download_if_not_exists "https://huggingface.co/tommy1900/Parakeet-onnx/resolve/main/joiner.onnx" "$PARAKEET_DIR/joiner.onnx"
+    download_if_not_exists "https://huggingface.co/tommy1900/Parakeet-onnx/resolve/main/tokens.txt" "$PARAKEET_DIR/tokens.txt"
+    download_if_not_exists "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/silero_vad.onnx" "$PARAKEET_DIR/silero_vad.onnx"
+
+    # Piper files
+    PIPER_MODEL_DIR="src/distiller_cm5_sdk/piper/models"
+    PIPER_EXE_FILE_DIR="src/distiller_cm5_sdk/piper/piper"
+    PIPER_EXE_FILE_UNZIP_DIR="src/distiller_cm5_sdk/piper"
+    PIPER_TAR="src/distiller_cm5_sdk/piper/piper_arm64.tar.gz"
+    make_dir_if_not_exists "$PIPER_MODEL_DIR"
+    make_dir_if_not_exists "$PIPER_EXE_FILE_DIR"
+
+    # Check for Piper executable files
+    PIPER_REQUIRED_FILES=(
+        "libespeak-ng.so.1"
+        "libespeak-ng.so.1.1.51"
+        "libonnxruntime.so.1.14.1"
+        "libpiper_phonemize.so.1"
+        "libpiper_phonemize.so.1.1.0"
+        "libtashkeel_model.ort"
+        "piper"
+    )
+
+    piper_needs_download=false
+    for file in "${PIPER_REQUIRED_FILES[@]}"; do
+        if [ ! -f "$PIPER_EXE_FILE_DIR/$file" ]; then
+            piper_needs_download=true
+            break
+        fi
+    done
+
+    if [ "$piper_needs_download" = true ]; then
+        echo "[INFO] Piper files are incomplete. Downloading and extracting..."
+        download_if_not_exists "https://github.com/rhasspy/piper/releases/download/v1.2.0/piper_arm64.tar.gz" "$PIPER_TAR"
+        tar -xvf "$PIPER_TAR" -C "$PIPER_EXE_FILE_UNZIP_DIR"
+        rm "$PIPER_TAR"
+    else
+        echo "[INFO] All Piper executable files already exist."
     fi
-done
 
-if [ "$piper_needs_download" = true ]; then
-    echo "[INFO] Piper files are incomplete. Downloading and extracting..."
-    download_if_not_exists "https://github.com/rhasspy/piper/releases/download/v1.2.0/piper_arm64.tar.gz" "$PIPER_TAR"
-    tar -xvf "$PIPER_TAR" -C "$PIPER_EXE_FILE_UNZIP_DIR"
-    rm "$PIPER_TAR"
-else
-    echo "[INFO] All Piper executable files already exist."
-fi
+    # Piper voice model and config
+    PIPER_MODEL_URL="https://huggingface.co/rhasspy/piper-voices/resolve/v1.0.0/en/en_US/amy/medium/en_US-amy-medium.onnx?download=true"
+    PIPER_CONFIG_URL="https://huggingface.co/rhasspy/piper-voices/resolve/v1.0.0/en/en_US/amy/medium/en_US-amy-medium.onnx.json?download=true"
 
-# Piper voice model and config
-PIPER_MODEL_URL="https://huggingface.co/rhasspy/piper-voices/resolve/v1.0.0/en/en_US/amy/medium/en_US-amy-medium.onnx?download=true"
-PIPER_CONFIG_URL="https://huggingface.co/rhasspy/piper-voices/resolve/v1.0.0/en/en_US/amy/medium/en_US-amy-medium.onnx.json?download=true"
+    PIPER_MODEL_FILE="$PIPER_MODEL_DIR/en_US-amy-medium.onnx"
+    PIPER_CONFIG_FILE="$PIPER_MODEL_DIR/en_US-amy-medium.onnx.json"
 
-PIPER_MODEL_FILE="$PIPER_MODEL_DIR/en_US-amy-medium.onnx"
-PIPER_CONFIG_FILE="$PIPER_MODEL_DIR/en_US-amy-medium.onnx.json"
+    download_if_not_exists "$PIPER_MODEL_URL" "$PIPER_MODEL_FILE"
+    download_if_not_exists "$PIPER_CONFIG_URL" "$PIPER_CONFIG_FILE"
+}
 
-download_if_not_exists "$PIPER_MODEL_URL" "$PIPER_MODEL_FILE"
-download_if_not_exists "$PIPER_CONFIG_URL" "$PIPER_CONFIG_FILE"
+if [ "$MINIMAL" = true ]; then
+    echo "[INFO] --minimal flag detected, skipping Parakeet and Piper downloads"
+else
+    download_speech_models
+fi
 
 # Activate virtual environment
 if [ -f ".venv/bin/activate" ]; then
@@ -121,6 +196,9 @@ else
     exit 1
 fi
 
+# Installable C header; also fails the build if the library and package versions disagree
+python3 src/distiller_cm5_sdk/hardware/eink/lib/gen_header.py
+
 # Build wheel package
 echo "[INFO] Building the Python wheel package..."
 python -m build
@@ -0,0 +1,3 @@
+from .bundle import AppError, AppManifest, Screen, load_manifest
+from .launcher import AppLauncher
+from .manager import AppManager
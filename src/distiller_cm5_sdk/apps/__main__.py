@@ -0,0 +1,114 @@
+#!/usr/bin/env python3
+"""
+Mini-apps: python3 -m distiller_cm5_sdk.apps {install,remove,list,run} [options]
+"""
+
+import argparse
+import signal
+import sys
+
+from .bundle import AppError
+from .manager import AppManager
+
+
+def _install(args) -> int:
+    try:
+        app = AppManager(args.apps_dir).install(args.bundle, replace=not args.no_replace)
+    except AppError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    print(f"Installed {app.name} {app.version} ({app.id})")
+    return 0
+
+
+def _remove(args) -> int:
+    try:
+        AppManager(args.apps_dir).remove(args.app_id)
+    except AppError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    return 0
+
+
+def _list(args) -> int:
+    for app in AppManager(args.apps_dir).list():
+        print(f"{app.id:24} {app.version:10} {app.name}" + (f" - {app.description}" if app.description else ""))
+    return 0
+
+
+def _run(args) -> int:
+    from ..hardware.eink.display import Display, DisplayError
+    from ..recovery.buttons import ButtonError, GpioButtons, KeyboardButtons, parse_pins
+    from .launcher import AppLauncher
+
+    if not args.gpio_pins and not sys.stdin.isatty():
+        print("Error: --gpio-pins is needed without a terminal for the keyboard buttons", file=sys.stderr)
+        return 1
+    try:
+        buttons = GpioButtons(parse_pins(args.gpio_pins), chip_name=args.gpio_chip) if args.gpio_pins \
+            else KeyboardButtons()
+    except ButtonError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+
+    audio = led = None
+    if not args.no_audio:
+        try:
+            from ..hardware.audio.audio import Audio
+            audio = Audio(auto_check_config=False)
+        except Exception as e:
+            print(f"Warning: No audio for apps: {e}")
+    if not args.no_led:
+        try:
+            from ..hardware.sam.led import LED
+            led = LED()
+        except Exception as e:
+            print(f"Warning: No LED for apps: {e}")
+
+    try:
+        with Display(backend=args.backend) as display:
+            launcher = AppLauncher(display, buttons, AppManager(args.apps_dir), audio=audio, led=led)
+            signal.signal(signal.SIGTERM, lambda signum, frame: launcher.stop())
+            launcher.run()
+    except DisplayError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    except KeyboardInterrupt:
+        pass
+    finally:
+        buttons.close()
+    return 0
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Install and run on-device mini-apps")
+    parser.add_argument("--apps-dir", help="Directory of the installed apps (default: DISTILLER_APPS_DIR or "
+                                           "/opt/distiller-cm5-sdk/apps)")
+    commands = parser.add_subparsers(dest="command_name", required=True)
+
+    install = commands.add_parser("install", help="Install or update an app from a bundle")
+    install.add_argument("bundle", help="App directory or .dapp archive")
+    install.add_argument("--no-replace", action="store_true", help="Fail if the app is already installed")
+    install.set_defaults(handler=_install)
+
+    remove = commands.add_parser("remove", help="Uninstall an app")
+    remove.add_argument("app_id")
+    remove.set_defaults(handler=_remove)
+
+    commands.add_parser("list", help="List the installed apps").set_defaults(handler=_list)
+
+    run = commands.add_parser("run", help="Run the app switcher on the e-ink display")
+    run.add_argument("--backend", help="Display backend (default: from eink.toml)")
+    run.add_argument("--gpio-pins", metavar="UP,DOWN,SELECT",
+                     help="GPIO line offsets of the buttons (default: lines typed in the terminal: u, d, Enter)")
+    run.add_argument("--gpio-chip", default="gpiochip0", help="GPIO chip of the button lines")
+    run.add_argument("--no-audio", action="store_true", help="Do not give apps audio")
+    run.add_argument("--no-led", action="store_true", help="Do not give apps the LED")
+    run.set_defaults(handler=_run)
+
+    args = parser.parse_args(argv)
+    return args.handler(args)
+
+
+if __name__ == "__main__":
+    sys.exit(main())
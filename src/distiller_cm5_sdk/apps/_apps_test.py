@@ -0,0 +1,228 @@
+#!/usr/bin/env python3
+"""
+Mini-app bundle, installer and launcher unit tests for CM5 SDK.
+"""
+
+import json
+import os
+import sys
+import tempfile
+import unittest
+import zipfile
+from pathlib import Path
+from unittest.mock import Mock
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.apps.bundle import AppError, AppManifest
+from distiller_cm5_sdk.apps.launcher import APP_CLOSED, APP_EXIT, APP_OPENED, APP_SCREEN, AppLauncher
+from distiller_cm5_sdk.apps.manager import AppManager
+from distiller_cm5_sdk.device.focus import FocusManager, Priority, Resource
+from distiller_cm5_sdk.events.bus import EventBus
+from distiller_cm5_sdk.hardware.eink.backends.mock import get_mock_transport
+from distiller_cm5_sdk.hardware.eink.display import Display
+from distiller_cm5_sdk.recovery.buttons import Button
+
+
+def manifest(**overrides):
+    data = {
+        "id": "weather",
+        "name": "Weather",
+        "version": "1.0",
+        "script": "main.lua",
+        "screens": {
+            "today": {"text": "Sunny, 21 C", "buttons": {"down": "screen:about", "up": "emit:weather.refresh",
+                                                         "select": "exit"}},
+            "about": {"text": "Weather 1.0", "buttons": {"up": "screen:today"}},
+        },
+    }
+    data.update(overrides)
+    return data
+
+
+def write_bundle(directory, data=None, files=("main.lua",)):
+    os.makedirs(directory, exist_ok=True)
+    Path(directory, "app.json").write_text(json.dumps(data or manifest()))
+    for name in files:
+        Path(directory, name).write_text("-- app\n")
+    return directory
+
+
+class TestBundle(unittest.TestCase):
+    """Test cases for the manifest format."""
+
+    def test_manifest(self):
+        """Test a manifest is parsed with the first screen as default start."""
+        app = AppManifest.from_dict(manifest())
+        self.assertEqual(app.start, "today")
+        self.assertEqual(app.screens["today"].buttons["select"], "exit")
+        self.assertEqual(app.files(), ["main.lua"])
+
+    def test_invalid_manifests(self):
+        """Test broken references, unsafe ids and apps without a way out are rejected."""
+        today = manifest()["screens"]["today"]
+        for overrides in ({"id": "../etc"}, {"id": "Weather"}, {"name": ""}, {"screens": {}},
+                          {"start": "tomorrow"},
+                          {"screens": {"today": dict(today, buttons={"select": "screen:tomorrow"})}},
+                          {"screens": {"today": dict(today, buttons={"left": "exit"})}},
+                          {"screens": {"today": dict(today, buttons={"select": "reboot"})}},
+                          {"screens": {"today": dict(today, buttons={"select": "screen:today"})}},
+                          {"screens": {"today": {"buttons": {"select": "exit"}}}},
+                          {"screens": {"today": {"text": "x", "image": "x.png", "buttons": {"select": "exit"}}}}):
+            with self.subTest(overrides=overrides):
+                with self.assertRaises(AppError):
+                    AppManifest.from_dict(manifest(**overrides))
+
+
+class TestAppManager(unittest.TestCase):
+    """Test cases for installing and removing apps."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.manager = AppManager(os.path.join(self.tmpdir.name, "apps"))
+        self.source = write_bundle(os.path.join(self.tmpdir.name, "src", "weather"))
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def test_install_directory(self):
+        """Test a bundle directory is installed, updated, listed and removed."""
+        self.assertEqual(self.manager.install(self.source).id, "weather")
+        self.assertTrue(os.path.isfile(os.path.join(self.manager.app_dir("weather"), "main.lua")))
+        with self.assertRaises(AppError):
+            self.manager.install(self.source, replace=False)
+
+        write_bundle(self.source, manifest(version="1.1"))
+        self.manager.install(self.source)
+        self.assertEqual([(app.id, app.version) for app in self.manager.list()], [("weather", "1.1")])
+        self.assertEqual(os.listdir(self.manager.apps_dir), ["weather"])     # no staging left behind
+
+        self.manager.remove("weather")
+        self.assertEqual(self.manager.list(), [])
+        for app_id in ("weather", "..", ""):
+            with self.assertRaises(AppError):
+                self.manager.remove(app_id)
+
+    def test_install_archive(self):
+        """Test .dapp archives install with or without a top-level directory and unsafe ones are refused."""
+        archive = os.path.join(self.tmpdir.name, "weather.dapp")
+        with zipfile.ZipFile(archive, "w") as zf:
+            zf.write(os.path.join(self.source, "app.json"), "weather/app.json")
+            zf.write(os.path.join(self.source, "main.lua"), "weather/main.lua")
+        self.assertEqual(self.manager.install(archive).name, "Weather")
+
+        evil = os.path.join(self.tmpdir.name, "evil.dapp")
+        with zipfile.ZipFile(evil, "w") as zf:
+            zf.writestr("app.json", json.dumps(manifest(id="evil")))
+            zf.writestr("main.lua", "")
+            zf.writestr("../../escaped.txt", "x")
+        with self.assertRaises(AppError):
+            self.manager.install(evil)
+        self.assertFalse(os.path.exists(os.path.join(self.tmpdir.name, "escaped.txt")))
+
+    def test_install_rejects_incomplete_bundle(self):
+        """Test a bundle missing a file it names is not installed and the old version stays."""
+        self.manager.install(self.source)
+        os.remove(os.path.join(self.source, "main.lua"))
+        write_bundle(self.source, manifest(version="2.0"), files=())
+        with self.assertRaises(AppError):
+            self.manager.install(self.source)
+        self.assertEqual(self.manager.get("weather").version, "1.0")
+
+
+class TestAppLauncher(unittest.TestCase):
+    """Test cases for the app switcher."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.manager = AppManager(os.path.join(self.tmpdir.name, "apps"))
+        self.manager.install(write_bundle(os.path.join(self.tmpdir.name, "src", "weather")))
+        self.manager.install(write_bundle(os.path.join(self.tmpdir.name, "src", "clock"),
+                                          manifest(id="clock", name="Clock", script=None), files=()))
+        self.display = Display(backend="mock")
+        self.bus = EventBus()
+        self.events = []
+        self.bus.subscribe("*", lambda event: self.events.append((event.name, event.data)))
+        self.script = Mock()
+        self.factory = Mock(return_value=self.script)
+        self.launcher = AppLauncher(self.display, Mock(), self.manager, bus=self.bus, script_factory=self.factory)
+        self.launcher.refresh()
+
+    def tearDown(self):
+        self.display.close()
+        self.tmpdir.cleanup()
+
+    def test_switcher(self):
+        """Test the selection moves through the apps sorted by name and wraps around."""
+        self.assertEqual([app.id for app in self.launcher.apps], ["clock", "weather"])
+        self.launcher.handle("button", Button.UP)
+        self.assertEqual(self.launcher.selected, 1)
+        self.launcher.handle("button", Button.DOWN)
+        self.assertEqual(self.launcher.selected, 0)
+        self.assertNotEqual(set(get_mock_transport(self.display).last_frame), {0xFF})
+
+    def test_open_app(self):
+        """Test an app's screens follow its button actions and exit returns to the switcher."""
+        self.launcher.handle("button", Button.DOWN)
+        self.launcher.handle("button", Button.SELECT)
+        self.assertEqual(self.launcher.current.id, "weather")
+        self.factory.assert_called_once_with(os.path.join(os.path.realpath(self.manager.app_dir("weather")),
+                                                          "main.lua"))
+        self.assertIn((APP_OPENED, {"app": "weather"}), self.events)
+
+        self.launcher.handle("button", Button.UP)
+        self.assertIn(("weather.refresh", {"app": "weather", "screen": "today"}), self.events)
+        self.launcher.handle("button", Button.DOWN)
+        self.assertEqual(self.launcher.screen, "about")
+        self.launcher.handle("button", Button.SELECT)         # unmapped on this screen
+        self.assertEqual(self.launcher.screen, "about")
+        self.launcher.handle(APP_SCREEN, "today")
+        self.launcher.handle("button", Button.SELECT)
+        self.assertIsNone(self.launcher.current)
+        self.script.stop.assert_called_once()
+        self.assertIn((APP_CLOSED, {"app": "weather"}), self.events)
+
+    def test_script_exit(self):
+        """Test an app's script can close it, and a script that fails to start keeps the app closed."""
+        self.launcher.open(self.manager.get("weather"))
+        self.launcher.handle(APP_EXIT, None)
+        self.assertIsNone(self.launcher.current)
+
+        self.factory.side_effect = RuntimeError("does not compile")
+        with self.assertRaises(AppError):
+            self.launcher.open(self.manager.get("weather"))
+        self.assertIsNone(self.launcher.current)
+        self.launcher.open(self.manager.get("clock"))       # no script
+        self.assertEqual(self.launcher.current.id, "clock")
+
+    def test_focus(self):
+        """Test the open app owns the resources and a service taking them pauses drawing and input."""
+        focus = FocusManager()
+        launcher = AppLauncher(self.display, Mock(), self.manager, bus=self.bus, script_factory=self.factory,
+                               focus=focus)
+        launcher.refresh()
+        launcher._acquire()
+        launcher.open(self.manager.get("clock"))
+        self.assertEqual(focus.owner(Resource.BUTTONS), "app:clock")
+
+        transport = get_mock_transport(self.display)
+        frames = transport.frame_count
+        focus.request("timer", Resource.DISPLAY, Priority.SERVICE)
+        focus.request("timer", Resource.BUTTONS, Priority.SERVICE)
+        launcher.handle("button", Button.DOWN)
+        self.assertEqual(launcher.screen, "today")           # press ignored
+        launcher.show("about")
+        self.assertEqual(transport.frame_count, frames)     # nothing drawn
+
+        focus.release("timer")
+        self.assertEqual(launcher._inputs.get_nowait(), ("focus", Resource.DISPLAY))
+        launcher.handle("focus", Resource.DISPLAY)
+        self.assertEqual(transport.frame_count, frames + 1)
+        launcher.handle("button", Button.UP)                # about -> today
+        launcher.handle("button", Button.SELECT)
+        self.assertEqual(focus.owner(Resource.DISPLAY), "launcher")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,187 @@
+#!/usr/bin/env python3
+"""
+Mini-app bundle format for CM5 SDK.
+
+A mini-app is a directory, or a zip archive of one (conventionally named
+<id>.dapp), holding an app.json manifest next to its assets:
+
+    weather/
+        app.json
+        main.lua        optional script, run while the app is open
+        sunny.png
+
+    {
+        "id": "weather",
+        "name": "Weather",
+        "version": "1.0",
+        "description": "Today's forecast",
+        "script": "main.lua",
+        "start": "today",
+        "screens": {
+            "today": {"image": "sunny.png", "buttons": {"down": "screen:about", "select": "exit"}},
+            "about": {"text": "Weather 1.0", "buttons": {"up": "screen:today", "select": "exit"}}
+        }
+    }
+
+Screens are declarative: a full-screen PNG or a text, and an action for each
+of the UP, DOWN and SELECT buttons:
+
+    screen:<name>   show another screen of the app
+    emit:<event>    publish an event, e.g. for the app's script
+    exit            return to the app switcher
+
+Every app needs an exit action, so no app can keep the user from leaving it.
+"""
+
+import json
+import os
+import re
+from dataclasses import dataclass, field
+from typing import Any, Dict, Optional
+
+
+MANIFEST_NAME = "app.json"
+BUNDLE_SUFFIX = ".dapp"
+
+BUTTON_NAMES = ("up", "down", "select")
+SCREEN_ACTION = "screen:"
+EMIT_ACTION = "emit:"
+EXIT_ACTION = "exit"
+
+# App ids name directories, so they are restricted to safe characters
+_ID_PATTERN = re.compile(r"^[a-z0-9][a-z0-9._-]{0,63}$")
+
+
+class AppError(Exception):
+    """Custom exception for mini-app errors."""
+    pass
+
+
+@dataclass
+class Screen:
+    """A declarative screen of a mini-app."""
+    name: str
+    text: Optional[str] = None
+    image: Optional[str] = None                                 # file in the bundle
+    buttons: Dict[str, str] = field(default_factory=dict)       # button name -> action
+
+
+@dataclass
+class AppManifest:
+    """The app.json of a mini-app."""
+    id: str
+    name: str
+    version: str
+    screens: Dict[str, Screen]
+    start: str
+    description: str = ""
+    script: Optional[str] = None                                # Lua file in the bundle
+
+    @classmethod
+    def from_dict(cls, data: Any) -> "AppManifest":
+        """
+        Build a manifest from parsed app.json.
+
+        Raises:
+            AppError: If a field is missing or invalid
+        """
+        if not isinstance(data, dict):
+            raise AppError(f"{MANIFEST_NAME} must hold a JSON object")
+        for key in ("id", "name", "version"):
+            if not isinstance(data.get(key), str) or not data[key]:
+                raise AppError(f"{MANIFEST_NAME} needs a '{key}' string")
+        if not _ID_PATTERN.match(data["id"]):
+            raise AppError(f"Invalid app id '{data['id']}': use lowercase letters, digits, '.', '_' and '-'")
+
+        screens_data = data.get("screens")
+        if not isinstance(screens_data, dict) or not screens_data:
+            raise AppError(f"{MANIFEST_NAME} needs at least one screen")
+        screens = {}
+        for name, screen in screens_data.items():
+            if not isinstance(screen, dict):
+                raise AppError(f"Screen '{name}' must be an object")
+            buttons = screen.get("buttons", {})
+            if (not isinstance(buttons, dict)
+                    or not all(isinstance(action, str) for action in buttons.values())):
+                raise AppError(f"Screen '{name}': buttons must map button names to actions")
+            for key in ("text", "image"):
+                if screen.get(key) is not None and not isinstance(screen[key], str):
+                    raise AppError(f"Screen '{name}': '{key}' must be a string")
+            screens[name] = Screen(name, text=screen.get("text"), image=screen.get("image"), buttons=dict(buttons))
+
+        script = data.get("script")
+        if script is not None and not isinstance(script, str):
+            raise AppError(f"{MANIFEST_NAME}: 'script' must be a file name")
+        manifest = cls(id=data["id"], name=data["name"], version=data["version"], screens=screens,
+                       start=data.get("start", next(iter(screens))),
+                       description=str(data.get("description", "")), script=script)
+        manifest.validate()
+        return manifest
+
+    def validate(self) -> None:
+        """
+        Check the screens reference each other consistently.
+
+        Raises:
+            AppError: On an unknown start screen, button or action, or no exit action
+        """
+        if self.start not in self.screens:
+            raise AppError(f"Start screen '{self.start}' does not exist")
+        has_exit = False
+        for screen in self.screens.values():
+            if (screen.text is None) == (screen.image is None):
+                raise AppError(f"Screen '{screen.name}' needs either a text or an image")
+            for button, action in screen.buttons.items():
+                if button not in BUTTON_NAMES:
+                    raise AppError(f"Screen '{screen.name}': unknown button '{button}'")
+                if action == EXIT_ACTION:
+                    has_exit = True
+                elif action.startswith(SCREEN_ACTION):
+                    if action[len(SCREEN_ACTION):] not in self.screens:
+                        raise AppError(f"Screen '{screen.name}': '{action}' names no screen")
+                elif not action.startswith(EMIT_ACTION) or action == EMIT_ACTION:
+                    raise AppError(f"Screen '{screen.name}': unknown action '{action}'")
+        if not has_exit:
+            raise AppError("No screen has an 'exit' action")
+
+    def files(self):
+        """The bundle files the manifest refers to."""
+        names = [screen.image for screen in self.screens.values() if screen.image is not None]
+        if self.script is not None:
+            names.append(self.script)
+        return names
+
+
+def bundle_path(root: str, name: str) -> str:
+    """
+    Resolve a file name inside a bundle directory.
+
+    Raises:
+        AppError: If the name leaves the bundle
+    """
+    root = os.path.realpath(root)
+    path = os.path.realpath(os.path.join(root, name))
+    if os.path.commonpath([path, root]) != root:
+        raise AppError(f"'{name}' is outside the app")
+    return path
+
+
+def load_manifest(directory: str) -> AppManifest:
+    """
+    Read and check the manifest of an unpacked app, including that its files exist.
+
+    Raises:
+        AppError: If app.json is missing or invalid, or a file it names is missing
+    """
+    try:
+        with open(os.path.join(directory, MANIFEST_NAME), "r") as f:
+            data = json.load(f)
+    except FileNotFoundError:
+        raise AppError(f"No {MANIFEST_NAME} in {directory}")
+    except (OSError, ValueError) as e:
+        raise AppError(f"Cannot read {MANIFEST_NAME} in {directory}: {e}")
+    manifest = AppManifest.from_dict(data)
+    for name in manifest.files():
+        if not os.path.isfile(bundle_path(directory, name)):
+            raise AppError(f"App '{manifest.id}' is missing {name}")
+    return manifest
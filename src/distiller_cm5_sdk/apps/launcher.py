@@ -0,0 +1,327 @@
+#!/usr/bin/env python3
+"""
+Mini-app launcher for CM5 SDK.
+
+Shows the installed apps in a switcher on the e-ink display and runs the
+one picked with the buttons: UP/DOWN move the selection and SELECT opens
+the app. An open app shows its screens and runs its script, if it has one,
+until one of its exit actions returns to the switcher.
+
+Scripts drive the screens through events:
+
+    emit("app.screen", {name = "about"})    show another screen
+    emit("app.exit")                        return to the switcher
+
+The launcher publishes app.opened and app.closed with the app's id.
+
+With a FocusManager, the switcher holds the display, audio and buttons as
+"launcher" and an open app as "app:<id>". While a service of higher priority
+has taken the display or buttons, nothing is drawn and presses are ignored;
+the current view is redrawn when the display comes back.
+"""
+
+import queue
+import textwrap
+import threading
+from typing import Any, Callable, List, Optional
+
+from ..device.focus import FocusManager, Priority, Resource
+from ..diagnostics.log_levels import get_logger
+from ..events.bus import Event, EventBus, get_bus
+from ..hardware.eink.display import DisplayMode
+from ..hardware.eink.font import FONT_HEIGHT, FONT_WIDTH
+from ..hardware.eink.graphics import Color, Framebuffer
+from ..recovery.buttons import Button, ButtonSource
+from .bundle import EMIT_ACTION, EXIT_ACTION, SCREEN_ACTION, AppError, AppManifest, bundle_path
+from .manager import AppManager
+
+
+APP_OPENED = "app.opened"
+APP_CLOSED = "app.closed"
+APP_SCREEN = "app.screen"
+APP_EXIT = "app.exit"
+
+LAUNCHER_OWNER = "launcher"
+
+CHAR_WIDTH = FONT_WIDTH + 1
+LINE_HEIGHT = FONT_HEIGHT + 2
+MARGIN = 4
+
+
+class AppLauncher:
+    """App switcher and runner for the installed mini-apps."""
+
+    def __init__(self, display, buttons: ButtonSource, manager: Optional[AppManager] = None,
+                 bus: Optional[EventBus] = None, audio=None, led=None,
+                 script_factory: Optional[Callable[[str], Any]] = None,
+                 focus: Optional[FocusManager] = None):
+        """
+        Args:
+            display: Initialized Display
+            buttons: Source of button presses
+            manager: Installed apps; None uses the default apps directory
+            bus: Bus for app events and the scripts; defaults to get_bus()
+            audio: Optional Audio given to app scripts
+            led: Optional LED given to app scripts
+            script_factory: Creates the runnable script (run()/stop()) from a
+                            script path; defaults to a sandboxed LuaScript
+            focus: Arbitrates the display, audio and buttons with other
+                   consumers, or None to use them unconditionally
+        """
+        self.display = display
+        self.buttons = buttons
+        self.manager = manager or AppManager()
+        self.bus = bus or get_bus()
+        self.audio = audio
+        self.led = led
+        self.script_factory = script_factory or self._lua_script
+        self.focus = focus
+        self.width, self.height = display.get_dimensions()
+        self.chars_per_line = max(1, (self.width - 2 * MARGIN) // CHAR_WIDTH)
+        self.apps: List[AppManifest] = []
+        self.selected = 0
+        self.current: Optional[AppManifest] = None
+        self.screen: Optional[str] = None
+        self._script = None
+        self._log = get_logger("apps")
+        self._inputs: "queue.Queue[tuple]" = queue.Queue()
+        self._stop = threading.Event()
+
+    def _lua_script(self, path: str):
+        from ..scripting.lua import LuaScript
+        return LuaScript(path, display=self.display, audio=self.audio, led=self.led, bus=self.bus)
+
+    # -- Focus ----------------------------------------------------------------
+
+    @property
+    def owner(self) -> str:
+        """Focus owner of the current view: the open app, or the switcher."""
+        return f"app:{self.current.id}" if self.current is not None else LAUNCHER_OWNER
+
+    def _has(self, resource: Resource) -> bool:
+        return self.focus is None or self.focus.holds(self.owner, resource)
+
+    def _acquire(self) -> None:
+        if self.focus is not None:
+            for resource in Resource:
+                self.focus.request(self.owner, resource, Priority.APP, self._on_focus)
+
+    def _release(self) -> None:
+        if self.focus is not None:
+            self.focus.release(self.owner)
+
+    def _on_focus(self, resource: Resource, granted: bool) -> None:
+        if granted and resource == Resource.DISPLAY:
+            self._inputs.put(("focus", resource))
+
+    # -- Drawing --------------------------------------------------------------
+
+    def _present(self, fb: Framebuffer, mode: DisplayMode = DisplayMode.PARTIAL) -> None:
+        if self._has(Resource.DISPLAY):
+            self.display.display_image(fb.to_bytes(), mode)
+
+    def _title_bar(self, fb: Framebuffer, title: str) -> int:
+        """Draw an inverted title bar and return the y coordinate below it."""
+        fb.draw_rect(0, 0, self.width, LINE_HEIGHT + 2, fill=True)
+        fb.draw_text(MARGIN, 2, title[:self.chars_per_line], Color.WHITE)
+        return LINE_HEIGHT + 2 + MARGIN
+
+    def render_switcher(self, mode: DisplayMode = DisplayMode.PARTIAL) -> None:
+        """Draw the app list with the current selection highlighted."""
+        fb = Framebuffer(self.width, self.height)
+        y = self._title_bar(fb, "Apps")
+        if not self.apps:
+            fb.draw_text(MARGIN, y, "No apps installed"[:self.chars_per_line])
+        # Scroll so the selection stays on screen
+        rows = max(1, (self.height - y) // LINE_HEIGHT)
+        first = max(0, self.selected - rows + 1)
+        for index, app in enumerate(self.apps[first:first + rows], start=first):
+            label = app.name[:self.chars_per_line]
+            if index == self.selected:
+                fb.draw_rect(0, y - 1, self.width, LINE_HEIGHT, fill=True)
+                fb.draw_text(MARGIN, y, label, Color.WHITE)
+            else:
+                fb.draw_text(MARGIN, y, label)
+            y += LINE_HEIGHT
+        self._present(fb, mode)
+
+    def render_screen(self, mode: DisplayMode = DisplayMode.FULL) -> None:
+        """Draw the open app's current screen."""
+        app = self.current
+        screen = app.screens[self.screen]
+        if screen.image is not None:
+            if self._has(Resource.DISPLAY):
+                self.display.display_image(bundle_path(self.manager.app_dir(app.id), screen.image), mode)
+            return
+        fb = Framebuffer(self.width, self.height)
+        y = self._title_bar(fb, app.name)
+        for paragraph in screen.text.splitlines() or [""]:
+            for line in textwrap.wrap(paragraph, self.chars_per_line) or [""]:
+                if y + FONT_HEIGHT > self.height:
+                    break
+                fb.draw_text(MARGIN, y, line)
+                y += LINE_HEIGHT
+        self._present(fb, mode)
+
+    def show_error(self, text: str) -> None:
+        """Show a failure in place of the switcher until the next button press."""
+        fb = Framebuffer(self.width, self.height)
+        y = self._title_bar(fb, "Error")
+        for line in textwrap.wrap(text, self.chars_per_line):
+            if y + FONT_HEIGHT > self.height:
+                break
+            fb.draw_text(MARGIN, y, line)
+            y += LINE_HEIGHT
+        self._present(fb)
+
+    # -- Apps -----------------------------------------------------------------
+
+    def refresh(self) -> None:
+        """Reload the installed apps, keeping the selection on the same app."""
+        selected = self.apps[self.selected].id if self.apps else None
+        self.apps = self.manager.list()
+        ids = [app.id for app in self.apps]
+        self.selected = ids.index(selected) if selected in ids else 0
+
+    def open(self, app: AppManifest) -> None:
+        """
+        Open an app: show its start screen and start its script.
+
+        Raises:
+            AppError: If the app's script cannot be started
+        """
+        script = None
+        if app.script is not None:
+            try:
+                script = self.script_factory(bundle_path(self.manager.app_dir(app.id), app.script))
+            except Exception as e:
+                raise AppError(f"{app.name} cannot start: {e}")
+        self._release()
+        self.current, self.screen = app, app.start
+        self._script = script
+        self._acquire()
+        self.render_screen()
+        if script is not None:
+            threading.Thread(target=self._run_script, args=(app, script), name=f"app-{app.id}",
+                             daemon=True).start()
+        self._log.info("opened %s %s", app.id, app.version)
+        self.bus.publish(APP_OPENED, app=app.id)
+
+    def _run_script(self, app: AppManifest, script) -> None:
+        try:
+            script.run()
+        except Exception as e:
+            print(f"Warning: Script of app {app.id} failed: {e}")
+
+    def close(self) -> None:
+        """Stop the open app and return to the switcher."""
+        if self.current is None:
+            return
+        if self._script is not None:
+            self._script.stop()
+        self._release()
+        app, self.current, self.screen, self._script = self.current, None, None, None
+        self._acquire()
+        self._log.info("closed %s", app.id)
+        self.bus.publish(APP_CLOSED, app=app.id)
+
+    def _perform(self, action: str) -> None:
+        """Carry out a screen action of the open app."""
+        if action == EXIT_ACTION:
+            self.close()
+            self.render_switcher(DisplayMode.FULL)
+        elif action.startswith(SCREEN_ACTION):
+            self.show(action[len(SCREEN_ACTION):])
+        elif action.startswith(EMIT_ACTION):
+            self.bus.publish(action[len(EMIT_ACTION):], app=self.current.id, screen=self.screen)
+
+    def show(self, name: str) -> None:
+        """Switch the open app to another of its screens; unknown names are reported and ignored."""
+        if self.current is None:
+            return
+        if name not in self.current.screens:
+            print(f"Warning: App {self.current.id} has no screen '{name}'")
+            return
+        self.screen = name
+        self.render_screen()
+
+    # -- Input ----------------------------------------------------------------
+
+    def _on_app_event(self, event: Event) -> None:
+        self._inputs.put((event.name, event.data.get("name")))
+
+    def _read_buttons(self) -> None:
+        while not self._stop.is_set():
+            button = self.buttons.read(timeout=0.5)
+            if button is not None:
+                self._inputs.put(("button", button))
+
+    def handle(self, kind: str, value: Any) -> None:
+        """Apply one input: a button press, an app.screen or app.exit event, or regained focus."""
+        if kind == "focus":
+            if self.current is not None:
+                self.render_screen()
+            else:
+                self.render_switcher(DisplayMode.FULL)
+            return
+        if kind == "button" and not self._has(Resource.BUTTONS):
+            return          # another consumer has the buttons
+        if self.current is not None:
+            if kind == "button":
+                action = self.current.screens[self.screen].buttons.get(value.value)
+                if action is not None:
+                    self._perform(action)
+            elif kind == APP_SCREEN:
+                self.show(str(value))
+            elif kind == APP_EXIT:
+                self._perform(EXIT_ACTION)
+            return
+        if kind != "button":
+            return          # events of an app that was already closed
+        if value == Button.UP and self.apps:
+            self.selected = (self.selected - 1) % len(self.apps)
+        elif value == Button.DOWN and self.apps:
+            self.selected = (self.selected + 1) % len(self.apps)
+        elif value == Button.SELECT:
+            self.refresh()      # pick up apps installed meanwhile
+            if not self.apps:
+                self.render_switcher()
+                return
+            try:
+                self.open(self.apps[self.selected])
+                return
+            except AppError as e:
+                print(f"Warning: {e}")
+                self.show_error(str(e))
+                return
+        self.render_switcher()
+
+    # -- Main loop ------------------------------------------------------------
+
+    def run(self) -> None:
+        """Run the switcher until stop()."""
+        self.bus.subscribe(APP_SCREEN, self._on_app_event)
+        self.bus.subscribe(APP_EXIT, self._on_app_event)
+        threading.Thread(target=self._read_buttons, name="apps-buttons", daemon=True).start()
+        try:
+            self._acquire()
+            self.refresh()
+            self.render_switcher(DisplayMode.FULL)
+            while not self._stop.is_set():
+                try:
+                    kind, value = self._inputs.get(timeout=0.5)
+                except queue.Empty:
+                    continue
+                try:
+                    self.handle(kind, value)
+                except Exception as e:
+                    print(f"Error: App launcher failed to handle {kind}: {e}")
+        finally:
+            self.close()
+            self._release()
+            self.bus.unsubscribe(APP_SCREEN, self._on_app_event)
+            self.bus.unsubscribe(APP_EXIT, self._on_app_event)
+
+    def stop(self) -> None:
+        """End run() from another thread or a signal handler."""
+        self._stop.set()
@@ -0,0 +1,151 @@
+#!/usr/bin/env python3
+"""
+Mini-app installer for CM5 SDK.
+
+Installed apps live in one directory each under the apps directory,
+/opt/distiller-cm5-sdk/apps by default (DISTILLER_APPS_DIR overrides it),
+named after their id. An app is unpacked and checked next to the installed
+apps before it replaces any of them, so a broken bundle never leaves a
+half-installed app behind.
+"""
+
+import os
+import shutil
+import tempfile
+import zipfile
+from typing import List, Optional
+
+from .bundle import MANIFEST_NAME, AppError, AppManifest, load_manifest
+
+
+APPS_DIR = "/opt/distiller-cm5-sdk/apps"
+APPS_DIR_ENV_VAR = "DISTILLER_APPS_DIR"
+
+# Unpacked size limit of a bundle archive
+MAX_BUNDLE_BYTES = 64 * 1024 * 1024
+
+
+def default_apps_dir() -> str:
+    """Directory of the installed apps (DISTILLER_APPS_DIR overrides the default)."""
+    return os.environ.get(APPS_DIR_ENV_VAR, APPS_DIR)
+
+
+def _extract(archive: str, target: str) -> None:
+    """Unpack a bundle archive, refusing members that would land outside target."""
+    try:
+        with zipfile.ZipFile(archive) as zf:
+            members = zf.infolist()
+            if sum(member.file_size for member in members) > MAX_BUNDLE_BYTES:
+                raise AppError(f"{archive} unpacks to more than {MAX_BUNDLE_BYTES // (1024 * 1024)} MiB")
+            root = os.path.realpath(target)
+            for member in members:
+                path = os.path.realpath(os.path.join(root, member.filename))
+                if os.path.commonpath([path, root]) != root:
+                    raise AppError(f"{archive} has a file outside the app: {member.filename}")
+            zf.extractall(target)
+    except (OSError, zipfile.BadZipFile) as e:
+        raise AppError(f"Cannot unpack {archive}: {e}")
+
+
+def _bundle_root(directory: str) -> str:
+    """The directory holding app.json: the unpacked one, or its only subdirectory."""
+    if os.path.isfile(os.path.join(directory, MANIFEST_NAME)):
+        return directory
+    entries = os.listdir(directory)
+    if len(entries) == 1 and os.path.isfile(os.path.join(directory, entries[0], MANIFEST_NAME)):
+        return os.path.join(directory, entries[0])
+    raise AppError(f"No {MANIFEST_NAME} in the bundle")
+
+
+class AppManager:
+    """Installs, lists and removes the mini-apps in an apps directory."""
+
+    def __init__(self, apps_dir: Optional[str] = None):
+        """
+        Args:
+            apps_dir: Directory of the installed apps; None uses DISTILLER_APPS_DIR
+                      or /opt/distiller-cm5-sdk/apps
+        """
+        self.apps_dir = apps_dir or default_apps_dir()
+
+    def app_dir(self, app_id: str) -> str:
+        """Directory of an installed app."""
+        return os.path.join(self.apps_dir, app_id)
+
+    def list(self) -> List[AppManifest]:
+        """
+        The installed apps, sorted by name.
+
+        An app whose manifest no longer loads is reported and left out.
+        """
+        apps = []
+        if not os.path.isdir(self.apps_dir):
+            return apps
+        for entry in sorted(os.listdir(self.apps_dir)):
+            directory = self.app_dir(entry)
+            if entry.startswith(".") or not os.path.isdir(directory):
+                continue        # staging directories of running installs
+            try:
+                apps.append(load_manifest(directory))
+            except AppError as e:
+                print(f"Warning: Skipping app {entry}: {e}")
+        return sorted(apps, key=lambda app: app.name.lower())
+
+    def get(self, app_id: str) -> AppManifest:
+        """
+        Raises:
+            AppError: If the app is not installed or its manifest is broken
+        """
+        directory = self.app_dir(app_id)
+        if not os.path.isdir(directory):
+            raise AppError(f"App '{app_id}' is not installed")
+        return load_manifest(directory)
+
+    def install(self, source: str, replace: bool = True) -> AppManifest:
+        """
+        Install an app from a bundle directory or archive.
+
+        Args:
+            source: Bundle directory, or zip archive of one
+            replace: Replace an installed app of the same id, e.g. to update it
+
+        Returns:
+            The installed app's manifest
+
+        Raises:
+            AppError: If the bundle is invalid, or the app is installed and replace is False
+        """
+        os.makedirs(self.apps_dir, exist_ok=True)
+        staging = tempfile.mkdtemp(prefix=".install-", dir=self.apps_dir)
+        try:
+            unpacked = os.path.join(staging, "app")
+            if os.path.isdir(source):
+                shutil.copytree(source, unpacked, symlinks=False)
+            elif os.path.isfile(source):
+                _extract(source, unpacked)
+            else:
+                raise AppError(f"No app bundle at {source}")
+            unpacked = _bundle_root(unpacked)
+            manifest = load_manifest(unpacked)
+
+            target = self.app_dir(manifest.id)
+            if os.path.exists(target):
+                if not replace:
+                    raise AppError(f"App '{manifest.id}' is already installed")
+                os.rename(target, os.path.join(staging, "previous"))
+            os.rename(unpacked, target)
+            return manifest
+        except OSError as e:
+            raise AppError(f"Cannot install {source}: {e}")
+        finally:
+            shutil.rmtree(staging, ignore_errors=True)
+
+    def remove(self, app_id: str) -> None:
+        """
+        Raises:
+            AppError: If the app is not installed
+        """
+        directory = self.app_dir(app_id)
+        if os.path.basename(os.path.normpath(directory)) != app_id or not os.path.isdir(directory):
+            raise AppError(f"App '{app_id}' is not installed")
+        shutil.rmtree(directory)
@@ -0,0 +1,17 @@
+from .capabilities import (
+    AudioCapability, BatteryCapability, Capabilities, PanelCapability, SamCapability, SensorInfo, TouchCapability,
+    capabilities, probe_audio, probe_battery, probe_panel, probe_sam, probe_sensors, probe_touch,
+)
+from .factory_reset import (
+    FactoryResetError, ResetPlan, ResetProgress, ResetStep, confirm_on_panel, factory_reset, load_plan,
+)
+from .focus import FocusChange, FocusManager, Priority, Resource
+from .lock import LockError, ScreenLock, hash_pin, unlock_on_panel, verify_pin
+from .power_loss import (
+    GpioPowerFailSensor, PowerLossError, PowerLossMonitor, PowerLossReport, PowerSensor, VoltageSensor,
+)
+from .power_state import PowerState, PowerStateError, PowerStateMachine, StateTransition
+from .privacy import (
+    FileMuteSwitch, GpioMuteSwitch, MuteMonitor, MuteSwitch, PrivacyError, SoftwareMuteSwitch, draw_mic_muted,
+    is_mic_muted, set_mute_switch,
+)
@@ -0,0 +1,138 @@
+#!/usr/bin/env python3
+"""
+Capability discovery unit tests for CM5 SDK.
+"""
+
+import io
+import json
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.device.capabilities import (
+    capabilities, probe_battery, probe_panel, probe_sam, probe_sensors, probe_touch,
+)
+from distiller_cm5_sdk.diagnostics.__main__ import main
+from distiller_cm5_sdk.hardware.eink.display import Display
+from distiller_cm5_sdk.hardware.sam.link import LinkState
+
+INPUT_DEVICES = """I: Bus=0019 Vendor=0001 Product=0001 Version=0100
+N: Name="gpio_keys"
+H: Handlers=kbd event0
+B: PROP=0
+B: EV=3
+B: KEY=10000000 0 0 0
+
+I: Bus=0018 Vendor=0000 Product=0000 Version=0000
+N: Name="ft5x06_ts"
+H: Handlers=mouse0 event1
+B: PROP=2
+B: EV=b
+B: ABS=2608000 3
+"""
+
+
+class FakeLink:
+    def __init__(self, state, firmware, protocol_version):
+        self.state = state
+        self.firmware = firmware
+        self.protocol_version = protocol_version
+
+
+class TestCapabilities(unittest.TestCase):
+    """Test cases for describing the hardware of a unit."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.root = self.tmpdir.name
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def write(self, path, text=""):
+        path = os.path.join(self.root, path.lstrip("/"))
+        os.makedirs(os.path.dirname(path), exist_ok=True)
+        with open(path, "w") as f:
+            f.write(text)
+
+    def test_full_unit(self):
+        """Test every part of a unit with all the hardware is found."""
+        self.write("/proc/device-tree/model", "Raspberry Pi Compute Module 5 Rev 1.0\x00")
+        self.write("/proc/asound/pcm", "00-00: i2s-hifi tlv320aic3x-hifi-0 :  : playback 1 : capture 1\n")
+        self.write("/proc/asound/cards", " 0 [pamiraudio     ]: simple-card - Pamir Audio\n"
+                                         "                      Pamir Audio\n")
+        self.write("/proc/bus/input/devices", INPUT_DEVICES)
+        self.write("/sys/bus/iio/devices/iio:device0/name", "veml7700\n")
+        self.write("/sys/class/hwmon/hwmon0/name", "cpu_thermal\n")
+        self.write("/sys/class/hwmon/hwmon1/temp1_input", "41000\n")     # no name: not a sensor driver
+        self.write("/sys/class/power_supply/usb/type", "USB\n")
+        self.write("/sys/class/power_supply/bq27441-0/type", "Battery\n")
+        self.write("/sys/class/power_supply/bq27441-0/capacity", "87\n")
+        self.write("/sys/class/power_supply/bq27441-0/status", "Charging\n")
+
+        display = Display(backend="mock", auto_init=False)
+        caps = capabilities(display, FakeLink(LinkState.UP, "1.4.0", 2), self.root)
+        self.assertEqual(caps.model, "Raspberry Pi Compute Module 5 Rev 1.0")
+        self.assertEqual((caps.audio.input, caps.audio.output, caps.audio.cards),
+                         (True, True, ["simple-card - Pamir Audio"]))
+        self.assertEqual((caps.touch.present, caps.touch.name, caps.touch.device),
+                         (True, "ft5x06_ts", "/dev/input/event1"))
+        self.assertEqual([(sensor.name, sensor.kind, sensor.path) for sensor in caps.sensors],
+                         [("veml7700", "iio", "/sys/bus/iio/devices/iio:device0"),
+                          ("cpu_thermal", "hwmon", "/sys/class/hwmon/hwmon0")])
+        self.assertEqual((caps.sam.responding, caps.sam.firmware, caps.sam.protocol_version), (True, "1.4.0", 2))
+        self.assertEqual((caps.battery.present, caps.battery.percent, caps.battery.status), (True, 87.0, "charging"))
+        self.assertEqual((caps.panel.panel, caps.panel.width, caps.panel.height), ("epd128x250", 128, 250))
+        json.dumps(caps.to_dict())
+
+    def test_bare_unit(self):
+        """Test missing hardware is reported absent rather than failing."""
+        caps = capabilities(Display(backend="mock", auto_init=False), root=self.root, sam_timeout_s=0)
+        self.assertIsNone(caps.model)
+        self.assertFalse(caps.panel.present)
+        self.assertIn("mock backend", caps.panel.error)
+        self.assertEqual((caps.audio.input, caps.audio.output, caps.audio.controls), (False, False, False))
+        self.assertFalse(caps.touch.present)
+        self.assertEqual(caps.sensors, [])
+        self.assertFalse(caps.sam.present)
+        self.assertFalse(caps.battery.present)
+
+        # A touchpad is not a touchscreen
+        self.write("/proc/bus/input/devices", INPUT_DEVICES.replace("PROP=2", "PROP=1"))
+        self.assertFalse(probe_touch(self.root).present)
+        self.write("/sys/class/power_supply/bat/type", "Battery\n")
+        self.assertEqual((probe_battery(self.root).present, probe_battery(self.root).percent), (True, None))
+        self.write("/dev/pamir-uart")
+        self.assertEqual((probe_sam(root=self.root, timeout_s=0).present,
+                          probe_sam(root=self.root, timeout_s=0).responding), (True, False))
+        legacy = probe_sam(FakeLink(LinkState.UNKNOWN, None, 1))
+        self.assertEqual((legacy.responding, legacy.protocol_version), (False, None))
+        self.assertEqual(probe_sensors(self.root), [])
+
+    def test_panel_error(self):
+        """Test a panel that cannot be opened is absent, with the reason."""
+        with patch.dict(os.environ, {"DISTILLER_EINK_BACKEND": "nonexistent"}):
+            panel = probe_panel()
+        self.assertFalse(panel.present)
+        self.assertTrue(panel.error)
+
+    def test_cli(self):
+        """Test the diagnostics command prints a summary or JSON."""
+        with patch("distiller_cm5_sdk.device.capabilities.probe_panel", return_value=probe_panel(
+                Display(backend="mock", auto_init=False))):
+            with patch("sys.stdout", io.StringIO()) as out:
+                self.assertEqual(main(["capabilities", "--sam-timeout", "0"]), 0)
+            self.assertIn("Panel:   none; mock backend", out.getvalue())
+            with patch("sys.stdout", io.StringIO()) as out:
+                self.assertEqual(main(["capabilities", "--json", "--sam-timeout", "0"]), 0)
+            self.assertEqual(json.loads(out.getvalue())["panel"]["backend"], "mock")
+
+
+if __name__ == "__main__":
+    unittest.main()
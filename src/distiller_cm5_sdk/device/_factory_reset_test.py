@@ -0,0 +1,129 @@
+#!/usr/bin/env python3
+"""
+Factory reset unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.apps.manager import APPS_DIR
+from distiller_cm5_sdk.device.factory_reset import (
+    FactoryResetError, ResetPlan, ResetStep, factory_reset, load_plan,
+)
+from distiller_cm5_sdk.device.lock import LOCK_PATH
+from distiller_cm5_sdk.device.privacy import PRIVACY_PATH
+from distiller_cm5_sdk.diagnostics.retention import RETENTION_PATH
+from distiller_cm5_sdk.events.history import EVENT_HISTORY_PATH
+from distiller_cm5_sdk.events.webhooks import WEBHOOKS_PATH
+from distiller_cm5_sdk.hardware.ir.receiver import KEYMAP_PATH as IR_KEYMAP_PATH
+from distiller_cm5_sdk.i18n.formatting import LOCALE_CONFIG_PATH
+from distiller_cm5_sdk.smarthome.homekit import STATE_PATH as HOMEKIT_STATE_PATH
+
+
+class TestFactoryReset(unittest.TestCase):
+    """Test cases for factory reset orchestration."""
+
+    def setUp(self):
+        """Lay out a fake device filesystem in a temporary directory."""
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.root = self.tmpdir.name
+        self.settings = self.touch("opt/eink.conf")
+        self.content_dir = os.path.join(self.root, "content")
+        self.touch("content/notes/today.txt")
+        self.touch("content/photo.png")
+        self.wifi = self.touch("nm/home.nmconnection")
+        self.keep = self.touch("nm/README")
+        self.plan = ResetPlan(
+            settings=[self.settings],
+            content=[self.content_dir],
+            network=[os.path.join(self.root, "nm", "*.nmconnection")],
+            identity=[os.path.join(self.root, "identity", "*")],
+        )
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def touch(self, relative):
+        path = os.path.join(self.root, relative)
+        os.makedirs(os.path.dirname(path), exist_ok=True)
+        open(path, "w").close()
+        return path
+
+    def test_wipes_every_category(self):
+        """Test files are removed and directories emptied but kept."""
+        events = []
+        self.assertTrue(factory_reset(self.plan, progress=events.append))
+
+        self.assertFalse(os.path.exists(self.settings))
+        self.assertEqual(os.listdir(self.content_dir), [])
+        self.assertFalse(os.path.exists(self.wifi))
+        self.assertTrue(os.path.exists(self.keep))
+        self.assertEqual([e.step for e in events],
+                         [ResetStep.SETTINGS, ResetStep.CONTENT, ResetStep.NETWORK,
+                          ResetStep.IDENTITY, ResetStep.DONE])
+        self.assertEqual(events[0].index, 1)
+        self.assertEqual(events[0].total, 4)
+
+    def test_cancelled_by_confirm(self):
+        """Test nothing is erased when confirmation is declined."""
+        self.assertFalse(factory_reset(self.plan, confirm=lambda: False))
+        self.assertTrue(os.path.exists(self.settings))
+
+    def test_errors_reported_after_all_stages(self):
+        """Test a failing path does not stop later stages."""
+        real_remove = os.remove
+
+        def remove(path):
+            if path == self.settings:
+                raise PermissionError(13, "Permission denied")
+            real_remove(path)
+
+        with patch("distiller_cm5_sdk.device.factory_reset.os.remove", side_effect=remove):
+            with self.assertRaises(FactoryResetError) as ctx:
+                factory_reset(self.plan)
+        self.assertIn("eink.conf", str(ctx.exception))
+        self.assertFalse(os.path.exists(self.wifi))
+
+    def test_load_plan(self):
+        """Test plan files override categories and reject unknown keys."""
+        path = os.path.join(self.root, "factory-reset.conf")
+        with open(path, "w") as f:
+            f.write("content = /data/a/*, /data/b\n")
+        plan = load_plan(path)
+        self.assertEqual(plan.content, ["/data/a/*", "/data/b"])
+        self.assertEqual(plan.network, ResetPlan().network)
+
+        for default in (LOCK_PATH, LOCALE_CONFIG_PATH, WEBHOOKS_PATH, RETENTION_PATH, IR_KEYMAP_PATH):
+            self.assertIn(default, plan.settings)
+        plan = load_plan(os.path.join(self.root, "missing.conf"))
+        for default in (APPS_DIR, EVENT_HISTORY_PATH, "/var/cache/distiller-eink"):
+            self.assertIn(default, plan.content)
+        self.assertNotIn(PRIVACY_PATH, plan.settings)       # mute switch wiring, not a setting
+        self.assertEqual(plan.identity, [HOMEKIT_STATE_PATH])
+
+        with open(path, "w") as f:
+            f.write("wallpaper = /data\n")
+        with self.assertRaises(FactoryResetError):
+            load_plan(path)
+
+
+    def test_paths_match_owners(self):
+        """Test the paths copied to keep other subsystems out of the recovery bundle match their modules."""
+        module = sys.modules["distiller_cm5_sdk.device.factory_reset"]
+        self.assertEqual(module.WEBHOOKS_PATH, WEBHOOKS_PATH)
+        self.assertEqual(module.LOCALE_CONFIG_PATH, LOCALE_CONFIG_PATH)
+        self.assertEqual(module.HOMEKIT_STATE_PATH, HOMEKIT_STATE_PATH)
+        self.assertEqual(module.IR_KEYMAP_PATH, IR_KEYMAP_PATH)
+        self.assertEqual(module.APPS_DIR, APPS_DIR)
+        self.assertEqual(module.EVENT_HISTORY_PATH, EVENT_HISTORY_PATH)
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
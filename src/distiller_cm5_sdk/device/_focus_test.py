@@ -0,0 +1,90 @@
+#!/usr/bin/env python3
+"""
+Resource focus unit tests for CM5 SDK.
+"""
+
+import sys
+import unittest
+from pathlib import Path
+from unittest.mock import Mock
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.device.focus import FocusChange, FocusManager, Priority, Resource
+from distiller_cm5_sdk.events.bus import EventBus
+from distiller_cm5_sdk.events.sources import FOCUS_GRANTED, FOCUS_REVOKED, publish_focus
+
+
+class TestFocusManager(unittest.TestCase):
+    """Test cases for granting and revoking resources."""
+
+    def setUp(self):
+        self.focus = FocusManager()
+        self.changes = []
+        self.focus.add_listener(self.changes.append)
+
+    def test_request_and_release(self):
+        """Test a free resource is granted and waiting requests follow in order."""
+        app, other = Mock(), Mock()
+        self.assertTrue(self.focus.request("app:weather", Resource.DISPLAY, on_change=app))
+        self.assertTrue(self.focus.holds("app:weather", Resource.DISPLAY))
+        self.assertTrue(self.focus.request("app:weather", Resource.DISPLAY, on_change=app))   # already held
+        self.assertFalse(self.focus.request("app:clock", Resource.DISPLAY, on_change=other))
+        self.assertIsNone(self.focus.owner(Resource.AUDIO))
+
+        self.focus.release("app:weather")
+        self.assertEqual(self.focus.owner(Resource.DISPLAY), "app:clock")
+        other.assert_called_once_with(Resource.DISPLAY, True)
+        app.assert_not_called()
+        self.assertEqual(self.changes, [
+            FocusChange(Resource.DISPLAY, "app:weather", True),
+            FocusChange(Resource.DISPLAY, "app:weather", False),
+            FocusChange(Resource.DISPLAY, "app:clock", True),
+        ])
+
+    def test_preemption(self):
+        """Test a higher priority revokes the holder, which gets the resource back afterwards."""
+        app = Mock()
+        self.focus.request("app:weather", Resource.AUDIO, Priority.APP, app)
+        self.focus.request("app:clock", Resource.AUDIO, Priority.APP)
+        self.assertTrue(self.focus.request("timer", Resource.AUDIO, Priority.SERVICE))
+        app.assert_called_once_with(Resource.AUDIO, False)
+        self.assertIn(FocusChange(Resource.AUDIO, "app:weather", False, by="timer"), self.changes)
+        self.assertFalse(self.focus.request("battery", Resource.AUDIO, Priority.SERVICE))
+
+        # Waiting requests go by priority, then by age: the preempted app before the later one
+        self.focus.release("timer")
+        self.assertEqual(self.focus.owner(Resource.AUDIO), "battery")
+        self.focus.release("battery")
+        self.assertEqual(self.focus.owner(Resource.AUDIO), "app:weather")
+        app.assert_called_with(Resource.AUDIO, True)
+
+    def test_release_withdraws_waiting(self):
+        """Test releasing withdraws a waiting request and a failing callback does not stop the others."""
+        self.focus.request("timer", Resource.BUTTONS, Priority.SERVICE)
+        self.focus.request("app:weather", Resource.BUTTONS, on_change=Mock(side_effect=RuntimeError("boom")))
+        self.focus.request("app:clock", Resource.BUTTONS)
+        self.focus.release("app:clock")
+        self.focus.release("timer")
+        self.assertEqual(self.focus.owner(Resource.BUTTONS), "app:weather")
+        self.focus.release("app:weather")
+        self.assertIsNone(self.focus.owner(Resource.BUTTONS))
+
+    def test_events(self):
+        """Test grants and revocations are published on the bus."""
+        bus = EventBus()
+        events = []
+        bus.subscribe("focus.*", events.append)
+        publish_focus(self.focus, bus)
+        self.focus.request("app:weather", Resource.DISPLAY)
+        self.focus.request("battery", Resource.DISPLAY, Priority.ALERT)
+        self.assertEqual([(event.name, event.data) for event in events], [
+            (FOCUS_GRANTED, {"resource": "display", "owner": "app:weather", "by": None}),
+            (FOCUS_REVOKED, {"resource": "display", "owner": "app:weather", "by": "battery"}),
+            (FOCUS_GRANTED, {"resource": "display", "owner": "battery", "by": None}),
+        ])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,85 @@
+#!/usr/bin/env python3
+"""
+Screen lock unit tests for CM5 SDK.
+"""
+
+import os
+import stat
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.device.lock import LockError, ScreenLock, hash_pin, verify_pin
+
+
+class TestScreenLock(unittest.TestCase):
+    """Test cases for the PIN screen lock."""
+
+    def test_hash_round_trip(self):
+        """Test PIN hashes are salted and verify only the right PIN."""
+        first = hash_pin("1234", iterations=1000)
+        self.assertNotEqual(first, hash_pin("1234", iterations=1000))
+        self.assertNotIn("1234", first.split("$")[-1])
+        self.assertTrue(verify_pin("1234", first))
+        self.assertFalse(verify_pin("4321", first))
+        self.assertFalse(verify_pin("1234", "garbage"))
+        with self.assertRaises(LockError):
+            hash_pin("12a4")
+
+    def test_lock_and_unlock(self):
+        """Test an enabled lock starts locked and gates operations."""
+        self.assertFalse(ScreenLock().locked)
+        lock = ScreenLock(hash_pin("1234", iterations=1000))
+        self.assertTrue(lock.locked)
+        with self.assertRaises(LockError):
+            lock.require_unlocked("Drawing")
+        self.assertFalse(lock.unlock("0000"))
+        self.assertTrue(lock.unlock("1234"))
+        lock.require_unlocked()
+        lock.lock()
+        self.assertTrue(lock.locked)
+
+    @patch("distiller_cm5_sdk.device.lock.time.monotonic")
+    def test_lockout(self, mock_time):
+        """Test repeated wrong PINs refuse even the right PIN until the lockout ends."""
+        mock_time.return_value = 100.0
+        lock = ScreenLock(hash_pin("1234", iterations=1000), max_attempts=3, lockout_seconds=30)
+        for _ in range(3):
+            self.assertFalse(lock.unlock("9999"))
+        self.assertFalse(lock.unlock("1234"))
+        self.assertEqual(lock.lockout_remaining(), 30)
+
+        mock_time.return_value = 131.0
+        self.assertTrue(lock.unlock("1234"))
+
+    def test_file_round_trip(self):
+        """Test set_pin() and save() produce a file from_file() reads back."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "lock.conf")
+            self.assertFalse(ScreenLock.from_file(path).enabled)
+
+            lock = ScreenLock(pin_length=6)
+            lock.set_pin("135790")
+            with self.assertRaises(LockError):
+                lock.set_pin("1234")
+            lock.save(path)
+            self.assertEqual(stat.S_IMODE(os.stat(path).st_mode), 0o600)
+
+            loaded = ScreenLock.from_file(path)
+            self.assertTrue(loaded.locked)
+            self.assertEqual(loaded.pin_length, 6)
+            self.assertTrue(loaded.unlock("135790"))
+
+            with open(path, "a") as f:
+                f.write("colour = blue\n")
+            with self.assertRaises(LockError):
+                ScreenLock.from_file(path)
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
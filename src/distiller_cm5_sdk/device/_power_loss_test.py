@@ -0,0 +1,77 @@
+#!/usr/bin/env python3
+"""
+Power-loss handling unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import Mock
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.device.power_loss import PowerLossMonitor, VoltageSensor
+
+
+class TestPowerLossMonitor(unittest.TestCase):
+    """Test cases for spending the hold-up window."""
+
+    def setUp(self):
+        self.now = 0.0
+        self.display = Mock()
+        self.display.get_dimensions.return_value = (128, 250)
+        self.calls = []
+        self.display.abort.side_effect = lambda: self.calls.append("abort")
+        self.display.emergency_frame.side_effect = lambda frame: self.calls.append(
+            "frame" if frame is not None else "sleep")
+
+    def handler(self, name, seconds=0.0, fail=False):
+        def run():
+            self.calls.append(name)
+            self.now += seconds
+            if fail:
+                raise OSError("disk gone")
+        return run
+
+    def test_order_and_frame(self):
+        """The display is aborted first, handlers run in order, then the final frame is shown."""
+        monitor = PowerLossMonitor(display=self.display, hold_up_s=2.0, clock=lambda: self.now)
+        monitor.add_handler("settings", self.handler("settings", 0.2))
+        monitor.add_handler("logs", self.handler("logs", fail=True))
+        report = monitor.trigger("test")
+        self.assertEqual(self.calls, ["abort", "settings", "logs", "frame"])
+        self.assertEqual((report.handlers_run, report.handlers_failed), (["settings"], ["logs"]))
+        self.assertTrue(report.frame_shown)
+        self.assertAlmostEqual(report.elapsed_s, 0.2)
+        self.assertIs(monitor.trigger("again"), report)
+        self.assertEqual(self.display.emergency_frame.call_count, 1)
+
+    def test_budget(self):
+        """Handlers past the hold-up window are skipped, and the frame needs its reserve."""
+        monitor = PowerLossMonitor(display=self.display, hold_up_s=1.0, clock=lambda: self.now)
+        monitor.add_handler("state", self.handler("state", 0.5))
+        monitor.add_handler("cache", self.handler("cache", 0.6))
+        monitor.add_handler("stats", self.handler("stats"))
+        report = monitor.trigger()
+        self.assertEqual(report.handlers_skipped, ["stats"])
+        self.assertFalse(report.frame_shown)
+        self.assertEqual(self.calls[-1], "sleep")
+
+    def test_voltage_sensor(self):
+        """A hold-up voltage below the threshold triggers on the next check."""
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "in_voltage0_raw")
+            Path(path).write_text("5100\n")
+            monitor = PowerLossMonitor(VoltageSensor(path, threshold_v=4.6), hold_up_s=1.0)
+            self.assertFalse(monitor.check())
+            Path(path).write_text("4400\n")
+            self.assertTrue(monitor.check())
+            os.unlink(path)
+            self.assertTrue(monitor.check())   # stays triggered
+
+
+if __name__ == "__main__":
+    unittest.main()
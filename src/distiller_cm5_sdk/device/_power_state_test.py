@@ -0,0 +1,85 @@
+#!/usr/bin/env python3
+"""
+Sleep/wake state machine unit tests for CM5 SDK.
+"""
+
+import sys
+import unittest
+from pathlib import Path
+from unittest.mock import Mock, patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.device.power_state import PowerState, PowerStateError, PowerStateMachine
+
+
+class TestPowerStateMachine(unittest.TestCase):
+    """Test cases for power state transitions."""
+
+    def setUp(self):
+        """Create a machine over a mock display with a controllable clock."""
+        self.now = 1000.0
+        patcher = patch("distiller_cm5_sdk.device.power_state.time.monotonic", side_effect=lambda: self.now)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+        self.display = Mock()
+        self.machine = PowerStateMachine(self.display, dim_after=10, sleep_after=60,
+                                         suspend_after=None, suspend_command=None)
+        self.events = []
+        self.machine.add_listener(self.events.append)
+
+    def test_idle_timeouts(self):
+        """Test idle time steps through DIM and PANEL_SLEEP, and activity wakes."""
+        self.now += 5
+        self.assertEqual(self.machine.tick(), PowerState.ACTIVE)
+        self.now += 5
+        self.assertEqual(self.machine.tick(), PowerState.DIM)
+        self.display.sleep.assert_not_called()
+        self.now += 100
+        self.assertEqual(self.machine.tick(), PowerState.PANEL_SLEEP)
+        self.display.sleep.assert_called_once()
+        self.assertEqual(self.machine.tick(), PowerState.PANEL_SLEEP)
+
+        self.machine.activity()
+        self.assertEqual(self.machine.state, PowerState.ACTIVE)
+        self.display.wake.assert_called_once()
+        self.assertEqual([(e.previous, e.state, e.reason) for e in self.events], [
+            (PowerState.ACTIVE, PowerState.DIM, "idle"),
+            (PowerState.DIM, PowerState.PANEL_SLEEP, "idle"),
+            (PowerState.PANEL_SLEEP, PowerState.ACTIVE, "activity"),
+        ])
+
+    def test_veto(self):
+        """Test a veto hook holds the state until it allows the transition."""
+        busy = [True]
+        self.machine.add_veto(lambda transition: not busy[0])
+        self.assertFalse(self.machine.request(PowerState.PANEL_SLEEP))
+        self.now += 10
+        self.assertEqual(self.machine.tick(), PowerState.ACTIVE)
+
+        busy[0] = False
+        self.assertEqual(self.machine.tick(), PowerState.DIM)
+        busy[0] = True
+        self.assertTrue(self.machine.request(PowerState.ACTIVE))
+
+    def test_invalid_transition(self):
+        """Test going shallower to anything but ACTIVE is refused."""
+        self.machine.request(PowerState.PANEL_SLEEP)
+        with self.assertRaises(PowerStateError):
+            self.machine.request(PowerState.DIM)
+
+    @patch("distiller_cm5_sdk.device.power_state.subprocess.run")
+    def test_suspend_resumes(self, mock_run):
+        """Test SUSPEND runs the suspend command and wakes once it returns."""
+        self.machine.suspend_command = ("systemctl", "suspend")
+        self.machine.request(PowerState.SUSPEND, "button")
+        mock_run.assert_called_once()
+        self.assertEqual(self.machine.state, PowerState.ACTIVE)
+        self.assertEqual([e.state for e in self.events], [PowerState.SUSPEND, PowerState.ACTIVE])
+        self.display.sleep.assert_called_once()
+        self.display.wake.assert_called_once()
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
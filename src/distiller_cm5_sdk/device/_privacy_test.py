@@ -0,0 +1,162 @@
+#!/usr/bin/env python3
+"""
+Privacy mode unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import Mock, patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.device import privacy
+from distiller_cm5_sdk.device.privacy import (
+    FileMuteSwitch, MuteMonitor, PrivacyError, SoftwareMuteSwitch, capture_allowed, draw_mic_muted, is_mic_muted,
+    load_mute_switch, set_mute_switch,
+)
+from distiller_cm5_sdk.diagnostics.audit import AuditCategory, disable_audit_log, enable_audit_log
+from distiller_cm5_sdk.events.bus import EventBus
+from distiller_cm5_sdk.events.sources import publish_mic_mute
+from distiller_cm5_sdk.hardware.eink.graphics import Framebuffer
+
+
+class TestMuteSwitch(unittest.TestCase):
+    """Test cases for reading and configuring the mute switch."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.state = os.path.join(self.tmpdir.name, "muted")
+
+    def tearDown(self):
+        set_mute_switch(None)
+        self.tmpdir.cleanup()
+
+    def write(self, name, text):
+        path = os.path.join(self.tmpdir.name, name)
+        with open(path, "w") as f:
+            f.write(text)
+        return path
+
+    def test_load(self):
+        """Test the configuration names a sysfs switch, or none."""
+        switch = load_mute_switch(self.write("privacy.conf", f"mute_path = {self.state}\n"))
+        self.assertIsInstance(switch, FileMuteSwitch)
+        self.write("muted", "1\n")
+        self.assertTrue(switch.is_muted())
+        self.write("muted", "0\n")
+        self.assertFalse(switch.is_muted())
+
+        self.assertIsNone(load_mute_switch(os.path.join(self.tmpdir.name, "missing.conf")))
+        with self.assertRaisesRegex(PrivacyError, "unknown key 'mute_pin'"):
+            load_mute_switch(self.write("privacy.conf", "mute_pin = 5\n"))
+        with self.assertRaisesRegex(PrivacyError, "exclusive"):
+            load_mute_switch(self.write("privacy.conf", "mute_gpio = 5\nmute_path = /x\n"))
+
+    def test_fail_closed(self):
+        """Test an unreadable or broken switch counts as muted, and no switch as live."""
+        set_mute_switch(None)
+        self.assertFalse(is_mic_muted())
+        set_mute_switch(FileMuteSwitch(self.state))
+        self.assertTrue(is_mic_muted())
+        self.write("muted", "on\n")
+        self.assertTrue(is_mic_muted())
+        self.write("muted", "0\n")
+        self.assertFalse(is_mic_muted())
+
+        privacy._switch_loaded = False
+        with patch.object(privacy, "load_mute_switch", side_effect=PrivacyError("no gpiod")):
+            self.assertTrue(is_mic_muted())
+        privacy._switch_loaded = False
+        with patch.object(privacy, "load_mute_switch", return_value=None):
+            self.assertFalse(is_mic_muted())
+
+    def test_capture_allowed(self):
+        """Test refused captures are audited."""
+        log = enable_audit_log(os.path.join(self.tmpdir.name, "audit.log"))
+        try:
+            switch = SoftwareMuteSwitch()
+            set_mute_switch(switch)
+            self.assertTrue(capture_allowed("audio"))
+            switch.set_muted(True)
+            self.assertFalse(capture_allowed("parakeet"))
+            entries = log.query(category=AuditCategory.SECURITY)
+            self.assertEqual([(e.action, e.source) for e in entries], [("capture_refused", "parakeet")])
+        finally:
+            disable_audit_log()
+
+
+class TestMuteMonitor(unittest.TestCase):
+    """Test cases for following the switch."""
+
+    def setUp(self):
+        self.switch = SoftwareMuteSwitch()
+        set_mute_switch(self.switch)
+        self.frame = Framebuffer(128, 250)
+        self.frame.draw_rect(100, 0, 28, 28, fill=True)
+        self.display = Mock()
+        self.display.get_dimensions.return_value = (128, 250)
+        self.display.get_framebuffer.side_effect = lambda: self.frame.to_bytes()
+        self.display.display_image.side_effect = lambda data, mode: setattr(
+            self, "frame", Framebuffer(128, 250, data))
+
+    def tearDown(self):
+        set_mute_switch(None)
+
+    def test_changes(self):
+        """Test listeners and events see each change once."""
+        bus = EventBus()
+        events = []
+        bus.subscribe("privacy.*", lambda event: events.append(event.name))
+        monitor = MuteMonitor()
+        publish_mic_mute(monitor, bus)
+        self.assertFalse(monitor.check())
+        self.switch.set_muted(True)
+        self.assertTrue(monitor.check())
+        self.assertTrue(monitor.check())
+        self.switch.set_muted(False)
+        monitor.check()
+        self.assertEqual(events, ["privacy.mic_unmuted", "privacy.mic_muted", "privacy.mic_unmuted"])
+
+    def test_glyph(self):
+        """Test the glyph covers the top-right corner while muted, and the pixels under it come back."""
+        original = self.frame.to_bytes()
+        monitor = MuteMonitor(display=self.display)
+        monitor.check()
+        self.display.display_image.assert_not_called()
+
+        self.switch.set_muted(True)
+        monitor.check()
+        expected = Framebuffer(128, 250, original)
+        draw_mic_muted(expected, 110, 2)
+        self.assertEqual(self.frame.to_bytes(), expected.to_bytes())
+        self.switch.set_muted(False)
+        monitor.check()
+        self.assertEqual(self.frame.to_bytes(), original)
+
+        # A frame drawn while muted is left alone
+        self.switch.set_muted(True)
+        monitor.check()
+        self.frame = Framebuffer(128, 250)
+        self.switch.set_muted(False)
+        monitor.check()
+        self.assertEqual(self.display.display_image.call_count, 3)
+        self.assertEqual(self.frame.to_bytes(), Framebuffer(128, 250).to_bytes())
+
+    def test_glyph_drawing(self):
+        """Test the glyph stays within its square at any size."""
+        for size in (8, 16, 24):
+            fb = Framebuffer(40, 40, fill=0)
+            draw_mic_muted(fb, 4, 4, size)
+            for y in range(40):
+                for x in range(40):
+                    if not (4 <= x < 4 + size and 4 <= y < 4 + size):
+                        self.assertEqual(fb.get_pixel(x, y), 0, (size, x, y))
+            self.assertIn(0, [fb.get_pixel(4 + size // 2, y) for y in range(4, 4 + size)])
+
+
+if __name__ == "__main__":
+    unittest.main()
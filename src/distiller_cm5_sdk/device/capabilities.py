@@ -0,0 +1,258 @@
+#!/usr/bin/env python3
+"""
+Capability discovery for CM5 SDK.
+
+Distiller units differ in panel, audio, touch, sensors and battery. Rather
+than ship a build per SKU, an application asks what this unit has and
+adapts at runtime:
+
+    caps = capabilities()
+    if caps.panel.present and caps.panel.width >= 400:
+        show_two_columns()
+    if not caps.audio.input:
+        disable_voice_commands()
+    if caps.battery.present:
+        BatteryMonitor(lambda: probe_battery().percent).start()
+
+Each part is probed on its own, from the kernel's view of the hardware
+(sysfs, procfs, /dev) and the SDK's own drivers, so one missing or broken
+part never hides the rest. Nothing is drawn on the panel; the only
+hardware access is a Hello exchange with the SAM MCU for its firmware
+version, which sam_timeout_s=0 skips.
+"""
+
+import glob
+import os
+import time
+from dataclasses import asdict, dataclass, field
+from typing import List, Optional
+
+from ..hardware.eink.display import Display, DisplayError
+
+
+# How long to wait for the SAM MCU to answer Hello
+SAM_TIMEOUT_S = 1.0
+
+# Input device property (linux/input-event-codes.h) set for touchscreens, not touchpads
+_INPUT_PROP_DIRECT = 1
+
+
+@dataclass
+class PanelCapability:
+    """The e-ink panel, as configured; see Display.get_info()."""
+    present: bool                       # a panel driver is loaded, not a headless fallback
+    panel: Optional[str] = None         # PanelType value, e.g. "epd128x250"
+    width: int = 0                      # in the configured rotation
+    height: int = 0
+    backend: Optional[str] = None
+    error: Optional[str] = None         # why the panel is not present
+
+
+@dataclass
+class AudioCapability:
+    """Sound cards the kernel found, and the Pamir soundcard's level controls."""
+    input: bool = False                 # a capture device, e.g. the microphones
+    output: bool = False                # a playback device, e.g. the speaker
+    controls: bool = False              # microphone gain and speaker volume are adjustable
+    cards: List[str] = field(default_factory=list)
+
+
+@dataclass
+class TouchCapability:
+    """A touchscreen among the input devices."""
+    present: bool = False
+    name: Optional[str] = None
+    device: Optional[str] = None        # event device, e.g. "/dev/input/event2"
+
+
+@dataclass
+class SensorInfo:
+    """A sensor driver bound in the kernel."""
+    name: str
+    kind: str                           # "iio" (light, IMU, ADC, ...) or "hwmon" (temperatures, voltages)
+    path: str
+
+
+@dataclass
+class SamCapability:
+    """The SAM MCU on /dev/pamir-uart."""
+    present: bool = False               # the UART device exists
+    responding: bool = False            # the MCU answered
+    firmware: Optional[str] = None
+    protocol_version: Optional[int] = None
+
+
+@dataclass
+class BatteryCapability:
+    """The battery, if the kernel has a power supply of type Battery."""
+    present: bool = False
+    percent: Optional[float] = None
+    status: Optional[str] = None        # "charging", "discharging", "full", ...
+
+
+@dataclass
+class Capabilities:
+    """What is present and working on this unit."""
+    model: Optional[str]                # board model from the device tree
+    panel: PanelCapability
+    audio: AudioCapability
+    touch: TouchCapability
+    sensors: List[SensorInfo]
+    sam: SamCapability
+    battery: BatteryCapability
+
+    def to_dict(self) -> dict:
+        """The capabilities as JSON-serializable data."""
+        return asdict(self)
+
+
+def _path(root: str, path: str) -> str:
+    return os.path.join(root, path.lstrip("/"))
+
+
+def _read(path: str) -> Optional[str]:
+    try:
+        with open(path, "r", errors="replace") as f:
+            return f.read().replace("\x00", "").strip()
+    except OSError:
+        return None
+
+
+def probe_panel(display=None) -> PanelCapability:
+    """
+    Describe the panel without touching it.
+
+    Args:
+        display: Open Display to describe; None opens one from the display config
+    """
+    from ..hardware.eink.backends import NATIVE_BACKEND, PYTHON_BACKEND
+    try:
+        own = display is None
+        display = display or Display(auto_init=False)
+        try:
+            info = display.get_info()
+        finally:
+            if own:
+                display.close()
+    except (DisplayError, OSError) as e:
+        return PanelCapability(False, error=str(e))
+    present = info["backend"] in (NATIVE_BACKEND, PYTHON_BACKEND) and info["degraded"] is None
+    error = info["degraded"] or (None if present else f"{info['backend']} backend, no panel driver")
+    return PanelCapability(present, info["panel"], info["width"], info["height"], info["backend"], error)
+
+
+def probe_audio(root: str = "/") -> AudioCapability:
+    """Find capture and playback devices in /proc/asound."""
+    audio = AudioCapability()
+    for line in (_read(_path(root, "/proc/asound/pcm")) or "").splitlines():
+        audio.input = audio.input or "capture" in line
+        audio.output = audio.output or "playback" in line
+    for line in (_read(_path(root, "/proc/asound/cards")) or "").splitlines():
+        if "]:" in line:
+            audio.cards.append(line.split("]:", 1)[1].strip())
+    try:
+        from ..hardware.audio.audio import Audio
+        audio.controls = all(os.path.exists(_path(root, path)) for path in (Audio.MIC_GAIN_PATH,
+                                                                             Audio.SPEAKER_VOLUME_PATH))
+    except ImportError:     # the audio module needs numpy
+        pass
+    return audio
+
+
+def probe_touch(root: str = "/") -> TouchCapability:
+    """Find a touchscreen in /proc/bus/input/devices: an absolute pointer with INPUT_PROP_DIRECT."""
+    for block in (_read(_path(root, "/proc/bus/input/devices")) or "").split("\n\n"):
+        name = device = None
+        direct = absolute = False
+        for line in block.splitlines():
+            if line.startswith("N: Name="):
+                name = line[len("N: Name="):].strip('"')
+            elif line.startswith("H: Handlers="):
+                events = [handler for handler in line.split("=", 1)[1].split() if handler.startswith("event")]
+                device = f"/dev/input/{events[0]}" if events else None
+            elif line.startswith("B: PROP="):
+                direct = bool(int(line.split("=", 1)[1].split()[-1], 16) & (1 << _INPUT_PROP_DIRECT))
+            elif line.startswith("B: ABS="):
+                absolute = any(int(word, 16) for word in line.split("=", 1)[1].split())
+        if direct and absolute:
+            return TouchCapability(True, name, device)
+    return TouchCapability()
+
+
+def probe_sensors(root: str = "/") -> List[SensorInfo]:
+    """List the IIO and hwmon sensor drivers bound in sysfs."""
+    sensors = []
+    for kind, pattern in (("iio", "/sys/bus/iio/devices/iio:device*"), ("hwmon", "/sys/class/hwmon/hwmon*")):
+        for path in sorted(glob.glob(_path(root, pattern))):
+            name = _read(os.path.join(path, "name"))
+            if name:
+                sensors.append(SensorInfo(name, kind, "/" + os.path.relpath(path, root)))
+    return sensors
+
+
+def probe_sam(link=None, root: str = "/", timeout_s: float = SAM_TIMEOUT_S) -> SamCapability:
+    """
+    Find the SAM MCU and ask for its firmware version.
+
+    Args:
+        link: Running SamLink to read the version from; None opens the UART
+              for a Hello exchange of up to timeout_s seconds
+        root: Root of the filesystem to look in
+        timeout_s: 0 to only check the UART device exists
+    """
+    from ..hardware.sam.link import DEVICE_PATH, LinkState, SamLink, UartChannel
+    if link is None:
+        path = _path(root, DEVICE_PATH)
+        if not os.path.exists(path):
+            return SamCapability()
+        if timeout_s <= 0:
+            return SamCapability(True)
+        link = SamLink(UartChannel(path), interval_s=timeout_s)
+        deadline = time.monotonic() + timeout_s
+        try:
+            while link.firmware is None and link.state != LinkState.UP and time.monotonic() < deadline:
+                link.poll(0.1)
+        finally:
+            link.stop()
+    # Firmware without Hello still answers heartbeats
+    responding = link.state == LinkState.UP or link.firmware is not None
+    return SamCapability(True, responding, link.firmware, link.protocol_version if responding else None)
+
+
+def probe_battery(root: str = "/") -> BatteryCapability:
+    """Read the first power supply of type Battery in /sys/class/power_supply."""
+    for path in sorted(glob.glob(_path(root, "/sys/class/power_supply/*"))):
+        if _read(os.path.join(path, "type")) != "Battery":
+            continue
+        capacity = _read(os.path.join(path, "capacity"))
+        status = _read(os.path.join(path, "status"))
+        try:
+            percent = float(capacity) if capacity else None
+        except ValueError:
+            percent = None
+        return BatteryCapability(True, percent, status.lower() if status else None)
+    return BatteryCapability()
+
+
+def capabilities(display=None, sam_link=None, root: str = "/", sam_timeout_s: float = SAM_TIMEOUT_S) -> Capabilities:
+    """
+    Describe what is present and working on this unit.
+
+    Args:
+        display: Open Display to describe; None opens one from the display config
+        sam_link: Running SamLink; None asks the MCU directly (see probe_sam())
+        root: Root of the filesystem to probe, e.g. a copy of a unit's /sys and /proc
+        sam_timeout_s: Longest wait for the SAM MCU; 0 skips asking it
+
+    Returns:
+        Capabilities; a part that cannot be probed is reported absent
+    """
+    return Capabilities(
+        model=_read(_path(root, "/proc/device-tree/model")),
+        panel=probe_panel(display),
+        audio=probe_audio(root),
+        touch=probe_touch(root),
+        sensors=probe_sensors(root),
+        sam=probe_sam(sam_link, root, sam_timeout_s),
+        battery=probe_battery(root),
+    )
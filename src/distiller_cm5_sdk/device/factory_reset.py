@@ -0,0 +1,214 @@
+#!/usr/bin/env python3
+"""
+Factory reset orchestration for CM5 SDK.
+
+Wipes the settings store, content directory, network credentials and
+identity enrollment in a fixed order, reporting progress through a callback.
+What belongs to each category is described by a per-SKU plan file instead of
+per-SKU shell scripts:
+
+    # /opt/distiller-cm5-sdk/factory-reset.conf
+    settings = /opt/distiller-cm5-sdk/eink.toml, /opt/distiller-cm5-sdk/settings/*
+    content = /home/distiller/content/*
+    network = /etc/NetworkManager/system-connections/*
+    identity = /opt/distiller-cm5-sdk/identity/*
+"""
+
+import glob
+import os
+import shutil
+from dataclasses import dataclass, field
+from enum import Enum
+from typing import Callable, List, Optional
+
+from ..diagnostics.audit import AuditCategory, audit
+from ..diagnostics.retention import RETENTION_PATH
+from ..hardware.eink.config import CONFIG_PATH as EINK_CONFIG_PATH
+from ..hardware.eink.config import STRUCTURED_CONFIG_PATH as EINK_STRUCTURED_CONFIG_PATH
+from ..hardware.eink.config import read_config_file
+from ..hardware.eink.display import DisplayError
+from .lock import LOCK_PATH
+
+
+PLAN_PATH = "/opt/distiller-cm5-sdk/factory-reset.conf"
+
+# Files of subsystems the recovery bundle leaves out, so they are not imported
+# here; _factory_reset_test.py checks them against the owning modules
+WEBHOOKS_PATH = "/opt/distiller-cm5-sdk/webhooks.conf"              # events.webhooks
+LOCALE_CONFIG_PATH = "/opt/distiller-cm5-sdk/locale.conf"           # i18n.formatting
+HOMEKIT_STATE_PATH = "/var/lib/distiller-cm5-sdk/homekit.state"     # smarthome.homekit.STATE_PATH
+IR_KEYMAP_PATH = "/var/lib/distiller-cm5-sdk/ir-keymap.json"        # hardware.ir.receiver.KEYMAP_PATH
+APPS_DIR = "/opt/distiller-cm5-sdk/apps"                            # apps.manager
+EVENT_HISTORY_PATH = "/var/log/distiller-cm5-sdk/events.jsonl"      # events.history
+
+# Processed image cache suggested for the e-ink cache_dir setting
+EINK_CACHE_DIR = "/var/cache/distiller-eink"
+
+RESET_WARNING = "All settings and user data will be erased."
+
+
+class FactoryResetError(Exception):
+    """Custom exception for factory reset errors."""
+    pass
+
+
+class ResetStep(Enum):
+    """Stages of a factory reset, in the order they run."""
+    SETTINGS = "settings"
+    CONTENT = "content"
+    NETWORK = "network"
+    IDENTITY = "identity"
+    DONE = "done"
+
+
+@dataclass
+class ResetProgress:
+    """Progress event emitted as each stage starts and when the reset finishes."""
+    step: ResetStep
+    index: int              # 1-based stage number
+    total: int              # number of stages, excluding DONE
+    message: str
+    errors: List[str] = field(default_factory=list)
+
+
+@dataclass
+class ResetPlan:
+    """
+    Glob patterns wiped by each stage.
+
+    Matching files and symlinks are deleted. Matching directories are emptied
+    but kept, so services that own them keep working after the reset.
+
+    The mute switch wiring in privacy.conf is hardware description, not a
+    setting, and is kept: deleting it would stop enforcing the mute switch.
+    """
+    settings: List[str] = field(default_factory=lambda: [EINK_STRUCTURED_CONFIG_PATH, EINK_CONFIG_PATH, LOCK_PATH,
+                                                         LOCALE_CONFIG_PATH, WEBHOOKS_PATH, RETENTION_PATH,
+                                                         IR_KEYMAP_PATH])
+    content: List[str] = field(default_factory=lambda: [APPS_DIR, EVENT_HISTORY_PATH, EVENT_HISTORY_PATH + ".1",
+                                                        EINK_CACHE_DIR])
+    network: List[str] = field(default_factory=lambda: ["/etc/NetworkManager/system-connections/*"])
+    identity: List[str] = field(default_factory=lambda: [HOMEKIT_STATE_PATH])
+
+    def patterns(self, step: ResetStep) -> List[str]:
+        return getattr(self, step.value)
+
+
+_STAGES = (ResetStep.SETTINGS, ResetStep.CONTENT, ResetStep.NETWORK, ResetStep.IDENTITY)
+
+_STAGE_MESSAGES = {
+    ResetStep.SETTINGS: "Erasing settings",
+    ResetStep.CONTENT: "Erasing content",
+    ResetStep.NETWORK: "Removing network credentials",
+    ResetStep.IDENTITY: "Removing identity enrollment",
+}
+
+
+def load_plan(path: str = PLAN_PATH) -> ResetPlan:
+    """
+    Load the reset plan for this device.
+
+    Each key holds comma-separated glob patterns and replaces that category's
+    default. A missing file yields the default plan.
+
+    Args:
+        path: Plan file path
+
+    Returns:
+        ResetPlan
+
+    Raises:
+        FactoryResetError: If the file is malformed or has an unknown key
+    """
+    try:
+        values = read_config_file(path)
+    except DisplayError as e:
+        raise FactoryResetError(str(e))
+
+    plan = ResetPlan()
+    for key, value in values.items():
+        if key not in {step.value for step in _STAGES}:
+            raise FactoryResetError(f"{path}: unknown key '{key}'")
+        setattr(plan, key, [p.strip() for p in value.split(",") if p.strip()])
+    return plan
+
+
+def _wipe(pattern: str) -> List[str]:
+    """Delete everything matching a pattern. Returns error messages."""
+    errors = []
+    for path in glob.glob(pattern):
+        try:
+            if os.path.isdir(path) and not os.path.islink(path):
+                for entry in os.listdir(path):
+                    child = os.path.join(path, entry)
+                    if os.path.isdir(child) and not os.path.islink(child):
+                        shutil.rmtree(child)
+                    else:
+                        os.remove(child)
+            else:
+                os.remove(path)
+        except OSError as e:
+            errors.append(f"{path}: {e.strerror or e}")
+    return errors
+
+
+def factory_reset(plan: Optional[ResetPlan] = None,
+                  confirm: Optional[Callable[[], bool]] = None,
+                  progress: Optional[Callable[[ResetProgress], None]] = None) -> bool:
+    """
+    Restore the device to its factory state.
+
+    Every stage runs even if an earlier one fails, so a single unremovable file
+    does not leave credentials behind. Rebooting afterwards is left to the caller.
+
+    Args:
+        plan: What to wipe. If None, loaded with load_plan().
+        confirm: Called before anything is erased; returning False cancels the
+                 reset. Use confirm_on_panel() to ask on the e-ink display.
+        progress: Called with a ResetProgress as each stage starts and once more
+                  with ResetStep.DONE when the reset has finished
+
+    Returns:
+        True if the reset ran, False if it was cancelled
+
+    Raises:
+        FactoryResetError: If the plan is invalid or any path could not be removed
+    """
+    if plan is None:
+        plan = load_plan()
+
+    if confirm is not None and not confirm():
+        return False
+
+    total = len(_STAGES)
+    errors = []
+    for index, step in enumerate(_STAGES, 1):
+        if progress is not None:
+            progress(ResetProgress(step, index, total, _STAGE_MESSAGES[step], list(errors)))
+        for pattern in plan.patterns(step):
+            errors.extend(_wipe(pattern))
+
+    audit(AuditCategory.CONFIG, "factory_reset", errors=len(errors))
+
+    if progress is not None:
+        message = "Factory reset complete" if not errors else "Factory reset finished with errors"
+        progress(ResetProgress(ResetStep.DONE, total, total, message, list(errors)))
+
+    if errors:
+        raise FactoryResetError("Factory reset incomplete:\n" + "\n".join(f"  - {e}" for e in errors))
+    return True
+
+
+def confirm_on_panel(display, buttons) -> bool:
+    """
+    Ask for factory reset confirmation on the e-ink display.
+
+    Args:
+        display: Initialized Display
+        buttons: recovery.ButtonSource; SELECT confirms, any other button cancels
+
+    Returns:
+        True if the user confirmed
+    """
+    from ..recovery.recovery import RecoveryUI
+    return RecoveryUI(display, buttons).confirm("Factory reset", RESET_WARNING)
@@ -0,0 +1,164 @@
+#!/usr/bin/env python3
+"""
+Resource focus for CM5 SDK.
+
+Decides which consumer owns the display, audio and button input when
+several run in one process, e.g. the mini-app launcher, the app it runs and
+system services that need to interrupt it:
+
+    focus = FocusManager()
+    focus.request("timer", Resource.AUDIO, Priority.SERVICE, on_change)
+    ...
+    focus.release("timer")
+
+A request is granted at once when the resource is free or held at a lower
+priority; the holder is then revoked and waits to get the resource back.
+Otherwise the request waits in line. Waiting requests are granted by
+priority, and in order of arrival within one priority, as the resource is
+released. on_change(resource, granted) reports every later grant and
+revocation to the requester.
+"""
+
+import itertools
+import threading
+from dataclasses import dataclass, field
+from enum import Enum, IntEnum
+from typing import Callable, Dict, List, Optional
+
+
+class Resource(Enum):
+    """Resources owned by one consumer at a time."""
+    DISPLAY = "display"
+    AUDIO = "audio"
+    BUTTONS = "buttons"
+
+
+class Priority(IntEnum):
+    """Request priorities; a higher one takes a resource from a lower one."""
+    APP = 0             # the foreground mini-app or application
+    SERVICE = 1         # system services, e.g. a timer ringing
+    ALERT = 2           # must be seen now, e.g. low battery or power loss
+
+
+ChangeCallback = Callable[[Resource, bool], None]
+
+
+@dataclass(frozen=True)
+class FocusChange:
+    """A resource granted to or revoked from an owner."""
+    resource: Resource
+    owner: str
+    granted: bool
+    by: Optional[str] = None        # owner whose request revoked it
+
+
+@dataclass
+class _Request:
+    owner: str
+    priority: Priority
+    on_change: Optional[ChangeCallback]
+    order: int = field(default=0)
+
+
+class FocusManager:
+    """Arbitrates the display, audio and buttons between named owners."""
+
+    def __init__(self):
+        self._lock = threading.RLock()
+        self._holders: Dict[Resource, _Request] = {}
+        self._waiting: Dict[Resource, List[_Request]] = {resource: [] for resource in Resource}
+        self._listeners: List[Callable[[FocusChange], None]] = []
+        self._order = itertools.count()
+
+    def add_listener(self, callback: Callable[[FocusChange], None]) -> None:
+        """Call callback after every grant and revocation, including immediate grants."""
+        self._listeners.append(callback)
+
+    def remove_listener(self, callback: Callable[[FocusChange], None]) -> None:
+        """Stop calling a listener."""
+        self._listeners.remove(callback)
+
+    def owner(self, resource: Resource) -> Optional[str]:
+        """Owner currently holding a resource, or None."""
+        with self._lock:
+            holder = self._holders.get(resource)
+            return holder.owner if holder is not None else None
+
+    def holds(self, owner: str, resource: Resource) -> bool:
+        """Whether owner currently holds the resource."""
+        return self.owner(resource) == owner
+
+    def request(self, owner: str, resource: Resource, priority: Priority = Priority.APP,
+                on_change: Optional[ChangeCallback] = None) -> bool:
+        """
+        Ask for a resource.
+
+        Args:
+            owner: Name of the requester, e.g. "app:weather" or "timer"
+            resource: Resource wanted
+            priority: Takes the resource from holders of lower priority
+            on_change: Called with (resource, granted) when the resource is
+                       later granted or revoked; not called for an immediate grant
+
+        Returns:
+            True if granted now, False if the request waits in line
+        """
+        changes = []
+        with self._lock:
+            self._withdraw(owner, resource)
+            holder = self._holders.get(resource)
+            if holder is not None and holder.owner == owner:
+                holder.priority, holder.on_change = Priority(priority), on_change
+                return True
+            request = _Request(owner, Priority(priority), on_change, next(self._order))
+            if holder is not None and holder.priority >= request.priority:
+                self._waiting[resource].append(request)
+                return False
+            if holder is not None:
+                # The revoked holder waits ahead of its priority's later requests
+                self._waiting[resource].append(holder)
+                changes.append((holder, FocusChange(resource, holder.owner, False, by=owner)))
+            self._holders[resource] = request
+            changes.append((None, FocusChange(resource, owner, True)))
+        self._notify(changes)
+        return True
+
+    def release(self, owner: str, resource: Optional[Resource] = None) -> None:
+        """
+        Give up a resource, or all of owner's resources, and withdraw waiting requests.
+
+        The next waiting request is granted the released resource.
+        """
+        changes = []
+        with self._lock:
+            for item in [resource] if resource is not None else list(Resource):
+                self._withdraw(owner, item)
+                holder = self._holders.get(item)
+                if holder is None or holder.owner != owner:
+                    continue
+                del self._holders[item]
+                changes.append((None, FocusChange(item, owner, False)))
+                waiting = self._waiting[item]
+                if waiting:
+                    waiting.sort(key=lambda request: (-request.priority, request.order))
+                    successor = waiting.pop(0)
+                    self._holders[item] = successor
+                    changes.append((successor, FocusChange(item, successor.owner, True)))
+        self._notify(changes)
+
+    def _withdraw(self, owner: str, resource: Resource) -> None:
+        self._waiting[resource] = [request for request in self._waiting[resource] if request.owner != owner]
+
+    def _notify(self, changes) -> None:
+        """Report changes outside the lock, so callbacks may request and release."""
+        for request, change in changes:
+            if request is not None and request.on_change is not None:
+                try:
+                    request.on_change(change.resource, change.granted)
+                except Exception as e:
+                    print(f"Warning: Focus callback of {change.owner} failed: {e}")
+            for listener in list(self._listeners):
+                try:
+                    listener(change)
+                except Exception as e:
+                    print(f"Warning: Focus listener failed: {e}")
@@ -0,0 +1,245 @@
+#!/usr/bin/env python3
+"""
+Screen lock for CM5 SDK.
+
+An optional PIN lock for devices in shared spaces. While locked, the local
+menu stays behind the PIN entry screen and services that accept drawing
+requests from other processes should refuse them (see require_unlocked()).
+
+The lock is enabled by a PIN hash in its configuration file:
+
+    # /opt/distiller-cm5-sdk/lock.conf
+    pin = pbkdf2_sha256$100000$<salt hex>$<hash hex>
+    pin_length = 4
+    max_attempts = 5
+    lockout_seconds = 30
+"""
+
+import hashlib
+import hmac
+import os
+import threading
+import time
+from typing import Dict, Optional
+
+from ..diagnostics.audit import AuditCategory, audit
+from ..hardware.eink.config import read_config_file
+from ..hardware.eink.display import DisplayError
+
+
+LOCK_PATH = "/opt/distiller-cm5-sdk/lock.conf"
+
+DEFAULT_PIN_LENGTH = 4
+DEFAULT_MAX_ATTEMPTS = 5
+DEFAULT_LOCKOUT_S = 30
+
+_HASH_SCHEME = "pbkdf2_sha256"
+_HASH_ITERATIONS = 100000
+
+
+class LockError(Exception):
+    """Custom exception for screen lock errors."""
+    pass
+
+
+def hash_pin(pin: str, iterations: int = _HASH_ITERATIONS) -> str:
+    """
+    Hash a PIN for storage in the lock file.
+
+    Args:
+        pin: PIN digits
+        iterations: PBKDF2 iteration count
+
+    Returns:
+        "pbkdf2_sha256$<iterations>$<salt hex>$<hash hex>"
+
+    Raises:
+        LockError: If the PIN is empty or not all digits
+    """
+    if not pin or not pin.isdigit():
+        raise LockError("PIN must be one or more digits")
+    salt = os.urandom(16)
+    digest = hashlib.pbkdf2_hmac("sha256", pin.encode(), salt, iterations)
+    return f"{_HASH_SCHEME}${iterations}${salt.hex()}${digest.hex()}"
+
+
+def verify_pin(pin: str, pin_hash: str) -> bool:
+    """Check a PIN against a hash from hash_pin()."""
+    try:
+        scheme, iterations, salt, expected = pin_hash.split("$")
+        if scheme != _HASH_SCHEME:
+            return False
+        digest = hashlib.pbkdf2_hmac("sha256", pin.encode(), bytes.fromhex(salt), int(iterations))
+    except ValueError:
+        return False
+    return hmac.compare_digest(digest.hex(), expected)
+
+
+class ScreenLock:
+    """
+    PIN lock state shared by the local menu and drawing services.
+
+    A lock without a PIN hash is disabled and never locks. After max_attempts
+    wrong PINs in a row, unlock() rejects every attempt for lockout_seconds.
+    """
+
+    def __init__(self, pin_hash: Optional[str] = None, pin_length: int = DEFAULT_PIN_LENGTH,
+                 max_attempts: int = DEFAULT_MAX_ATTEMPTS, lockout_seconds: float = DEFAULT_LOCKOUT_S):
+        """
+        Create a screen lock. Enabled locks start locked.
+
+        Args:
+            pin_hash: Hash from hash_pin(), or None to disable the lock
+            pin_length: Number of digits the PIN entry screen asks for
+            max_attempts: Wrong PINs allowed before a lockout
+            lockout_seconds: How long unlocking is refused after a lockout
+        """
+        self.pin_hash = pin_hash
+        self.pin_length = pin_length
+        self.max_attempts = max_attempts
+        self.lockout_seconds = lockout_seconds
+        self._locked = pin_hash is not None
+        self._failures = 0
+        self._lockout_until = 0.0
+        self._lock = threading.Lock()
+
+    @classmethod
+    def from_file(cls, path: str = LOCK_PATH) -> "ScreenLock":
+        """
+        Load the lock configuration. A missing file yields a disabled lock.
+
+        Raises:
+            LockError: If the file is malformed or has an unknown key
+        """
+        try:
+            values = read_config_file(path)
+        except DisplayError as e:
+            raise LockError(str(e))
+
+        kwargs: Dict[str, object] = {}
+        try:
+            for key, value in values.items():
+                if key == "pin":
+                    kwargs["pin_hash"] = value or None
+                elif key == "pin_length":
+                    kwargs["pin_length"] = int(value)
+                elif key == "max_attempts":
+                    kwargs["max_attempts"] = int(value)
+                elif key == "lockout_seconds":
+                    kwargs["lockout_seconds"] = float(value)
+                else:
+                    raise LockError(f"{path}: unknown key '{key}'")
+        except ValueError as e:
+            raise LockError(f"{path}: {e}")
+        return cls(**kwargs)
+
+    def save(self, path: str = LOCK_PATH) -> None:
+        """
+        Write the lock configuration, readable only by its owner.
+
+        Raises:
+            LockError: If the file cannot be written
+        """
+        lines = [
+            f"pin = {self.pin_hash or ''}",
+            f"pin_length = {self.pin_length}",
+            f"max_attempts = {self.max_attempts}",
+            f"lockout_seconds = {self.lockout_seconds:g}",
+        ]
+        try:
+            fd = os.open(path, os.O_WRONLY | os.O_CREAT | os.O_TRUNC, 0o600)
+            with os.fdopen(fd, "w") as f:
+                f.write("\n".join(lines) + "\n")
+        except OSError as e:
+            raise LockError(f"Failed to write {path}: {e}")
+
+    @property
+    def enabled(self) -> bool:
+        """Whether a PIN is set."""
+        return self.pin_hash is not None
+
+    @property
+    def locked(self) -> bool:
+        """Whether the lock currently gates access."""
+        return self._locked
+
+    def lockout_remaining(self) -> float:
+        """Seconds until unlocking is allowed again, or 0."""
+        return max(0.0, self._lockout_until - time.monotonic())
+
+    def set_pin(self, pin: Optional[str]) -> None:
+        """
+        Set a new PIN, or disable the lock with None. Leaves the lock unlocked.
+
+        Raises:
+            LockError: If the PIN is not pin_length digits
+        """
+        with self._lock:
+            if pin is None:
+                self.pin_hash = None
+            else:
+                if len(pin) != self.pin_length:
+                    raise LockError(f"PIN must be {self.pin_length} digits")
+                self.pin_hash = hash_pin(pin)
+            self._locked = False
+            self._failures = 0
+        audit(AuditCategory.SECURITY, "set_pin", enabled=self.enabled)
+
+    def lock(self) -> None:
+        """Lock the screen. Does nothing when no PIN is set."""
+        with self._lock:
+            if self.enabled and not self._locked:
+                self._locked = True
+                audit(AuditCategory.SECURITY, "lock")
+
+    def unlock(self, pin: str) -> bool:
+        """
+        Try to unlock with a PIN.
+
+        Returns:
+            True if the lock is now unlocked
+        """
+        with self._lock:
+            if not self._locked:
+                return True
+            if self.lockout_remaining() > 0:
+                return False
+            if verify_pin(pin, self.pin_hash):
+                self._locked = False
+                self._failures = 0
+                audit(AuditCategory.SECURITY, "unlock")
+                return True
+            self._failures += 1
+            if self._failures >= self.max_attempts:
+                self._failures = 0
+                self._lockout_until = time.monotonic() + self.lockout_seconds
+                audit(AuditCategory.SECURITY, "unlock_lockout", seconds=self.lockout_seconds)
+            return False
+
+    def require_unlocked(self, operation: str = "This operation") -> None:
+        """
+        Gate for services acting on behalf of other processes, e.g. draw requests.
+
+        Raises:
+            LockError: If the screen is locked
+        """
+        if self._locked:
+            raise LockError(f"{operation} is not permitted while the screen is locked")
+
+
+def unlock_on_panel(lock: ScreenLock, display, buttons) -> bool:
+    """
+    Show the PIN entry screen until the lock opens or input ends.
+
+    UP and DOWN change the current digit and SELECT accepts it.
+
+    Args:
+        lock: ScreenLock to open
+        display: Initialized Display
+        buttons: recovery.ButtonSource
+
+    Returns:
+        True if unlocked, False if the button source closed first
+    """
+    from ..recovery.recovery import RecoveryUI
+    return RecoveryUI(display, buttons).unlock(lock)
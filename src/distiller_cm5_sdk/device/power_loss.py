@@ -0,0 +1,262 @@
+#!/usr/bin/env python3
+"""
+Power-loss handling for CM5 SDK.
+
+On devices with a UPS hat or a supercap hold-up circuit, the board keeps
+running for a short window after external power is cut. PowerLossMonitor
+watches a PMIC power-fail line or the hold-up voltage and, when power is
+going away, uses that window in order of importance:
+
+1. Abort the display update in flight, so it neither keeps the panel busy
+   nor lets a queued update start.
+2. Run the registered persist handlers (save state, flush logs) within
+   the hold-up budget.
+3. Show a "power lost" frame if time remains, and put the panel into deep
+   sleep so the frame stays visible without power.
+
+    monitor = PowerLossMonitor(GpioPowerFailSensor(17), display=display, hold_up_s=2.0)
+    monitor.add_handler("settings", settings.save)
+    monitor.start()
+"""
+
+import threading
+import time
+from dataclasses import dataclass, field
+from typing import Callable, List, Optional, Tuple
+
+from ..diagnostics.audit import AuditCategory, audit
+from ..diagnostics.log_levels import get_logger
+
+
+DEFAULT_HOLD_UP_S = 1.5         # usable time between detection and brown-out
+FRAME_RESERVE_S = 1.0           # hold-up time a full refresh needs to finish
+POLL_INTERVAL_S = 0.05
+
+
+class PowerLossError(Exception):
+    """Custom exception for power-loss detection errors."""
+    pass
+
+
+class PowerSensor:
+    """Source telling whether external power is failing."""
+
+    def is_failing(self) -> bool:
+        raise NotImplementedError
+
+    def close(self) -> None:
+        pass
+
+
+class GpioPowerFailSensor(PowerSensor):
+    """PMIC or UPS power-fail interrupt line."""
+
+    def __init__(self, pin: int, chip_name: str = "gpiochip0", active_low: bool = True):
+        """
+        Request the power-fail line.
+
+        Args:
+            pin: GPIO line offset
+            chip_name: GPIO chip the line belongs to
+            active_low: The line is pulled low while power is failing
+
+        Raises:
+            PowerLossError: If the gpiod module is missing or the line can't be requested
+        """
+        try:
+            import gpiod
+        except ImportError as e:
+            raise PowerLossError(f"GPIO power-fail detection requires the gpiod module: {e}")
+
+        self._active = 0 if active_low else 1
+        try:
+            self._chip = gpiod.Chip(chip_name)
+            self._line = self._chip.get_line(pin)
+            self._line.request(consumer="power-loss", type=gpiod.LINE_REQ_DIR_IN)
+        except (OSError, AttributeError) as e:
+            raise PowerLossError(f"Failed to request power-fail line: {e}")
+
+    def is_failing(self) -> bool:
+        return self._line.get_value() == self._active
+
+    def close(self) -> None:
+        self._line.release()
+        self._chip.close()
+
+
+class VoltageSensor(PowerSensor):
+    """Hold-up voltage read from sysfs, e.g. an IIO ADC channel or a power_supply node."""
+
+    def __init__(self, path: str, threshold_v: float, scale: float = 0.001):
+        """
+        Args:
+            path: sysfs file holding the voltage as a number, e.g.
+                  /sys/bus/iio/devices/iio:device0/in_voltage0_raw
+            threshold_v: Power is failing below this voltage
+            scale: Volts per unit read (0.001 for millivolts, 1e-6 for
+                   power_supply voltage_now)
+        """
+        self.path = path
+        self.threshold_v = threshold_v
+        self.scale = scale
+
+    def voltage(self) -> float:
+        """
+        Raises:
+            PowerLossError: If the file cannot be read
+        """
+        try:
+            with open(self.path) as f:
+                return float(f.read().strip()) * self.scale
+        except (OSError, ValueError) as e:
+            raise PowerLossError(f"Failed to read {self.path}: {e}")
+
+    def is_failing(self) -> bool:
+        return self.voltage() < self.threshold_v
+
+
+@dataclass
+class PowerLossReport:
+    """What was done within the hold-up window."""
+    reason: str
+    handlers_run: List[str] = field(default_factory=list)
+    handlers_failed: List[str] = field(default_factory=list)
+    handlers_skipped: List[str] = field(default_factory=list)   # out of time
+    frame_shown: bool = False
+    elapsed_s: float = 0.0
+
+
+def render_power_lost(width: int, height: int, message: str = "Power lost"):
+    """Draw the default final frame: the message centered in a border."""
+    from ..hardware.eink.font import FONT_HEIGHT, FONT_WIDTH
+    from ..hardware.eink.graphics import Framebuffer
+
+    fb = Framebuffer(width, height)
+    fb.draw_rect(2, 2, width - 4, height - 4)
+    scale = 1
+    while (len(message) * (FONT_WIDTH + 1) * (scale + 1) <= width - 16
+           and FONT_HEIGHT * (scale + 1) <= height // 3):
+        scale += 1
+    text_width = len(message) * (FONT_WIDTH + 1) * scale - scale
+    fb.draw_text((width - text_width) // 2, (height - FONT_HEIGHT * scale) // 2, message, scale=scale)
+    return fb
+
+
+class PowerLossMonitor:
+    """Detects imminent power loss and spends the hold-up window safely."""
+
+    def __init__(self, sensor: Optional[PowerSensor] = None, display=None,
+                 hold_up_s: float = DEFAULT_HOLD_UP_S, message: Optional[str] = "Power lost",
+                 clock: Callable[[], float] = time.monotonic):
+        """
+        Args:
+            sensor: Power-fail source polled by start(); None when only
+                    trigger() is used (e.g. from a UPS daemon)
+            display: Optional initialized Display to abort and put to sleep
+            hold_up_s: Seconds available after detection
+            message: Text of the final frame; None leaves the last image
+            clock: Monotonic clock, replaceable for testing
+        """
+        self.sensor = sensor
+        self.display = display
+        self.hold_up_s = hold_up_s
+        self.message = message
+        self._clock = clock
+        self._handlers: List[Tuple[str, Callable[[], None]]] = []
+        self._log = get_logger("device.power_loss")
+        self._lock = threading.Lock()
+        self._report: Optional[PowerLossReport] = None
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    def add_handler(self, name: str, callback: Callable[[], None]) -> None:
+        """Persist state on power loss. Handlers run in the order added, most important first."""
+        self._handlers.append((name, callback))
+
+    @property
+    def triggered(self) -> bool:
+        return self._report is not None
+
+    def trigger(self, reason: str = "power_fail") -> PowerLossReport:
+        """
+        Handle imminent power loss. Runs once; later calls return the first report.
+
+        Args:
+            reason: Recorded in the report and audit log
+        """
+        with self._lock:
+            if self._report is not None:
+                return self._report
+            start = self._clock()
+            report = self._report = PowerLossReport(reason)
+            self._log.warning("power loss (%s), %.1f s hold-up", reason, self.hold_up_s)
+
+            if self.display is not None:
+                try:
+                    self.display.abort()
+                except Exception as e:
+                    print(f"Warning: Could not abort display update: {e}")
+
+            for name, callback in self._handlers:
+                if self._clock() - start >= self.hold_up_s:
+                    report.handlers_skipped.append(name)
+                    continue
+                try:
+                    callback()
+                    report.handlers_run.append(name)
+                except Exception as e:
+                    print(f"Warning: Power-loss handler '{name}' failed: {e}")
+                    report.handlers_failed.append(name)
+
+            if self.display is not None:
+                # A refresh cut off by the brown-out looks worse than the old image
+                show = self.message is not None and self.hold_up_s - (self._clock() - start) >= FRAME_RESERVE_S
+                frame = None
+                if show:
+                    width, height = self.display.get_dimensions()
+                    frame = render_power_lost(width, height, self.message).to_bytes()
+                try:
+                    self.display.emergency_frame(frame)
+                    report.frame_shown = show
+                except Exception as e:
+                    print(f"Warning: Emergency display update failed: {e}")
+
+            report.elapsed_s = self._clock() - start
+            audit(AuditCategory.POWER, "power_loss", reason, handlers=len(report.handlers_run),
+                  skipped=len(report.handlers_skipped), frame=report.frame_shown,
+                  elapsed_ms=int(report.elapsed_s * 1000))
+            return report
+
+    def check(self) -> bool:
+        """Poll the sensor once, triggering on failure. Returns True once triggered."""
+        if self.sensor is not None and not self.triggered:
+            try:
+                failing = self.sensor.is_failing()
+            except PowerLossError as e:
+                self._log.info("%s", e)
+                failing = False
+            if failing:
+                self.trigger()
+        return self.triggered
+
+    def start(self, poll_interval_s: float = POLL_INTERVAL_S) -> None:
+        """Poll the sensor in a background thread until triggered or stop()."""
+        if self.sensor is None:
+            raise PowerLossError("No power sensor to poll")
+        if self._thread is not None:
+            return
+        self._stop.clear()
+
+        def run() -> None:
+            while not self._stop.is_set() and not self.check():
+                self._stop.wait(poll_interval_s)
+
+        self._thread = threading.Thread(target=run, name="power-loss", daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop polling."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join(timeout=2)
+            self._thread = None
@@ -0,0 +1,235 @@
+#!/usr/bin/env python3
+"""
+Sleep/wake state machine for CM5 SDK.
+
+Formalizes the device's power states and the transitions between them:
+
+    ACTIVE ──> DIM ──> PANEL_SLEEP ──> SUSPEND
+      ^         │           │             │
+      └─────────┴───────────┴─────────────┘  (wake)
+
+A device may go from any state to any deeper one, and wake from any state
+straight back to ACTIVE. Idle timeouts move it one level deeper at a time.
+Apps observe transitions through listeners and can veto a transition into a
+deeper state, e.g. while a long refresh or recording is in progress. Waking is
+never vetoed.
+"""
+
+import subprocess
+import threading
+import time
+from dataclasses import dataclass
+from enum import Enum
+from typing import Callable, List, Optional, Sequence
+
+from ..diagnostics.audit import AuditCategory, audit
+
+
+SUSPEND_COMMAND = ("systemctl", "suspend")
+
+
+class PowerStateError(Exception):
+    """Custom exception for power state errors."""
+    pass
+
+
+class PowerState(Enum):
+    """Device power states, from most to least awake."""
+    ACTIVE = "active"               # normal operation
+    DIM = "dim"                     # idle; apps should stop non-essential refreshes
+    PANEL_SLEEP = "panel_sleep"     # e-ink panel in deep sleep; image stays visible
+    SUSPEND = "suspend"             # system suspended to RAM
+
+    @property
+    def depth(self) -> int:
+        return _DEPTH[self]
+
+
+_DEPTH = {PowerState.ACTIVE: 0, PowerState.DIM: 1, PowerState.PANEL_SLEEP: 2, PowerState.SUSPEND: 3}
+
+
+@dataclass
+class StateTransition:
+    """A change from one power state to another."""
+    previous: PowerState
+    state: PowerState
+    reason: str             # "idle", "activity", or the reason passed to request()
+
+    @property
+    def is_wake(self) -> bool:
+        return self.state.depth < self.previous.depth
+
+
+class PowerStateMachine:
+    """
+    Device power state with idle timeouts, transition events and veto hooks.
+
+    Call activity() on user input and tick() periodically (or run start()) to
+    apply idle timeouts. Entering PANEL_SLEEP puts the display to sleep and
+    leaving it wakes the display. Entering SUSPEND runs the suspend command;
+    when it returns, the system has resumed and the machine wakes to ACTIVE.
+    """
+
+    def __init__(self, display=None,
+                 dim_after: Optional[float] = 30.0,
+                 sleep_after: Optional[float] = 120.0,
+                 suspend_after: Optional[float] = None,
+                 suspend_command: Optional[Sequence[str]] = SUSPEND_COMMAND):
+        """
+        Create the state machine in ACTIVE.
+
+        Args:
+            display: Display to sleep and wake, or None
+            dim_after: Idle seconds before DIM, or None to never dim
+            sleep_after: Idle seconds before PANEL_SLEEP, or None to never sleep
+            suspend_after: Idle seconds before SUSPEND, or None to never suspend
+            suspend_command: Command that suspends the system, or None to only
+                             change state (e.g. when suspend is handled elsewhere)
+        """
+        self.display = display
+        self.dim_after = dim_after
+        self.sleep_after = sleep_after
+        self.suspend_after = suspend_after
+        self.suspend_command = suspend_command
+        self._state = PowerState.ACTIVE
+        self._last_activity = time.monotonic()
+        self._listeners: List[Callable[[StateTransition], None]] = []
+        self._vetoes: List[Callable[[StateTransition], bool]] = []
+        self._lock = threading.RLock()
+        self._thread: Optional[threading.Thread] = None
+        self._stop = threading.Event()
+
+    @property
+    def state(self) -> PowerState:
+        """Current power state."""
+        return self._state
+
+    def add_listener(self, callback: Callable[[StateTransition], None]) -> None:
+        """Call callback after every transition."""
+        self._listeners.append(callback)
+
+    def remove_listener(self, callback: Callable[[StateTransition], None]) -> None:
+        """Stop calling a listener."""
+        self._listeners.remove(callback)
+
+    def add_veto(self, callback: Callable[[StateTransition], bool]) -> None:
+        """
+        Let callback refuse transitions into deeper states.
+
+        The callback is called before the transition happens and returns False
+        to keep the current state. It is not consulted when waking.
+        """
+        self._vetoes.append(callback)
+
+    def remove_veto(self, callback: Callable[[StateTransition], bool]) -> None:
+        """Stop consulting a veto hook."""
+        self._vetoes.remove(callback)
+
+    def request(self, state: PowerState, reason: str = "request") -> bool:
+        """
+        Move to a state.
+
+        Args:
+            state: Target state; must be deeper than the current state, or ACTIVE
+            reason: Recorded in the transition event
+
+        Returns:
+            True if the device is now in the state, False if a hook vetoed it
+
+        Raises:
+            PowerStateError: If the transition is not allowed
+        """
+        with self._lock:
+            previous = self._state
+            if state == previous:
+                return True
+            if state != PowerState.ACTIVE and state.depth < previous.depth:
+                raise PowerStateError(f"Cannot go from {previous.value} to {state.value}; wake to active first")
+
+            transition = StateTransition(previous, state, reason)
+            if not transition.is_wake:
+                for veto in list(self._vetoes):
+                    if not veto(transition):
+                        return False
+
+            self._apply(transition)
+            self._state = state
+            if transition.is_wake:
+                self._last_activity = time.monotonic()
+
+        audit(AuditCategory.POWER, "state", state.value, previous=previous.value, reason=reason)
+        for listener in list(self._listeners):
+            listener(transition)
+
+        if state == PowerState.SUSPEND and self.suspend_command is not None:
+            self._suspend()
+        return True
+
+    def _apply(self, transition: StateTransition) -> None:
+        if self.display is None:
+            return
+        asleep = transition.previous.depth >= PowerState.PANEL_SLEEP.depth
+        sleeping = transition.state.depth >= PowerState.PANEL_SLEEP.depth
+        if sleeping and not asleep:
+            self.display.sleep()
+        elif asleep and not sleeping:
+            self.display.wake()
+
+    def _suspend(self) -> None:
+        try:
+            subprocess.run(list(self.suspend_command), check=True, timeout=60)
+        except (OSError, subprocess.SubprocessError) as e:
+            print(f"Warning: Suspend failed: {e}")
+        # The command returns once the system resumes (or failed to suspend)
+        self.request(PowerState.ACTIVE, "resume")
+
+    def activity(self) -> None:
+        """Record user activity, waking the device if it is not ACTIVE."""
+        self._last_activity = time.monotonic()
+        if self._state != PowerState.ACTIVE:
+            self.request(PowerState.ACTIVE, "activity")
+
+    def idle_time(self) -> float:
+        """Seconds since the last activity or wake."""
+        return time.monotonic() - self._last_activity
+
+    def tick(self) -> PowerState:
+        """
+        Apply idle timeouts, moving at most one state deeper.
+
+        A vetoed idle transition is retried on the next tick.
+
+        Returns:
+            The state after the tick
+        """
+        idle = self.idle_time()
+        for state, after in ((PowerState.DIM, self.dim_after),
+                             (PowerState.PANEL_SLEEP, self.sleep_after),
+                             (PowerState.SUSPEND, self.suspend_after)):
+            if state.depth <= self._state.depth or after is None:
+                continue
+            if idle >= after:
+                self.request(state, "idle")
+            break
+        return self._state
+
+    def start(self, interval: float = 1.0) -> None:
+        """Call tick() every interval seconds on a background thread."""
+        if self._thread is not None:
+            return
+        self._stop.clear()
+
+        def loop():
+            while not self._stop.wait(interval):
+                self.tick()
+
+        self._thread = threading.Thread(target=loop, name="power-state", daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop the background thread started by start()."""
+        if self._thread is None:
+            return
+        self._stop.set()
+        self._thread.join()
+        self._thread = None
@@ -0,0 +1,348 @@
+#!/usr/bin/env python3
+"""
+Privacy mode for CM5 SDK.
+
+Devices with a hardware microphone mute switch (or a mic kill line driven by
+the PMIC) report its state here, and the SDK's capture paths refuse to
+record while it is muted: Audio.record() and stream_record() raise
+MicMutedError, speech-to-text start_recording() returns False, and
+recordings already running stop within a buffer.
+
+The switch is configured in its configuration file:
+
+    # /opt/distiller-cm5-sdk/privacy.conf
+    mute_gpio = 5
+    mute_chip = gpiochip0
+    mute_active_low = true
+    # or a sysfs attribute reading 1 while muted:
+    # mute_path = /sys/class/gpio/gpio517/value
+
+If the switch cannot be read, the microphone counts as muted.
+
+    monitor = MuteMonitor(display=display)      # shows a glyph while muted
+    monitor.add_listener(lambda muted: print("muted" if muted else "live"))
+    monitor.start()
+"""
+
+import threading
+from typing import Callable, List, Optional
+
+from ..diagnostics.audit import AuditCategory, audit
+from ..hardware.eink.config import read_config_file
+from ..hardware.eink.display import DisplayError
+
+
+PRIVACY_PATH = "/opt/distiller-cm5-sdk/privacy.conf"
+
+POLL_INTERVAL_S = 0.1
+GLYPH_SIZE = 16
+
+
+class PrivacyError(Exception):
+    """Custom exception for microphone mute switch errors."""
+    pass
+
+
+class MuteSwitch:
+    """Source telling whether the microphone is muted."""
+
+    def is_muted(self) -> bool:
+        raise NotImplementedError
+
+    def close(self) -> None:
+        pass
+
+
+class GpioMuteSwitch(MuteSwitch):
+    """Mute switch or mic kill line on a GPIO."""
+
+    def __init__(self, pin: int, chip_name: str = "gpiochip0", active_low: bool = True):
+        """
+        Request the mute line.
+
+        Args:
+            pin: GPIO line offset
+            chip_name: GPIO chip the line belongs to
+            active_low: The line is pulled low while the microphone is muted
+
+        Raises:
+            PrivacyError: If the gpiod module is missing or the line can't be requested
+        """
+        try:
+            import gpiod
+        except ImportError as e:
+            raise PrivacyError(f"GPIO mute switch requires the gpiod module: {e}")
+
+        self._active = 0 if active_low else 1
+        try:
+            self._chip = gpiod.Chip(chip_name)
+            self._line = self._chip.get_line(pin)
+            self._line.request(consumer="mic-mute", type=gpiod.LINE_REQ_DIR_IN)
+        except (OSError, AttributeError) as e:
+            raise PrivacyError(f"Failed to request mute line: {e}")
+
+    def is_muted(self) -> bool:
+        return self._line.get_value() == self._active
+
+    def close(self) -> None:
+        self._line.release()
+        self._chip.close()
+
+
+class FileMuteSwitch(MuteSwitch):
+    """Mute state read from a sysfs attribute or a file kept by another service."""
+
+    def __init__(self, path: str):
+        """
+        Args:
+            path: File reading 1 while the microphone is muted and 0 otherwise
+        """
+        self.path = path
+
+    def is_muted(self) -> bool:
+        """
+        Raises:
+            PrivacyError: If the file cannot be read
+        """
+        try:
+            with open(self.path) as f:
+                value = f.read().strip()
+        except OSError as e:
+            raise PrivacyError(f"Failed to read {self.path}: {e}")
+        if value not in ("0", "1"):
+            raise PrivacyError(f"{self.path}: expected 0 or 1, got '{value}'")
+        return value == "1"
+
+
+class SoftwareMuteSwitch(MuteSwitch):
+    """Mute state set by software, e.g. a privacy toggle in the menu or the simulator."""
+
+    def __init__(self, muted: bool = False):
+        self.muted = muted
+
+    def set_muted(self, muted: bool) -> None:
+        self.muted = muted
+
+    def is_muted(self) -> bool:
+        return self.muted
+
+
+def load_mute_switch(path: str = PRIVACY_PATH) -> Optional[MuteSwitch]:
+    """
+    Open the mute switch named in the privacy configuration.
+
+    Returns:
+        The switch, or None if the file is missing or names none
+
+    Raises:
+        PrivacyError: If the file is malformed or the switch can't be opened
+    """
+    try:
+        values = read_config_file(path)
+    except DisplayError as e:
+        raise PrivacyError(str(e))
+
+    for key in values:
+        if key not in ("mute_gpio", "mute_chip", "mute_active_low", "mute_path"):
+            raise PrivacyError(f"{path}: unknown key '{key}'")
+    if "mute_gpio" in values and "mute_path" in values:
+        raise PrivacyError(f"{path}: mute_gpio and mute_path are exclusive")
+    if "mute_path" in values:
+        return FileMuteSwitch(values["mute_path"])
+    if "mute_gpio" in values:
+        try:
+            pin = int(values["mute_gpio"])
+        except ValueError as e:
+            raise PrivacyError(f"{path}: {e}")
+        active_low = values.get("mute_active_low", "true").lower() in ("1", "true", "yes")
+        return GpioMuteSwitch(pin, values.get("mute_chip", "gpiochip0"), active_low)
+    return None
+
+
+_switch: Optional[MuteSwitch] = None
+_switch_error: Optional[str] = None
+_switch_loaded = False
+_read_failed = False            # warned about a failed read since the last good one
+_switch_lock = threading.Lock()
+
+
+def set_mute_switch(switch: Optional[MuteSwitch]) -> None:
+    """Use switch for the rest of the process instead of the configured one; None for no switch."""
+    global _switch, _switch_error, _switch_loaded
+    with _switch_lock:
+        _switch, _switch_error, _switch_loaded = switch, None, True
+
+
+def get_mute_switch() -> Optional[MuteSwitch]:
+    """The switch set with set_mute_switch(), or the configured one, opened on first use."""
+    global _switch, _switch_error, _switch_loaded
+    with _switch_lock:
+        if not _switch_loaded:
+            _switch_error = None
+            try:
+                _switch = load_mute_switch()
+            except PrivacyError as e:
+                _switch_error = str(e)
+                print(f"Warning: Mute switch unavailable, treating the microphone as muted: {e}")
+            _switch_loaded = True
+        return _switch
+
+
+def is_mic_muted() -> bool:
+    """
+    Whether the microphone is muted. Without a mute switch it never is; if
+    the switch is configured but cannot be read, it always is.
+    """
+    global _read_failed
+    switch = get_mute_switch()
+    if _switch_error is not None:
+        return True
+    if switch is None:
+        return False
+    try:
+        muted = switch.is_muted()
+    except (PrivacyError, OSError) as e:
+        if not _read_failed:
+            print(f"Warning: Failed to read mute switch, treating the microphone as muted: {e}")
+        _read_failed = True
+        return True
+    _read_failed = False
+    return muted
+
+
+def capture_allowed(source: str) -> bool:
+    """
+    Check the switch before opening a capture stream; a refusal is audited.
+
+    Args:
+        source: What wanted to record, e.g. "audio" or "parakeet"
+
+    Returns:
+        False if the microphone is muted
+    """
+    if is_mic_muted():
+        audit(AuditCategory.SECURITY, "capture_refused", source)
+        return False
+    return True
+
+
+def draw_mic_muted(fb, x: int, y: int, size: int = GLYPH_SIZE) -> None:
+    """
+    Draw the muted-microphone glyph, a crossed-out microphone on a white
+    square, with its top-left corner at (x, y).
+
+    Args:
+        fb: Framebuffer to draw into
+        size: Width and height in pixels, at least 8
+    """
+    from ..hardware.eink.graphics import Color
+
+    def at(value: int) -> int:
+        return round(value * size / 16)
+
+    fb.draw_rect(x, y, size, size, Color.WHITE, fill=True)
+    # Capsule, holder, stand and base
+    fb.draw_rect(x + at(6), y + at(1), at(4), at(9), fill=True)
+    fb.draw_vline(x + at(3), y + at(6), at(5))
+    fb.draw_vline(x + at(12), y + at(6), at(5))
+    fb.draw_hline(x + at(3), y + at(11), at(10))
+    fb.draw_vline(x + at(8), y + at(11), at(4))
+    fb.draw_hline(x + at(5), y + at(15), at(7))
+    # Slash, two pixels wide
+    fb.draw_line(x + 1, y, x + size - 1, y + size - 2)
+    fb.draw_line(x, y + 1, x + size - 2, y + size - 1)
+
+
+class MuteMonitor:
+    """Follows the mute switch, reporting changes and optionally showing the glyph."""
+
+    def __init__(self, display=None, glyph_size: int = GLYPH_SIZE):
+        """
+        Args:
+            display: Optional initialized Display to show draw_mic_muted() in
+                     its top-right corner while muted
+            glyph_size: Size of the glyph in pixels
+        """
+        self.display = display
+        self.glyph_size = glyph_size
+        self.muted: Optional[bool] = None           # unknown until the first check()
+        self._listeners: List[Callable[[bool], None]] = []
+        self._covered = None                        # (framebuffer shown, pixels under the glyph)
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    def add_listener(self, callback: Callable[[bool], None]) -> None:
+        """Call callback(muted) on every change, including the first check()."""
+        self._listeners.append(callback)
+
+    def remove_listener(self, callback: Callable[[bool], None]) -> None:
+        """Stop calling a listener."""
+        self._listeners.remove(callback)
+
+    def check(self) -> bool:
+        """Read the switch once and report a change. Returns whether the microphone is muted."""
+        muted = is_mic_muted()
+        if muted == self.muted:
+            return muted
+        first = self.muted is None
+        self.muted = muted
+        if not (first and not muted):
+            audit(AuditCategory.SECURITY, "mic_mute" if muted else "mic_unmute", "microphone")
+        if self.display is not None:
+            try:
+                self._show_glyph(muted)
+            except Exception as e:
+                print(f"Warning: Could not show mute glyph: {e}")
+        for listener in list(self._listeners):
+            try:
+                listener(muted)
+            except Exception as e:
+                print(f"Warning: Mute listener failed: {e}")
+        return muted
+
+    def _show_glyph(self, muted: bool) -> None:
+        from ..hardware.eink.display import DisplayMode
+        from ..hardware.eink.graphics import Color, Framebuffer
+
+        width, height = self.display.get_dimensions()
+        frame = self.display.get_framebuffer()
+        fb = Framebuffer(width, height, frame)
+        x, y = width - self.glyph_size - 2, 2
+        if muted:
+            under = Framebuffer(self.glyph_size, self.glyph_size)
+            for py in range(self.glyph_size):
+                for px in range(self.glyph_size):
+                    under.set_pixel(px, py, Color(fb.get_pixel(x + px, y + py)))
+            draw_mic_muted(fb, x, y, self.glyph_size)
+            self._covered = (fb.to_bytes(), under)
+        elif self._covered is not None:
+            shown, under = self._covered
+            self._covered = None
+            if frame != shown:
+                return              # redrawn since, the glyph is gone already
+            fb.blit(under, x, y)
+        else:
+            return
+        self.display.display_image(fb.to_bytes(), DisplayMode.PARTIAL)
+
+    def start(self, poll_interval_s: float = POLL_INTERVAL_S) -> None:
+        """Check the switch every poll_interval_s seconds on a background thread."""
+        if self._thread is not None:
+            return
+        self._stop.clear()
+
+        def run() -> None:
+            while True:
+                self.check()
+                if self._stop.wait(poll_interval_s):
+                    return
+
+        self._thread = threading.Thread(target=run, name="mic-mute", daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop checking."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join(timeout=2)
+            self._thread = None
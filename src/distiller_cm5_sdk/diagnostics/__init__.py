@@ -0,0 +1,13 @@
+from .audit import (
+    AuditCategory, AuditEntry, AuditLog, audit, disable_audit_log, enable_audit_log, get_audit_log,
+    query_audit_log,
+)
+from .log_levels import LogLevelError, get_levels, get_logger, set_level, watch_log_levels
+from .retention import (
+    DataCategory, PurgeReport, RetentionEnforcer, RetentionError, RetentionPolicy, get_retention_policy,
+    load_retention, purge, save_retention, set_retention_policy,
+)
+from .tracing import (
+    Interaction, StageTiming, add_trace_listener, current_interaction, current_trace_id, interaction, record_stage,
+    remove_trace_listener, stage,
+)
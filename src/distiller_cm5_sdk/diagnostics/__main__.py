@@ -0,0 +1,272 @@
+#!/usr/bin/env python3
+"""
+Diagnostics command line: python3 -m distiller_cm5_sdk.diagnostics
+"""
+
+import argparse
+import sys
+
+from .log_levels import LogLevelError, levels_path, parse_level, read_levels_file, write_levels_file
+
+
+def _log_level(args) -> int:
+    path = args.file or levels_path()
+    try:
+        levels = read_levels_file(path)
+        if args.module is None:
+            for module, level in sorted(levels.items()):
+                print(f"{module or '(sdk)'} = {level}")
+            return 0
+        if args.level is None:
+            print(levels.get(args.module, "default"))
+            return 0
+        parse_level(args.level)
+        if args.level.strip().lower() == "default":
+            levels.pop(args.module, None)
+        else:
+            levels[args.module] = args.level.strip().lower()
+        write_levels_file(path, levels)
+    except LogLevelError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    print(f"{args.module or '(sdk)'} = {args.level}; running SDK processes apply it within a few seconds")
+    return 0
+
+
+def _panel_usage(args) -> int:
+    import time
+    from ..hardware.eink.usage import load_usage, usage_path
+
+    usage = load_usage(args.file or usage_path())
+    if not usage:
+        print("No panel usage recorded")
+        return 0
+    print(f"{'Panel':<28} {'Type':<12} {'Full':>8} {'Partial':>9} {'Last refresh':<20}")
+    for entry in usage.values():
+        last = time.strftime("%Y-%m-%d %H:%M:%S", time.localtime(entry.last_refresh)) if entry.last_refresh else "-"
+        print(f"{entry.panel_key:<28} {entry.panel:<12} {entry.full_refreshes:>8} {entry.partial_refreshes:>9} {last:<20}")
+    return 0
+
+
+def _retention(args) -> int:
+    from .retention import DataCategory, RetentionError, load_retention, purge, retention_path
+
+    path = args.file or retention_path()
+    try:
+        policies = load_retention(path)
+    except RetentionError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    for category in DataCategory:
+        policy = policies[category]
+        if not policy.persist:
+            rule = "keep nothing"
+        else:
+            limits = [f"max age {policy.max_age_s:g} s" if policy.max_age_s is not None else "",
+                      f"max size {policy.max_bytes} bytes" if policy.max_bytes is not None else ""]
+            rule = ", ".join(limit for limit in limits if limit) or "keep everything"
+        print(f"{category.value:<11} {rule}; paths: {', '.join(policy.paths) or '-'}")
+        if args.purge:
+            report = purge(category, policy)
+            print(f"{'':<11} purged {len(report.deleted)} files, {report.freed_bytes} bytes")
+    return 0
+
+
+def _events(args) -> int:
+    import json
+    import time
+    from ..events.history import query_event_history
+
+    since = time.time() - args.last if args.last is not None else None
+    events = query_event_history(args.pattern, since=since, limit=args.limit, path=args.file)
+    for event in events:
+        if args.json:
+            print(json.dumps({"event": event.name, "timestamp": event.timestamp, "data": event.data}, default=str))
+            continue
+        stamp = time.strftime("%Y-%m-%d %H:%M:%S", time.localtime(event.timestamp))
+        fields = " ".join(f"{key}={value}" for key, value in event.data.items())
+        print(f"{stamp}.{int(event.timestamp * 1000) % 1000:03d} {event.name} {fields}".rstrip())
+    if not events and not args.json:
+        print("No events recorded")
+    return 0
+
+
+def _eink_pattern(args) -> int:
+    from ..hardware.eink import Display, DisplayError, DisplayMode
+    from ..hardware.eink.patterns import PatternError, PatternSpec, generate, pixel_walk_steps
+
+    spec = PatternSpec(args.pattern, size=args.size, vertical=args.vertical, step=args.step, invert=args.invert)
+    mode = DisplayMode.PARTIAL if args.partial else DisplayMode.FULL
+    try:
+        with Display(backend=args.backend) as display:
+            if args.pattern == "pixel-walk" and args.walk:
+                steps = pixel_walk_steps(display.WIDTH, display.HEIGHT, args.size or 1)
+                for step in range(args.step, steps):
+                    walk = PatternSpec(args.pattern, size=args.size, step=step, invert=args.invert)
+                    display.display_image(generate(walk, panel=display.panel).to_bytes(), DisplayMode.PARTIAL)
+                return 0
+            display.display_image(generate(spec, panel=display.panel).to_bytes(), mode)
+    except (PatternError, DisplayError) as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    return 0
+
+
+def _eink_hil(args) -> int:
+    import json
+    from ..hardware.eink import Display
+    from ..hardware.eink.hil import HilError, HilRunner
+
+    link = power_cycle = None
+    if args.sam_power:
+        from ..hardware.sam.link import SamLink
+        from ..hardware.sam.panel_power import PanelPowerRail
+        link = SamLink()
+        power_cycle = PanelPowerRail(link).cycle
+    try:
+        runner = HilRunner(lambda: Display(backend=args.backend), args.iterations, args.tolerance, power_cycle)
+        report = runner.run(args.scenario)
+    except HilError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    finally:
+        if link is not None:
+            link.stop()
+    print(report.format_text())
+    if args.report:
+        with open(args.report, "w") as f:
+            json.dump(report.to_dict(), f, indent=2)
+    return 0 if report.passed else 1
+
+
+def _eink_bench(args) -> int:
+    from ..hardware.eink.bench import BenchError, BenchReport, parse_panels, run_benchmarks
+
+    try:
+        panels = parse_panels(args.panel) if args.panel else None
+        baseline = BenchReport.load(args.baseline) if args.baseline else None
+        report = run_benchmarks(args.case, panels, args.repeat, args.backend)
+        if baseline is not None:
+            report.compare(baseline, args.max_regression)
+    except BenchError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    print(report.format_text())
+    if args.report:
+        report.save(args.report)
+    return 0 if report.passed else 1
+
+
+def _capabilities(args) -> int:
+    import json
+    from ..device.capabilities import capabilities
+
+    caps = capabilities(sam_timeout_s=args.sam_timeout)
+    if args.json:
+        print(json.dumps(caps.to_dict(), indent=2))
+        return 0
+    panel, audio, touch, sam, battery = caps.panel, caps.audio, caps.touch, caps.sam, caps.battery
+    print(f"Model:   {caps.model or 'unknown'}")
+    print(f"Panel:   {f'{panel.panel} {panel.width}x{panel.height} ({panel.backend})' if panel.present else 'none'}"
+          + (f"; {panel.error}" if panel.error else ""))
+    print(f"Audio:   input {'yes' if audio.input else 'no'}, output {'yes' if audio.output else 'no'}, "
+          f"level controls {'yes' if audio.controls else 'no'}")
+    print(f"Touch:   {f'{touch.name} ({touch.device})' if touch.present else 'none'}")
+    print(f"Sensors: {', '.join(f'{sensor.name} ({sensor.kind})' for sensor in caps.sensors) or 'none'}")
+    if not sam.present:
+        sam_text = "none"
+    elif not sam.responding:
+        sam_text = "not responding"
+    else:
+        sam_text = f"firmware {sam.firmware or 'unknown'}, protocol {sam.protocol_version}"
+    print(f"SAM:     {sam_text}")
+    if battery.present:
+        percent = "unknown charge" if battery.percent is None else f"{battery.percent:g}%"
+        print(f"Battery: {percent}, {battery.status or 'unknown status'}")
+    else:
+        print("Battery: none")
+    return 0
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Distiller CM5 SDK diagnostics")
+    commands = parser.add_subparsers(dest="command", required=True)
+
+    log_level = commands.add_parser("log-level", help="Show or change per-module log levels of running SDK processes")
+    log_level.add_argument("module", nargs="?",
+                           help='Module relative to the SDK package, e.g. hardware.eink.spi ("" for all)')
+    log_level.add_argument("level", nargs="?", help="debug, info, warning, error, critical or default")
+    log_level.add_argument("--file", help="Levels file (default: DISTILLER_LOG_LEVELS or /run/distiller-cm5-sdk/log-levels.conf)")
+    log_level.set_defaults(handler=_log_level)
+
+    panel_usage = commands.add_parser("panel-usage", help="Show cumulative e-ink refresh counts per panel")
+    panel_usage.add_argument("--file", help="Usage file (default: DISTILLER_EINK_USAGE_FILE or "
+                                            "/var/lib/distiller-cm5-sdk/panel-usage.json)")
+    panel_usage.set_defaults(handler=_panel_usage)
+
+    retention = commands.add_parser("retention", help="Show the data retention policies, or apply them now")
+    retention.add_argument("--purge", action="store_true", help="Delete the files the policies no longer keep")
+    retention.add_argument("--file", help="Policy file (default: DISTILLER_RETENTION_CONFIG or "
+                                          "/opt/distiller-cm5-sdk/settings/retention.conf)")
+    retention.set_defaults(handler=_retention)
+
+    events = commands.add_parser("events", help="Show recent device events from the event history")
+    events.add_argument("pattern", nargs="?", default="*", help='Event name or glob, e.g. "button.*" (default: all)')
+    events.add_argument("--last", type=float, metavar="SECONDS", help="Only events of the last SECONDS")
+    events.add_argument("--limit", type=int, help="Show at most this many of the newest events")
+    events.add_argument("--json", action="store_true", help="One JSON object per line")
+    events.add_argument("--file", help="History file (default: DISTILLER_EVENT_HISTORY or "
+                                       "/var/log/distiller-cm5-sdk/events.jsonl)")
+    events.set_defaults(handler=_events)
+
+    eink_pattern = commands.add_parser("eink-pattern", help="Show a test pattern on the e-ink display")
+    eink_pattern.add_argument("pattern", help="checkerboard, stripes, gradient, crosshatch, border, "
+                                              "resolution, pixel-walk, black or white")
+    eink_pattern.add_argument("--size", type=int, help="Cell, stripe, line or block size in pixels")
+    eink_pattern.add_argument("--vertical", action="store_true", help="Vertical stripes or top-to-bottom gradient")
+    eink_pattern.add_argument("--invert", action="store_true", help="Swap black and white")
+    eink_pattern.add_argument("--step", type=int, default=0, help="pixel-walk position")
+    eink_pattern.add_argument("--walk", action="store_true", help="pixel-walk: step over the whole panel")
+    eink_pattern.add_argument("--partial", action="store_true", help="Use a partial refresh")
+    eink_pattern.add_argument("--backend", help="Display backend (default: from eink.toml)")
+    eink_pattern.set_defaults(handler=_eink_pattern)
+
+    eink_hil = commands.add_parser("eink-hil", help="Run hardware-in-the-loop tests on the e-ink panel")
+    eink_hil.add_argument("--scenario", action="append",
+                          help="init-storm, rapid-partials, sleep-wake or power-cycle; repeatable (default: all)")
+    eink_hil.add_argument("--iterations", type=int, default=10, help="Repetitions of each scenario")
+    eink_hil.add_argument("--tolerance", type=float, default=2.0,
+                          help="Multiple of the panel's typical refresh time a step may take")
+    eink_hil.add_argument("--report", help="Also save the report as JSON to this file")
+    eink_hil.add_argument("--backend", help="Display backend (default: from eink.toml)")
+    eink_hil.add_argument("--sam-power", action="store_true",
+                          help="Run power-cycle by switching the panel's power rail through the SAM MCU")
+    eink_hil.set_defaults(handler=_eink_hil)
+
+    eink_bench = commands.add_parser("eink-bench", help="Time the e-ink image pipeline and check for regressions")
+    eink_bench.add_argument("--case", action="append",
+                            help="dither-<mode>, scale-<method> or pack-<format>; repeatable (default: all)")
+    eink_bench.add_argument("--panel", action="append",
+                            help="epd128x250, epd296x128 or epd400x300; repeatable (default: all)")
+    eink_bench.add_argument("--repeat", type=int, default=5, help="Timed runs of each case")
+    eink_bench.add_argument("--backend", default="mock",
+                            help="Backend of the pack cases: mock (default, runs anywhere) or native")
+    eink_bench.add_argument("--baseline", help="Compare with a report saved by an earlier run")
+    eink_bench.add_argument("--max-regression", type=float,
+                            help="Slowdown factor over the baseline that fails a case (default: 1.5)")
+    eink_bench.add_argument("--report", help="Also save the report as JSON to this file")
+    eink_bench.set_defaults(handler=_eink_bench)
+
+    caps = commands.add_parser("capabilities", help="Show the panel, audio, touch, sensors, SAM firmware and "
+                                                    "battery this unit has")
+    caps.add_argument("--json", action="store_true", help="Print JSON")
+    caps.add_argument("--sam-timeout", type=float, default=1.0, metavar="SECONDS",
+                      help="How long to wait for the SAM MCU's firmware version; 0 skips asking (default: 1)")
+    caps.set_defaults(handler=_capabilities)
+
+    args = parser.parse_args(argv)
+    return args.handler(args)
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,97 @@
+#!/usr/bin/env python3
+"""
+Audit log unit tests for CM5 SDK.
+"""
+
+import json
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.diagnostics.audit import (
+    AuditCategory, AuditLog, disable_audit_log, enable_audit_log, query_audit_log,
+)
+from distiller_cm5_sdk.hardware.eink.display import Display, RotationMode
+
+
+class TestAuditLog(unittest.TestCase):
+    """Test cases for the audit log."""
+
+    def setUp(self):
+        """Point the process-wide audit log at a temporary file."""
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "logs", "audit.jsonl")
+
+    def tearDown(self):
+        disable_audit_log()
+        self.tmpdir.cleanup()
+
+    def test_record_and_query(self):
+        """Test entries round-trip with filters applied."""
+        log = AuditLog(self.path)
+        log.record(AuditCategory.DISPLAY, "update", "/tmp/a.png", mode="FULL")
+        log.record(AuditCategory.AUDIO, "play", "/tmp/a.wav")
+        log.record(AuditCategory.DISPLAY, "clear")
+
+        entries = log.query(category=AuditCategory.DISPLAY)
+        self.assertEqual([e.action for e in entries], ["update", "clear"])
+        self.assertEqual(entries[0].source, "/tmp/a.png")
+        self.assertEqual(entries[0].details, {"mode": "FULL"})
+        self.assertEqual(entries[0].pid, os.getpid())
+        self.assertEqual(len(log.query(limit=1)), 1)
+        self.assertEqual(log.query(limit=1)[0].action, "clear")
+
+    def test_size_bounded(self):
+        """Test the file rotates to a single backup at its size limit."""
+        log = AuditLog(self.path, max_bytes=1024)
+        for i in range(100):
+            log.record(AuditCategory.POWER, "display_sleep", str(i))
+
+        self.assertLessEqual(os.path.getsize(self.path), 1024)
+        self.assertLessEqual(os.path.getsize(self.path + ".1"), 1024)
+        sources = [e.source for e in log.query()]
+        self.assertEqual(sources[-1], "99")
+        self.assertEqual(sources, sorted(sources, key=int))
+
+    def test_skips_corrupt_lines(self):
+        """Test an interrupted write does not hide other entries."""
+        log = AuditLog(self.path)
+        log.record(AuditCategory.CONFIG, "rotation", "CW_90")
+        with open(self.path, "a") as f:
+            f.write('{"timestamp": 1, "categ')
+        self.assertEqual(len(log.query()), 1)
+
+    def test_display_operations_audited(self):
+        """Test display updates, power and config changes are recorded."""
+        enable_audit_log(self.path)
+        with Display(backend="mock") as display:
+            display.set_rotation(RotationMode.CW_90)
+            display.display_image(b"\xff" * Display.ARRAY_SIZE)
+            display.sleep()
+
+        actions = [(e.category, e.action) for e in query_audit_log()]
+        self.assertEqual(actions, [
+            (AuditCategory.POWER, "display_init"),
+            (AuditCategory.CONFIG, "rotation"),
+            (AuditCategory.DISPLAY, "update"),
+            (AuditCategory.POWER, "display_sleep"),
+            (AuditCategory.POWER, "display_cleanup"),
+        ])
+        with open(self.path) as f:
+            self.assertEqual(json.loads(f.readline())["details"], {"backend": "mock"})
+
+    def test_disabled_by_default(self):
+        """Test nothing is written when auditing is disabled."""
+        disable_audit_log()
+        with Display(backend="mock") as display:
+            display.clear()
+        self.assertFalse(os.path.exists(self.path))
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
@@ -0,0 +1,73 @@
+#!/usr/bin/env python3
+"""
+Runtime log level control unit tests for CM5 SDK.
+"""
+
+import io
+import logging
+import os
+import sys
+import tempfile
+import unittest
+from contextlib import redirect_stdout
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.diagnostics.__main__ import main
+from distiller_cm5_sdk.diagnostics.log_levels import (
+    LevelFileWatcher, LogLevelError, logger_name, parse_level, set_level,
+)
+
+
+class TestLogLevels(unittest.TestCase):
+    """Test cases for per-module log levels."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "log-levels.conf")
+        self.spi = logging.getLogger("distiller_cm5_sdk.hardware.eink.spi")
+
+    def tearDown(self):
+        set_level("hardware.eink.spi", "default")
+        set_level("hardware.audio", "default")
+        self.tmpdir.cleanup()
+
+    def test_set_level(self):
+        """Test levels are set on the module's logger under the SDK package."""
+        self.assertEqual(logger_name("hardware.eink.spi"), "distiller_cm5_sdk.hardware.eink.spi")
+        self.assertEqual(logger_name(""), "distiller_cm5_sdk")
+        set_level("hardware.eink.spi", "DEBUG")
+        self.assertTrue(self.spi.isEnabledFor(logging.DEBUG))
+        set_level("hardware.eink.spi", "default")
+        self.assertEqual(self.spi.level, logging.NOTSET)
+        with self.assertRaises(LogLevelError):
+            parse_level("loud")
+
+    def test_cli_updates_running_watcher(self):
+        """Test a level set from the CLI reaches a watching process and can be removed."""
+        watcher = LevelFileWatcher(self.path)
+        self.assertTrue(watcher.check())
+
+        with redirect_stdout(io.StringIO()):
+            self.assertEqual(main(["log-level", "hardware.eink.spi", "debug", "--file", self.path]), 0)
+            self.assertEqual(main(["log-level", "hardware.audio", "info", "--file", self.path]), 0)
+        self.assertTrue(watcher.check())
+        self.assertEqual(self.spi.level, logging.DEBUG)
+        self.assertFalse(watcher.check())
+
+        with redirect_stdout(io.StringIO()):
+            self.assertEqual(main(["log-level", "hardware.eink.spi", "default", "--file", self.path]), 0)
+        os.utime(self.path, (0, 0))  # mtime resolution may hide a quick rewrite
+        self.assertTrue(watcher.check())
+        self.assertEqual(self.spi.level, logging.NOTSET)
+        self.assertEqual(logging.getLogger("distiller_cm5_sdk.hardware.audio").level, logging.INFO)
+
+        with redirect_stdout(io.StringIO()) as out:
+            main(["log-level", "--file", self.path])
+        self.assertEqual(out.getvalue(), "hardware.audio = info\n")
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
@@ -0,0 +1,151 @@
+#!/usr/bin/env python3
+"""
+Data retention unit tests for CM5 SDK.
+"""
+
+import json
+import os
+import sys
+import tempfile
+import time
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.diagnostics.audit import AuditCategory, AuditLog, disable_audit_log, enable_audit_log
+from distiller_cm5_sdk.diagnostics.retention import (
+    RETENTION_PATH_ENV_VAR, DataCategory, RetentionError, RetentionPolicy, get_retention_policy, load_retention,
+    parse_duration, parse_size, purge, save_retention, set_retention_policy,
+)
+
+
+class TestRetention(unittest.TestCase):
+    """Test cases for retention policies and purging."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.config = os.path.join(self.tmpdir.name, "settings", "retention.conf")
+        self.env = patch.dict(os.environ, {RETENTION_PATH_ENV_VAR: self.config})
+        self.env.start()
+        self.data = os.path.join(self.tmpdir.name, "recordings")
+        os.makedirs(os.path.join(self.data, "old"))
+
+    def tearDown(self):
+        for category in DataCategory:
+            set_retention_policy(category, None)
+        disable_audit_log()
+        self.env.stop()
+        self.tmpdir.cleanup()
+
+    def write(self, text):
+        os.makedirs(os.path.dirname(self.config), exist_ok=True)
+        with open(self.config, "w") as f:
+            f.write(text)
+
+    def touch(self, name, size, age_s):
+        path = os.path.join(self.data, name)
+        with open(path, "wb") as f:
+            f.write(b"\0" * size)
+        mtime = time.time() - age_s
+        os.utime(path, (mtime, mtime))
+        return path
+
+    def test_parse(self):
+        """Test durations and sizes with and without units."""
+        self.assertEqual(parse_duration("90"), 90)
+        self.assertEqual(parse_duration("12h"), 12 * 3600)
+        self.assertEqual(parse_duration("7d"), 7 * 86400)
+        self.assertEqual(parse_size("4096"), 4096)
+        self.assertEqual(parse_size("200M"), 200 * 1024 * 1024)
+        self.assertEqual(parse_size("1GiB"), 1024 ** 3)
+        for text in ("soon", "-1d"):
+            with self.assertRaises(RetentionError):
+                parse_duration(text)
+        with self.assertRaises(RetentionError):
+            parse_size("lots")
+
+    def test_load_and_save(self):
+        """Test the policy file round-trips, and unmentioned categories keep everything."""
+        self.assertEqual(load_retention(), {category: RetentionPolicy() for category in DataCategory})
+        self.write(f"[recordings]\npaths = {self.data}, /tmp/x*\nmax_age = 7d\nmax_size = 1M\n\n"
+                   "[logs]\npersist = false\n")
+        policies = load_retention()
+        self.assertEqual(policies[DataCategory.RECORDINGS],
+                         RetentionPolicy(True, 7 * 86400, 1024 * 1024, [self.data, "/tmp/x*"]))
+        self.assertFalse(policies[DataCategory.LOGS].persist)
+        self.assertEqual(policies[DataCategory.CONTENT], RetentionPolicy())
+
+        save_retention(policies)
+        self.assertEqual(load_retention(), policies)
+
+        self.write("[photos]\nmax_age = 1d\n")
+        with self.assertRaisesRegex(RetentionError, r"unknown category \[photos\]"):
+            load_retention()
+        self.write("[logs]\nmax_days = 1\n")
+        with self.assertRaisesRegex(RetentionError, "unknown key 'max_days'"):
+            load_retention()
+
+    def test_get_policy(self):
+        """Test processes follow changes to the file, and a broken file keeps nothing."""
+        self.assertTrue(get_retention_policy(DataCategory.RECORDINGS).persist)
+        self.write("[recordings]\npersist = no\n")
+        self.assertFalse(get_retention_policy(DataCategory.RECORDINGS).persist)
+        set_retention_policy(DataCategory.RECORDINGS, RetentionPolicy(max_age_s=60))
+        self.assertEqual(get_retention_policy(DataCategory.RECORDINGS).max_age_s, 60)
+        set_retention_policy(DataCategory.RECORDINGS, None)
+
+        self.write("[recordings]\npersist = maybe\n")
+        os.utime(self.config, (time.time() + 5, time.time() + 5))
+        self.assertFalse(get_retention_policy(DataCategory.CONTENT).persist)
+
+    def test_purge(self):
+        """Test old files go first, then the oldest until the rest fit."""
+        old = self.touch("old/a.wav", 100, 10 * 86400)
+        older = self.touch("b.wav", 100, 9 * 86400)
+        mid = self.touch("c.wav", 300, 3600)
+        new = self.touch("d.wav", 300, 60)
+        newest = self.touch("e.wav", 300, 0)
+
+        policy = RetentionPolicy(max_age_s=7 * 86400, max_bytes=700, paths=[self.data])
+        report = purge(DataCategory.RECORDINGS, policy)
+        self.assertEqual(report.deleted, [old, older, mid])
+        self.assertEqual(report.freed_bytes, 500)
+        self.assertTrue(os.path.exists(new) and os.path.exists(newest))
+
+        wav_files = os.path.join(self.data, "*.wav")
+        report = purge(DataCategory.RECORDINGS, RetentionPolicy(persist=False, paths=[wav_files]))
+        self.assertEqual(report.deleted, [new, newest])
+        self.assertEqual(purge(DataCategory.RECORDINGS, RetentionPolicy()).deleted, [])
+
+    def test_audit_log(self):
+        """Test the logs policy turns the audit log off, bounds its size and expires entries."""
+        path = os.path.join(self.tmpdir.name, "audit.jsonl")
+        log = enable_audit_log(path)
+        set_retention_policy(DataCategory.LOGS, RetentionPolicy(persist=False))
+        log.record(AuditCategory.DISPLAY, "update")
+        self.assertFalse(os.path.exists(path))
+
+        set_retention_policy(DataCategory.LOGS, RetentionPolicy(max_bytes=2048))
+        for i in range(100):
+            log.record(AuditCategory.POWER, "display_sleep", str(i))
+        self.assertLessEqual(os.path.getsize(path) + os.path.getsize(path + ".1"), 2048)
+
+        with open(path, "r") as f:
+            lines = f.readlines()
+        lines[0] = json.dumps(dict(json.loads(lines[0]), timestamp=time.time() - 3 * 86400)) + "\n"
+        with open(path, "w") as f:
+            f.writelines(lines)
+        with open(path + ".1", "w") as f:
+            f.write(json.dumps({"timestamp": 1.0, "category": "power", "action": "state"}) + "\n")
+        purge(DataCategory.LOGS, RetentionPolicy(max_age_s=86400))
+        self.assertFalse(os.path.exists(path + ".1"))
+        self.assertEqual(len(log.query()), len(lines) - 1)
+        self.assertEqual(AuditLog(path).expire(time.time() + 1), len(lines) - 1)
+        self.assertEqual(log.query(), [])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,86 @@
+#!/usr/bin/env python3
+"""
+Interaction latency tracing unit tests for CM5 SDK.
+"""
+
+import sys
+import threading
+import time
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.diagnostics.tracing import (
+    STAGE_DISPLAY, add_trace_listener, current_interaction, current_trace_id, interaction, record_stage,
+    remove_trace_listener, stage,
+)
+from distiller_cm5_sdk.hardware.eink import Display, DisplayMode
+
+
+class TestTracing(unittest.TestCase):
+    """Test cases for interactions and their stages."""
+
+    def setUp(self):
+        self.finished = []
+        add_trace_listener(self.finished.append)
+
+    def tearDown(self):
+        remove_trace_listener(self.finished.append)
+
+    def test_stages(self):
+        """Test stages are timed in order against the interaction's ID and reported when it ends."""
+        with self.assertLogs("distiller_cm5_sdk.diagnostics.trace", "INFO") as logs:
+            with interaction("voice", budget_ms=5000) as trace:
+                record_stage("wake_word")
+                with stage("stt", model="test"):
+                    time.sleep(0.02)
+                self.assertEqual(current_trace_id(), trace.id)
+                with self.assertRaises(ValueError):
+                    with stage("llm"):
+                        raise ValueError("no answer")
+        self.assertIsNone(current_interaction())
+        self.assertEqual(self.finished, [trace])
+        self.assertEqual([(s.name, s.ok) for s in trace.stages], [("wake_word", True), ("stt", True), ("llm", False)])
+        self.assertGreaterEqual(trace.stages[1].duration_ms, 20)
+        self.assertEqual(trace.stages[1].details, {"model": "test"})
+        self.assertGreaterEqual(trace.duration_ms, trace.stages[1].duration_ms)
+        self.assertFalse(trace.over_budget)
+        self.assertIn(f"trace {trace.id} voice", logs.output[-1])
+        self.assertTrue(logs.output[-1].startswith("INFO"))
+
+        # Outside an interaction nothing is recorded
+        with stage("stt"):
+            pass
+        record_stage("display", 10)
+        self.assertEqual(len(self.finished), 1)
+
+    def test_budget_and_threads(self):
+        """Test a worker thread joins the interaction, and over-budget stages are logged as warnings."""
+        with self.assertLogs("distiller_cm5_sdk.diagnostics.trace", "WARNING") as logs:
+            with interaction("button", trace_id="abc123") as trace:
+                def worker():
+                    with trace.attach():
+                        with stage("tts", budget_ms=1):
+                            time.sleep(0.01)
+                thread = threading.Thread(target=worker)
+                thread.start()
+                thread.join()
+        self.assertEqual(trace.id, "abc123")
+        self.assertEqual([s.name for s in trace.stages], ["tts"])
+        self.assertTrue(trace.over_budget)
+        self.assertIn("over budget 1 ms", logs.output[0])
+
+    def test_display_stage(self):
+        """Test display refreshes inside an interaction are recorded with the driver's refresh time."""
+        with Display(backend="mock") as display:
+            with interaction("button") as trace:
+                display.display_image(bytes(display.ARRAY_SIZE), DisplayMode.PARTIAL)
+        self.assertEqual([s.name for s in trace.stages], [STAGE_DISPLAY])
+        self.assertEqual(trace.stages[0].details, {"mode": "PARTIAL"})
+        self.assertEqual(trace.to_dict()["stages"][0]["name"], STAGE_DISPLAY)
+
+
+if __name__ == "__main__":
+    unittest.main()
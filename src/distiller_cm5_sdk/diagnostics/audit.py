@@ -0,0 +1,272 @@
+#!/usr/bin/env python3
+"""
+Audit log of hardware operations for CM5 SDK.
+
+Records display updates, audio playback, power events, configuration
+changes and screen lock events as JSON lines in an append-only file. When the file reaches its size
+limit it is rotated to a single ".1" backup, so the log never grows beyond
+about twice the limit. The logs retention policy can turn the log off,
+bound both files together, and expire old entries (see retention).
+
+Auditing is off unless enabled, either by setting DISTILLER_AUDIT_LOG to a file
+path before the SDK is used or by calling enable_audit_log(). Failures to write
+the log never interrupt the operation being recorded.
+"""
+
+import json
+import os
+import sys
+import threading
+import time
+from dataclasses import dataclass, field
+from enum import Enum
+from typing import Any, Dict, List, Optional
+
+from .retention import DataCategory, get_retention_policy
+
+AUDIT_LOG_ENV_VAR = "DISTILLER_AUDIT_LOG"
+AUDIT_LOG_PATH = "/var/log/distiller-cm5-sdk/audit.jsonl"
+AUDIT_LOG_MAX_BYTES = 1024 * 1024
+
+
+class AuditCategory(Enum):
+    """Kinds of audited operations."""
+    DISPLAY = "display"
+    AUDIO = "audio"
+    POWER = "power"
+    CONFIG = "config"
+    SECURITY = "security"
+
+
+@dataclass
+class AuditEntry:
+    """A single audited operation."""
+    timestamp: float                  # seconds since the epoch
+    category: AuditCategory
+    action: str                       # e.g. "update", "play", "sleep", "rotation"
+    source: Optional[str] = None      # what was shown/played/changed, e.g. a file path
+    pid: int = 0
+    process: str = ""
+    details: Dict[str, Any] = field(default_factory=dict)
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "timestamp": self.timestamp,
+            "category": self.category.value,
+            "action": self.action,
+            "source": self.source,
+            "pid": self.pid,
+            "process": self.process,
+            "details": self.details,
+        }
+
+    @classmethod
+    def from_dict(cls, data: Dict[str, Any]) -> "AuditEntry":
+        return cls(
+            timestamp=float(data["timestamp"]),
+            category=AuditCategory(data["category"]),
+            action=data["action"],
+            source=data.get("source"),
+            pid=int(data.get("pid", 0)),
+            process=data.get("process", ""),
+            details=data.get("details") or {},
+        )
+
+
+class AuditLog:
+    """Append-only, size-bounded audit log file."""
+
+    def __init__(self, path: str = AUDIT_LOG_PATH, max_bytes: int = AUDIT_LOG_MAX_BYTES):
+        """
+        Open an audit log.
+
+        Args:
+            path: Log file path; its directory is created on first write
+            max_bytes: Size at which the file is rotated to path + ".1"
+        """
+        self.path = path
+        self.max_bytes = max_bytes
+        self._lock = threading.Lock()
+        self._warned = False
+
+    def record(self, category: AuditCategory, action: str, source: Optional[str] = None,
+               **details: Any) -> None:
+        """
+        Append an entry.
+
+        Args:
+            category: Kind of operation
+            action: What happened
+            source: What was shown, played or changed
+            **details: Additional JSON-serializable fields
+        """
+        policy = get_retention_policy(DataCategory.LOGS)
+        if not policy.persist:
+            return
+        max_bytes = self.max_bytes if policy.max_bytes is None else min(self.max_bytes, policy.max_bytes // 2)
+        entry = AuditEntry(time.time(), category, action, source, os.getpid(),
+                           os.path.basename(sys.argv[0]) if sys.argv and sys.argv[0] else "", details)
+        line = json.dumps(entry.to_dict(), default=str) + "\n"
+
+        with self._lock:
+            try:
+                directory = os.path.dirname(self.path)
+                if directory:
+                    os.makedirs(directory, exist_ok=True)
+                if os.path.exists(self.path) and os.path.getsize(self.path) + len(line) > max_bytes:
+                    os.replace(self.path, self.path + ".1")
+                fd = os.open(self.path, os.O_WRONLY | os.O_APPEND | os.O_CREAT, 0o640)
+                try:
+                    os.write(fd, line.encode("utf-8"))
+                finally:
+                    os.close(fd)
+            except OSError as e:
+                if not self._warned:
+                    print(f"Warning: Failed to write audit log {self.path}: {e}")
+                    self._warned = True
+
+    def expire(self, before: float) -> int:
+        """
+        Drop the entries older than an epoch time from both files.
+
+        Returns:
+            The number of entries dropped
+        """
+        dropped = 0
+        with self._lock:
+            for path in (self.path + ".1", self.path):
+                try:
+                    with open(path, "r", encoding="utf-8") as f:
+                        lines = f.readlines()
+                except OSError:
+                    continue
+                kept = []
+                for line in lines:
+                    try:
+                        if json.loads(line)["timestamp"] >= before:
+                            kept.append(line)
+                    except (ValueError, KeyError, TypeError):
+                        continue  # partial line from an interrupted write
+                if len(kept) == len(lines):
+                    continue
+                dropped += len(lines) - len(kept)
+                try:
+                    if kept or path == self.path:
+                        tmp_path = path + ".tmp"
+                        with open(tmp_path, "w", encoding="utf-8") as f:
+                            f.writelines(kept)
+                        os.chmod(tmp_path, 0o640)
+                        os.replace(tmp_path, path)
+                    else:
+                        os.remove(path)
+                except OSError as e:
+                    print(f"Warning: Failed to expire audit log {path}: {e}")
+        return dropped
+
+    def query(self, category: Optional[AuditCategory] = None, since: Optional[float] = None,
+              until: Optional[float] = None, limit: Optional[int] = None) -> List[AuditEntry]:
+        """
+        Read entries, oldest first.
+
+        Args:
+            category: Only entries of this category
+            since: Only entries at or after this epoch time
+            until: Only entries before this epoch time
+            limit: Return at most this many of the newest matching entries
+
+        Returns:
+            Matching entries from the rotated backup and the current file
+        """
+        entries = []
+        with self._lock:
+            for path in (self.path + ".1", self.path):
+                try:
+                    with open(path, "r", encoding="utf-8") as f:
+                        lines = f.readlines()
+                except OSError:
+                    continue
+                for line in lines:
+                    try:
+                        entry = AuditEntry.from_dict(json.loads(line))
+                    except (ValueError, KeyError, TypeError):
+                        continue  # partial line from an interrupted write
+                    if category is not None and entry.category != category:
+                        continue
+                    if since is not None and entry.timestamp < since:
+                        continue
+                    if until is not None and entry.timestamp >= until:
+                        continue
+                    entries.append(entry)
+
+        if limit is not None:
+            entries = entries[-limit:] if limit > 0 else []
+        return entries
+
+
+_audit_log: Optional[AuditLog] = None
+_env_checked = False
+
+
+def enable_audit_log(path: str = AUDIT_LOG_PATH, max_bytes: int = AUDIT_LOG_MAX_BYTES) -> AuditLog:
+    """
+    Start auditing hardware operations in this process.
+
+    Args:
+        path: Log file path
+        max_bytes: Rotation size
+
+    Returns:
+        The active AuditLog
+    """
+    global _audit_log, _env_checked
+    _audit_log = AuditLog(path, max_bytes)
+    _env_checked = True
+    return _audit_log
+
+
+def disable_audit_log() -> None:
+    """Stop auditing in this process."""
+    global _audit_log, _env_checked
+    _audit_log = None
+    _env_checked = True
+
+
+def get_audit_log() -> Optional[AuditLog]:
+    """Get the active AuditLog, enabling it from DISTILLER_AUDIT_LOG on first use."""
+    global _env_checked
+    if not _env_checked:
+        _env_checked = True
+        path = os.environ.get(AUDIT_LOG_ENV_VAR)
+        if path:
+            enable_audit_log(path)
+    return _audit_log
+
+
+def audit(category: AuditCategory, action: str, source: Optional[str] = None, **details: Any) -> None:
+    """Record an operation if auditing is enabled; otherwise do nothing."""
+    log = get_audit_log()
+    if log is not None:
+        log.record(category, action, source, **details)
+
+
+def query_audit_log(category: Optional[AuditCategory] = None, since: Optional[float] = None,
+                    until: Optional[float] = None, limit: Optional[int] = None,
+                    path: Optional[str] = None) -> List[AuditEntry]:
+    """
+    Query the audit log.
+
+    Args:
+        category: Only entries of this category
+        since: Only entries at or after this epoch time
+        until: Only entries before this epoch time
+        limit: Return at most this many of the newest matching entries
+        path: Log file to read. If None, the active log, or AUDIT_LOG_PATH when
+              auditing is disabled in this process.
+
+    Returns:
+        Matching entries, oldest first
+    """
+    if path is None:
+        log = get_audit_log()
+        path = log.path if log is not None else AUDIT_LOG_PATH
+    return AuditLog(path).query(category, since, until, limit)
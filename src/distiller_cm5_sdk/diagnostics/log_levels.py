@@ -0,0 +1,238 @@
+#!/usr/bin/env python3
+"""
+Runtime log level control for CM5 SDK.
+
+SDK modules log through loggers under "distiller_cm5_sdk" (e.g.
+"distiller_cm5_sdk.hardware.eink.spi" for SPI transfers). Levels can be
+changed per module while a process runs, either from code with set_level() or
+from another process by editing the shared levels file, which every SDK
+process polls:
+
+    python3 -m distiller_cm5_sdk.diagnostics log-level hardware.eink.spi debug
+
+    # /run/distiller-cm5-sdk/log-levels.conf
+    hardware.eink.spi = debug
+    hardware.audio = info
+"""
+
+import logging
+import os
+import tempfile
+import threading
+from typing import Dict, Optional, Union
+
+
+LOG_LEVELS_ENV_VAR = "DISTILLER_LOG_LEVELS"
+LOG_LEVELS_PATH = "/run/distiller-cm5-sdk/log-levels.conf"
+POLL_INTERVAL_S = 2.0
+
+ROOT_LOGGER = "distiller_cm5_sdk"
+
+_LEVEL_NAMES = {
+    "debug": logging.DEBUG,
+    "info": logging.INFO,
+    "warning": logging.WARNING,
+    "error": logging.ERROR,
+    "critical": logging.CRITICAL,
+    "default": logging.NOTSET,
+}
+
+
+class LogLevelError(Exception):
+    """Custom exception for log level errors."""
+    pass
+
+
+def parse_level(level: Union[str, int]) -> int:
+    """
+    Parse a level name (debug, info, warning, error, critical, default) or number.
+
+    Raises:
+        LogLevelError: If the level is not recognised
+    """
+    if isinstance(level, int):
+        return level
+    key = level.strip().lower()
+    if key.isdigit():
+        return int(key)
+    if key not in _LEVEL_NAMES:
+        raise LogLevelError(f"Invalid log level '{level}'; expected one of {', '.join(_LEVEL_NAMES)}")
+    return _LEVEL_NAMES[key]
+
+
+def logger_name(module: str) -> str:
+    """Full logger name of a module given relative to the SDK package ("" is the whole SDK)."""
+    module = module.strip().strip(".")
+    if module.startswith(ROOT_LOGGER):
+        return module
+    return f"{ROOT_LOGGER}.{module}" if module else ROOT_LOGGER
+
+
+def _ensure_handler() -> None:
+    # Without any configured handler, records below WARNING would be dropped
+    sdk_logger = logging.getLogger(ROOT_LOGGER)
+    if not logging.root.handlers and not sdk_logger.handlers:
+        handler = logging.StreamHandler()
+        handler.setFormatter(logging.Formatter("%(asctime)s %(name)s %(levelname)s: %(message)s"))
+        sdk_logger.addHandler(handler)
+
+
+def set_level(module: str, level: Union[str, int]) -> None:
+    """
+    Set the log level of an SDK module in this process.
+
+    Args:
+        module: Module relative to the SDK package, e.g. "hardware.eink.spi";
+                "" for the whole SDK
+        level: Level name or number; "default" restores inheritance from the parent
+
+    Raises:
+        LogLevelError: If the level is not recognised
+    """
+    value = parse_level(level)
+    if value != logging.NOTSET and value < logging.WARNING:
+        _ensure_handler()
+    logging.getLogger(logger_name(module)).setLevel(value)
+
+
+def get_levels() -> Dict[str, str]:
+    """Explicitly set SDK logger levels in this process, keyed by full logger name."""
+    levels = {}
+    for name, logger in logging.Logger.manager.loggerDict.items():
+        if name.startswith(ROOT_LOGGER) and isinstance(logger, logging.Logger) and logger.level:
+            levels[name] = logging.getLevelName(logger.level).lower()
+    return levels
+
+
+def read_levels_file(path: str) -> Dict[str, str]:
+    """
+    Read module = level lines. A missing file yields an empty dictionary.
+
+    Raises:
+        LogLevelError: If a line is malformed
+    """
+    levels = {}
+    try:
+        with open(path, "r") as f:
+            lines = f.readlines()
+    except OSError:
+        return levels
+    for lineno, line in enumerate(lines, 1):
+        line = line.strip()
+        if not line or line.startswith("#"):
+            continue
+        if "=" not in line:
+            raise LogLevelError(f"{path}:{lineno}: expected module = level")
+        module, level = line.split("=", 1)
+        levels[module.strip()] = level.strip()
+    return levels
+
+
+def write_levels_file(path: str, levels: Dict[str, str]) -> None:
+    """
+    Replace the levels file atomically so readers never see a partial file.
+
+    Raises:
+        LogLevelError: If the file cannot be written
+    """
+    directory = os.path.dirname(path) or "."
+    try:
+        os.makedirs(directory, exist_ok=True)
+        fd, tmp_path = tempfile.mkstemp(dir=directory, prefix=".log-levels.")
+        with os.fdopen(fd, "w") as f:
+            for module, level in sorted(levels.items()):
+                f.write(f"{module} = {level}\n")
+        os.chmod(tmp_path, 0o644)
+        os.replace(tmp_path, path)
+    except OSError as e:
+        raise LogLevelError(f"Failed to write {path}: {e}")
+
+
+class LevelFileWatcher:
+    """Applies the levels file whenever it changes."""
+
+    def __init__(self, path: str, interval: float = POLL_INTERVAL_S):
+        self.path = path
+        self.interval = interval
+        self._mtime: Optional[float] = None
+        self._checked = False
+        self._applied: Dict[str, str] = {}
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    def check(self) -> bool:
+        """Apply the file if it changed since the last check. Returns True if applied."""
+        try:
+            mtime = os.stat(self.path).st_mtime
+        except OSError:
+            mtime = None
+        if self._checked and mtime == self._mtime:
+            return False
+        self._checked = True
+        self._mtime = mtime
+
+        try:
+            levels = read_levels_file(self.path)
+        except LogLevelError as e:
+            print(f"Warning: Ignoring log levels file: {e}")
+            return False
+
+        # Modules dropped from the file go back to their default level
+        for module in set(self._applied) - set(levels):
+            set_level(module, logging.NOTSET)
+        for module, level in levels.items():
+            try:
+                set_level(module, level)
+            except LogLevelError as e:
+                print(f"Warning: {self.path}: {e}")
+        self._applied = levels
+        return True
+
+    def start(self) -> None:
+        if self._thread is not None:
+            return
+        self.check()
+
+        def loop():
+            while not self._stop.wait(self.interval):
+                self.check()
+
+        self._thread = threading.Thread(target=loop, name="log-levels", daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        if self._thread is None:
+            return
+        self._stop.set()
+        self._thread.join()
+        self._thread = None
+
+
+_watcher: Optional[LevelFileWatcher] = None
+_watcher_lock = threading.Lock()
+
+
+def levels_path() -> str:
+    """Path of the shared levels file (DISTILLER_LOG_LEVELS overrides the default)."""
+    return os.environ.get(LOG_LEVELS_ENV_VAR, LOG_LEVELS_PATH)
+
+
+def watch_log_levels() -> LevelFileWatcher:
+    """Start applying the shared levels file in this process (once per process)."""
+    global _watcher
+    with _watcher_lock:
+        if _watcher is None:
+            _watcher = LevelFileWatcher(levels_path())
+            _watcher.start()
+        return _watcher
+
+
+def get_logger(module: str) -> logging.Logger:
+    """
+    Get an SDK module's logger, with runtime level control active.
+
+    Args:
+        module: Module relative to the SDK package, e.g. "hardware.eink.spi"
+    """
+    watch_log_levels()
+    return logging.getLogger(logger_name(module))
@@ -0,0 +1,373 @@
+#!/usr/bin/env python3
+"""
+Data retention policies for CM5 SDK.
+
+Privacy-conscious deployments limit how long recordings, captured content
+and logs stay on the device. The policies live in the settings store, so a
+factory reset clears them along with the other settings:
+
+    # /opt/distiller-cm5-sdk/settings/retention.conf
+    [recordings]
+    paths = /home/distiller/recordings
+    max_age = 7d
+    max_size = 200M
+
+    [content]
+    paths = /home/distiller/content
+    max_age = 30d
+
+    [logs]
+    persist = false
+
+A category with persist = false keeps nothing: Audio.record() and saving
+Camera captures refuse to write files, and the audit log is not written.
+Otherwise purge() deletes the category's files older than max_age, then the
+oldest ones until the rest fit in max_size. The recorder and camera purge
+their category after each file they write, and RetentionEnforcer purges
+every category periodically.
+"""
+
+import configparser
+import glob
+import os
+import tempfile
+import threading
+import time
+from dataclasses import dataclass, field
+from enum import Enum
+from typing import Dict, List, Optional, Tuple
+
+from .log_levels import get_logger
+
+
+RETENTION_PATH = "/opt/distiller-cm5-sdk/settings/retention.conf"
+RETENTION_PATH_ENV_VAR = "DISTILLER_RETENTION_CONFIG"
+
+DEFAULT_PURGE_INTERVAL_S = 3600
+
+_DURATION_UNITS = {"s": 1, "m": 60, "h": 3600, "d": 86400, "w": 604800}
+_SIZE_UNITS = {"": 1, "b": 1, "k": 1024, "m": 1024 ** 2, "g": 1024 ** 3}
+
+
+class RetentionError(Exception):
+    """Custom exception for retention policy errors."""
+    pass
+
+
+class DataCategory(Enum):
+    """Kinds of data a retention policy applies to."""
+    RECORDINGS = "recordings"       # audio recorded to files
+    CONTENT = "content"             # camera captures and other user content
+    LOGS = "logs"                   # the audit log and other log files
+
+
+@dataclass
+class RetentionPolicy:
+    """How long one category of data is kept."""
+    persist: bool = True
+    max_age_s: Optional[float] = None       # None keeps files regardless of age
+    max_bytes: Optional[int] = None         # None keeps files regardless of total size
+    paths: List[str] = field(default_factory=list)     # directories, files or glob patterns
+
+
+@dataclass
+class PurgeReport:
+    """What purge() deleted."""
+    category: DataCategory
+    deleted: List[str] = field(default_factory=list)
+    freed_bytes: int = 0
+
+
+def parse_duration(text: str) -> float:
+    """
+    Parse a duration such as "90", "30m", "12h", "7d" or "2w" into seconds.
+
+    Raises:
+        RetentionError: If the text is not a duration
+    """
+    text = text.strip().lower()
+    unit = text[-1:] if text[-1:] in _DURATION_UNITS else "s"
+    number = text[:-1] if text[-1:] in _DURATION_UNITS else text
+    try:
+        value = float(number)
+    except ValueError:
+        raise RetentionError(f"Invalid duration: '{text}'")
+    if value < 0:
+        raise RetentionError(f"Invalid duration: '{text}'")
+    return value * _DURATION_UNITS[unit]
+
+
+def parse_size(text: str) -> int:
+    """
+    Parse a size such as "4096", "512K", "200M" or "1G" into bytes.
+
+    Raises:
+        RetentionError: If the text is not a size
+    """
+    text = text.strip().lower()
+    if text.endswith("ib"):
+        text = text[:-2]
+    unit = text[-1:] if text[-1:] in _SIZE_UNITS else ""
+    number = text[:-1] if unit else text
+    try:
+        value = float(number)
+    except ValueError:
+        raise RetentionError(f"Invalid size: '{text}'")
+    if value < 0:
+        raise RetentionError(f"Invalid size: '{text}'")
+    return int(value * _SIZE_UNITS[unit])
+
+
+def retention_path() -> str:
+    """Policy file path: DISTILLER_RETENTION_CONFIG, or the settings store default."""
+    return os.environ.get(RETENTION_PATH_ENV_VAR, RETENTION_PATH)
+
+
+def load_retention(path: Optional[str] = None) -> Dict[DataCategory, RetentionPolicy]:
+    """
+    Read the retention policies.
+
+    Args:
+        path: Policy file; defaults to retention_path()
+
+    Returns:
+        A policy for every category; categories the file does not mention,
+        or all of them if it does not exist, keep everything
+
+    Raises:
+        RetentionError: If the file is malformed or a value is invalid
+    """
+    path = path or retention_path()
+    parser = configparser.ConfigParser(interpolation=None)
+    try:
+        parser.read(path)
+    except configparser.Error as e:
+        raise RetentionError(f"{path}: {e}")
+
+    policies = {category: RetentionPolicy() for category in DataCategory}
+    for name in parser.sections():
+        try:
+            category = DataCategory(name)
+        except ValueError:
+            raise RetentionError(f"{path}: unknown category [{name}]")
+        section = parser[name]
+        policy = policies[category]
+        for key, value in section.items():
+            if key == "persist":
+                try:
+                    policy.persist = section.getboolean(key)
+                except ValueError:
+                    raise RetentionError(f"{path}: [{name}] persist must be true or false")
+            elif key == "max_age":
+                policy.max_age_s = parse_duration(value)
+            elif key == "max_size":
+                policy.max_bytes = parse_size(value)
+            elif key == "paths":
+                policy.paths = [p.strip() for p in value.split(",") if p.strip()]
+            else:
+                raise RetentionError(f"{path}: [{name}] unknown key '{key}'")
+    return policies
+
+
+def save_retention(policies: Dict[DataCategory, RetentionPolicy], path: Optional[str] = None) -> None:
+    """
+    Replace the policy file atomically, e.g. from a settings screen.
+
+    Raises:
+        RetentionError: If the file cannot be written
+    """
+    path = path or retention_path()
+    lines = []
+    for category in DataCategory:
+        policy = policies.get(category)
+        if policy is None or policy == RetentionPolicy():
+            continue
+        lines.append(f"[{category.value}]")
+        if not policy.persist:
+            lines.append("persist = false")
+        if policy.max_age_s is not None:
+            lines.append(f"max_age = {policy.max_age_s:g}")
+        if policy.max_bytes is not None:
+            lines.append(f"max_size = {policy.max_bytes}")
+        if policy.paths:
+            lines.append(f"paths = {', '.join(policy.paths)}")
+        lines.append("")
+
+    directory = os.path.dirname(path) or "."
+    try:
+        os.makedirs(directory, exist_ok=True)
+        fd, tmp_path = tempfile.mkstemp(dir=directory, prefix=".retention.")
+        with os.fdopen(fd, "w") as f:
+            f.write("\n".join(lines))
+        os.chmod(tmp_path, 0o644)
+        os.replace(tmp_path, path)
+    except OSError as e:
+        raise RetentionError(f"Failed to write {path}: {e}")
+    _reset_cache()
+
+
+_overrides: Dict[DataCategory, RetentionPolicy] = {}
+_cache: Optional[Tuple[str, Optional[float], Dict[DataCategory, RetentionPolicy]]] = None
+_cache_lock = threading.Lock()
+_warned = False
+
+
+def _reset_cache() -> None:
+    global _cache
+    with _cache_lock:
+        _cache = None
+
+
+def set_retention_policy(category: DataCategory, policy: Optional[RetentionPolicy]) -> None:
+    """Use policy for category in this process instead of the file's; None goes back to the file."""
+    if policy is None:
+        _overrides.pop(category, None)
+    else:
+        _overrides[category] = policy
+
+
+def get_retention_policy(category: DataCategory) -> RetentionPolicy:
+    """
+    The policy for a category, as set with set_retention_policy() or read
+    from the policy file. The file is read again when it changes, so running
+    processes follow the settings store.
+
+    A malformed file prints a warning and keeps nothing for every category,
+    so a typo never keeps more than intended.
+    """
+    global _cache, _warned
+    if category in _overrides:
+        return _overrides[category]
+    path = retention_path()
+    try:
+        mtime: Optional[float] = os.stat(path).st_mtime
+    except OSError:
+        mtime = None
+    with _cache_lock:
+        if _cache is None or _cache[:2] != (path, mtime):
+            try:
+                policies = load_retention(path)
+                _warned = False
+            except RetentionError as e:
+                if not _warned:
+                    print(f"Warning: {e}; keeping no data until it is fixed")
+                    _warned = True
+                policies = {c: RetentionPolicy(persist=False) for c in DataCategory}
+            _cache = (path, mtime, policies)
+        return _cache[2][category]
+
+
+def _files(paths: List[str]) -> List[Tuple[str, float, int]]:
+    """(path, mtime, size) of every regular file under paths."""
+    found = {}
+    for pattern in paths:
+        for match in glob.glob(os.path.expanduser(pattern)):
+            if os.path.isdir(match) and not os.path.islink(match):
+                candidates = (os.path.join(root, name) for root, _, names in os.walk(match) for name in names)
+            else:
+                candidates = iter([match])
+            for candidate in candidates:
+                try:
+                    st = os.lstat(candidate)
+                except OSError:
+                    continue
+                if os.path.isfile(candidate) and not os.path.islink(candidate):
+                    found[candidate] = (candidate, st.st_mtime, st.st_size)
+    return list(found.values())
+
+
+def purge(category: DataCategory, policy: Optional[RetentionPolicy] = None,
+          now: Optional[float] = None) -> PurgeReport:
+    """
+    Delete the files of a category its policy no longer keeps.
+
+    Files that cannot be deleted print a warning and are skipped. For LOGS,
+    entries of the active audit log and event history older than max_age are
+    dropped too.
+
+    Args:
+        category: Category to purge
+        policy: Policy to apply; defaults to get_retention_policy(category)
+        now: Current epoch time, replaceable for testing
+
+    Returns:
+        The files deleted and the bytes freed
+    """
+    policy = policy or get_retention_policy(category)
+    now = time.time() if now is None else now
+    report = PurgeReport(category)
+
+    files = sorted(_files(policy.paths), key=lambda f: f[1])     # oldest first
+    total = sum(size for _, _, size in files)
+    for path, mtime, size in files:
+        expired = (not policy.persist
+                   or (policy.max_age_s is not None and now - mtime > policy.max_age_s)
+                   or (policy.max_bytes is not None and total > policy.max_bytes))
+        if not expired:
+            continue
+        try:
+            os.remove(path)
+        except OSError as e:
+            print(f"Warning: Failed to purge {path}: {e}")
+            continue
+        total -= size
+        report.deleted.append(path)
+        report.freed_bytes += size
+
+    if category == DataCategory.LOGS and policy.max_age_s is not None:
+        from .audit import get_audit_log
+        log = get_audit_log()
+        if log is not None:
+            log.expire(now - policy.max_age_s)
+        from ..events.history import get_event_history
+        history = get_event_history()
+        if history is not None:
+            history.expire(now - policy.max_age_s)
+
+    if report.deleted:
+        get_logger("diagnostics.retention").info("purged %d %s files, %d bytes", len(report.deleted),
+                                                 category.value, report.freed_bytes)
+    return report
+
+
+class RetentionEnforcer:
+    """Purges every category periodically on a background thread."""
+
+    def __init__(self, interval_s: float = DEFAULT_PURGE_INTERVAL_S):
+        """
+        Args:
+            interval_s: Seconds between purges
+        """
+        self.interval_s = interval_s
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    def purge_all(self) -> List[PurgeReport]:
+        """Purge each category once."""
+        return [purge(category) for category in DataCategory]
+
+    def start(self) -> None:
+        """Purge now, then every interval_s seconds, until stop()."""
+        if self._thread is not None:
+            return
+        self._stop.clear()
+
+        def loop():
+            while True:
+                try:
+                    self.purge_all()
+                except Exception as e:
+                    print(f"Warning: Retention purge failed: {e}")
+                if self._stop.wait(self.interval_s):
+                    return
+
+        self._thread = threading.Thread(target=loop, name="retention", daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop purging."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join()
+            self._thread = None
+
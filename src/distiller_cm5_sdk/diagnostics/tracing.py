@@ -0,0 +1,240 @@
+#!/usr/bin/env python3
+"""
+Latency tracing of user interactions for CM5 SDK.
+
+One user interaction, e.g. a voice request from the wake word to the answer
+on the display, gets a correlation ID. Stages timed while it is active, by
+SDK modules or by the app, are recorded against that ID, and the finished
+interaction is logged with every stage's timing:
+
+    with interaction("voice", budget_ms=2000):
+        record_stage("wake_word")                           # a moment, no duration
+        text = next(parakeet.transcribe_buffer(audio))      # times itself as "stt"
+        with stage("llm"):
+            answer = ask(text)
+        piper.speak_stream(answer)                          # "tts"
+        display.display_image(frame)                        # "display"
+
+    trace 5e1c09a2 voice 1840 ms (budget 2000 ms): wake_word @0, stt 640 ms @3, llm 710 ms @650, ...
+
+The active interaction follows the code through contextvars, so functions it
+calls and asyncio tasks it starts see it; a thread handed part of the work
+joins it with Interaction.attach(). Stages outside an interaction cost one
+lookup and are not recorded.
+
+The output goes to the "diagnostics.trace" logger: the summary at info, or
+at warning when the interaction or a stage is over its budget, and each
+stage at debug as it ends.
+"""
+
+import contextvars
+import threading
+import time
+import uuid
+from contextlib import contextmanager
+from dataclasses import dataclass, field
+from typing import Any, Callable, Dict, Iterator, List, Optional
+
+from .log_levels import get_logger
+
+
+# Stage names used by the SDK's own modules
+STAGE_STT = "stt"
+STAGE_TTS = "tts"
+STAGE_DISPLAY = "display"
+
+_current: contextvars.ContextVar[Optional["Interaction"]] = contextvars.ContextVar(
+    "distiller_interaction", default=None)
+_listeners: List[Callable[["Interaction"], None]] = []
+
+
+@dataclass
+class StageTiming:
+    """One timed stage of an interaction."""
+    name: str
+    start_ms: int                     # since the interaction started
+    duration_ms: int
+    ok: bool = True                   # False if the stage raised
+    budget_ms: Optional[int] = None
+    details: Dict[str, Any] = field(default_factory=dict)
+
+    @property
+    def over_budget(self) -> bool:
+        return self.budget_ms is not None and self.duration_ms > self.budget_ms
+
+
+class Interaction:
+    """A traced user interaction and the stages timed in it."""
+
+    def __init__(self, name: str, budget_ms: Optional[int] = None, trace_id: Optional[str] = None):
+        """
+        Args:
+            name: What the user did, e.g. "voice" or "button"
+            budget_ms: End-to-end latency the interaction should stay within
+            trace_id: Correlation ID to continue, e.g. one received from another
+                      process; a new one is made if None
+        """
+        self.id = trace_id or uuid.uuid4().hex[:8]
+        self.name = name
+        self.budget_ms = budget_ms
+        self.started = time.time()
+        self.stages: List[StageTiming] = []
+        self.duration_ms: Optional[int] = None      # set by finish()
+        self._start = time.monotonic()
+        self._lock = threading.Lock()
+
+    def elapsed_ms(self) -> int:
+        """Milliseconds since the interaction started."""
+        return int((time.monotonic() - self._start) * 1000)
+
+    @property
+    def over_budget(self) -> bool:
+        """Whether the interaction, or any of its stages, took longer than its budget."""
+        total = self.duration_ms if self.duration_ms is not None else self.elapsed_ms()
+        return (self.budget_ms is not None and total > self.budget_ms) or any(s.over_budget for s in self.stages)
+
+    def add_stage(self, timing: StageTiming) -> None:
+        """Record a stage; stages after finish() are ignored."""
+        with self._lock:
+            if self.duration_ms is not None:
+                return
+            self.stages.append(timing)
+        get_logger("diagnostics.trace").debug(f"trace {self.id} {self.name}: {_describe(timing)}")
+
+    @contextmanager
+    def attach(self) -> Iterator["Interaction"]:
+        """Make this the active interaction in the calling thread or task, e.g. in a worker thread."""
+        token = _current.set(self)
+        try:
+            yield self
+        finally:
+            _current.reset(token)
+
+    def finish(self) -> None:
+        """End the interaction, log its timings and pass it to the trace listeners. Only the first call counts."""
+        with self._lock:
+            if self.duration_ms is not None:
+                return
+            self.duration_ms = self.elapsed_ms()
+        logger = get_logger("diagnostics.trace")
+        if self.over_budget:
+            logger.warning(self.summary())
+        else:
+            logger.info(self.summary())
+        for listener in list(_listeners):
+            try:
+                listener(self)
+            except Exception as e:
+                print(f"Warning: Trace listener failed: {e}")
+
+    def summary(self) -> str:
+        """One line with the interaction's total time and each stage's."""
+        total = self.duration_ms if self.duration_ms is not None else self.elapsed_ms()
+        budget = f" (budget {self.budget_ms} ms)" if self.budget_ms is not None else ""
+        stages = ", ".join(_describe(timing) for timing in self.stages) or "no stages"
+        return f"trace {self.id} {self.name} {total} ms{budget}: {stages}"
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "id": self.id,
+            "name": self.name,
+            "started": self.started,
+            "duration_ms": self.duration_ms,
+            "budget_ms": self.budget_ms,
+            "stages": [
+                {"name": s.name, "start_ms": s.start_ms, "duration_ms": s.duration_ms, "ok": s.ok,
+                 "budget_ms": s.budget_ms, "details": s.details}
+                for s in self.stages
+            ],
+        }
+
+
+def _describe(timing: StageTiming) -> str:
+    text = f"{timing.name} {timing.duration_ms} ms @{timing.start_ms}" if timing.duration_ms else \
+        f"{timing.name} @{timing.start_ms}"
+    if not timing.ok:
+        text += " failed"
+    if timing.over_budget:
+        text += f" over budget {timing.budget_ms} ms"
+    return text
+
+
+@contextmanager
+def interaction(name: str, budget_ms: Optional[int] = None,
+                trace_id: Optional[str] = None) -> Iterator[Interaction]:
+    """
+    Trace a user interaction for the duration of the block.
+
+    Args:
+        name: What the user did, e.g. "voice"
+        budget_ms: End-to-end latency it should stay within; slower ones are logged as warnings
+        trace_id: Correlation ID to continue instead of making a new one
+
+    Yields:
+        The Interaction, also returned by current_interaction() inside the block
+    """
+    current = Interaction(name, budget_ms, trace_id)
+    with current.attach():
+        try:
+            yield current
+        finally:
+            current.finish()
+
+
+def current_interaction() -> Optional[Interaction]:
+    """The active interaction, or None outside one."""
+    return _current.get()
+
+
+def current_trace_id() -> Optional[str]:
+    """Correlation ID of the active interaction, e.g. to pass to another process, or None."""
+    current = _current.get()
+    return current.id if current is not None else None
+
+
+@contextmanager
+def stage(name: str, budget_ms: Optional[int] = None, **details: Any) -> Iterator[Optional[Interaction]]:
+    """
+    Time the block as a stage of the active interaction; does nothing outside one.
+
+    Args:
+        name: Stage name, e.g. "stt", "llm" or "tts"
+        budget_ms: Time the stage should take at most
+        **details: JSON-serializable fields recorded with the stage
+
+    Yields:
+        The active Interaction, or None
+    """
+    current = _current.get()
+    if current is None:
+        yield None
+        return
+    start_ms = current.elapsed_ms()
+    ok = False
+    try:
+        yield current
+        ok = True
+    finally:
+        current.add_stage(StageTiming(name, start_ms, current.elapsed_ms() - start_ms, ok, budget_ms, details))
+
+
+def record_stage(name: str, duration_ms: int = 0, **details: Any) -> None:
+    """
+    Record a stage that ended now and was timed elsewhere, or a moment such
+    as the wake word being heard (duration 0); does nothing outside an interaction.
+    """
+    current = _current.get()
+    if current is None:
+        return
+    end_ms = current.elapsed_ms()
+    current.add_stage(StageTiming(name, max(0, end_ms - duration_ms), duration_ms, details=details))
+
+
+def add_trace_listener(callback: Callable[[Interaction], None]) -> None:
+    """Call callback with every finished interaction, e.g. to export latency metrics."""
+    _listeners.append(callback)
+
+
+def remove_trace_listener(callback: Callable[[Interaction], None]) -> None:
+    """Stop calling a trace listener."""
+    _listeners.remove(callback)
@@ -0,0 +1,10 @@
+from .bus import Event, EventBus, get_bus
+from .history import (
+    EventHistory, disable_event_history, enable_event_history, get_event_history, query_event_history,
+)
+from .sources import (
+    BATTERY_LOW, BATTERY_OK, BUTTON_EVENTS, FOCUS_GRANTED, FOCUS_REVOKED, MIC_MUTED, MIC_UNMUTED, TOUCH_LONG_PRESS,
+    TOUCH_SWIPE, TOUCH_TAP, WAKE_WORD, BatteryMonitor, publish_buttons, publish_focus, publish_mic_mute,
+    publish_power_states, publish_touch,
+)
+from .webhooks import Webhook, WebhookEngine, WebhookError, load_webhooks
@@ -0,0 +1,104 @@
+#!/usr/bin/env python3
+"""
+Device events: python3 -m distiller_cm5_sdk.events webhooks | send <event> [KEY=VALUE ...]
+"""
+
+import argparse
+import signal
+import sys
+import threading
+
+from .bus import Event
+from .webhooks import WebhookEngine, WebhookError, load_webhooks
+
+
+def _parse_field(text: str):
+    key, sep, value = text.partition("=")
+    if not sep or not key:
+        raise argparse.ArgumentTypeError(f"Expected KEY=VALUE, got '{text}'")
+    return key, value
+
+
+def _load(args):
+    try:
+        webhooks = load_webhooks(args.config)
+    except WebhookError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return None
+    if not webhooks:
+        print("Error: No webhooks configured", file=sys.stderr)
+        return None
+    return webhooks
+
+
+def _webhooks(args) -> int:
+    from ..recovery.buttons import ButtonError, GpioButtons, parse_pins
+    from ..smarthome.endpoints import DeviceBridge
+    from .sources import publish_buttons
+
+    webhooks = _load(args)
+    if webhooks is None:
+        return 1
+    try:
+        buttons = GpioButtons(parse_pins(args.gpio_pins), chip_name=args.gpio_chip) if args.gpio_pins else None
+    except ButtonError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+
+    engine = WebhookEngine(webhooks)
+    bridge = DeviceBridge.from_device(buttons=buttons, temperature_path=None)
+    publish_buttons(bridge)
+    stop = threading.Event()
+    signal.signal(signal.SIGTERM, lambda signum, frame: stop.set())
+    engine.start()
+    bridge.start()
+    print(f"Serving {len(webhooks)} webhook(s): {', '.join(w.name for w in webhooks)}")
+    try:
+        stop.wait()
+    except KeyboardInterrupt:
+        pass
+    finally:
+        bridge.stop()
+        engine.stop(timeout=30)
+        if buttons is not None:
+            buttons.close()
+    return 0
+
+
+def _send(args) -> int:
+    webhooks = _load(args)
+    if webhooks is None:
+        return 1
+    engine = WebhookEngine(webhooks)
+    event = Event(args.event, dict(args.field))
+    matching = [webhook for webhook in webhooks if webhook.matches(event.name)]
+    if not matching:
+        print(f"Error: No webhook subscribes to {event.name}", file=sys.stderr)
+        return 1
+    failed = [webhook.name for webhook in matching if not engine.deliver(webhook, event)]
+    for webhook in matching:
+        print(f"{webhook.name}: {'failed' if webhook.name in failed else 'delivered'}")
+    return 1 if failed else 0
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Act on device events")
+    parser.add_argument("--config", help="Webhook config file (default: /opt/distiller-cm5-sdk/webhooks.conf)")
+    commands = parser.add_subparsers(dest="command_name", required=True)
+
+    webhooks = commands.add_parser("webhooks", help="Post button presses to the configured webhooks")
+    webhooks.add_argument("--gpio-pins", metavar="UP,DOWN,SELECT", help="GPIO line offsets of the buttons")
+    webhooks.add_argument("--gpio-chip", default="gpiochip0", help="GPIO chip of the button lines")
+    webhooks.set_defaults(handler=_webhooks)
+
+    send = commands.add_parser("send", help="Post one event to the webhooks subscribed to it, e.g. to test them")
+    send.add_argument("event", help="Event name, e.g. battery.low")
+    send.add_argument("field", nargs="*", type=_parse_field, metavar="KEY=VALUE", help="Event data")
+    send.set_defaults(handler=_send)
+
+    args = parser.parse_args(argv)
+    return args.handler(args)
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+"""
+Event bus and event source unit tests for CM5 SDK.
+"""
+
+import sys
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.device.power_state import PowerState, PowerStateMachine
+from distiller_cm5_sdk.events.bus import EventBus
+from distiller_cm5_sdk.events.sources import BatteryMonitor, publish_buttons, publish_power_states, publish_touch
+from distiller_cm5_sdk.hardware.touch import TouchAction, TouchPanel, TouchPoint
+from distiller_cm5_sdk.smarthome.endpoints import DeviceBridge, PressType
+
+
+class FakeTouchController:
+    def __init__(self):
+        self.points = []
+
+    def read_points(self):
+        return self.points
+
+
+class TestEventBus(unittest.TestCase):
+    """Test cases for publishing and subscribing."""
+
+    def test_subscriptions(self):
+        """Test handlers get the events matching their pattern, even when another handler fails."""
+        bus = EventBus()
+        buttons, everything = [], []
+
+        def failing(event):
+            raise RuntimeError("boom")
+
+        bus.subscribe("button.*", failing)
+        bus.subscribe("button.*", buttons.append)
+        bus.subscribe("*", everything.append)
+        event = bus.publish("button.long_press", button="select")
+        bus.publish("battery.low", percent=9)
+        self.assertEqual(buttons, [event])
+        self.assertEqual(event.data, {"button": "select"})
+        self.assertEqual([e.name for e in everything], ["button.long_press", "battery.low"])
+
+        bus.unsubscribe("*", everything.append)
+        bus.publish("battery.ok", percent=30)
+        self.assertEqual(len(everything), 2)
+
+
+class TestEventSources(unittest.TestCase):
+    """Test cases for the adapters publishing device events."""
+
+    def setUp(self):
+        self.bus = EventBus()
+        self.events = []
+        self.bus.subscribe("*", lambda event: self.events.append((event.name, event.data)))
+
+    def test_buttons_and_power_states(self):
+        """Test button presses and power transitions are published."""
+        bridge = DeviceBridge()
+        bridge.add_button("select")
+        publish_buttons(bridge, self.bus)
+        bridge.buttons["select"].press(PressType.LONG)
+
+        machine = PowerStateMachine(suspend_command=None)
+        publish_power_states(machine, self.bus)
+        machine.request(PowerState.DIM, reason="test")
+
+        self.assertEqual(self.events, [
+            ("button.long_press", {"button": "select"}),
+            ("power.dim", {"previous": "active", "reason": "test"}),
+        ])
+
+    def test_touch(self):
+        """Test taps and swipes are published with their coordinates."""
+        controller = FakeTouchController()
+        panel = TouchPanel(controller)
+        publish_touch(panel, self.bus)
+        for now, points in ((0.0, [TouchPoint(0, 10, 20, TouchAction.DOWN)]), (0.1, []),
+                            (1.0, [TouchPoint(1, 10, 200, TouchAction.DOWN)]),
+                            (1.1, [TouchPoint(1, 12, 100, TouchAction.CONTACT)]), (1.2, [])):
+            controller.points = points
+            panel.poll(now)
+
+        self.assertEqual(self.events, [
+            ("touch.tap", {"x": 10, "y": 20}),
+            ("touch.swipe", {"direction": "up", "x": 10, "y": 200, "end_x": 12, "end_y": 100}),
+        ])
+
+    def test_battery_monitor(self):
+        """Test battery.low and battery.ok are published once per crossing, with hysteresis."""
+        readings = iter([50, 14, 12, 16, None, 25, 30])
+        monitor = BatteryMonitor(lambda: next(readings), self.bus, low_percent=15, ok_percent=20)
+        for _ in range(7):
+            monitor.check()
+        self.assertEqual(self.events, [("battery.low", {"percent": 14}), ("battery.ok", {"percent": 25})])
+        with self.assertRaises(ValueError):
+            BatteryMonitor(lambda: 50, self.bus, low_percent=20, ok_percent=10)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,122 @@
+#!/usr/bin/env python3
+"""
+Event history unit tests for CM5 SDK.
+"""
+
+import io
+import json
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.diagnostics.__main__ import main
+from distiller_cm5_sdk.diagnostics.retention import DataCategory, RetentionPolicy, purge, set_retention_policy
+from distiller_cm5_sdk.events.bus import Event, EventBus
+from distiller_cm5_sdk.events.history import (
+    EVENT_HISTORY_ENV_VAR, EventHistory, disable_event_history, enable_event_history, get_event_history,
+    query_event_history,
+)
+
+
+class TestEventHistory(unittest.TestCase):
+    """Test cases for recording and querying past events."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "events.jsonl")
+
+    def tearDown(self):
+        disable_event_history()
+        set_retention_policy(DataCategory.LOGS, None)
+        self.tmpdir.cleanup()
+
+    def test_ring_buffer(self):
+        """Test the newest events are kept and queried by pattern, time and count."""
+        history = EventHistory(capacity=3)
+        for i, name in enumerate(["button.single_press", "battery.low", "button.long_press",
+                                  "button.single_press"]):
+            history.record(Event(name, {"n": i}, timestamp=100.0 + i))
+        self.assertEqual([event.data["n"] for event in history.query()], [1, 2, 3])
+        self.assertEqual([event.name for event in history.query("button.*")],
+                         ["button.long_press", "button.single_press"])
+        self.assertEqual([event.data["n"] for event in history.query(since=102, until=103)], [2])
+        self.assertEqual([event.data["n"] for event in history.query(limit=1)], [3])
+        self.assertEqual(history.query(limit=0), [])
+
+        bus = EventBus()
+        history.attach(bus)
+        bus.publish("battery.ok", percent=21)
+        history.detach()
+        bus.publish("battery.low", percent=9)
+        self.assertEqual(history.query("battery.*")[-1].data, {"percent": 21})
+
+    def test_persisted(self):
+        """Test the history is kept in a rotated file that another process reads back."""
+        bus = EventBus()
+        history = enable_event_history(self.path, capacity=10, max_bytes=400, bus=bus)
+        for i in range(12):
+            bus.publish("button.single_press", button="select", n=i)
+        self.assertTrue(os.path.exists(self.path + ".1"))
+        self.assertLessEqual(os.path.getsize(self.path), 400)
+        with open(self.path, "a") as f:
+            f.write('{"event": "button.sing')                    # interrupted write
+        disable_event_history()
+
+        on_disk = query_event_history("button.*", path=self.path)
+        self.assertEqual([event.data["n"] for event in on_disk][-3:], [9, 10, 11])
+        self.assertEqual(on_disk[0].data["button"], "select")
+        reopened = EventHistory(capacity=5, path=self.path)
+        self.assertEqual([event.data["n"] for event in reopened.query()], [7, 8, 9, 10, 11])
+        self.assertEqual(history.query(limit=1), reopened.query(limit=1))
+
+        with patch.dict(os.environ, {EVENT_HISTORY_ENV_VAR: self.path}):
+            self.assertEqual(len(query_event_history(limit=2)), 2)
+
+    def test_retention(self):
+        """Test the logs policy keeps the history out of the file and expires old events."""
+        set_retention_policy(DataCategory.LOGS, RetentionPolicy(persist=False))
+        history = enable_event_history(self.path, bus=EventBus())
+        history.record(Event("battery.low", {"percent": 9}, timestamp=1000.0))
+        self.assertFalse(os.path.exists(self.path))
+        self.assertEqual(len(history.query()), 1)
+
+        set_retention_policy(DataCategory.LOGS, RetentionPolicy(max_age_s=60))
+        history.record(Event("battery.low", {"percent": 8}, timestamp=1000.0))
+        history.record(Event("battery.ok", {"percent": 20}, timestamp=1100.0))
+        purge(DataCategory.LOGS, now=1130.0)
+        self.assertEqual([event.name for event in history.query()], ["battery.ok"])
+        self.assertEqual([event.name for event in query_event_history(path=self.path)], ["battery.ok"])
+
+    def test_env(self):
+        """Test DISTILLER_EVENT_HISTORY records get_bus() from its first use."""
+        import distiller_cm5_sdk.events.history as module
+        from distiller_cm5_sdk.events.bus import get_bus
+        with patch.dict(os.environ, {EVENT_HISTORY_ENV_VAR: self.path}), patch.object(module, "_env_checked", False):
+            get_bus().publish("voice.wake_word", word="hey distiller")
+            self.assertEqual(get_event_history().path, self.path)
+        self.assertEqual(query_event_history("voice.*", path=self.path)[0].data, {"word": "hey distiller"})
+
+    def test_cli(self):
+        """Test the diagnostics command shows recent events of the history file."""
+        history = EventHistory(path=self.path)
+        history.record(Event("button.long_press", {"button": "select"}))
+        history.record(Event("battery.low", {"percent": 9}, timestamp=1000.0))
+        with patch("sys.stdout", io.StringIO()) as out:
+            self.assertEqual(main(["events", "button.*", "--last", "60", "--file", self.path]), 0)
+        self.assertRegex(out.getvalue(), r"^\d{4}-\d\d-\d\d \d\d:\d\d:\d\d\.\d{3} button.long_press button=select\n$")
+        with patch("sys.stdout", io.StringIO()) as out:
+            main(["events", "--json", "--limit", "1", "--file", self.path])
+        self.assertEqual(json.loads(out.getvalue())["data"], {"percent": 9})
+        with patch("sys.stdout", io.StringIO()) as out:
+            main(["events", "privacy.*", "--file", self.path])
+        self.assertEqual(out.getvalue(), "No events recorded\n")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,136 @@
+#!/usr/bin/env python3
+"""
+Webhook unit tests for CM5 SDK.
+"""
+
+import json
+import os
+import queue
+import sys
+import tempfile
+import threading
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.events.bus import Event, EventBus
+from distiller_cm5_sdk.events.webhooks import Webhook, WebhookEngine, WebhookError, load_webhooks
+
+
+class FakeEndpoint:
+    """Answers each request with the next status; an exception is raised instead."""
+
+    def __init__(self, *responses):
+        self.responses = list(responses)
+        self.requests = []
+
+    def __call__(self, url, body, headers, timeout_s):
+        self.requests.append((url, json.loads(body), headers))
+        response = self.responses.pop(0) if self.responses else 200
+        if isinstance(response, Exception):
+            raise response
+        return response
+
+
+class TestWebhooks(unittest.TestCase):
+    """Test cases for webhook configuration and delivery."""
+
+    def test_load_webhooks(self):
+        """Test webhooks are read from their sections, and invalid ones are rejected."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "webhooks.conf")
+            self.assertEqual(load_webhooks(path), [])
+            Path(path).write_text("[doorbell]\nevents = button.long_press, voice.*\nurl = https://example.com/hook\n"
+                                  "header.Authorization = Bearer 123\nretries = 1\n\n"
+                                  "[all]\nurl = http://10.0.0.2/events\n")
+            doorbell, everything = load_webhooks(path)
+            self.assertEqual(doorbell.events, ("button.long_press", "voice.*"))
+            self.assertEqual((doorbell.headers, doorbell.retries), ({"Authorization": "Bearer 123"}, 1))
+            self.assertTrue(doorbell.matches("voice.wake_word"))
+            self.assertFalse(doorbell.matches("button.single_press"))
+            self.assertTrue(everything.matches("battery.low"))
+
+            for bad in ("[a]\nevents = x\n", "[a]\nurl = ftp://example.com\n", "[a]\nurl = http://x\nretries = -1\n",
+                        "[a]\nurl = http://x\ntimeout = soon\n", "url = http://x\n"):
+                with self.subTest(config=bad):
+                    Path(path).write_text(bad)
+                    with self.assertRaises(WebhookError):
+                        load_webhooks(path)
+
+    def test_retries(self):
+        """Test failed deliveries are retried with backoff, except for client errors."""
+        delays = []
+        event = Event("battery.low", {"percent": 9}, timestamp=1000.0)
+        webhook = Webhook("battery", "https://example.com/hook", headers={"X-Token": "t"}, retries=3)
+
+        endpoint = FakeEndpoint(OSError("timed out"), 503, 200)
+        engine = WebhookEngine([webhook], EventBus(), post=endpoint, sleep=delays.append)
+        self.assertTrue(engine.deliver(webhook, event))
+        self.assertEqual(delays, [2.0, 4.0])
+        url, payload, headers = endpoint.requests[-1]
+        self.assertEqual(payload["event"], "battery.low")
+        self.assertEqual((payload["timestamp"], payload["data"]), (1000.0, {"percent": 9}))
+        self.assertEqual((headers["X-Token"], headers["Content-Type"]), ("t", "application/json"))
+
+        engine = WebhookEngine([webhook], EventBus(), post=FakeEndpoint(404), sleep=delays.append)
+        self.assertFalse(engine.deliver(webhook, event))
+        engine = WebhookEngine([webhook], EventBus(), post=FakeEndpoint(500, 500, 500, 500), sleep=delays.append)
+        self.assertFalse(engine.deliver(webhook, event))
+        self.assertEqual(delays, [2.0, 4.0, 2.0, 4.0, 8.0])
+
+    def test_engine(self):
+        """Test the engine posts matching bus events in the background until stopped."""
+        bus = EventBus()
+        endpoint = FakeEndpoint()
+        engine = WebhookEngine([Webhook("doorbell", "https://example.com/a", events=("button.long_press",)),
+                                Webhook("all", "https://example.com/b")], bus, post=endpoint)
+        engine.start()
+        bus.publish("button.long_press", button="select")
+        bus.publish("battery.low", percent=9)
+        engine.stop(timeout=5)
+        bus.publish("battery.ok", percent=30)
+        # Each webhook has its own worker, so only the order per webhook is fixed
+        self.assertEqual(sorted((url, payload["event"]) for url, payload, _ in endpoint.requests), [
+            ("https://example.com/a", "button.long_press"),
+            ("https://example.com/b", "battery.low"),
+            ("https://example.com/b", "button.long_press"),
+        ])
+        self.assertEqual([payload["event"] for url, payload, _ in endpoint.requests if url.endswith("/b")],
+                         ["button.long_press", "battery.low"])
+
+    def test_slow_webhook(self):
+        """Test a stalled webhook drops its oldest events and does not hold up the others."""
+        bus = EventBus()
+        entered, released = threading.Event(), threading.Event()
+        slow, fast = [], queue.Queue()
+
+        def endpoint(url, body, headers, timeout_s):
+            n = json.loads(body)["data"]["n"]
+            if url.endswith("/fast"):
+                fast.put(n)
+                return 200
+            entered.set()
+            released.wait(5)
+            slow.append(n)
+            return 200
+
+        webhooks = [Webhook("slow", "https://example.com/slow"), Webhook("fast", "https://example.com/fast")]
+        engine = WebhookEngine(webhooks, bus, post=endpoint, queue_size=2)
+        engine.start()
+        bus.publish("tick", n=0)
+        self.assertTrue(entered.wait(5))        # the slow worker is now stuck on event 0
+        self.assertEqual(fast.get(timeout=5), 0)
+        for n in range(1, 5):
+            bus.publish("tick", n=n)
+            self.assertEqual(fast.get(timeout=5), n)    # delivered while the slow endpoint stalls
+        released.set()
+        engine.stop(timeout=5)
+        self.assertEqual(slow, [0, 3, 4])
+        with self.assertRaises(WebhookError):
+            WebhookEngine([], bus, queue_size=0)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,86 @@
+#!/usr/bin/env python3
+"""
+In-process event bus for CM5 SDK.
+
+Device components publish what happens to them under dotted names, e.g.
+"button.long_press" or "battery.low", and any number of consumers, such as
+webhooks, subscribe by name or glob pattern without knowing the producers:
+
+    bus = get_bus()
+    bus.subscribe("button.*", lambda event: print(event.name, event.data))
+    bus.publish("button.long_press", button="select")
+"""
+
+import fnmatch
+import threading
+import time
+from dataclasses import dataclass, field
+from typing import Any, Callable, Dict, List, Tuple
+
+
+@dataclass(frozen=True)
+class Event:
+    """Something that happened on the device."""
+    name: str                                   # dotted, e.g. "battery.low"
+    data: Dict[str, Any] = field(default_factory=dict)
+    timestamp: float = field(default_factory=time.time)     # seconds since the epoch
+
+
+Handler = Callable[[Event], None]
+
+
+class EventBus:
+    """Delivers published events to the handlers subscribed to their names."""
+
+    def __init__(self):
+        self._lock = threading.Lock()
+        self._subscriptions: List[Tuple[str, Handler]] = []
+
+    def subscribe(self, pattern: str, handler: Handler) -> None:
+        """
+        Call handler with every event whose name matches pattern.
+
+        Args:
+            pattern: Event name, or a glob such as "button.*" or "*"
+            handler: Called on the publishing thread; it should return quickly
+        """
+        with self._lock:
+            self._subscriptions.append((pattern, handler))
+
+    def unsubscribe(self, pattern: str, handler: Handler) -> None:
+        """Stop calling a handler subscribed with this pattern."""
+        with self._lock:
+            self._subscriptions.remove((pattern, handler))
+
+    def publish(self, name: str, **data: Any) -> Event:
+        """
+        Publish an event to the matching handlers, in subscription order.
+
+        A failing handler is reported and does not keep the event from the
+        others.
+
+        Returns:
+            The published event
+        """
+        event = Event(name, data)
+        with self._lock:
+            handlers = [handler for pattern, handler in self._subscriptions if fnmatch.fnmatchcase(name, pattern)]
+        for handler in handlers:
+            try:
+                handler(event)
+            except Exception as e:
+                print(f"Warning: Handler for {name} failed: {e}")
+        return event
+
+
+_default_bus = EventBus()
+
+
+def get_bus() -> EventBus:
+    """
+    The process-wide bus that the SDK's event sources publish to by default.
+    Its history is recorded if DISTILLER_EVENT_HISTORY is set (see history).
+    """
+    from .history import get_event_history
+    get_event_history()
+    return _default_bus
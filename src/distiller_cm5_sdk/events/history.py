@@ -0,0 +1,261 @@
+#!/usr/bin/env python3
+"""
+Event history for CM5 SDK.
+
+Keeps the most recent events of a bus in a ring buffer, so what happened
+shortly before an intermittent problem can be looked up afterwards:
+
+    history = enable_event_history()
+    presses = history.query("button.*", since=time.time() - 60)
+
+Given a file, the history is also kept there as JSON lines, rotated to a
+single ".1" backup at a size limit like the audit log. Other processes, and
+the diagnostics command line, read it with query_event_history(). The logs
+retention policy can turn the file off and expire old entries (see
+diagnostics.retention); the ring buffer is kept either way.
+
+Setting DISTILLER_EVENT_HISTORY to a file path records the history of
+get_bus() in every SDK process that uses it.
+"""
+
+import fnmatch
+import json
+import os
+import threading
+from collections import deque
+from typing import List, Optional
+
+from .bus import Event, EventBus
+
+EVENT_HISTORY_ENV_VAR = "DISTILLER_EVENT_HISTORY"
+EVENT_HISTORY_PATH = "/var/log/distiller-cm5-sdk/events.jsonl"
+DEFAULT_CAPACITY = 1000
+DEFAULT_MAX_BYTES = 512 * 1024
+
+
+def _to_line(event: Event) -> str:
+    return json.dumps({"event": event.name, "timestamp": event.timestamp, "data": event.data}, default=str) + "\n"
+
+
+def _from_line(line: str) -> Event:
+    data = json.loads(line)
+    return Event(data["event"], data.get("data") or {}, float(data["timestamp"]))
+
+
+def _read_file(path: str) -> List[Event]:
+    """Events of a history file and its backup, oldest first."""
+    events = []
+    for name in (path + ".1", path):
+        try:
+            with open(name, "r", encoding="utf-8") as f:
+                lines = f.readlines()
+        except OSError:
+            continue
+        for line in lines:
+            try:
+                events.append(_from_line(line))
+            except (ValueError, KeyError, TypeError):
+                continue  # partial line from an interrupted write
+    return events
+
+
+def _select(events: List[Event], pattern: str, since: Optional[float], until: Optional[float],
+            limit: Optional[int]) -> List[Event]:
+    matching = [event for event in events
+                if fnmatch.fnmatchcase(event.name, pattern)
+                and (since is None or event.timestamp >= since)
+                and (until is None or event.timestamp < until)]
+    if limit is not None:
+        matching = matching[-limit:] if limit > 0 else []
+    return matching
+
+
+class EventHistory:
+    """The most recent events of a bus, in memory and optionally in a file."""
+
+    def __init__(self, capacity: int = DEFAULT_CAPACITY, path: Optional[str] = None,
+                 max_bytes: int = DEFAULT_MAX_BYTES):
+        """
+        Args:
+            capacity: Events kept in memory; the oldest are dropped first
+            path: JSON lines file to keep the history in too, or None; the
+                  newest events already in it are loaded
+            max_bytes: Size at which the file is rotated to path + ".1"
+        """
+        self.capacity = capacity
+        self.path = path
+        self.max_bytes = max_bytes
+        self._events: deque = deque(_read_file(path)[-capacity:] if path else (), maxlen=capacity)
+        self._lock = threading.Lock()
+        self._bus: Optional[EventBus] = None
+        self._warned = False
+
+    def attach(self, bus: EventBus) -> None:
+        """Record every event published on bus from now on."""
+        self.detach()
+        bus.subscribe("*", self.record)
+        self._bus = bus
+
+    def detach(self) -> None:
+        """Stop recording the bus given to attach()."""
+        if self._bus is not None:
+            self._bus.unsubscribe("*", self.record)
+            self._bus = None
+
+    def record(self, event: Event) -> None:
+        """Add an event; a file that cannot be written prints one warning."""
+        with self._lock:
+            self._events.append(event)
+            if self.path is not None:
+                self._write(_to_line(event))
+
+    def _write(self, line: str) -> None:
+        from ..diagnostics.retention import DataCategory, get_retention_policy
+        policy = get_retention_policy(DataCategory.LOGS)
+        if not policy.persist:
+            return
+        max_bytes = self.max_bytes if policy.max_bytes is None else min(self.max_bytes, policy.max_bytes // 2)
+        try:
+            directory = os.path.dirname(self.path)
+            if directory:
+                os.makedirs(directory, exist_ok=True)
+            if os.path.exists(self.path) and os.path.getsize(self.path) + len(line) > max_bytes:
+                os.replace(self.path, self.path + ".1")
+            fd = os.open(self.path, os.O_WRONLY | os.O_APPEND | os.O_CREAT, 0o640)
+            try:
+                os.write(fd, line.encode("utf-8"))
+            finally:
+                os.close(fd)
+        except OSError as e:
+            if not self._warned:
+                print(f"Warning: Failed to write event history {self.path}: {e}")
+                self._warned = True
+
+    def query(self, pattern: str = "*", since: Optional[float] = None, until: Optional[float] = None,
+              limit: Optional[int] = None) -> List[Event]:
+        """
+        Recorded events, oldest first.
+
+        Args:
+            pattern: Event name, or a glob such as "button.*"
+            since: Only events at or after this epoch time
+            until: Only events before this epoch time
+            limit: Return at most this many of the newest matching events
+        """
+        with self._lock:
+            events = list(self._events)
+        return _select(events, pattern, since, until, limit)
+
+    def expire(self, before: float) -> int:
+        """
+        Drop the events older than an epoch time, from memory and the file.
+
+        Returns:
+            The number of events dropped from the file, or from memory without one
+        """
+        with self._lock:
+            kept = [event for event in self._events if event.timestamp >= before]
+            dropped = len(self._events) - len(kept)
+            self._events = deque(kept, maxlen=self.capacity)
+            if self.path is None:
+                return dropped
+            dropped = 0
+            for path in (self.path + ".1", self.path):
+                try:
+                    with open(path, "r", encoding="utf-8") as f:
+                        lines = f.readlines()
+                except OSError:
+                    continue
+                kept_lines = []
+                for line in lines:
+                    try:
+                        if _from_line(line).timestamp >= before:
+                            kept_lines.append(line)
+                    except (ValueError, KeyError, TypeError):
+                        continue
+                if len(kept_lines) == len(lines):
+                    continue
+                dropped += len(lines) - len(kept_lines)
+                try:
+                    if kept_lines or path == self.path:
+                        tmp_path = path + ".tmp"
+                        with open(tmp_path, "w", encoding="utf-8") as f:
+                            f.writelines(kept_lines)
+                        os.chmod(tmp_path, 0o640)
+                        os.replace(tmp_path, path)
+                    else:
+                        os.remove(path)
+                except OSError as e:
+                    print(f"Warning: Failed to expire event history {path}: {e}")
+        return dropped
+
+
+_history: Optional[EventHistory] = None
+_env_checked = False
+
+
+def enable_event_history(path: Optional[str] = None, capacity: int = DEFAULT_CAPACITY,
+                         max_bytes: int = DEFAULT_MAX_BYTES, bus: Optional[EventBus] = None) -> EventHistory:
+    """
+    Start recording the events of a bus in this process.
+
+    Args:
+        path: File to keep the history in too, or None for memory only
+        capacity: Events kept in memory
+        max_bytes: Rotation size of the file
+        bus: Bus to record; defaults to get_bus()
+
+    Returns:
+        The active EventHistory
+    """
+    global _history, _env_checked
+    from .bus import get_bus
+    disable_event_history()
+    _history = EventHistory(capacity, path, max_bytes)
+    _env_checked = True
+    _history.attach(bus if bus is not None else get_bus())
+    return _history
+
+
+def disable_event_history() -> None:
+    """Stop recording events in this process."""
+    global _history, _env_checked
+    if _history is not None:
+        _history.detach()
+    _history = None
+    _env_checked = True
+
+
+def get_event_history() -> Optional[EventHistory]:
+    """Get the active EventHistory, enabling it from DISTILLER_EVENT_HISTORY on first use."""
+    global _env_checked
+    if not _env_checked:
+        _env_checked = True
+        path = os.environ.get(EVENT_HISTORY_ENV_VAR)
+        if path:
+            enable_event_history(path)
+    return _history
+
+
+def query_event_history(pattern: str = "*", since: Optional[float] = None, until: Optional[float] = None,
+                        limit: Optional[int] = None, path: Optional[str] = None) -> List[Event]:
+    """
+    Query the event history, e.g. that of another process.
+
+    Args:
+        pattern: Event name, or a glob such as "button.*"
+        since: Only events at or after this epoch time
+        until: Only events before this epoch time
+        limit: Return at most this many of the newest matching events
+        path: History file to read. If None, the history enabled in this
+              process, or else the file DISTILLER_EVENT_HISTORY names, or
+              EVENT_HISTORY_PATH.
+
+    Returns:
+        Matching events, oldest first
+    """
+    if path is None:
+        if _history is not None:
+            return _history.query(pattern, since, until, limit)
+        path = os.environ.get(EVENT_HISTORY_ENV_VAR) or EVENT_HISTORY_PATH
+    return _select(_read_file(path), pattern, since, until, limit)
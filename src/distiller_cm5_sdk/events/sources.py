@@ -0,0 +1,183 @@
+#!/usr/bin/env python3
+"""
+Event sources for CM5 SDK.
+
+Adapters that publish the SDK's device events on an EventBus:
+
+    Event name                     Data                          Source
+    button.single_press            button                        publish_buttons()
+    button.double_press            button                        publish_buttons()
+    button.long_press              button                        publish_buttons()
+    power.<state>                  previous, reason              publish_power_states()
+    battery.low / battery.ok       percent                       BatteryMonitor
+    focus.granted / focus.revoked  resource, owner, by           publish_focus()
+    privacy.mic_muted / _unmuted   (none)                        publish_mic_mute()
+    touch.tap / touch.long_press   x, y                          publish_touch()
+    touch.swipe                    direction, x, y, end_x, end_y publish_touch()
+    voice.wake_word                word                          the application's wake-word detector
+
+The SDK has no wake-word detector of its own; applications running one
+publish WAKE_WORD themselves so the same consumers can react to it.
+"""
+
+import threading
+from typing import Callable, Optional
+
+from ..smarthome.endpoints import PressType
+from .bus import EventBus, get_bus
+
+
+BUTTON_EVENTS = {
+    PressType.SINGLE: "button.single_press",
+    PressType.DOUBLE: "button.double_press",
+    PressType.LONG: "button.long_press",
+}
+POWER_EVENT_PREFIX = "power."
+BATTERY_LOW = "battery.low"
+BATTERY_OK = "battery.ok"
+FOCUS_GRANTED = "focus.granted"
+FOCUS_REVOKED = "focus.revoked"
+MIC_MUTED = "privacy.mic_muted"
+MIC_UNMUTED = "privacy.mic_unmuted"
+WAKE_WORD = "voice.wake_word"
+TOUCH_TAP = "touch.tap"
+TOUCH_LONG_PRESS = "touch.long_press"
+TOUCH_SWIPE = "touch.swipe"
+
+
+def publish_buttons(bridge, bus: Optional[EventBus] = None) -> None:
+    """
+    Publish the presses of a smarthome DeviceBridge's buttons.
+
+    Args:
+        bridge: DeviceBridge whose buttons to follow; start() it to forward presses
+        bus: Bus to publish to; defaults to get_bus()
+    """
+    bus = bus or get_bus()
+    for name, button in bridge.buttons.items():
+        button.add_listener(lambda press, name=name: bus.publish(BUTTON_EVENTS[press], button=name))
+
+
+def publish_power_states(machine, bus: Optional[EventBus] = None) -> None:
+    """
+    Publish the transitions of a PowerStateMachine as power.<state>, e.g. power.panel_sleep.
+
+    Args:
+        machine: device.power_state.PowerStateMachine to follow
+        bus: Bus to publish to; defaults to get_bus()
+    """
+    bus = bus or get_bus()
+    machine.add_listener(lambda transition: bus.publish(
+        POWER_EVENT_PREFIX + transition.state.value, previous=transition.previous.value,
+        reason=transition.reason))
+
+
+def publish_focus(manager, bus: Optional[EventBus] = None) -> None:
+    """
+    Publish the grants and revocations of a FocusManager as focus.granted and focus.revoked.
+
+    Args:
+        manager: device.focus.FocusManager to follow
+        bus: Bus to publish to; defaults to get_bus()
+    """
+    bus = bus or get_bus()
+    manager.add_listener(lambda change: bus.publish(
+        FOCUS_GRANTED if change.granted else FOCUS_REVOKED, resource=change.resource.value,
+        owner=change.owner, by=change.by))
+
+
+def publish_mic_mute(monitor, bus: Optional[EventBus] = None) -> None:
+    """
+    Publish the changes of the microphone mute switch as privacy.mic_muted and privacy.mic_unmuted.
+
+    Args:
+        monitor: device.privacy.MuteMonitor to follow; start() it to check the switch
+        bus: Bus to publish to; defaults to get_bus()
+    """
+    bus = bus or get_bus()
+    monitor.add_listener(lambda muted: bus.publish(MIC_MUTED if muted else MIC_UNMUTED))
+
+
+def publish_touch(panel, bus: Optional[EventBus] = None) -> None:
+    """
+    Publish the gestures of a touch panel as touch.tap, touch.long_press and touch.swipe.
+
+    Args:
+        panel: hardware.touch.TouchPanel to follow; start() it to read the controller
+        bus: Bus to publish to; defaults to get_bus()
+    """
+    bus = bus or get_bus()
+
+    def on_gesture(gesture):
+        if gesture.direction is not None:
+            bus.publish(TOUCH_SWIPE, direction=gesture.direction.value, x=gesture.x, y=gesture.y,
+                        end_x=gesture.end_x, end_y=gesture.end_y)
+        else:
+            bus.publish(TOUCH_TAP if gesture.type.value == "tap" else TOUCH_LONG_PRESS, x=gesture.x, y=gesture.y)
+
+    panel.add_listener(on_gesture)
+
+
+class BatteryMonitor:
+    """Publishes battery.low once the charge drops below a threshold, and battery.ok once it recovers."""
+
+    def __init__(self, read_percent: Callable[[], Optional[float]], bus: Optional[EventBus] = None,
+                 low_percent: float = 15.0, ok_percent: float = 20.0, interval_s: float = 60.0):
+        """
+        Args:
+            read_percent: Returns the charge in percent, or None when unknown
+            bus: Bus to publish to; defaults to get_bus()
+            low_percent: Charge below which battery.low is published
+            ok_percent: Charge from which battery.ok is published again; above
+                        low_percent so a charge hovering at the threshold does
+                        not publish repeatedly
+            interval_s: Seconds between readings while started
+        """
+        if ok_percent < low_percent:
+            raise ValueError("ok_percent must not be below low_percent")
+        self.read_percent = read_percent
+        self.bus = bus or get_bus()
+        self.low_percent = low_percent
+        self.ok_percent = ok_percent
+        self.interval_s = interval_s
+        self.low = False
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    def check(self) -> Optional[float]:
+        """Read the charge once and publish a change of state. Returns the charge."""
+        percent = self.read_percent()
+        if percent is None:
+            return None
+        if not self.low and percent < self.low_percent:
+            self.low = True
+            self.bus.publish(BATTERY_LOW, percent=percent)
+        elif self.low and percent >= self.ok_percent:
+            self.low = False
+            self.bus.publish(BATTERY_OK, percent=percent)
+        return percent
+
+    def start(self) -> None:
+        """Check the battery every interval_s seconds on a background thread."""
+        if self._thread is not None:
+            return
+        self._stop.clear()
+
+        def loop():
+            while True:
+                try:
+                    self.check()
+                except Exception as e:
+                    print(f"Warning: Battery reading failed: {e}")
+                if self._stop.wait(self.interval_s):
+                    return
+
+        self._thread = threading.Thread(target=loop, name="battery-monitor", daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop checking."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join()
+            self._thread = None
@@ -0,0 +1,270 @@
+#!/usr/bin/env python3
+"""
+Webhooks for CM5 SDK.
+
+Posts events from the EventBus as JSON to URLs configured in
+/opt/distiller-cm5-sdk/webhooks.conf, one [section] per webhook, so that
+automation services (Zapier, IFTTT, n8n, Home Assistant webhooks, ...) can
+react to the device without any code on it:
+
+    [doorbell]
+    events = button.long_press
+    url = https://hooks.example.com/doorbell
+    header.Authorization = Bearer 0123456789
+    retries = 3
+
+    [battery]
+    events = battery.low, battery.ok
+    url = https://ntfy.example.com/distiller
+
+Each matching event is sent as
+
+    {"event": "button.long_press", "timestamp": 1767225600.0, "device": "distiller",
+     "data": {"button": "select"}}
+
+Failed deliveries (network errors, timeouts, HTTP 429 and 5xx) are retried
+with exponential backoff; other HTTP errors are not, since they will fail
+again the same way. Each webhook has its own worker thread and a bounded
+queue, so a slow or failing endpoint delays only its own events; when its
+queue is full, the oldest event is dropped with a warning.
+"""
+
+import configparser
+import fnmatch
+import json
+import os
+import socket
+import threading
+import time
+import urllib.error
+import urllib.request
+from collections import deque
+from dataclasses import dataclass, field
+from typing import Callable, Deque, Dict, List, Optional, Tuple
+
+from .bus import Event, EventBus, get_bus
+
+
+WEBHOOKS_PATH = "/opt/distiller-cm5-sdk/webhooks.conf"
+WEBHOOKS_PATH_ENV_VAR = "DISTILLER_WEBHOOKS_CONFIG"
+HEADER_PREFIX = "header."
+USER_AGENT = "distiller-cm5-sdk-webhooks"
+QUEUE_SIZE = 100        # undelivered events kept per webhook
+
+
+class WebhookError(Exception):
+    """Custom exception for webhook configuration errors."""
+    pass
+
+
+@dataclass
+class Webhook:
+    """Where and when to post events."""
+    name: str
+    url: str
+    events: Tuple[str, ...] = ("*",)          # event names or glob patterns
+    headers: Dict[str, str] = field(default_factory=dict)
+    retries: int = 3                          # attempts after the first
+    retry_delay_s: float = 2.0                # doubled after each attempt
+    timeout_s: float = 10.0
+
+    def matches(self, event_name: str) -> bool:
+        return any(fnmatch.fnmatchcase(event_name, pattern) for pattern in self.events)
+
+    def validate(self) -> None:
+        """
+        Raises:
+            WebhookError: If the URL is not http(s) or a number is out of range
+        """
+        if not self.url.startswith(("http://", "https://")):
+            raise WebhookError(f"Webhook '{self.name}': url must start with http:// or https://")
+        if not self.events:
+            raise WebhookError(f"Webhook '{self.name}': no events")
+        if self.retries < 0 or self.retry_delay_s < 0 or self.timeout_s <= 0:
+            raise WebhookError(f"Webhook '{self.name}': retries and retry_delay must not be negative, "
+                               f"timeout must be positive")
+
+
+def load_webhooks(path: Optional[str] = None) -> List[Webhook]:
+    """
+    Read webhooks from a config file.
+
+    Args:
+        path: Config file path. If None, uses DISTILLER_WEBHOOKS_CONFIG, defaulting
+              to /opt/distiller-cm5-sdk/webhooks.conf.
+
+    Returns:
+        The configured webhooks; none if the file does not exist
+
+    Raises:
+        WebhookError: If the file is malformed or a setting is invalid
+    """
+    if path is None:
+        path = os.environ.get(WEBHOOKS_PATH_ENV_VAR, WEBHOOKS_PATH)
+    parser = configparser.ConfigParser(interpolation=None)
+    parser.optionxform = str            # keep the case of header names
+    try:
+        if not parser.read(path):
+            return []
+    except configparser.Error as e:
+        raise WebhookError(f"{path}: {e}")
+
+    webhooks = []
+    for name in parser.sections():
+        section = parser[name]
+        if "url" not in section:
+            raise WebhookError(f"{path}: webhook '{name}' has no url")
+        try:
+            webhook = Webhook(
+                name=name,
+                url=section["url"],
+                events=tuple(e.strip() for e in section.get("events", "*").split(",") if e.strip()),
+                headers={key[len(HEADER_PREFIX):]: value for key, value in section.items()
+                         if key.startswith(HEADER_PREFIX)},
+                retries=int(section.get("retries", "3")),
+                retry_delay_s=float(section.get("retry_delay", "2")),
+                timeout_s=float(section.get("timeout", "10")),
+            )
+        except ValueError as e:
+            raise WebhookError(f"{path}: webhook '{name}': {e}")
+        webhook.validate()
+        webhooks.append(webhook)
+    return webhooks
+
+
+def _post(url: str, body: bytes, headers: Dict[str, str], timeout_s: float) -> int:
+    request = urllib.request.Request(url, data=body, headers=headers, method="POST")
+    try:
+        with urllib.request.urlopen(request, timeout=timeout_s) as response:
+            return response.status
+    except urllib.error.HTTPError as e:
+        return e.code
+
+
+def _retryable(status: int) -> bool:
+    return status == 429 or status >= 500
+
+
+class _Worker:
+    """Delivers one webhook's events in order, on its own thread."""
+
+    def __init__(self, webhook: Webhook, deliver: Callable[[Webhook, Event], bool], queue_size: int):
+        self.webhook = webhook
+        self._deliver = deliver
+        self._queue_size = queue_size
+        self._pending: Deque[Event] = deque()
+        self._condition = threading.Condition()
+        self._stopping = False
+        self.thread = threading.Thread(target=self._run, name=f"webhook-{webhook.name}", daemon=True)
+
+    def put(self, event: Event) -> None:
+        with self._condition:
+            if len(self._pending) >= self._queue_size:
+                dropped = self._pending.popleft()
+                print(f"Warning: Webhook '{self.webhook.name}' is {self._queue_size} events behind; "
+                      f"dropped the oldest, {dropped.name}")
+            self._pending.append(event)
+            self._condition.notify()
+
+    def stop(self) -> None:
+        with self._condition:
+            self._stopping = True
+            self._condition.notify()
+
+    def _run(self) -> None:
+        while True:
+            with self._condition:
+                while not self._pending and not self._stopping:
+                    self._condition.wait()
+                if not self._pending:
+                    return
+                event = self._pending.popleft()
+            self._deliver(self.webhook, event)
+
+
+class WebhookEngine:
+    """Posts the bus events that match each webhook, on a background thread per webhook."""
+
+    def __init__(self, webhooks: List[Webhook], bus: Optional[EventBus] = None,
+                 post: Callable[[str, bytes, Dict[str, str], float], int] = _post,
+                 sleep: Callable[[float], None] = time.sleep, queue_size: int = QUEUE_SIZE):
+        """
+        Args:
+            webhooks: Webhooks to serve, e.g. from load_webhooks()
+            bus: Bus to take events from; defaults to get_bus()
+            post: Sends one request and returns the HTTP status, replaceable for testing
+            sleep: Waits between retries, replaceable for testing
+            queue_size: Undelivered events kept per webhook; beyond it the oldest is dropped
+
+        Raises:
+            WebhookError: If queue_size is not positive
+        """
+        if queue_size < 1:
+            raise WebhookError("queue_size must be positive")
+        self.webhooks = list(webhooks)
+        self.bus = bus or get_bus()
+        self.device = socket.gethostname()
+        self.queue_size = queue_size
+        self._post = post
+        self._sleep = sleep
+        self._workers: List[_Worker] = []
+        self._running = False
+
+    def payload(self, event: Event) -> bytes:
+        """JSON body posted for an event."""
+        return json.dumps({"event": event.name, "timestamp": event.timestamp, "device": self.device,
+                           "data": event.data}, default=str).encode()
+
+    def deliver(self, webhook: Webhook, event: Event) -> bool:
+        """
+        Post an event to one webhook now, retrying failed attempts.
+
+        Returns:
+            True once the endpoint accepted it with a 2xx status
+        """
+        headers = {"Content-Type": "application/json", "User-Agent": USER_AGENT, **webhook.headers}
+        body = self.payload(event)
+        error = ""
+        for attempt in range(webhook.retries + 1):
+            if attempt:
+                self._sleep(webhook.retry_delay_s * 2 ** (attempt - 1))
+            try:
+                status = self._post(webhook.url, body, headers, webhook.timeout_s)
+            except OSError as e:            # connection errors and timeouts
+                error = str(e)
+                continue
+            if 200 <= status < 300:
+                return True
+            error = f"HTTP {status}"
+            if not _retryable(status):
+                break
+        print(f"Warning: Webhook '{webhook.name}' failed for {event.name}: {error}")
+        return False
+
+    def _on_event(self, event: Event) -> None:
+        for worker in self._workers:
+            if worker.webhook.matches(event.name):
+                worker.put(event)
+
+    def start(self) -> None:
+        """Subscribe to the bus and deliver matching events in the background, in order per webhook."""
+        if self._running:
+            return
+        self._running = True
+        self._workers = [_Worker(webhook, self.deliver, self.queue_size) for webhook in self.webhooks]
+        for worker in self._workers:
+            worker.thread.start()
+        self.bus.subscribe("*", self._on_event)
+
+    def stop(self, timeout: Optional[float] = None) -> None:
+        """Unsubscribe, then finish the deliveries already queued, waiting at most timeout seconds in all."""
+        if not self._running:
+            return
+        self._running = False
+        self.bus.unsubscribe("*", self._on_event)
+        deadline = None if timeout is None else time.monotonic() + timeout
+        for worker in self._workers:
+            worker.stop()
+        for worker in self._workers:
+            worker.thread.join(None if deadline is None else max(deadline - time.monotonic(), 0))
+        self._workers = []
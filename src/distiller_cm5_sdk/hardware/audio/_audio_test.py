@@ -1,5 +1,11 @@
-from audio import Audio
+import sys
 import time
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.audio.audio import Audio
 
 print("Testing static methods:")
 print(f"Current mic gain: {Audio.get_mic_gain_static()}")
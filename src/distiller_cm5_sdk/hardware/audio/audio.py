@@ -14,12 +14,21 @@ import numpy as np
 import platform
 from typing import Optional, Union, Callable, Tuple, List, BinaryIO
 
+from ...diagnostics.audit import AuditCategory, audit
+from ...device.privacy import capture_allowed, is_mic_muted
+from ...diagnostics.retention import DataCategory, get_retention_policy, purge
+
 
 class AudioError(Exception):
     """Custom exception for Audio-related errors."""
     pass
 
 
+class MicMutedError(AudioError):
+    """Custom exception for recordings refused while the microphone mute switch is on."""
+    pass
+
+
 class Audio:
     """
     Audio class for interacting with the CM5 audio system.
@@ -171,9 +180,9 @@ class Audio:
         """
         if not self._has_hw_controls:
             self._mic_gain = gain
-            return
-
-        self._mic_gain = Audio.set_mic_gain_static(gain)
+        else:
+            self._mic_gain = Audio.set_mic_gain_static(gain)
+        audit(AuditCategory.CONFIG, "mic_gain", str(self._mic_gain))
     
     @staticmethod
     def set_mic_gain_static(gain: int) -> int:
@@ -272,9 +281,9 @@ class Audio:
         """
         if not self._has_hw_controls:
             self._speaker_volume = volume
-            return
-
-        self._speaker_volume = Audio.set_speaker_volume_static(volume)
+        else:
+            self._speaker_volume = Audio.set_speaker_volume_static(volume)
+        audit(AuditCategory.CONFIG, "speaker_volume", str(self._speaker_volume))
     
     @staticmethod
     def set_speaker_volume_static(volume: int) -> int:
@@ -373,7 +382,8 @@ class Audio:
             str: Path to the recorded file
             
         Raises:
-            AudioError: If recording fails
+            MicMutedError: If the microphone is muted
+            AudioError: If recording fails, or the recordings retention policy keeps no files
         """
         if self._is_recording:
             raise AudioError("Recording already in progress")
@@ -381,6 +391,12 @@ class Audio:
         if duration is not None and not isinstance(duration, (int, float)):
             raise AudioError(f"Invalid duration: {duration}. Must be a number or None.")
             
+        if not get_retention_policy(DataCategory.RECORDINGS).persist:
+            raise AudioError("Recordings are not kept by the retention policy; use stream_record()")
+            
+        if not capture_allowed("audio"):
+            raise MicMutedError("Microphone is muted by the privacy switch")
+            
         # Ensure the directory exists
         os.makedirs(os.path.dirname(os.path.abspath(filepath)), exist_ok=True)
         
@@ -409,23 +425,35 @@ class Audio:
                     # For manual stop, run in a thread
                     def record_thread():
                         proc = subprocess.Popen(cmd)
-                        # Wait for stop event
+                        # Wait for stop event, or the mute switch
                         while not self._stop_recording.is_set():
-                            if proc.poll() is not None:
+                            if proc.poll() is not None or is_mic_muted():
                                 break
                             time.sleep(0.1)
                         # Kill if still running
                         if proc.poll() is None:
                             proc.terminate()
                             proc.wait()
+                        purge(DataCategory.RECORDINGS)
                     
                     self._record_thread = threading.Thread(target=record_thread)
                     self._record_thread.start()
                     return filepath
                 else:
-                    # For fixed duration, run synchronously
-                    subprocess.run(cmd, check=True)
+                    # For fixed duration, run synchronously; muting cuts it short
+                    proc = subprocess.Popen(cmd)
+                    while proc.poll() is None:
+                        if is_mic_muted():
+                            print("Warning: Microphone muted, recording stopped early")
+                            proc.terminate()
+                            proc.wait()
+                            break
+                        time.sleep(0.1)
+                    else:
+                        if proc.returncode != 0:
+                            raise subprocess.CalledProcessError(proc.returncode, cmd)
                     self._is_recording = False
+                    purge(DataCategory.RECORDINGS)
                     return filepath
                     
         except Exception as e:
@@ -464,6 +492,7 @@ class Audio:
             threading.Thread: The recording thread
             
         Raises:
+            MicMutedError: If the microphone is muted
             AudioError: If recording fails
         """
         if self._is_recording:
@@ -472,6 +501,9 @@ class Audio:
         if not callable(callback):
             raise AudioError("Callback must be a callable function")
             
+        if not capture_allowed("audio"):
+            raise MicMutedError("Microphone is muted by the privacy switch")
+            
         # Use provided stop event or create one
         self._stop_recording = stop_event if stop_event is not None else threading.Event()
         
@@ -494,6 +526,9 @@ class Audio:
                     audio_data = process.stdout.read(buffer_size)
                     if not audio_data:
                         break
+                    if is_mic_muted():
+                        print("Warning: Microphone muted, stream recording stopped")
+                        break
                     # Call the callback with the audio data
                     callback(audio_data)
                     
@@ -564,6 +599,8 @@ class Audio:
         except Exception as e:
             self._is_playing = False
             raise AudioError(f"Playback failed: {str(e)}")
+        
+        audit(AuditCategory.AUDIO, "play", filepath, device=self.output_device)
     
     def stop_playback(self) -> None:
         """
@@ -658,6 +695,9 @@ class Audio:
         except Exception as e:
             self._is_playing = False
             raise AudioError(f"Stream playback failed: {str(e)}")
+        
+        audit(AuditCategory.AUDIO, "play", "stream", device=self.output_device,
+              format=fmt, sample_rate=rate, channels=chans)
     
     def is_recording(self) -> bool:
         """
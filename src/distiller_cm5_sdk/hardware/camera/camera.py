@@ -13,6 +13,8 @@ import numpy as np
 import tempfile
 from typing import Optional, Tuple, Dict, Union, List, Callable
 
+from ...diagnostics.retention import DataCategory, get_retention_policy, purge
+
 
 class CameraError(Exception):
     """Custom exception for Camera-related errors."""
@@ -312,10 +314,13 @@ class Camera:
             np.ndarray: The captured image
             
         Raises:
-            CameraError: If image cannot be captured
+            CameraError: If image cannot be captured, or saving is refused by the content retention policy
         """
         # If filepath is provided, capture directly to that file using libcamera-still
         if filepath:
+            if not get_retention_policy(DataCategory.CONTENT).persist:
+                raise CameraError("Captured images are not kept by the content retention policy")
+            
             cmd = [
                 "libcamera-still",
                 "-n",                           # No preview
@@ -346,6 +351,7 @@ class Camera:
             elif self.format == 'gray':
                 frame = cv2.cvtColor(frame, cv2.COLOR_BGR2GRAY)
             
+            purge(DataCategory.CONTENT)
             return frame
         else:
             # If no filepath is provided, use get_frame
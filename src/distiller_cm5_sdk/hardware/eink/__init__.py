@@ -1 +1,12 @@
-from .display import Display, DisplayError, DisplayMode, display_png, clear_display, get_display_info
+from .display import Display, DisplayError, DisplayErrorCode, DisplayEvent, DisplayEventType, DisplayTimeoutError, DisplayMode, DisplayStatus, LutKind, LutPreset, PixelFormat, ProcessingOptions, RamPlane, RotationMode, ScreenSnapshot, display_png, tag_updates, show_file, clear_display, get_display_info
+from .client import DisplayClient
+from .config import DisplayConfig, DisplayConfigHandle, SpiConfig, list_displays, load_config, save_config
+from .group import DisplayGroup
+from .panels import PanelSpec, PanelType, load_panel_descriptor
+from .graphics import Color, Framebuffer, measure_text
+from .imaging import DitherMode, ImageError, ImageOptions, ScalingFilter, ScalingMethod, process_image
+from .idle import IdleAction
+from .layout import Layout, LayoutError, load_layout, render_layout
+from .markdown import markdown_height, render_markdown
+from .patterns import PatternError, PatternSpec
+from .textstream import StreamingTextRenderer
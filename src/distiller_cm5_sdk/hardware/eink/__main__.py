@@ -0,0 +1,372 @@
+#!/usr/bin/env python3
+"""
+E-ink display command line: distiller-eink <command> [options]
+(or python3 -m distiller_cm5_sdk.hardware.eink)
+
+For shell scripts and systemd units:
+
+    distiller-eink show photo.jpg --scale crop --dither floyd-steinberg
+    distiller-eink text "Backup finished" --size 3
+    distiller-eink qr "https://example.com/setup"
+    distiller-eink layout dashboard.json --data values.json
+    distiller-eink layout status.md --data values.json
+    distiller-eink clear && distiller-eink sleep
+    distiller-eink info --json
+    distiller-eink serve --bind 0.0.0.0 --token s3cret
+    distiller-eink mqtt tcp://broker.local --topic "device/+/display"
+    distiller-eink flipbook radar.mp4 radar.flip --fps 2 && distiller-eink play radar.flip --loop
+    distiller-eink mirror --console 1 --partial
+"""
+
+import argparse
+import json
+import math
+import os
+import signal
+import sys
+
+from ...device.lock import LockError, ScreenLock
+from .client import DisplayClient
+from .display import Display, DisplayError, DisplayMode
+from .config import MAX_SHARPEN, load_config
+from .flipbook import (
+    DEFAULT_FPS, DEFAULT_KEYFRAME_EVERY, DEFAULT_MAX_SECONDS, Flipbook, FlipbookError, FlipbookPlayer, convert_video,
+)
+from .font import FONT_HEIGHT
+from .graphics import Color, Framebuffer, wrap_text
+from .http_server import DEFAULT_PORT, DisplayHTTPServer
+from .imaging import (
+    DitherMode, ImageError, ImageOptions, ScalingFilter, ScalingMethod, default_image_options, process_image,
+)
+from .layout import LayoutError, load_layout
+from .mirror import FRAMEBUFFER_DEVICE, ConsoleSource, FramebufferSource, mirror_board
+from .mqtt import DisplayMQTTSubscriber
+from .panels import PanelType, get_panel
+from .qr import ERROR_CORRECTION_LEVELS, QRCodeError, draw_qr
+from .tiles import MIN_POLL_INTERVAL_S
+
+
+# Margin around text and QR codes, in pixels
+MARGIN = 2
+
+TOKEN_ENV_VAR = "DISTILLER_EINK_HTTP_TOKEN"
+MQTT_PASSWORD_ENV_VAR = "DISTILLER_MQTT_PASSWORD"
+
+
+def _mode(args) -> DisplayMode:
+    return DisplayMode.PARTIAL if args.partial else DisplayMode.FULL
+
+
+def _read_text(text: str) -> str:
+    return sys.stdin.read() if text == "-" else text
+
+
+def _show(display: Display, args) -> None:
+    source = sys.stdin.buffer.read() if args.image == "-" else args.image
+    profile = default_image_options()
+    threshold = profile.threshold if args.threshold is None else args.threshold
+    dither = profile.dither if args.dither is None else DitherMode(args.dither)
+    scaling_filter = profile.scaling_filter if args.filter is None else ScalingFilter(args.filter)
+    sharpen = profile.sharpen if args.sharpen is None else args.sharpen
+    options = ImageOptions(ScalingMethod(args.scale), dither, threshold, args.invert, profile.contrast,
+                           profile.parallel, scaling_filter, sharpen, Color[args.pad.upper()], args.background,
+                           args.rotate, args.deskew)
+    width, height = display.get_dimensions()
+    display.display_image(process_image(source, options, width, height).to_bytes(), _mode(args))
+
+
+def _text(display: Display, args) -> None:
+    width, height = display.get_dimensions()
+    fb = Framebuffer(width, height)
+    lines = wrap_text(_read_text(args.text), width - 2 * MARGIN, args.size).split("\n")
+    fitting = max(1, (height - 2 * MARGIN) // (FONT_HEIGHT * args.size))
+    if len(lines) > fitting:
+        print(f"Warning: Text does not fit; {len(lines) - fitting} lines cut off", file=sys.stderr)
+    fb.draw_text(MARGIN, MARGIN, "\n".join(lines[:fitting]), scale=args.size)
+    display.display_image(fb.to_bytes(), _mode(args))
+
+
+def _qr(display: Display, args) -> None:
+    width, height = display.get_dimensions()
+    fb = Framebuffer(width, height)
+    size = min(width, height) - 2 * MARGIN
+    draw_qr(fb, _read_text(args.text).strip(), (width - size) // 2, (height - size) // 2, size, args.ec)
+    display.display_image(fb.to_bytes(), _mode(args))
+
+
+def _layout(display: Display, args) -> None:
+    layout = load_layout(args.template)
+    data = {}
+    if args.data:
+        if args.data == "-":
+            text = sys.stdin.read()
+        else:
+            with open(args.data, "r") as f:
+                text = f.read()
+        try:
+            data = json.loads(text)
+        except ValueError as e:
+            raise LayoutError(f"{args.data}: invalid JSON: {e}")
+        if not isinstance(data, dict):
+            raise LayoutError(f"{args.data}: data must be a JSON object")
+    width, height = display.get_dimensions()
+    display.display_image(layout.render(data, width, height).to_bytes(), _mode(args))
+
+
+def _flipbook(args) -> int:
+    # Offline: the panel only sets the frame size and refresh budget, and is not opened
+    panel = get_panel(PanelType(args.panel)) if args.panel else load_config().panel_spec()
+    width, height = (panel.height, panel.width) if args.landscape else (panel.width, panel.height)
+    profile = default_image_options()
+    dither = DitherMode.ORDERED if args.dither is None else DitherMode(args.dither)
+    options = ImageOptions(ScalingMethod(args.scale), dither, profile.threshold, False, profile.contrast,
+                           profile.parallel, profile.scaling_filter, profile.sharpen)
+    book = convert_video(args.video, args.fps, width, height, panel, options, args.seconds, args.keyframe_every)
+    book.save(args.output)
+    keyframes = sum(frame.keyframe for frame in book.frames)
+    print(f"{args.output}: {len(book.frames)} frames ({keyframes} keyframes, {book.dropped} dropped), "
+          f"{book.duration_s:.1f} s at {width}x{height}")
+    return 0
+
+
+def _play(display: Display, args) -> None:
+    player = FlipbookPlayer(display, Flipbook.load(args.flipbook))
+    signal.signal(signal.SIGTERM, lambda signum, frame: player.stop())
+    try:
+        player.run(0 if args.loop else args.loops)
+    except KeyboardInterrupt:
+        pass
+
+
+def _mirror(display: Display, args) -> None:
+    interval = {} if args.interval is None else {"poll_interval_s": args.interval}
+    source = FramebufferSource(args.fb, **interval) if args.fb else ConsoleSource(args.console, **interval)
+    # A wrong device or missing permission fails now rather than showing a placeholder
+    with open(source.device, "rb"):
+        pass
+    if args.partial:
+        width, height = display.get_dimensions()
+        display.set_auto_threshold(width * height)
+    board = mirror_board(display, source)
+    signal.signal(signal.SIGTERM, lambda signum, frame: board.stop())
+    try:
+        board.run()
+    except KeyboardInterrupt:
+        pass
+
+
+def _info(args) -> int:
+    display = Display(backend=args.backend, auto_init=False)
+    try:
+        info = display.get_info()
+    finally:
+        display.close()
+    if args.json:
+        print(json.dumps(info, indent=2))
+    else:
+        for key, value in info.items():
+            print(f"{key}: {value}")
+    return 0
+
+
+def _screen_lock(args):
+    # Through the daemon, its own screen lock applies
+    return None if args.daemon else ScreenLock.from_file()
+
+
+def _serve(args) -> int:
+    token = args.token or os.environ.get(TOKEN_ENV_VAR) or None
+    if token is None and args.bind not in ("127.0.0.1", "localhost", "::1"):
+        print("Warning: Serving without a token; anyone on the network can draw on the display", file=sys.stderr)
+    screen_lock = _screen_lock(args)
+    display = DisplayClient() if args.daemon else Display(backend=args.backend)
+    try:
+        with DisplayHTTPServer(display, args.bind, args.port, token, screen_lock) as server:
+            print(f"Serving the display on http://{args.bind}:{server.server_address[1]}")
+            try:
+                server.serve_forever()
+            except KeyboardInterrupt:
+                pass
+    finally:
+        display.close()
+    return 0
+
+
+def _mqtt(args) -> int:
+    screen_lock = _screen_lock(args)
+    display = DisplayClient() if args.daemon else Display(backend=args.backend)
+    try:
+        subscriber = DisplayMQTTSubscriber(display, args.broker, args.topic, client_id=args.client_id,
+                                           username=args.username,
+                                           password=args.password or os.environ.get(MQTT_PASSWORD_ENV_VAR),
+                                           status_topic=args.status_topic, screen_lock=screen_lock)
+        signal.signal(signal.SIGTERM, lambda signum, frame: subscriber.stop())
+        subscriber.run()
+    except ValueError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    finally:
+        display.close()
+    return 0
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(prog="distiller-eink", description="Drive the e-ink display from the shell")
+    parser.add_argument("--backend", help="Display backend: native, python, mock or simulator "
+                                          "(default: eink.toml or DISTILLER_EINK_BACKEND)")
+    commands = parser.add_subparsers(dest="command", required=True)
+
+    def add_refresh_option(command):
+        command.add_argument("--partial", action="store_true", help="Partial refresh: faster, may leave ghosting")
+
+    show = commands.add_parser("show", help="Show an image file (- reads it from stdin)")
+    show.add_argument("image", help="Image file; PNG, or any format Pillow reads with the image extra")
+    show.add_argument("--scale", choices=[method.value for method in ScalingMethod],
+                      default=ScalingMethod.LETTERBOX.value, help="How the image is fitted (default: letterbox)")
+    show.add_argument("--dither", choices=[mode.value for mode in DitherMode],
+                      help="How grays become black and white (default: eink.toml dither, or floyd-steinberg)")
+    show.add_argument("--threshold", type=int, help="Gray level below which pixels are black, 0-255 "
+                                                    "(default: eink.toml threshold, or 128)")
+    show.add_argument("--invert", action="store_true", help="Swap black and white")
+    show.add_argument("--filter", choices=[method.value for method in ScalingFilter],
+                      help="How pixels are resampled when scaling (default: eink.toml filter, or lanczos3)")
+    show.add_argument("--pad", choices=["white", "black"], default="white",
+                      help="Color of the margins left by letterbox and none (default: white)")
+    show.add_argument("--background", help="Image tiled in those margins instead, e.g. a branded frame")
+    show.add_argument("--sharpen", type=float, help="Unsharp-mask amount after downscaling, e.g. 0.5 "
+                                                    "(default: eink.toml sharpen, or 0)")
+    show.add_argument("--rotate", type=float, default=0.0,
+                      help="Degrees counter-clockwise to turn the image first, any angle (default: 0)")
+    show.add_argument("--deskew", action="store_true",
+                      help="Straighten tilted text lines, e.g. in a photographed document")
+    add_refresh_option(show)
+
+    commands.add_parser("clear", help="Clear the display to white")
+    commands.add_parser("sleep", help="Put the panel into deep sleep; the image stays")
+
+    text = commands.add_parser("text", help="Show wrapped text (- reads it from stdin)")
+    text.add_argument("text")
+    text.add_argument("--size", type=int, default=2, help="Font magnification (default: 2)")
+    add_refresh_option(text)
+
+    qr = commands.add_parser("qr", help="Show a QR code (needs the qr extra; - reads the text from stdin)")
+    qr.add_argument("text")
+    qr.add_argument("--ec", choices=ERROR_CORRECTION_LEVELS, default="M", help="Error correction level (default: M)")
+    add_refresh_option(qr)
+
+    layout = commands.add_parser("layout", help="Show a JSON or TOML layout template, or a Markdown file, "
+                                                "filled in with data")
+    layout.add_argument("template")
+    layout.add_argument("--data", help="JSON object with the template's fields (- reads it from stdin)")
+    add_refresh_option(layout)
+
+    info = commands.add_parser("info", help="Show the panel's size, type and backend without touching it")
+    info.add_argument("--json", action="store_true", help="Print JSON")
+
+    serve = commands.add_parser("serve", help="Take images over HTTP: POST /display, GET /screenshot, GET /info")
+    serve.add_argument("--bind", default="127.0.0.1", help="Address to listen on (default: 127.0.0.1; "
+                                                            "0.0.0.0 for every interface)")
+    serve.add_argument("--port", type=int, default=DEFAULT_PORT, help=f"TCP port (default: {DEFAULT_PORT})")
+    serve.add_argument("--token", help=f"Bearer token required on every request (default: {TOKEN_ENV_VAR})")
+    serve.add_argument("--daemon", action="store_true", help="Draw through distiller-eink-daemon "
+                                                             "instead of opening the display")
+
+    mqtt = commands.add_parser("mqtt", help="Show images and text layouts published on MQTT topics")
+    mqtt.add_argument("broker", help="Broker URL: tcp://host[:port], or mqtts://host[:port] for TLS")
+    mqtt.add_argument("--topic", action="append", default=[],
+                      help="Topic filter, + and # allowed; repeat for more (default: distiller/<host name>/display)")
+    mqtt.add_argument("--username", help="Broker user")
+    mqtt.add_argument("--password", help=f"Broker password (default: {MQTT_PASSWORD_ENV_VAR})")
+    mqtt.add_argument("--client-id", help="MQTT client ID (default: distiller-eink-<host name>)")
+    mqtt.add_argument("--status-topic", help="Where online and the offline last will are published "
+                                             "(default: distiller/<host name>/eink/status)")
+    mqtt.add_argument("--daemon", action="store_true", help="Draw through distiller-eink-daemon "
+                                                            "instead of opening the display")
+
+    flipbook = commands.add_parser("flipbook", help="Convert a short video, or a directory of images, "
+                                                    "into a flipbook for play (videos need ffmpeg)")
+    flipbook.add_argument("video", help="Video file, or a directory of images played in name order")
+    flipbook.add_argument("output", help="Flipbook file to write, e.g. clip.flip")
+    flipbook.add_argument("--fps", type=float, default=DEFAULT_FPS,
+                          help=f"Frames per second sampled (default: {DEFAULT_FPS:g})")
+    flipbook.add_argument("--seconds", type=float, default=DEFAULT_MAX_SECONDS,
+                          help=f"Longest part of the video converted (default: {DEFAULT_MAX_SECONDS:g})")
+    flipbook.add_argument("--panel", choices=[panel.value for panel in PanelType if panel != PanelType.CUSTOM],
+                          help="Panel played on (default: eink.toml panel)")
+    flipbook.add_argument("--landscape", action="store_true", help="Frames for a display rotated 90 degrees")
+    flipbook.add_argument("--scale", choices=[method.value for method in ScalingMethod],
+                          default=ScalingMethod.LETTERBOX.value, help="How frames are fitted (default: letterbox)")
+    flipbook.add_argument("--dither", choices=[mode.value for mode in DitherMode],
+                          help="How grays become black and white (default: ordered)")
+    flipbook.add_argument("--keyframe-every", type=int, default=DEFAULT_KEYFRAME_EVERY,
+                          help="Partial refreshes between full-refresh keyframes; 0 only keys the first "
+                               f"(default: {DEFAULT_KEYFRAME_EVERY})")
+
+    play = commands.add_parser("play", help="Play a flipbook made by the flipbook command")
+    play.add_argument("flipbook")
+    play.add_argument("--loops", type=int, default=1, help="Times to play it (default: 1)")
+    play.add_argument("--loop", action="store_true", help="Repeat until interrupted")
+
+    mirror = commands.add_parser("mirror", help="Mirror a text console, or a framebuffer, until interrupted")
+    mirrored = mirror.add_mutually_exclusive_group()
+    mirrored.add_argument("--console", type=int, default=0,
+                          help="Virtual console, as in /dev/ttyN (default: 0, the one in the foreground)")
+    mirrored.add_argument("--fb", nargs="?", const=FRAMEBUFFER_DEVICE,
+                          help=f"Framebuffer device to mirror instead (default device: {FRAMEBUFFER_DEVICE})")
+    mirror.add_argument("--interval", type=float,
+                        help="Seconds between captures (default: 1 for a console, 2 for a framebuffer)")
+    mirror.add_argument("--partial", action="store_true",
+                        help="Refresh partially however much changed; the ghosting policy still clears the panel")
+    args = parser.parse_args(argv)
+
+    if getattr(args, "size", 1) < 1:
+        parser.error("--size must be at least 1")
+    if getattr(args, "threshold", None) is not None and not 0 <= args.threshold <= 255:
+        parser.error("--threshold must be 0-255")
+    if getattr(args, "sharpen", None) is not None and not 0 <= args.sharpen <= MAX_SHARPEN:
+        parser.error(f"--sharpen must be 0-{MAX_SHARPEN:g}")
+    if not math.isfinite(getattr(args, "rotate", 0.0)):
+        parser.error("--rotate must be a number of degrees")
+    if getattr(args, "keyframe_every", 0) < 0:
+        parser.error("--keyframe-every must not be negative")
+    if getattr(args, "loops", 1) < 1:
+        parser.error("--loops must be at least 1")
+    if getattr(args, "console", 0) < 0:
+        parser.error("--console must not be negative")
+    if getattr(args, "interval", None) is not None and not args.interval >= MIN_POLL_INTERVAL_S:
+        parser.error(f"--interval must be at least {MIN_POLL_INTERVAL_S:g} seconds")
+
+    try:
+        if args.command == "info":
+            return _info(args)
+        if args.command == "serve":
+            return _serve(args)
+        if args.command == "mqtt":
+            return _mqtt(args)
+        if args.command == "flipbook":
+            return _flipbook(args)
+        with Display(backend=args.backend) as display:
+            if args.command == "show":
+                _show(display, args)
+            elif args.command == "clear":
+                display.clear()
+            elif args.command == "sleep":
+                display.sleep()
+            elif args.command == "text":
+                _text(display, args)
+            elif args.command == "qr":
+                _qr(display, args)
+            elif args.command == "layout":
+                _layout(display, args)
+            elif args.command == "play":
+                _play(display, args)
+            elif args.command == "mirror":
+                _mirror(display, args)
+    except (DisplayError, FlipbookError, ImageError, LayoutError, LockError, QRCodeError, OSError) as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,889 @@
+#!/usr/bin/env python3
+"""
+Display backend unit tests for CM5 SDK.
+"""
+
+import ctypes
+import os
+import struct
+import sys
+import tempfile
+import threading
+import time
+import unittest
+import zlib
+from pathlib import Path
+from unittest.mock import Mock, patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.display import (
+    Display, DisplayError, DisplayErrorCode, DisplayEventType, DisplayMode, DisplayTimeoutError, LutKind, LutPreset,
+    PixelFormat, ProcessingOptions, RamPlane, RotationMode, rotate_bitpacked,
+)
+from distiller_cm5_sdk.hardware.eink.backends import resolve_backend_name
+from distiller_cm5_sdk.hardware.eink.config import DisplayConfig
+from distiller_cm5_sdk.hardware.eink.graphics import Color
+from distiller_cm5_sdk.hardware.eink.idle import IdleAction
+from distiller_cm5_sdk.hardware.eink.backends.mock import MOCK_DIR_ENV_VAR, MockTransport, get_mock_transport
+from distiller_cm5_sdk.hardware.eink.backends.python import PythonDisplayLibrary, SpiGpioTransport, Transport
+from distiller_cm5_sdk.hardware.eink.backends.simulator import (
+    GHOST_STEP, PAPER, SimulatorTransport, add_key_listener, pump_windows, remove_key_listener,
+)
+from distiller_cm5_sdk.hardware.eink.panels import (
+    FAST_REFRESH_LUT, PanelType, get_panel, identify_panel, load_panel_descriptor,
+)
+from distiller_cm5_sdk.hardware.eink.pngcodec import PNGError, decode_png, encode_png_1bit
+
+
+def make_png(width, height, gray_rows, color_type=0):
+    """Build a minimal 8-bit PNG from rows of gray values."""
+    def chunk(kind, data):
+        return struct.pack(">I", len(data)) + kind + data + struct.pack(">I", zlib.crc32(kind + data))
+
+    raw = bytearray()
+    for row in gray_rows:
+        raw.append(0)  # filter: none
+        for v in row:
+            raw.extend([v] if color_type == 0 else [v, v, v])
+    header = struct.pack(">IIBBBBB", width, height, 8, color_type, 0, 0, 0)
+    return (b"\x89PNG\r\n\x1a\n" + chunk(b"IHDR", header) +
+            chunk(b"IDAT", zlib.compress(bytes(raw))) + chunk(b"IEND", b""))
+
+
+class FakeTransport(Transport):
+    """Transport that records panel traffic instead of touching hardware."""
+
+    def __init__(self):
+        self.opened = False
+        self.commands = []
+        self.ram = b""
+        self.old_ram = b""
+        self._last_cmd = None
+
+    def open(self):
+        self.opened = True
+        return True
+
+    def close(self):
+        self.opened = False
+
+    def reset(self):
+        pass
+
+    def write_command(self, cmd):
+        self.commands.append(cmd)
+        self._last_cmd = cmd
+
+    def write_data(self, data):
+        if self._last_cmd == 0x24:
+            self.ram = bytes(data)
+        elif self._last_cmd == 0x26:
+            self.old_ram = bytes(data)
+
+    def wait_busy(self, timeout_ms):
+        return True
+
+
+class TestPNGCodec(unittest.TestCase):
+    """Test cases for the pure-Python PNG decoder."""
+
+    def test_decode_gray(self):
+        """Test decoding an 8-bit grayscale PNG to RGBA."""
+        width, height, rgba = decode_png(make_png(2, 1, [[0, 200]]))
+        self.assertEqual((width, height), (2, 1))
+        self.assertEqual(rgba, bytes([0, 0, 0, 255, 200, 200, 200, 255]))
+
+    def test_decode_rgb(self):
+        """Test decoding an 8-bit RGB PNG to RGBA."""
+        _, _, rgba = decode_png(make_png(1, 2, [[10], [20]], color_type=2))
+        self.assertEqual(rgba, bytes([10, 10, 10, 255, 20, 20, 20, 255]))
+
+    def test_encode_round_trip(self):
+        """Test packed 1-bit data survives encoding with unaligned rows."""
+        data = bytes([0b10100000, 0xFF, 0x00])  # 5x4 pixels
+        width, height, rgba = decode_png(encode_png_1bit(data, 5, 4))
+        self.assertEqual((width, height), (5, 4))
+        self.assertEqual([rgba[i * 4] // 255 for i in range(20)],
+                         [1, 0, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0])
+
+    def test_reject_non_png(self):
+        """Test non-PNG data is rejected."""
+        with self.assertRaises(PNGError):
+            decode_png(b"not a png")
+
+    def test_reject_malformed(self):
+        """Test a short IHDR and oversized images are rejected, and image data is inflated only as needed."""
+        png = make_png(2, 1, [[0, 200]])
+        with self.assertRaises(PNGError):
+            decode_png(png[:8] + struct.pack(">I", 12) + png[12:])
+        for width, height in [(16385, 1), (1, 0), (8192, 8192)]:
+            oversized = bytearray(png)
+            oversized[16:24] = struct.pack(">II", width, height)
+            with self.assertRaises(PNGError):
+                decode_png(bytes(oversized))
+
+        bomb = bytearray(png)
+        idat = zlib.compress(bytes(64 * 1024 * 1024))
+        bomb[33:] = (struct.pack(">I", len(idat)) + b"IDAT" + idat + bytes(4)
+                     + struct.pack(">I", 0) + b"IEND" + bytes(4))
+        self.assertEqual(decode_png(bytes(bomb)), (2, 1, bytes([0, 0, 0, 255, 0, 0, 0, 255])))
+
+
+class TestMockBackend(unittest.TestCase):
+    """Test cases for the hardware-free mock backend."""
+
+    def test_frames_in_memory(self):
+        """Test frames are captured in memory with no hardware present."""
+        with patch.dict(os.environ, {"DISTILLER_EINK_BACKEND": "mock"}):
+            with Display() as display:
+                transport = get_mock_transport(display)
+                self.assertIsNone(transport.last_frame)
+                data = bytes(range(256)) * 15 + bytes(160)
+                display.display_image(data)
+                display.clear()
+                display.sleep()
+
+        self.assertEqual(transport.frame_count, 2)
+        self.assertEqual(transport.frames[0], data)
+        self.assertEqual(transport.last_frame, b"\xff" * Display.ARRAY_SIZE)
+        self.assertTrue(transport.asleep)
+
+    def test_idle_policy(self):
+        """Test an idle panel is cleared and put to sleep, and woken by the next update."""
+        with Display(backend="mock") as display:
+            transport = get_mock_transport(display)
+            display.display_image(bytes(Display.ARRAY_SIZE))
+            display.set_idle_policy(60, IdleAction.CLEAR)
+            now = time.monotonic()
+            self.assertFalse(display._idle.check(now + 30))
+            self.assertTrue(display._idle.check(now + 61))
+            self.assertEqual(transport.last_frame, b"\xff" * Display.ARRAY_SIZE)
+            self.assertTrue(transport.asleep)
+            self.assertFalse(display._idle.check(now + 200))          # already asleep
+
+            display.display_image(bytes(Display.ARRAY_SIZE))
+            self.assertFalse(transport.asleep)
+            self.assertEqual(transport.frame_count, 3)
+
+            with self.assertRaises(DisplayError):
+                display.set_idle_policy(60, IdleAction.SHOW_IMAGE, b"short")
+            display.set_idle_policy(None)
+            self.assertIsNone(display._idle)
+
+    def test_frames_written_as_png(self):
+        """Test DISTILLER_EINK_MOCK_DIR receives one PNG per refresh."""
+        with tempfile.TemporaryDirectory() as out_dir:
+            with patch.dict(os.environ, {MOCK_DIR_ENV_VAR: out_dir}):
+                with Display(backend="mock") as display:
+                    display.display_image(b"\x00" * Display.ARRAY_SIZE, DisplayMode.PARTIAL)
+            with open(os.path.join(out_dir, "frame_0001.png"), "rb") as f:
+                width, height, rgba = decode_png(f.read())
+
+        self.assertEqual((width, height), (Display.WIDTH, Display.HEIGHT))
+        self.assertEqual(set(rgba[0::4]), {0})
+
+    def test_not_mock(self):
+        """Test non-mock displays are rejected."""
+        display = Display(backend="python", auto_init=False)
+        with self.assertRaises(ValueError):
+            get_mock_transport(display)
+
+    def test_headless_fallback(self):
+        """Test a panel that cannot be set up falls back to the mock backend when configured."""
+        config = DisplayConfig(fallback_backend="mock", autodetect=False, rotation=RotationMode.CW_90)
+        with patch("sys.stdout"), Display(backend="python", config=config) as display:
+            self.assertEqual(display.backend, "mock")
+            self.assertIn("python backend: Failed to initialize", display.get_info()["degraded"])
+            self.assertEqual(display.get_dimensions(), (250, 128))
+            display.display_image(b"\x00" * Display.ARRAY_SIZE, DisplayMode.PARTIAL)
+            self.assertEqual(get_mock_transport(display).last_frame, b"\x00" * Display.ARRAY_SIZE)
+
+        with patch("sys.stdout"), Display(library_path="/nonexistent/libdisplay.so", config=config) as display:
+            self.assertEqual(display.backend, "mock")
+            self.assertIn("Display library not found", display.degraded)
+        with Display(backend="mock", config=config) as display:
+            self.assertIsNone(display.get_info()["degraded"])
+
+        with patch("sys.stdout"), self.assertRaises(DisplayError):
+            Display(backend="python", config=DisplayConfig(autodetect=False))
+
+    def test_other_panels(self):
+        """Test the 2.9" and 4.2" panels use their own geometry end to end."""
+        for panel_type, size in ((PanelType.EPD296x128, (128, 296)), (PanelType.EPD400x300, (400, 300))):
+            with self.subTest(panel=panel_type):
+                config = DisplayConfig(panel=panel_type)
+                with Display(backend="mock", config=config) as display:
+                    self.assertEqual(display.get_dimensions(), size)
+                    self.assertEqual(display.ARRAY_SIZE, size[0] * size[1] // 8)
+                    display.display_image(b"\x00" * display.ARRAY_SIZE)
+                    with self.assertRaises(DisplayError):
+                        display.display_image(b"\x00" * Display.ARRAY_SIZE)
+                    transport = get_mock_transport(display)
+                self.assertEqual(len(transport.last_frame), size[0] * size[1] // 8)
+
+                config.rotation = RotationMode.CW_90
+                with Display(backend="mock", config=config) as display:
+                    self.assertEqual(display.get_dimensions(), (size[1], size[0]))
+
+        with self.assertRaises(DisplayError) as ctx:
+            Display(backend="native", auto_init=False, config=DisplayConfig(panel=PanelType.EPD400x300))
+        self.assertIn("epd400x300 is python-backend only", str(ctx.exception))
+
+    def test_panel_autodetect(self):
+        """Test the panel is identified from its User ID, falling back to the configured one."""
+        self.assertEqual(identify_panel(b"\xa1\xb2\x07" + bytes(7), {b"\xa1": PanelType.EPD296x128,
+                                                                      b"\xa1\xb2": PanelType.EPD400x300}),
+                         PanelType.EPD400x300)
+        self.assertIsNone(identify_panel(b"\xff" * 10, {b"\xff": PanelType.EPD400x300}))
+        self.assertIsNone(identify_panel(None))
+
+        ids = {b"\xa1\xb2": PanelType.EPD400x300}
+        for user_id, expected in ((b"\xa1\xb2" + bytes(8), (400, 300)),
+                                  (b"\x55" * 10, (128, 250)),
+                                  (None, (128, 250))):
+            with self.subTest(user_id=user_id):
+                transport = MockTransport(panel=get_panel(PanelType.EPD400x300), user_id=user_id)
+                display = Display(backend="mock", auto_init=False, config=DisplayConfig())
+                display._lib = PythonDisplayLibrary(transport, autodetect=True, panel_ids=ids)
+                display.initialize()
+                self.assertEqual(display.get_dimensions(), expected)
+                self.assertEqual(display.ARRAY_SIZE, expected[0] * expected[1] // 8)
+
+    def test_custom_panel(self):
+        """Test a custom panel's geometry, LUT and pin overrides reach the backend."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "panel.json")
+            with open(path, "w") as f:
+                f.write('{"width": 152, "height": 152, "init": ["11 03"], "lut": "aa bb",'
+                        ' "pins": {"reset": 5, "busy": 6}}')
+            spec = load_panel_descriptor(path)
+
+        library = PythonDisplayLibrary(panel=spec)
+        self.assertEqual((library.transport.rst_pin, library.transport.busy_pin), (5, 6))
+
+        transport = FakeTransport()
+        display = Display(backend="mock", auto_init=False,
+                          config=DisplayConfig(panel=PanelType.CUSTOM, custom_panel=spec))
+        display._lib = PythonDisplayLibrary(transport, spec)
+        display.initialize()
+        self.assertEqual(display.get_dimensions(), (152, 152))
+        self.assertEqual(transport.commands[-2:], [0x11, 0x32])
+        display.display_image(b"\x00" * display.ARRAY_SIZE)
+        self.assertEqual(len(transport.ram), 152 * 152 // 8)
+        display.close()
+
+        with self.assertRaises(DisplayError):
+            Display(backend="native", auto_init=False,
+                    config=DisplayConfig(panel=PanelType.CUSTOM, custom_panel=spec))
+
+    def test_simulator_ghosting(self):
+        """Test partial refreshes leave ghosting that a full refresh clears."""
+        transport = SimulatorTransport(speed=0, window=False)
+        display = Display(backend="mock", auto_init=False)
+        display._lib = PythonDisplayLibrary(transport)
+        display.initialize()
+
+        black = b"\x00" * Display.ARRAY_SIZE
+        white = b"\xff" * Display.ARRAY_SIZE
+        display.display_image(black, DisplayMode.FULL)
+        display.display_image(white, DisplayMode.PARTIAL)
+        self.assertTrue(transport.partial)
+        ghosted = transport.render_rgb(white)[0]
+        self.assertEqual(ghosted, PAPER - GHOST_STEP)
+
+        display.display_image(white, DisplayMode.FULL)
+        self.assertFalse(transport.partial)
+        self.assertEqual(transport.render_rgb(white)[0], PAPER)
+        self.assertEqual(transport.frame_count, 3)
+
+    def test_simulator_keys(self):
+        """Test keys pressed in an open simulator window reach the key listeners."""
+        pygame = Mock(QUIT=256, KEYDOWN=768)
+        pygame.event.get.return_value = [Mock(type=768, key=13), Mock(type=1024)]
+        pygame.key.name.return_value = "return"
+        pygame.display.set_mode.return_value.get_size.return_value = (384, 750)
+        keys = []
+        add_key_listener(keys.append)
+        try:
+            with patch.dict(sys.modules, {"pygame": pygame}):
+                transport = SimulatorTransport(speed=0)
+                self.assertTrue(transport.open())
+                pump_windows()
+                transport.close()
+                pump_windows()
+        finally:
+            remove_key_listener(keys.append)
+        self.assertEqual(keys, ["return", "return"])       # opening drew the blank panel, which pumps too
+        pygame.key.name.assert_called_with(13)
+
+
+class TestPythonBackend(unittest.TestCase):
+    """Test cases for the pure-Python display library."""
+
+    def setUp(self):
+        """Create a Display driven by the Python backend over a fake transport."""
+        self.transport = FakeTransport()
+        self.display = Display(backend="python", auto_init=False)
+        self.display._lib = PythonDisplayLibrary(self.transport)
+        self.display.initialize()
+
+    def test_backend_name_resolution(self):
+        """Test backend names are validated."""
+        self.assertEqual(resolve_backend_name("Python"), "python")
+        with self.assertRaises(ValueError):
+            resolve_backend_name("bogus")
+        with self.assertRaises(DisplayError):
+            Display(backend="bogus", auto_init=False)
+
+    def test_unsafe_registers(self):
+        """Test raw register access is gated by configuration and sends commands unchanged."""
+        with self.assertRaises(DisplayError):
+            self.display.unsafe_registers()
+
+        display = Display(backend="python", auto_init=False, config=DisplayConfig(register_access=True))
+        transport = FakeTransport()
+        display._lib = PythonDisplayLibrary(transport)
+        with self.assertRaises(DisplayError):
+            display.unsafe_registers()
+        display.initialize()
+        registers = display.unsafe_registers()
+        with self.assertLogs("distiller_cm5_sdk.hardware.eink.registers", "WARNING"):
+            registers.write(0x32, bytes(153))
+        registers.refresh(DisplayMode.PARTIAL)
+        self.assertEqual(transport.commands[-3:], [0x32, 0x22, 0x20])
+        self.assertIsNone(registers.read(0x2E, 10))
+        with self.assertRaises(DisplayError):
+            registers.write(0x32, [256])
+
+    def test_busy_timeout_recovery(self):
+        """Test a busy timeout resets and retries the update, then fails with DisplayTimeoutError."""
+        class StuckTransport(FakeTransport):
+            def __init__(self):
+                super().__init__()
+                self.stuck_waits = 0
+                self.resets = 0
+
+            def reset(self):
+                self.resets += 1
+
+            def wait_busy(self, timeout_ms):
+                self.timeout_ms = timeout_ms
+                if self.stuck_waits:
+                    self.stuck_waits -= 1
+                    return False
+                return True
+
+        transport = StuckTransport()
+        display = Display(backend="python", auto_init=False,
+                          config=DisplayConfig(busy_timeout_ms=500, busy_retries=2))
+        display._lib = PythonDisplayLibrary(transport)
+        display._configure_busy_policy(500, 2)
+        display.initialize()
+        self.assertEqual((transport.resets, transport.timeout_ms), (1, 500))
+
+        transport.stuck_waits = 1
+        display.display_image(b"\x00" * display.ARRAY_SIZE)
+        self.assertEqual(transport.resets, 2)
+
+        transport.stuck_waits = 100
+        with self.assertRaises(DisplayTimeoutError):
+            display.clear()
+        self.assertEqual(transport.resets, 4)
+
+        transport.stuck_waits = 0
+        display.clear()
+
+    def test_spi_data_chunked(self):
+        """Test frame data is split into block_size SPI transfers."""
+        transport = SpiGpioTransport(block_size=4096)
+        transport._spi = Mock()
+        transport._dc = Mock()
+        transport.write_data(bytes(range(256)) * 40)
+        sizes = [len(call.args[0]) for call in transport._spi.writebytes2.call_args_list]
+        self.assertEqual(sizes, [4096, 4096, 2048])
+        sent = b"".join(bytes(call.args[0]) for call in transport._spi.writebytes2.call_args_list)
+        self.assertEqual(sent, bytes(range(256)) * 40)
+
+    def test_init_sends_swreset(self):
+        """Test initialization runs the panel init sequence."""
+        self.assertTrue(self.transport.opened)
+        self.assertIn(0x12, self.transport.commands)
+
+    def test_display_raw(self):
+        """Test raw data reaches panel RAM unchanged."""
+        data = bytes(range(256)) * 15 + bytes(160)
+        self.display.display_image(data, DisplayMode.PARTIAL)
+        self.assertEqual(self.transport.ram, data)
+
+    def test_display_image_auto(self):
+        """Test the refresh mode follows the number of pixels changed since the last frame."""
+        white = bytearray(b"\xff" * Display.ARRAY_SIZE)
+        self.assertEqual(self.display.display_image_auto(bytes(white)), DisplayMode.FULL)
+        white[:100] = bytes(100)                                    # 800 pixels changed
+        self.assertEqual(self.display.display_image_auto(bytes(white)), DisplayMode.PARTIAL)
+        self.assertEqual(self.transport.ram, bytes(white))
+        self.assertEqual(self.display.display_image_auto(bytes(Display.ARRAY_SIZE)), DisplayMode.FULL)
+
+        self.display.set_auto_threshold(0)
+        self.assertEqual(self.display.display_image_auto(bytes(Display.ARRAY_SIZE)), DisplayMode.FULL)
+        self.display.set_auto_threshold(10000)
+        self.display.set_rotation(RotationMode.CW_90)
+        self.assertEqual(self.display.display_image_auto(bytes(Display.ARRAY_SIZE)), DisplayMode.PARTIAL)
+        self.display.close()
+        self.display.initialize()
+        self.assertEqual(self.display.display_image_auto(bytes(Display.ARRAY_SIZE)), DisplayMode.FULL)
+
+    def test_sleep_restores_reference(self):
+        """Test an update after sleep() wakes the panel and restores the last frame for partial refresh."""
+        first = bytes(range(256)) * 15 + bytes(160)
+        self.display.display_image(first)
+        self.display.sleep()
+        self.assertTrue(self.display.is_asleep())
+        self.assertEqual(self.transport.commands[-1], 0x10)
+        mark = len(self.transport.commands)
+
+        second = bytes(10) + first[10:]
+        self.assertEqual(self.display.display_image_auto(second), DisplayMode.PARTIAL)
+        self.assertFalse(self.display.is_asleep())
+        self.assertIn(0x12, self.transport.commands[mark:])             # woken by a reset
+        self.assertEqual(self.transport.old_ram, first)                  # reference restored
+        self.assertEqual(self.transport.ram, second)
+
+        self.display.abort()
+        with self.assertRaises(DisplayError):
+            self.display.display_image(first)
+        self.assertFalse(self.display._lib.display_restore_frame(first))    # nor is a frame restored
+        self.assertEqual(self.display.get_framebuffer(native=True), second)
+        self.display.wake()                                             # resumes updates
+        self.display.display_image(first)
+        self.assertEqual(self.transport.ram, first)
+
+    def test_stage_and_flip(self):
+        """Test a staged frame is shown by flip() without being sent again, and any other update discards it."""
+        first = bytes(range(256)) * 15 + bytes(160)
+        second = bytes(10) + first[10:]
+        self.display.display_image(first)
+        self.display.stage_frame(second)
+        self.assertEqual(self.transport.ram, second)
+        self.assertEqual(self.display.get_framebuffer(), first)          # nothing shown yet
+
+        mark = len(self.transport.commands)
+        self.assertEqual(self.display.flip(), DisplayMode.PARTIAL)
+        self.assertNotIn(0x24, self.transport.commands[mark:])          # no RAM write
+        self.assertEqual(self.transport.commands[-1], 0x20)
+        self.assertEqual(self.display.get_framebuffer(), second)
+        self.assertEqual(self.display.get_info()["last_update"]["mode"], "partial")
+        with self.assertRaises(DisplayError):
+            self.display.flip()                                         # consumed
+
+        self.display.write_plane(RamPlane.OLD, first)
+        self.assertEqual(self.transport.old_ram, first)
+        self.display.stage_frame(first)
+        self.display.display_image(second, DisplayMode.PARTIAL)
+        with self.assertRaises(DisplayError):
+            self.display.flip()
+
+        # Staged in the caller's frame, rotated like any update; sleep loses the RAM
+        self.display.set_rotation(RotationMode.CW_90)
+        self.display.stage_frame(first)
+        self.display.sleep()
+        self.display.flip(DisplayMode.FULL)
+        self.assertEqual(self.transport.ram, rotate_bitpacked(first, 250, 128, RotationMode.CW_90))
+        self.assertEqual(self.display.get_framebuffer(), first)
+        with self.assertRaises(DisplayError):
+            self.display.write_plane(RamPlane.NEW, bytes(10))
+
+    def test_power_rail(self):
+        """Test power_off() sleeps and releases the panel before cutting the rail, and power_on() restores the frame."""
+        from distiller_cm5_sdk.hardware.sam.link import SamLinkError
+        steps = []
+        rail = Mock()
+        rail.off.side_effect = lambda: steps.append(("off", self.transport.commands[-1], self.transport.opened))
+        rail.on.side_effect = lambda: steps.append(("on", self.transport.opened))
+        first = bytes(range(256)) * 15 + bytes(160)
+        self.display.display_image(first)
+
+        self.display.power_off(rail)
+        self.assertEqual(steps, [("off", 0x10, False)])                 # deep sleep, transport closed
+        self.assertTrue(self.display.is_powered_off())
+        with self.assertRaisesRegex(DisplayError, "power_on"):
+            self.display.display_image(first)
+        with self.assertRaisesRegex(DisplayError, "power_on"):
+            self.display.wake()
+
+        self.transport.ram = self.transport.old_ram = b""
+        self.display.power_on(rail)
+        self.assertEqual(steps[1:], [("on", False)])
+        self.assertFalse(self.display.is_powered_off())
+        self.assertEqual((self.transport.old_ram, self.transport.ram), (first, first))
+        self.assertEqual(self.display.get_framebuffer(native=True), first)
+        second = bytes(10) + first[10:]
+        self.assertEqual(self.display.display_image_auto(second), DisplayMode.PARTIAL)
+        self.assertEqual(self.transport.old_ram, first)
+
+        rail.off.side_effect = SamLinkError("SAM did not confirm switching the panel power off")
+        with self.assertRaisesRegex(DisplayError, "Cannot cut the panel power"):
+            self.display.power_off(rail)
+        self.assertTrue(self.display.is_powered_off())
+        rail.on.side_effect = None
+        self.display.power_on(rail)
+        self.assertEqual(self.transport.old_ram, second)
+
+    def test_event_callback(self):
+        """Test refreshes, sleep, wake and failures are reported to the event callback."""
+        events = []
+        self.display.set_event_callback(events.append)
+        self.display.display_image(bytes(self.display.ARRAY_SIZE), DisplayMode.PARTIAL)
+        self.display.sleep()
+        self.display.clear()
+        self.assertEqual([(event.type, event.mode) for event in events], [
+            (DisplayEventType.REFRESH_STARTED, DisplayMode.PARTIAL),
+            (DisplayEventType.REFRESH_COMPLETED, DisplayMode.PARTIAL),
+            (DisplayEventType.PANEL_SLEEP, DisplayMode.FULL),
+            (DisplayEventType.PANEL_WAKE, DisplayMode.FULL),
+            (DisplayEventType.REFRESH_STARTED, DisplayMode.FULL),
+            (DisplayEventType.REFRESH_COMPLETED, DisplayMode.FULL),
+        ])
+        self.assertEqual({event.display_id for event in events}, {0})
+
+        # A failed refresh ends with ERROR and its cause
+        events.clear()
+        self.transport.wait_busy = lambda timeout_ms: False
+        self.display._lib.display_set_busy_policy(100, 0)
+        with self.assertRaises(DisplayTimeoutError):
+            self.display.clear()
+        self.assertEqual(events[-1].type, DisplayEventType.ERROR)
+        self.assertEqual(events[-1].error, DisplayErrorCode.TIMEOUT)
+        self.assertIn("update abandoned", events[-1].message)
+
+        # A failing callback does not fail the update
+        self.transport.wait_busy = lambda timeout_ms: True
+        self.display.set_event_callback(Mock(side_effect=RuntimeError("boom")))
+        self.display.clear()
+        self.display.set_event_callback(None)
+        events.clear()
+        self.display.clear()
+        self.assertEqual(events, [])
+
+    def test_stats(self):
+        """Test refreshes, failures and transport writes are counted for get_stats()."""
+        self.display.display_image(bytes(self.display.ARRAY_SIZE), DisplayMode.PARTIAL)
+        self.display.clear()
+        stats = self.display.get_stats()
+        self.assertEqual((stats["full_refreshes"], stats["partial_refreshes"], stats["failed_refreshes"]), (1, 1, 0))
+        self.assertGreater(stats["spi_transfers"], 4)
+        self.assertGreaterEqual(stats["spi_bytes"], 2 * self.display.ARRAY_SIZE)
+        self.assertEqual(stats["average_spi_us"], stats["total_spi_us"] // stats["spi_transfers"])
+
+        self.transport.wait_busy = lambda timeout_ms: False
+        self.display._lib.display_set_busy_policy(100, 0)
+        with self.assertRaises(DisplayTimeoutError):
+            self.display.clear()
+        self.assertEqual(self.display.get_stats()["failed_refreshes"], 1)
+
+    def test_lut_override(self):
+        """Test LUT overrides are written before each refresh using them, with their voltages."""
+        writes = []
+        write_data = self.transport.write_data
+
+        def record(data):
+            writes.append((self.transport._last_cmd, bytes(data)))
+            write_data(data)
+
+        self.transport.write_data = record
+        frame = bytes(self.display.ARRAY_SIZE)
+        self.display.set_lut(LutKind.PARTIAL, LutPreset.FAST_REFRESH)
+        self.display.display_image(frame, DisplayMode.FULL)
+        self.assertEqual([data for cmd, data in writes if cmd in (0x32, 0x22)], [b"\xf7"])
+
+        writes.clear()
+        self.display.display_image(frame, DisplayMode.PARTIAL)
+        self.assertEqual([(cmd, data) for cmd, data in writes if cmd != 0x24][-6:],
+                         [(0x32, FAST_REFRESH_LUT[:153]), (0x3F, b"\x22"), (0x03, b"\x17"),
+                          (0x04, b"\x41\x00\x32"), (0x2C, b"\x36"), (0x22, b"\xcf")])
+
+        writes.clear()
+        custom = bytes(range(153))
+        self.display.set_lut(LutKind.FULL, custom)
+        self.display.set_lut(LutKind.PARTIAL, None)
+        self.display.display_image(frame, DisplayMode.FULL)
+        self.display.display_image(frame, DisplayMode.PARTIAL)
+        self.assertEqual([(cmd, data) for cmd, data in writes if cmd in (0x32, 0x3F, 0x22)],
+                         [(0x32, custom), (0x22, b"\xc7"), (0x22, b"\xff")])
+
+        with self.assertRaisesRegex(DisplayError, "LUT must be 153 or 159 bytes"):
+            self.display.set_lut(LutKind.FULL, bytes(10))
+        self.assertFalse(self.display._lib.display_set_lut(2, None, 0))
+        self.assertFalse(self.display._lib.display_set_lut_preset(LutKind.FULL, 7))
+
+    def test_temperature_compensation(self):
+        """Test external readings replace the sensor's, suspend LUT overrides when cold and stretch busy waits."""
+        writes, timeouts = [], []
+        write_data = self.transport.write_data
+
+        def record(data):
+            writes.append((self.transport._last_cmd, bytes(data)))
+            write_data(data)
+
+        self.transport.write_data = record
+        self.transport.wait_busy = lambda timeout_ms: timeouts.append(timeout_ms) or True
+        frame = bytes(self.display.ARRAY_SIZE)
+        self.assertIsNone(self.display.get_temperature())       # the fake link cannot read
+
+        self.display.set_temperature(25)
+        self.display.display_image(frame, DisplayMode.FULL)
+        self.assertEqual([(cmd, data) for cmd, data in writes if cmd in (0x1A, 0x22)],
+                         [(0x1A, b"\x19\x00"), (0x22, b"\xd7")])
+        self.assertEqual(timeouts, [10000])
+        self.assertEqual(self.display.get_temperature(), 25.0)
+
+        writes.clear()
+        timeouts.clear()
+        self.display.set_lut(LutKind.PARTIAL, LutPreset.FAST_REFRESH)
+        self.display.set_temperature(5)
+        self.display.display_image(frame, DisplayMode.PARTIAL)
+        self.display.set_temperature(-5)
+        self.display.display_image(frame, DisplayMode.PARTIAL)
+        self.assertEqual([(cmd, data) for cmd, data in writes if cmd in (0x32, 0x1A, 0x22)],
+                         [(0x1A, b"\x05\x00"), (0x22, b"\xdf"), (0x1A, b"\xfb\x00"), (0x22, b"\xdf")])
+        self.assertEqual(timeouts, [20000, 30000])
+
+        with self.assertRaisesRegex(DisplayError, "out of range"):
+            self.display.set_temperature(-60)
+        self.assertFalse(self.display._lib.display_set_temperature(150000))
+
+        # A link that can read reports the sensor's temperature after each refresh
+        self.transport.read_data = lambda length: {0x2E: b"\x01" * length, 0x1B: b"\x08\x00"}.get(
+            self.transport._last_cmd)
+        self.display.set_temperature(None)
+        writes.clear()
+        timeouts.clear()
+        self.display.display_image(frame, DisplayMode.PARTIAL)
+        self.assertEqual(self.display.get_temperature(), 8.0)
+        self.display.display_image(frame, DisplayMode.PARTIAL)
+        self.assertEqual([(cmd, data) for cmd, data in writes if cmd in (0x32, 0x1A, 0x22)],
+                         [(0x32, FAST_REFRESH_LUT[:153]), (0x22, b"\xcf"), (0x22, b"\xff")])
+        self.assertEqual(timeouts, [10000, 20000])
+
+    def test_screenshot(self):
+        """Test the last frame reads back in the caller's frame and saves as a PNG."""
+        self.assertIsNone(self.display.get_framebuffer())
+        self.display.set_rotation(RotationMode.CW_90)
+        width, height = self.display.get_dimensions()
+        frame = bytearray(b"\xff" * Display.ARRAY_SIZE)
+        frame[0] = 0x0f                                 # four black pixels at the top left
+        self.display.display_image(bytes(frame))
+        self.assertEqual(self.display.get_framebuffer(), bytes(frame))
+        self.assertEqual(self.display.get_framebuffer(native=True), self.transport.ram)
+
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "shot.png")
+            self.assertEqual(self.display.save_screenshot(path), (width, height))
+            png_width, png_height, rgba = decode_png(Path(path).read_bytes())
+        self.assertEqual((png_width, png_height), (width, height))
+        self.assertEqual([rgba[x * 4] for x in range(6)], [0, 0, 0, 0, 255, 255])
+
+    def test_emergency_frame(self):
+        """Test an emergency frame aborts the update in flight and runs ahead of later ones."""
+        class SlowTransport(FakeTransport):
+            def __init__(self):
+                super().__init__()
+                self.hold = False
+                self.waiting = threading.Event()
+
+            def wait_busy(self, timeout_ms):
+                if not self.hold:
+                    return True
+                self.waiting.set()
+                return not self.abort.wait(timeout_ms / 1000.0)
+
+        transport = SlowTransport()
+        display = Display(backend="python", auto_init=False)
+        display._lib = PythonDisplayLibrary(transport)
+        display.initialize()
+
+        errors = []
+        def update():
+            try:
+                display.display_image(bytes(Display.ARRAY_SIZE))
+            except DisplayError as e:
+                errors.append(e)
+        transport.hold = True
+        worker = threading.Thread(target=update)
+        worker.start()
+        self.assertTrue(transport.waiting.wait(5))
+        transport.hold = False
+        display.emergency_frame(b"\xff" * Display.ARRAY_SIZE)
+        worker.join(5)
+
+        self.assertEqual(len(errors), 1)
+        self.assertIn("aborted", str(errors[0]))
+        self.assertEqual(transport.ram, b"\xff" * Display.ARRAY_SIZE)
+        self.assertEqual(transport.commands[-1], 0x10)                 # deep sleep
+        with self.assertRaises(DisplayError):
+            display.display_image(bytes(Display.ARRAY_SIZE))
+        display.wake()
+        display.display_image(bytes(Display.ARRAY_SIZE))
+        self.assertEqual(transport.ram, bytes(Display.ARRAY_SIZE))
+
+    def test_splash_and_progress(self):
+        """Test progress frames redraw only the bar region, partially, over the splash."""
+        self.display.display_splash(title="Updating")
+        splash = self.transport.ram
+        self.assertEqual(self.display.display_progress(10, "Flashing"), DisplayMode.PARTIAL)
+        first = self.transport.ram
+        self.assertEqual(first[:Display.ARRAY_SIZE // 2], splash[:Display.ARRAY_SIZE // 2])
+        self.assertNotEqual(first, splash)
+        self.assertIsNone(self.display.display_progress(10.1, "Flashing"))      # same pixels
+        self.assertEqual(self.display.display_progress(60, "Flashing"), DisplayMode.PARTIAL)
+
+        self.display.clear()
+        self.assertEqual(self.display.display_progress(60, "Flashing"), DisplayMode.FULL)
+        self.assertEqual(self.transport.ram[:100], splash[:100])                 # splash kept as background
+
+    def test_compose_frame(self):
+        """Test a composed frame is pushed once, with the mode chosen from its delta."""
+        fb = self.display.begin_frame()
+        fb.draw_rect(0, 0, Display.WIDTH, Display.HEIGHT // 2, fill=True)
+        fb.draw_text(4, 200, "Hi")
+        with self.assertRaises(DisplayError):
+            self.display.begin_frame()
+        self.assertEqual(self.transport.ram, b"")
+        self.assertEqual(self.display.end_frame(), DisplayMode.FULL)
+        self.assertEqual(self.transport.ram, fb.to_bytes())
+
+        with self.display.frame() as fb:
+            self.assertEqual(fb.get_pixel(0, 0), 0)            # starts from the last frame
+        self.assertEqual(self.display.ghosting.partials, 0)   # unchanged, not refreshed
+
+        with self.display.frame() as fb:
+            fb.draw_text(4, 220, "there")
+        self.assertEqual(self.transport.ram, fb.to_bytes())
+        self.assertEqual(self.display.ghosting.partials, 1)
+
+        with self.assertRaises(ValueError):
+            with self.display.frame(clear=True) as fb:
+                raise ValueError("drawing failed")
+        with self.assertRaises(DisplayError):
+            self.display.end_frame()
+
+    def test_save_restore_screen(self):
+        """Test a dialog drawn over the screen is taken down with a partial refresh of the saved content."""
+        with self.assertRaises(DisplayError):
+            self.display.save_screen()
+        with self.display.frame() as fb:
+            fb.draw_rect(0, 0, Display.WIDTH, Display.HEIGHT // 2, fill=True)
+            fb.draw_text(4, 200, "Inbox")
+        screen = self.transport.ram
+
+        with self.display.save_screen() as snapshot:
+            with self.display.frame() as fb:
+                fb.draw_rect(14, 100, 100, 40, Color.WHITE, fill=True)
+                fb.draw_text(20, 110, "Delete?")
+            self.assertNotEqual(self.transport.ram, screen)
+        self.assertEqual(self.transport.ram, screen)
+        self.assertEqual(self.display.ghosting.partials, 2)
+        with self.display.frame() as fb:
+            self.assertEqual(fb.to_bytes(), screen)        # composing resumes from the restored content
+        self.assertIsNone(snapshot.restore())               # already showing, not refreshed
+
+        self.display.display_image(bytes(Display.ARRAY_SIZE))
+        self.assertEqual(snapshot.restore(DisplayMode.FULL), DisplayMode.FULL)
+        self.assertEqual(self.transport.ram, screen)
+        self.display.set_rotation(RotationMode.CW_90)
+        with self.assertRaises(DisplayError):
+            snapshot.restore()
+
+    def test_png_bytes_threshold(self):
+        """Test an in-memory PNG is thresholded to packed 1-bit data."""
+        rows = [[255] * 64 + [0] * 64 for _ in range(Display.HEIGHT)]
+        self.display.display_image_bytes(make_png(Display.WIDTH, Display.HEIGHT, rows))
+        self.assertEqual(self.transport.ram, (b"\xff" * 8 + b"\x00" * 8) * Display.HEIGHT)
+
+    def test_pixels_with_invert(self):
+        """Test pixel-buffer input honors processing options."""
+        gray = bytes(Display.WIDTH * Display.HEIGHT)
+        self.display.display_pixels(gray, Display.WIDTH, Display.HEIGHT, PixelFormat.GRAY8,
+                                    ProcessingOptions(invert_colors=True))
+        self.assertEqual(self.transport.ram, b"\xff" * Display.ARRAY_SIZE)
+
+    def test_pixels_wrong_dimensions(self):
+        """Test dimension mismatches are reported as errors."""
+        gray = bytes(10 * 10)
+        with self.assertRaises(DisplayError):
+            self.display.display_pixels(gray, 10, 10, PixelFormat.GRAY8)
+
+    def test_error_codes(self):
+        """Test failures carry the library's error code and message, per thread."""
+        lib = self.display._lib
+        with self.assertRaises(DisplayError) as ctx:
+            self.display.display_pixels(bytes(10 * 10), 10, 10, PixelFormat.GRAY8)
+        self.assertEqual(ctx.exception.code, DisplayErrorCode.INVALID_SIZE)
+        self.assertIn("(10x10) don't match expected (128x250)", str(ctx.exception))
+
+        with self.assertRaises(DisplayError) as ctx:
+            self.display.display_image_bytes(b"\x89PNG\r\n\x1a\n" + b"truncated")
+        self.assertEqual(ctx.exception.code, DisplayErrorCode.DECODE)
+        self.assertEqual(lib.display_get_last_error(), DisplayErrorCode.DECODE)
+        self.assertEqual(lib.display_error_string(lib.display_get_last_error()), b"image decode failed")
+
+        buf = ctypes.create_string_buffer(8)
+        length = lib.display_get_last_error_message(buf, len(buf))
+        self.assertTrue(buf.value.startswith(b"Cannot "))
+        self.assertEqual(len(buf.value), 7)
+        self.assertGreater(length, 7)
+
+        # Other threads see only their own failures
+        codes = []
+        thread = threading.Thread(target=lambda: codes.append(lib.display_get_last_error()))
+        thread.start()
+        thread.join()
+        self.assertEqual(codes, [DisplayErrorCode.OK])
+
+        gif = b"GIF89a" + bytes(16)                     # only PNG and JPEG are decoded from memory
+        self.assertFalse(lib.process_image_from_memory(gif, len(gif), (ctypes.c_ubyte * 4000)()))
+        self.assertEqual(lib.display_get_last_error(), DisplayErrorCode.INVALID_ARGUMENT)
+        jpeg = b"\xff\xd8\xff\xe0" + bytes(16)
+        self.assertFalse(lib.process_image_from_memory(jpeg, len(jpeg), (ctypes.c_ubyte * 4000)()))
+        self.assertEqual(lib.display_get_last_error(), DisplayErrorCode.DECODE)
+
+        self.display.close()
+        with self.assertRaises(DisplayError):
+            self.display.clear()
+        self.assertEqual(lib.display_clear(), False)
+        self.assertEqual(lib.display_get_last_error(), DisplayErrorCode.NOT_INITIALIZED)
+
+    def test_dimensions(self):
+        """Test byref dimension outputs are filled in."""
+        self.assertEqual(self.display.get_dimensions(), (Display.WIDTH, Display.HEIGHT))
+
+    def test_rotation_landscape_frame(self):
+        """Test a landscape frame is rotated onto the portrait panel."""
+        self.display.set_rotation(RotationMode.CW_90)
+        self.assertEqual(self.display.get_rotation(), RotationMode.CW_90)
+        self.assertEqual(self.display.get_dimensions(), (Display.HEIGHT, Display.WIDTH))
+
+        # Left half black in the 250x128 frame becomes the top half of the panel
+        rows = [[0] * 125 + [255] * 125 for _ in range(Display.WIDTH)]
+        self.display.display_image_bytes(make_png(Display.HEIGHT, Display.WIDTH, rows))
+        half = Display.ARRAY_SIZE // 2
+        self.assertEqual(self.transport.ram, b"\x00" * half + b"\xff" * half)
+
+        with self.assertRaises(DisplayError):
+            self.display.display_image_bytes(make_png(Display.WIDTH, Display.HEIGHT,
+                                                      [[0] * Display.WIDTH] * Display.HEIGHT))
+
+    def test_rotate_bitpacked_round_trip(self):
+        """Test 180-degree rotation is its own inverse and CW undoes CCW."""
+        data = bytes(range(256)) * 15 + bytes(160)
+        turned = rotate_bitpacked(data, Display.WIDTH, Display.HEIGHT, RotationMode.ROTATE_180)
+        self.assertNotEqual(turned, data)
+        self.assertEqual(rotate_bitpacked(turned, Display.WIDTH, Display.HEIGHT, RotationMode.ROTATE_180), data)
+        ccw = rotate_bitpacked(data, Display.WIDTH, Display.HEIGHT, RotationMode.CCW_90)
+        self.assertEqual(rotate_bitpacked(ccw, Display.HEIGHT, Display.WIDTH, RotationMode.CW_90), data)
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
@@ -0,0 +1,122 @@
+#!/usr/bin/env python3
+"""
+Image pipeline benchmark unit tests for CM5 SDK.
+"""
+
+import io
+import json
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.diagnostics.__main__ import main
+from distiller_cm5_sdk.hardware.eink.bench import CASES, BenchError, BenchReport, run_benchmarks
+from distiller_cm5_sdk.hardware.eink.panels import PanelType
+
+
+class FakeClock:
+    """Time that advances by a fixed step on every reading."""
+
+    def __init__(self, step_s):
+        self.now = 0.0
+        self.step_s = step_s
+
+    def __call__(self):
+        self.now += self.step_s
+        return self.now
+
+
+class TestBenchmarks(unittest.TestCase):
+    """Test cases for timing the pipeline and comparing with a baseline."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def test_every_case(self):
+        """Test every case runs headless on the mock backend, on every panel size."""
+        self.assertIn("pack-rgb888", CASES)
+        self.assertIn("scale-letterbox", CASES)
+        report = run_benchmarks(repeat=1)
+        self.assertEqual(len(report.results), len(CASES) * 3)
+        self.assertEqual({(result.width, result.height) for result in report.results},
+                         {(128, 250), (128, 296), (400, 300)})
+        self.assertTrue(all(result.best_ms >= 0 for result in report.results))
+        self.assertTrue(report.passed)
+        self.assertNotIn("PASSED", report.format_text())
+
+        with self.assertRaises(BenchError):
+            run_benchmarks(["dither-atkinson"])
+        with self.assertRaises(BenchError):
+            run_benchmarks(["pack-gray8"], repeat=0)
+        with self.assertRaises(BenchError):
+            run_benchmarks(["pack-gray8"], [PanelType.CUSTOM])
+
+    def test_regression(self):
+        """Test a case slower than the baseline by the factor fails, and a saved report round-trips."""
+        panels = [PanelType.EPD128x250]
+        path = os.path.join(self.tmpdir.name, "baseline.json")
+        run_benchmarks(["dither-ordered", "pack-gray8"], panels, repeat=3, clock=FakeClock(0.005)).save(path)
+        baseline = BenchReport.load(path)
+        self.assertEqual([result.best_ms for result in baseline.results], [5.0, 5.0])
+
+        same = run_benchmarks(["dither-ordered", "pack-gray8"], panels, repeat=3, clock=FakeClock(0.006))
+        self.assertTrue(same.compare(baseline).passed)
+        self.assertAlmostEqual(same.results[0].ratio, 1.2)
+
+        slower = run_benchmarks(["pack-gray8", "dither-none"], panels, repeat=3, clock=FakeClock(0.010))
+        slower.compare(baseline)
+        self.assertFalse(slower.passed)
+        self.assertEqual([result.case for result in slower.regressions], ["pack-gray8"])
+        self.assertIsNone(slower.results[1].ratio)
+        self.assertIn("REGRESSED", slower.format_text())
+        self.assertTrue(slower.compare(baseline, max_regression=2.5).passed)
+
+        # Under a millisecond slower is noise, whatever the ratio
+        tiny = run_benchmarks(["pack-gray8"], panels, repeat=1, clock=FakeClock(0.0001))
+        self.assertTrue(run_benchmarks(["pack-gray8"], panels, repeat=1, clock=FakeClock(0.0009))
+                        .compare(tiny).passed)
+
+        with self.assertRaises(BenchError):
+            same.compare(BenchReport(0.0, "native", "device", "aarch64", 3))
+        with open(path, "w") as f:
+            f.write("{}")
+        with self.assertRaises(BenchError):
+            BenchReport.load(path)
+
+    def test_cli(self):
+        """Test the diagnostics command saves a report and exits 1 on a regression."""
+        baseline = os.path.join(self.tmpdir.name, "baseline.json")
+        report = os.path.join(self.tmpdir.name, "report.json")
+        with patch("sys.stdout", io.StringIO()) as out:
+            self.assertEqual(main(["eink-bench", "--case", "pack-gray8", "--panel", "epd296x128",
+                                   "--repeat", "2", "--report", baseline]), 0)
+        self.assertIn("pack-gray8", out.getvalue())
+        with open(baseline) as f:
+            saved = json.load(f)
+        saved["results"][0]["runs_ms"] = [0.001]
+        with open(baseline, "w") as f:
+            json.dump(saved, f)
+
+        with patch("sys.stdout", io.StringIO()) as out:
+            self.assertEqual(main(["eink-bench", "--case", "pack-gray8", "--panel", "epd296x128",
+                                   "--repeat", "2", "--baseline", baseline, "--report", report]), 1)
+        self.assertIn("FAILED", out.getvalue())
+        with open(report) as f:
+            self.assertFalse(json.load(f)["passed"])
+
+        with patch("sys.stderr", io.StringIO()) as err:
+            self.assertEqual(main(["eink-bench", "--panel", "epd999x1"]), 1)
+        self.assertIn("epd400x300", err.getvalue())
+
+
+if __name__ == "__main__":
+    unittest.main()
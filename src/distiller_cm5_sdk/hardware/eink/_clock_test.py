@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+"""
+Clock face engine unit tests for CM5 SDK.
+"""
+
+import datetime
+import sys
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.clock import ClockEngine, ClockFace, render_analog, render_digital
+from distiller_cm5_sdk.hardware.eink.config import DisplayConfig
+from distiller_cm5_sdk.hardware.eink.display import Display, DisplayMode, RotationMode
+from distiller_cm5_sdk.i18n.formatting import Formatter
+
+
+class FakeDisplay:
+    """Records refresh modes; each refresh advances the fake clock."""
+
+    def __init__(self, clock, refresh_s=0.4):
+        self.clock = clock
+        self.refresh_s = refresh_s
+        self.updates = []
+
+    def get_dimensions(self):
+        return (250, 128)
+
+    def display_image(self, data, mode):
+        self.updates.append((self.clock.now, DisplayMode(mode)))
+        self.clock.now += self.refresh_s
+
+
+class FakeClock:
+    def __init__(self, now):
+        self.now = now
+
+    def __call__(self):
+        return self.now
+
+
+class FakeStop:
+    """Stop event whose waits advance the fake clock; set after a number of waits."""
+
+    def __init__(self, clock, waits):
+        self.clock = clock
+        self.waits = waits
+
+    def is_set(self):
+        return self.waits <= 0
+
+    def wait(self, seconds):
+        self.clock.now += seconds
+        self.waits -= 1
+        return False
+
+
+class TestClock(unittest.TestCase):
+    """Test cases for clock faces and minute-aligned scheduling."""
+
+    def black_pixels(self, fb):
+        return sum(1 for y in range(fb.height) for x in range(fb.width) if fb.get_pixel(x, y) == 0)
+
+    def test_faces_render(self):
+        """Test both faces draw something in portrait and landscape frames."""
+        now = datetime.datetime(2026, 10, 16, 14, 5)
+        for render in (render_digital, render_analog):
+            for width, height in ((128, 250), (250, 128)):
+                fb = render(now, width, height, Formatter("en_GB"))
+                self.assertEqual((fb.width, fb.height), (width, height))
+                self.assertGreater(self.black_pixels(fb), 100)
+        earlier = render_analog(now.replace(minute=35), 250, 128, Formatter("en_GB"))
+        self.assertNotEqual(earlier.data, render_analog(now, 250, 128, Formatter("en_GB")).data)
+
+    def test_minute_schedule(self):
+        """Test updates land on minute boundaries, partial except on the hour, without drift."""
+        start = datetime.datetime(2026, 10, 16, 13, 58, 20).timestamp()
+        clock = FakeClock(start)
+        display = FakeDisplay(clock)
+        engine = ClockEngine(display, ClockFace.DIGITAL, locale="en_US", clock=clock)
+        engine.run(FakeStop(clock, 4))
+
+        self.assertEqual([mode for _, mode in display.updates],
+                         [DisplayMode.FULL, DisplayMode.PARTIAL, DisplayMode.FULL, DisplayMode.PARTIAL,
+                          DisplayMode.PARTIAL])
+        for when, _ in display.updates[1:]:
+            finished = when + display.refresh_s
+            boundary = round(finished / 60) * 60
+            self.assertLess(abs(finished - boundary), 0.25)
+        self.assertEqual(engine._shown, datetime.datetime(2026, 10, 16, 14, 2))
+
+    def test_engine_on_display(self):
+        """Test the engine drives a real Display in its rotated frame."""
+        display = Display(backend="mock", config=DisplayConfig(rotation=RotationMode.CCW_90))
+        engine = ClockEngine(display, ClockFace.ANALOG, locale="de_DE")
+        self.assertEqual(engine.update(datetime.datetime(2026, 10, 16, 9, 30)), DisplayMode.FULL)
+        self.assertEqual(engine.update(datetime.datetime(2026, 10, 16, 9, 31)), DisplayMode.PARTIAL)
+        self.assertEqual(engine.render(datetime.datetime(2026, 10, 16, 9, 31)).width, 250)
+        display.close()
+
+
+if __name__ == "__main__":
+    unittest.main()
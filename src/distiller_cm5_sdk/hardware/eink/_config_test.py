@@ -0,0 +1,516 @@
+#!/usr/bin/env python3
+"""
+Display configuration unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import threading
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.config import (
+    CONTRAST_ENV_VAR, PANEL_ENV_VAR, ROTATION_ENV_VAR, DisplayConfig, DisplayConfigHandle, SpiConfig,
+    current_config_handle, list_displays, load_cache_settings, load_config, load_image_profile, migrate_config,
+    parse_rotation, save_config, save_config_values,
+)
+from distiller_cm5_sdk.hardware.eink.display import DisplayError, RotationMode
+from distiller_cm5_sdk.hardware.eink.imaging import DitherMode, ImageOptions, ScalingFilter, default_image_options
+from distiller_cm5_sdk.hardware.eink.panels import PanelType
+
+
+class TestDisplayConfig(unittest.TestCase):
+    """Test cases for eink.conf parsing."""
+
+    def setUp(self):
+        """Write a config file in a temporary directory."""
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "eink.conf")
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def write(self, text):
+        with open(self.path, "w") as f:
+            f.write(text)
+
+    def test_parse_rotation(self):
+        """Test accepted rotation spellings."""
+        self.assertEqual(parse_rotation("none"), RotationMode.NONE)
+        self.assertEqual(parse_rotation("CCW90"), RotationMode.CCW_90)
+        self.assertEqual(parse_rotation(" 180 "), RotationMode.ROTATE_180)
+        self.assertEqual(parse_rotation("CW_90"), RotationMode.CW_90)
+        with self.assertRaises(DisplayError):
+            parse_rotation("45")
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_load_from_file(self):
+        """Test rotation is read from the config file, ignoring comments."""
+        self.write("# Mounted upside down\n\nrotation = 180\n")
+        self.assertEqual(load_config(self.path).rotation, RotationMode.ROTATE_180)
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_missing_file_uses_defaults(self):
+        """Test a missing config file yields the default settings."""
+        self.assertEqual(load_config(self.path).rotation, RotationMode.NONE)
+
+    @patch.dict(os.environ, {ROTATION_ENV_VAR: "cw90"}, clear=True)
+    def test_env_overrides_file(self):
+        """Test the environment variable takes precedence over the file."""
+        self.write("rotation = ccw90\n")
+        self.assertEqual(load_config(self.path).rotation, RotationMode.CW_90)
+
+    @patch.dict(os.environ, {PANEL_ENV_VAR: "EPD400x300"}, clear=True)
+    def test_panel(self):
+        """Test the panel type is read from the file and environment."""
+        self.write("panel = epd296x128\n")
+        self.assertEqual(load_config(self.path).panel, PanelType.EPD400x300)
+        del os.environ[PANEL_ENV_VAR]
+        self.assertEqual(load_config(self.path).panel, PanelType.EPD296x128)
+        self.write("panel = epd999x1\n")
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_autodetect_settings(self):
+        """Test autodetection is on by default and panel IDs are parsed from hex."""
+        self.assertTrue(load_config(self.path).autodetect)
+        self.write("autodetect = no\npanel_ids = A1B2:epd296x128, 0xc0:EPD400x300\n")
+        config = load_config(self.path)
+        self.assertFalse(config.autodetect)
+        self.assertFalse(config.register_access)
+        self.assertEqual(config.panel_ids, {b"\xa1\xb2": PanelType.EPD296x128, b"\xc0": PanelType.EPD400x300})
+        self.write("panel_ids = a1b2\n")
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_custom_panel(self):
+        """Test panel = custom:<path> loads a JSON descriptor, and bad descriptors are rejected."""
+        descriptor = os.path.join(self.tmpdir.name, "panel.json")
+        with open(descriptor, "w") as f:
+            f.write('{"width": 200, "height": 200, "init": [[1, 199, 0, 0], "11 03"],'
+                    ' "lut": ["0x80", "0x48"], "pins": {"busy": 24}}')
+        self.write(f"panel = custom:{descriptor}\n")
+        config = load_config(self.path)
+        self.assertEqual(config.panel, PanelType.CUSTOM)
+        spec = config.panel_spec()
+        self.assertEqual((spec.width, spec.height, spec.array_size), (200, 200, 5000))
+        self.assertEqual(spec.init_commands, ((0x01, b"\xc7\x00\x00"), (0x11, b"\x03")))
+        self.assertEqual((spec.lut, spec.busy_pin, spec.reset_pin), (b"\x80\x48", 24, None))
+        self.assertEqual(config.spi, SpiConfig(busy_pin=24))
+
+        for bad in ('{"width": 100, "height": 200, "init": []}',
+                    '{"width": 200, "height": 200}',
+                    '{"width": 200, "height": 200, "init": [[256]]}',
+                    '{"width": 200, "height": 200, "init": [], "pins": {"dc": 7}}'):
+            with self.subTest(descriptor=bad):
+                with open(descriptor, "w") as f:
+                    f.write(bad)
+                with self.assertRaises(DisplayError):
+                    load_config(self.path)
+
+        self.write("panel = custom\n")
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_BUSY_PIN": "25"}, clear=True)
+    def test_spi_settings(self):
+        """Test SPI/GPIO wiring is read from the file and environment, and validated."""
+        self.assertEqual(load_config(self.path).spi, SpiConfig(busy_pin=25))
+        self.write("spi_bus = 1\nspi_cs = 2\nspi_speed_hz = 0x1312D00\nreset_pin = 22\ngpio_chip = gpiochip4\n"
+                   "spi_block_size = 1024\nspi_chunk_delay_us = 50\n")
+        self.assertEqual(load_config(self.path).spi,
+                         SpiConfig(bus=1, cs=2, speed_hz=20000000, reset_pin=22, busy_pin=25, gpio_chip="gpiochip4",
+                                   block_size=1024, chunk_delay_us=50))
+        self.assertEqual(load_config(self.path).spi.device_path, "/dev/spidev1.2")
+
+        for bad in ("spi_bus = one\n", "dc_pin = 25\n", "spi_speed_hz = 0\n", "spi_block_size = 0\n"):
+            with self.subTest(setting=bad):
+                self.write(bad)
+                with self.assertRaises(DisplayError):
+                    load_config(self.path)
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_REGISTER_ACCESS": "1"}, clear=True)
+    def test_register_access(self):
+        """Test register access is enabled from the environment or file."""
+        self.assertTrue(load_config(self.path).register_access)
+        del os.environ["DISTILLER_EINK_REGISTER_ACCESS"]
+        self.write("register_access = maybe\n")
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_FALLBACK_BACKEND": "Simulator"}, clear=True)
+    def test_fallback_backend(self):
+        """Test the fallback backend is read from the environment or file, and validated."""
+        self.assertEqual(load_config(self.path).fallback_backend, "simulator")
+        del os.environ["DISTILLER_EINK_FALLBACK_BACKEND"]
+        self.assertIsNone(load_config(self.path).fallback_backend)
+        self.write("fallback_backend = mock\n")
+        self.assertEqual(load_config(self.path).fallback_backend, "mock")
+        self.write("fallback_backend = none\n")
+        self.assertIsNone(load_config(self.path).fallback_backend)
+        self.write("fallback_backend = native\n")
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_BUSY_RETRIES": "3"}, clear=True)
+    def test_busy_policy(self):
+        """Test the busy timeout and retry count are read and validated."""
+        self.write("busy_timeout_ms = 2500\nbusy_retries = 0\n")
+        config = load_config(self.path)
+        self.assertEqual((config.busy_timeout_ms, config.busy_retries), (2500, 3))
+        self.write("busy_timeout_ms = 0\n")
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_FULL_REFRESH_INTERVAL": "90"}, clear=True)
+    def test_full_refresh_policy(self):
+        """Test the forced full refresh settings default to off and are validated."""
+        self.assertEqual(load_config(self.path).full_refresh_every, 0)
+        self.write("full_refresh_every = 8\n")
+        config = load_config(self.path)
+        self.assertEqual((config.full_refresh_every, config.full_refresh_interval_s), (8, 90.0))
+        self.write("full_refresh_every = often\n")
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_THERMAL_INTERVAL": "0"}, clear=True)
+    def test_thermal_limits(self):
+        """Test the thermal throttling limits are read and validated."""
+        self.write("thermal_soc_max = 75\nthermal_ambient_min = -10\n")
+        config = load_config(self.path)
+        self.assertEqual((config.thermal_soc_max_c, config.thermal_ambient_max_c, config.thermal_ambient_min_c,
+                          config.thermal_interval_s), (75.0, 45.0, -10.0, 0.0))
+        for text in ("thermal_soc_max = hot\n", "thermal_ambient_min = 50\n"):
+            self.write(text)
+            with self.assertRaises(DisplayError):
+                load_config(self.path)
+
+    @patch.dict(os.environ, {CONTRAST_ENV_VAR: "1.5"}, clear=True)
+    def test_image_profile(self):
+        """Test the processing profile is read, validated and overridden by the environment."""
+        self.write("threshold = 140\ncontrast = 1.2\n[status]\nthreshold = 100\n")
+        self.assertEqual((load_image_profile(self.path).threshold, load_image_profile(self.path).contrast),
+                         (140, 1.5))
+        self.assertEqual(load_config(self.path, "status").image_options.threshold, 100)
+        for bad in ("threshold = 300\n", "threshold = dark\n"):
+            self.write(bad)
+            with self.assertRaises(DisplayError):
+                load_image_profile(self.path)
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_CACHE_MAX_MB": "16"}, clear=True)
+    def test_image_cache(self):
+        """Test the processed-image cache is off by default and read from the file and environment."""
+        self.assertEqual(load_cache_settings(self.path), (None, 16.0))
+        self.write("cache_dir = /var/cache/distiller-eink\ncache_max_mb = 8\n")
+        config = load_config(self.path)
+        self.assertEqual((config.image_cache_dir, config.image_cache_max_mb), ("/var/cache/distiller-eink", 16.0))
+        self.write("cache_dir = /tmp/cache\ncache_max_mb = 0\n")
+        del os.environ["DISTILLER_EINK_CACHE_MAX_MB"]
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_save_values(self):
+        """Test saved keys replace or follow the display's own, keeping comments and other sections."""
+        save_config_values({"rotation": "cw90"}, self.path)
+        self.assertEqual(load_config(self.path).rotation, RotationMode.CW_90)
+
+        self.write("# Mounted upside down\nrotation = 180\nbusy_retries = 2\n\n[status]\nspi_cs = 1\n")
+        save_config_values({"Rotation": "ccw90", "threshold": "136"}, self.path)
+        save_config_values({"contrast": "0.8"}, self.path, "status")
+        with open(self.path) as f:
+            self.assertEqual(f.read(), "# Mounted upside down\nrotation = ccw90\nbusy_retries = 2\nthreshold = 136\n\n"
+                                       "[status]\nspi_cs = 1\ncontrast = 0.8\n")
+        with self.assertRaises(DisplayError):
+            save_config_values({"rotation": "none"}, self.path, "front")
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_malformed_line(self):
+        """Test lines without '=' are rejected."""
+        self.write("rotation ccw90\n")
+        with self.assertRaises(DisplayError):
+            load_config(self.path)
+
+    @patch.dict(os.environ, {ROTATION_ENV_VAR: "180", "DISTILLER_EINK_BACKEND": "mock"}, clear=True)
+    def test_display_sections(self):
+        """Test additional displays inherit the main settings but not the environment."""
+        self.write("rotation = cw90\nbusy_retries = 3\n"
+                   "[status]\npanel = epd296x128\nbackend = python\nspi_cs = 1\nbusy_pin = 24\n")
+        self.assertEqual(list_displays(self.path), ["main", "status"])
+        main = load_config(self.path)
+        self.assertEqual((main.name, main.backend, main.rotation), ("main", "mock", RotationMode.ROTATE_180))
+        status = load_config(self.path, "status")
+        self.assertEqual((status.name, status.backend, status.panel), ("status", "python", PanelType.EPD296x128))
+        self.assertEqual((status.rotation, status.busy_retries), (RotationMode.CW_90, 3))
+        self.assertEqual(status.spi, SpiConfig(cs=1, busy_pin=24))
+        self.assertEqual(load_config(self.path).spi, SpiConfig())
+
+        with self.assertRaises(DisplayError):
+            load_config(self.path, "front")
+        for bad in ("[main]\n", "[]\n", "[a]\n[a]\n"):
+            with self.subTest(sections=bad):
+                self.write(bad)
+                with self.assertRaises(DisplayError):
+                    list_displays(self.path)
+
+
+class TestStructuredConfig(unittest.TestCase):
+    """Test cases for eink.toml parsing and saving."""
+
+    def setUp(self):
+        """Write a config file in a temporary directory."""
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "eink.toml")
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def write(self, text):
+        with open(self.path, "w") as f:
+            f.write(text)
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_load_tables(self):
+        """Test settings are read from their tables, with additional displays under [displays]."""
+        self.write('[display]\nrotation = "ccw90"\nautodetect = false\n\n[spi]\nspeed_hz = 20_000_000\n\n'
+                   '[processing]\nthreshold = 136\ndither = "ordered"\nparallel = true\nfilter = "catmull-rom"\nsharpen = 0.5\n\n'
+                   '[thermal]\ninterval = 0\n\n'
+                   '[displays.status.display]\npanel = "epd296x128"\n[displays.status.spi]\ncs = 1\nbusy_pin = 24\n')
+        config = load_config(self.path)
+        self.assertEqual((config.rotation, config.autodetect, config.thermal_interval_s),
+                         (RotationMode.CCW_90, False, 0.0))
+        self.assertEqual(config.spi, SpiConfig(speed_hz=20000000))
+        self.assertEqual((config.image_options.threshold, config.image_options.dither), (136, DitherMode.ORDERED))
+        self.assertEqual(load_image_profile(self.path).dither, DitherMode.ORDERED)
+        self.assertTrue(load_image_profile(self.path).parallel)
+        self.assertEqual((config.image_options.scaling_filter, config.image_options.sharpen),
+                         (ScalingFilter.CATMULL_ROM, 0.5))
+        self.assertEqual(list_displays(self.path), ["main", "status"])
+        status = load_config(self.path, "status")
+        self.assertEqual((status.panel, status.rotation), (PanelType.EPD296x128, RotationMode.CCW_90))
+        self.assertEqual(status.spi, SpiConfig(cs=1, speed_hz=20000000, busy_pin=24))
+
+        for bad in ('rotation = "cw90"\n', '[screen]\nrotation = "cw90"\n', '[display]\nrotate = "cw90"\n',
+                    '[display]\nrotation = ["cw90"]\n', '[processing]\ndither = "halftone"\n', '[processing]\nparallel = "sometimes"\n',
+                    '[processing]\nfilter = "bicubic"\n', '[processing]\nsharpen = 3\n',
+                    '[display]\nrotation = "cw90"\n[display]\npanel = "x"\n'):
+            with self.subTest(config=bad):
+                self.write(bad)
+                with self.assertRaises(DisplayError):
+                    load_config(self.path)
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_save_config(self):
+        """Test a saved config loads back equal, into its tables, leaving other displays alone."""
+        self.write('[display]\npanel = "epd400x300"\n\n[displays.status.spi]\ncs = 1\n')
+        config = load_config(self.path)
+        config.panel = PanelType.EPD296x128
+        config.rotation = RotationMode.CW_90
+        config.spi = SpiConfig(bus=1, busy_pin=24)
+        config.thermal_ambient_min_c = -5.5
+        save_config(config, self.path)
+        self.assertEqual(load_config(self.path), config)
+        self.assertEqual(load_config(self.path, "status").spi, SpiConfig(bus=1, cs=1, busy_pin=24))
+        with open(self.path) as f:
+            text = f.read()
+        self.assertTrue(text.startswith('[display]\npanel = "epd296x128"\nrotation = "cw90"\nautodetect = true\n'))
+        self.assertIn("\n[thermal]\nsoc_max = 80.0\n", text)
+        self.assertTrue(text.endswith("\n[displays.status.spi]\ncs = 1\n"))
+
+        save_config_values({"spi_speed_hz": "0x1000000", "register_access": "yes"}, self.path, "status")
+        status = load_config(self.path, "status")
+        self.assertEqual((status.spi.speed_hz, status.register_access), (0x1000000, True))
+        for bad in ({"rotate": "cw90"}, {"autodetect": "maybe"}, {"busy_retries": "two"}):
+            with self.subTest(settings=bad):
+                with self.assertRaises(DisplayError):
+                    save_config_values(bad, self.path)
+        with self.assertRaises(DisplayError):
+            save_config_values({"rotation": "none"}, self.path, "front")
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_migrate(self):
+        """Test a key=value file converts to tables, with its additional displays."""
+        source = os.path.join(self.tmpdir.name, "eink.conf")
+        with open(source, "w") as f:
+            f.write("# Mounted upside down\nrotation = 180\nautodetect = no\nspi_bus = 1\nthermal_soc_max = 75\n"
+                    "\n[status]\npanel = epd296x128\nspi_cs = 1\n")
+        migrate_config(source, self.path)
+        with open(self.path) as f:
+            self.assertEqual(f.read(), '[display]\nrotation = "180"\nautodetect = false\n\n[spi]\nbus = 1\n\n'
+                                       '[thermal]\nsoc_max = 75.0\n\n[displays.status.display]\n'
+                                       'panel = "epd296x128"\n\n[displays.status.spi]\ncs = 1\n')
+        for name in list_displays(source):
+            self.assertEqual(load_config(self.path, name), load_config(source, name))
+
+        with open(source, "a") as f:
+            f.write("brightness = 3\n")
+        with self.assertRaisesRegex(DisplayError, "unknown setting 'brightness'"):
+            migrate_config(source, self.path)
+        with self.assertRaises(DisplayError):
+            migrate_config(os.path.join(self.tmpdir.name, "missing.conf"), self.path)
+
+    def test_default_path(self):
+        """Test eink.toml is the default, with eink.conf read on devices that only have that."""
+        legacy = os.path.join(self.tmpdir.name, "eink.conf")
+        self.write('[display]\nrotation = "cw90"\n')
+        with open(legacy, "w") as f:
+            f.write("rotation = 180\n")
+        with patch.dict(os.environ, {}, clear=True), \
+                patch("distiller_cm5_sdk.hardware.eink.config.CONFIG_PATH", legacy), \
+                patch("distiller_cm5_sdk.hardware.eink.config.STRUCTURED_CONFIG_PATH", self.path):
+            self.assertEqual(load_config().rotation, RotationMode.CW_90)
+            os.remove(self.path)
+            self.assertEqual(load_config().rotation, RotationMode.ROTATE_180)
+            os.remove(legacy)
+            save_config_values({"rotation": "ccw90"})
+            self.assertTrue(os.path.exists(self.path))
+            self.assertEqual(load_config().rotation, RotationMode.CCW_90)
+
+
+class TestConfigHandle(unittest.TestCase):
+    """Test cases for per-scope settings that leave the file and environment alone."""
+
+    @patch.dict(os.environ, {ROTATION_ENV_VAR: "180", "DISTILLER_EINK_BACKEND": "mock"}, clear=True)
+    def test_handle_copies(self):
+        """Test a handle is unaffected by changes to the config it came from or gave out."""
+        config = DisplayConfig(backend="mock")
+        handle = DisplayConfigHandle(config)
+        config.rotation = RotationMode.CW_90
+        handle.config.busy_retries = 5
+        self.assertEqual((handle.config.rotation, handle.config.busy_retries), (RotationMode.NONE, 1))
+        changed = handle.with_changes(rotation=RotationMode.CCW_90, image_options=ImageOptions(threshold=90))
+        self.assertEqual(handle.config.rotation, RotationMode.NONE)
+        self.assertEqual((changed.config.rotation, changed.image_options.threshold), (RotationMode.CCW_90, 90))
+        self.assertEqual(DisplayConfigHandle().config.rotation, RotationMode.ROTATE_180)    # from the environment
+        with self.assertRaises(TypeError):
+            handle.with_changes(colour=True)
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_BACKEND": "mock", "DISTILLER_EINK_CONFIG": os.devnull}, clear=True)
+    def test_scopes(self):
+        """Test scoped handles are the default per thread, nest, and end with their block."""
+        from distiller_cm5_sdk.hardware.eink import Display
+
+        portrait = DisplayConfigHandle(DisplayConfig(backend="mock", image_options=ImageOptions(threshold=100)))
+        landscape = portrait.with_changes(rotation=RotationMode.CW_90, image_options=ImageOptions(threshold=150))
+        barrier = threading.Barrier(2)
+        seen = {}
+
+        def worker(name, handle):
+            with handle.scope():
+                barrier.wait()          # both scopes active at once
+                display = Display(auto_init=False)
+                seen[name] = (display.config.rotation, default_image_options().threshold)
+                display.close()
+
+        threads = [threading.Thread(target=worker, args=args) for args in (("p", portrait), ("l", landscape))]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+        self.assertEqual(seen, {"p": (RotationMode.NONE, 100), "l": (RotationMode.CW_90, 150)})
+
+        self.assertIsNone(current_config_handle())
+        with portrait.scope():
+            with landscape.scope():
+                self.assertIs(current_config_handle(), landscape)
+            self.assertIs(current_config_handle(), portrait)
+            display = Display(auto_init=False, config=landscape)    # an explicit config wins
+            self.assertEqual(display.config.rotation, RotationMode.CW_90)
+            display.close()
+            self.assertEqual(default_image_options(landscape).threshold, 150)
+        self.assertIsNone(current_config_handle())
+        self.assertEqual(default_image_options().threshold, 128)
+
+
+class TestConfigReload(unittest.TestCase):
+    """Test cases for reloading the config of an open display."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "eink.toml")
+        self.write('[display]\nbackend = "mock"\n')
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def write(self, text):
+        # Replaced, as editors and save_config() do
+        with open(self.path + ".tmp", "w") as f:
+            f.write(text)
+        os.replace(self.path + ".tmp", self.path)
+
+    def open(self):
+        from distiller_cm5_sdk.hardware.eink import Display
+        display = Display(config=load_config(self.path))
+        self.addCleanup(display.close)
+        events = []
+        display.set_event_callback(events.append)
+        return display, events
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_reload(self):
+        """Test live settings are applied and reported, and the others left for reopening."""
+        from distiller_cm5_sdk.hardware.eink.display import DisplayEventType
+
+        display, events = self.open()
+        self.assertEqual(display.reload_config(self.path), ([], []))
+        self.assertEqual(events, [])
+
+        self.write('[display]\nbackend = "mock"\nrotation = "cw90"\npanel = "epd296x128"\n'
+                   '[processing]\ndither = "ordered"\n[refresh]\nfull_refresh_every = 5\n'
+                   '[thermal]\ninterval = 0\n')
+        with patch("sys.stdout"):
+            applied, pending = display.reload_config(self.path)
+        self.assertEqual(applied, ["dither", "full_refresh_every", "rotation", "thermal_interval"])
+        self.assertEqual(pending, ["panel"])
+        self.assertEqual(display.get_dimensions(), (250, 128))
+        self.assertEqual(display.config.image_options.dither, DitherMode.ORDERED)
+        self.assertEqual(display.config.panel, PanelType.EPD128x250)
+        self.assertEqual((display.ghosting.full_every, display.thermal.enabled), (5, False))
+        self.assertEqual([event.type for event in events], [DisplayEventType.CONFIG_RELOADED])
+        self.assertEqual(events[0].message,
+                         "applied dither, full_refresh_every, rotation, thermal_interval; on reopen panel")
+
+        self.write('[display]\nbackend = "mock"\nrotation = "sideways"\n')
+        with self.assertRaises(DisplayError):
+            display.reload_config(self.path)
+        self.assertEqual(display.config.rotation, RotationMode.CW_90)
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_watch(self):
+        """Test the watcher reloads on file changes, through inotify and by polling, and on SIGHUP."""
+        import signal
+        import time
+
+        def wait_for(condition):
+            deadline = time.monotonic() + 5
+            while not condition() and time.monotonic() < deadline:
+                time.sleep(0.05)
+
+        for options in ({}, {"poll_interval_s": 0.05}):
+            with self.subTest(**options):
+                self.write('[display]\nbackend = "mock"\n')
+                display, events = self.open()
+                display.watch_config(self.path, sighup=True, **options)
+                self.write('[display]\nbackend = "mock"\nrotation = "180"\n')
+                wait_for(lambda: events)
+                self.assertEqual(display.config.rotation, RotationMode.ROTATE_180)
+
+                handler = signal.getsignal(signal.SIGHUP)
+                with patch.object(display, "reload_config") as reload:
+                    handler(signal.SIGHUP, None)
+                    wait_for(lambda: reload.called)
+                    reload.assert_called_with(self.path)
+                display.close()
+                self.assertEqual(signal.getsignal(signal.SIGHUP), signal.SIG_DFL)
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
@@ -0,0 +1,156 @@
+#!/usr/bin/env python3
+"""
+Display daemon and client unit tests for CM5 SDK.
+"""
+
+import os
+import stat
+import sys
+import tempfile
+import threading
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.device.lock import ScreenLock, hash_pin
+from distiller_cm5_sdk.diagnostics.audit import disable_audit_log, enable_audit_log, query_audit_log
+from distiller_cm5_sdk.hardware.eink import Display, DisplayClient, DisplayError, DisplayMode, tag_updates
+from distiller_cm5_sdk.hardware.eink.display import UNTAGGED
+from distiller_cm5_sdk.hardware.eink.daemon import DisplayServer
+from distiller_cm5_sdk.hardware.eink.graphics import Framebuffer
+
+
+class TestDisplayDaemon(unittest.TestCase):
+    """Test cases for drawing through the daemon."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.socket_path = os.path.join(self.tmpdir.name, "run", "eink.sock")
+        self.display = Display(backend="mock")
+        self.server = DisplayServer(self.display, self.socket_path, mode=0o600)
+        ready = threading.Event()
+        self.thread = threading.Thread(target=self.server.run, args=(ready,))
+        self.thread.start()
+        self.assertTrue(ready.wait(5))
+
+    def tearDown(self):
+        self.server.stop()
+        self.thread.join(5)
+        self.display.close()
+        self.tmpdir.cleanup()
+
+    def test_draw(self):
+        """Test frames, commands and screenshots round-trip through the socket."""
+        self.assertEqual(stat.S_IMODE(os.stat(self.socket_path).st_mode), 0o600)
+        with DisplayClient(self.socket_path) as client:
+            client.ping()
+            self.assertIsNone(client.get_framebuffer())
+            width, height = client.get_dimensions()
+            self.assertEqual(client.get_info()["backend"], "mock")
+            fb = Framebuffer(width, height)
+            fb.draw_text(4, 4, "Hello")
+            client.display_image(fb.to_bytes(), DisplayMode.PARTIAL)
+            self.assertEqual(client.get_framebuffer(), fb.to_bytes())
+            client.sleep()
+            self.assertTrue(self.display.is_asleep())
+            client.clear()
+            self.assertEqual(client.get_framebuffer(), Framebuffer(width, height).to_bytes())
+
+            with self.assertRaisesRegex(DisplayError, "expected 4000"):
+                client.display_image(b"\x00" * 10)
+            with self.assertRaisesRegex(DisplayError, "unknown command"):
+                client.request("reboot")
+            with self.assertRaisesRegex(DisplayError, "invalid mode"):
+                client.request("display", fb.to_bytes(), mode="fast")
+            client.ping()
+
+    def test_clients(self):
+        """Test several clients draw at once, each request carried out whole."""
+        errors = []
+
+        def draw(value):
+            try:
+                with DisplayClient(self.socket_path) as client:
+                    for _ in range(5):
+                        client.display_image(bytes([value]) * 4000, DisplayMode.PARTIAL)
+            except DisplayError as e:
+                errors.append(e)
+
+        threads = [threading.Thread(target=draw, args=(value,)) for value in (0x00, 0xFF, 0x0F)]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join(10)
+        self.assertEqual(errors, [])
+        self.assertIn(self.display.get_framebuffer(), [bytes([value]) * 4000 for value in (0x00, 0xFF, 0x0F)])
+
+    def test_tags(self):
+        """Test updates are tagged with the client's tag or process name and its process ID."""
+        audit_path = os.path.join(self.tmpdir.name, "audit.jsonl")
+        enable_audit_log(audit_path)
+        self.addCleanup(disable_audit_log)
+        frame = Framebuffer(128, 250).to_bytes()
+        pid = f"(pid {os.getpid()})"
+        with DisplayClient(self.socket_path, tag="weather") as client:
+            self.assertIsNone(client.get_info()["last_update"])
+            client.display_image(frame, DisplayMode.PARTIAL)
+            last = client.get_info()["last_update"]
+            self.assertEqual((last["tag"], last["mode"]), (f"weather {pid}", "partial"))
+            with tag_updates("alarm"):
+                client.clear()
+            self.assertEqual(client.get_info()["last_update"]["tag"], f"alarm {pid}")
+        with DisplayClient(self.socket_path) as client:
+            client.display_image(frame)
+        name = self.display.get_info()["last_update"]["tag"]
+        self.assertRegex(name, r"^\S.* \(pid \d+\)$")
+        self.assertNotIn("weather", name)
+
+        self.display.display_image(frame)
+        self.display.tag = "local"
+        self.display.display_image(frame)
+        self.assertEqual(self.display.get_update_counts(),
+                         {f"weather {pid}": 1, f"alarm {pid}": 1, name: 1, UNTAGGED: 1, "local": 1})
+        self.assertEqual([entry.details.get("tag") for entry in query_audit_log(path=audit_path)],
+                         [f"weather {pid}", f"alarm {pid}", name, None, "local"])
+
+    def test_lock(self):
+        """Test a locked daemon refuses frames and screenshots until a client unlocks it with the PIN."""
+        self.server.lock = ScreenLock(hash_pin("2580"), max_attempts=2)
+        frame = Framebuffer(128, 250).to_bytes()
+        with DisplayClient(self.socket_path) as client:
+            with self.assertRaisesRegex(DisplayError, "screen is locked"):
+                client.display_image(frame)
+            with self.assertRaisesRegex(DisplayError, "screen is locked"):
+                client.get_framebuffer()
+            self.assertIsNone(self.display.get_framebuffer())
+            client.ping()
+            self.assertEqual(client.get_info()["backend"], "mock")
+
+            with self.assertRaisesRegex(DisplayError, "wrong PIN"):
+                client.unlock("0000")
+            client.unlock("2580")
+            client.display_image(frame)
+            self.assertEqual(client.get_framebuffer(), frame)
+            client.lock()
+            with self.assertRaisesRegex(DisplayError, "screen is locked"):
+                client.clear()
+
+        self.server.lock = None
+        with DisplayClient(self.socket_path) as client:
+            with self.assertRaisesRegex(DisplayError, "no screen lock"):
+                client.unlock("2580")
+
+    def test_stop(self):
+        """Test stopping removes the socket, and clients then fail with DisplayError."""
+        self.server.stop()
+        self.thread.join(5)
+        self.assertFalse(self.thread.is_alive())
+        self.assertFalse(os.path.exists(self.socket_path))
+        with self.assertRaisesRegex(DisplayError, "not reachable"):
+            DisplayClient(self.socket_path)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,89 @@
+#!/usr/bin/env python3
+"""
+Frame-diff stream unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.diffstream import (
+    DiffDecoder, DiffEncoder, DiffStreamError, rle_decode, rle_encode,
+)
+from distiller_cm5_sdk.hardware.eink.graphics import Color, Framebuffer
+
+
+class TestDiffStream(unittest.TestCase):
+    """Test cases for encoding frames as diffs and rebuilding them."""
+
+    def test_round_trip(self):
+        """Test a keyframe, then only the changed windows, rebuild every frame."""
+        encoder = DiffEncoder(128, 250)
+        decoder = DiffDecoder()
+        fb = Framebuffer(128, 250)
+        fb.draw_text(4, 4, "12:00")
+        first = encoder.encode(fb)
+        self.assertLess(len(first), 4000 // 4)                         # mostly white, run-length encoded
+        self.assertEqual(decoder.decode(first), fb.to_bytes())
+        self.assertIsNone(encoder.encode(fb))                          # unchanged
+
+        fb.draw_text(4, 4, "12:01")
+        fb.draw_rect(100, 230, 10, 10, fill=True)
+        diff = encoder.encode(fb)
+        # Header flags and window count: a diff of two windows, the status line and the corner
+        self.assertEqual((diff[5], int.from_bytes(diff[14:16], "little")), (0, 2))
+        self.assertLess(len(diff), 100)
+        self.assertEqual(decoder.decode(diff), fb.to_bytes())
+        self.assertEqual(decoder.frame, fb.to_bytes())
+
+        raw = DiffEncoder(128, 250, rle=False).encode(fb.to_bytes())
+        self.assertGreater(len(raw), 4000)
+        self.assertEqual(DiffDecoder().decode(raw), fb.to_bytes())
+
+        # Changing most of the frame sends a keyframe instead
+        noise = Framebuffer(128, 250, data=os.urandom(4000))
+        message = encoder.encode(noise)
+        self.assertEqual((message[5], int.from_bytes(message[14:16], "little")), (1, 1))
+        self.assertEqual(DiffDecoder().decode(message), noise.to_bytes())
+
+    def test_lost_message(self):
+        """Test a decoder that missed a message waits for the keyframe the encoder is asked for."""
+        encoder = DiffEncoder(16, 8)
+        decoder = DiffDecoder()
+        fb = Framebuffer(16, 8)
+        decoder.decode(encoder.encode(fb))
+        fb.set_pixel(1, 1, Color.BLACK)
+        encoder.encode(fb)                                              # lost on the way
+        fb.set_pixel(2, 2, Color.BLACK)
+        with self.assertRaises(DiffStreamError):
+            decoder.decode(encoder.encode(fb))
+        fb.set_pixel(3, 3, Color.BLACK)
+        with self.assertRaises(DiffStreamError):
+            decoder.decode(encoder.encode(fb))                          # still waiting
+
+        encoder.request_keyframe()
+        self.assertEqual(decoder.decode(encoder.encode(fb)), fb.to_bytes())
+
+        for bad in (b"PNG", b"EDIF\x01", encoder.encode(Framebuffer(16, 8))[:-1]):
+            with self.subTest(message=bad):
+                with self.assertRaises(DiffStreamError):
+                    DiffDecoder().decode(bad)
+        with self.assertRaises(DiffStreamError):
+            encoder.encode(bytes(15))
+
+    def test_rle(self):
+        """Test run-length encoding round-trips runs, literals and block limits."""
+        for data in (b"", b"\x00", b"\x00" * 300, b"ab", bytes(range(200)), b"aab" * 50 + b"\xff" * 129):
+            with self.subTest(size=len(data)):
+                self.assertEqual(rle_decode(rle_encode(data), len(data)), data)
+        self.assertEqual(rle_encode(b"\xff" * 128), b"\x81\xff")
+        with self.assertRaises(DiffStreamError):
+            rle_decode(b"\x05ab", 6)
+
+
+if __name__ == "__main__":
+    unittest.main()
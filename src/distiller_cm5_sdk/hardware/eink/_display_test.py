@@ -3,12 +3,20 @@
 Display module unit tests for CM5 SDK.
 """
 
+import sys
 import unittest
+import ctypes
 import os
+import subprocess
 import tempfile
-from unittest.mock import Mock, patch, MagicMock
+from pathlib import Path
+from unittest.mock import Mock, call, patch, MagicMock
 
-from display import Display, DisplayError, DisplayMode, display_png, clear_display, get_display_info
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.config import DisplayConfig, SpiConfig
+from distiller_cm5_sdk.hardware.eink.display import DISPLAY_SDK_ABI_VERSION, DISPLAY_SDK_VERSION, Display, DisplayError, DisplayErrorCode, DisplayTimeoutError, DisplayMode, DisplayStatus, PixelFormat, ProcessingOptions, RotationMode, _DisplayResult, display_png, clear_display, get_display_info
 
 
 class TestDisplay(unittest.TestCase):
@@ -18,6 +26,8 @@ class TestDisplay(unittest.TestCase):
         """Set up test fixtures."""
         # Mock the library loading to avoid hardware dependencies
         self.mock_lib = Mock()
+        self.mock_lib.display_sdk_abi_version.return_value = DISPLAY_SDK_ABI_VERSION
+        self.mock_lib.display_sdk_version.return_value = DISPLAY_SDK_VERSION.encode()
         self.mock_lib.display_init.return_value = True
         self.mock_lib.display_clear.return_value = True
         self.mock_lib.display_image_png.return_value = True
@@ -25,9 +35,13 @@ class TestDisplay(unittest.TestCase):
         self.mock_lib.convert_png_to_1bit.return_value = True
         self.mock_lib.display_cleanup.return_value = None
         self.mock_lib.display_sleep.return_value = None
+        self.mock_lib.display_set_rotation.return_value = True
         
         # Mock dimensions - return void, but we'll override the method
         self.mock_lib.display_get_dimensions.return_value = None
+        
+        # A library without handles, driving its single panel; test_handles covers handles
+        del self.mock_lib.display_open
     
     @patch('ctypes.CDLL')
     @patch('os.path.exists')
@@ -88,6 +102,121 @@ class TestDisplay(unittest.TestCase):
         
         self.mock_lib.display_image_raw.assert_called_once()
     
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_display_png_bytes(self, mock_exists, mock_cdll):
+        """Test displaying an in-memory PNG without a temp file."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_image_from_memory.return_value = True
+        
+        display = Display(auto_init=True)
+        png_data = b"\x89PNG\r\n\x1a\n" + bytes(32)
+        display.display_image_bytes(png_data, DisplayMode.FULL)
+        
+        self.mock_lib.display_image_from_memory.assert_called_once()
+        args = self.mock_lib.display_image_from_memory.call_args[0]
+        self.assertEqual(args[1], len(png_data))
+        self.assertEqual(bytes(args[0]), png_data)
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_display_bytes_rejects_empty(self, mock_exists, mock_cdll):
+        """Test that empty in-memory image data is rejected."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        
+        display = Display(auto_init=True)
+        with self.assertRaises(DisplayError):
+            display.display_image_bytes(b"")
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_show_file(self, mock_exists, mock_cdll):
+        """Test one-shot init+process+display call."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_show_file.return_value = _DisplayResult(DisplayStatus.OK, b"OK")
+        
+        display = Display(auto_init=False)
+        display.show_file("image.png", ProcessingOptions(threshold=100, rotate=True))
+        
+        self.assertTrue(display.is_initialized())
+        self.mock_lib.display_init.assert_not_called()
+        self.mock_lib.display_show_file.assert_called_once()
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_show_file_error(self, mock_exists, mock_cdll):
+        """Test that a structured failure surfaces as DisplayError."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_show_file.return_value = _DisplayResult(
+            DisplayStatus.PROCESS_FAILED, b"Image dimensions (10x10) don't match expected (128x250)")
+        
+        display = Display(auto_init=False)
+        with self.assertRaises(DisplayError) as ctx:
+            display.show_file("image.png")
+        
+        self.assertIn("PROCESS_FAILED", str(ctx.exception))
+        self.assertIn("10x10", str(ctx.exception))
+        self.assertFalse(display.is_initialized())
+        
+        # Bad arguments are refused before initializing; a refresh that timed out ran after it
+        self.mock_lib.display_show_file.return_value = _DisplayResult(
+            DisplayStatus.INVALID_ARGUMENT, b"Invalid processing_options_t struct_size")
+        with self.assertRaises(DisplayError):
+            display.show_file("image.png")
+        self.assertFalse(display.is_initialized())
+        self.mock_lib.display_show_file.return_value = _DisplayResult(
+            DisplayStatus.TIMEOUT, b"Display stayed busy after reset and 1 retries")
+        with self.assertRaises(DisplayTimeoutError):
+            display.show_file("image.png")
+        self.assertTrue(display.is_initialized())
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_display_pixels(self, mock_exists, mock_cdll):
+        """Test raw pixel-buffer input."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_pixels.return_value = True
+        
+        display = Display(auto_init=True)
+        gray = bytes(Display.WIDTH * Display.HEIGHT)
+        display.display_pixels(gray, Display.WIDTH, Display.HEIGHT, PixelFormat.GRAY8)
+        
+        self.mock_lib.display_pixels.assert_called_once()
+        args = self.mock_lib.display_pixels.call_args[0]
+        self.assertEqual(args[1:4], (Display.WIDTH, Display.HEIGHT, int(PixelFormat.GRAY8)))
+        
+        # RGB buffer with the wrong size is rejected before reaching the library
+        with self.assertRaises(DisplayError):
+            display.display_pixels(gray, Display.WIDTH, Display.HEIGHT, PixelFormat.RGB888)
+        with self.assertRaises(DisplayError):
+            display.display_pixels([0] * len(gray), Display.WIDTH, Display.HEIGHT, PixelFormat.GRAY8)
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_display_pixels_buffers(self, mock_exists, mock_cdll):
+        """Test writable buffers reach the library in place and strided views packed in row order."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_pixels.return_value = True
+        display = Display(auto_init=True)
+        
+        # 2-D view, like a (height, width) numpy array
+        gray = bytearray(Display.WIDTH * Display.HEIGHT)
+        display.display_pixels(memoryview(gray).cast("B", (Display.HEIGHT, Display.WIDTH)),
+                               Display.WIDTH, Display.HEIGHT, PixelFormat.GRAY8)
+        gray[0] = 0x7F
+        self.assertEqual(self.mock_lib.display_pixels.call_args[0][0][0], 0x7F)
+        
+        # Every other byte of a buffer twice the size, like a numpy slice
+        wide = bytearray(b"\x10\x20" * (Display.WIDTH * Display.HEIGHT))
+        display.display_pixels(memoryview(wide)[::2], Display.WIDTH, Display.HEIGHT, PixelFormat.GRAY8)
+        self.assertEqual(set(bytes(self.mock_lib.display_pixels.call_args[0][0])), {0x10})
+    
     @patch('ctypes.CDLL')
     @patch('os.path.exists')
     def test_context_manager(self, mock_exists, mock_cdll):
@@ -121,6 +250,277 @@ class TestDisplay(unittest.TestCase):
         self.assertEqual(width, 128)
         self.assertEqual(height, 250)
     
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_config_rotation_applied(self, mock_exists, mock_cdll):
+        """Test the configured rotation is passed to the library on open."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        
+        Display(auto_init=False, config=DisplayConfig(rotation=RotationMode.CW_90))
+        self.mock_lib.display_set_rotation.assert_called_once_with(int(RotationMode.CW_90))
+        
+        self.mock_lib.display_set_rotation.return_value = False
+        with self.assertRaises(DisplayError):
+            Display(auto_init=False, config=DisplayConfig(rotation=RotationMode.NONE))
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_sleep_and_wake(self, mock_exists, mock_cdll):
+        """Test wake() leaves sleep through the library, or re-runs the init sequence with an older library."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_init.return_value = True
+        self.mock_lib.display_wake.return_value = True
+        
+        display = Display()
+        display.sleep()
+        self.mock_lib.display_sleep.assert_called_once()
+        display.wake()
+        self.mock_lib.display_wake.assert_called_once()
+        self.mock_lib.display_cleanup.assert_not_called()
+        
+        del self.mock_lib.display_wake
+        display.sleep()
+        display.wake()
+        self.mock_lib.display_cleanup.assert_called_once()
+        self.assertEqual(self.mock_lib.display_init.call_count, 2)
+        self.assertTrue(display.is_initialized())
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_spi_config_passed_to_library(self, mock_exists, mock_cdll):
+        """Test non-default wiring is passed to the library before init, and default wiring is not."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_set_spi_config.return_value = True
+        
+        Display(auto_init=False, config=DisplayConfig())
+        self.mock_lib.display_set_spi_config.assert_not_called()
+        
+        Display(auto_init=False, config=DisplayConfig(spi=SpiConfig(bus=1, busy_pin=24)))
+        struct = self.mock_lib.display_set_spi_config.call_args[0][0]._obj
+        self.assertEqual((struct.bus, struct.cs, struct.busy_pin, struct.gpio_chip), (1, 0, 24, b"gpiochip0"))
+        
+        self.mock_lib.display_set_spi_config.return_value = False
+        with self.assertRaises(DisplayError):
+            Display(auto_init=False, config=DisplayConfig(spi=SpiConfig(bus=1)))
+        with self.assertRaises(DisplayError):
+            Display(auto_init=False, config=DisplayConfig(spi=SpiConfig(dc_pin=9)))
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_handles(self, mock_exists, mock_cdll):
+        """Test each Display opens a library handle for its wiring and passes it to every call."""
+        mock_exists.return_value = True
+        lib = Mock()
+        lib.display_sdk_abi_version.return_value = DISPLAY_SDK_ABI_VERSION
+        lib.display_sdk_version.return_value = DISPLAY_SDK_VERSION.encode()
+        lib.display_open.side_effect = [1001, 1002, None]
+        lib.display_get_id.side_effect = lambda handle: handle - 1000
+        mock_cdll.return_value = lib
+        
+        first = Display(config=DisplayConfig())
+        second = Display(config=DisplayConfig(spi=SpiConfig(cs=1, dc_pin=22, reset_pin=23, busy_pin=24)))
+        self.assertEqual(lib.display_open.call_args[0][0]._obj.cs, 1)
+        lib.display_h_init.assert_has_calls([call(1001), call(1002)])
+        lib.display_init.assert_not_called()
+        self.assertEqual(lib.display_h_image_raw.argtypes[0], ctypes.c_void_p)
+        self.assertEqual((first.native_id, second.native_id), (1, 2))
+        
+        second.clear()
+        lib.display_h_clear.assert_called_once_with(1002)
+        first.display_image(b"\x00" * first.ARRAY_SIZE, DisplayMode.PARTIAL)
+        self.assertEqual(lib.display_h_image_raw.call_args[0][0], 1001)
+        lib.display_image_raw.assert_not_called()
+        
+        with self.assertRaises(DisplayError):
+            Display(config=DisplayConfig())     # display_open() rejected the wiring
+        
+        second.close()
+        lib.display_h_cleanup.assert_called_once_with(1002)
+        del second
+        lib.display_close.assert_called_once_with(1002)
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_busy_timeout(self, mock_exists, mock_cdll):
+        """Test the busy policy is passed to the library and a timed-out update raises DisplayTimeoutError."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_set_busy_policy.return_value = True
+        
+        display = Display(config=DisplayConfig(busy_timeout_ms=3000, busy_retries=0))
+        self.mock_lib.display_set_busy_policy.assert_called_once_with(3000, 0)
+        
+        self.mock_lib.display_clear.return_value = False
+        self.mock_lib.display_get_last_status.return_value = int(DisplayStatus.TIMEOUT)
+        with self.assertRaises(DisplayTimeoutError):
+            display.clear()
+        self.mock_lib.display_get_last_status.return_value = int(DisplayStatus.UPDATE_FAILED)
+        with self.assertRaises(DisplayError) as ctx:
+            display.clear()
+        self.assertNotIsInstance(ctx.exception, DisplayTimeoutError)
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_ghosting_policy(self, mock_exists, mock_cdll):
+        """Test every Nth partial refresh is done as a full refresh and deghost() flushes black and white."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        
+        display = Display(config=DisplayConfig(full_refresh_every=2))
+        frame = b"\x00" * display.ARRAY_SIZE
+        for _ in range(3):
+            display.display_image(frame, DisplayMode.PARTIAL)
+        display.display_image(frame, DisplayMode.PARTIAL)
+        modes = [call[0][1] for call in self.mock_lib.display_image_raw.call_args_list]
+        self.assertEqual(modes, [DisplayMode.PARTIAL, DisplayMode.PARTIAL, DisplayMode.FULL, DisplayMode.PARTIAL])
+        
+        self.mock_lib.display_image_raw.reset_mock()
+        display.deghost(cycles=2)
+        calls = self.mock_lib.display_image_raw.call_args_list
+        self.assertEqual([call[0][1] for call in calls], [DisplayMode.FULL] * 4)
+        self.assertEqual([call[0][0][0] for call in calls], [0x00, 0xFF, 0x00, 0xFF])
+        self.assertEqual(display.ghosting.partials, 0)
+    
+    def test_ghosting_interval(self):
+        """Test a partial refresh becomes full once the interval since the last full refresh has passed."""
+        from distiller_cm5_sdk.hardware.eink.ghosting import GhostingPolicy
+        policy = GhostingPolicy(full_interval_s=60)
+        policy.record(DisplayMode.FULL, now=100.0)
+        self.assertEqual(policy.mode_for(DisplayMode.PARTIAL, now=159.0), DisplayMode.PARTIAL)
+        self.assertEqual(policy.mode_for(DisplayMode.PARTIAL, now=160.0), DisplayMode.FULL)
+        self.assertEqual(GhostingPolicy().mode_for(DisplayMode.PARTIAL), DisplayMode.PARTIAL)
+    
+    def test_thermal_policy(self):
+        """Test throttling starts beyond a limit, ends with hysteresis and spaces refreshes."""
+        from distiller_cm5_sdk.hardware.eink.thermal import CHECK_INTERVAL_S, ThermalPolicy, ThrottleReason
+        readings = {"soc": 60.0, "ambient": None}
+        changes = []
+        now = [0.0]
+        policy = ThermalPolicy(soc_max_c=80, ambient_max_c=45, ambient_min_c=0, interval_s=10,
+                               read_soc=lambda: readings["soc"], read_ambient=lambda: readings["ambient"],
+                               on_change=lambda reason, celsius: changes.append((reason, celsius)),
+                               clock=lambda: now[0])
+        self.assertFalse(policy.check())
+        policy.record()
+        self.assertEqual(policy.mode_for(DisplayMode.FULL), DisplayMode.FULL)
+        self.assertEqual(policy.delay(), 0.0)
+        
+        readings["soc"] = 85.0
+        self.assertFalse(policy.check(now=1.0))             # not read again within CHECK_INTERVAL_S
+        now[0] = CHECK_INTERVAL_S
+        self.assertTrue(policy.check())
+        self.assertEqual(changes, [(ThrottleReason.SOC_HOT, 85.0)])
+        self.assertEqual(policy.mode_for(DisplayMode.FULL), DisplayMode.PARTIAL)
+        self.assertEqual(policy.delay(), 10.0 - CHECK_INTERVAL_S)
+        
+        readings["soc"], readings["ambient"] = 76.0, -5.0    # another limit takes over
+        now[0] += CHECK_INTERVAL_S
+        self.assertTrue(policy.check())
+        self.assertEqual(policy.reason, ThrottleReason.AMBIENT_COLD)
+        readings["soc"], readings["ambient"] = 78.0, 20.0    # SoC within HYSTERESIS_C of its limit
+        now[0] += CHECK_INTERVAL_S
+        self.assertTrue(policy.check())
+        self.assertEqual(policy.reason, ThrottleReason.SOC_HOT)
+        readings["soc"] = 70.0
+        now[0] += CHECK_INTERVAL_S
+        self.assertFalse(policy.check())
+        self.assertEqual(changes[1:], [(None, 20.0)])
+        self.assertFalse(ThermalPolicy(interval_s=0, read_soc=lambda: 100.0).check())
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_thermal_throttling(self, mock_exists, mock_cdll):
+        """Test a throttled display waits between refreshes, prefers partial ones and reports throttle events."""
+        from distiller_cm5_sdk.hardware.eink.display import DisplayEventType
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        
+        display = Display(config=DisplayConfig(thermal_soc_max_c=70, thermal_interval_s=30))
+        events, sleeps = [], []
+        display.set_event_callback(events.append)
+        display.thermal.read_soc = lambda: 75.0
+        display.thermal._sleep = sleeps.append
+        frame = b"\x00" * display.ARRAY_SIZE
+        with patch('builtins.print'):
+            display.display_image(frame, DisplayMode.FULL)
+            display.display_image(frame, DisplayMode.FULL)
+        modes = [call[0][1] for call in self.mock_lib.display_image_raw.call_args_list]
+        self.assertEqual(modes, [DisplayMode.PARTIAL, DisplayMode.PARTIAL])
+        self.assertEqual(len(sleeps), 1)
+        self.assertAlmostEqual(sleeps[0], 30, delta=1)
+        self.assertEqual([(event.type, event.message) for event in events],
+                         [(DisplayEventType.THROTTLE_STARTED, "soc hot at 75.0 °C")])
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_abi_version_mismatch(self, mock_exists, mock_cdll):
+        """Test that a library with a different ABI version is refused."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.mock_lib.display_sdk_abi_version.return_value = DISPLAY_SDK_ABI_VERSION + 1
+        
+        with self.assertRaises(DisplayError):
+            Display(auto_init=False)
+        self.mock_lib.display_init.assert_not_called()
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_library_version(self, mock_exists, mock_cdll):
+        """Test the library's SDK release is reported, with a warning when it differs from the bindings'."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        self.assertEqual(Display(auto_init=False).library_version, DISPLAY_SDK_VERSION)
+        
+        self.mock_lib.display_sdk_version.return_value = b"0.0.9"
+        with patch('builtins.print') as mock_print:
+            self.assertEqual(Display(auto_init=False).library_version, "0.0.9")
+        self.assertIn("0.0.9", mock_print.call_args[0][0])
+        
+        del self.mock_lib.display_sdk_version
+        self.assertIsNone(Display(auto_init=False).library_version)
+    
+    @patch('ctypes.CDLL')
+    @patch('os.path.exists')
+    def test_error_codes(self, mock_exists, mock_cdll):
+        """Test error codes read as success or failure and failures carry the library's code and message."""
+        mock_exists.return_value = True
+        mock_cdll.return_value = self.mock_lib
+        display = Display()
+        self.assertIs(self.mock_lib.display_clear.errcheck(0, None, ()), True)
+        self.assertIs(self.mock_lib.display_clear.errcheck(int(DisplayErrorCode.SPI), None, ()), False)
+        
+        def message(buf, length):
+            ctypes.memmove(buf, b"SPI transfer to the display failed: Broken pipe\0", 48)
+            return 47
+        
+        self.mock_lib.display_clear.return_value = False
+        self.mock_lib.display_get_last_error.return_value = int(DisplayErrorCode.SPI)
+        self.mock_lib.display_get_last_error_message.side_effect = message
+        with self.assertRaises(DisplayError) as ctx:
+            display.clear()
+        self.assertEqual(ctx.exception.code, DisplayErrorCode.SPI)
+        self.assertEqual(str(ctx.exception), "Failed to clear display: SPI transfer to the display failed: Broken pipe")
+        
+        self.mock_lib.display_get_last_error.return_value = int(DisplayErrorCode.TIMEOUT)
+        with self.assertRaises(DisplayTimeoutError) as ctx:
+            display.clear()
+        self.assertEqual(ctx.exception.code, DisplayErrorCode.TIMEOUT)
+    
+    def test_generated_header_current(self):
+        """Test include/distiller_eink.h matches the library header and all versions agree."""
+        script = Path(__file__).resolve().parent / "lib" / "gen_header.py"
+        result = subprocess.run([sys.executable, str(script), "--check"], capture_output=True, text=True)
+        self.assertEqual(result.returncode, 0, result.stderr)
+    
+    def test_processing_options_struct_size(self):
+        """Test that ProcessingOptions reports its own size to the library."""
+        options = ProcessingOptions(threshold=90)
+        self.assertEqual(options.struct_size, ctypes.sizeof(ProcessingOptions))
+        self.assertEqual(options.threshold, 90)
+    
     def test_display_constants(self):
         """Test display constants."""
         self.assertEqual(Display.WIDTH, 128)
@@ -0,0 +1,208 @@
+#!/usr/bin/env python3
+"""
+Flipbook conversion and playback unit tests for CM5 SDK.
+"""
+
+import io
+import os
+import random
+import subprocess
+import sys
+import tempfile
+import threading
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.__main__ import main
+from distiller_cm5_sdk.hardware.eink.display import DisplayMode
+from distiller_cm5_sdk.hardware.eink.flipbook import (
+    Flipbook, FlipbookError, FlipbookPlayer, build_flipbook, convert_video, read_video_frames,
+)
+from distiller_cm5_sdk.hardware.eink.graphics import Framebuffer
+from distiller_cm5_sdk.hardware.eink.panels import get_panel
+from distiller_cm5_sdk.hardware.eink.pngcodec import encode_png_1bit
+
+
+def moving_square(count, width=250, height=128, step=9):
+    """Frames of a 20-pixel square moving right across a fixed line."""
+    frames = []
+    for i in range(count):
+        fb = Framebuffer(width, height)
+        fb.draw_hline(0, height - 1, width)
+        fb.draw_rect(3 + i * step, 40, 20, 20, fill=True)
+        frames.append(fb)
+    return frames
+
+
+class FakeClock:
+    def __init__(self):
+        self.now = 0.0
+
+    def __call__(self):
+        return self.now
+
+
+class FakeDisplay:
+    """Records frames and modes; each refresh advances the fake clock."""
+
+    def __init__(self, clock, width=250, height=128, refresh_s=0.3):
+        self.clock = clock
+        self.size = (width, height)
+        self.refresh_s = refresh_s
+        self.updates = []
+
+    def get_dimensions(self):
+        return self.size
+
+    def display_image(self, data, mode):
+        self.updates.append((round(self.clock.now, 3), data, DisplayMode(mode)))
+        self.clock.now += self.refresh_s
+
+
+class FakeEvent:
+    """threading.Event whose waits advance the fake clock instead of blocking."""
+
+    def __init__(self, clock, stop_at=None):
+        self.clock = clock
+        self.stop_at = stop_at
+
+    def is_set(self):
+        return self.stop_at is not None and self.clock.now >= self.stop_at
+
+    def wait(self, timeout):
+        self.clock.now += timeout
+        return self.is_set()
+
+
+class TestFlipbook(unittest.TestCase):
+    """Test cases for flipbook planning, files and playback."""
+
+    def test_windows(self):
+        """Test frames are rebuilt exactly from keyframes and changed windows, at odd widths too."""
+        rng = random.Random(7)
+        frames = moving_square(6)
+        noisy = Framebuffer(250, 128, bytes(rng.getrandbits(8) for _ in range(4000)))
+        frames.append(noisy)
+        book = build_flipbook(frames, fps=0.5, panel=get_panel())
+        self.assertEqual(book.dropped, 0)
+        self.assertEqual([frame.keyframe for frame in book.frames], [True] + [False] * 5 + [True])
+        self.assertEqual(book.frames[1].window, (3, 40, 29, 20))    # old and new square
+        self.assertEqual(len(book.frames[1].pixels), (29 * 20 + 7) // 8)
+        self.assertEqual([image for image, _, _ in book.images()], [frame.to_bytes() for frame in frames])
+        self.assertEqual([mode for _, mode, _ in book.images()][:2], [DisplayMode.FULL, DisplayMode.PARTIAL])
+
+    def test_refresh_budget(self):
+        """Test frames the panel cannot keep up with are dropped, unchanged ones merged, keyframes spaced."""
+        panel = get_panel()                         # full 1.5 s, partial 0.3 s
+        frames = moving_square(20)
+        frames[12:16] = [frames[11]] * 4            # a pause
+        book = build_flipbook(frames, fps=2.5, panel=panel, keyframe_every=3)
+        self.assertEqual(len(book.frames) + book.dropped + 4, 20)
+        self.assertGreater(book.dropped, 0)
+        self.assertEqual(max(frame.duration_ms for frame in book.frames), 1900)     # held through the pause
+        for frame in book.frames:
+            self.assertGreaterEqual(frame.duration_ms,
+                                    (panel.full_refresh_s if frame.keyframe else panel.partial_refresh_s) * 1000)
+        keys = [i for i, frame in enumerate(book.frames) if frame.keyframe]
+        self.assertEqual(keys[0], 0)
+        self.assertTrue(all(b - a <= 4 for a, b in zip(keys, keys[1:])))
+        self.assertAlmostEqual(book.duration_s, 8.0, delta=0.35)   # the clip keeps its length
+
+        with self.assertRaises(FlipbookError):
+            build_flipbook([], fps=2)
+        with self.assertRaises(FlipbookError):
+            build_flipbook([Framebuffer(), Framebuffer(250, 128)])
+
+    def test_file(self):
+        """Test flipbooks survive saving and loading, and damaged files are rejected."""
+        book = build_flipbook(moving_square(4), fps=1)
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "clip.flip")
+            book.save(path)
+            loaded = Flipbook.load(path)
+        self.assertEqual((loaded.width, loaded.height, loaded.frames), (book.width, book.height, book.frames))
+
+        data = book.to_bytes()
+        for bad in (b"GIF89a", data[:20], data[:-4] + b"junk"):
+            with self.subTest(data=bad[:8]):
+                with self.assertRaises(FlipbookError):
+                    Flipbook.from_bytes(bad)
+
+    def test_player(self):
+        """Test playback keeps the flipbook's schedule, loops from its keyframe and stops."""
+        book = build_flipbook(moving_square(3), fps=1)
+        clock = FakeClock()
+        display = FakeDisplay(clock)
+        FlipbookPlayer(display, book, clock).run(loops=2, stop=FakeEvent(clock))
+        self.assertEqual([(at, mode) for at, _, mode in display.updates],
+                         [(0.0, DisplayMode.FULL), (1.5, DisplayMode.PARTIAL), (2.0, DisplayMode.PARTIAL),
+                          (3.0, DisplayMode.FULL), (4.5, DisplayMode.PARTIAL), (5.0, DisplayMode.PARTIAL)])
+        self.assertEqual([data for _, data, _ in display.updates[:3]], [fb.to_bytes() for fb in moving_square(3)])
+
+        display.updates.clear()
+        FlipbookPlayer(display, book, clock).run(loops=0, stop=FakeEvent(clock, stop_at=clock.now + 7.5))
+        self.assertEqual(len(display.updates), 7)          # stopped in the third loop
+
+        player = FlipbookPlayer(display, book)
+        player.stop()
+        display.updates.clear()
+        player.run(loops=0)
+        self.assertEqual(display.updates, [])
+        with self.assertRaises(FlipbookError):
+            FlipbookPlayer(FakeDisplay(clock, 128, 250), book)
+
+    def test_read_frames(self):
+        """Test frames come from a directory in name order, or from ffmpeg's PNG stream."""
+        frames = moving_square(3, 128, 250)
+        pngs = [encode_png_1bit(fb.to_bytes(), 128, 250) for fb in frames]
+        with tempfile.TemporaryDirectory() as tmpdir:
+            for i, png in enumerate(pngs):
+                with open(os.path.join(tmpdir, f"radar_{i:02d}.png"), "wb") as f:
+                    f.write(png)
+            with open(os.path.join(tmpdir, "notes.txt"), "w") as f:
+                f.write("not a frame")
+            self.assertEqual(read_video_frames(tmpdir), pngs)
+            book = convert_video(tmpdir, fps=0.5, width=128, height=250)
+            self.assertEqual([image for image, _, _ in book.images()], [fb.to_bytes() for fb in frames])
+
+        done = subprocess.CompletedProcess([], 0, b"".join(pngs), b"")
+        with patch("shutil.which", return_value="/usr/bin/ffmpeg"), \
+                patch("subprocess.run", return_value=done) as run:
+            self.assertEqual(read_video_frames("radar.mp4", fps=4, max_seconds=5), pngs)
+        self.assertIn("fps=4", run.call_args[0][0])
+        failed = subprocess.CompletedProcess([], 1, b"", b"radar.mp4: No such file or directory")
+        with patch("shutil.which", return_value="/usr/bin/ffmpeg"), patch("subprocess.run", return_value=failed):
+            with self.assertRaisesRegex(FlipbookError, "No such file"):
+                read_video_frames("radar.mp4")
+        with patch("shutil.which", return_value=None):
+            with self.assertRaisesRegex(FlipbookError, "needs ffmpeg"):
+                read_video_frames("radar.mp4")
+
+    @patch.dict(os.environ, {"DISTILLER_EINK_CONFIG": os.devnull}, clear=True)
+    def test_cli(self):
+        """Test distiller-eink flipbook converts offline and play shows the frames."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            for i, fb in enumerate(moving_square(4, 128, 250)):
+                with open(os.path.join(tmpdir, f"{i}.png"), "wb") as f:
+                    f.write(encode_png_1bit(fb.to_bytes(), 128, 250))
+            path = os.path.join(tmpdir, "clip.flip")
+            with patch("sys.stdout", io.StringIO()) as out:
+                self.assertEqual(main(["flipbook", tmpdir, path, "--fps", "1"]), 0)
+            self.assertIn("4 frames (1 keyframes, 0 dropped)", out.getvalue())
+
+            done = threading.Event()
+            with patch("distiller_cm5_sdk.hardware.eink.flipbook.FlipbookPlayer.run",
+                       side_effect=lambda loops: done.set()):
+                self.assertEqual(main(["--backend", "mock", "play", path]), 0)
+            self.assertTrue(done.is_set())
+            with patch("sys.stderr", io.StringIO()) as err:
+                self.assertEqual(main(["--backend", "mock", "play", os.path.join(tmpdir, "0.png")]), 1)
+            self.assertIn("Not a flipbook", err.getvalue())
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,119 @@
+#!/usr/bin/env python3
+"""
+Graphics module unit tests for CM5 SDK.
+"""
+
+import sys
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.graphics import Color, Framebuffer, measure_text
+
+
+class TestFramebuffer(unittest.TestCase):
+    """Test cases for Framebuffer drawing primitives."""
+
+    def setUp(self):
+        """Set up a small white framebuffer."""
+        self.fb = Framebuffer(16, 8)
+
+    def count_black(self, fb):
+        return sum(1 for y in range(fb.height) for x in range(fb.width) if fb.get_pixel(x, y) == 0)
+
+    def test_default_panel_size(self):
+        """Test the default framebuffer matches the panel data size."""
+        fb = Framebuffer()
+        self.assertEqual(len(fb.to_bytes()), 4000)
+        self.assertEqual(fb.to_bytes(), bytes([0xFF] * 4000))
+
+    def test_set_pixel_msb_first(self):
+        """Test pixels are packed MSB first with 1 = white."""
+        self.fb.set_pixel(0, 0, Color.BLACK)
+        self.fb.set_pixel(9, 1, Color.BLACK)
+        self.assertEqual(self.fb.data[0], 0x7F)
+        self.assertEqual(self.fb.data[3], 0xBF)  # bit 25 -> byte 3, second bit
+
+    def test_clipping(self):
+        """Test drawing outside bounds is ignored."""
+        self.fb.draw_line(-10, -10, 30, 30)
+        self.fb.draw_rect(-5, -5, 100, 100, fill=True)
+        self.assertEqual(self.count_black(self.fb), 16 * 8)
+
+    def test_line(self):
+        """Test a diagonal line sets one pixel per step."""
+        self.fb.draw_line(0, 0, 7, 7)
+        self.assertEqual(self.count_black(self.fb), 8)
+        for i in range(8):
+            self.assertEqual(self.fb.get_pixel(i, i), 0)
+
+    def test_rect_outline_and_fill(self):
+        """Test rectangle outline and fill pixel counts."""
+        self.fb.draw_rect(1, 1, 4, 3)
+        self.assertEqual(self.count_black(self.fb), 10)
+        self.fb.fill(Color.WHITE)
+        self.fb.draw_rect(1, 1, 4, 3, fill=True)
+        self.assertEqual(self.count_black(self.fb), 12)
+
+    def test_filled_circle_is_symmetric(self):
+        """Test a filled circle covers its center and is symmetric."""
+        fb = Framebuffer(21, 21)
+        fb.draw_circle(10, 10, 5, fill=True)
+        self.assertEqual(fb.get_pixel(10, 10), 0)
+        self.assertEqual(fb.get_pixel(10, 16), 1)
+        for y in range(21):
+            for x in range(21):
+                self.assertEqual(fb.get_pixel(x, y), fb.get_pixel(20 - x, y))
+
+    def test_fill_polygon(self):
+        """Test a filled square polygon matches a filled rectangle."""
+        self.fb.fill_polygon([(2, 2), (5, 2), (5, 5), (2, 5)])
+        expected = Framebuffer(16, 8)
+        expected.draw_rect(2, 2, 4, 4, fill=True)
+        self.assertEqual(self.fb.to_bytes(), expected.to_bytes())
+
+    def test_draw_text(self):
+        """Test text renders glyph columns and advances one cell per character."""
+        self.fb.draw_text(0, 0, "I-")
+        # 'I' is a vertical bar in column 2 with serifs top and bottom
+        self.assertEqual([self.fb.get_pixel(2, y) for y in range(7)], [0] * 7)
+        self.assertEqual(self.fb.get_pixel(0, 3), 1)
+        # '-' starts at x=6 and is a horizontal bar on row 3
+        self.assertEqual([self.fb.get_pixel(x, 3) for x in range(6, 11)], [0] * 5)
+        self.assertEqual(self.fb.get_pixel(6, 0), 1)
+
+    def test_measure_text(self):
+        """Test measured text matches what draw_text() draws, across lines, scripts and wrapping."""
+        self.assertEqual(measure_text("I-"), (11, 8, 7))
+        self.assertEqual(measure_text("Hi\nthere", size=2), (58, 32, 14))
+        self.assertEqual(measure_text("Grüße 20°C"), (59, 8, 7))          # one cell per character
+        self.assertEqual(measure_text("", font="5x8"), (0, 8, 7))
+        self.assertEqual(measure_text("one two three", max_width=30), (29, 24, 7))
+
+        self.fb.draw_text(0, 0, "Hi\nthere", scale=2)
+        inked = [(x, y) for y in range(self.fb.height) for x in range(self.fb.width) if not self.fb.get_pixel(x, y)]
+        width, height, _ = measure_text("Hi\nthere", size=2)
+        self.assertLess(max(x for x, _ in inked), width)
+        self.assertLess(max(y for _, y in inked), height)
+        with self.assertRaises(ValueError):
+            measure_text("Hi", font="DejaVuSans")
+        with self.assertRaises(ValueError):
+            measure_text("Hi", size=0)
+
+    def test_in_place_bytearray(self):
+        """Test drawing into an existing bytearray modifies it in place."""
+        buf = bytearray([0xFF] * 16)
+        fb = Framebuffer(16, 8, data=buf)
+        fb.set_pixel(0, 0)
+        self.assertEqual(buf[0], 0x7F)
+
+    def test_invalid_data_size(self):
+        """Test mismatched data size is rejected."""
+        with self.assertRaises(ValueError):
+            Framebuffer(16, 8, data=bytes(3))
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
@@ -0,0 +1,85 @@
+#!/usr/bin/env python3
+"""
+Multi-panel unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import threading
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.backends.mock import get_mock_transport
+from distiller_cm5_sdk.hardware.eink.config import load_config
+from distiller_cm5_sdk.hardware.eink.display import Display, DisplayError
+from distiller_cm5_sdk.hardware.eink.group import DisplayGroup, check_panels, check_wiring
+
+
+class TestDisplayGroup(unittest.TestCase):
+    """Test cases for driving several configured panels."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "eink.conf")
+        with open(self.path, "w") as f:
+            f.write("backend = mock\n[status]\npanel = epd296x128\nspi_cs = 1\n"
+                    "dc_pin = 22\nreset_pin = 23\nbusy_pin = 24\n")
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_independent_queues(self):
+        """Test each panel has its own type and queue, and one blocked panel does not hold up the other."""
+        with DisplayGroup.open(path=self.path) as panels:
+            self.assertEqual(panels.names, ["main", "status"])
+            self.assertEqual(panels["main"].get_dimensions(), (128, 250))
+            self.assertEqual(panels["status"].get_dimensions(), (128, 296))
+
+            release = threading.Event()
+            blocked = panels.submit("main", lambda display: release.wait(5))
+            status = panels["status"]
+            done = panels.submit("status", Display.display_image, b"\x00" * status.ARRAY_SIZE)
+            done.result(timeout=5)
+            self.assertFalse(blocked.done())
+            self.assertEqual(get_mock_transport(status).last_frame, b"\x00" * status.ARRAY_SIZE)
+            self.assertIsNone(get_mock_transport(panels["main"]).last_frame)
+
+            failed = panels.submit("main", Display.display_image, b"short")
+            release.set()
+            self.assertTrue(panels.wait(timeout=5))
+            self.assertRaises(DisplayError, failed.result)
+        self.assertFalse(status.is_initialized())
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_wiring_conflicts(self):
+        """Test panels on hardware may not share a chip select or GPIO line."""
+        main, status = load_config(self.path), load_config(self.path, "status")
+        check_wiring([main, status])
+        with open(self.path, "a") as f:
+            f.write("spi_cs = 0\nbackend = python\n")
+        check_wiring([main, load_config(self.path, "status")])      # the main panel is mocked
+        with self.assertRaises(DisplayError):
+            DisplayGroup.open(path=self.path, backend="python")
+        with self.assertRaises(DisplayError):
+            DisplayGroup.open(["front"], path=self.path)
+
+    @patch.dict(os.environ, {}, clear=True)
+    def test_native_panel_types(self):
+        """Test a native display configured for a panel other than the default is refused before opening any."""
+        main, status = load_config(self.path), load_config(self.path, "status")
+        check_panels([main, status])
+        with patch("distiller_cm5_sdk.hardware.eink.group.Display") as display:
+            with self.assertRaises(DisplayError) as ctx:
+                DisplayGroup.open(path=self.path, backend="native")
+            display.assert_not_called()
+        self.assertIn("Display 'status' is configured for the epd296x128 panel", str(ctx.exception))
+
+
+if __name__ == "__main__":
+    unittest.main()
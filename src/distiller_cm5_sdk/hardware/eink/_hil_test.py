@@ -0,0 +1,128 @@
+#!/usr/bin/env python3
+"""
+Hardware-in-the-loop runner unit tests for CM5 SDK.
+"""
+
+import io
+import json
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.diagnostics.__main__ import main
+from distiller_cm5_sdk.hardware.eink import Display, DisplayError, DisplayMode
+from distiller_cm5_sdk.hardware.eink.backends.mock import add_frame_listener, remove_frame_listener
+from distiller_cm5_sdk.hardware.eink.display import DisplayErrorCode
+from distiller_cm5_sdk.hardware.eink.hil import SCENARIOS, HilError, HilRunner, Outcome
+
+
+class FakeClock:
+    """Time that only moves when a frame is shown."""
+
+    def __init__(self, frame_s):
+        self.now = 0.0
+        self.frame_s = frame_s
+
+    def __call__(self):
+        return self.now
+
+    def on_frame(self, transport, frame):
+        self.now += self.frame_s
+
+
+class TestHilRunner(unittest.TestCase):
+    """Test cases for the HIL scenarios and report."""
+
+    def test_mock_passes(self):
+        """Test every scenario passes on the mock panel, power-cycle skipped without power control."""
+        report = HilRunner(lambda: Display(backend="mock"), iterations=3).run()
+        self.assertTrue(report.passed)
+        self.assertEqual([scenario.name for scenario in report.scenarios], list(SCENARIOS))
+        outcomes = {scenario.name: scenario for scenario in report.scenarios}
+        self.assertEqual(outcomes["power-cycle"].outcome, Outcome.SKIPPED)
+        self.assertEqual({name: timing["count"] for name, timing in outcomes["init-storm"].timings().items()},
+                         {"init": 4, "full refresh": 1})
+        self.assertEqual({name: timing["count"] for name, timing in outcomes["sleep-wake"].timings().items()},
+                         {"init": 1, "full refresh": 1, "sleep": 3, "wake": 3, "partial refresh": 3})
+        self.assertEqual(report.display["backend"], "mock")
+
+        data = json.loads(json.dumps(report.to_dict()))
+        self.assertTrue(data["passed"])
+        self.assertEqual(data["scenarios"][1]["outcome"], "passed")
+        self.assertEqual(data["scenarios"][1]["timings"]["partial refresh"]["count"], 3)
+        self.assertIn("power-cycle     SKIPPED  no panel power control", report.format_text())
+
+    def test_timing_budget(self):
+        """Test refreshes slower than tolerance times the panel's typical time fail."""
+        clock = FakeClock(1.0)          # full refresh 1.5 s and partial 0.3 s typical
+        add_frame_listener(clock.on_frame)
+        try:
+            report = HilRunner(lambda: Display(backend="mock"), iterations=2, clock=clock).run(["rapid-partials"])
+        finally:
+            remove_frame_listener(clock.on_frame)
+        self.assertFalse(report.passed)
+        scenario = report.scenarios[0]
+        self.assertEqual(scenario.outcome, Outcome.FAILED)
+        self.assertEqual([(step.name, step.error) for step in scenario.failures],
+                         [("partial refresh", "took 1000 ms, budget 600 ms")] * 2)
+        self.assertIn("FAILED partial refresh: took 1000 ms, budget 600 ms", report.format_text())
+
+    def test_errors(self):
+        """Test display errors and power cycle failures are recorded with their codes."""
+        def factory():
+            display = Display(backend="mock")
+            show = display.display_image
+
+            def display_image(data, mode=DisplayMode.FULL):
+                if mode == DisplayMode.PARTIAL:
+                    raise DisplayError("Panel stayed busy", DisplayErrorCode.TIMEOUT)
+                show(data, mode)
+
+            display.display_image = display_image
+            return display
+
+        cycles = []
+
+        def power_cycle():
+            cycles.append(True)
+            if len(cycles) == 2:
+                raise DisplayError("SAM did not answer")
+
+        report = HilRunner(factory, iterations=3, power_cycle=power_cycle).run(["sleep-wake", "power-cycle"])
+        sleep_wake, power = report.scenarios
+        self.assertEqual([(step.name, step.error) for step in sleep_wake.failures],
+                         [("partial refresh", "Panel stayed busy (TIMEOUT)")] * 3)
+        self.assertEqual(len(cycles), 2)
+        self.assertEqual(power.outcome, Outcome.FAILED)
+        self.assertEqual([step.name for step in power.steps],
+                         ["init", "full refresh", "power cycle", "init", "full refresh", "power cycle"])
+
+        with self.assertRaisesRegex(HilError, "Unknown scenario 'brownout'"):
+            HilRunner(factory).run(["brownout"])
+        with self.assertRaises(HilError):
+            HilRunner(factory, iterations=0)
+
+    def test_cli(self):
+        """Test eink-hil prints the report, saves it as JSON and exits 1 on errors."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "hil.json")
+            with patch("sys.stdout", io.StringIO()) as out:
+                self.assertEqual(main(["eink-hil", "--backend", "mock", "--iterations", "2",
+                                       "--scenario", "init-storm", "--report", path]), 0)
+            self.assertIn("init-storm      PASSED", out.getvalue())
+            with open(path) as f:
+                self.assertEqual([scenario["name"] for scenario in json.load(f)["scenarios"]], ["init-storm"])
+
+        with patch("sys.stderr", io.StringIO()) as err:
+            self.assertEqual(main(["eink-hil", "--backend", "mock", "--scenario", "brownout"]), 1)
+        self.assertIn("Unknown scenario", err.getvalue())
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,130 @@
+#!/usr/bin/env python3
+"""
+HTTP display endpoint unit tests for CM5 SDK.
+"""
+
+import http.client
+import json
+import struct
+import sys
+import threading
+import unittest
+import urllib.error
+import urllib.request
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.device.lock import ScreenLock, hash_pin
+from distiller_cm5_sdk.hardware.eink import Display
+from distiller_cm5_sdk.hardware.eink.graphics import Color, Framebuffer
+from distiller_cm5_sdk.hardware.eink.http_server import DisplayHTTPServer
+from distiller_cm5_sdk.hardware.eink.pngcodec import decode_png, encode_png_1bit
+
+
+class TestHTTPServer(unittest.TestCase):
+    """Test cases for POST /display, GET /screenshot and GET /info."""
+
+    def setUp(self):
+        self.display = Display(backend="mock")
+        self.server = DisplayHTTPServer(self.display, port=0, token="s3cret")
+        self.thread = threading.Thread(target=self.server.serve_forever)
+        self.thread.start()
+        self.url = f"http://127.0.0.1:{self.server.server_address[1]}"
+        self.png = encode_png_1bit(Framebuffer(64, 64, fill=Color.BLACK).to_bytes(), 64, 64)
+
+    def tearDown(self):
+        self.server.shutdown()
+        self.thread.join()
+        self.server.server_close()
+        self.display.close()
+
+    def request(self, path, body=None, content_type="image/png", token="s3cret"):
+        request = urllib.request.Request(self.url + path, data=body)
+        if body is not None:
+            request.add_header("Content-Type", content_type)
+        if token is not None:
+            request.add_header("Authorization", f"Bearer {token}")
+        try:
+            with urllib.request.urlopen(request, timeout=10) as response:
+                return response.status, response.headers["Content-Type"], response.read()
+        except urllib.error.HTTPError as e:
+            return e.code, e.headers["Content-Type"], e.read()
+
+    def test_push_and_screenshot(self):
+        """Test a raw PNG is scaled onto the panel and comes back as the screenshot."""
+        self.assertEqual(self.request("/screenshot")[0], 404)
+        status, _, body = self.request("/display?scale=stretch&dither=none&mode=partial", self.png)
+        self.assertEqual((status, json.loads(body)), (200, {"ok": True, "width": 128, "height": 250}))
+        self.assertEqual(self.display.get_framebuffer(), bytes(4000))
+        self.assertEqual(self.display.get_info()["last_update"]["tag"], "http 127.0.0.1")
+
+        status, content_type, body = self.request("/screenshot")
+        self.assertEqual((status, content_type), (200, "image/png"))
+        width, height, rgba = decode_png(body)
+        self.assertEqual((width, height, rgba[:4]), (128, 250, bytes([0, 0, 0, 255])))
+
+        self.request("/display?tag=menu-board", self.png)
+        status, _, body = self.request("/info")
+        self.assertEqual(json.loads(body)["last_update"]["tag"], "menu-board (http 127.0.0.1)")
+
+    def test_multipart(self):
+        """Test an image uploaded as a form file is displayed."""
+        boundary = "----distiller"
+        body = (f"--{boundary}\r\nContent-Disposition: form-data; name=\"caption\"\r\n\r\nMenu\r\n"
+                f"--{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"menu.png\"\r\n"
+                f"Content-Type: image/png\r\n\r\n").encode() + self.png + f"\r\n--{boundary}--\r\n".encode()
+        status, _, _ = self.request("/display?invert=true&dither=none", body,
+                                    f"multipart/form-data; boundary={boundary}")
+        self.assertEqual(status, 200)
+        # Letterboxed: white bars inverted to black, the black image to white
+        frame = self.display.get_framebuffer()
+        self.assertEqual(frame[0], 0x00)
+        self.assertEqual(frame[2000], 0xFF)
+
+    def test_info_and_errors(self):
+        """Test info, authentication and bad requests."""
+        status, _, body = self.request("/info")
+        self.assertEqual((status, json.loads(body)["panel"]), (200, "epd128x250"))
+        self.assertEqual(self.request("/info", token=None)[0], 401)
+        self.assertEqual(self.request("/info", token="guess")[0], 401)
+        self.assertEqual(self.request("/display", b"not an image")[0], 400)
+        self.assertEqual(self.request("/display?scale=zoom", self.png)[0], 400)
+        self.assertEqual(self.request("/display?threshold=300", self.png)[0], 400)
+        short_header = self.png[:8] + struct.pack(">I", 12) + self.png[12:]
+        self.assertEqual(self.request("/display", short_header)[0], 400)
+        connection = http.client.HTTPConnection("127.0.0.1", self.server.server_address[1], timeout=10)
+        connection.putrequest("POST", "/display")
+        connection.putheader("Authorization", "Bearer s3cret")
+        connection.putheader("Content-Length", "-1")
+        connection.endheaders()
+        self.assertEqual(connection.getresponse().status, 400)
+        connection.close()
+        self.assertEqual(self.request("/reboot", b"")[0], 404)
+        status, content_type, body = self.request("/nothing")
+        self.assertEqual((status, content_type, json.loads(body)["ok"]), (404, "application/json", False))
+
+    def test_screen_lock(self):
+        """Test drawing and screenshots are refused while the screen lock is locked, until /unlock."""
+        self.assertEqual(self.request("/unlock", b'{"pin": "2580"}')[0], 404)
+        self.server.screen_lock = ScreenLock(hash_pin("2580"), max_attempts=2)
+        status, _, body = self.request("/display", self.png)
+        self.assertEqual((status, json.loads(body)["ok"]), (423, False))
+        self.assertEqual(self.request("/screenshot")[0], 423)
+        self.assertIsNone(self.display.get_framebuffer())
+        self.assertEqual(self.request("/info")[0], 200)
+
+        self.assertEqual(self.request("/unlock", b"2580")[0], 400)
+        self.assertEqual(self.request("/unlock", b'{"pin": "0000"}')[0], 403)
+        self.assertEqual(self.request("/unlock", b'{"pin": "1111"}')[0], 429)
+        self.server.screen_lock = ScreenLock(hash_pin("2580"))
+        self.assertEqual(self.request("/unlock", b'{"pin": "2580"}')[0], 200)
+        self.assertEqual(self.request("/display", self.png)[0], 200)
+
+        self.assertEqual(self.request("/lock", b"")[0], 200)
+        self.assertEqual(self.request("/screenshot")[0], 423)
+
+
+if __name__ == "__main__":
+    unittest.main()
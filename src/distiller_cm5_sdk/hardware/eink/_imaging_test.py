@@ -0,0 +1,425 @@
+#!/usr/bin/env python3
+"""
+Image preparation, QR code and distiller-eink unit tests for CM5 SDK.
+"""
+
+import io
+import os
+import sys
+import tempfile
+import types
+import unittest
+from contextlib import redirect_stdout
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.__main__ import main
+from distiller_cm5_sdk.hardware.eink.backends.mock import add_frame_listener, remove_frame_listener
+from distiller_cm5_sdk.hardware.eink.graphics import Color, Framebuffer, wrap_text
+from distiller_cm5_sdk.hardware.eink.imagecache import ImageCache, default_image_cache
+from distiller_cm5_sdk.hardware.eink.imaging import (
+    DitherMode, ImageError, ImageOptions, ScalingFilter, ScalingMethod, adjust_contrast, default_image_options,
+    detect_skew, dither_grayscale, fit, is_svg, load_grayscale, process_image, resize_grayscale, rotate_grayscale,
+    sharpen_grayscale, smart_crop_position, svg_size,
+)
+from distiller_cm5_sdk.hardware.eink.patterns import PatternSpec
+from distiller_cm5_sdk.hardware.eink.pngcodec import encode_png_1bit
+from distiller_cm5_sdk.hardware.eink.qr import QRCodeError, draw_qr
+
+
+def _fake_qrcode(matrix):
+    """A stand-in for the qrcode package whose codes are always matrix."""
+    class QRCode:
+        def __init__(self, error_correction=None, border=4):
+            self.error_correction = error_correction
+
+        def add_data(self, data):
+            if len(data) > 20:
+                raise ValueError("too long")
+
+        def make(self, fit=True):
+            pass
+
+        def get_matrix(self):
+            return matrix
+
+    module = types.ModuleType("qrcode")
+    module.QRCode = QRCode
+    module.constants = types.ModuleType("qrcode.constants")
+    for level in "LMQH":
+        setattr(module.constants, f"ERROR_CORRECT_{level}", level)
+    return {"qrcode": module, "qrcode.constants": module.constants}
+
+
+def _fake_resvg(renders):
+    """A stand-in for resvg_py that draws the left half black at the requested size, recording it in renders."""
+    def svg_to_bytes(svg_string, width, height):
+        renders.append((width, height))
+        frame = Framebuffer(width, height)
+        frame.draw_rect(0, 0, width // 2, height, fill=True)
+        return list(encode_png_1bit(frame.to_bytes(), width, height))
+
+    module = types.ModuleType("resvg_py")
+    module.svg_to_bytes = svg_to_bytes
+    return {"resvg_py": module}
+
+
+class TestImaging(unittest.TestCase):
+    """Test cases for scaling and dithering images to frames."""
+
+    def test_fit(self):
+        """Test each scaling method's size and placement."""
+        self.assertEqual(fit(256, 128, 128, 250, ScalingMethod.LETTERBOX), (128, 64, 0, 93))
+        self.assertEqual(fit(256, 128, 128, 250, ScalingMethod.CROP), (500, 250, -186, 0))
+        self.assertEqual(fit(256, 128, 128, 250, ScalingMethod.STRETCH), (128, 250, 0, 0))
+        self.assertEqual(fit(64, 50, 128, 250, ScalingMethod.NONE), (64, 50, 32, 100))
+        self.assertEqual(fit(256, 128, 128, 250, ScalingMethod.SMART_CROP), (500, 250, -186, 0))
+
+    def test_smart_crop(self):
+        """Test the crop window follows the detail, and stays centered on a flat image."""
+        # A 32x8 image, flat but for a checkered patch near the left edge
+        image = Framebuffer(32, 8)
+        for y in range(8):
+            for x in range(2, 8):
+                if (x + y) % 2:
+                    image.set_pixel(x, y, Color.BLACK)
+        pixels = bytes(0 if image.get_pixel(x, y) == Color.BLACK else 255 for y in range(8) for x in range(32))
+        self.assertEqual(smart_crop_position(pixels, 32, 8, 8, 8), (-1, 0))
+        self.assertEqual(smart_crop_position(bytes([200]) * 256, 32, 8, 8, 8), (-12, 0))
+        self.assertEqual(smart_crop_position(bytes(256), 8, 32, 8, 8), (0, -12))
+        self.assertEqual(smart_crop_position(bytes(64), 8, 8, 16, 16), (4, 4))
+
+        png = encode_png_1bit(image.to_bytes(), 32, 8)
+        smart = process_image(png, ImageOptions(ScalingMethod.SMART_CROP, DitherMode.NONE), 8, 8, cache=False)
+        self.assertEqual([smart.get_pixel(x, 0) for x in range(8)], [image.get_pixel(x + 1, 0) for x in range(8)])
+
+    def test_dither(self):
+        """Test a mid gray comes out half black with dithering and all one color without."""
+        gray = bytes([128]) * (16 * 16)
+        for mode in (DitherMode.FLOYD_STEINBERG, DitherMode.ORDERED):
+            fb = dither_grayscale(gray, 16, 16, mode)
+            black = sum(fb.get_pixel(x, y) == Color.BLACK for y in range(16) for x in range(16))
+            self.assertAlmostEqual(black, 128, delta=16, msg=mode)
+        self.assertEqual(dither_grayscale(gray, 16, 16, DitherMode.NONE).to_bytes(), bytes([0xFF]) * 32)
+        self.assertEqual(dither_grayscale(gray, 16, 16, DitherMode.NONE, threshold=129).to_bytes(), bytes(32))
+
+    def test_parallel_dither(self):
+        """Test banded dithering keeps the tone, stays close to serial and does not depend on the workers."""
+        width, height = 400, 300
+        gradient = bytes((x * 255 // (width - 1) + y % 7) % 256 for y in range(height) for x in range(width))
+        serial = dither_grayscale(gradient, width, height)
+        parallel = dither_grayscale(gradient, width, height, parallel=True)
+        serial_black = sum(bin(byte ^ 0xFF).count("1") for byte in serial.to_bytes())
+        parallel_black = sum(bin(byte ^ 0xFF).count("1") for byte in parallel.to_bytes())
+        self.assertAlmostEqual(parallel_black, serial_black, delta=serial_black // 100)
+        # The top band has nothing above it and matches serial dithering exactly
+        self.assertEqual(parallel.to_bytes()[:height // 4 * width // 8], serial.to_bytes()[:height // 4 * width // 8])
+
+        with patch("distiller_cm5_sdk.hardware.eink.imaging.ProcessPoolExecutor", side_effect=OSError("no /dev/shm")):
+            with redirect_stdout(io.StringIO()) as out:
+                fallback = dither_grayscale(gradient, width, height, parallel=True)
+        self.assertEqual(fallback.to_bytes(), parallel.to_bytes())
+        self.assertIn("Warning: Cannot start dithering workers", out.getvalue())
+        small = bytes([100]) * (64 * 64)
+        self.assertEqual(dither_grayscale(small, 64, 64, parallel=True).to_bytes(),
+                         dither_grayscale(small, 64, 64).to_bytes())
+
+    def test_process_png(self):
+        """Test a PNG is scaled to the frame, with white letterbox bars, without Pillow."""
+        source = Framebuffer(8, 4, fill=Color.BLACK)
+        png = encode_png_1bit(source.to_bytes(), 8, 4)
+        fb = process_image(png, ImageOptions(dither=DitherMode.NONE), 16, 16)
+        self.assertEqual(fb.get_pixel(8, 0), Color.WHITE)
+        self.assertEqual(fb.get_pixel(8, 8), Color.BLACK)
+        inverted = process_image(png, ImageOptions(dither=DitherMode.NONE, invert=True), 16, 16)
+        self.assertEqual(inverted.get_pixel(8, 8), Color.WHITE)
+        with self.assertRaises(ImageError):
+            process_image(b"not an image", width=16, height=16)
+
+    def test_filter_and_sharpen(self):
+        """Test nearest scaling keeps hard edges, and sharpening after downscaling steepens them."""
+        stripes = bytes([0, 0, 255, 255]) * 4                          # 4x4, two columns of each
+        self.assertEqual(resize_grayscale(stripes, 4, 4, 2, 2, ScalingFilter.NEAREST), bytes([0, 255]) * 2)
+        self.assertEqual(resize_grayscale(stripes, 4, 4, 8, 1, ScalingFilter.NEAREST),
+                         bytes([0, 0, 0, 0, 255, 255, 255, 255]))
+        smooth = resize_grayscale(stripes, 4, 4, 3, 1, ScalingFilter.TRIANGLE)
+        self.assertTrue(0 < smooth[1] < 255)
+
+        ramp = bytes([64, 64, 96, 160, 192, 192])
+        sharpened = sharpen_grayscale(ramp, 6, 1, 1.0)
+        self.assertLess(sharpened[2], ramp[2])
+        self.assertGreater(sharpened[3], ramp[3])
+        self.assertEqual(sharpened[0], 64)                              # flat areas unchanged
+        self.assertEqual(sharpen_grayscale(bytes([0, 255, 0]), 3, 1, 2.0), bytes([0, 255, 0]))   # clamped
+        self.assertEqual(sharpen_grayscale(ramp, 6, 1, 0.0), ramp)
+
+        # Sharpening only follows downscaling, and is part of the options the cache keys on
+        gray = Framebuffer(8, 8)
+        gray.draw_rect(0, 0, 4, 8, fill=True)
+        png = encode_png_1bit(gray.to_bytes(), 8, 8)
+        plain = ImageOptions(ScalingMethod.STRETCH, DitherMode.NONE, scaling_filter=ScalingFilter.TRIANGLE)
+        sharp = ImageOptions(ScalingMethod.STRETCH, DitherMode.NONE, scaling_filter=ScalingFilter.TRIANGLE,
+                             sharpen=1.0)
+        self.assertEqual(process_image(png, sharp, 8, 8, cache=False).to_bytes(),
+                         process_image(png, plain, 8, 8, cache=False).to_bytes())
+        self.assertNotEqual(ImageCache.key(png, plain, 3, 8), ImageCache.key(png, sharp, 3, 8))
+
+    def test_letterbox_fill(self):
+        """Test the margins take the pad color, or a tiled background image or pattern."""
+        png = encode_png_1bit(Framebuffer(8, 4, fill=Color.BLACK).to_bytes(), 8, 4)
+        black = process_image(png, ImageOptions(dither=DitherMode.NONE, pad=Color.BLACK), 8, 8, cache=False)
+        self.assertEqual(black.to_bytes(), bytes(8))
+
+        tile = Framebuffer(2, 2)
+        tile.set_pixel(0, 0, Color.BLACK)
+        tile_png = encode_png_1bit(tile.to_bytes(), 2, 2)
+        fb = process_image(png, ImageOptions(ScalingMethod.NONE, DitherMode.NONE, background=tile_png), 12, 8,
+                           cache=False)
+        self.assertEqual([fb.get_pixel(x, 0) for x in range(4)], [Color.BLACK, Color.WHITE] * 2)
+        self.assertEqual(fb.get_pixel(2, 6), Color.BLACK)               # the tile repeats down too
+        self.assertEqual(fb.get_pixel(3, 3), Color.BLACK)               # the image covers it
+        self.assertEqual(fb.get_pixel(5, 3), Color.BLACK)
+
+        stripes = process_image(png, ImageOptions(dither=DitherMode.FLOYD_STEINBERG,
+                                                  background=PatternSpec("stripes", size=1)), 8, 8, cache=False)
+        self.assertEqual([stripes.get_pixel(0, y) for y in range(2)], [Color.BLACK, Color.WHITE])
+        with self.assertRaises(ImageError):
+            process_image(png, ImageOptions(background=PatternSpec("plaid")), 8, 8, cache=False)
+
+        # A background file is part of the cache key by its content
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "frame.png")
+            with open(path, "wb") as f:
+                f.write(tile_png)
+            cache = ImageCache(os.path.join(tmpdir, "cache"))
+            options = ImageOptions(ScalingMethod.NONE, DitherMode.NONE, background=path)
+            self.assertEqual(process_image(png, options, 12, 8, cache).to_bytes(), fb.to_bytes())
+            with open(path, "wb") as f:
+                f.write(encode_png_1bit(bytes(1), 2, 2))
+            self.assertEqual(process_image(png, options, 12, 8, cache).get_pixel(1, 0), Color.BLACK)
+            self.assertEqual(cache.hits, 0)
+
+    def test_rotate_and_deskew(self):
+        """Test quarter turns are exact, other angles enlarge the image, and a tilted page is found and straightened."""
+        self.assertEqual(rotate_grayscale(bytes(range(6)), 3, 2, 90), (2, 3, bytes([2, 5, 1, 4, 0, 3])))
+        self.assertEqual(rotate_grayscale(bytes(range(6)), 3, 2, -90), (2, 3, bytes([3, 0, 4, 1, 5, 2])))
+        self.assertEqual(rotate_grayscale(bytes(range(6)), 3, 2, 540)[2], bytes([5, 4, 3, 2, 1, 0]))
+        width, height, pixels = rotate_grayscale(bytes(100), 10, 10, 45, fill=200)
+        self.assertEqual((width, height, pixels[0], pixels[7 * width + 7]), (15, 15, 200, 0))
+
+        page = Framebuffer(120, 80)
+        for y in range(6, 76, 12):
+            page.draw_text(4, y, "Lorem ipsum dolor sit")
+        pixels = bytes(0 if page.get_pixel(x, y) == Color.BLACK else 255 for y in range(80) for x in range(120))
+        self.assertEqual(detect_skew(pixels, 120, 80), 0.0)
+        self.assertEqual(detect_skew(bytes([255]) * 100, 10, 10), 0.0)
+        for angle in (6, -9.5):
+            with self.subTest(angle=angle):
+                tilted = rotate_grayscale(pixels, 120, 80, angle)
+                self.assertAlmostEqual(detect_skew(tilted[2], tilted[0], tilted[1]), angle, delta=0.5)
+
+        # The page photographed a quarter turn and a little off: straightened and turned back
+        width, height, tilted = rotate_grayscale(pixels, 120, 80, -90 + 6)
+        png = encode_png_1bit(dither_grayscale(tilted, width, height, DitherMode.NONE).to_bytes(), width, height)
+        options = ImageOptions(ScalingMethod.NONE, DitherMode.NONE, rotate=90, deskew=True)
+        fixed = process_image(png, options, 140, 100, cache=False)
+        rows = [sum(fixed.get_pixel(x, y) == Color.BLACK for x in range(140)) for y in range(100)]
+        self.assertEqual(sum(1 for a, b in zip(rows, rows[1:]) if a and not b), 6)     # six separate lines
+
+    def test_svg(self):
+        """Test an SVG is rendered at the size it is fitted to, not resampled from another."""
+        svg = b'<?xml version="1.0"?>\n<svg xmlns="http://www.w3.org/2000/svg" width="64" height="32"/>'
+        self.assertTrue(is_svg(svg))
+        self.assertFalse(is_svg(encode_png_1bit(bytes(8), 8, 8)))
+        self.assertEqual(svg_size(b'<svg width="1in" height="50%" viewBox="0 0 2 1"/>'), (96, 48))
+        self.assertEqual(svg_size(b'<svg viewBox="0,0,10,20"/>'), (10, 20))
+        self.assertEqual(svg_size(b"<svg/>"), (300, 150))
+        for invalid in (b"<html/>", b"<svg"):
+            with self.assertRaises(ImageError):
+                svg_size(invalid)
+
+        renders = []
+        with patch.dict(sys.modules, _fake_resvg(renders)):
+            frame = process_image(svg, ImageOptions(ScalingMethod.LETTERBOX, DitherMode.NONE), 128, 250, cache=False)
+            # Scaled to 128x64, centered at y=93: a sharp edge down the middle, nothing blurred into the margins
+            self.assertEqual(renders, [(128, 64)])
+            self.assertEqual([frame.get_pixel(x, 120) for x in (0, 63, 64, 127)],
+                             [Color.BLACK, Color.BLACK, Color.WHITE, Color.WHITE])
+            self.assertEqual((frame.get_pixel(10, 92), frame.get_pixel(10, 93), frame.get_pixel(10, 156),
+                              frame.get_pixel(10, 157)), (Color.WHITE, Color.BLACK, Color.BLACK, Color.WHITE))
+            process_image(svg, ImageOptions(ScalingMethod.CROP, rotate=90), 128, 250, cache=False)
+            process_image(svg, ImageOptions(ScalingMethod.NONE), 128, 250, cache=False)
+            self.assertEqual(renders[1:], [(256, 128), (64, 32)])          # turned, it covers 128x256
+            self.assertEqual(load_grayscale(svg)[:2], (64, 32))
+        with patch.dict(sys.modules, {"resvg_py": None}):
+            with self.assertRaisesRegex(ImageError, r"\[svg\]"):
+                process_image(svg, cache=False)
+
+    def test_contrast(self):
+        """Test contrast spreads gray levels around mid-gray, and the profile supplies the defaults."""
+        self.assertEqual(adjust_contrast(bytes([0, 96, 128, 160, 255]), 2.0), bytes([0, 64, 128, 192, 255]))
+        self.assertEqual(adjust_contrast(bytes([0, 255]), 0.5), bytes([64, 192]))
+        self.assertEqual(adjust_contrast(bytes([7]), 1.0), bytes([7]))
+
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "eink.conf")
+            with open(path, "w") as f:
+                f.write("threshold = 100\ncontrast = 0.5\n")
+            with patch.dict(os.environ, {"DISTILLER_EINK_CONFIG": path}):
+                self.assertEqual(default_image_options(), ImageOptions(threshold=100, contrast=0.5))
+                # Halving the contrast lifts black to 64, which turns white below threshold 64
+                png = encode_png_1bit(Framebuffer(4, 4, fill=Color.BLACK).to_bytes(), 4, 4)
+                self.assertEqual(process_image(png, None, 4, 4).get_pixel(0, 0), Color.BLACK)
+                with open(path, "w") as f:
+                    f.write("threshold = 60\ncontrast = 0.5\n")
+                self.assertEqual(process_image(png, None, 4, 4).get_pixel(0, 0), Color.WHITE)
+                with open(path, "w") as f:
+                    f.write("contrast = lots\n")
+                with redirect_stdout(io.StringIO()) as out:
+                    self.assertEqual(default_image_options(), ImageOptions())
+                self.assertIn("Warning: Invalid contrast", out.getvalue())
+
+    def test_cache(self):
+        """Test processed frames are reused by content and options, and the least recently used evicted."""
+        black = encode_png_1bit(Framebuffer(4, 4, fill=Color.BLACK).to_bytes(), 4, 4)
+        white = encode_png_1bit(Framebuffer(4, 4).to_bytes(), 4, 4)
+        options = ImageOptions(dither=DitherMode.NONE)
+        with tempfile.TemporaryDirectory() as tmpdir:
+            cache = ImageCache(tmpdir)
+            path = os.path.join(tmpdir, "slide.png")
+            with open(path, "wb") as f:
+                f.write(black)
+            first = process_image(path, options, 8, 8, cache)
+            with patch("distiller_cm5_sdk.hardware.eink.imaging._process") as processing:
+                self.assertEqual(process_image(black, options, 8, 8, cache).to_bytes(), first.to_bytes())
+                processing.assert_not_called()
+            self.assertEqual((cache.hits, cache.misses), (1, 1))
+
+            with open(path, "wb") as f:
+                f.write(white)          # same name, new content
+            self.assertEqual(process_image(path, options, 8, 8, cache).get_pixel(0, 0), Color.WHITE)
+            process_image(black, ImageOptions(dither=DitherMode.NONE, invert=True), 8, 8, cache)
+            process_image(black, options, 16, 8, cache)
+            self.assertEqual(cache.size()[0], 4)
+
+            entry = os.path.join(tmpdir, ImageCache.key(black, options, 8, 8) + ".frame")
+            with open(entry, "wb") as f:
+                f.write(b"damaged")
+            self.assertEqual(process_image(black, options, 8, 8, cache).to_bytes(), first.to_bytes())
+
+            # Touched by a hit, the first frame outlives older ones when trimmed
+            cache.max_bytes = cache.size()[1] // 2
+            os.utime(entry, (0, 0))
+            process_image(black, options, 8, 8, cache)
+            cache.trim()
+            self.assertLessEqual(cache.size()[1], cache.max_bytes)
+            self.assertTrue(os.path.exists(entry))
+            cache.clear()
+            self.assertEqual(cache.size(), (0, 0))
+
+    def test_configured_cache(self):
+        """Test process_image() uses the cache set in the config unless told not to."""
+        png = encode_png_1bit(Framebuffer(4, 4, fill=Color.BLACK).to_bytes(), 4, 4)
+        with tempfile.TemporaryDirectory() as tmpdir:
+            cache_dir = os.path.join(tmpdir, "cache")
+            with patch.dict(os.environ, {"DISTILLER_EINK_CONFIG": os.devnull, "DISTILLER_EINK_CACHE_DIR": cache_dir,
+                                         "DISTILLER_EINK_CACHE_MAX_MB": "0.5"}):
+                self.assertEqual((default_image_cache().directory, default_image_cache().max_bytes),
+                                 (cache_dir, 512 * 1024))
+                process_image(png, None, 4, 4)
+                process_image(png, None, 4, 4, cache=False)
+                self.assertEqual(len(os.listdir(cache_dir)), 1)
+                self.assertEqual(default_image_cache().hits, 0)
+                process_image(png, None, 4, 4)
+                self.assertEqual(default_image_cache().hits, 1)
+
+                os.environ["DISTILLER_EINK_CACHE_MAX_MB"] = "lots"
+                with redirect_stdout(io.StringIO()) as out:
+                    self.assertIsNone(default_image_cache())
+                self.assertIn("Invalid cache_max_mb", out.getvalue())
+                os.environ.update({"DISTILLER_EINK_CACHE_DIR": "none", "DISTILLER_EINK_CACHE_MAX_MB": "8"})
+                self.assertIsNone(default_image_cache())
+
+    def test_wrap_text(self):
+        """Test text breaks between words to fit, splitting words longer than a line."""
+        self.assertEqual(wrap_text("the quick brown fox", 60), "the quick\nbrown fox")
+        self.assertEqual(wrap_text("abcdefghijkl", 30), "abcde\nfghij\nkl")
+        self.assertEqual(wrap_text("a\nb", 60, scale=2), "a\nb")
+
+
+class TestQRCode(unittest.TestCase):
+    """Test cases for drawing QR codes."""
+
+    def test_draw(self):
+        """Test modules are drawn as whole-pixel squares centered in the square."""
+        matrix = [[True, False], [False, True]]
+        fb = Framebuffer(20, 20, fill=Color.BLACK)
+        with patch.dict(sys.modules, _fake_qrcode(matrix)):
+            self.assertEqual(draw_qr(fb, "hello", 0, 0, 20), 3)
+            with self.assertRaises(QRCodeError):
+                draw_qr(fb, "x" * 30, 0, 0, 20)
+            with self.assertRaises(QRCodeError):
+                draw_qr(fb, "hello", 0, 0, 5)
+        self.assertEqual([fb.get_pixel(x, 7) for x in (6, 7, 9, 10, 12)],
+                         [Color.WHITE, Color.BLACK, Color.BLACK, Color.WHITE, Color.WHITE])
+        self.assertEqual(fb.get_pixel(10, 10), Color.BLACK)
+
+    def test_missing_package(self):
+        """Test a missing qrcode package names the extra to install."""
+        with patch.dict(sys.modules, {"qrcode": None}):
+            with self.assertRaisesRegex(QRCodeError, r"\[qr\]"):
+                draw_qr(Framebuffer(), "hello", 0, 0, 100)
+
+
+class TestCommandLine(unittest.TestCase):
+    """Test cases for distiller-eink."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.frames = []
+        add_frame_listener(self.on_frame)
+
+    def tearDown(self):
+        remove_frame_listener(self.on_frame)
+        self.tmpdir.cleanup()
+
+    def on_frame(self, transport, frame):
+        self.frames.append(frame)
+
+    def run_cli(self, *args):
+        out = io.StringIO()
+        with redirect_stdout(out):
+            code = main(["--backend", "mock"] + list(args))
+        return code, out.getvalue()
+
+    def test_commands(self):
+        """Test each drawing command shows one frame."""
+        image = os.path.join(self.tmpdir.name, "image.png")
+        Path(image).write_bytes(encode_png_1bit(Framebuffer(32, 32, fill=Color.BLACK).to_bytes(), 32, 32))
+        self.assertEqual(self.run_cli("show", image, "--scale", "crop", "--dither", "ordered")[0], 0)
+        self.assertEqual(self.run_cli("text", "Backup finished", "--size", "3", "--partial")[0], 0)
+        self.assertEqual(self.run_cli("clear")[0], 0)
+        self.assertEqual(self.run_cli("sleep")[0], 0)
+        with patch.dict(sys.modules, _fake_qrcode([[True] * 21] * 21)):
+            self.assertEqual(self.run_cli("qr", "https://pamir.ai")[0], 0)
+        self.assertEqual(len(self.frames), 4)
+        self.assertNotEqual(self.frames[0], self.frames[1])
+
+        with patch("sys.stderr", io.StringIO()) as err:
+            self.assertEqual(self.run_cli("show", os.path.join(self.tmpdir.name, "missing.png"))[0], 1)
+        self.assertIn("Error: Cannot read image", err.getvalue())
+
+    def test_info(self):
+        """Test info reports the panel without refreshing it."""
+        code, out = self.run_cli("info", "--json")
+        self.assertEqual(code, 0)
+        self.assertIn('"width": 128', out)
+        self.assertIn('"backend": "mock"', out)
+        self.assertEqual(self.frames, [])
+
+
+if __name__ == "__main__":
+    unittest.main()
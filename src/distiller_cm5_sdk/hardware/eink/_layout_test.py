@@ -0,0 +1,151 @@
+#!/usr/bin/env python3
+"""
+Layout template unit tests for CM5 SDK.
+"""
+
+import io
+import json
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.__main__ import main
+from distiller_cm5_sdk.hardware.eink.graphics import Color, Framebuffer
+from distiller_cm5_sdk.hardware.eink.layout import Layout, LayoutError, load_layout, render_layout
+from distiller_cm5_sdk.hardware.eink.markdown import render_markdown
+from distiller_cm5_sdk.hardware.eink.pngcodec import encode_png_1bit
+
+
+class TestLayout(unittest.TestCase):
+    """Test cases for validating and rendering layout templates."""
+
+    def test_column(self):
+        """Test text, progress bars and dividers stack with padding and gaps."""
+        template = {"type": "column", "padding": 2, "gap": 2, "children": [
+            {"type": "text", "text": "{room}", "align": "center"},
+            {"type": "progress", "value": "{humidity}", "max": 50},
+            {"type": "divider"},
+            {"type": "text", "text": "{temperature:.1f} C", "align": "right"},
+        ]}
+        fb = render_layout(template, {"room": "Hall", "humidity": 25, "temperature": 21.46}, 64, 60)
+
+        expected = Framebuffer(64, 60)
+        expected.draw_text(2 + (60 - 23) // 2, 2, "Hall")
+        expected.draw_rect(2, 12, 60, 8)
+        expected.draw_rect(2, 12, 30, 8, fill=True)
+        expected.draw_hline(2, 22, 60)
+        expected.draw_text(2 + 60 - 35, 25, "21.5 C")
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+    def test_row_weights(self):
+        """Test fixed widths are kept and the rest is shared by weight, with borders and inversion."""
+        template = {"type": "row", "children": [
+            {"type": "spacer", "width": 10},
+            {"type": "spacer", "border": True},
+            {"type": "spacer", "weight": 3, "invert": True},
+        ]}
+        fb = Layout(template).render(None, 50, 10)
+        expected = Framebuffer(50, 10)
+        expected.draw_rect(10, 0, 10, 10)
+        expected.draw_rect(20, 0, 30, 10, fill=True)
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+    def test_clipping(self):
+        """Test text that does not fit its box is cut off at the box."""
+        fb = render_layout({"type": "column", "children": [
+            {"type": "text", "text": "WWWW WWWW", "height": 8, "padding": 0},
+            {"type": "spacer"},
+        ]}, {}, 20, 20)
+        for y in range(8, 20):
+            self.assertEqual([fb.get_pixel(x, y) for x in range(20)], [Color.WHITE] * 20, y)
+
+    def test_image(self):
+        """Test images come from paths or image bytes in the data."""
+        black = encode_png_1bit(Framebuffer(8, 8, fill=Color.BLACK).to_bytes(), 8, 8)
+        template = {"type": "row", "children": [{"type": "image", "src": "{icon}", "width": 8, "dither": "none"},
+                                                {"type": "spacer"}]}
+        fb = render_layout(template, {"icon": black}, 16, 8)
+        expected = Framebuffer(16, 8)
+        expected.draw_rect(0, 0, 8, 8, fill=True)
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "icon.png")
+            with open(path, "wb") as f:
+                f.write(black)
+            fb = render_layout(dict(template, children=[dict(template["children"][0], src=path), {"type": "spacer"}]),
+                               {}, 16, 8)
+            self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+    def test_markdown(self):
+        """Test Markdown is filled in from the data and takes the height it needs in a column."""
+        template = {"type": "column", "children": [
+            {"type": "markdown", "text": "## {room}\n- **{temperature:.1f}** C", "padding": 1},
+            {"type": "divider"},
+        ]}
+        fb = render_layout(template, {"room": "Hall", "temperature": 21.46}, 64, 40)
+        expected = Framebuffer(64, 40)
+        expected.blit(render_markdown("## Hall\n- **21.5** C", 62), 1, 1)
+        expected.draw_hline(0, 1 + 22 + 1, 64)
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "status.md")
+            Path(path).write_text("# {title}\n\n| a | b |\n|---|---|\n| 1 | 2 |\n")
+            self.assertEqual(load_layout(path).render({"title": "Up"}, 64, 40).to_bytes(),
+                             render_markdown("# Up\n\n| a | b |\n|---|---|\n| 1 | 2 |", 64, 40).to_bytes())
+
+    def test_load(self):
+        """Test JSON templates load from files, with errors naming the file and node."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "screen.json")
+            with open(path, "w") as f:
+                json.dump({"type": "column", "children": [{"type": "text", "text": "{x}"}]}, f)
+            fb = load_layout(path).render({"x": "A"}, 10, 10)
+            expected = Framebuffer(10, 10)
+            expected.draw_text(0, 0, "A")
+            self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+            with open(path, "w") as f:
+                json.dump({"type": "column", "children": [{"type": "text", "txt": "x"}]}, f)
+            with self.assertRaisesRegex(LayoutError, r"screen.json: root.children\[0\]: unknown key 'txt'"):
+                load_layout(path)
+
+    def test_cli(self):
+        """Test distiller-eink layout fills a template from a data file."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            template = os.path.join(tmpdir, "screen.json")
+            data = os.path.join(tmpdir, "values.json")
+            Path(template).write_text(json.dumps({"type": "text", "text": "{status}", "size": 2}))
+            Path(data).write_text(json.dumps({"status": "Backup finished"}))
+            self.assertEqual(main(["--backend", "mock", "layout", template, "--data", data, "--partial"]), 0)
+
+            Path(data).write_text("[]")
+            with patch("sys.stderr", io.StringIO()) as err:
+                self.assertEqual(main(["--backend", "mock", "layout", template, "--data", data]), 1)
+            self.assertIn("data must be a JSON object", err.getvalue())
+
+    def test_errors(self):
+        """Test invalid templates and missing data raise LayoutError."""
+        for template in ({"type": "grid"}, {"type": "row"}, {"type": "text", "text": 5},
+                         {"type": "text", "text": "x", "align": "middle"}, {"type": "spacer", "width": -1},
+                         {"type": "image", "src": "x", "dither": "wobbly"}, {"type": "progress"},
+                         {"type": "qr", "text": "x", "ec": "Z"}, {"type": "spacer", "weight": 0}, []):
+            with self.assertRaises(LayoutError, msg=template):
+                Layout(template)
+
+        layout = Layout({"type": "column", "children": [{"type": "text", "text": "{missing}"}]})
+        with self.assertRaisesRegex(LayoutError, "No data for field 'missing'"):
+            layout.render({})
+        with self.assertRaisesRegex(LayoutError, "not a number"):
+            render_layout({"type": "progress", "value": "{level}"}, {"level": "high"})
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,89 @@
+#!/usr/bin/env python3
+"""
+Markdown rendering unit tests for CM5 SDK.
+"""
+
+import sys
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.graphics import Framebuffer
+from distiller_cm5_sdk.hardware.eink.markdown import markdown_height, render_markdown
+
+
+def _bold(fb, x, y, text, scale=1):
+    fb.draw_text(x, y, text, scale=scale)
+    fb.draw_text(x + 1, y, text, scale=scale)
+
+
+class TestMarkdown(unittest.TestCase):
+    """Test cases for drawing Markdown in the built-in font."""
+
+    def test_blocks(self):
+        """Test headings, paragraphs with bold runs, lists and rules stack with their gaps."""
+        text = "# Hi\n\nSome **bold** text\n\n## Sub\n- one\n  - two\n3. three\n\n---"
+        fb = render_markdown(text, 64)
+
+        expected = Framebuffer(64, 85)
+        _bold(expected, 0, 0, "Hi", scale=2)
+        expected.draw_text(0, 20, "Some")
+        _bold(expected, 30, 20, "bold")
+        expected.draw_text(0, 28, "text")                               # wrapped at 10 characters
+        _bold(expected, 0, 40, "Sub")
+        expected.draw_hline(0, 49, 64)
+        expected.draw_text(0, 54, "-")
+        expected.draw_text(12, 54, "one")
+        expected.draw_text(12, 63, "-")
+        expected.draw_text(24, 63, "two")
+        expected.draw_text(0, 72, "3.")
+        expected.draw_text(18, 72, "three")
+        expected.draw_hline(0, 84, 64)
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+        self.assertEqual(markdown_height(text, 64), 85)
+        self.assertEqual(render_markdown(text, 64, 40).to_bytes(), expected.to_bytes()[:64 * 40 // 8])
+
+    def test_inline(self):
+        """Test line breaks, escapes and markers that are not Markdown are drawn as written."""
+        fb = render_markdown("a **b\\*  \nc\\*\\* #tag", 128)
+        expected = Framebuffer(128, 16)
+        expected.draw_text(0, 0, "a **b*")
+        expected.draw_text(0, 8, "c** #tag")
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+        fb = render_markdown("__x__ - - -", 128, 20)
+        expected = Framebuffer(128, 20)
+        _bold(expected, 0, 0, "x")
+        expected.draw_text(6, 0, " - - -")
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+        self.assertEqual(render_markdown("").height, 1)
+
+    def test_table(self):
+        """Test table columns are aligned, the header is bold and ruled, and wide columns are narrowed to fit."""
+        fb = render_markdown("| A | B | C |\n|:-:|--:|---|\n| xxx | y | \\| |\n| 1 |", 64)
+        expected = Framebuffer(64, 28)
+        _bold(expected, 6, 0, "A")
+        _bold(expected, 23, 0, "B")
+        _bold(expected, 34, 0, "C")
+        expected.draw_hline(0, 9, 39)
+        expected.draw_text(0, 11, "xxx")
+        expected.draw_text(23, 11, "y")
+        expected.draw_text(34, 11, "|")
+        expected.draw_text(6, 20, "1")
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+        # The narrow column keeps its width, the wide one gets the rest and wraps
+        fb = render_markdown("| Name | % |\n|---|---|\n| aaaa bbbb cccc dddd | 50 |", 64)
+        expected = Framebuffer(64, 43)
+        _bold(expected, 0, 0, "Name")
+        _bold(expected, 53, 0, "%")
+        expected.draw_hline(0, 9, 64)
+        expected.draw_text(0, 11, "aaaa\nbbbb\ncccc\ndddd")
+        expected.draw_text(53, 11, "50")
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,147 @@
+#!/usr/bin/env python3
+"""
+Console and framebuffer mirroring unit tests for CM5 SDK.
+"""
+
+import io
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.__main__ import main
+from distiller_cm5_sdk.hardware.eink.backends.mock import add_frame_listener, remove_frame_listener
+from distiller_cm5_sdk.hardware.eink.graphics import Color, Framebuffer
+from distiller_cm5_sdk.hardware.eink.imaging import DitherMode, ImageOptions, ScalingMethod
+from distiller_cm5_sdk.hardware.eink.mirror import (
+    ConsoleSource, FramebufferSource, framebuffer_grayscale, render_console,
+)
+from distiller_cm5_sdk.hardware.eink.tiles import TileBoard, TileError
+
+
+def _dump(lines, columns, cursor_x, cursor_y, cell):
+    """A vcsa screen dump whose cells are cell(row, column) -> (character, attribute)."""
+    data = bytearray([lines, columns, cursor_x, cursor_y])
+    for row in range(lines):
+        for column in range(columns):
+            data += bytes(cell(row, column))
+    return bytes(data)
+
+
+class TestMirror(unittest.TestCase):
+    """Test cases for capturing consoles and framebuffers for the display."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def write(self, name, data):
+        path = os.path.join(self.tmpdir.name, name)
+        os.makedirs(os.path.dirname(path), exist_ok=True)
+        with open(path, "wb" if isinstance(data, bytes) else "w") as f:
+            f.write(data)
+        return path
+
+    def test_render_console(self):
+        """Test cells are drawn in the font, reverse video and the cursor inverted."""
+        text = ["ab ", "xy\x01"]
+        dump = _dump(2, 3, 1, 1, lambda row, column: (ord(text[row][column]),
+                                                      0x70 if (row, column) == (1, 0) else 0x07))
+        expected = Framebuffer(18, 16)
+        expected.draw_text(0, 0, "ab")
+        expected.draw_rect(0, 8, 12, 8, fill=True)
+        expected.draw_text(0, 8, "xy", Color.WHITE)
+        expected.draw_text(12, 8, "?")
+        self.assertEqual(render_console(dump, 18, 16).to_bytes(), expected.to_bytes())
+
+        expected.draw_rect(6, 8, 6, 8, Color.WHITE, fill=True)
+        expected.draw_text(6, 8, "y")
+        self.assertEqual(render_console(dump, 18, 16, cursor=False).to_bytes(), expected.to_bytes())
+
+        with self.assertRaises(TileError):
+            render_console(dump[:-1], 18, 16)
+
+    def test_console_window(self):
+        """Test a console larger than the frame is shown from the window that follows the cursor."""
+        def letter(row, column):
+            return ord("A") + (row + column) % 26, 0x07
+
+        fb = render_console(_dump(30, 40, 12, 20, letter), 60, 16)
+        expected = Framebuffer(60, 16)
+        for row in range(2):
+            expected.draw_text(0, row * 8, "".join(chr(letter(19 + row, 3 + column)[0]) for column in range(10)))
+        expected.draw_rect(54, 8, 6, 8, fill=True)
+        expected.draw_text(54, 8, chr(letter(20, 12)[0]), Color.WHITE)
+        self.assertEqual(fb.to_bytes(), expected.to_bytes())
+
+    def test_console_source(self):
+        """Test an unchanged console is not drawn again, and an unreadable one fails."""
+        path = self.write("vcsa1", _dump(1, 2, 0, 0, lambda row, column: (ord("a"), 0x07)))
+        source = ConsoleSource(device=path)
+        self.assertIsInstance(source.fetch(12, 8), Framebuffer)
+        self.assertIsNone(source.fetch(12, 8))
+        os.remove(path)
+        with self.assertRaises(TileError):
+            source.fetch(12, 8)
+
+    def test_framebuffer(self):
+        """Test pixel formats are converted to grayscale, and captures that look the same are skipped."""
+        self.assertEqual(framebuffer_grayscale(b"\xff\xff\xff\x00\x00\x00\xff\x00", 2, 1, 32, 8), (2, 1, b"\xff\x4c"))
+        self.assertEqual(framebuffer_grayscale(b"\xff\xff\x00\xf8\x00\x00" * 2, 2, 2, 16, 6),
+                         (2, 2, b"\xff\x4c" * 2))
+        self.assertEqual(framebuffer_grayscale(bytes(range(12)), 4, 3, 8, 4, step=2), (2, 2, bytes([0, 2, 8, 10])))
+        with self.assertRaises(TileError):
+            framebuffer_grayscale(bytes(4), 2, 1, 12, 4)
+
+        sysfs = os.path.join(self.tmpdir.name, "graphics")
+        self.write("graphics/fb0/virtual_size", "8,4\n")
+        self.write("graphics/fb0/bits_per_pixel", "16\n")
+        self.write("graphics/fb0/stride", "16\n")
+        row = b"\x00\x00" * 4 + b"\xff\xff" * 4
+        device = self.write("fb0", row * 4)
+        source = FramebufferSource(device, options=ImageOptions(ScalingMethod.NONE, DitherMode.NONE), sysfs=sysfs)
+        frame = source.fetch(8, 4)
+        self.assertEqual((frame.get_pixel(3, 0), frame.get_pixel(4, 0)), (Color.BLACK, Color.WHITE))
+        self.assertIsNone(source.fetch(8, 4))
+        self.write("fb0", row * 3 + row.replace(b"\xff\xff", b"\xde\xf7"))      # still white when thresholded
+        self.assertIsNone(source.fetch(8, 4))
+        self.write("fb0", row[::-1] * 4)
+        self.assertEqual(source.fetch(8, 4).get_pixel(0, 0), Color.WHITE)
+
+        os.remove(os.path.join(sysfs, "fb0", "bits_per_pixel"))
+        with self.assertRaises(TileError):
+            source.fetch(8, 4)
+
+    def test_cli(self):
+        """Test distiller-eink mirror shows the console, and fails on a device it cannot read."""
+        frames = []
+
+        def on_frame(transport, frame):
+            frames.append(frame)
+
+        path = self.write("vcsa", _dump(2, 10, 0, 1, lambda row, column: (ord("$"), 0x07)))
+        add_frame_listener(on_frame)
+        try:
+            with patch("distiller_cm5_sdk.hardware.eink.mirror.console_device", return_value=path):
+                with patch.object(TileBoard, "run", lambda board: board.update()):
+                    self.assertEqual(main(["--backend", "mock", "mirror", "--partial"]), 0)
+        finally:
+            remove_frame_listener(on_frame)
+        self.assertEqual(len(frames), 1)
+
+        with patch("sys.stderr", io.StringIO()) as err:
+            self.assertEqual(main(["--backend", "mock", "mirror", "--fb", os.path.join(self.tmpdir.name, "fb9")]), 1)
+            self.assertIn("fb9", err.getvalue())
+            with self.assertRaises(SystemExit):
+                main(["--backend", "mock", "mirror", "--interval", "0.5"])
+
+
+if __name__ == "__main__":
+    unittest.main()
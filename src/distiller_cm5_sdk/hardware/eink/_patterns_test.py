@@ -0,0 +1,74 @@
+#!/usr/bin/env python3
+"""
+Test pattern unit tests for CM5 SDK.
+"""
+
+import sys
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.panels import PanelType, get_panel
+from distiller_cm5_sdk.hardware.eink.patterns import (
+    PATTERNS, PatternError, PatternSpec, generate, pixel_walk_steps,
+)
+
+
+def black_pixels(fb):
+    return {(x, y) for y in range(fb.height) for x in range(fb.width) if fb.get_pixel(x, y) == 0}
+
+
+class TestPatterns(unittest.TestCase):
+    """Test cases for display test patterns."""
+
+    def test_every_pattern_fits_panel(self):
+        """Test every pattern generates a frame of the panel's data size."""
+        panel = get_panel(PanelType.EPD296x128)
+        for name in PATTERNS:
+            fb = generate(PatternSpec(name), panel=panel)
+            self.assertEqual(len(fb.to_bytes()), panel.array_size, name)
+
+    def test_checkerboard_and_stripes(self):
+        """Test cells and stripes alternate at the requested size."""
+        fb = generate(PatternSpec("checkerboard", size=2), 8, 4)
+        self.assertEqual(fb.to_bytes(), bytes([0x33, 0x33, 0xCC, 0xCC]))
+        fb = generate(PatternSpec("stripes", size=1, vertical=True), 8, 2)
+        self.assertEqual(fb.to_bytes(), bytes([0x55, 0x55]))
+        fb = generate(PatternSpec("stripes", size=1, invert=True), 8, 2)
+        self.assertEqual(fb.to_bytes(), bytes([0xFF, 0x00]))
+
+    def test_gradient_darkens(self):
+        """Test the gradient goes from white to mostly black."""
+        fb = generate(PatternSpec("gradient"), 64, 16)
+        pixels = black_pixels(fb)
+        columns = [sum(1 for y in range(16) if (x, y) in pixels) for x in range(64)]
+        self.assertEqual(columns[0], 0)
+        self.assertGreater(columns[-1], 12)
+        self.assertLessEqual(sum(columns[:32]), sum(columns[32:]))
+
+    def test_crosshatch_and_border_edges(self):
+        """Test crosshatch and border cover all four edges."""
+        for name in ("crosshatch", "border"):
+            pixels = black_pixels(generate(PatternSpec(name), 32, 40))
+            for corner in ((0, 0), (31, 0), (0, 39), (31, 39)):
+                self.assertIn(corner, pixels, name)
+
+    def test_pixel_walk(self):
+        """Test the pixel walk moves one block row by row and wraps."""
+        self.assertEqual(black_pixels(generate(PatternSpec("pixel-walk", step=9), 8, 4)), {(1, 1)})
+        self.assertEqual(black_pixels(generate(PatternSpec("pixel-walk", step=32), 8, 4)), {(0, 0)})
+        self.assertEqual(pixel_walk_steps(8, 4, size=3), 6)
+        self.assertEqual(len(black_pixels(generate(PatternSpec("pixel-walk", size=2, step=1), 8, 4))), 4)
+
+    def test_invalid_spec(self):
+        """Test unknown patterns and sizes are rejected."""
+        with self.assertRaises(PatternError):
+            generate(PatternSpec("plaid"))
+        with self.assertRaises(PatternError):
+            generate(PatternSpec("stripes", size=0))
+
+
+if __name__ == "__main__":
+    unittest.main()
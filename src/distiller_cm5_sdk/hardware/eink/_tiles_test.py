@@ -0,0 +1,201 @@
+#!/usr/bin/env python3
+"""
+Live tile unit tests for CM5 SDK.
+"""
+
+import io
+import os
+import sys
+import tempfile
+import unittest
+import urllib.error
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.display import DisplayMode
+from distiller_cm5_sdk.hardware.eink.graphics import Color, Framebuffer
+from distiller_cm5_sdk.hardware.eink.imaging import DitherMode, ImageOptions, ScalingMethod
+from distiller_cm5_sdk.hardware.eink.pngcodec import encode_png_1bit
+from distiller_cm5_sdk.hardware.eink.tiles import (
+    FileSource, ImageSource, MetricChartSource, RetryPolicy, TileBoard, TileError, UrlSource, render_placeholder,
+)
+
+
+class FakeDisplay:
+    """Records the frames shown."""
+
+    def __init__(self):
+        self.frames = []
+
+    def get_dimensions(self):
+        return (128, 64)
+
+    def display_image_auto(self, data):
+        self.frames.append(Framebuffer(128, 64, data=data))
+        return DisplayMode.PARTIAL
+
+
+class FakeClock:
+    def __init__(self):
+        self.now = 0.0
+
+    def __call__(self):
+        return self.now
+
+
+class BlockSource(ImageSource):
+    """A black block on every fetch, or an error once failing is set."""
+
+    def __init__(self, poll_interval_s):
+        self.poll_interval_s = poll_interval_s
+        self.fetches = 0
+        self.failing = False
+
+    def fetch(self, width, height):
+        self.fetches += 1
+        if self.failing:
+            raise TileError("offline")
+        return Framebuffer(4, 4, fill=Color.BLACK)
+
+
+class FakeResponse(io.BytesIO):
+    def __init__(self, body, headers):
+        super().__init__(body)
+        self.headers = headers
+
+
+class TestTiles(unittest.TestCase):
+    """Test cases for hosting image sources as auto-updating tiles."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.display = FakeDisplay()
+        self.clock = FakeClock()
+        self.board = TileBoard(self.display, clock=self.clock)
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def test_board(self):
+        """Test tiles are fetched when due, composed in place, and only changes refresh the display."""
+        fast, slow = BlockSource(2.0), BlockSource(10.0)
+        self.board.add(fast, 0, 0, 8, 8)
+        self.board.add(slow, 32, 16, 8, 8, border=True)
+        self.assertEqual(self.board.update(), DisplayMode.PARTIAL)
+        frame = self.display.frames[-1]
+        self.assertEqual((frame.get_pixel(3, 3), frame.get_pixel(5, 5)), (Color.BLACK, Color.WHITE))
+        self.assertEqual((frame.get_pixel(35, 19), frame.get_pixel(39, 23), frame.get_pixel(37, 22)),
+                         (Color.BLACK, Color.BLACK, Color.WHITE))
+        self.assertEqual(self.board.next_due(), 2.0)
+
+        self.clock.now = 1.0
+        self.assertIsNone(self.board.update())
+        self.clock.now = 4.0
+        self.board.update()
+        self.assertEqual((fast.fetches, slow.fetches, len(self.display.frames)), (2, 1, 2))
+
+        with self.assertRaises(TileError):
+            self.board.add(BlockSource(2.0), 124, 0, 8, 8)
+        with self.assertRaises(TileError):
+            self.board.add(BlockSource(0.1), 0, 0, 8, 8)
+        with self.assertRaises(TileError):
+            self.board.add(BlockSource(2.0), 0, 0, 8, 8, retry=RetryPolicy(10, 2, 5))
+
+    def test_failures(self):
+        """Test a failing tile shows the placeholder, backs off, and shows its content again once it recovers."""
+        source = BlockSource(60.0)
+        self.board.wall_clock = lambda: 1792161120.0
+        tile = self.board.add(source, 0, 0, 128, 40, retry=RetryPolicy(10, 3, 60))
+        self.board.update()
+        self.assertEqual(tile.updated, 1792161120.0)
+
+        source.failing = True
+        self.clock.now = 60.0
+        with patch("sys.stdout", io.StringIO()) as out:
+            self.assertEqual(self.board.update(), DisplayMode.PARTIAL)
+            delays = [tile.due - self.clock.now]
+            for _ in range(3):
+                self.clock.now = tile.due
+                self.assertIsNone(self.board.update())
+                delays.append(tile.due - self.clock.now)
+        self.assertEqual(delays, [10, 30, 60, 60])
+        self.assertIn("Warning: Tile 0 (BlockSource) not updated: offline; retrying in 10 s", out.getvalue())
+        frame = self.display.frames[-1]
+        self.assertEqual(frame.get_pixel(3, 3), Color.WHITE)
+        placeholder = render_placeholder(128, 40, tile.updated).to_bytes()
+        self.assertEqual(self.board.render().to_bytes()[:640], placeholder)
+        self.assertNotEqual(render_placeholder(128, 40).to_bytes(), placeholder)          # "No data"
+        render_placeholder(6, 6)                                        # only part of the sign fits
+
+        source.failing = False
+        self.clock.now = tile.due
+        self.assertEqual(self.board.update(), DisplayMode.PARTIAL)
+        self.assertEqual((self.display.frames[-1].get_pixel(3, 3), tile.failures), (Color.BLACK, 0))
+        self.assertEqual(tile.due - self.clock.now, 60)
+        self.assertEqual(RetryPolicy(10, 3, 60).delay(5000), 60)
+
+    def test_file_source(self):
+        """Test a file is shown again only when it changes."""
+        path = os.path.join(self.tmpdir.name, "plot.png")
+        with open(path, "wb") as f:
+            f.write(encode_png_1bit(bytes(8), 8, 8))
+        source = FileSource(path, options=ImageOptions(ScalingMethod.NONE, DitherMode.NONE))
+        self.board.add(source, 0, 0, 16, 16)
+        self.board.update()
+        self.assertEqual(self.display.frames[-1].get_pixel(4, 4), Color.BLACK)
+        self.assertIsNone(source.fetch(16, 16))
+
+        with open(path, "wb") as f:
+            f.write(encode_png_1bit(b"\xff" * 32, 16, 16))
+        self.assertEqual(source.fetch(16, 16), path)
+        os.remove(path)
+        with self.assertRaises(TileError):
+            source.fetch(16, 16)
+
+    def test_url_source(self):
+        """Test an unchanged download, by validator or content, is skipped."""
+        png = encode_png_1bit(bytes(8), 8, 8)
+        source = UrlSource("http://example.com/map.png")
+
+        def respond(request, timeout):
+            return FakeResponse(png, {"ETag": '"v1"'})
+
+        with patch("urllib.request.urlopen", side_effect=respond) as urlopen:
+            self.assertEqual(source.fetch(16, 16), png)
+            self.assertIsNone(source.fetch(16, 16))
+        self.assertEqual(urlopen.call_args[0][0].get_header("If-none-match"), '"v1"')
+
+        not_modified = urllib.error.HTTPError(source.url, 304, "Not Modified", {}, None)
+        with patch("urllib.request.urlopen", side_effect=not_modified):
+            self.assertIsNone(source.fetch(16, 16))
+        for error in (urllib.error.HTTPError(source.url, 500, "Error", {}, None), urllib.error.URLError("down")):
+            with self.subTest(error=error):
+                with patch("urllib.request.urlopen", side_effect=error):
+                    with self.assertRaises(TileError):
+                        source.fetch(16, 16)
+
+    def test_metric_chart(self):
+        """Test a chart keeps one sample per column and scales them to its height."""
+        values = iter([1.0, 3.0, 2.0])
+        source = MetricChartSource(lambda: next(values), "CPU", value_format=".1f")
+        for _ in range(3):
+            chart = source.fetch(20, 30)
+        self.assertEqual(source.samples.maxlen, 20)
+        # Newest sample at the right edge, halfway; the highest one column before it, at the top
+        self.assertEqual(chart.get_pixel(19, 20), Color.BLACK)
+        self.assertEqual(chart.get_pixel(18, 10), Color.BLACK)
+        self.assertEqual(chart.get_pixel(17, 29), Color.BLACK)
+        self.assertEqual(chart.get_pixel(0, 20), Color.WHITE)
+
+        fixed = MetricChartSource(lambda: 50, history=3, minimum=0, maximum=100)
+        chart = fixed.fetch(21, 30)
+        self.assertEqual(chart.get_pixel(20, 20), Color.BLACK)
+        with self.assertRaises(TileError):
+            source.fetch(20, 30)                                        # the metric ran out of values
+
+
+if __name__ == "__main__":
+    unittest.main()
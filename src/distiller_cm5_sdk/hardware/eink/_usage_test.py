@@ -0,0 +1,100 @@
+#!/usr/bin/env python3
+"""
+Panel aging counter unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.config import USAGE_FILE_ENV_VAR, DisplayConfig, load_config
+from distiller_cm5_sdk.hardware.eink.display import Display, DisplayMode
+from distiller_cm5_sdk.hardware.eink.backends.mock import MockTransport
+from distiller_cm5_sdk.hardware.eink.backends.python import PythonDisplayLibrary
+from distiller_cm5_sdk.hardware.eink.usage import UsageCounter, load_usage, panel_key
+
+
+class TestPanelUsage(unittest.TestCase):
+    """Test cases for persistent refresh counters."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "usage", "panel-usage.json")
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def test_panel_key(self):
+        """Test panels are keyed by a readable User ID, falling back to the panel type."""
+        self.assertEqual(panel_key("epd128x250", b"\xa1\xb2" + bytes(8)), "uid:a1b2" + "00" * 8)
+        self.assertEqual(panel_key("epd128x250", b"\xff" * 10), "type:epd128x250")
+        self.assertEqual(panel_key("epd128x250", None), "type:epd128x250")
+
+    def test_counts_buffered_and_merged(self):
+        """Test counts are written every flush_every refreshes and merged across processes."""
+        first = UsageCounter("epd128x250", path=self.path, flush_every=3)
+        second = UsageCounter("epd128x250", path=self.path, flush_every=100)
+        first.record(DisplayMode.FULL)
+        first.record(DisplayMode.PARTIAL)
+        self.assertEqual(load_usage(self.path), {})
+        self.assertEqual(first.get().total_refreshes, 2)
+        first.record(DisplayMode.PARTIAL)
+
+        second.record(DisplayMode.FULL)
+        second.flush()
+        usage = load_usage(self.path)["type:epd128x250"]
+        self.assertEqual((usage.full_refreshes, usage.partial_refreshes), (2, 2))
+        self.assertGreater(usage.last_refresh, 0)
+
+    def test_unwritable_file_keeps_counts(self):
+        """Test a failed write keeps the buffered counts for the next attempt."""
+        blocker = os.path.join(self.tmpdir.name, "file")
+        open(blocker, "w").close()
+        counter = UsageCounter("epd128x250", path=os.path.join(blocker, "usage.json"))
+        counter.record(DisplayMode.FULL)
+        counter.flush()
+        self.assertEqual(counter.get().full_refreshes, 1)
+
+    def test_display_counts_refreshes(self):
+        """Test Display counts each refresh under the panel's User ID and writes on close."""
+        config = DisplayConfig(usage_file=self.path)
+        display = Display(backend="python", auto_init=False, config=config)
+        transport = MockTransport(user_id=b"\x12\x34" + bytes(8))
+        display._lib = PythonDisplayLibrary(transport)
+        display.initialize()
+        display.clear()
+        display.display_image(b"\x00" * display.ARRAY_SIZE, DisplayMode.PARTIAL)
+        display.display_image(b"\x00" * display.ARRAY_SIZE, DisplayMode.PARTIAL)
+        self.assertEqual(display.get_usage().partial_refreshes, 2)
+        display.close()
+
+        usage = load_usage(self.path)
+        self.assertEqual(list(usage), ["uid:1234" + "00" * 8])
+        self.assertEqual(usage["uid:1234" + "00" * 8].full_refreshes, 1)
+
+    def test_mock_backend_not_counted(self):
+        """Test the mock backend and a disabled setting record nothing."""
+        with Display(backend="mock", config=DisplayConfig(usage_file=self.path)) as display:
+            display.clear()
+            self.assertIsNone(display.get_usage())
+        self.assertFalse(os.path.exists(self.path))
+
+    @patch.dict(os.environ, {USAGE_FILE_ENV_VAR: "none"}, clear=True)
+    def test_config(self):
+        """Test usage tracking is enabled by usage_file and disabled by none."""
+        config_path = os.path.join(self.tmpdir.name, "eink.conf")
+        with open(config_path, "w") as f:
+            f.write(f"usage_file = {self.path}\n")
+        self.assertIsNone(load_config(config_path).usage_file)
+        del os.environ[USAGE_FILE_ENV_VAR]
+        self.assertEqual(load_config(config_path).usage_file, self.path)
+
+
+if __name__ == "__main__":
+    unittest.main()
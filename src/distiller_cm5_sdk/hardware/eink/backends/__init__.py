@@ -0,0 +1,77 @@
+"""
+Display backend selection.
+
+The default "native" backend loads the compiled C library with ctypes. The
+"python" backend implements the same API in pure Python (spidev + gpiod), and
+the "mock" backend runs that implementation without hardware, capturing frames.
+The "simulator" backend additionally mirrors frames in a desktop window.
+Select one with Display(backend=...) or the DISTILLER_EINK_BACKEND variable.
+"""
+
+import os
+from typing import Dict, Optional
+
+from ..config import SpiConfig
+from ..panels import PanelSpec, PanelType
+
+
+BACKEND_ENV_VAR = "DISTILLER_EINK_BACKEND"
+
+NATIVE_BACKEND = "native"
+PYTHON_BACKEND = "python"
+MOCK_BACKEND = "mock"
+SIMULATOR_BACKEND = "simulator"
+
+BACKENDS = (NATIVE_BACKEND, PYTHON_BACKEND, MOCK_BACKEND, SIMULATOR_BACKEND)
+
+# Backends a display falls back to when its panel cannot be set up (DisplayConfig.fallback_backend)
+FALLBACK_BACKENDS = (MOCK_BACKEND, SIMULATOR_BACKEND)
+
+
+def resolve_backend_name(name: Optional[str] = None) -> str:
+    """
+    Resolve the backend to use.
+
+    Args:
+        name: Explicit backend name, or None to read DISTILLER_EINK_BACKEND
+
+    Returns:
+        Backend name (defaults to "native")
+
+    Raises:
+        ValueError: If the name is not a known backend
+    """
+    if name is None:
+        name = os.environ.get(BACKEND_ENV_VAR, NATIVE_BACKEND)
+    name = name.strip().lower()
+    if name not in BACKENDS:
+        raise ValueError(f"Unknown display backend '{name}'. Expected one of: {', '.join(BACKENDS)}")
+    return name
+
+
+def load_backend(name: str, panel: Optional[PanelSpec] = None, autodetect: bool = False,
+                 panel_ids: Optional[Dict[bytes, PanelType]] = None, spi: Optional[SpiConfig] = None):
+    """
+    Create the library object for a non-native backend.
+
+    Args:
+        name: Backend name as returned by resolve_backend_name()
+        panel: Panel to drive; defaults to EPD128x250
+        autodetect: Identify the panel from its User ID at init (python backend only;
+                    the mock and simulator emulate the panel they were given)
+        panel_ids: Additional User ID prefixes for autodetection
+        spi: SPI device and GPIO wiring (python backend only)
+
+    Returns:
+        Object implementing the display library's C API
+    """
+    if name == PYTHON_BACKEND:
+        from .python import PythonDisplayLibrary
+        return PythonDisplayLibrary(panel=panel, autodetect=autodetect, panel_ids=panel_ids, spi=spi)
+    if name == MOCK_BACKEND:
+        from .mock import create_mock_library
+        return create_mock_library(panel=panel)
+    if name == SIMULATOR_BACKEND:
+        from .simulator import create_simulator_library
+        return create_simulator_library(panel)
+    raise ValueError(f"Backend '{name}' is not loaded through load_backend()")
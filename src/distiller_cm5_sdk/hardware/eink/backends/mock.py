@@ -0,0 +1,148 @@
+#!/usr/bin/env python3
+"""
+Mock display backend for CM5 SDK.
+
+Runs the pure-Python panel logic against a transport that captures frames
+instead of driving SPI/GPIO, so display code can run on development machines.
+Frames are kept in memory and, when DISTILLER_EINK_MOCK_DIR is set, written
+out as PNG files.
+"""
+
+import os
+from collections import deque
+from typing import Callable, Deque, List, Optional
+
+from ..panels import PanelSpec, get_panel
+from ..pngcodec import encode_png_1bit
+from .python import PythonDisplayLibrary, Transport
+
+
+MOCK_DIR_ENV_VAR = "DISTILLER_EINK_MOCK_DIR"
+
+# Frames kept in MockTransport.frames
+MAX_FRAMES = 32
+
+_CMD_WRITE_RAM = 0x24
+_CMD_UPDATE = 0x20
+_CMD_DEEP_SLEEP = 0x10
+_CMD_READ_USER_ID = 0x2E
+
+# Callbacks for every frame shown by any mock or simulator display
+_frame_listeners: List[Callable[["MockTransport", bytes], None]] = []
+
+
+def add_frame_listener(callback: Callable[["MockTransport", bytes], None]) -> None:
+    """Call callback(transport, frame) with each frame a mock or simulator display shows."""
+    _frame_listeners.append(callback)
+
+
+def remove_frame_listener(callback: Callable[["MockTransport", bytes], None]) -> None:
+    """Stop calling a frame listener."""
+    _frame_listeners.remove(callback)
+
+
+class MockTransport(Transport):
+    """
+    Transport that records each refreshed frame instead of touching hardware.
+
+    A frame is captured when the panel's update command follows a RAM write, so
+    frames appear in the order they would have been shown. Frames are packed
+    1-bit data in the panel's native orientation (128x250 by default).
+    """
+
+    def __init__(self, output_dir: Optional[str] = None, max_frames: int = MAX_FRAMES,
+                 panel: Optional[PanelSpec] = None, user_id: Optional[bytes] = None):
+        """
+        Create a mock transport.
+
+        Args:
+            output_dir: Directory to write frame_NNNN.png files into, or None to
+                        keep frames in memory only
+            max_frames: Number of recent frames kept in memory
+            panel: Panel being emulated; defaults to EPD128x250
+            user_id: OTP User ID returned to reads, or None to emulate a link
+                     that cannot read
+        """
+        self.output_dir = output_dir
+        self.panel = panel if panel is not None else get_panel()
+        self.user_id = user_id
+        self.frames: Deque[bytes] = deque(maxlen=max_frames)
+        self.frame_count = 0
+        self.is_open = False
+        self.asleep = False
+        self._ram = b""
+        self._last_cmd = None
+
+    @property
+    def last_frame(self) -> Optional[bytes]:
+        """Most recently shown frame, or None if nothing has been shown."""
+        return self.frames[-1] if self.frames else None
+
+    def open(self) -> bool:
+        if self.output_dir is not None:
+            os.makedirs(self.output_dir, exist_ok=True)
+        self.is_open = True
+        return True
+
+    def close(self) -> None:
+        self.is_open = False
+
+    def reset(self) -> None:
+        self.asleep = False
+
+    def write_command(self, cmd: int) -> None:
+        self._last_cmd = cmd
+        if cmd == _CMD_UPDATE and self._ram:
+            self._capture(self._ram)
+        elif cmd == _CMD_DEEP_SLEEP:
+            self.asleep = True
+
+    def write_data(self, data: bytes) -> None:
+        if self._last_cmd == _CMD_WRITE_RAM:
+            self._ram = bytes(data)
+
+    def wait_busy(self, timeout_ms: int) -> bool:
+        return True
+
+    def read_data(self, length: int) -> Optional[bytes]:
+        if self._last_cmd == _CMD_READ_USER_ID and self.user_id is not None:
+            return self.user_id[:length]
+        return None
+
+    def _capture(self, frame: bytes) -> None:
+        self.frames.append(frame)
+        self.frame_count += 1
+        if self.output_dir is not None:
+            path = os.path.join(self.output_dir, f"frame_{self.frame_count:04d}.png")
+            with open(path, "wb") as f:
+                f.write(encode_png_1bit(frame, self.panel.width, self.panel.height))
+        for listener in list(_frame_listeners):
+            listener(self, frame)
+
+
+def create_mock_library(output_dir: Optional[str] = None,
+                        panel: Optional[PanelSpec] = None) -> PythonDisplayLibrary:
+    """
+    Create a display library backed by a MockTransport.
+
+    Args:
+        output_dir: PNG output directory. If None, uses DISTILLER_EINK_MOCK_DIR
+                    (frames stay in memory when it is unset).
+        panel: Panel to emulate; defaults to EPD128x250
+    """
+    if output_dir is None:
+        output_dir = os.environ.get(MOCK_DIR_ENV_VAR) or None
+    return PythonDisplayLibrary(MockTransport(output_dir, panel=panel), panel)
+
+
+def get_mock_transport(display) -> MockTransport:
+    """
+    Get the MockTransport behind a Display opened with the mock or simulator backend.
+
+    Raises:
+        ValueError: If the display is not using the mock or simulator backend
+    """
+    transport = getattr(display._lib, "transport", None)
+    if not isinstance(transport, MockTransport):
+        raise ValueError("Display is not using the mock or simulator backend")
+    return transport
@@ -0,0 +1,1031 @@
+#!/usr/bin/env python3
+"""
+Pure-Python display backend for CM5 SDK.
+
+Implements the display library's C API in Python so the e-ink display can be
+driven without the compiled libdistiller_display_sdk_shared.so (e.g. on
+minimal/static root filesystems where no cross-compiled library is available).
+
+Hardware access goes through a Transport. SpiGpioTransport talks to the panel
+with the spidev and gpiod Python modules; other transports (such as a mock)
+can be substituted without changing the panel logic.
+"""
+
+import ctypes
+import io
+import logging
+import os
+import threading
+import time
+from typing import Dict, Optional, Tuple
+
+from ..display import (
+    COLD_C, DISPLAY_SDK_ABI_VERSION, JPEG_SIGNATURE, FREEZING_C, TEMPERATURE_INTERNAL, TEMPERATURE_MAX_C, TEMPERATURE_MIN_C, DISPLAY_SDK_VERSION, LUT_SIZE, LUT_SIZE_VOLTAGES, Display, DisplayErrorCode,
+    DisplayEventType, DisplayMode, DisplayStatus, LutKind, LutPreset, PixelFormat, PIXEL_FORMAT_BYTES, RamPlane, RotationMode, _DisplayEventStruct, _DisplayResult,
+    _DisplayStatsStruct,
+    flip_bitpacked_horizontal,
+    invert_bitpacked_colors, rotate_bitpacked, rotate_bitpacked_ccw_90,
+)
+from ..config import SpiConfig
+from ..panels import (
+    CMD_READ_TEMPERATURE, CMD_READ_USER_ID, CMD_WRITE_LUT, CMD_WRITE_TEMPERATURE, FAST_REFRESH_LUT,
+    LUT_FULL_UPDATE, LUT_PARTIAL_UPDATE, LUT_VOLTAGE_COMMANDS, UPDATE_LOAD_TEMPERATURE, USER_ID_LENGTH, PanelSpec,
+    PanelType, decode_temperature, encode_temperature, get_panel, identify_panel,
+)
+from ..pngcodec import PNG_SIGNATURE, PNGError, decode_png
+from ....diagnostics.log_levels import get_logger
+
+
+# Default panel geometry and wiring (mirrors distiller_display_sdk.h)
+EPD_WIDTH = Display.WIDTH
+EPD_HEIGHT = Display.HEIGHT
+EPD_ARRAY = Display.ARRAY_SIZE
+
+DC_PIN = 7
+RST_PIN = 13
+BUSY_PIN = 9
+
+SPI_BUS = 0
+SPI_DEVICE = 0
+SPI_SPEED_HZ = 40000000
+SPI_BLOCK_SIZE = 4096       # spidev's default bufsiz
+
+BUSY_TIMEOUT_MS = 10000
+BUSY_RETRIES = 1            # resets and retries after an update's busy timeout
+
+AUTO_PARTIAL_FRACTION = 8   # display_image_auto() refreshes partially up to 1/8 of the pixels changed
+
+# display_error_string() of each code
+ERROR_STRINGS = {
+    DisplayErrorCode.OK: "success",
+    DisplayErrorCode.INVALID_ARGUMENT: "invalid argument",
+    DisplayErrorCode.NOT_INITIALIZED: "display not initialized",
+    DisplayErrorCode.INVALID_SIZE: "image size mismatch",
+    DisplayErrorCode.CONFIG: "invalid configuration",
+    DisplayErrorCode.SPI: "SPI error",
+    DisplayErrorCode.GPIO: "GPIO error",
+    DisplayErrorCode.TIMEOUT: "busy timeout",
+    DisplayErrorCode.ABORTED: "update aborted",
+    DisplayErrorCode.DECODE: "image decode failed",
+    DisplayErrorCode.NO_MEMORY: "out of memory",
+    DisplayErrorCode.NO_FRAME: "no frame displayed",
+    DisplayErrorCode.UNSUPPORTED: "not supported",
+}
+
+
+class Transport:
+    """
+    Low-level link to the panel controller.
+
+    Subclasses implement the wire protocol; PythonDisplayLibrary builds the
+    panel command sequences on top of it.
+    """
+
+    # Set by PythonDisplayLibrary when display_abort() is called; wait_busy()
+    # implementations that can return early should check it
+    abort: Optional[threading.Event] = None
+
+    def open(self) -> bool:
+        """Acquire the hardware. Returns True on success."""
+        raise NotImplementedError
+
+    def close(self) -> None:
+        """Release the hardware."""
+        raise NotImplementedError
+
+    def reset(self) -> None:
+        """Pulse the controller's reset line."""
+        raise NotImplementedError
+
+    def write_command(self, cmd: int) -> None:
+        """Send a command byte (DC low)."""
+        raise NotImplementedError
+
+    def write_data(self, data: bytes) -> None:
+        """Send data bytes (DC high)."""
+        raise NotImplementedError
+
+    def wait_busy(self, timeout_ms: int) -> bool:
+        """Wait for the busy line to release. Returns False on timeout."""
+        raise NotImplementedError
+
+    def read_data(self, length: int) -> Optional[bytes]:
+        """Read bytes after a read command, or None if the link cannot read."""
+        return None
+
+
+class SpiGpioTransport(Transport):
+    """Transport using the spidev and gpiod (libgpiod v1) Python modules."""
+
+    def __init__(self, bus: int = SPI_BUS, device: int = SPI_DEVICE, speed_hz: int = SPI_SPEED_HZ,
+                 chip_name: str = "gpiochip0", dc_pin: int = DC_PIN, rst_pin: int = RST_PIN,
+                 busy_pin: int = BUSY_PIN, block_size: int = SPI_BLOCK_SIZE, chunk_delay_us: int = 0):
+        self.bus = bus
+        self.device = device
+        self.speed_hz = speed_hz
+        self.chip_name = chip_name
+        self.dc_pin = dc_pin
+        self.rst_pin = rst_pin
+        self.busy_pin = busy_pin
+        self.block_size = block_size
+        self.chunk_delay_us = chunk_delay_us
+        self._spi = None
+        self._chip = None
+        self._dc = None
+        self._rst = None
+        self._busy = None
+        self._log = logging.getLogger("distiller_cm5_sdk.hardware.eink.spi")
+
+    @classmethod
+    def from_config(cls, spi: SpiConfig) -> "SpiGpioTransport":
+        """Create a transport for the wiring described by an SpiConfig."""
+        return cls(bus=spi.bus, device=spi.cs, speed_hz=spi.speed_hz, chip_name=spi.gpio_chip,
+                   dc_pin=spi.dc_pin, rst_pin=spi.reset_pin, busy_pin=spi.busy_pin,
+                   block_size=spi.block_size, chunk_delay_us=spi.chunk_delay_us)
+
+    def open(self) -> bool:
+        # SPI traffic is logged at DEBUG; enable with set_level("hardware.eink.spi", "debug")
+        self._log = get_logger("hardware.eink.spi")
+        try:
+            import spidev
+            import gpiod
+        except ImportError as e:
+            print(f"Error: Python backend requires spidev and gpiod modules: {e}")
+            return False
+
+        try:
+            self._spi = spidev.SpiDev()
+            self._spi.open(self.bus, self.device)
+            self._spi.mode = 0
+            self._spi.bits_per_word = 8
+            self._spi.max_speed_hz = self.speed_hz
+
+            self._chip = gpiod.Chip(self.chip_name)
+            self._dc = self._chip.get_line(self.dc_pin)
+            self._rst = self._chip.get_line(self.rst_pin)
+            self._busy = self._chip.get_line(self.busy_pin)
+            self._dc.request(consumer="dc", type=gpiod.LINE_REQ_DIR_OUT, default_vals=[0])
+            self._rst.request(consumer="rst", type=gpiod.LINE_REQ_DIR_OUT, default_vals=[1])
+            self._busy.request(consumer="busy", type=gpiod.LINE_REQ_DIR_IN)
+        except (OSError, AttributeError) as e:
+            print(f"Error: Failed to open SPI/GPIO: {e}")
+            self.close()
+            return False
+
+        return True
+
+    def close(self) -> None:
+        for line in (self._dc, self._rst, self._busy):
+            if line is not None:
+                try:
+                    line.release()
+                except OSError:
+                    pass
+        self._dc = self._rst = self._busy = None
+
+        if self._chip is not None:
+            self._chip.close()
+            self._chip = None
+        if self._spi is not None:
+            self._spi.close()
+            self._spi = None
+
+    def reset(self) -> None:
+        self._rst.set_value(0)
+        time.sleep(0.01)
+        self._rst.set_value(1)
+        time.sleep(0.01)
+
+    def write_command(self, cmd: int) -> None:
+        if self._log.isEnabledFor(logging.DEBUG):
+            self._log.debug("cmd 0x%02X", cmd)
+        self._dc.set_value(0)
+        self._spi.writebytes2([cmd])
+
+    def write_data(self, data: bytes) -> None:
+        if self._log.isEnabledFor(logging.DEBUG):
+            preview = bytes(data[:16]).hex(" ")
+            self._log.debug("data %d bytes: %s%s", len(data), preview, " ..." if len(data) > 16 else "")
+        self._dc.set_value(1)
+        # Single transfers larger than spidev's bufsiz fail with "Message too long"
+        view = memoryview(bytes(data))
+        for offset in range(0, len(view), self.block_size):
+            if offset and self.chunk_delay_us:
+                time.sleep(self.chunk_delay_us / 1e6)
+            self._spi.writebytes2(view[offset:offset + self.block_size])
+
+    def read_data(self, length: int) -> Optional[bytes]:
+        # Needs the panel's SDA readable by the host (3-wire SPI or MISO tied to SDA);
+        # otherwise the bus reads back all 0x00 or 0xFF, which callers treat as no data
+        self._dc.set_value(1)
+        try:
+            data = bytes(self._spi.readbytes(length))
+        except OSError as e:
+            self._log.debug("read failed: %s", e)
+            return None
+        self._log.debug("read %d bytes: %s", length, data.hex(" "))
+        return data
+
+    def wait_busy(self, timeout_ms: int) -> bool:
+        deadline = time.monotonic() + timeout_ms / 1000.0
+        start = time.monotonic()
+        while self._busy.get_value() == 1:  # =1 BUSY
+            if self.abort is not None and self.abort.is_set():
+                return False
+            if time.monotonic() >= deadline:
+                self._log.debug("busy timeout after %d ms", timeout_ms)
+                return False
+            time.sleep(0.01)
+        if self._log.isEnabledFor(logging.DEBUG):
+            self._log.debug("busy released after %d ms", int((time.monotonic() - start) * 1000))
+        return True
+
+
+def _byref_target(ref):
+    """Return the object behind a ctypes.byref() argument, or None."""
+    if ref is None:
+        return None
+    return getattr(ref, "_obj", ref)
+
+
+def _read_bytes(buf, length: int) -> bytes:
+    """Copy length bytes out of a ctypes array/pointer argument."""
+    return ctypes.string_at(buf, length)
+
+
+def _write_bytes(out, data: bytes) -> None:
+    """Copy data into a ctypes output buffer argument."""
+    ctypes.memmove(out, data, len(data))
+
+
+def _elapsed_ms(start: float) -> int:
+    """Milliseconds since a time.monotonic() reading."""
+    return int((time.monotonic() - start) * 1000)
+
+
+def _options_from(options_ref) -> Tuple[int, bool, bool, bool]:
+    """Resolve a processing_options_t argument to (threshold, rotate, flip, invert)."""
+    options = _byref_target(options_ref)
+    if options is None:
+        return 128, False, False, False
+    return (options.threshold, bool(options.rotate),
+            bool(options.flip_horizontal), bool(options.invert_colors))
+
+
+def pack_pixels_threshold(pixels: bytes, width: int, height: int,
+                          pixel_format: PixelFormat, threshold: int) -> bytes:
+    """Threshold a pixel buffer to packed 1-bit data (1 = white, MSB first)."""
+    bpp = PIXEL_FORMAT_BYTES[PixelFormat(pixel_format)]
+    out = bytearray((width * height + 7) // 8)
+    for idx in range(width * height):
+        p = idx * bpp
+        gray = pixels[p] if bpp == 1 else (pixels[p] + pixels[p + 1] + pixels[p + 2]) // 3
+        if gray > threshold:
+            out[idx // 8] |= 1 << (7 - (idx % 8))
+    return bytes(out)
+
+
+class PythonDisplayLibrary:
+    """
+    Python implementation of the display library's C API.
+
+    Method names and argument conventions match the ctypes signatures used by
+    Display, so an instance can stand in for the loaded shared library.
+    Functions returning a display_error_t in C return True or False, as
+    Display sees the library's functions; display_get_last_error() gives the
+    code.
+    """
+
+    def __init__(self, transport: Optional[Transport] = None, panel: Optional[PanelSpec] = None,
+                 autodetect: bool = False, panel_ids: Optional[Dict[bytes, PanelType]] = None,
+                 spi: Optional[SpiConfig] = None):
+        self._panel = panel if panel is not None else get_panel()
+        if transport is None:
+            if spi is None:
+                # Default wiring, with any pins the panel definition overrides
+                spi = SpiConfig(
+                    reset_pin=self._panel.reset_pin if self._panel.reset_pin is not None else RST_PIN,
+                    busy_pin=self._panel.busy_pin if self._panel.busy_pin is not None else BUSY_PIN,
+                )
+            transport = SpiGpioTransport.from_config(spi)
+        self._transport = transport
+        self._autodetect = autodetect
+        self._panel_ids = panel_ids or {}
+        self._initialized = False
+        self._user_id: Optional[bytes] = None
+        self._user_id_read = False
+        self._rotation = RotationMode.NONE
+        self._stats = _DisplayStatsStruct()         # display_get_stats() counters since init
+        self._busy_timeout_ms = BUSY_TIMEOUT_MS
+        self._busy_retries = BUSY_RETRIES
+        self._busy_timed_out = False
+        self._last_status = DisplayStatus.OK
+        self._last_frame: Optional[bytes] = None   # last frame sent, in panel orientation
+        self._staged_frame: Optional[bytes] = None # frame display_write_plane() put in the new-frame RAM
+        self._auto_max_pixels: Optional[int] = None
+        self._update_lock = threading.Lock()        # one frame update at a time
+        self._abort = threading.Event()             # stop the busy wait in flight
+        self._transport.abort = self._abort
+        self._updates_blocked = False               # set by display_abort() until display_init() or display_wake()
+        self._asleep = False                        # deep sleep entered by display_sleep()
+        self._errors = threading.local()            # most recent failure, per thread as in C
+        self._event_callback = None                 # display_set_event_callback()
+        self._event_user_data = None
+        self._luts = {LutKind.FULL: b"", LutKind.PARTIAL: b""}   # display_set_lut(); empty uses the OTP waveform
+        self._temperature_mc = TEMPERATURE_INTERNAL # display_set_temperature() reading
+        self._sensor_mc: Optional[int] = None       # panel sensor, read after each refresh if the link can
+
+    @property
+    def transport(self) -> Transport:
+        """Transport the panel commands are sent over."""
+        return self._transport
+
+    @property
+    def panel(self) -> PanelSpec:
+        """Panel this library drives; may change during display_init() when autodetecting."""
+        return self._panel
+
+    def read_user_id(self) -> Optional[bytes]:
+        """Read the controller's OTP User ID, or None if the transport cannot read."""
+        self._transport.write_command(CMD_READ_USER_ID)
+        self._user_id = self._transport.read_data(USER_ID_LENGTH)
+        self._user_id_read = True
+        return self._user_id
+
+    @property
+    def user_id(self) -> Optional[bytes]:
+        """OTP User ID of the initialized panel, read once and cached (None if unreadable)."""
+        if self._initialized and not self._user_id_read:
+            self.read_user_id()
+        return self._user_id
+
+    def detect_panel(self) -> Optional[PanelSpec]:
+        """Identify the connected panel from its User ID. Returns None if unknown."""
+        panel_type = identify_panel(self.read_user_id(), self._panel_ids)
+        return get_panel(panel_type) if panel_type is not None else None
+
+    # -- Errors ---------------------------------------------------------------
+
+    def _set_error(self, code: DisplayErrorCode, message: str) -> bool:
+        """Record a failure for display_get_last_error(); returns False."""
+        self._errors.code = code
+        self._errors.message = message
+        return False
+
+    def _fail(self, code: DisplayErrorCode, message: str) -> bool:
+        """Record a failure and report it; returns False."""
+        print(f"Error: {message}")
+        return self._set_error(code, message)
+
+    def _emit_event(self, event_type: DisplayEventType, mode: int = DisplayMode.FULL, duration_ms: int = 0,
+                    error: int = DisplayErrorCode.OK) -> None:
+        if not self._event_callback:
+            return
+        message = None
+        if event_type == DisplayEventType.ERROR:
+            message = getattr(self._errors, "message", "").encode()
+        event = _DisplayEventStruct(ctypes.sizeof(_DisplayEventStruct), event_type, 0, mode, duration_ms, error,
+                                    message)
+        self._event_callback(ctypes.pointer(event), self._event_user_data)
+
+    # -- Panel protocol -----------------------------------------------------
+
+    def _timed_write(self, write, payload, length: int) -> None:
+        """One transport write, counted in the display_get_stats() counters."""
+        start = time.monotonic()
+        write(payload)
+        self._stats.total_spi_us += int((time.monotonic() - start) * 1_000_000)
+        self._stats.spi_transfers += 1
+        self._stats.spi_bytes += length
+
+    def _write_command(self, cmd: int) -> None:
+        self._timed_write(self._transport.write_command, cmd, 1)
+
+    def _write_data(self, data: bytes) -> None:
+        self._timed_write(self._transport.write_data, data, len(data))
+
+    def _cmd(self, cmd: int, *data: int) -> None:
+        self._write_command(cmd)
+        if data:
+            self._write_data(bytes(data))
+
+    def _compensation_mc(self) -> Optional[int]:
+        """Temperature refreshes are compensated for, or None if unknown."""
+        return self._temperature_mc if self._temperature_mc != TEMPERATURE_INTERNAL else self._sensor_mc
+
+    def _is_cold(self) -> bool:
+        temperature = self._compensation_mc()
+        return temperature is not None and temperature < COLD_C * 1000
+
+    def _busy_timeout(self) -> int:
+        """The busy policy's timeout, stretched for a cold panel."""
+        if not self._is_cold():
+            return self._busy_timeout_ms
+        return self._busy_timeout_ms * (3 if self._compensation_mc() < FREEZING_C * 1000 else 2)
+
+    def _read_sensor(self) -> None:
+        """Read the temperature the last refresh loaded from the panel's sensor, if the link can read."""
+        if self.user_id is None:
+            return
+        self._write_command(CMD_READ_TEMPERATURE)
+        data = self._transport.read_data(2)
+        if data is not None and len(data) == 2:
+            self._sensor_mc = decode_temperature(data)
+
+    def _chkstatus(self) -> None:
+        start = time.monotonic()
+        timeout_ms = self._busy_timeout()
+        if self._abort.is_set():
+            print("Warning: Display update aborted")
+            self._busy_timed_out = True
+        elif not self._transport.wait_busy(timeout_ms):
+            if self._abort.is_set():
+                print("Warning: Display update aborted")
+            else:
+                print(f"Warning: Display busy timeout after {timeout_ms} ms")
+            self._busy_timed_out = True
+        self._stats.last_busy_ms = _elapsed_ms(start)
+        self._stats.total_busy_ms += self._stats.last_busy_ms
+
+    def _init_hardware(self) -> None:
+        self._transport.reset()
+        self._chkstatus()
+        self._cmd(0x12)  # SWRESET
+        self._chkstatus()
+
+        for cmd, data in self._panel.init_commands:
+            self._cmd(cmd, *data)
+        if self._panel.lut:
+            self._cmd(CMD_WRITE_LUT, *self._panel.lut)
+        self._chkstatus()
+
+    def _logical_dimensions(self) -> Tuple[int, int]:
+        # Dimensions of the frame callers draw into under the active rotation
+        if self._rotation in (RotationMode.CCW_90, RotationMode.CW_90):
+            return self._panel.height, self._panel.width
+        return self._panel.width, self._panel.height
+
+    def _wake_panel(self) -> bool:
+        # Only a hardware reset brings the controller out of deep sleep, and it
+        # clears the controller RAM. The last frame is written back to both RAM
+        # banks, so the next partial refresh still has its reference frame.
+        start = time.monotonic()
+        self._busy_timed_out = False
+        self._init_hardware()
+        if self._busy_timed_out:
+            self._last_status = DisplayStatus.TIMEOUT
+            self._fail(DisplayErrorCode.TIMEOUT, "Display stayed busy while waking")
+            self._emit_event(DisplayEventType.ERROR, duration_ms=_elapsed_ms(start), error=DisplayErrorCode.TIMEOUT)
+            return False
+        if self._last_frame is not None:
+            self._write_command(0x26)  # previous-frame RAM, compared by partial refresh
+            self._write_data(self._last_frame)
+            self.write_image_data(self._last_frame)
+        self._staged_frame = None
+        self._asleep = False
+        self._last_status = DisplayStatus.OK
+        self._emit_event(DisplayEventType.PANEL_WAKE, duration_ms=_elapsed_ms(start))
+        return True
+
+    def _write_frame(self, data: bytes, mode: int) -> bool:
+        with self._update_lock:
+            if self._updates_blocked:
+                self._last_status = DisplayStatus.ABORTED
+                return self._fail(DisplayErrorCode.ABORTED, "Display updates stopped by display_abort()")
+            return self._send_with_retries(self._to_panel_orientation(data), mode)
+
+    def _to_panel_orientation(self, data: bytes) -> bytes:
+        # Map the caller's frame onto the panel's native orientation
+        if self._rotation == RotationMode.NONE:
+            return data
+        width, height = self._logical_dimensions()
+        return rotate_bitpacked(data, width, height, self._rotation)
+
+    def _send_with_retries(self, data: bytes, mode: int, staged: bool = False) -> bool:
+        # data is in the panel's native orientation
+        if self._asleep:
+            if not self._wake_panel():
+                return False
+            staged = False  # the reset cleared the RAM
+
+        self._emit_event(DisplayEventType.REFRESH_STARTED, mode)
+        start = time.monotonic()
+        ok = self._refresh_panel(data, mode, staged)
+        if ok:
+            if mode == DisplayMode.FULL:
+                self._stats.full_refreshes += 1
+            else:
+                self._stats.partial_refreshes += 1
+            self._stats.last_refresh_ms = _elapsed_ms(start)
+            self._emit_event(DisplayEventType.REFRESH_COMPLETED, mode, self._stats.last_refresh_ms)
+        else:
+            self._stats.failed_refreshes += 1
+            self._emit_event(DisplayEventType.ERROR, mode, _elapsed_ms(start), self._errors.code)
+        return ok
+
+    def _refresh_panel(self, data: bytes, mode: int, staged: bool = False) -> bool:
+        # Refresh with a frame in the panel's native orientation. A staged frame is
+        # already in the new-frame RAM, and is only written again after a reset.
+        self._staged_frame = None
+
+        # A wedged controller is reset and re-initialized, then the frame is sent again
+        for attempt in range(self._busy_retries + 1):
+            if attempt:
+                print(f"Warning: Resetting display after busy timeout (retry {attempt} of {self._busy_retries})")
+                self._init_hardware()
+            self._busy_timed_out = False
+            self._send_frame(data, mode, write=not staged or attempt > 0)
+            if not self._busy_timed_out:
+                self._last_frame = data
+                self._last_status = DisplayStatus.OK
+                if self._temperature_mc == TEMPERATURE_INTERNAL:
+                    self._read_sensor()
+                return True
+            if self._abort.is_set():
+                # The panel content is undefined; leave the controller as it is
+                self._abort.clear()
+                self._last_frame = None
+                self._last_status = DisplayStatus.ABORTED
+                return self._set_error(DisplayErrorCode.ABORTED, "Display update aborted")
+
+        self._last_frame = None
+        self._last_status = DisplayStatus.TIMEOUT
+        return self._fail(DisplayErrorCode.TIMEOUT,
+                          f"Display busy timeout after {self._busy_retries} retries, update abandoned")
+
+    def _send_frame(self, data: bytes, mode: int, write: bool = True) -> None:
+        if mode == DisplayMode.PARTIAL:
+            for cmd, setup in self._panel.partial_setup:
+                self._cmd(cmd, *setup)
+
+        if write:
+            self.write_image_data(data)
+
+        update = self._panel.full_update if mode == DisplayMode.FULL else self._panel.partial_update
+        lut = self._luts[LutKind(mode)]
+        if lut and not self._is_cold():
+            self._write_lut(lut)
+            update = LUT_FULL_UPDATE if mode == DisplayMode.FULL else LUT_PARTIAL_UPDATE
+        elif self._temperature_mc != TEMPERATURE_INTERNAL:
+            self._cmd(CMD_WRITE_TEMPERATURE, *encode_temperature(self._temperature_mc))
+            update &= ~UPDATE_LOAD_TEMPERATURE
+        self._cmd(0x22, update)  # Display Update Control
+        self._cmd(0x20)  # Activate Display Update Sequence
+        self._chkstatus()
+
+    def _write_lut(self, lut: bytes) -> None:
+        """Write a display_set_lut() waveform, and its voltages if it has them."""
+        self._write_command(CMD_WRITE_LUT)
+        self._write_data(lut[:LUT_SIZE])
+        offset = LUT_SIZE
+        if len(lut) == LUT_SIZE_VOLTAGES:
+            for cmd, length in LUT_VOLTAGE_COMMANDS:
+                self._cmd(cmd, *lut[offset:offset + length])
+                offset += length
+
+    def write_image_data(self, data: bytes) -> None:
+        """Write a packed frame to the panel's black/white RAM (0x24), in transport-sized blocks."""
+        self._write_command(0x24)  # write RAM for black(0)/white (1)
+        self._write_data(data)
+
+    def _process_rgba(self, rgba: bytes, width: int, height: int, options_ref) -> Tuple[Optional[bytes], str]:
+        return self._process_pixels(rgba, width, height, PixelFormat.RGBA8888, options_ref)
+
+    def _process_pixels(self, pixels: bytes, width: int, height: int, pixel_format: int,
+                        options_ref) -> Tuple[Optional[bytes], str]:
+        threshold, rotate, flip, invert = _options_from(options_ref)
+        if pixel_format not in PIXEL_FORMAT_BYTES:
+            return None, f"Unsupported pixel format {pixel_format}"
+
+        frame_width, frame_height = self._logical_dimensions()
+        expected = (frame_height, frame_width) if rotate else (frame_width, frame_height)
+        if (width, height) != expected:
+            return None, (f"Image dimensions ({width}x{height}) don't match expected "
+                          f"({expected[0]}x{expected[1]})")
+
+        packed = pack_pixels_threshold(pixels, width, height, pixel_format, threshold)
+
+        # Apply transformations in DistillerGUI order: flip, rotate, then invert colors
+        if flip:
+            packed = flip_bitpacked_horizontal(packed, width, height)
+        if rotate:
+            packed = rotate_bitpacked_ccw_90(packed, width, height)
+        if invert:
+            packed = invert_bitpacked_colors(packed)
+
+        return packed, ""
+
+    def _decode_png(self, data: bytes) -> Tuple[Optional[Tuple[int, int, bytes]], str]:
+        try:
+            return decode_png(data), ""
+        except PNGError as e:
+            return None, str(e)
+
+    def _decode_jpeg(self, data: bytes, expected: Tuple[int, int]) -> Tuple[Optional[Tuple[int, int, bytes]], str]:
+        # libjpeg in the C library; Pillow here. The size is checked before decoding, like the C library does
+        try:
+            from PIL import Image
+        except ImportError:
+            return None, "Pillow is required to decode JPEG"
+        try:
+            with Image.open(io.BytesIO(data), formats=["JPEG"]) as img:
+                if img.mode == "CMYK":
+                    return None, "CMYK images are not supported"
+                if img.size != expected:
+                    return (img.width, img.height, b""), ""
+                return (img.width, img.height, img.convert("RGBA").tobytes()), ""
+        except Exception as e:
+            return None, str(e)
+
+    # -- C API ----------------------------------------------------------------
+
+    def display_sdk_abi_version(self) -> int:
+        return DISPLAY_SDK_ABI_VERSION
+
+    def display_sdk_version(self) -> bytes:
+        return DISPLAY_SDK_VERSION.encode()
+
+    def display_get_last_error(self) -> int:
+        return int(getattr(self._errors, "code", DisplayErrorCode.OK))
+
+    def display_get_last_error_message(self, buf, length: int) -> int:
+        message = getattr(self._errors, "message", "").encode()
+        if buf is not None and length > 0:
+            ctypes.memmove(buf, message[:length - 1] + b"\0", min(len(message), length - 1) + 1)
+        return len(message)
+
+    def display_error_string(self, error: int) -> bytes:
+        return ERROR_STRINGS.get(error, "unknown error").encode()
+
+    def _check_update(self, data) -> bool:
+        if not self._initialized:
+            self._last_status = DisplayStatus.INVALID_ARGUMENT
+            return self._fail(DisplayErrorCode.NOT_INITIALIZED, "Display not initialized")
+        if data is None:
+            self._last_status = DisplayStatus.INVALID_ARGUMENT
+            return self._fail(DisplayErrorCode.INVALID_ARGUMENT, "Invalid image data")
+        return True
+
+    def display_init(self) -> bool:
+        if self._initialized:
+            return True
+        if not self._transport.open():
+            # The transport reported why
+            return self._set_error(DisplayErrorCode.SPI, "Cannot open the display's SPI device or GPIO lines")
+
+        self._stats = _DisplayStatsStruct()
+        self._user_id_read = False
+        self._busy_timed_out = False
+        self._init_hardware()
+        if self._autodetect and not self._busy_timed_out:
+            detected = self.detect_panel()
+            if detected is not None and detected != self._panel:
+                print(f"Detected {detected.description} panel")
+                self._panel = detected
+                self._init_hardware()
+        if self._busy_timed_out:
+            self._transport.close()
+            self._last_status = DisplayStatus.TIMEOUT
+            return self._fail(DisplayErrorCode.TIMEOUT, "Display stayed busy during initialization")
+        self._last_status = DisplayStatus.OK
+        self._last_frame = None
+        self._abort.clear()
+        self._updates_blocked = False
+        self._asleep = False
+        self._initialized = True
+        print("Display SDK initialized successfully")
+        return True
+
+    def display_image_raw(self, data, mode: int) -> bool:
+        if not self._check_update(data):
+            return False
+        return self._write_frame(_read_bytes(data, self._panel.array_size), mode)
+
+    def display_image_auto(self, data, mode_ref) -> bool:
+        if not self._check_update(data):
+            return False
+        frame = _read_bytes(data, self._panel.array_size)
+
+        # Compare in panel orientation, as _last_frame is stored
+        native = self._to_panel_orientation(frame)
+
+        max_pixels = self._auto_max_pixels
+        if max_pixels is None:
+            max_pixels = self._panel.width * self._panel.height // AUTO_PARTIAL_FRACTION
+        mode = DisplayMode.FULL
+        if self._last_frame is not None and max_pixels > 0:
+            changed = bin(int.from_bytes(native, "big") ^ int.from_bytes(self._last_frame, "big")).count("1")
+            if changed <= max_pixels:
+                mode = DisplayMode.PARTIAL
+
+        target = _byref_target(mode_ref)
+        if target is not None:
+            target.value = int(mode)
+        return self._write_frame(frame, mode)
+
+    def display_set_auto_threshold(self, max_pixels: int) -> None:
+        self._auto_max_pixels = max_pixels
+
+    def display_get_framebuffer(self, out) -> bool:
+        if out is None:
+            return self._set_error(DisplayErrorCode.INVALID_ARGUMENT, "Invalid output buffer")
+        if self._last_frame is None:
+            return self._set_error(DisplayErrorCode.NO_FRAME,
+                                   "No frame displayed since initialization or the last failed update")
+        ctypes.memmove(out, self._last_frame, len(self._last_frame))
+        return True
+
+    def display_restore_frame(self, data) -> bool:
+        if not self._check_update(data):
+            return False
+        with self._update_lock:
+            if self._updates_blocked:
+                self._last_status = DisplayStatus.ABORTED
+                return self._fail(DisplayErrorCode.ABORTED, "Display updates stopped by display_abort()")
+            self._last_frame = _read_bytes(data, self._panel.array_size)
+            self._staged_frame = None
+            # A sleeping panel gets it when it wakes
+            if not self._asleep:
+                self._write_command(0x26)  # previous-frame RAM, compared by partial refresh
+                self._write_data(self._last_frame)
+                self.write_image_data(self._last_frame)
+        return True
+
+    def display_write_plane(self, plane: int, data) -> bool:
+        if not self._check_update(data):
+            return False
+        if plane not in (RamPlane.NEW, RamPlane.OLD):
+            return self._set_error(DisplayErrorCode.INVALID_ARGUMENT, f"Invalid RAM plane {plane}")
+        with self._update_lock:
+            if self._updates_blocked:
+                self._last_status = DisplayStatus.ABORTED
+                return self._fail(DisplayErrorCode.ABORTED, "Display updates stopped by display_abort()")
+            if self._asleep and not self._wake_panel():
+                return False
+            frame = self._to_panel_orientation(_read_bytes(data, self._panel.array_size))
+            if plane == RamPlane.NEW:
+                self.write_image_data(frame)
+                # Kept to write again should a busy timeout reset the controller during the flip
+                self._staged_frame = frame
+            else:
+                self._write_command(0x26)  # previous-frame RAM, compared by partial refresh
+                self._write_data(frame)
+        return True
+
+    def display_flip(self, mode: int) -> bool:
+        if not self._initialized:
+            self._last_status = DisplayStatus.INVALID_ARGUMENT
+            return self._fail(DisplayErrorCode.NOT_INITIALIZED, "Display not initialized")
+        with self._update_lock:
+            if self._updates_blocked:
+                self._last_status = DisplayStatus.ABORTED
+                return self._fail(DisplayErrorCode.ABORTED, "Display updates stopped by display_abort()")
+            if self._staged_frame is None:
+                return self._set_error(DisplayErrorCode.NO_FRAME,
+                                       "No frame staged with display_write_plane() since the last update")
+            return self._send_with_retries(self._staged_frame, mode, staged=True)
+
+    def display_abort(self) -> None:
+        self._updates_blocked = True
+        self._abort.set()
+
+    def display_emergency_frame(self, data) -> bool:
+        # Block updates first, so none waiting on the lock runs before this frame
+        self.display_abort()
+        with self._update_lock:
+            self._abort.clear()
+            ok = True
+            if data is not None:
+                if not self._check_update(data):
+                    ok = False
+                else:
+                    frame = _read_bytes(data, self._panel.array_size)
+                    ok = self._send_with_retries(self._to_panel_orientation(frame), DisplayMode.FULL)
+            self.display_sleep()
+        return ok
+
+    def display_image_png(self, filename: bytes, mode: int) -> bool:
+        if not self._initialized:
+            return self._fail(DisplayErrorCode.NOT_INITIALIZED, "Display not initialized")
+        output = (ctypes.c_ubyte * self._panel.array_size)()
+        if not self.convert_png_to_1bit(filename, output):
+            return False
+        return self.display_image_raw(output, mode)
+
+    def display_image_from_memory(self, buf, length: int, mode: int) -> bool:
+        if not self._initialized:
+            return self._fail(DisplayErrorCode.NOT_INITIALIZED, "Display not initialized")
+        output = (ctypes.c_ubyte * self._panel.array_size)()
+        if not self.process_image_from_memory(buf, length, output):
+            return False
+        return self.display_image_raw(output, mode)
+
+    def display_clear(self) -> bool:
+        if not self._initialized:
+            return self._fail(DisplayErrorCode.NOT_INITIALIZED, "Display not initialized")
+        size = self._panel.array_size
+        white = (ctypes.c_ubyte * size)(*([0xFF] * size))
+        return self.display_image_raw(white, DisplayMode.FULL)
+
+    def display_sleep(self) -> None:
+        if not self._initialized:
+            return
+        self._cmd(0x10, 0x01)  # Enter deep sleep
+        time.sleep(0.1)
+        self._asleep = True
+        self._emit_event(DisplayEventType.PANEL_SLEEP)
+
+    def display_wake(self) -> bool:
+        if not self._initialized:
+            return self.display_init()
+        with self._update_lock:
+            self._abort.clear()
+            self._updates_blocked = False
+            return self._wake_panel()
+
+    def display_is_asleep(self) -> bool:
+        return self._initialized and self._asleep
+
+    def display_cleanup(self) -> None:
+        self._transport.close()
+        self._asleep = False
+        self._initialized = False
+        print("Display SDK cleaned up")
+
+    def display_set_event_callback(self, callback, user_data) -> None:
+        with self._update_lock:
+            self._event_callback = callback
+            self._event_user_data = user_data
+
+    def display_set_busy_policy(self, timeout_ms: int, retries: int) -> bool:
+        if timeout_ms <= 0 or retries < 0:
+            return self._fail(DisplayErrorCode.CONFIG, "Busy timeout must be non-zero")
+        self._busy_timeout_ms = timeout_ms
+        self._busy_retries = retries
+        return True
+
+    def display_set_lut(self, kind: int, lut, length: int) -> bool:
+        if kind not in (LutKind.FULL, LutKind.PARTIAL):
+            return self._fail(DisplayErrorCode.INVALID_ARGUMENT, f"Invalid LUT kind {kind}")
+        if lut is not None and length not in (LUT_SIZE, LUT_SIZE_VOLTAGES):
+            return self._fail(DisplayErrorCode.INVALID_ARGUMENT,
+                              f"LUT must be {LUT_SIZE} or {LUT_SIZE_VOLTAGES} bytes, got {length}")
+        with self._update_lock:
+            self._luts[LutKind(kind)] = _read_bytes(lut, length) if lut is not None else b""
+        return True
+
+    def display_set_lut_preset(self, kind: int, preset: int) -> bool:
+        if preset == LutPreset.OTP:
+            return self.display_set_lut(kind, None, 0)
+        if preset == LutPreset.FAST_REFRESH:
+            return self.display_set_lut(kind, FAST_REFRESH_LUT, len(FAST_REFRESH_LUT))
+        return self._fail(DisplayErrorCode.INVALID_ARGUMENT, f"Invalid LUT preset {preset}")
+
+    def display_set_temperature(self, millicelsius: int) -> bool:
+        if millicelsius != TEMPERATURE_INTERNAL and not \
+                TEMPERATURE_MIN_C * 1000 <= millicelsius <= TEMPERATURE_MAX_C * 1000:
+            return self._fail(DisplayErrorCode.INVALID_ARGUMENT, f"Temperature {millicelsius} m°C out of range")
+        with self._update_lock:
+            self._temperature_mc = millicelsius
+        return True
+
+    def display_get_temperature(self, millicelsius_ref) -> bool:
+        millicelsius = _byref_target(millicelsius_ref)
+        if millicelsius is None:
+            return self._fail(DisplayErrorCode.INVALID_ARGUMENT, "Invalid temperature pointer")
+        temperature = self._compensation_mc()
+        if temperature is None:
+            return self._set_error(DisplayErrorCode.UNSUPPORTED,
+                                   "The panel's temperature sensor cannot be read over this link; "
+                                   "set a reading with display_set_temperature()")
+        millicelsius.value = temperature
+        return True
+
+    def display_get_last_status(self) -> int:
+        return int(self._last_status)
+
+    def display_get_busy_stats(self, last_ref, total_ref) -> None:
+        last = _byref_target(last_ref)
+        total = _byref_target(total_ref)
+        if last is not None:
+            last.value = self._stats.last_busy_ms
+        if total is not None:
+            total.value = self._stats.total_busy_ms
+
+    def display_get_stats(self, stats_ref) -> bool:
+        stats = _byref_target(stats_ref)
+        if stats is None or stats.struct_size < _DisplayStatsStruct.full_refreshes.offset:
+            return self._fail(DisplayErrorCode.INVALID_ARGUMENT, "Invalid display_stats_t")
+        # Callers built against an older header get the fields they know about
+        caller_size = stats.struct_size
+        ctypes.memmove(ctypes.addressof(stats), ctypes.addressof(self._stats),
+                       min(caller_size, ctypes.sizeof(_DisplayStatsStruct)))
+        stats.struct_size = caller_size
+        if self._stats.spi_transfers:
+            stats.average_spi_us = self._stats.total_spi_us // self._stats.spi_transfers
+        return True
+
+    def display_set_rotation(self, rotation: int) -> bool:
+        try:
+            self._rotation = RotationMode(rotation)
+        except ValueError:
+            return self._fail(DisplayErrorCode.INVALID_ARGUMENT, f"Invalid rotation {rotation}")
+        return True
+
+    def display_get_rotation(self) -> int:
+        return int(self._rotation)
+
+    def display_get_dimensions(self, width_ref, height_ref) -> None:
+        width = _byref_target(width_ref)
+        height = _byref_target(height_ref)
+        logical_width, logical_height = self._logical_dimensions()
+        if width is not None:
+            width.value = logical_width
+        if height is not None:
+            height.value = logical_height
+
+    def convert_png_to_1bit(self, filename: bytes, output_data) -> bool:
+        if not filename or output_data is None:
+            return self._set_error(DisplayErrorCode.INVALID_ARGUMENT, "Invalid filename or output buffer")
+        try:
+            with open(os.fsdecode(filename), "rb") as f:
+                data = f.read()
+        except OSError as e:
+            return self._fail(DisplayErrorCode.DECODE, f"Cannot load PNG {os.fsdecode(filename)}: {e}")
+        return self.process_image_from_memory(data, len(data), output_data)
+
+    def process_image_from_memory(self, buf, length: int, output_data) -> bool:
+        if buf is None or length == 0 or output_data is None:
+            return self._set_error(DisplayErrorCode.INVALID_ARGUMENT, "Invalid image buffer or output buffer")
+        data = buf if isinstance(buf, bytes) else _read_bytes(buf, length)
+        expected_width, expected_height = self._logical_dimensions()
+        if data.startswith(JPEG_SIGNATURE):
+            kind = "JPEG"
+            decoded, error = self._decode_jpeg(data, (expected_width, expected_height))
+        elif data.startswith(PNG_SIGNATURE):
+            kind = "PNG"
+            decoded, error = self._decode_png(data)
+        else:
+            return self._set_error(DisplayErrorCode.INVALID_ARGUMENT,
+                                   "Not a PNG or JPEG image; only these are decoded from memory")
+        if decoded is None:
+            return self._fail(DisplayErrorCode.DECODE, f"Cannot decode {kind} buffer: {error}")
+
+        width, height, rgba = decoded
+        if (width, height) != (expected_width, expected_height):
+            return self._fail(DisplayErrorCode.INVALID_SIZE,
+                              f"{kind} dimensions ({width}x{height}) don't match display "
+                              f"({expected_width}x{expected_height})")
+
+        packed, _ = self._process_rgba(rgba, width, height, None)
+        _write_bytes(output_data, packed)
+        return True
+
+    def display_show_file(self, filename: bytes, options_ref, mode: int) -> _DisplayResult:
+        if not filename:
+            return _DisplayResult(DisplayStatus.INVALID_ARGUMENT, b"Invalid filename")
+        if not self._initialized and not self.display_init():
+            message = f"Failed to initialize display hardware: {self._errors.message}"
+            return _DisplayResult(DisplayStatus.INIT_FAILED, message.encode()[:127])
+
+        path = os.fsdecode(filename)
+        try:
+            with open(path, "rb") as f:
+                decoded, error = self._decode_png(f.read())
+        except OSError as e:
+            decoded, error = None, str(e)
+        if decoded is None:
+            return _DisplayResult(DisplayStatus.LOAD_FAILED, f"Failed to load {path}: {error}".encode()[:127])
+
+        width, height, rgba = decoded
+        packed, error = self._process_rgba(rgba, width, height, options_ref)
+        if packed is None:
+            return _DisplayResult(DisplayStatus.PROCESS_FAILED, error.encode()[:127])
+
+        if not self._write_frame(packed, mode):
+            message = f"Display stayed busy after reset and {self._busy_retries} retries"
+            return _DisplayResult(DisplayStatus.TIMEOUT, message.encode())
+        return _DisplayResult(DisplayStatus.OK, b"OK")
+
+    def process_pixels_for_display(self, pixels, width: int, height: int, pixel_format: int,
+                                   options_ref, output_data) -> bool:
+        if pixels is None or output_data is None:
+            return self._set_error(DisplayErrorCode.INVALID_ARGUMENT, "Invalid pixel buffer or output buffer")
+        if pixel_format not in PIXEL_FORMAT_BYTES:
+            return self._fail(DisplayErrorCode.INVALID_ARGUMENT, f"Unsupported pixel format {pixel_format}")
+        length = width * height * PIXEL_FORMAT_BYTES[PixelFormat(pixel_format)]
+        packed, error = self._process_pixels(_read_bytes(pixels, length), width, height,
+                                             pixel_format, options_ref)
+        if packed is None:
+            return self._fail(DisplayErrorCode.INVALID_SIZE, error)
+        _write_bytes(output_data, packed)
+        return True
+
+    def display_pixels(self, pixels, width: int, height: int, pixel_format: int,
+                       options_ref, mode: int) -> bool:
+        if not self._initialized:
+            return self._fail(DisplayErrorCode.NOT_INITIALIZED, "Display not initialized")
+        output = (ctypes.c_ubyte * self._panel.array_size)()
+        if not self.process_pixels_for_display(pixels, width, height, pixel_format, options_ref, output):
+            return False
+        return self.display_image_raw(output, mode)
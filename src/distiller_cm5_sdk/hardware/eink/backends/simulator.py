@@ -0,0 +1,207 @@
+#!/usr/bin/env python3
+"""
+Desktop simulator display backend for CM5 SDK.
+
+Extends the mock backend with a pygame (SDL2) window that mirrors the panel
+as frames are refreshed, including the panel's refresh latency and the
+ghosting that builds up over partial refreshes. Requires the "simulator"
+extra: pip install distiller-cm5-sdk[simulator]
+"""
+
+import os
+import time
+from typing import Callable, List, Optional
+
+from ..panels import PanelSpec
+from .mock import MOCK_DIR_ENV_VAR, MockTransport
+from .python import PythonDisplayLibrary
+
+
+SCALE_ENV_VAR = "DISTILLER_EINK_SIM_SCALE"
+SPEED_ENV_VAR = "DISTILLER_EINK_SIM_SPEED"
+
+# Rendered gray levels
+PAPER = 232
+INK = 24
+GHOST_STEP = 18   # residue added per partial refresh a pixel has changed in
+MAX_GHOST = 4
+
+_CMD_UPDATE_CONTROL = 0x22
+
+# Open simulator windows, and callbacks for the keys pressed in them
+_windows: List["SimulatorTransport"] = []
+_key_listeners: List[Callable[[str], None]] = []
+
+
+def add_key_listener(callback: Callable[[str], None]) -> None:
+    """Call callback with the name of each key pressed in a simulator window, e.g. "up" or "return"."""
+    _key_listeners.append(callback)
+
+
+def remove_key_listener(callback: Callable[[str], None]) -> None:
+    """Stop calling a key listener."""
+    _key_listeners.remove(callback)
+
+
+def pump_windows() -> None:
+    """
+    Process the events of the open simulator windows.
+
+    Windows are pumped while a frame refreshes; code waiting for key presses
+    between refreshes calls this to keep them coming.
+    """
+    for window in list(_windows):
+        window._pump()
+
+
+class SimulatorTransport(MockTransport):
+    """
+    MockTransport that also shows each frame in a desktop window.
+
+    Full refreshes wipe all ghosting. Each partial refresh leaves a faint
+    residue on pixels that changed from black to white, darkening with
+    repeated partial updates, as on the real panel.
+    """
+
+    def __init__(self, scale: int = 3, speed: float = 1.0, output_dir: Optional[str] = None,
+                 window: bool = True, panel: Optional[PanelSpec] = None):
+        """
+        Create a simulator transport.
+
+        Args:
+            scale: Window pixels per panel pixel
+            speed: Multiplier for simulated refresh latency (0 disables it)
+            output_dir: Optional directory to also write frame PNGs into
+            window: Open the desktop window; False runs the model headless
+            panel: Panel to emulate, including its refresh times; defaults to EPD128x250
+        """
+        super().__init__(output_dir, panel=panel)
+        self.scale = max(1, scale)
+        self.speed = max(0.0, speed)
+        self.window = window
+        self.partial = False
+        self._ghost = bytearray(self.panel.width * self.panel.height)
+        self._previous: Optional[bytes] = None
+        self._busy_s = 0.0
+        self._pygame = None
+        self._screen = None
+
+    def open(self) -> bool:
+        if not super().open():
+            return False
+        if not self.window:
+            return True
+        try:
+            import pygame
+        except ImportError as e:
+            print(f"Error: Simulator backend requires pygame (pip install distiller-cm5-sdk[simulator]): {e}")
+            return False
+
+        pygame.display.init()
+        self._pygame = pygame
+        self._screen = pygame.display.set_mode((self.panel.width * self.scale,
+                                                self.panel.height * self.scale))
+        pygame.display.set_caption(f"Distiller e-ink simulator ({self.panel.description})")
+        _windows.append(self)
+        self._show(self.render_rgb(b"\xff" * self.panel.array_size))
+        return True
+
+    def close(self) -> None:
+        self._close_window()
+        super().close()
+
+    def _close_window(self) -> None:
+        if self._pygame is not None:
+            self._pygame.display.quit()
+            self._pygame = None
+            self._screen = None
+        if self in _windows:
+            _windows.remove(self)
+
+    def write_data(self, data: bytes) -> None:
+        if self._last_cmd == _CMD_UPDATE_CONTROL and data:
+            self.partial = data[0] == self.panel.partial_update
+        super().write_data(data)
+
+    def wait_busy(self, timeout_ms: int) -> bool:
+        deadline = time.monotonic() + self._busy_s
+        self._busy_s = 0.0
+        while True:
+            self._pump()
+            remaining = deadline - time.monotonic()
+            if remaining <= 0:
+                return True
+            time.sleep(min(remaining, 0.02))
+
+    def _capture(self, frame: bytes) -> None:
+        super()._capture(frame)
+        self.update_ghosting(frame, self.partial)
+        refresh_s = self.panel.partial_refresh_s if self.partial else self.panel.full_refresh_s
+        self._busy_s = refresh_s * self.speed
+        self._show(self.render_rgb(frame))
+
+    def update_ghosting(self, frame: bytes, partial: bool) -> None:
+        """Advance the ghosting model for a newly refreshed frame."""
+        previous = self._previous
+        self._previous = frame
+        pixels = self.panel.width * self.panel.height
+        if not partial or previous is None:
+            self._ghost = bytearray(pixels)
+            return
+        for idx in range(pixels):
+            shift = 7 - (idx % 8)
+            was_black = not (previous[idx // 8] >> shift) & 1
+            now_white = (frame[idx // 8] >> shift) & 1
+            if was_black and now_white and self._ghost[idx] < MAX_GHOST:
+                self._ghost[idx] += 1
+
+    def render_rgb(self, frame: bytes) -> bytes:
+        """Render a frame, with ghosting, to RGB bytes at panel resolution."""
+        pixels = self.panel.width * self.panel.height
+        out = bytearray(pixels * 3)
+        for idx in range(pixels):
+            if (frame[idx // 8] >> (7 - (idx % 8))) & 1:
+                level = PAPER - self._ghost[idx] * GHOST_STEP
+            else:
+                level = INK
+            out[idx * 3:idx * 3 + 3] = bytes((level, level, level))
+        return bytes(out)
+
+    def _show(self, rgb: bytes) -> None:
+        if self._screen is None:
+            return
+        pygame = self._pygame
+        surface = pygame.image.frombuffer(rgb, (self.panel.width, self.panel.height), "RGB")
+        self._screen.blit(pygame.transform.scale(surface, self._screen.get_size()), (0, 0))
+        pygame.display.flip()
+        self._pump()
+
+    def _pump(self) -> None:
+        """Keep the window responsive; closing it stops mirroring but not the app."""
+        if self._pygame is None:
+            return
+        for event in self._pygame.event.get():
+            if event.type == self._pygame.QUIT:
+                self._close_window()
+                return
+            if event.type == self._pygame.KEYDOWN:
+                key = self._pygame.key.name(event.key)
+                for listener in list(_key_listeners):
+                    listener(key)
+
+
+def create_simulator_library(panel: Optional[PanelSpec] = None) -> PythonDisplayLibrary:
+    """
+    Create a display library backed by a SimulatorTransport.
+
+    Args:
+        panel: Panel to emulate; defaults to EPD128x250
+
+    Window scale and latency come from DISTILLER_EINK_SIM_SCALE (default 3) and
+    DISTILLER_EINK_SIM_SPEED (default 1.0; 0 disables latency). Frames are also
+    written to DISTILLER_EINK_MOCK_DIR when it is set.
+    """
+    scale = int(os.environ.get(SCALE_ENV_VAR, "3"))
+    speed = float(os.environ.get(SPEED_ENV_VAR, "1.0"))
+    output_dir = os.environ.get(MOCK_DIR_ENV_VAR) or None
+    return PythonDisplayLibrary(SimulatorTransport(scale, speed, output_dir, panel=panel), panel)
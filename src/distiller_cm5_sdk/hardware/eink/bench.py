@@ -0,0 +1,299 @@
+#!/usr/bin/env python3
+"""
+Benchmarks of the e-ink image pipeline.
+
+Times each stage a frame goes through on its way to the panel, on every
+supported panel size, so a change that slows one down is caught before
+release rather than noticed as a sluggish device:
+
+- dither-<mode>: dither_grayscale() of a frame-sized image, for each DitherMode
+- scale-<method>: fit(), resize_grayscale() and place_grayscale() of a
+  photo-sized image, for each ScalingMethod, with smart_crop_position()
+  for smart-crop
+- pack-<format>: Display.convert_pixels_to_raw() of a frame-sized buffer,
+  for each PixelFormat; thresholding and packing into the 1-bit frame
+
+The pack cases run on a Display of the given backend that is never
+initialized, so no panel is needed. The mock backend runs anywhere and
+suits CI; the native backend times the C library, including its NEON path
+on 64-bit ARM, and needs to run on the device.
+
+Each case is timed repeat times and its fastest run kept, which is least
+affected by other load. Compared with a baseline report, a case regresses
+when it takes more than max_regression times as long as before:
+
+    baseline = BenchReport.load("bench-1.4.json")
+    report = run_benchmarks(backend="native").compare(baseline)
+    print(report.format_text())
+
+From the command line:
+python3 -m distiller_cm5_sdk.diagnostics eink-bench --baseline bench-1.4.json
+"""
+
+import json
+import platform
+import statistics
+import time
+from dataclasses import asdict, dataclass, field
+from typing import Callable, List, Optional, Sequence
+
+from .config import DisplayConfig
+from .display import Display, PixelFormat, PIXEL_FORMAT_BYTES
+from .imaging import (
+    DitherMode, ScalingMethod, dither_grayscale, fit, place_grayscale, resize_grayscale, smart_crop_position,
+)
+from .panels import PANELS, PanelType
+
+
+CASES = (tuple(f"dither-{mode.value}" for mode in DitherMode)
+         + tuple(f"scale-{method.value}" for method in ScalingMethod)
+         + tuple(f"pack-{pixel_format.name.lower()}" for pixel_format in PixelFormat))
+
+DEFAULT_REPEAT = 5
+DEFAULT_MAX_REGRESSION = 1.5    # times the baseline's fastest run
+
+# Slowdowns smaller than this are timer noise, whatever the ratio
+MIN_REGRESSION_MS = 1.0
+
+# Size of the image the scale cases fit to the frame, as from a phone photo scaled down
+SOURCE_SIZE = (640, 480)
+
+
+class BenchError(Exception):
+    """Custom exception for benchmark errors."""
+    pass
+
+
+@dataclass
+class CaseResult:
+    """Timings of one case on one panel size."""
+    case: str
+    panel: str
+    width: int
+    height: int
+    runs_ms: List[float]
+    baseline_ms: Optional[float] = None     # fastest run of the baseline, if it has this case
+
+    @property
+    def best_ms(self) -> float:
+        return min(self.runs_ms)
+
+    @property
+    def median_ms(self) -> float:
+        return statistics.median(self.runs_ms)
+
+    @property
+    def ratio(self) -> Optional[float]:
+        """Fastest run relative to the baseline's, or None without one."""
+        if not self.baseline_ms:
+            return None
+        return self.best_ms / self.baseline_ms
+
+    def regressed(self, max_regression: float) -> bool:
+        return (self.baseline_ms is not None and self.best_ms > self.baseline_ms * max_regression
+                and self.best_ms - self.baseline_ms >= MIN_REGRESSION_MS)
+
+
+@dataclass
+class BenchReport:
+    """Everything a run measured, with the backend and host it ran on."""
+    started: float
+    backend: str
+    host: str
+    machine: str
+    repeat: int
+    max_regression: float = DEFAULT_MAX_REGRESSION
+    results: List[CaseResult] = field(default_factory=list)
+
+    @property
+    def regressions(self) -> List[CaseResult]:
+        return [result for result in self.results if result.regressed(self.max_regression)]
+
+    @property
+    def passed(self) -> bool:
+        """True if no case regressed against the baseline."""
+        return not self.regressions
+
+    def compare(self, baseline: "BenchReport", max_regression: Optional[float] = None) -> "BenchReport":
+        """
+        Set the baseline timing of every case the baseline report also has.
+
+        Args:
+            baseline: A report of the same cases, e.g. from the last release
+            max_regression: Slowdown factor that fails a case; defaults to the report's
+
+        Returns:
+            This report
+
+        Raises:
+            BenchError: If the baseline was measured with another backend
+        """
+        if baseline.backend != self.backend:
+            raise BenchError(f"Baseline was measured with the {baseline.backend} backend, not {self.backend}")
+        if max_regression is not None:
+            if max_regression <= 0:
+                raise BenchError(f"Invalid regression factor {max_regression}")
+            self.max_regression = max_regression
+        before = {(result.case, result.panel): result.best_ms for result in baseline.results}
+        for result in self.results:
+            result.baseline_ms = before.get((result.case, result.panel))
+        return self
+
+    def to_dict(self) -> dict:
+        """The report as JSON-serializable data."""
+        data = asdict(self)
+        data["passed"] = self.passed
+        for result, entry in zip(self.results, data["results"]):
+            entry["best_ms"] = result.best_ms
+            entry["median_ms"] = result.median_ms
+        return data
+
+    @classmethod
+    def from_dict(cls, data: dict) -> "BenchReport":
+        """A report saved with to_dict()."""
+        try:
+            results = [CaseResult(entry["case"], entry["panel"], entry["width"], entry["height"],
+                                  [float(ms) for ms in entry["runs_ms"]], entry.get("baseline_ms"))
+                       for entry in data["results"]]
+            return cls(float(data["started"]), data["backend"], data["host"], data["machine"], int(data["repeat"]),
+                       float(data.get("max_regression", DEFAULT_MAX_REGRESSION)), results)
+        except (KeyError, TypeError, ValueError) as e:
+            raise BenchError(f"Malformed benchmark report: {e!r}")
+
+    def save(self, path: str) -> None:
+        """Save the report as JSON, e.g. as the baseline of later runs."""
+        with open(path, "w") as f:
+            json.dump(self.to_dict(), f, indent=2)
+
+    @classmethod
+    def load(cls, path: str) -> "BenchReport":
+        """
+        Load a report saved with save().
+
+        Raises:
+            BenchError: If the file cannot be read or is not a report
+        """
+        try:
+            with open(path, "r") as f:
+                data = json.load(f)
+        except (OSError, ValueError) as e:
+            raise BenchError(f"Cannot read benchmark report {path}: {e}")
+        return cls.from_dict(data)
+
+    def format_text(self) -> str:
+        """A human-readable table, marking every regressed case."""
+        started = time.strftime("%Y-%m-%d %H:%M:%S", time.localtime(self.started))
+        lines = [f"E-ink benchmark {started} on {self.host} ({self.machine})",
+                 f"{self.backend} backend, best of {self.repeat} runs"]
+        for result in self.results:
+            line = (f"{result.case:<22} {result.width:>3}x{result.height:<3} "
+                    f"best {result.best_ms:>9.2f} ms  median {result.median_ms:>9.2f} ms")
+            if result.ratio is not None:
+                line += f"  {result.ratio:>5.2f}x baseline"
+                if result.regressed(self.max_regression):
+                    line += "  REGRESSED"
+            lines.append(line)
+        if any(result.baseline_ms is not None for result in self.results):
+            lines.append("PASSED" if self.passed else
+                         f"FAILED: {len(self.regressions)} cases over {self.max_regression:g}x their baseline")
+        return "\n".join(lines)
+
+
+def sample_pixels(width: int, height: int, channels: int = 1) -> bytes:
+    """A deterministic photo-like image: gradients with fine texture, every gray level in use."""
+    pixels = bytearray(width * height * channels)
+    i = 0
+    for y in range(height):
+        for x in range(width):
+            base = (x * 255 // max(1, width - 1) + y * 255 // max(1, height - 1)) // 2
+            texture = ((x * 7) ^ (y * 13)) & 0x1F
+            for channel in range(channels):
+                pixels[i] = (base + texture + channel * 40) & 0xFF
+                i += 1
+    return bytes(pixels)
+
+
+def _case_action(case: str, panel: PanelType, backend: str) -> Callable[[], object]:
+    """The timed operation of a case on a panel, with its inputs prepared beforehand."""
+    spec = PANELS[panel]
+    width, height = spec.width, spec.height
+    kind, _, name = case.partition("-")
+    if kind == "dither":
+        mode = DitherMode(name)
+        pixels = sample_pixels(width, height)
+        return lambda: dither_grayscale(pixels, width, height, mode)
+    if kind == "scale":
+        method = ScalingMethod(name)
+        source_width, source_height = SOURCE_SIZE
+        pixels = sample_pixels(source_width, source_height)
+
+        def scale():
+            scaled_width, scaled_height, x, y = fit(source_width, source_height, width, height, method)
+            scaled = resize_grayscale(pixels, source_width, source_height, scaled_width, scaled_height)
+            if method == ScalingMethod.SMART_CROP:
+                x, y = smart_crop_position(scaled, scaled_width, scaled_height, width, height)
+            return place_grayscale(scaled, scaled_width, scaled_height, width, height, x, y)
+        return scale
+    pixel_format = PixelFormat[name.upper()]
+    display = Display(backend=backend, auto_init=False, config=DisplayConfig(panel=panel))
+    frame_width, frame_height = display.get_dimensions()
+    pixels = sample_pixels(frame_width, frame_height, PIXEL_FORMAT_BYTES[pixel_format])
+    return lambda: display.convert_pixels_to_raw(pixels, frame_width, frame_height, pixel_format)
+
+
+def run_benchmarks(cases: Optional[Sequence[str]] = None, panels: Optional[Sequence[PanelType]] = None,
+                   repeat: int = DEFAULT_REPEAT, backend: str = "mock",
+                   clock: Callable[[], float] = time.perf_counter) -> BenchReport:
+    """
+    Time cases on panel sizes.
+
+    Args:
+        cases: Names from CASES; None for all
+        panels: Panel sizes to run each case on; None for every built-in panel
+        repeat: Timed runs of each case, after one untimed warm-up run
+        backend: Display backend of the pack cases: "mock" anywhere,
+                 "native" for the C library on the device
+        clock: Clock in seconds, replaceable for testing
+
+    Returns:
+        The report, in the order of cases and panels
+
+    Raises:
+        BenchError: If a case is unknown, repeat is not positive or a
+                    pack case cannot open the backend
+    """
+    cases = list(CASES if cases is None else cases)
+    unknown = [case for case in cases if case not in CASES]
+    if unknown:
+        raise BenchError(f"Unknown benchmark cases: {', '.join(unknown)} (valid: {', '.join(CASES)})")
+    panels = list(PANELS if panels is None else panels)
+    if any(panel not in PANELS for panel in panels):
+        raise BenchError("Only built-in panels can be benchmarked")
+    if repeat < 1:
+        raise BenchError(f"Invalid repeat count {repeat}")
+
+    report = BenchReport(time.time(), backend, platform.node(), platform.machine(), repeat)
+    for case in cases:
+        for panel in panels:
+            try:
+                action = _case_action(case, panel, backend)
+            except Exception as e:
+                raise BenchError(f"Cannot prepare {case} on {panel.value}: {e}")
+            action()
+            runs_ms = []
+            for _ in range(repeat):
+                start = clock()
+                action()
+                runs_ms.append(round((clock() - start) * 1000, 4))
+            spec = PANELS[panel]
+            report.results.append(CaseResult(case, panel.value, spec.width, spec.height, runs_ms))
+    return report
+
+
+def parse_panels(names: Sequence[str]) -> List[PanelType]:
+    """Panel types from names such as "epd400x300"."""
+    try:
+        return [PanelType(name) for name in names]
+    except ValueError as e:
+        raise BenchError(f"{e} (valid: {', '.join(panel.value for panel in PANELS)})")
+
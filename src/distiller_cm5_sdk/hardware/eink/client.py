@@ -0,0 +1,125 @@
+#!/usr/bin/env python3
+"""
+Client of the e-ink display daemon (distiller-eink-daemon).
+
+Draws through the daemon instead of opening the display, so several
+processes can share the panel and none of them needs root:
+
+    with DisplayClient() as display:
+        width, height = display.get_dimensions()
+        fb = Framebuffer(width, height)
+        fb.draw_text(4, 4, "Hello")
+        display.display_image(fb.to_bytes(), DisplayMode.PARTIAL)
+
+The methods mirror the Display methods of the same name.
+"""
+
+import socket
+import threading
+from typing import Dict, Optional, Tuple
+
+from .daemon import default_socket_path, recv_message, send_message
+from .display import DisplayError, DisplayMode, current_update_tag
+
+
+class DisplayClient:
+    """A connection to the display daemon."""
+
+    def __init__(self, socket_path: Optional[str] = None, timeout: float = 30.0, tag: Optional[str] = None):
+        """
+        Args:
+            socket_path: Daemon socket; None uses DISTILLER_EINK_SOCKET or
+                         /run/distiller-cm5-sdk/eink.sock
+            timeout: Seconds to wait for each reply; a full refresh takes a
+                     few seconds, and longer while other clients are drawing
+            tag: Who the updates are for, e.g. the app's name; a
+                 tag_updates() block overrides it. The daemon records it
+                 with the client's process ID, or the process name without one.
+
+        Raises:
+            DisplayError: If the daemon is not running or not accessible
+        """
+        self.socket_path = socket_path or default_socket_path()
+        self.tag = tag
+        self._lock = threading.Lock()
+        self._info: Optional[Dict] = None
+        self._conn = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        self._conn.settimeout(timeout)
+        try:
+            self._conn.connect(self.socket_path)
+        except OSError as e:
+            self._conn.close()
+            raise DisplayError(f"Display daemon not reachable at {self.socket_path}: {e}")
+
+    def request(self, command: str, body: bytes = b"", **fields) -> Tuple[Dict, bytes]:
+        """
+        Send one command and wait for its reply.
+
+        Returns:
+            (reply header, reply body)
+
+        Raises:
+            DisplayError: If the command failed or the connection broke
+        """
+        tag = current_update_tag() or self.tag
+        if tag is not None:
+            fields["tag"] = tag
+        with self._lock:
+            try:
+                send_message(self._conn, {"cmd": command, **fields}, body)
+                reply, reply_body = recv_message(self._conn)
+            except (OSError, ValueError) as e:
+                raise DisplayError(f"Display daemon connection failed: {e}")
+        if not reply.get("ok"):
+            raise DisplayError(reply.get("error", f"{command} failed"))
+        return reply, reply_body
+
+    def display_image(self, data: bytes, mode: DisplayMode = DisplayMode.FULL) -> None:
+        """Show a packed 1-bit frame of get_info()["data_size"] bytes."""
+        self.request("display", bytes(data), mode=DisplayMode(mode).name.lower())
+
+    def clear(self) -> None:
+        self.request("clear")
+
+    def sleep(self) -> None:
+        self.request("sleep")
+
+    def wake(self) -> None:
+        self.request("wake")
+
+    def get_info(self) -> Dict:
+        """The daemon display's Display.get_info()."""
+        reply, _ = self.request("info")
+        reply.pop("ok")
+        self._info = reply
+        return dict(reply)
+
+    def get_dimensions(self) -> Tuple[int, int]:
+        info = self._info or self.get_info()
+        return info["width"], info["height"]
+
+    def get_framebuffer(self) -> Optional[bytes]:
+        """The frame the panel shows, or None if nothing was displayed yet."""
+        _, frame = self.request("screenshot")
+        return frame or None
+
+    def lock(self) -> None:
+        """Lock the daemon's screen lock; raises DisplayError if no PIN is set."""
+        self.request("lock")
+
+    def unlock(self, pin: str) -> None:
+        """Unlock the daemon's screen lock; raises DisplayError for a wrong PIN."""
+        self.request("unlock", pin=pin)
+
+    def ping(self) -> None:
+        """Check the daemon answers; raises DisplayError if not."""
+        self.request("ping")
+
+    def close(self) -> None:
+        self._conn.close()
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, exc_type, exc_val, exc_tb):
+        self.close()
@@ -0,0 +1,234 @@
+#!/usr/bin/env python3
+"""
+Clock face engine for CM5 SDK.
+
+Draws digital or analog clock faces and keeps them on the panel, updating
+with a partial refresh as each minute starts and with a full refresh on the
+hour to clear ghosting.
+
+Updates are scheduled from the wall clock at every step, so the time spent
+rendering and refreshing never accumulates into drift. The engine also
+measures how long a refresh takes and starts the next one that much before
+the minute boundary, so the new minute appears on time rather than a
+refresh later.
+
+    from distiller_cm5_sdk.hardware.eink import Display, RotationMode
+    from distiller_cm5_sdk.hardware.eink.clock import ClockEngine, ClockFace
+
+    with Display() as display:
+        display.set_rotation(RotationMode.CCW_90)
+        ClockEngine(display, ClockFace.ANALOG).run()
+"""
+
+import datetime
+import math
+import threading
+import time
+from enum import Enum
+from typing import Callable, Optional
+
+from ...i18n.formatting import Formatter
+from .display import DisplayMode
+from .font import FONT_HEIGHT
+from .graphics import Framebuffer, measure_text
+
+
+# Refresh latency assumed until one is measured, and the longest measurement trusted
+DEFAULT_LEAD_S = 0.5
+MAX_LEAD_S = 5.0
+
+
+class ClockFace(Enum):
+    """Clock face styles."""
+    DIGITAL = "digital"
+    ANALOG = "analog"
+
+
+def _text_width(text: str, scale: int) -> int:
+    return measure_text(text, size=scale)[0]
+
+
+def _draw_centered(fb: Framebuffer, y: int, text: str, scale: int) -> None:
+    fb.draw_text((fb.width - _text_width(text, scale)) // 2, y, text, scale=scale)
+
+
+def _largest_scale(text: str, width: int, height: int) -> int:
+    scale = 1
+    while _text_width(text, scale + 1) <= width and FONT_HEIGHT * (scale + 1) <= height:
+        scale += 1
+    return scale
+
+
+def render_digital(now: datetime.datetime, width: int, height: int,
+                   formatter: Optional[Formatter] = None, show_date: bool = True) -> Framebuffer:
+    """
+    Draw a digital clock face: the time as large as fits, the date below it.
+
+    Args:
+        now: Time to show
+        width: Frame width in pixels
+        height: Frame height in pixels
+        formatter: Locale formatting; None uses the device locale
+        show_date: Draw the weekday and date under the time
+    """
+    formatter = formatter or Formatter()
+    fb = Framebuffer(width, height)
+    margin = max(width, height) // 25
+    time_text = formatter.time(now)
+    date_text = f"{formatter.weekday(now)} {formatter.date(now)}" if show_date else ""
+
+    date_scale = _largest_scale(date_text, width - 2 * margin, height // 6) if date_text else 0
+    date_height = FONT_HEIGHT * date_scale
+    gap = date_height // 2
+    time_scale = _largest_scale(time_text, width - 2 * margin, height - 2 * margin - date_height - gap)
+    block = FONT_HEIGHT * time_scale + (gap + date_height if date_text else 0)
+
+    top = (height - block) // 2
+    _draw_centered(fb, top, time_text, time_scale)
+    if date_text:
+        _draw_centered(fb, top + FONT_HEIGHT * time_scale + gap, date_text, date_scale)
+    return fb
+
+
+def render_analog(now: datetime.datetime, width: int, height: int,
+                  formatter: Optional[Formatter] = None, show_date: bool = True) -> Framebuffer:
+    """
+    Draw an analog clock face with hour and minute hands.
+
+    On a landscape frame the date is drawn beside the dial, on a portrait
+    frame below it.
+
+    Args:
+        now: Time to show
+        width: Frame width in pixels
+        height: Frame height in pixels
+        formatter: Locale formatting; None uses the device locale
+        show_date: Draw the weekday and day of the month
+    """
+    formatter = formatter or Formatter()
+    fb = Framebuffer(width, height)
+    landscape = width > height
+    dial = min(height, width * 2 // 3) if landscape else min(width, height * 2 // 3)
+    radius = dial // 2 - 3
+    cx, cy = (dial // 2 + 2, height // 2) if landscape else (width // 2, dial // 2 + 2)
+
+    fb.draw_circle(cx, cy, radius)
+    fb.draw_circle(cx, cy, radius - 1)
+    for hour in range(12):
+        angle = math.radians(hour * 30)
+        inner = radius * (0.8 if hour % 3 == 0 else 0.88)
+        fb.draw_line(cx + round(math.sin(angle) * inner), cy - round(math.cos(angle) * inner),
+                     cx + round(math.sin(angle) * (radius - 2)), cy - round(math.cos(angle) * (radius - 2)))
+
+    minute_angle = math.radians(now.minute * 6)
+    hour_angle = math.radians((now.hour % 12) * 30 + now.minute / 2)
+    for angle, length, thickness in ((hour_angle, radius * 0.5, 2), (minute_angle, radius * 0.78, 1)):
+        tip = (cx + round(math.sin(angle) * length), cy - round(math.cos(angle) * length))
+        for dx in range(-thickness + 1, thickness):
+            for dy in range(-thickness + 1, thickness):
+                fb.draw_line(cx + dx, cy + dy, tip[0] + dx, tip[1] + dy)
+    fb.draw_circle(cx, cy, 2, fill=True)
+
+    if show_date:
+        weekday, day = formatter.weekday(now), str(now.day)
+        if landscape:
+            left = dial + 4
+            area = width - left - 2
+            scale = _largest_scale(max(weekday, day, key=len), area, height // 4)
+            for index, text in enumerate((weekday, day)):
+                x = left + (area - _text_width(text, scale)) // 2
+                fb.draw_text(x, height // 2 - FONT_HEIGHT * scale - 2 + index * (FONT_HEIGHT * scale + 4),
+                             text, scale=scale)
+        else:
+            text = f"{weekday} {day}"
+            scale = _largest_scale(text, width - 4, (height - dial) // 2)
+            _draw_centered(fb, dial + (height - dial - FONT_HEIGHT * scale) // 2, text, scale)
+    return fb
+
+
+_RENDERERS = {ClockFace.DIGITAL: render_digital, ClockFace.ANALOG: render_analog}
+
+
+class ClockEngine:
+    """Keeps a clock face on the display, refreshed at each minute boundary."""
+
+    def __init__(self, display, face: ClockFace = ClockFace.DIGITAL, show_date: bool = True,
+                 locale: Optional[str] = None,
+                 renderer: Optional[Callable[[datetime.datetime, int, int], Framebuffer]] = None,
+                 clock: Callable[[], float] = time.time):
+        """
+        Args:
+            display: Initialized Display
+            face: Built-in face to draw
+            show_date: Draw the date on the built-in faces
+            locale: Locale for time and date formats; None uses the device locale
+            renderer: Custom face, called as renderer(now, width, height) and
+                      returning a Framebuffer; replaces face
+            clock: Wall clock in seconds since the epoch, replaceable for testing
+        """
+        self.display = display
+        self.face = ClockFace(face)
+        self.show_date = show_date
+        self.formatter = Formatter(locale)
+        self.renderer = renderer
+        self.clock = clock
+        self.lead_s = DEFAULT_LEAD_S
+        self._shown: Optional[datetime.datetime] = None
+        self._stop = threading.Event()
+
+    def render(self, now: datetime.datetime) -> Framebuffer:
+        """Draw the face for a time at the display's current dimensions."""
+        width, height = self.display.get_dimensions()
+        if self.renderer is not None:
+            return self.renderer(now, width, height)
+        return _RENDERERS[self.face](now, width, height, self.formatter, self.show_date)
+
+    def update(self, now: Optional[datetime.datetime] = None) -> DisplayMode:
+        """
+        Show the face for a time (default: now, to the minute).
+
+        The first update and every update on the hour is a full refresh, the
+        rest are partial.
+
+        Returns:
+            The refresh mode used
+        """
+        if now is None:
+            now = datetime.datetime.fromtimestamp(self.clock())
+        now = now.replace(second=0, microsecond=0)
+        full = self._shown is None or now.minute == 0 or now - self._shown > datetime.timedelta(hours=1)
+        mode = DisplayMode.FULL if full else DisplayMode.PARTIAL
+        self.display.display_image(self.render(now).to_bytes(), mode)
+        self._shown = now
+        return mode
+
+    def next_boundary(self, timestamp: float) -> float:
+        """Epoch time of the first minute boundary at least lead_s after timestamp."""
+        return (math.floor((timestamp + self.lead_s) / 60) + 1) * 60
+
+    def run(self, stop: Optional[threading.Event] = None) -> None:
+        """
+        Show the clock until stop() is called or the stop event is set.
+
+        Args:
+            stop: Optional event that ends the loop when set
+        """
+        stop = stop or self._stop
+        self.update()
+        while not stop.is_set():
+            boundary = self.next_boundary(self.clock())
+            # Recomputed from the wall clock each time, so delays never accumulate
+            if stop.wait(max(boundary - self.lead_s - self.clock(), 0)):
+                break
+            started = self.clock()
+            # After a suspend the boundary may be long past; show the current time instead
+            shown = boundary if started < boundary + 60 else started
+            self.update(datetime.datetime.fromtimestamp(shown))
+            finished = self.clock()
+            if finished - started < MAX_LEAD_S:
+                # Smoothed, so one slow refresh does not move the schedule much
+                self.lead_s = 0.7 * self.lead_s + 0.3 * (finished - started)
+
+    def stop(self) -> None:
+        """End run() from another thread."""
+        self._stop.set()
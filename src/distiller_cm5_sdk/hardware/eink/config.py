@@ -0,0 +1,944 @@
+#!/usr/bin/env python3
+"""
+Persistent display configuration for CM5 SDK.
+
+Settings are read from a structured settings file (eink.toml), grouped in
+tables, and may be overridden per process with environment variables:
+
+    # /opt/distiller-cm5-sdk/eink.toml
+    [display]
+    rotation = "ccw90"
+    panel = "epd296x128"            # or "custom:/etc/distiller/panel.toml"
+    backend = "python"              # panels other than epd128x250 are python-backend only
+    autodetect = true
+    panel_ids = "a1b2:epd296x128"
+    usage_file = "/var/lib/distiller-cm5-sdk/panel-usage.json"
+    fallback_backend = "mock"       # run headless if the panel cannot be set up
+
+    [spi]
+    bus = 1
+    speed_hz = 20000000
+    busy_pin = 24
+
+    [refresh]
+    full_refresh_every = 10
+
+    [processing]                    # processing profile, see calibration
+    threshold = 136
+    contrast = 1.2
+    dither = "ordered"
+    filter = "catmull-rom"          # scaling filter, with sharpen for small text
+    sharpen = 0.5
+    cache_dir = "/var/cache/distiller-eink"     # processed images, see imagecache
+
+    DISTILLER_EINK_ROTATION=180 python3 app.py
+
+Boards with a second panel describe it in [displays.<name>] tables. Its
+settings start from the main display's and are not overridden by the
+environment; give it its own chip select and GPIO lines:
+
+    [displays.status.display]
+    panel = "epd296x128"
+    backend = "python"
+
+    [displays.status.spi]
+    cs = 1
+    dc_pin = 22
+    reset_pin = 23
+    busy_pin = 24
+
+save_config() writes a DisplayConfig back, so settings changed at runtime
+survive a reboot. Devices set up before the structured file keep their
+key=value eink.conf, read as long as there is no eink.toml; its keys are
+the eink.toml ones, prefixed with the table name for spi (spi_bus) and
+thermal (thermal_soc_max), and its [sections] are additional displays.
+migrate_config() converts it.
+
+The file and environment are shared by the whole process. A part of it that
+needs other settings, e.g. one thread of a multi-threaded app, holds them in
+a DisplayConfigHandle instead of changing either:
+
+    handle = DisplayConfigHandle(load_config()).with_changes(rotation=RotationMode.CW_90)
+    display = Display(config=handle)
+    with handle.scope():
+        show_page()         # Displays and process_image() here default to the handle
+"""
+
+import contextvars
+import json
+import os
+import re
+import tempfile
+from contextlib import contextmanager
+from dataclasses import dataclass, field, replace
+from typing import Any, Dict, Iterator, List, Mapping, Optional, Tuple
+
+from .display import DisplayError, RotationMode
+from .imagecache import DEFAULT_MAX_MB as DEFAULT_CACHE_MAX_MB
+from .imaging import DitherMode, ImageOptions, ScalingFilter
+from .thermal import DEFAULT_AMBIENT_MAX_C, DEFAULT_AMBIENT_MIN_C, DEFAULT_INTERVAL_S, DEFAULT_SOC_MAX_C
+from .panels import (
+    CUSTOM_PREFIX, DEFAULT_PANEL, PanelSpec, PanelType, get_panel, load_panel_descriptor, parse_panel,
+    parse_panel_ids,
+)
+
+
+CONFIG_PATH = "/opt/distiller-cm5-sdk/eink.conf"             # key=value format, read without eink.toml
+STRUCTURED_CONFIG_PATH = "/opt/distiller-cm5-sdk/eink.toml"
+CONFIG_PATH_ENV_VAR = "DISTILLER_EINK_CONFIG"
+ROTATION_ENV_VAR = "DISTILLER_EINK_ROTATION"
+PANEL_ENV_VAR = "DISTILLER_EINK_PANEL"
+AUTODETECT_ENV_VAR = "DISTILLER_EINK_AUTODETECT"
+USAGE_FILE_ENV_VAR = "DISTILLER_EINK_USAGE_FILE"
+REGISTER_ACCESS_ENV_VAR = "DISTILLER_EINK_REGISTER_ACCESS"
+FALLBACK_BACKEND_ENV_VAR = "DISTILLER_EINK_FALLBACK_BACKEND"
+BUSY_TIMEOUT_ENV_VAR = "DISTILLER_EINK_BUSY_TIMEOUT_MS"
+BUSY_RETRIES_ENV_VAR = "DISTILLER_EINK_BUSY_RETRIES"
+FULL_REFRESH_EVERY_ENV_VAR = "DISTILLER_EINK_FULL_REFRESH_EVERY"
+FULL_REFRESH_INTERVAL_ENV_VAR = "DISTILLER_EINK_FULL_REFRESH_INTERVAL"
+AUTO_PARTIAL_ENV_VAR = "DISTILLER_EINK_AUTO_PARTIAL_MAX_PIXELS"
+THRESHOLD_ENV_VAR = "DISTILLER_EINK_THRESHOLD"
+CONTRAST_ENV_VAR = "DISTILLER_EINK_CONTRAST"
+DITHER_ENV_VAR = "DISTILLER_EINK_DITHER"
+PARALLEL_DITHER_ENV_VAR = "DISTILLER_EINK_PARALLEL_DITHER"
+SCALING_FILTER_ENV_VAR = "DISTILLER_EINK_SCALING_FILTER"
+SHARPEN_ENV_VAR = "DISTILLER_EINK_SHARPEN"
+CACHE_DIR_ENV_VAR = "DISTILLER_EINK_CACHE_DIR"
+CACHE_MAX_MB_ENV_VAR = "DISTILLER_EINK_CACHE_MAX_MB"
+
+# Range of the contrast setting
+MIN_CONTRAST = 0.25
+MAX_CONTRAST = 4.0
+
+# Range of the sharpen setting
+MAX_SHARPEN = 2.0
+
+# Name of the display configured outside any [section]
+MAIN_DISPLAY = "main"
+
+# Busy-wait policy defaults (BUSY_TIMEOUT_MS and BUSY_RETRIES in distiller_display_sdk.h)
+BUSY_TIMEOUT_MS = 10000
+BUSY_RETRIES = 1
+
+# Keys of the SPI/GPIO wiring; each is overridden by DISTILLER_EINK_<KEY>
+_SPI_KEYS = ("spi_bus", "spi_cs", "spi_speed_hz", "spi_block_size", "spi_chunk_delay_us",
+             "dc_pin", "reset_pin", "busy_pin", "gpio_chip")
+
+# Keys of the thermal throttling limits, with their DisplayConfig
+# fields; each is overridden by DISTILLER_EINK_<KEY>
+_THERMAL_KEYS = (("thermal_soc_max", "thermal_soc_max_c"), ("thermal_ambient_max", "thermal_ambient_max_c"),
+                 ("thermal_ambient_min", "thermal_ambient_min_c"), ("thermal_interval", "thermal_interval_s"))
+
+# Tables of eink.toml: for each setting, its name in the table, its key in
+# the key=value format and its type
+_TABLES = {
+    "display": (("backend", "backend", str), ("rotation", "rotation", str), ("panel", "panel", str),
+                ("autodetect", "autodetect", bool), ("panel_ids", "panel_ids", str),
+                ("usage_file", "usage_file", str), ("register_access", "register_access", bool),
+                ("fallback_backend", "fallback_backend", str)),
+    "spi": (("bus", "spi_bus", int), ("cs", "spi_cs", int), ("speed_hz", "spi_speed_hz", int),
+            ("block_size", "spi_block_size", int), ("chunk_delay_us", "spi_chunk_delay_us", int),
+            ("dc_pin", "dc_pin", int), ("reset_pin", "reset_pin", int), ("busy_pin", "busy_pin", int),
+            ("gpio_chip", "gpio_chip", str)),
+    "refresh": (("full_refresh_every", "full_refresh_every", int),
+                ("full_refresh_interval", "full_refresh_interval", float),
+                ("auto_partial_max_pixels", "auto_partial_max_pixels", int),
+                ("busy_timeout_ms", "busy_timeout_ms", int), ("busy_retries", "busy_retries", int)),
+    "processing": (("threshold", "threshold", int), ("contrast", "contrast", float), ("dither", "dither", str),
+                   ("parallel", "parallel", bool), ("filter", "scaling_filter", str), ("sharpen", "sharpen", float),
+                   ("cache_dir", "cache_dir", str), ("cache_max_mb", "cache_max_mb", float)),
+    "thermal": (("soc_max", "thermal_soc_max", float), ("ambient_max", "thermal_ambient_max", float),
+                ("ambient_min", "thermal_ambient_min", float), ("interval", "thermal_interval", float)),
+}
+_SETTINGS = {key: (table, name, kind) for table, settings in _TABLES.items() for name, key, kind in settings}
+
+_TRUE = ("1", "yes", "true", "on")
+_FALSE = ("0", "no", "false", "off")
+
+# How config_values() writes each rotation
+_ROTATION_SETTINGS = {
+    RotationMode.NONE: "none",
+    RotationMode.CCW_90: "ccw90",
+    RotationMode.ROTATE_180: "180",
+    RotationMode.CW_90: "cw90",
+}
+
+# Accepted spellings for the rotation setting
+_ROTATION_NAMES = {
+    "none": RotationMode.NONE,
+    "0": RotationMode.NONE,
+    "ccw90": RotationMode.CCW_90,
+    "ccw_90": RotationMode.CCW_90,
+    "180": RotationMode.ROTATE_180,
+    "rotate_180": RotationMode.ROTATE_180,
+    "cw90": RotationMode.CW_90,
+    "cw_90": RotationMode.CW_90,
+}
+
+
+@dataclass(frozen=True)
+class SpiConfig:
+    """
+    SPI device and GPIO lines the panel is wired to (display_spi_config_t).
+
+    The defaults match the Distiller carrier board: /dev/spidev0.0 at 40 MHz,
+    DC on GPIO 7, reset on GPIO 13 and busy on GPIO 9 of gpiochip0.
+
+    Frame data is sent in transfers of at most block_size bytes, since spidev
+    rejects single transfers larger than its bufsiz module parameter (4096 by
+    default) with "Message too long".
+    """
+    bus: int = 0
+    cs: int = 0
+    speed_hz: int = 40000000
+    dc_pin: int = 7
+    reset_pin: int = 13
+    busy_pin: int = 9
+    gpio_chip: str = "gpiochip0"
+    block_size: int = 4096
+    chunk_delay_us: int = 0
+
+    def validate(self) -> None:
+        """
+        Raises:
+            DisplayError: If a value is out of range or two roles share a GPIO line
+        """
+        for name in ("bus", "cs", "dc_pin", "reset_pin", "busy_pin", "chunk_delay_us"):
+            if getattr(self, name) < 0:
+                raise DisplayError(f"Invalid SPI configuration: {name} must not be negative")
+        if self.speed_hz <= 0 or self.block_size <= 0:
+            raise DisplayError("Invalid SPI configuration: speed_hz and block_size must be positive")
+        if len({self.dc_pin, self.reset_pin, self.busy_pin}) != 3:
+            raise DisplayError("Invalid SPI configuration: dc_pin, reset_pin and busy_pin must differ")
+        if not self.gpio_chip or len(self.gpio_chip.encode()) >= 32:
+            raise DisplayError("Invalid SPI configuration: gpio_chip must be 1-31 bytes")
+
+    @property
+    def device_path(self) -> str:
+        return f"/dev/spidev{self.bus}.{self.cs}"
+
+
+@dataclass
+class DisplayConfig:
+    """Display settings applied whenever a Display is opened."""
+    name: str = MAIN_DISPLAY                  # additional display's name; MAIN_DISPLAY for the main one
+    backend: Optional[str] = None             # backend unless Display(backend=...) names one; None for the default
+    fallback_backend: Optional[str] = None    # "mock" or "simulator" when the panel cannot be set up; None raises
+    rotation: RotationMode = RotationMode.NONE
+    panel: PanelType = DEFAULT_PANEL          # used when autodetection finds nothing
+    autodetect: bool = True                   # identify the panel from its OTP User ID
+    panel_ids: Dict[bytes, PanelType] = field(default_factory=dict)
+    custom_panel: Optional[PanelSpec] = None  # definition used when panel is PanelType.CUSTOM
+    spi: SpiConfig = field(default_factory=SpiConfig)
+    usage_file: Optional[str] = None          # panel aging counters; None disables them
+    register_access: bool = False             # allow Display.unsafe_registers()
+    busy_timeout_ms: int = BUSY_TIMEOUT_MS    # longest wait for one busy period
+    busy_retries: int = BUSY_RETRIES          # resets and retries after an update times out
+    full_refresh_every: int = 0               # partials before a forced full refresh; 0 disables
+    full_refresh_interval_s: float = 0.0      # seconds before a forced full refresh; 0 disables
+    auto_partial_max_pixels: Optional[int] = None  # display_image_auto() threshold; None for 1/8 of the panel
+    thermal_soc_max_c: float = DEFAULT_SOC_MAX_C         # throttle refreshes above this SoC temperature
+    thermal_ambient_max_c: float = DEFAULT_AMBIENT_MAX_C  # and outside this ambient range
+    thermal_ambient_min_c: float = DEFAULT_AMBIENT_MIN_C
+    thermal_interval_s: float = DEFAULT_INTERVAL_S       # seconds between throttled refreshes; 0 disables
+    image_options: ImageOptions = field(default_factory=ImageOptions)  # processing profile (threshold, contrast)
+    image_cache_dir: Optional[str] = None     # processed images kept for process_image(); None disables it
+    image_cache_max_mb: float = DEFAULT_CACHE_MAX_MB
+
+    def panel_spec(self) -> PanelSpec:
+        """
+        Spec of the configured panel.
+
+        Raises:
+            DisplayError: If panel is PanelType.CUSTOM but no custom_panel is set
+        """
+        if self.panel == PanelType.CUSTOM:
+            if self.custom_panel is None:
+                raise DisplayError("Custom panel selected without a panel definition")
+            return self.custom_panel
+        return get_panel(self.panel)
+
+
+_active_handle: contextvars.ContextVar[Optional["DisplayConfigHandle"]] = contextvars.ContextVar(
+    "distiller_eink_config", default=None)
+
+
+class DisplayConfigHandle:
+    """
+    Display settings for one part of a process, used in place of the config
+    file and environment without changing them for the rest of the process.
+
+    A handle keeps its own copy of the settings, so changes to the
+    DisplayConfig it was made from, or to the one it returns, do not reach
+    it. Pass it to Display(config=...) or imaging.default_image_options(),
+    or make it the default inside a block with scope().
+    """
+
+    def __init__(self, config: Optional[DisplayConfig] = None):
+        """
+        Args:
+            config: Settings to hold; if None, the main display's from load_config()
+
+        Raises:
+            DisplayError: If config is None and the config file is invalid
+        """
+        self._config = replace(config if config is not None else load_config())
+
+    @property
+    def config(self) -> DisplayConfig:
+        """A copy of the settings."""
+        return replace(self._config)
+
+    @property
+    def image_options(self) -> ImageOptions:
+        """The processing profile of the settings."""
+        return self._config.image_options
+
+    def with_changes(self, **changes: Any) -> "DisplayConfigHandle":
+        """
+        A new handle with some DisplayConfig fields replaced, e.g.
+        with_changes(rotation=RotationMode.CW_90); this one is unchanged.
+        """
+        return DisplayConfigHandle(replace(self._config, **changes))
+
+    @contextmanager
+    def scope(self) -> Iterator["DisplayConfigHandle"]:
+        """
+        Make the handle the default for the block: Display() without a config
+        and process_image() without options use it instead of the config file
+        and environment. The scope follows contextvars, so it covers the
+        calling thread and asyncio tasks started inside the block, not other
+        threads. Scopes nest.
+        """
+        token = _active_handle.set(self)
+        try:
+            yield self
+        finally:
+            _active_handle.reset(token)
+
+
+def current_config_handle() -> Optional[DisplayConfigHandle]:
+    """The handle of the innermost active DisplayConfigHandle.scope(), or None outside one."""
+    return _active_handle.get()
+
+
+def parse_rotation(value: str) -> RotationMode:
+    """
+    Parse a rotation setting.
+
+    Args:
+        value: One of none, ccw90, 180 or cw90 (case-insensitive); RotationMode
+               names such as CCW_90 are also accepted
+
+    Returns:
+        The matching RotationMode
+
+    Raises:
+        DisplayError: If the value is not recognised
+    """
+    key = value.strip().lower()
+    if key not in _ROTATION_NAMES:
+        raise DisplayError(f"Invalid rotation '{value}'; expected one of none, ccw90, 180, cw90")
+    return _ROTATION_NAMES[key]
+
+
+def _parse_spi(config: SpiConfig, values: Dict[str, str], env: Mapping[str, str]) -> SpiConfig:
+    # Later sources win: defaults, custom panel pins, the config file, then the environment
+    settings = {}
+    for key in _SPI_KEYS:
+        value = env.get(f"DISTILLER_EINK_{key.upper()}", values.get(key))
+        if not value:
+            continue
+        field_name = key[len("spi_"):] if key.startswith("spi_") else key
+        if key == "gpio_chip":
+            settings[field_name] = value.strip()
+            continue
+        try:
+            settings[field_name] = int(value.strip(), 0)
+        except ValueError:
+            raise DisplayError(f"Invalid {key} '{value}'; expected an integer")
+    config = replace(config, **settings)
+    config.validate()
+    return config
+
+
+def _is_structured(path: str) -> bool:
+    return path.lower().endswith(".toml")
+
+
+def _load_toml(path: str) -> Dict[str, Any]:
+    # A missing file is empty; other read errors raise OSError
+    try:
+        with open(path, "rb") as f:
+            content = f.read()
+    except FileNotFoundError:
+        return {}
+    try:
+        import tomllib
+    except ImportError:  # Python < 3.11
+        try:
+            import tomli as tomllib
+        except ImportError:
+            raise DisplayError(f"{path}: reading TOML needs Python 3.11+ or the tomli module")
+    try:
+        return tomllib.loads(content.decode("utf-8"))
+    except (ValueError, UnicodeDecodeError) as e:
+        raise DisplayError(f"{path}: invalid TOML: {e}")
+
+
+def _flatten(tables: Dict[str, Any], where: str) -> Dict[str, str]:
+    # eink.toml tables as key=value settings, so both formats are validated alike
+    values: Dict[str, str] = {}
+    for table, settings in tables.items():
+        if table not in _TABLES or not isinstance(settings, dict):
+            raise DisplayError(f"{where}: unknown table [{table}]; expected one of {', '.join(_TABLES)}")
+        keys = {name: key for name, key, _ in _TABLES[table]}
+        for name, value in settings.items():
+            if name not in keys:
+                raise DisplayError(f"{where}: unknown setting '{name}' in [{table}]")
+            if isinstance(value, bool):
+                value = "yes" if value else "no"
+            elif not isinstance(value, (str, int, float)):
+                raise DisplayError(f"{where}: [{table}] {name} must be a string, number or boolean")
+            values[keys[name]] = str(value)
+    return values
+
+
+def _read_structured(path: str) -> Tuple[Dict[str, str], Dict[str, Dict[str, str]]]:
+    try:
+        data = _load_toml(path)
+    except OSError:
+        return {}, {}
+    displays = data.pop("displays", {})
+    if not isinstance(displays, dict):
+        raise DisplayError(f"{path}: displays must be a table of displays")
+    sections: Dict[str, Dict[str, str]] = {}
+    for name, tables in displays.items():
+        key = name.lower()
+        if key == MAIN_DISPLAY or key in sections or not isinstance(tables, dict):
+            raise DisplayError(f"{path}: invalid or repeated display [displays.{name}]")
+        sections[key] = _flatten(tables, f"{path} [displays.{name}]")
+    return _flatten(data, path), sections
+
+
+def _read_sections(path: str) -> Tuple[Dict[str, str], Dict[str, Dict[str, str]]]:
+    if _is_structured(path):
+        return _read_structured(path)
+    values: Dict[str, str] = {}
+    sections: Dict[str, Dict[str, str]] = {}
+    try:
+        with open(path, "r") as f:
+            lines = f.readlines()
+    except OSError:
+        return values, sections
+
+    current = values
+    for lineno, line in enumerate(lines, 1):
+        line = line.strip()
+        if not line or line.startswith("#"):
+            continue
+        if line.startswith("[") and line.endswith("]"):
+            name = line[1:-1].strip().lower()
+            if not name or name == MAIN_DISPLAY or name in sections:
+                raise DisplayError(f"{path}:{lineno}: invalid or repeated display section [{name}]")
+            current = sections[name] = {}
+            continue
+        if "=" not in line:
+            raise DisplayError(f"{path}:{lineno}: expected key = value")
+        key, value = line.split("=", 1)
+        current[key.strip().lower()] = value.strip()
+
+    return values, sections
+
+
+def read_config_file(path: str) -> Dict[str, str]:
+    """
+    Read key=value pairs from a config file.
+
+    Blank lines and lines starting with '#' are ignored, as are the
+    [sections] of additional displays. A missing or unreadable file yields an
+    empty dictionary. A *.toml display config is read as the equivalent
+    key=value settings of its main display.
+
+    Raises:
+        DisplayError: If a line is not a key=value pair
+    """
+    return _read_sections(path)[0]
+
+
+def _parse_image_options(values: Dict[str, str], env: Mapping[str, str]) -> ImageOptions:
+    options = ImageOptions()
+    threshold = env.get(THRESHOLD_ENV_VAR, values.get("threshold"))
+    if threshold:
+        try:
+            number = int(threshold.strip())
+        except ValueError:
+            number = -1
+        if not 0 <= number <= 255:
+            raise DisplayError(f"Invalid threshold '{threshold}'; expected a gray level 0-255")
+        options = replace(options, threshold=number)
+    contrast = env.get(CONTRAST_ENV_VAR, values.get("contrast"))
+    if contrast:
+        try:
+            factor = float(contrast.strip())
+        except ValueError:
+            factor = -1.0
+        if not MIN_CONTRAST <= factor <= MAX_CONTRAST:
+            raise DisplayError(f"Invalid contrast '{contrast}'; expected {MIN_CONTRAST:g}-{MAX_CONTRAST:g}")
+        options = replace(options, contrast=factor)
+    dither = env.get(DITHER_ENV_VAR, values.get("dither"))
+    if dither:
+        try:
+            options = replace(options, dither=DitherMode(dither.strip().lower()))
+        except ValueError:
+            raise DisplayError(f"Invalid dither '{dither}'; expected one of "
+                               f"{', '.join(mode.value for mode in DitherMode)}")
+    parallel = env.get(PARALLEL_DITHER_ENV_VAR, values.get("parallel"))
+    if parallel:
+        key = parallel.strip().lower()
+        if key not in _TRUE + _FALSE:
+            raise DisplayError(f"Invalid parallel '{parallel}'; expected yes or no")
+        options = replace(options, parallel=key in _TRUE)
+    scaling_filter = env.get(SCALING_FILTER_ENV_VAR, values.get("scaling_filter"))
+    if scaling_filter:
+        try:
+            options = replace(options, scaling_filter=ScalingFilter(scaling_filter.strip().lower()))
+        except ValueError:
+            raise DisplayError(f"Invalid filter '{scaling_filter}'; expected one of "
+                               f"{', '.join(method.value for method in ScalingFilter)}")
+    sharpen = env.get(SHARPEN_ENV_VAR, values.get("sharpen"))
+    if sharpen:
+        try:
+            amount = float(sharpen.strip())
+        except ValueError:
+            amount = -1.0
+        if not 0 <= amount <= MAX_SHARPEN:
+            raise DisplayError(f"Invalid sharpen '{sharpen}'; expected 0-{MAX_SHARPEN:g}")
+        options = replace(options, sharpen=amount)
+    return options
+
+
+def _parse_cache_settings(values: Dict[str, str], env: Mapping[str, str]) -> Tuple[Optional[str], float]:
+    directory = (env.get(CACHE_DIR_ENV_VAR, values.get("cache_dir")) or "").strip()
+    if directory.lower() in ("", "none") + _FALSE:
+        directory = None
+    max_mb = env.get(CACHE_MAX_MB_ENV_VAR, values.get("cache_max_mb"))
+    if not max_mb:
+        return directory, DEFAULT_CACHE_MAX_MB
+    try:
+        number = float(max_mb.strip())
+    except ValueError:
+        number = -1.0
+    if number <= 0:
+        raise DisplayError(f"Invalid cache_max_mb '{max_mb}'; expected megabytes")
+    return directory, number
+
+
+def _config_path(path: Optional[str]) -> str:
+    if path is not None:
+        return path
+    if CONFIG_PATH_ENV_VAR in os.environ:
+        return os.environ[CONFIG_PATH_ENV_VAR]
+    # Until migrated, devices keep the key=value file they were set up with
+    if os.path.exists(CONFIG_PATH) and not os.path.exists(STRUCTURED_CONFIG_PATH):
+        return CONFIG_PATH
+    return STRUCTURED_CONFIG_PATH
+
+
+def list_displays(path: Optional[str] = None) -> List[str]:
+    """
+    Names of the configured displays, MAIN_DISPLAY first, then each [section]
+    of the config file in order.
+
+    Args:
+        path: Config file path, as for load_config()
+
+    Raises:
+        DisplayError: If the file is malformed
+    """
+    return [MAIN_DISPLAY] + list(_read_sections(_config_path(path))[1])
+
+
+def load_config(path: Optional[str] = None, display: str = MAIN_DISPLAY) -> DisplayConfig:
+    """
+    Load display settings from the config file and environment.
+
+    Args:
+        path: Config file path; *.toml files are structured, others key=value.
+              If None, uses DISTILLER_EINK_CONFIG, defaulting to
+              /opt/distiller-cm5-sdk/eink.toml, or eink.conf on devices that
+              still have only that.
+        display: Display to load, one of list_displays()
+
+    Returns:
+        DisplayConfig with environment overrides applied (main display only)
+
+    Raises:
+        DisplayError: If a setting has an invalid value or the display is not configured
+    """
+    from .backends import BACKEND_ENV_VAR, FALLBACK_BACKENDS
+
+    path = _config_path(path)
+    values, sections = _read_sections(path)
+    env: Mapping[str, str] = os.environ
+    if display != MAIN_DISPLAY:
+        if display not in sections:
+            raise DisplayError(f"No display '{display}' in {path}; configured: {', '.join(list_displays(path))}")
+        values = {**values, **sections[display]}
+        env = {}
+    config = DisplayConfig(name=display)
+
+    backend = env.get(BACKEND_ENV_VAR, values.get("backend"))
+    if backend:
+        config.backend = backend.strip().lower()
+
+    rotation = env.get(ROTATION_ENV_VAR, values.get("rotation"))
+    if rotation:
+        config.rotation = parse_rotation(rotation)
+
+    panel = env.get(PANEL_ENV_VAR, values.get("panel"))
+    if panel and panel.strip().lower().startswith(CUSTOM_PREFIX):
+        config.panel = PanelType.CUSTOM
+        config.custom_panel = load_panel_descriptor(panel.strip()[len(CUSTOM_PREFIX):].strip())
+    elif panel:
+        config.panel = parse_panel(panel)
+
+    autodetect = env.get(AUTODETECT_ENV_VAR, values.get("autodetect"))
+    if autodetect:
+        key = autodetect.strip().lower()
+        if key not in _TRUE + _FALSE:
+            raise DisplayError(f"Invalid autodetect '{autodetect}'; expected yes or no")
+        config.autodetect = key in _TRUE
+
+    register_access = env.get(REGISTER_ACCESS_ENV_VAR, values.get("register_access"))
+    if register_access:
+        key = register_access.strip().lower()
+        if key not in _TRUE + _FALSE:
+            raise DisplayError(f"Invalid register_access '{register_access}'; expected yes or no")
+        config.register_access = key in _TRUE
+
+    for attr, env_var in (("busy_timeout_ms", BUSY_TIMEOUT_ENV_VAR), ("busy_retries", BUSY_RETRIES_ENV_VAR)):
+        value = env.get(env_var, values.get(attr))
+        if value:
+            try:
+                number = int(value.strip())
+            except ValueError:
+                number = -1
+            if number < (1 if attr == "busy_timeout_ms" else 0):
+                raise DisplayError(f"Invalid {attr} '{value}'")
+            setattr(config, attr, number)
+
+    every = env.get(FULL_REFRESH_EVERY_ENV_VAR, values.get("full_refresh_every"))
+    if every:
+        try:
+            config.full_refresh_every = int(every.strip())
+        except ValueError:
+            config.full_refresh_every = -1
+        if config.full_refresh_every < 0:
+            raise DisplayError(f"Invalid full_refresh_every '{every}'; expected a count, 0 to disable")
+
+    interval = env.get(FULL_REFRESH_INTERVAL_ENV_VAR, values.get("full_refresh_interval"))
+    if interval:
+        try:
+            config.full_refresh_interval_s = float(interval.strip())
+        except ValueError:
+            config.full_refresh_interval_s = -1.0
+        if config.full_refresh_interval_s < 0:
+            raise DisplayError(f"Invalid full_refresh_interval '{interval}'; expected seconds, 0 to disable")
+
+    auto_partial = env.get(AUTO_PARTIAL_ENV_VAR, values.get("auto_partial_max_pixels"))
+    if auto_partial:
+        try:
+            config.auto_partial_max_pixels = int(auto_partial.strip())
+        except ValueError:
+            config.auto_partial_max_pixels = -1
+        if config.auto_partial_max_pixels < 0:
+            raise DisplayError(f"Invalid auto_partial_max_pixels '{auto_partial}'; expected a pixel count")
+
+    for key, attr in _THERMAL_KEYS:
+        value = env.get(f"DISTILLER_EINK_{key.upper()}", values.get(key))
+        if value:
+            try:
+                setattr(config, attr, float(value.strip()))
+            except ValueError:
+                unit = "seconds" if key == "thermal_interval" else "degrees Celsius"
+                raise DisplayError(f"Invalid {key} '{value}'; expected {unit}")
+    if config.thermal_interval_s < 0:
+        raise DisplayError(f"Invalid thermal_interval '{config.thermal_interval_s:g}'; expected seconds, 0 to disable")
+    if config.thermal_ambient_min_c >= config.thermal_ambient_max_c:
+        raise DisplayError("Invalid thermal limits: thermal_ambient_min must be below thermal_ambient_max")
+
+    if values.get("panel_ids"):
+        config.panel_ids = parse_panel_ids(values["panel_ids"])
+
+    if config.custom_panel is not None:
+        pins = {"busy_pin": config.custom_panel.busy_pin, "reset_pin": config.custom_panel.reset_pin}
+        config.spi = replace(config.spi, **{name: pin for name, pin in pins.items() if pin is not None})
+    config.spi = _parse_spi(config.spi, values, env)
+
+    usage_file = env.get(USAGE_FILE_ENV_VAR, values.get("usage_file"))
+    if usage_file and usage_file.strip().lower() not in ("none",) + _FALSE:
+        config.usage_file = usage_file.strip()
+
+    fallback = env.get(FALLBACK_BACKEND_ENV_VAR, values.get("fallback_backend"))
+    if fallback and fallback.strip().lower() not in ("none",) + _FALSE:
+        fallback = fallback.strip().lower()
+        if fallback not in FALLBACK_BACKENDS:
+            raise DisplayError(f"Invalid fallback_backend '{fallback}'; expected {' or '.join(FALLBACK_BACKENDS)}")
+        config.fallback_backend = fallback
+
+    config.image_options = _parse_image_options(values, env)
+    config.image_cache_dir, config.image_cache_max_mb = _parse_cache_settings(values, env)
+    return config
+
+
+def load_image_profile(path: Optional[str] = None) -> ImageOptions:
+    """
+    Read just the main display's processing profile, threshold, contrast,
+    dither and scaling, without the rest of load_config().
+
+    Raises:
+        DisplayError: If a setting of the profile is invalid
+    """
+    return _parse_image_options(read_config_file(_config_path(path)), os.environ)
+
+
+def load_cache_settings(path: Optional[str] = None) -> Tuple[Optional[str], float]:
+    """
+    Read just the processed-image cache settings of the main display.
+
+    Returns:
+        The cache directory, or None if caching is off, and its cap in megabytes
+
+    Raises:
+        DisplayError: If cache_max_mb is invalid
+    """
+    return _parse_cache_settings(read_config_file(_config_path(path)), os.environ)
+
+
+def _write_file(path: str, text: str) -> None:
+    # Replace the file atomically, so a crash never leaves a truncated config
+    directory = os.path.dirname(path) or "."
+    try:
+        os.makedirs(directory, exist_ok=True)
+        fd, tmp_path = tempfile.mkstemp(dir=directory, prefix=".eink.")
+        with os.fdopen(fd, "w") as f:
+            f.write(text)
+        os.chmod(tmp_path, 0o644)
+        os.replace(tmp_path, path)
+    except OSError as e:
+        raise DisplayError(f"Failed to write {path}: {e}")
+
+
+def _toml_value(value: Any) -> str:
+    if isinstance(value, bool):
+        return "true" if value else "false"
+    if isinstance(value, (int, float)):
+        return repr(value)
+    if isinstance(value, list):
+        return "[" + ", ".join(_toml_value(item) for item in value) + "]"
+    return json.dumps(str(value), ensure_ascii=False)     # JSON string escapes are valid TOML
+
+
+def _toml_key(key: str) -> str:
+    return key if re.fullmatch(r"[A-Za-z0-9_-]+", key) else json.dumps(key, ensure_ascii=False)
+
+
+def _format_toml(data: Dict[str, Any], prefix: Tuple[str, ...] = ()) -> List[str]:
+    lines: List[str] = []
+    tables = [(key, value) for key, value in data.items() if isinstance(value, dict)]
+    scalars = [(key, value) for key, value in data.items() if not isinstance(value, dict)]
+    if prefix and (scalars or not tables):
+        lines.append("[" + ".".join(_toml_key(key) for key in prefix) + "]")
+    lines.extend(f"{_toml_key(key)} = {_toml_value(value)}" for key, value in scalars)
+    for key, value in tables:
+        if lines:
+            lines.append("")
+        lines.extend(_format_toml(value, prefix + (key,)))
+    return lines
+
+
+def _typed(key: str, value: str, kind: type) -> Any:
+    text = str(value).strip()
+    if kind is bool:
+        if text.lower() not in _TRUE + _FALSE:
+            raise DisplayError(f"Invalid {key} '{value}'; expected yes or no")
+        return text.lower() in _TRUE
+    try:
+        return int(text, 0) if kind is int else kind(text)
+    except ValueError:
+        raise DisplayError(f"Invalid {key} '{value}'; expected a{'n integer' if kind is int else ' number'}")
+
+
+def _save_structured(settings: Mapping[str, str], path: str, display: str) -> None:
+    try:
+        data = _load_toml(path)
+    except OSError as e:
+        raise DisplayError(f"Failed to read {path}: {e}")
+    target = data
+    if display != MAIN_DISPLAY:
+        target = data.get("displays", {}).get(display)
+        if not isinstance(target, dict):
+            raise DisplayError(f"No display '{display}' in {path}; configured: {', '.join(list_displays(path))}")
+    for key, value in settings.items():
+        key = key.lower()
+        if key not in _SETTINGS:
+            raise DisplayError(f"Unknown setting '{key}'")
+        table, name, kind = _SETTINGS[key]
+        tables = target.setdefault(table, {})
+        tables[name] = value if kind is str else _typed(key, value, kind)
+    if "displays" in data:
+        data["displays"] = data.pop("displays")     # additional displays after the main one's tables
+    _write_file(path, "\n".join(_format_toml(data)) + "\n")
+
+
+def save_config_values(settings: Mapping[str, str], path: Optional[str] = None,
+                       display: str = MAIN_DISPLAY) -> None:
+    """
+    Set keys in the config file, keeping its other lines and comments.
+
+    A key already set for the display is replaced in place; new keys are
+    added at the end of the display's part of the file. The file is
+    replaced atomically. In a structured *.toml file each key goes to its
+    table, converted to the setting's type; comments are not kept there.
+
+    Args:
+        settings: Keys and values in the key=value format, e.g.
+                  {"rotation": "ccw90", "spi_speed_hz": "20000000"}
+        path: Config file path; defaults as for load_config()
+        display: Display whose settings to change, one of list_displays()
+
+    Raises:
+        DisplayError: If the display is not configured, a key of a structured
+                      file is unknown, or the file cannot be written
+    """
+    path = _config_path(path)
+    if _is_structured(path):
+        _save_structured(settings, path, display)
+        return
+    try:
+        with open(path, "r") as f:
+            lines = f.read().splitlines()
+    except FileNotFoundError:
+        lines = []
+    except OSError as e:
+        raise DisplayError(f"Failed to read {path}: {e}")
+
+    # The display's part of the file: the top level up to the first section, or its own section
+    start, end = 0, len(lines)
+    current = MAIN_DISPLAY
+    for index, line in enumerate(lines):
+        stripped = line.strip()
+        if stripped.startswith("[") and stripped.endswith("]"):
+            if current == display:
+                end = index
+                break
+            current = stripped[1:-1].strip().lower()
+            if current == display:
+                start = index + 1
+    else:
+        if current != display:
+            raise DisplayError(f"No display '{display}' in {path}; configured: {', '.join(list_displays(path))}")
+
+    pending = {key.lower(): value for key, value in settings.items()}
+    for index in range(start, end):
+        stripped = lines[index].strip()
+        if stripped.startswith("#") or "=" not in stripped:
+            continue
+        key = stripped.split("=", 1)[0].strip().lower()
+        if key in pending:
+            lines[index] = f"{key} = {pending.pop(key)}"
+    while end > start and not lines[end - 1].strip():
+        end -= 1        # add new keys before the blank lines separating the next section
+    lines[end:end] = [f"{key} = {value}" for key, value in pending.items()]
+    _write_file(path, "\n".join(lines) + "\n")
+
+
+def config_values(config: DisplayConfig) -> Dict[str, str]:
+    """
+    Every setting of config as keys and values in the key=value format, for
+    save_config_values(). A custom panel is left out: the config only has
+    its definition, not the descriptor path to save.
+    """
+    values = {
+        "rotation": _ROTATION_SETTINGS[config.rotation],
+        "autodetect": "yes" if config.autodetect else "no",
+        "usage_file": config.usage_file or "none",
+        "register_access": "yes" if config.register_access else "no",
+        "fallback_backend": config.fallback_backend or "none",
+        "busy_timeout_ms": str(config.busy_timeout_ms),
+        "busy_retries": str(config.busy_retries),
+        "full_refresh_every": str(config.full_refresh_every),
+        "full_refresh_interval": f"{config.full_refresh_interval_s:g}",
+        "threshold": str(config.image_options.threshold),
+        "contrast": f"{config.image_options.contrast:g}",
+        "dither": config.image_options.dither.value,
+        "parallel": "yes" if config.image_options.parallel else "no",
+        "scaling_filter": config.image_options.scaling_filter.value,
+        "sharpen": f"{config.image_options.sharpen:g}",
+        "cache_dir": config.image_cache_dir or "none",
+        "cache_max_mb": f"{config.image_cache_max_mb:g}",
+    }
+    if config.backend is not None:
+        values["backend"] = config.backend
+    if config.panel != PanelType.CUSTOM:
+        values["panel"] = config.panel.value
+    if config.panel_ids:
+        values["panel_ids"] = ", ".join(f"{prefix.hex()}:{panel.value}" for prefix, panel in config.panel_ids.items())
+    if config.auto_partial_max_pixels is not None:
+        values["auto_partial_max_pixels"] = str(config.auto_partial_max_pixels)
+    for key, attr in _THERMAL_KEYS:
+        values[key] = f"{getattr(config, attr):g}"
+    for key in _SPI_KEYS:
+        values[key] = str(getattr(config.spi, key[len("spi_"):] if key.startswith("spi_") else key))
+    return values
+
+
+def save_config(config: DisplayConfig, path: Optional[str] = None) -> None:
+    """
+    Save every setting of config for its display (config.name), e.g. after
+    changing the default panel, so the change survives a reboot:
+
+        config = load_config()
+        config.panel = PanelType.EPD296x128
+        save_config(config)
+
+    Environment overrides applied by load_config() are saved as well.
+
+    Args:
+        config: Settings to save
+        path: Config file path; defaults as for load_config()
+
+    Raises:
+        DisplayError: As for save_config_values()
+    """
+    save_config_values(config_values(config), path, config.name)
+
+
+def migrate_config(source: str = CONFIG_PATH, target: str = STRUCTURED_CONFIG_PATH) -> None:
+    """
+    Convert a key=value config file, with its additional displays, to a
+    structured one. The source file is left in place but no longer read by
+    default once the target exists.
+
+    Raises:
+        DisplayError: If the source is malformed, has a key eink.toml does
+                      not know, or the target cannot be written
+    """
+    if not os.path.exists(source):
+        raise DisplayError(f"No config file {source} to migrate")
+    values, sections = _read_sections(source)
+
+    def tables(settings: Dict[str, str]) -> Dict[str, Any]:
+        result: Dict[str, Any] = {}
+        for key, value in settings.items():
+            if key not in _SETTINGS:
+                raise DisplayError(f"{source}: unknown setting '{key}'")
+            table, name, kind = _SETTINGS[key]
+            result.setdefault(table, {})[name] = value if kind is str else _typed(key, value, kind)
+        return result
+
+    data = tables(values)
+    if sections:
+        data["displays"] = {name: tables(section) for name, section in sections.items()}
+    _write_file(target, "\n".join(_format_toml(data)) + "\n")
@@ -0,0 +1,315 @@
+#!/usr/bin/env python3
+"""
+E-ink display daemon: distiller-eink-daemon [--socket PATH] [--group GROUP]
+
+Owns the display and draws for other processes, which connect to a Unix
+socket with DisplayClient instead of opening the SPI device themselves.
+Requests from all clients are carried out one at a time, so processes no
+longer fight over the panel, and only the daemon needs access to the
+hardware; clients need write access to the socket.
+
+Protocol: every block is a 4-byte big-endian length followed by that many
+bytes. A message, request or reply, is two blocks, a JSON header and a
+body, which is empty except for frames:
+
+    request  {"cmd": "display", "mode": "partial"} + packed 1-bit frame
+    reply    {"ok": true} + empty body
+
+    request  {"cmd": "screenshot"} + empty body
+    reply    {"ok": true} + packed 1-bit frame of what the panel shows
+
+Commands: display, clear, sleep, wake, info, screenshot, ping, lock and
+unlock. A failed command is answered with {"ok": false, "error": "..."}. A
+connection stays open for any number of requests.
+
+While the screen lock (device.lock) is locked, display, clear, sleep, wake
+and screenshot are refused until a client sends {"cmd": "unlock", "pin":
+"..."}. The lock state belongs to the daemon process, so entering the PIN on
+the panel's menu does not unlock the daemon.
+
+A request may carry a "tag" naming who it is for, e.g. the app. Updates are
+tagged with it and the client's process ID, which the daemon reads from the
+socket, or with the client's process name without one (see tag_updates()),
+so the audit log and info show which process drew the screen.
+"""
+
+import argparse
+import grp
+import json
+import os
+import signal
+import socket
+import struct
+import sys
+import threading
+from typing import Dict, Optional, Tuple
+
+from ...device.lock import LOCK_PATH, LockError, ScreenLock
+from ...diagnostics.log_levels import get_logger
+from .display import MAX_TAG_LENGTH, Display, DisplayError, DisplayMode, tag_updates
+
+
+SOCKET_PATH = "/run/distiller-cm5-sdk/eink.sock"
+SOCKET_PATH_ENV_VAR = "DISTILLER_EINK_SOCKET"
+
+# Blocks larger than this are refused; frames are a few KiB
+MAX_BLOCK_SIZE = 1 << 20
+
+COMMANDS = ("display", "clear", "sleep", "wake", "info", "screenshot", "ping", "lock", "unlock")
+
+# Commands refused while the screen lock is locked
+LOCKED_COMMANDS = ("display", "clear", "sleep", "wake", "screenshot")
+
+_LENGTH = struct.Struct(">I")
+
+
+def default_socket_path() -> str:
+    """Path of the daemon socket (DISTILLER_EINK_SOCKET overrides the default)."""
+    return os.environ.get(SOCKET_PATH_ENV_VAR, SOCKET_PATH)
+
+
+def _recv_exact(conn: socket.socket, size: int) -> bytes:
+    data = bytearray()
+    while len(data) < size:
+        chunk = conn.recv(size - len(data))
+        if not chunk:
+            raise ConnectionError("connection closed")
+        data += chunk
+    return bytes(data)
+
+
+def _recv_block(conn: socket.socket) -> bytes:
+    (size,) = _LENGTH.unpack(_recv_exact(conn, _LENGTH.size))
+    if size > MAX_BLOCK_SIZE:
+        raise ValueError(f"block of {size} bytes is over the {MAX_BLOCK_SIZE} byte limit")
+    return _recv_exact(conn, size)
+
+
+def peer_process(conn: socket.socket) -> Optional[Tuple[int, str]]:
+    """Process ID and name of the process at the other end of a Unix socket, or None if unknown."""
+    try:
+        pid, _, _ = struct.unpack("3i", conn.getsockopt(socket.SOL_SOCKET, socket.SO_PEERCRED,
+                                                        struct.calcsize("3i")))
+    except (AttributeError, OSError):
+        return None     # not Linux, or not a Unix socket
+    try:
+        with open(f"/proc/{pid}/comm", "r") as f:
+            name = f.read().strip()
+    except OSError:
+        name = "unknown"
+    return pid, name
+
+
+def send_message(conn: socket.socket, header: Dict, body: bytes = b"") -> None:
+    """Send a JSON header and a body, each length-prefixed."""
+    payload = json.dumps(header).encode()
+    conn.sendall(_LENGTH.pack(len(payload)) + payload + _LENGTH.pack(len(body)) + body)
+
+
+def recv_message(conn: socket.socket) -> Tuple[Dict, bytes]:
+    """
+    Receive a JSON header and a body.
+
+    Raises:
+        ConnectionError: If the peer closed the connection
+        ValueError: If a block is too large or the header is not a JSON object
+    """
+    header = json.loads(_recv_block(conn))
+    if not isinstance(header, dict):
+        raise ValueError("header is not a JSON object")
+    return header, _recv_block(conn)
+
+
+class DisplayServer:
+    """Serves a Display to DisplayClients over a Unix socket."""
+
+    def __init__(self, display: Display, socket_path: Optional[str] = None, mode: int = 0o660,
+                 group: Optional[str] = None, lock: Optional[ScreenLock] = None):
+        """
+        Args:
+            display: Initialized Display to draw on
+            socket_path: Socket path; None uses DISTILLER_EINK_SOCKET or
+                         /run/distiller-cm5-sdk/eink.sock
+            mode: Permissions of the socket file
+            group: Group given the socket file, so its members can draw
+                   without being root
+            lock: Screen lock gating draw requests; None for no lock
+        """
+        self.display = display
+        self.socket_path = socket_path or default_socket_path()
+        self.mode = mode
+        self.group = group
+        self.lock = lock
+        self._log = get_logger("hardware.eink.daemon")
+        self._lock = threading.Lock()
+        self._stop = threading.Event()
+        self._server: Optional[socket.socket] = None
+
+    def handle(self, header: Dict, body: bytes = b"",
+               peer: Optional[Tuple[int, str]] = None) -> Tuple[Dict, bytes]:
+        """
+        Carry out one request; requests from all connections are serialized.
+
+        Args:
+            header: The request header
+            body: The request body
+            peer: Process ID and name of the client, as from peer_process()
+
+        Returns:
+            (reply header, reply body)
+        """
+        command = header.get("cmd")
+        if command not in COMMANDS:
+            return {"ok": False, "error": f"unknown command '{command}'"}, b""
+        tag = header.get("tag")
+        tag = str(tag)[:MAX_TAG_LENGTH] if tag else None
+        if peer is not None:
+            tag = f"{tag or peer[1]} (pid {peer[0]})"
+        if command in ("lock", "unlock"):
+            return self._handle_lock(command, header)
+        if self.lock is not None and command in LOCKED_COMMANDS:
+            try:
+                self.lock.require_unlocked(f"'{command}'")
+            except LockError as e:
+                return {"ok": False, "error": str(e)}, b""
+        try:
+            with self._lock, tag_updates(tag):
+                reply = b""
+                if command == "display":
+                    if len(body) != self.display.ARRAY_SIZE:
+                        raise DisplayError(f"Frame is {len(body)} bytes, expected {self.display.ARRAY_SIZE}")
+                    self.display.display_image(body, DisplayMode[str(header.get("mode", "full")).upper()])
+                elif command == "clear":
+                    self.display.clear()
+                elif command == "sleep":
+                    self.display.sleep()
+                elif command == "wake":
+                    self.display.wake()
+                elif command == "info":
+                    return {"ok": True, **self.display.get_info()}, b""
+                elif command == "screenshot":
+                    reply = self.display.get_framebuffer() or b""
+                return {"ok": True}, reply
+        except KeyError:
+            return {"ok": False, "error": f"invalid mode '{header.get('mode')}'"}, b""
+        except DisplayError as e:
+            return {"ok": False, "error": str(e)}, b""
+
+    def _handle_lock(self, command: str, header: Dict) -> Tuple[Dict, bytes]:
+        if self.lock is None or not self.lock.enabled:
+            return {"ok": False, "error": "no screen lock is set"}, b""
+        if command == "lock":
+            self.lock.lock()
+        elif not self.lock.unlock(str(header.get("pin", ""))):
+            remaining = self.lock.lockout_remaining()
+            if remaining > 0:
+                return {"ok": False, "error": f"too many wrong PINs; try again in {remaining:.0f} s"}, b""
+            return {"ok": False, "error": "wrong PIN"}, b""
+        return {"ok": True}, b""
+
+    def stop(self) -> None:
+        """End run() from another thread."""
+        self._stop.set()
+        if self._server is not None:
+            try:
+                self._server.shutdown(socket.SHUT_RDWR)
+            except OSError:
+                pass
+
+    def _open_socket(self) -> None:
+        directory = os.path.dirname(self.socket_path)
+        if directory:
+            os.makedirs(directory, exist_ok=True)
+        if os.path.exists(self.socket_path):
+            os.unlink(self.socket_path)  # left over from a previous run
+        self._server = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        self._server.bind(self.socket_path)
+        if self.group is not None:
+            try:
+                gid = grp.getgrnam(self.group).gr_gid
+            except KeyError:
+                raise OSError(f"No group named '{self.group}'")
+            os.chown(self.socket_path, -1, gid)
+        os.chmod(self.socket_path, self.mode)
+        self._server.listen(8)
+
+    def _serve_client(self, conn: socket.socket) -> None:
+        peer = peer_process(conn)
+        with conn:
+            while not self._stop.is_set():
+                try:
+                    header, body = recv_message(conn)
+                except ConnectionError:
+                    return
+                except (OSError, ValueError) as e:
+                    self._log.info("client dropped: %s", e)
+                    return
+                reply, reply_body = self.handle(header, body, peer)
+                try:
+                    send_message(conn, reply, reply_body)
+                except OSError as e:
+                    self._log.info("client dropped: %s", e)
+                    return
+
+    def run(self, ready: Optional[threading.Event] = None) -> None:
+        """
+        Serve clients until stop().
+
+        Args:
+            ready: Set once the socket accepts connections
+        """
+        self._open_socket()
+        self._log.info("serving the display on %s", self.socket_path)
+        if ready is not None:
+            ready.set()
+        try:
+            while not self._stop.is_set():
+                try:
+                    conn, _ = self._server.accept()
+                except OSError:
+                    break  # socket shut down by stop()
+                threading.Thread(target=self._serve_client, args=(conn,), name="eink-daemon-client",
+                                 daemon=True).start()
+        finally:
+            self._server.close()
+            try:
+                os.unlink(self.socket_path)
+            except OSError:
+                pass
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(prog="distiller-eink-daemon",
+                                     description="Own the e-ink display and draw for other processes")
+    parser.add_argument("--socket", help="Socket path (default: DISTILLER_EINK_SOCKET or "
+                                         "/run/distiller-cm5-sdk/eink.sock)")
+    parser.add_argument("--group", help="Group allowed to draw through the socket")
+    parser.add_argument("--mode", type=lambda value: int(value, 8), default=0o660,
+                        help="Socket permissions, in octal (default: 660)")
+    parser.add_argument("--backend", help="Display backend (default: from eink.toml)")
+    parser.add_argument("--lock-file", default=LOCK_PATH, help=f"Screen lock configuration (default: {LOCK_PATH})")
+    args = parser.parse_args(argv)
+
+    try:
+        lock = ScreenLock.from_file(args.lock_file)
+    except LockError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+
+    try:
+        with Display(backend=args.backend) as display:
+            server = DisplayServer(display, args.socket, args.mode, args.group, lock)
+            # systemd stops the daemon with SIGTERM; close the display and socket cleanly
+            signal.signal(signal.SIGTERM, lambda signum, frame: server.stop())
+            try:
+                server.run()
+            except KeyboardInterrupt:
+                pass
+    except (DisplayError, OSError) as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
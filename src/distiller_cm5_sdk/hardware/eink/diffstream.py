@@ -0,0 +1,275 @@
+#!/usr/bin/env python3
+"""
+Compact frame-diff stream for mirroring the display remotely.
+
+A PNG of every refresh is several kilobytes, most of it pixels that did not
+change. DiffEncoder turns each frame into a message holding only the
+windows of pixels that changed since the previous one, optionally
+run-length encoded, so a viewer can follow the panel over a slow or
+metered link. DiffDecoder on the viewer's side rebuilds the frames:
+
+    encoder = DiffEncoder(*display.get_dimensions())
+    send(encoder.encode(display.get_framebuffer()))     # after each refresh
+
+    decoder = DiffDecoder()
+    frame = decoder.decode(receive())                    # packed like a Framebuffer
+
+The first message is a keyframe holding the whole frame, as is any message
+whose windows would take more room than the frame. Messages are numbered;
+a decoder that missed one raises DiffStreamError, and the sender answers
+with request_keyframe() before the next encode(). The transport, e.g. a
+WebSocket, only has to deliver messages in order.
+
+Message layout, little-endian: MAGIC, _HEADER, then for each window
+_WINDOW followed by its payload: the window's pixels packed 8 per byte,
+MSB first, rows not padded, as stored (raw) or PackBits-encoded (rle).
+"""
+
+import struct
+from typing import List, Optional, Sequence, Tuple, Union
+
+from .flipbook import _apply_window, _extract_window, _pack_rows, _unpack_rows
+from .graphics import Framebuffer
+
+
+MAGIC = b"EDIF"
+VERSION = 1
+_HEADER = struct.Struct("<BBHHIH")      # version, flags, width, height, sequence, window count
+_WINDOW = struct.Struct("<HHHHBI")      # x, y, width, height, encoding, payload length
+_KEYFRAME = 0x01
+
+ENCODING_RAW = 0
+ENCODING_RLE = 1
+
+# Changed rows closer than this are sent in one window; a wider gap of
+# unchanged rows starts another, e.g. for a status bar and the content
+MERGE_GAP_ROWS = 8
+
+_MAX_SEQUENCE = 0xFFFFFFFF
+
+
+class DiffStreamError(Exception):
+    """Custom exception for frame-diff stream errors."""
+    pass
+
+
+def rle_encode(data: bytes) -> bytes:
+    """PackBits: runs of 3 or more equal bytes become a count and the byte, the rest literal blocks."""
+    out = bytearray()
+    literal = bytearray()
+    i = 0
+    while i < len(data):
+        run = 1
+        while i + run < len(data) and run < 128 and data[i + run] == data[i]:
+            run += 1
+        if run >= 3:
+            if literal:
+                out.append(len(literal) - 1)
+                out += literal
+                literal.clear()
+            out.append(257 - run)
+            out.append(data[i])
+            i += run
+            continue
+        literal.append(data[i])
+        i += 1
+        if len(literal) == 128:
+            out.append(127)
+            out += literal
+            literal.clear()
+    if literal:
+        out.append(len(literal) - 1)
+        out += literal
+    return bytes(out)
+
+
+def rle_decode(data: bytes, size: int) -> bytes:
+    """
+    Undo rle_encode().
+
+    Raises:
+        DiffStreamError: If the data is truncated or does not decode to size bytes
+    """
+    out = bytearray()
+    i = 0
+    try:
+        while i < len(data):
+            count = data[i]
+            if count < 128:
+                block = data[i + 1:i + 2 + count]
+                if len(block) != count + 1:
+                    raise IndexError
+                out += block
+                i += count + 2
+            elif count > 128:
+                out += bytes([data[i + 1]]) * (257 - count)
+                i += 2
+            else:
+                i += 1
+    except IndexError:
+        raise DiffStreamError("Truncated run-length data")
+    if len(out) != size:
+        raise DiffStreamError(f"Run-length data decodes to {len(out)} bytes, expected {size}")
+    return bytes(out)
+
+
+def _changed_windows(old: Sequence[int], new: Sequence[int], width: int) -> List[Tuple[int, int, int, int]]:
+    """Windows around the changed pixels, one per group of changed rows."""
+    windows = []
+    group: List[int] = []
+    for y in range(len(new)):
+        if old[y] == new[y]:
+            continue
+        if group and y - group[-1] > MERGE_GAP_ROWS:
+            windows.append(_row_window(old, new, width, group))
+            group = []
+        group.append(y)
+    if group:
+        windows.append(_row_window(old, new, width, group))
+    return windows
+
+
+def _row_window(old: Sequence[int], new: Sequence[int], width: int, rows: List[int]) -> Tuple[int, int, int, int]:
+    diff = 0
+    for y in rows:
+        diff |= old[y] ^ new[y]
+    left = width - diff.bit_length()
+    right = width - ((diff & -diff).bit_length() - 1)
+    return left, rows[0], right - left, rows[-1] - rows[0] + 1
+
+
+class DiffEncoder:
+    """Encodes the frames of one display as a stream of diff messages."""
+
+    def __init__(self, width: int, height: int, rle: bool = True):
+        """
+        Args:
+            width: Frame width, e.g. Display.get_dimensions()[0]
+            height: Frame height
+            rle: Run-length encode a window when that makes it smaller
+        """
+        if width < 1 or height < 1 or width > 0xFFFF or height > 0xFFFF:
+            raise DiffStreamError(f"Invalid frame size {width}x{height}")
+        self.width = width
+        self.height = height
+        self.rle = rle
+        self.sequence = 0
+        self._rows: Optional[List[int]] = None
+
+    def request_keyframe(self) -> None:
+        """Send the whole frame next, e.g. when a viewer joins or lost a message."""
+        self._rows = None
+
+    def encode(self, frame: Union[bytes, Framebuffer]) -> Optional[bytes]:
+        """
+        Encode a frame as a message.
+
+        Args:
+            frame: Packed 1-bit frame, as from Display.get_framebuffer() or a Framebuffer
+
+        Returns:
+            The message, or None if nothing changed since the last frame
+
+        Raises:
+            DiffStreamError: If the frame does not have the encoder's size
+        """
+        if isinstance(frame, Framebuffer):
+            if (frame.width, frame.height) != (self.width, self.height):
+                raise DiffStreamError(f"Frame is {frame.width}x{frame.height}, expected {self.width}x{self.height}")
+            frame = frame.to_bytes()
+        size = (self.width * self.height + 7) // 8
+        if len(frame) != size:
+            raise DiffStreamError(f"Frame must be exactly {size} bytes, got {len(frame)}")
+
+        rows = _unpack_rows(bytes(frame), self.width, self.height)
+        keyframe = self._rows is None
+        windows = [] if keyframe else _changed_windows(self._rows, rows, self.width)
+        if not keyframe and not windows:
+            return None
+        parts = [self._window(rows, window) for window in windows]
+        if keyframe or sum(len(part) for part in parts) >= size + _WINDOW.size:
+            keyframe = True
+            parts = [self._window(rows, (0, 0, self.width, self.height))]
+
+        self._rows = rows
+        self.sequence = (self.sequence + 1) & _MAX_SEQUENCE
+        header = _HEADER.pack(VERSION, _KEYFRAME if keyframe else 0, self.width, self.height, self.sequence,
+                              len(parts))
+        return MAGIC + header + b"".join(parts)
+
+    def _window(self, rows: List[int], window: Tuple[int, int, int, int]) -> bytes:
+        pixels = _extract_window(rows, self.width, window)
+        encoding = ENCODING_RAW
+        if self.rle:
+            packed = rle_encode(pixels)
+            if len(packed) < len(pixels):
+                pixels, encoding = packed, ENCODING_RLE
+        return _WINDOW.pack(*window, encoding, len(pixels)) + pixels
+
+
+class DiffDecoder:
+    """Rebuilds frames from the messages of a DiffEncoder."""
+
+    def __init__(self):
+        self.width: Optional[int] = None
+        self.height: Optional[int] = None
+        self.sequence: Optional[int] = None
+        self._rows: Optional[List[int]] = None
+
+    @property
+    def frame(self) -> Optional[bytes]:
+        """The last frame decoded, packed like a Framebuffer, or None before the first keyframe."""
+        if self._rows is None:
+            return None
+        return _pack_rows(self._rows, self.width)
+
+    def decode(self, message: bytes) -> bytes:
+        """
+        Apply a message to the last frame.
+
+        Returns:
+            The frame after the message, packed like a Framebuffer
+
+        Raises:
+            DiffStreamError: If the message is damaged, or a diff does not
+                             follow the last message decoded; the decoder
+                             then waits for a keyframe
+        """
+        if not message.startswith(MAGIC):
+            raise DiffStreamError("Not a frame-diff message")
+        try:
+            version, flags, width, height, sequence, count = _HEADER.unpack_from(message, len(MAGIC))
+        except struct.error as e:
+            raise DiffStreamError(f"Damaged frame-diff message: {e}")
+        if version != VERSION:
+            raise DiffStreamError(f"Unsupported frame-diff version {version}")
+        keyframe = bool(flags & _KEYFRAME)
+        if not keyframe:
+            if self._rows is None:
+                raise DiffStreamError("Waiting for a keyframe")
+            if (width, height) != (self.width, self.height) or sequence != (self.sequence + 1) & _MAX_SEQUENCE:
+                self._rows = None
+                raise DiffStreamError(f"Missed messages before {sequence}; waiting for a keyframe")
+
+        rows = [0] * height if keyframe else list(self._rows)
+        offset = len(MAGIC) + _HEADER.size
+        try:
+            for _ in range(count):
+                x, y, w, h, encoding, length = _WINDOW.unpack_from(message, offset)
+                offset += _WINDOW.size
+                payload = message[offset:offset + length]
+                offset += length
+                size = (w * h + 7) // 8
+                if len(payload) != length or x + w > width or y + h > height:
+                    raise DiffStreamError("Damaged frame-diff window")
+                if encoding == ENCODING_RLE:
+                    payload = rle_decode(payload, size)
+                elif encoding != ENCODING_RAW or length != size:
+                    raise DiffStreamError("Damaged frame-diff window")
+                if w and h:
+                    _apply_window(rows, width, (x, y, w, h), payload)
+        except struct.error as e:
+            raise DiffStreamError(f"Damaged frame-diff message: {e}")
+
+        self.width, self.height, self.sequence, self._rows = width, height, sequence, rows
+        return _pack_rows(rows, width)
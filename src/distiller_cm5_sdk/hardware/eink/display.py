@@ -5,15 +5,39 @@ Provides functionality for e-ink display control and image display.
 """
 
 import os
+import contextvars
 import ctypes
+import functools
+from contextlib import contextmanager
 from ctypes import c_bool, c_char_p, c_uint32, POINTER
+from dataclasses import dataclass
 from enum import IntEnum
-from typing import Optional, Tuple, Union
+from typing import Callable, Dict, Iterator, List, Optional, Tuple, Union
 import tempfile
+import time
+import weakref
+
+from ...diagnostics.audit import AuditCategory, audit
+from ...diagnostics.tracing import STAGE_DISPLAY, current_interaction, record_stage
+from .font import FONT_HEIGHT, FONT_WIDTH
+
+# ABI version of the C library these bindings were written against
+DISPLAY_SDK_ABI_VERSION = 2
+
+# SDK release of these bindings (DISPLAY_SDK_VERSION in distiller_display_sdk.h)
+DISPLAY_SDK_VERSION = "0.1.0"
 
 
 class DisplayError(Exception):
     """Custom exception for Display-related errors."""
+    
+    def __init__(self, message: str = "", code: Optional["DisplayErrorCode"] = None):
+        super().__init__(message)
+        self.code = code    # library error code of the failed call, if it reported one
+
+
+class DisplayTimeoutError(DisplayError):
+    """The panel stayed busy, even after a hardware reset and the configured retries."""
     pass
 
 
@@ -23,6 +47,321 @@ class DisplayMode(IntEnum):
     PARTIAL = 1   # Partial refresh - fast updates
 
 
+class RotationMode(IntEnum):
+    """Rotation from the caller's frame to the panel (display_rotation_t)."""
+    NONE = 0        # Portrait 128x250, as the panel is wired
+    CCW_90 = 1      # Landscape 250x128 frame rotated 90 degrees counter-clockwise
+    ROTATE_180 = 2  # Portrait frame rotated 180 degrees
+    CW_90 = 3       # Landscape 250x128 frame rotated 90 degrees clockwise
+
+
+class RamPlane(IntEnum):
+    """RAM planes of the panel controller (display_plane_t)."""
+    NEW = 0     # The frame the next refresh shows
+    OLD = 1     # The frame a partial refresh compares against
+
+
+class DisplayStatus(IntEnum):
+    """Status codes returned in structured results (display_status_t)."""
+    OK = 0
+    INVALID_ARGUMENT = 1
+    INIT_FAILED = 2
+    LOAD_FAILED = 3
+    PROCESS_FAILED = 4
+    UPDATE_FAILED = 5
+    TIMEOUT = 6
+    ABORTED = 7
+
+
+# display_show_file() statuses that leave the library initialized: it got past display_init()
+_SHOW_FILE_INITIALIZED = (DisplayStatus.OK, DisplayStatus.LOAD_FAILED, DisplayStatus.UPDATE_FAILED,
+                          DisplayStatus.TIMEOUT)
+
+
+class LutKind(IntEnum):
+    """Refreshes whose waveform Display.set_lut() replaces (display_lut_kind_t)."""
+    FULL = 0
+    PARTIAL = 1
+
+
+class LutPreset(IntEnum):
+    """Built-in waveforms for Display.set_lut() (display_lut_preset_t)."""
+    OTP = 0             # The panel's own waveform (default)
+    FAST_REFRESH = 1    # Short waveform: sub-second updates, more ghosting
+
+
+# Size of an SSD1680 waveform LUT, and of one followed by its voltages (DISPLAY_LUT_SIZE*)
+LUT_SIZE = 153
+LUT_SIZE_VOLTAGES = 159
+
+# Temperature compensation (DISPLAY_TEMPERATURE_* and DISPLAY_*_MC): below
+# COLD_C LUT overrides are suspended and busy waits doubled, below
+# FREEZING_C tripled
+TEMPERATURE_INTERNAL = -2**31
+TEMPERATURE_MIN_C = -40.0
+TEMPERATURE_MAX_C = 100.0
+COLD_C = 10.0
+FREEZING_C = 0.0
+
+
+class DisplayErrorCode(IntEnum):
+    """Error codes returned by C functions that can fail (display_error_t)."""
+    OK = 0
+    INVALID_ARGUMENT = -1
+    NOT_INITIALIZED = -2
+    INVALID_SIZE = -3
+    CONFIG = -4
+    SPI = -5
+    GPIO = -6
+    TIMEOUT = -7
+    ABORTED = -8
+    DECODE = -9
+    NO_MEMORY = -10
+    NO_FRAME = -11
+    UNSUPPORTED = -12
+
+
+class DisplayEventType(IntEnum):
+    """Refresh lifecycle events reported to set_event_callback() (display_event_type_t).
+
+    THROTTLE_STARTED and THROTTLE_ENDED come from the thermal policy (see
+    thermal.py) and CONFIG_RELOADED from reload_config(), not from the
+    display library.
+    """
+    REFRESH_STARTED = 0
+    REFRESH_COMPLETED = 1
+    PANEL_SLEEP = 2
+    PANEL_WAKE = 3
+    ERROR = 4
+    THROTTLE_STARTED = 5
+    THROTTLE_ENDED = 6
+    CONFIG_RELOADED = 7
+
+
+@dataclass(frozen=True)
+class DisplayEvent:
+    """A refresh lifecycle event of one panel."""
+    type: DisplayEventType
+    display_id: int                 # native_id of the panel, 0 for the default one
+    mode: DisplayMode               # refresh mode, for refresh events
+    duration_ms: int = 0            # time taken, for REFRESH_COMPLETED, PANEL_WAKE and ERROR
+    error: DisplayErrorCode = DisplayErrorCode.OK
+    message: str = ""               # cause of an ERROR, THROTTLE_STARTED or THROTTLE_ENDED
+
+
+# Key of get_update_counts() for updates made without a tag
+UNTAGGED = "untagged"
+
+# Tags received from other processes are cut off at this length
+MAX_TAG_LENGTH = 64
+
+# Who the display updates made in this context are for; see tag_updates()
+_update_tag: contextvars.ContextVar[Optional[str]] = contextvars.ContextVar("distiller_eink_update_tag",
+                                                                          default=None)
+
+
+@contextmanager
+def tag_updates(tag: Optional[str]) -> Iterator[None]:
+    """
+    Attribute the display updates made inside the block to tag, e.g. an app
+    name or the peer of an IPC request, on every Display.
+
+    The tag is recorded with each update in the audit log, counted in
+    Display.get_update_counts() and reported as the last update by
+    Display.get_info(), so operators of a shared device can tell what drew
+    the screen. It follows the code through contextvars like an interaction
+    (see diagnostics.tracing), and DisplayClient passes it to the daemon.
+
+        with tag_updates("weather"):
+            display.display_image(frame)
+    """
+    token = _update_tag.set(tag)
+    try:
+        yield
+    finally:
+        _update_tag.reset(token)
+
+
+def current_update_tag() -> Optional[str]:
+    """The tag set by the innermost tag_updates() block, or None."""
+    return _update_tag.get()
+
+
+class ScreenSnapshot:
+    """
+    Content of the screen captured by Display.save_screen(), to put back
+    with restore(). As a context manager it restores on exit.
+    """
+
+    def __init__(self, display: "Display", frame: bytes, rotation: RotationMode):
+        self.display = display
+        self.frame = frame
+        self.rotation = rotation
+
+    def restore(self, mode: Optional[DisplayMode] = None) -> Optional[DisplayMode]:
+        """Put the captured content back on the screen (see Display.restore_screen())."""
+        return self.display.restore_screen(self, mode)
+
+    def __enter__(self) -> "ScreenSnapshot":
+        return self
+
+    def __exit__(self, exc_type, exc_val, exc_tb) -> None:
+        self.restore()
+
+
+class PixelFormat(IntEnum):
+    """Pixel layouts accepted for raw pixel-buffer input (pixel_format_t)."""
+    GRAY8 = 0      # 1 byte per pixel
+    RGB888 = 1     # 3 bytes per pixel
+    RGBA8888 = 2   # 4 bytes per pixel, alpha ignored
+
+
+PIXEL_FORMAT_BYTES = {
+    PixelFormat.GRAY8: 1,
+    PixelFormat.RGB888: 3,
+    PixelFormat.RGBA8888: 4,
+}
+
+
+class ProcessingOptions(ctypes.Structure):
+    """Image processing options passed to the C library (processing_options_t)."""
+    _fields_ = [
+        ("struct_size", c_uint32),
+        ("threshold", ctypes.c_uint8),
+        ("rotate", c_bool),
+        ("flip_horizontal", c_bool),
+        ("invert_colors", c_bool),
+    ]
+    
+    def __init__(self, threshold: int = 128, rotate: bool = False,
+                 flip_horizontal: bool = False, invert_colors: bool = False):
+        super().__init__(ctypes.sizeof(ProcessingOptions), threshold, rotate,
+                         flip_horizontal, invert_colors)
+
+
+class _SpiConfigStruct(ctypes.Structure):
+    """SPI device and GPIO wiring passed to the C library (display_spi_config_t)."""
+    _fields_ = [
+        ("struct_size", c_uint32),
+        ("bus", c_uint32),
+        ("cs", c_uint32),
+        ("speed_hz", c_uint32),
+        ("dc_pin", c_uint32),
+        ("reset_pin", c_uint32),
+        ("busy_pin", c_uint32),
+        ("gpio_chip", ctypes.c_char * 32),
+        ("block_size", c_uint32),
+        ("chunk_delay_us", c_uint32),
+    ]
+
+
+class _DisplayEventStruct(ctypes.Structure):
+    """Event passed to the event callback (display_event_t)."""
+    _fields_ = [
+        ("struct_size", c_uint32),
+        ("type", ctypes.c_int),
+        ("display_id", c_uint32),
+        ("mode", ctypes.c_int),
+        ("duration_ms", c_uint32),
+        ("error", ctypes.c_int),
+        ("message", c_char_p),
+    ]
+
+
+class _DisplayStatsStruct(ctypes.Structure):
+    """Driver counters read by display_get_stats() (display_stats_t)."""
+    _fields_ = [
+        ("struct_size", c_uint32),
+        ("last_refresh_ms", c_uint32),
+        ("full_refreshes", ctypes.c_uint64),
+        ("partial_refreshes", ctypes.c_uint64),
+        ("failed_refreshes", ctypes.c_uint64),
+        ("total_busy_ms", ctypes.c_uint64),
+        ("last_busy_ms", c_uint32),
+        ("average_spi_us", c_uint32),
+        ("spi_transfers", ctypes.c_uint64),
+        ("spi_bytes", ctypes.c_uint64),
+        ("total_spi_us", ctypes.c_uint64),
+    ]
+
+
+# display_event_callback_t(const display_event_t* event, void* user_data)
+_EVENT_CALLBACK = ctypes.CFUNCTYPE(None, POINTER(_DisplayEventStruct), ctypes.c_void_p)
+
+
+def _spi_config_struct(spi: "SpiConfig") -> _SpiConfigStruct:
+    return _SpiConfigStruct(ctypes.sizeof(_SpiConfigStruct), spi.bus, spi.cs, spi.speed_hz,
+                            spi.dc_pin, spi.reset_pin, spi.busy_pin, spi.gpio_chip.encode(),
+                            spi.block_size, spi.chunk_delay_us)
+
+
+class _DisplayResult(ctypes.Structure):
+    """Structured result returned by one-shot calls (display_result_t)."""
+    _fields_ = [
+        ("status", ctypes.c_int),
+        ("message", ctypes.c_char * 128),
+    ]
+
+
+# C functions that act on one panel, and so have a display_h_*() variant taking a handle
+_HANDLE_FUNCTIONS = (
+    "display_init", "display_image_raw", "display_image_png", "display_clear", "display_sleep",
+    "display_cleanup", "display_set_rotation", "display_get_rotation", "display_get_dimensions",
+    "display_show_file", "process_pixels_for_display", "display_pixels", "display_get_busy_stats",
+    "convert_png_to_1bit", "process_image_from_memory", "display_image_from_memory",
+    "display_set_busy_policy", "display_get_last_status", "display_image_auto",
+    "display_set_auto_threshold", "display_get_framebuffer", "display_abort", "display_emergency_frame",
+    "display_wake", "display_is_asleep", "display_set_event_callback", "display_get_stats", "display_set_lut",
+    "display_set_lut_preset", "display_set_temperature", "display_get_temperature", "display_restore_frame",
+    "display_write_plane", "display_flip",
+)
+
+
+# C functions returning DISPLAY_OK or a negative display_error_t
+_ERROR_CODE_FUNCTIONS = (
+    "display_init", "display_image_raw", "display_image_png", "display_clear", "display_set_rotation",
+    "process_pixels_for_display", "display_pixels", "convert_png_to_1bit", "process_image_from_memory",
+    "display_image_from_memory", "display_set_busy_policy", "display_image_auto", "display_get_framebuffer",
+    "display_emergency_frame", "display_wake", "display_set_spi_config", "display_get_stats", "display_set_lut",
+    "display_set_lut_preset", "display_set_temperature", "display_get_temperature", "display_restore_frame",
+    "display_write_plane", "display_flip",
+)
+
+
+def _succeeded(result: int, func, args) -> bool:
+    """ctypes errcheck turning an error code into success or failure; _failure() reads the cause."""
+    return result == DisplayErrorCode.OK
+
+
+def _handle_function(name: str) -> str:
+    """Name of the display_h_*() variant of a C function, e.g. display_clear -> display_h_clear."""
+    return "display_h_" + (name[len("display_"):] if name.startswith("display_") else name)
+
+
+class _NativeHandle:
+    """
+    One display_handle_t of the C library, called with the free functions'
+    names: display_clear() calls display_h_clear(handle). Each native Display
+    owns one, so several panels can be driven from one process.
+    """
+    
+    def __init__(self, lib, handle: int):
+        self._lib = lib
+        self.handle = handle
+    
+    def __getattr__(self, name: str):
+        if name not in _HANDLE_FUNCTIONS:
+            return getattr(self._lib, name)
+        func = functools.partial(getattr(self._lib, _handle_function(name)), self.handle)
+        setattr(self, name, func)
+        return func
+    
+    def __del__(self):
+        if self.handle:
+            self._lib.display_close(self.handle)
+            self.handle = None
+
+
 class Display:
     """
     Display class for interacting with the CM5 e-ink display system.
@@ -35,25 +374,137 @@ class Display:
     - Manage display power states
     """
     
-    # Display constants
+    # Display constants of the default panel; instances use those of their panel
     WIDTH = 128
     HEIGHT = 250
     ARRAY_SIZE = (WIDTH * HEIGHT) // 8  # 4000 bytes for 1-bit data
     
-    def __init__(self, library_path: Optional[str] = None, auto_init: bool = True):
+    def __init__(self, library_path: Optional[str] = None, auto_init: bool = True,
+                 backend: Optional[str] = None,
+                 config: Optional[Union["DisplayConfig", "DisplayConfigHandle"]] = None):
         """
         Initialize the Display object.
         
         Args:
             library_path: Optional path to the shared library. If None, searches common locations.
             auto_init: Whether to automatically initialize the display hardware
+            backend: "native" (compiled C library), "python" (pure Python, no shared
+                     library needed), "mock" (no hardware; frames captured in memory or
+                     as PNGs) or "simulator" (mock plus a desktop window). If None, uses
+                     the config's backend, else DISTILLER_EINK_BACKEND, defaulting to "native".
+            config: Persistent display settings, including the panel type, or a
+                    DisplayConfigHandle holding them. If None, those of the active
+                    DisplayConfigHandle.scope(), else the main display's loaded
+                    from eink.toml and the environment (see config.load_config()).
         
         Raises:
             DisplayError: If library can't be loaded or display can't be initialized
         """
         self._lib = None
         self._initialized = False
+        self._usage = None
+        self._front: Optional[bytes] = None     # last frame committed by end_frame()
+        self._back = None                       # Framebuffer between begin_frame() and end_frame()
+        self._progress_base: Optional[bytes] = None   # splash drawn behind display_progress()
+        self._progress_shown: Optional[bytes] = None  # last splash/progress frame, if still shown
+        self._idle: Optional["IdlePolicy"] = None
+        self._powered_off = False               # power_off() until power_on()
+        self._unpowered_frame: Optional[bytes] = None  # native frame left on the panel by power_off()
+        self._event_callback = None             # _EVENT_CALLBACK kept alive while the library holds it
+        self._event_listener: Optional[Callable[[DisplayEvent], None]] = None  # set_event_callback()
+        self._config_watcher: Optional["ConfigWatcher"] = None   # watch_config()
+        self.tag: Optional[str] = None          # tag of updates made outside tag_updates()
+        self.degraded: Optional[str] = None     # why the panel could not be set up, when running headless
+        self._last_update: Optional[dict] = None            # get_info()["last_update"]
+        self._update_counts: Dict[str, int] = {}            # get_update_counts()
+        
+        from .backends import NATIVE_BACKEND, load_backend, resolve_backend_name
+        from .panels import DEFAULT_PANEL, PanelType
+        from .config import DisplayConfigHandle, current_config_handle, load_config
+        if config is None and current_config_handle() is not None:
+            config = current_config_handle()
+        if isinstance(config, DisplayConfigHandle):
+            config = config.config
+        elif config is None:
+            config = load_config()
+        self.config = config
+        
+        try:
+            self.backend = resolve_backend_name(backend if backend is not None else config.backend)
+        except ValueError as e:
+            raise DisplayError(str(e))
+        
+        from .ghosting import GhostingPolicy
+        self.ghosting = GhostingPolicy(config.full_refresh_every, config.full_refresh_interval_s)
+        from .thermal import ThermalPolicy
+        # Through a proxy: the policy must not keep the display, and so its library handle, alive
+        this = weakref.proxy(self)
+        self.thermal = ThermalPolicy(config.thermal_soc_max_c, config.thermal_ambient_max_c,
+                                     config.thermal_ambient_min_c, config.thermal_interval_s,
+                                     read_ambient=lambda: this.get_temperature(),
+                                     on_change=lambda reason, celsius: this._on_throttle(reason, celsius))
         
+        self._set_panel(config.panel_spec())
+        
+        if self.backend == NATIVE_BACKEND:
+            if config.panel != DEFAULT_PANEL:
+                raise DisplayError(
+                    f"The native display library only drives the {DEFAULT_PANEL.value} panel; "
+                    f"{config.panel.value} is python-backend only (set backend = \"python\" in eink.toml)"
+                )
+            try:
+                self._load_native_library(library_path)
+                self._check_abi_version()
+            except DisplayError as e:
+                self._fall_back(e)
+        else:
+            # A custom definition is the user's explicit choice, so it is not probed away
+            autodetect = config.autodetect and config.panel != PanelType.CUSTOM
+            self._lib = load_backend(self.backend, self.panel, autodetect, config.panel_ids, config.spi)
+            self._check_abi_version()
+        
+        self._configure_library(config.rotation)
+        
+        if auto_init:
+            self.initialize()
+    
+    def _configure_library(self, rotation: RotationMode) -> None:
+        """Apply the config's wiring, busy policy and auto threshold, and rotation, to the library."""
+        from .backends import NATIVE_BACKEND
+        if self.backend == NATIVE_BACKEND:
+            self._configure_native_spi(self.config.spi)
+        self._configure_busy_policy(self.config.busy_timeout_ms, self.config.busy_retries)
+        if self.config.auto_partial_max_pixels is not None:
+            self.set_auto_threshold(self.config.auto_partial_max_pixels)
+        self.set_rotation(rotation)
+    
+    def _fall_back(self, error: DisplayError) -> None:
+        """
+        Go on headless with the config's fallback backend after the panel's
+        library or hardware could not be set up, or raise error without one.
+        The caller configures the new library.
+        """
+        from .backends import FALLBACK_BACKENDS, NATIVE_BACKEND, PYTHON_BACKEND, load_backend
+        fallback = self.config.fallback_backend
+        if fallback is None or self.backend not in (NATIVE_BACKEND, PYTHON_BACKEND):
+            raise error
+        if fallback not in FALLBACK_BACKENDS:
+            raise DisplayError(f"Invalid fallback backend '{fallback}'; expected {' or '.join(FALLBACK_BACKENDS)}")
+        print(f"Warning: {error}; running headless on the {fallback} backend")
+        self.degraded = f"{self.backend} backend: {error}"
+        self.backend = fallback
+        self._lib = load_backend(fallback, self.panel)
+        self._check_abi_version()
+        audit(AuditCategory.POWER, "display_degraded", str(error), backend=fallback)
+    
+    def _set_panel(self, panel: "PanelSpec") -> None:
+        self.panel = panel
+        self.WIDTH = panel.width
+        self.HEIGHT = panel.height
+        self.ARRAY_SIZE = panel.array_size
+    
+    def _load_native_library(self, library_path: Optional[str]) -> None:
+        """Load the compiled C library and declare its function signatures."""
         # Find and load the shared library
         if library_path is None:
             library_path = self._find_library()
@@ -68,9 +519,6 @@ class Display:
         
         # Set up function signatures
         self._setup_function_signatures()
-        
-        if auto_init:
-            self.initialize()
     
     def _find_library(self) -> str:
         """Find the shared library in common locations."""
@@ -98,23 +546,125 @@ class Display:
             "\n".join(f"  - {path}" for path in search_paths)
         )
     
+    def _check_abi_version(self) -> None:
+        """Refuse to drive a library built with an incompatible ABI, and warn about one from another release."""
+        abi_version = self._lib.display_sdk_abi_version()
+        if abi_version != DISPLAY_SDK_ABI_VERSION:
+            raise DisplayError(
+                f"Display library ABI version {abi_version} does not match "
+                f"expected version {DISPLAY_SDK_ABI_VERSION}; rebuild or reinstall the SDK"
+            )
+        self.library_version = self._library_version()
+        if self.library_version not in (None, DISPLAY_SDK_VERSION):
+            print(f"Warning: Display library is from SDK {self.library_version}, "
+                  f"these bindings from {DISPLAY_SDK_VERSION}")
+    
+    def _library_version(self) -> Optional[str]:
+        """SDK release of the loaded library; None for libraries built before display_sdk_version()."""
+        if not hasattr(self._lib, "display_sdk_version"):
+            return None
+        version = self._lib.display_sdk_version()
+        return version.decode() if isinstance(version, bytes) else version
+    
+    def _configure_native_spi(self, spi: "SpiConfig") -> None:
+        """
+        Open a handle of the C library for this display's wiring, so that each
+        Display drives its own panel. Libraries without handles are passed
+        non-default wiring for their single panel before it is initialized.
+        """
+        from .config import SpiConfig
+        if hasattr(self._lib, "display_open"):
+            spi.validate()
+            handle = self._lib.display_open(ctypes.byref(_spi_config_struct(spi)))
+            if not handle:
+                raise self._failure(f"Display library rejected SPI configuration {spi}")
+            self._lib = _NativeHandle(self._lib, handle)
+            return
+        if spi == SpiConfig():
+            return
+        if not hasattr(self._lib, "display_set_spi_config"):
+            raise DisplayError("The display library predates SPI configuration; rebuild or reinstall the SDK")
+        
+        # display_set_spi_config(const display_spi_config_t* config) -> int
+        self._lib.display_set_spi_config.restype = ctypes.c_int
+        self._lib.display_set_spi_config.argtypes = [POINTER(_SpiConfigStruct)]
+        self._lib.display_set_spi_config.errcheck = _succeeded
+        
+        spi.validate()
+        if not self._lib.display_set_spi_config(ctypes.byref(_spi_config_struct(spi))):
+            raise self._failure(f"Display library rejected SPI configuration {spi}")
+    
+    def _configure_busy_policy(self, timeout_ms: int, retries: int) -> None:
+        """Set the busy-wait timeout and the resets/retries that follow a timeout."""
+        from .config import BUSY_RETRIES, BUSY_TIMEOUT_MS
+        if not hasattr(self._lib, "display_set_busy_policy"):
+            if (timeout_ms, retries) == (BUSY_TIMEOUT_MS, BUSY_RETRIES):
+                return
+            raise DisplayError("The display library predates the busy policy; rebuild or reinstall the SDK")
+        
+        if timeout_ms <= 0 or retries < 0 or not self._lib.display_set_busy_policy(timeout_ms, retries):
+            raise DisplayError(f"Invalid busy policy: timeout {timeout_ms} ms, {retries} retries")
+    
+    def _last_status(self) -> Optional[DisplayStatus]:
+        """Status of the library's most recent init or update, if it reports one."""
+        get_last_status = getattr(self._lib, "display_get_last_status", None)
+        if get_last_status is None:
+            return None
+        status = get_last_status()
+        return DisplayStatus(status) if status in set(DisplayStatus) else None
+    
+    def _last_error(self) -> Tuple[Optional[DisplayErrorCode], str]:
+        """Code and message of the library's most recent failure on this thread, if it reports them."""
+        get_error = getattr(self._lib, "display_get_last_error", None)
+        get_message = getattr(self._lib, "display_get_last_error_message", None)
+        if get_error is None or get_message is None:
+            return None, ""
+        code = get_error()
+        if code not in set(DisplayErrorCode) or code == DisplayErrorCode.OK:
+            return None, ""
+        buf = ctypes.create_string_buffer(256)
+        get_message(buf, len(buf))
+        return DisplayErrorCode(code), buf.value.decode("utf-8", errors="replace")
+    
+    def _failure(self, message: str) -> DisplayError:
+        """Error for a failed library call, with the library's reason and a distinct type for a panel that stayed busy."""
+        code, detail = self._last_error()
+        if code is None:
+            # Libraries without error codes only report the outcome of updates
+            code = {DisplayStatus.TIMEOUT: DisplayErrorCode.TIMEOUT,
+                    DisplayStatus.ABORTED: DisplayErrorCode.ABORTED}.get(self._last_status())
+        if code == DisplayErrorCode.TIMEOUT:
+            return DisplayTimeoutError(f"{message}: display stayed busy after reset and retries", code)
+        if code == DisplayErrorCode.ABORTED:
+            return DisplayError(f"{message}: updates aborted (call wake() to resume)", code)
+        return DisplayError(f"{message}: {detail}" if detail else message, code)
+    
     def _setup_function_signatures(self):
         """Set up ctypes function signatures for all C functions."""
         
-        # display_init() -> bool
-        self._lib.display_init.restype = c_bool
+        # display_sdk_abi_version() -> uint32_t
+        self._lib.display_sdk_abi_version.restype = c_uint32
+        self._lib.display_sdk_abi_version.argtypes = []
+        
+        # display_sdk_version() -> const char* (absent from libraries built before it)
+        if hasattr(self._lib, "display_sdk_version"):
+            self._lib.display_sdk_version.restype = ctypes.c_char_p
+            self._lib.display_sdk_version.argtypes = []
+        
+        # display_init() -> int
+        self._lib.display_init.restype = ctypes.c_int
         self._lib.display_init.argtypes = []
         
-        # display_image_raw(const uint8_t* data, display_mode_t mode) -> bool
-        self._lib.display_image_raw.restype = c_bool
+        # display_image_raw(const uint8_t* data, display_mode_t mode) -> int
+        self._lib.display_image_raw.restype = ctypes.c_int
         self._lib.display_image_raw.argtypes = [ctypes.POINTER(ctypes.c_ubyte), ctypes.c_int]
         
-        # display_image_png(const char* filename, display_mode_t mode) -> bool
-        self._lib.display_image_png.restype = c_bool
+        # display_image_png(const char* filename, display_mode_t mode) -> int
+        self._lib.display_image_png.restype = ctypes.c_int
         self._lib.display_image_png.argtypes = [c_char_p, ctypes.c_int]
         
-        # display_clear() -> bool
-        self._lib.display_clear.restype = c_bool
+        # display_clear() -> int
+        self._lib.display_clear.restype = ctypes.c_int
         self._lib.display_clear.argtypes = []
         
         # display_sleep() -> void
@@ -125,13 +675,169 @@ class Display:
         self._lib.display_cleanup.restype = None
         self._lib.display_cleanup.argtypes = []
         
+        # display_set_rotation(display_rotation_t rotation) -> int
+        self._lib.display_set_rotation.restype = ctypes.c_int
+        self._lib.display_set_rotation.argtypes = [ctypes.c_int]
+        
+        # display_get_rotation() -> display_rotation_t
+        self._lib.display_get_rotation.restype = ctypes.c_int
+        self._lib.display_get_rotation.argtypes = []
+        
         # display_get_dimensions(uint32_t* width, uint32_t* height) -> void
         self._lib.display_get_dimensions.restype = None
         self._lib.display_get_dimensions.argtypes = [POINTER(c_uint32), POINTER(c_uint32)]
         
-        # convert_png_to_1bit(const char* filename, uint8_t* output_data) -> bool
-        self._lib.convert_png_to_1bit.restype = c_bool
+        # display_show_file(const char* filename, const processing_options_t* options, display_mode_t mode) -> display_result_t
+        self._lib.display_show_file.restype = _DisplayResult
+        self._lib.display_show_file.argtypes = [c_char_p, POINTER(ProcessingOptions), ctypes.c_int]
+        
+        # process_pixels_for_display(const uint8_t* pixels, uint32_t width, uint32_t height,
+        #                            pixel_format_t format, const processing_options_t* options,
+        #                            uint8_t* output_data) -> int
+        self._lib.process_pixels_for_display.restype = ctypes.c_int
+        self._lib.process_pixels_for_display.argtypes = [ctypes.POINTER(ctypes.c_ubyte), c_uint32, c_uint32,
+                                                         ctypes.c_int, POINTER(ProcessingOptions),
+                                                         ctypes.POINTER(ctypes.c_ubyte)]
+        
+        # display_pixels(const uint8_t* pixels, uint32_t width, uint32_t height, pixel_format_t format,
+        #                const processing_options_t* options, display_mode_t mode) -> int
+        self._lib.display_pixels.restype = ctypes.c_int
+        self._lib.display_pixels.argtypes = [ctypes.POINTER(ctypes.c_ubyte), c_uint32, c_uint32,
+                                             ctypes.c_int, POINTER(ProcessingOptions), ctypes.c_int]
+        
+        # display_get_busy_stats(uint32_t* last_ms, uint64_t* total_ms) -> void
+        self._lib.display_get_busy_stats.restype = None
+        self._lib.display_get_busy_stats.argtypes = [POINTER(c_uint32), POINTER(ctypes.c_uint64)]
+        
+        # convert_png_to_1bit(const char* filename, uint8_t* output_data) -> int
+        self._lib.convert_png_to_1bit.restype = ctypes.c_int
         self._lib.convert_png_to_1bit.argtypes = [c_char_p, ctypes.POINTER(ctypes.c_ubyte)]
+        
+        # process_image_from_memory(const uint8_t* buf, size_t len, uint8_t* output_data) -> int
+        self._lib.process_image_from_memory.restype = ctypes.c_int
+        self._lib.process_image_from_memory.argtypes = [ctypes.POINTER(ctypes.c_ubyte), ctypes.c_size_t, ctypes.POINTER(ctypes.c_ubyte)]
+        
+        # display_image_from_memory(const uint8_t* buf, size_t len, display_mode_t mode) -> int
+        self._lib.display_image_from_memory.restype = ctypes.c_int
+        self._lib.display_image_from_memory.argtypes = [ctypes.POINTER(ctypes.c_ubyte), ctypes.c_size_t, ctypes.c_int]
+        
+        # display_get_last_status() -> display_status_t (absent from libraries built before it)
+        if hasattr(self._lib, "display_get_last_status"):
+            self._lib.display_get_last_status.restype = ctypes.c_int
+            self._lib.display_get_last_status.argtypes = []
+        
+        # display_image_auto(const uint8_t* data, display_mode_t* mode_used) -> int, and
+        # display_set_auto_threshold(uint32_t max_pixels) (absent from libraries built before them)
+        if hasattr(self._lib, "display_image_auto"):
+            self._lib.display_image_auto.restype = ctypes.c_int
+            self._lib.display_image_auto.argtypes = [ctypes.POINTER(ctypes.c_ubyte), POINTER(ctypes.c_int)]
+            self._lib.display_set_auto_threshold.restype = None
+            self._lib.display_set_auto_threshold.argtypes = [c_uint32]
+        
+        # display_get_framebuffer(uint8_t* out) -> int (absent from libraries built before it)
+        if hasattr(self._lib, "display_get_framebuffer"):
+            self._lib.display_get_framebuffer.restype = ctypes.c_int
+            self._lib.display_get_framebuffer.argtypes = [ctypes.POINTER(ctypes.c_ubyte)]
+        
+        # display_restore_frame(const uint8_t* data) -> int (absent from libraries built before it)
+        if hasattr(self._lib, "display_restore_frame"):
+            self._lib.display_restore_frame.restype = ctypes.c_int
+            self._lib.display_restore_frame.argtypes = [ctypes.POINTER(ctypes.c_ubyte)]
+        
+        # display_write_plane(display_plane_t plane, const uint8_t* data) -> int
+        # display_flip(display_mode_t mode) -> int (absent from libraries built before them)
+        if hasattr(self._lib, "display_write_plane"):
+            self._lib.display_write_plane.restype = ctypes.c_int
+            self._lib.display_write_plane.argtypes = [ctypes.c_int, ctypes.POINTER(ctypes.c_ubyte)]
+            self._lib.display_flip.restype = ctypes.c_int
+            self._lib.display_flip.argtypes = [ctypes.c_int]
+        
+        # display_abort() and display_emergency_frame(const uint8_t* data) -> int
+        # (absent from libraries built before them)
+        if hasattr(self._lib, "display_abort"):
+            self._lib.display_abort.restype = None
+            self._lib.display_abort.argtypes = []
+            self._lib.display_emergency_frame.restype = ctypes.c_int
+            self._lib.display_emergency_frame.argtypes = [ctypes.POINTER(ctypes.c_ubyte)]
+        
+        # display_wake() -> int and display_is_asleep() -> bool (absent from libraries built before them)
+        if hasattr(self._lib, "display_wake"):
+            self._lib.display_wake.restype = ctypes.c_int
+            self._lib.display_wake.argtypes = []
+            self._lib.display_is_asleep.restype = c_bool
+            self._lib.display_is_asleep.argtypes = []
+        
+        # display_set_busy_policy(uint32_t timeout_ms, uint32_t retries) -> int (absent from libraries built before it)
+        if hasattr(self._lib, "display_set_busy_policy"):
+            self._lib.display_set_busy_policy.restype = ctypes.c_int
+            self._lib.display_set_busy_policy.argtypes = [c_uint32, c_uint32]
+        
+        # display_set_lut(display_lut_kind_t kind, const uint8_t* lut, size_t len) -> int and
+        # display_set_lut_preset(display_lut_kind_t kind, display_lut_preset_t preset) -> int
+        # (absent from libraries built before them)
+        if hasattr(self._lib, "display_set_lut"):
+            self._lib.display_set_lut.restype = ctypes.c_int
+            self._lib.display_set_lut.argtypes = [ctypes.c_int, ctypes.POINTER(ctypes.c_ubyte), ctypes.c_size_t]
+            self._lib.display_set_lut_preset.restype = ctypes.c_int
+            self._lib.display_set_lut_preset.argtypes = [ctypes.c_int, ctypes.c_int]
+        
+        # display_set_temperature(int32_t millicelsius) -> int and
+        # display_get_temperature(int32_t* millicelsius) -> int (absent from libraries built before them)
+        if hasattr(self._lib, "display_set_temperature"):
+            self._lib.display_set_temperature.restype = ctypes.c_int
+            self._lib.display_set_temperature.argtypes = [ctypes.c_int32]
+            self._lib.display_get_temperature.restype = ctypes.c_int
+            self._lib.display_get_temperature.argtypes = [POINTER(ctypes.c_int32)]
+        
+        # display_set_event_callback(display_event_callback_t callback, void* user_data)
+        # (absent from libraries built before it)
+        if hasattr(self._lib, "display_set_event_callback"):
+            self._lib.display_set_event_callback.restype = None
+            self._lib.display_set_event_callback.argtypes = [_EVENT_CALLBACK, ctypes.c_void_p]
+        
+        # display_get_stats(display_stats_t* stats) -> int (absent from libraries built before it)
+        if hasattr(self._lib, "display_get_stats"):
+            self._lib.display_get_stats.restype = ctypes.c_int
+            self._lib.display_get_stats.argtypes = [POINTER(_DisplayStatsStruct)]
+        
+        # display_get_last_error() -> int, display_get_last_error_message(char* buf, size_t len) -> size_t
+        # and display_error_string(int error) -> const char* (absent from libraries built before them)
+        if hasattr(self._lib, "display_get_last_error"):
+            self._lib.display_get_last_error.restype = ctypes.c_int
+            self._lib.display_get_last_error.argtypes = []
+            self._lib.display_get_last_error_message.restype = ctypes.c_size_t
+            self._lib.display_get_last_error_message.argtypes = [ctypes.c_char_p, ctypes.c_size_t]
+            self._lib.display_error_string.restype = c_char_p
+            self._lib.display_error_string.argtypes = [ctypes.c_int]
+        
+        # Functions that can fail report success or failure to Display, which asks
+        # the library for the cause (see _failure())
+        for name in _ERROR_CODE_FUNCTIONS:
+            if hasattr(self._lib, name):
+                getattr(self._lib, name).errcheck = _succeeded
+        
+        # display_open(const display_spi_config_t* config) -> display_handle_t*, display_close(display_handle_t*)
+        # and the display_h_*() variant of each function above, taking the handle first
+        # (absent from libraries built before them)
+        if hasattr(self._lib, "display_open"):
+            self._lib.display_open.restype = ctypes.c_void_p
+            self._lib.display_open.argtypes = [POINTER(_SpiConfigStruct)]
+            self._lib.display_close.restype = None
+            self._lib.display_close.argtypes = [ctypes.c_void_p]
+            for name in _HANDLE_FUNCTIONS:
+                if not hasattr(self._lib, name):
+                    continue
+                func = getattr(self._lib, name)
+                handle_func = getattr(self._lib, _handle_function(name))
+                handle_func.restype = func.restype
+                handle_func.argtypes = [ctypes.c_void_p] + list(func.argtypes)
+                if name in _ERROR_CODE_FUNCTIONS:
+                    handle_func.errcheck = _succeeded
+        
+        # display_get_id(display_handle_t*) -> uint32_t (absent from libraries built before it)
+        if hasattr(self._lib, "display_get_id"):
+            self._lib.display_get_id.restype = c_uint32
+            self._lib.display_get_id.argtypes = [ctypes.c_void_p]
     
     def initialize(self) -> None:
         """
@@ -143,11 +849,108 @@ class Display:
         if self._initialized:
             return
         
-        success = self._lib.display_init()
-        if not success:
-            raise DisplayError("Failed to initialize display hardware")
+        if not self._lib.display_init():
+            error = self._failure("Failed to initialize display hardware")
+            rotation = self.get_rotation()
+            self._fall_back(error)
+            self._configure_library(rotation)
+            if not self._lib.display_init():
+                raise self._failure("Failed to initialize display hardware")
+        
+        # Python backends that identify the panel at init may have switched to another one
+        from .backends import NATIVE_BACKEND
+        if self.backend != NATIVE_BACKEND and self._lib.panel != self.panel:
+            self._set_panel(self._lib.panel)
         
         self._initialized = True
+        self._start_usage_counter()
+        audit(AuditCategory.POWER, "display_init", backend=self.backend)
+    
+    def _start_usage_counter(self) -> None:
+        # Only real panels age; the mock and simulator backends are not counted
+        from .backends import NATIVE_BACKEND, PYTHON_BACKEND
+        if self._usage is not None or not self.config.usage_file:
+            return
+        if self.backend not in (NATIVE_BACKEND, PYTHON_BACKEND):
+            return
+        from .usage import UsageCounter
+        user_id = getattr(self._lib, "user_id", None) if self.backend == PYTHON_BACKEND else None
+        self._usage = UsageCounter(self.panel.panel_type.value, user_id, self.config.usage_file)
+    
+    def _processing_options(self, options: Optional[ProcessingOptions]) -> ProcessingOptions:
+        """options, or the configured processing profile's threshold when None."""
+        if options is not None:
+            return options
+        return ProcessingOptions(threshold=self.config.image_options.threshold)
+    
+    def _require_initialized(self) -> None:
+        """Raise unless initialized, first waking a panel the idle policy put to sleep."""
+        if self._powered_off:
+            raise DisplayError("Panel power is off. Call power_on() first.")
+        if self._idle is not None:
+            self._idle.wake()
+        if not self._initialized:
+            raise DisplayError("Display not initialized. Call initialize() first.")
+    
+    def _refresh_mode(self, mode: DisplayMode, wait: bool = True) -> DisplayMode:
+        """
+        Mode to refresh with for a requested one: partial while thermally
+        throttled, unless the ghosting policy needs a full refresh. With wait,
+        first hold off as long as throttling requires.
+        """
+        if wait:
+            self.thermal.wait()
+        return self.ghosting.mode_for(self.thermal.mode_for(mode))
+    
+    def _on_throttle(self, reason: Optional["ThrottleReason"], celsius: Optional[float]) -> None:
+        reading = f"{celsius:.1f} °C" if celsius is not None else "unknown temperature"
+        if reason is not None:
+            message = f"{reason.value.replace('_', ' ')} at {reading}"
+            print(f"Warning: Throttling display refreshes: {message}")
+            self._audit("throttle", reason.value, celsius=celsius)
+        else:
+            message = f"back within limits at {reading}"
+            self._audit("throttle_end", celsius=celsius)
+        self._emit(DisplayEventType.THROTTLE_STARTED if reason is not None else DisplayEventType.THROTTLE_ENDED,
+                   message)
+    
+    def _emit(self, event_type: DisplayEventType, message: str) -> None:
+        """Report an event of the Python SDK's own to set_event_callback()."""
+        if self._event_listener is not None:
+            try:
+                self._event_listener(DisplayEvent(event_type, self.native_id or 0, DisplayMode.PARTIAL,
+                                                  message=message))
+            except Exception as e:
+                print(f"Warning: Display event callback failed: {e}")
+    
+    def _current_tag(self) -> Optional[str]:
+        return _update_tag.get() or self.tag
+    
+    def _audit(self, action: str, source: Optional[str] = None, **details) -> None:
+        """Audit a display operation with the tag of the update it belongs to."""
+        tag = self._current_tag()
+        if tag is not None:
+            details["tag"] = tag
+        audit(AuditCategory.DISPLAY, action, source, **details)
+    
+    def _record_refresh(self, mode: DisplayMode) -> None:
+        tag = self._current_tag()
+        self._last_update = {"tag": tag, "mode": DisplayMode(mode).name.lower(), "time": time.time()}
+        key = tag if tag is not None else UNTAGGED
+        self._update_counts[key] = self._update_counts.get(key, 0) + 1
+        self.thermal.record()
+        if self._idle is not None:
+            self._idle.touch()
+        self._front = None  # end_frame() sets it again for its own frames
+        self._progress_shown = None  # likewise display_splash() and display_progress()
+        self.ghosting.record(mode)
+        if self._usage is not None:
+            self._usage.record(mode)
+        if current_interaction() is not None:
+            # The refresh has finished; the driver timed it
+            duration_ms = self.get_stats()["last_refresh_ms"] if hasattr(self._lib, "display_get_stats") else 0
+            details = {"tag": tag} if tag is not None else {}
+            record_stage(STAGE_DISPLAY, duration_ms, mode=DisplayMode(mode).name, **details)
     
     def display_image(self, image: Union[str, bytes], mode: DisplayMode = DisplayMode.FULL, rotate: bool = False, flip_horizontal: bool = False, invert_colors: bool = False, src_width: int = None, src_height: int = None) -> None:
         """
@@ -165,12 +968,14 @@ class Display:
         Raises:
             DisplayError: If display operation fails
         """
-        if not self._initialized:
-            raise DisplayError("Display not initialized. Call initialize() first.")
+        self._require_initialized()
         
+        mode = self._refresh_mode(mode)
         if isinstance(image, str):
             # PNG file path
             self._display_png(image, mode, rotate, flip_horizontal, invert_colors)
+            self._record_refresh(mode)
+            self._audit("update", image, mode=DisplayMode(mode).name)
         elif isinstance(image, (bytes, bytearray)):
             # Raw image data
             raw_data = bytes(image)
@@ -191,116 +996,1230 @@ class Display:
                     raw_data = invert_bitpacked_colors(raw_data)
             
             self._display_raw(raw_data, mode)
+            self._record_refresh(mode)
+            self._audit("update", "raw", mode=DisplayMode(mode).name)
         else:
             raise DisplayError(f"Invalid image type: {type(image)}. Expected str or bytes.")
     
-    def _display_png(self, filename: str, mode: DisplayMode, rotate: bool = False, flip_horizontal: bool = False, invert_colors: bool = False) -> None:
-        """Display a PNG image file."""
-        if not os.path.exists(filename):
-            raise DisplayError(f"PNG file not found: {filename}")
+    def display_image_auto(self, image: Union[str, bytes]) -> DisplayMode:
+        """
+        Display an image, choosing the refresh mode from how much changed.
         
-        if rotate or flip_horizontal or invert_colors:
-            # For PNG transformations, convert to raw data first
-            raw_data = self.convert_png_to_raw(filename)
-            # Assume PNG is 250x128 landscape format when transforming
+        The frame is compared with the last one displayed: a partial refresh is
+        used when few enough pixels changed (see set_auto_threshold()), a full
+        refresh otherwise and for the first frame after initialization. The
+        ghosting policy can still turn a partial refresh into a full one, and
+        while thermally throttled the refresh is partial.
+        
+        Args:
+            image: PNG file path or raw 1-bit image data in the current frame
             
-            # Apply transformations in DistillerGUI order: flip, rotate, then invert colors
-            if flip_horizontal:
-                raw_data = flip_bitpacked_horizontal(raw_data, 250, 128)
-                
-            if rotate:
-                raw_data = rotate_bitpacked_ccw_90(raw_data, 250, 128)
-                
-            if invert_colors:
-                raw_data = invert_bitpacked_colors(raw_data)
-                
-            self._display_raw(raw_data, mode)
+        Returns:
+            The refresh mode used
+            
+        Raises:
+            DisplayError: If display operation fails
+        """
+        self._require_initialized()
+        if not hasattr(self._lib, "display_image_auto"):
+            raise DisplayError("The display library predates display_image_auto(); rebuild or reinstall the SDK")
+        
+        data = self.convert_png_to_raw(image) if isinstance(image, str) else bytes(image)
+        if len(data) != self.ARRAY_SIZE:
+            raise DisplayError(f"Data must be exactly {self.ARRAY_SIZE} bytes, got {len(data)}")
+        
+        data_array = (ctypes.c_ubyte * len(data))(*data)
+        mode = self._refresh_mode(DisplayMode.PARTIAL)
+        if mode == DisplayMode.FULL or self.thermal.throttled:
+            success = self._lib.display_image_raw(data_array, int(mode))
         else:
-            # Direct PNG display (must be 128x250)
-            filename_bytes = filename.encode('utf-8')
-            success = self._lib.display_image_png(filename_bytes, int(mode))
-            if not success:
-                raise DisplayError(f"Failed to display PNG image: {filename}")
+            mode_used = ctypes.c_int(int(DisplayMode.FULL))
+            success = self._lib.display_image_auto(data_array, ctypes.byref(mode_used))
+            mode = DisplayMode(mode_used.value)
+        if not success:
+            raise self._failure("Failed to display raw image data")
+        self._record_refresh(mode)
+        self._audit("update", image if isinstance(image, str) else "raw", mode=mode.name, auto=True)
+        return mode
     
-    def _display_raw(self, data: bytes, mode: DisplayMode) -> None:
-        """Display raw 1-bit image data."""
+    def write_plane(self, plane: RamPlane, data: bytes) -> None:
+        """
+        Write a frame to one of the controller's RAM planes without refreshing.
+        
+        Writing RamPlane.NEW stages the frame for flip(). Writing RamPlane.OLD
+        sets what the next partial refresh compares against, e.g. to force
+        pixels that look unchanged to be driven again.
+        
+        Args:
+            plane: RAM plane to write
+            data: Raw 1-bit image data in the current frame
+            
+        Raises:
+            DisplayError: If the frame could not be written
+        """
+        self._require_initialized()
+        if not hasattr(self._lib, "display_write_plane"):
+            raise DisplayError("The display library predates display_write_plane(); rebuild or reinstall the SDK")
         if len(data) != self.ARRAY_SIZE:
             raise DisplayError(f"Data must be exactly {self.ARRAY_SIZE} bytes, got {len(data)}")
         
-        # Convert bytes to ctypes array
         data_array = (ctypes.c_ubyte * len(data))(*data)
+        if not self._lib.display_write_plane(int(RamPlane(plane)), data_array):
+            raise self._failure(f"Failed to write the {RamPlane(plane).name.lower()} RAM plane")
+    
+    def stage_frame(self, data: bytes) -> None:
+        """
+        Send the next frame to the panel ahead of time, for flip() to show.
         
-        success = self._lib.display_image_raw(data_array, int(mode))
-        if not success:
-            raise DisplayError("Failed to display raw image data")
+        Sending a frame takes a good part of a partial update; staging it while
+        the application is idle, e.g. the menu entry below the cursor, leaves
+        only the refresh for when it is needed. Any other update, wake() or
+        power_on() discards the staged frame.
+        
+        Args:
+            data: Raw 1-bit image data in the current frame
+            
+        Raises:
+            DisplayError: If the frame could not be written
+        """
+        self.write_plane(RamPlane.NEW, data)
     
-    def clear(self) -> None:
+    def flip(self, mode: DisplayMode = DisplayMode.PARTIAL) -> DisplayMode:
         """
-        Clear the display (set to white).
+        Show the frame staged by stage_frame(), without sending it again.
         
+        Args:
+            mode: Display refresh mode; the ghosting policy and thermal
+                  throttling apply as to any update
+            
+        Returns:
+            The refresh mode used
+            
         Raises:
-            DisplayError: If clear operation fails
+            DisplayError: If no frame is staged or the refresh fails
         """
-        if not self._initialized:
-            raise DisplayError("Display not initialized. Call initialize() first.")
+        self._require_initialized()
+        if not hasattr(self._lib, "display_flip"):
+            raise DisplayError("The display library predates display_flip(); rebuild or reinstall the SDK")
         
-        success = self._lib.display_clear()
-        if not success:
-            raise DisplayError("Failed to clear display")
+        mode = self._refresh_mode(mode)
+        if not self._lib.display_flip(int(mode)):
+            raise self._failure("Failed to flip to the staged frame")
+        self._record_refresh(mode)
+        self._audit("update", "staged", mode=DisplayMode(mode).name)
+        return mode
     
-    def sleep(self) -> None:
-        """Put display to sleep for power saving."""
-        if self._initialized:
-            self._lib.display_sleep()
+    def set_auto_threshold(self, max_pixels: int) -> None:
+        """
+        Set the most changed pixels display_image_auto() refreshes partially.
+        
+        Args:
+            max_pixels: Changed pixel count; 0 always refreshes fully. The
+                        default is 1/8 of the panel's pixels.
+        """
+        if max_pixels < 0:
+            raise DisplayError(f"Invalid auto threshold {max_pixels}")
+        if not hasattr(self._lib, "display_set_auto_threshold"):
+            raise DisplayError("The display library predates display_image_auto(); rebuild or reinstall the SDK")
+        self._lib.display_set_auto_threshold(max_pixels)
     
-    def get_dimensions(self) -> Tuple[int, int]:
+    def get_framebuffer(self, native: bool = False) -> Optional[bytes]:
         """
-        Get display dimensions.
+        Read back the last frame sent to the panel.
         
+        Args:
+            native: Return the frame in the panel's own orientation instead of
+                    the caller's frame under the active rotation
+            
         Returns:
-            Tuple of (width, height) in pixels
+            Packed 1-bit data as the panel shows it (after any color
+            inversion), or None if nothing was displayed since initialization
+            or the last update failed
         """
-        width = c_uint32()
-        height = c_uint32()
-        self._lib.display_get_dimensions(ctypes.byref(width), ctypes.byref(height))
-        return (width.value, height.value)
+        if not self._initialized:
+            raise DisplayError("Display not initialized. Call initialize() first.")
+        if not hasattr(self._lib, "display_get_framebuffer"):
+            raise DisplayError("The display library predates display_get_framebuffer(); rebuild or reinstall the SDK")
+        
+        out = (ctypes.c_ubyte * self.ARRAY_SIZE)()
+        if not self._lib.display_get_framebuffer(out):
+            return None
+        frame = bytes(out)
+        rotation = self.get_rotation()
+        if native or rotation == RotationMode.NONE:
+            return frame
+        # Undo the rotation applied on the way to the panel
+        inverse = {RotationMode.CCW_90: RotationMode.CW_90, RotationMode.CW_90: RotationMode.CCW_90,
+                   RotationMode.ROTATE_180: RotationMode.ROTATE_180}[rotation]
+        return rotate_bitpacked(frame, self.WIDTH, self.HEIGHT, inverse)
     
-    def convert_png_to_raw(self, filename: str) -> bytes:
+    def save_screenshot(self, path: str, native: bool = False) -> Tuple[int, int]:
         """
-        Convert PNG file to raw 1-bit data.
+        Save the last frame sent to the panel as a 1-bit PNG.
         
         Args:
-            filename: Path to PNG file (must be exactly 128x250 pixels)
+            path: Output PNG file path
+            native: Save in the panel's own orientation (see get_framebuffer())
             
         Returns:
-            Raw 1-bit packed image data (4000 bytes)
+            Tuple of (width, height) of the saved image
             
         Raises:
-            DisplayError: If conversion fails
+            DisplayError: If no frame is available or the file cannot be written
         """
-        if not os.path.exists(filename):
-            raise DisplayError(f"PNG file not found: {filename}")
+        from .pngcodec import encode_png_1bit
         
-        # Create output buffer
+        frame = self.get_framebuffer(native)
+        if frame is None:
+            raise DisplayError("No frame has been displayed since initialization")
+        width, height = (self.WIDTH, self.HEIGHT) if native else self.get_dimensions()
+        try:
+            with open(path, "wb") as f:
+                f.write(encode_png_1bit(frame, width, height))
+        except OSError as e:
+            raise DisplayError(f"Failed to write screenshot {path}: {e}")
+        return (width, height)
+    
+    def begin_frame(self, clear: bool = False) -> "Framebuffer":
+        """
+        Start composing a frame in a back buffer.
+        
+        The buffer starts as a copy of the last frame committed with
+        end_frame(), or white if another update came in between or clear is
+        set. Draw into it with the Framebuffer primitives or write its data
+        directly; nothing reaches the panel until end_frame().
+        
+        Returns:
+            Back buffer in the current frame (see get_dimensions())
+            
+        Raises:
+            DisplayError: If a frame is already open
+        """
+        from .graphics import Framebuffer
+        if self._back is not None:
+            raise DisplayError("A frame is already open; call end_frame() or discard_frame() first")
+        width, height = self.get_dimensions()
+        front = self._front if self._front is not None and len(self._front) == self.ARRAY_SIZE else None
+        self._back = Framebuffer(width, height, data=None if clear or front is None else bytearray(front))
+        return self._back
+    
+    def end_frame(self, mode: Optional[DisplayMode] = None) -> Optional[DisplayMode]:
+        """
+        Push the frame composed since begin_frame() to the panel.
+        
+        An unchanged frame is not refreshed at all. Otherwise the refresh mode
+        is chosen from the changed pixel count as in display_image_auto(),
+        unless mode is given.
+        
+        Args:
+            mode: Refresh mode to force, or None to choose from the delta
+            
+        Returns:
+            The refresh mode used, or None if nothing changed
+            
+        Raises:
+            DisplayError: If no frame is open or the update fails
+        """
+        if self._back is None:
+            raise DisplayError("No frame open; call begin_frame() first")
+        frame, self._back = self._back.to_bytes(), None
+        if frame == self._front:
+            return None
+        return self._push_frame(frame, mode)
+    
+    def _push_frame(self, frame: bytes, mode: Optional[DisplayMode]) -> DisplayMode:
+        """Refresh with frame, choosing the mode from the delta unless given, and compose on it next."""
+        if mode is None and hasattr(self._lib, "display_image_auto"):
+            used = self.display_image_auto(frame)
+        else:
+            used = self._refresh_mode(DisplayMode.FULL if mode is None else mode, wait=False)
+            self.display_image(frame, used)
+        self._front = frame
+        return used
+    
+    def discard_frame(self) -> None:
+        """Drop the frame composed since begin_frame() without refreshing."""
+        self._back = None
+    
+    def save_screen(self) -> ScreenSnapshot:
+        """
+        Capture what the screen shows, e.g. before a modal dialog covers it.
+        
+        Returns:
+            Snapshot whose restore() puts the content back
+            
+        Raises:
+            DisplayError: If nothing has been displayed yet
+        """
+        self._require_initialized()
+        frame = self.get_framebuffer() if hasattr(self._lib, "display_get_framebuffer") else self._front
+        if frame is None:
+            raise DisplayError("No frame has been displayed since initialization")
+        return ScreenSnapshot(self, frame, self.get_rotation())
+    
+    def restore_screen(self, snapshot: ScreenSnapshot, mode: Optional[DisplayMode] = None) -> Optional[DisplayMode]:
+        """
+        Put content captured with save_screen() back on the screen.
+        
+        Only what changed since the capture, typically the dialog's area, is
+        counted, so the refresh is usually partial; an unchanged screen is not
+        refreshed at all. The next begin_frame() starts from the restored
+        content.
+        
+        Args:
+            snapshot: Capture from save_screen() of this display
+            mode: Refresh mode to force, or None to choose from the delta
+            
+        Returns:
+            The refresh mode used, or None if nothing changed
+            
+        Raises:
+            DisplayError: If a frame is open, the snapshot is another display's
+                or the rotation changed since it was taken
+        """
+        if snapshot.display is not self:
+            raise DisplayError("The snapshot was taken on another display")
+        if self._back is not None:
+            raise DisplayError("A frame is open; call end_frame() or discard_frame() first")
+        if snapshot.rotation != self.get_rotation():
+            raise DisplayError(f"The snapshot was taken at rotation {snapshot.rotation.name}, "
+                               f"not {self.get_rotation().name}")
+        current = self.get_framebuffer() if hasattr(self._lib, "display_get_framebuffer") else self._front
+        if snapshot.frame == current:
+            self._front = snapshot.frame
+            return None
+        return self._push_frame(snapshot.frame, mode)
+    
+    @contextmanager
+    def frame(self, clear: bool = False, mode: Optional[DisplayMode] = None):
+        """
+        Compose a frame in a with block: begin_frame() on entry, end_frame()
+        on normal exit and discard_frame() if the block raises.
+        """
+        back = self.begin_frame(clear)
+        try:
+            yield back
+        except BaseException:
+            self.discard_frame()
+            raise
+        self.end_frame(mode)
+    
+    def display_splash(self, image_path: Optional[str] = None, title: Optional[str] = None) -> None:
+        """
+        Show a splash screen with a full refresh, as the background for
+        display_progress().
+        
+        Args:
+            image_path: PNG file in the current frame's dimensions, or None
+                        for a white screen
+            title: Optional text centered on the screen
+            
+        Raises:
+            DisplayError: If the image cannot be loaded or display fails
+        """
+        from .graphics import Framebuffer
+        self._require_initialized()
+        
+        width, height = self.get_dimensions()
+        data = bytearray(self.convert_png_to_raw(image_path)) if image_path else None
+        fb = Framebuffer(width, height, data=data)
+        if title:
+            scale = _largest_text_scale(title, width - 8, height // 6)
+            fb.draw_text((width - _text_width(title, scale)) // 2, (height - FONT_HEIGHT * scale) // 3,
+                         title, scale=scale)
+        frame = fb.to_bytes()
+        
+        mode = self._refresh_mode(DisplayMode.FULL)
+        self._display_raw(frame, mode)
+        self._record_refresh(mode)
+        self._progress_base = self._progress_shown = frame
+        self._audit("update", image_path or "splash", mode=DisplayMode(mode).name)
+    
+    def display_progress(self, percent: float, label: Optional[str] = None) -> Optional[DisplayMode]:
+        """
+        Draw a progress bar along the bottom of the screen.
+        
+        The bar is drawn over the last splash screen (or a white screen). While
+        the splash or an earlier progress frame is still shown, only the bar
+        region changes, so it is updated with a partial refresh without
+        flashing the rest of the screen. A call that would not change any
+        pixel does not refresh at all.
+        
+        Args:
+            percent: Progress from 0 to 100 (clamped)
+            label: Optional text above the bar; the percentage is appended
+            
+        Returns:
+            The refresh mode used, or None if nothing changed
+            
+        Raises:
+            DisplayError: If display fails
+        """
+        from .graphics import Color, Framebuffer
+        self._require_initialized()
+        
+        width, height = self.get_dimensions()
+        fb = Framebuffer(width, height, data=self._progress_base)
+        percent = max(0.0, min(float(percent), 100.0))
+        margin = max(width, height) // 25
+        bar_height = max(height // 20, 6)
+        bar_y = height - margin - bar_height
+        text = f"{label} {percent:.0f}%" if label else f"{percent:.0f}%"
+        text_y = bar_y - FONT_HEIGHT - 4
+        
+        # Clear the bar region, then draw the text, the outline and the filled part
+        fb.draw_rect(0, text_y - 2, width, height - text_y + 2, color=Color.WHITE, fill=True)
+        fb.draw_text(margin, text_y, text[:max((width - 2 * margin) // (FONT_WIDTH + 1), 0)])
+        fb.draw_rect(margin, bar_y, width - 2 * margin, bar_height)
+        fb.draw_rect(margin, bar_y, int((width - 2 * margin) * percent / 100), bar_height, fill=True)
+        frame = fb.to_bytes()
+        
+        if frame == self._progress_shown:
+            return None
+        mode = DisplayMode.PARTIAL if self._progress_shown is not None else DisplayMode.FULL
+        mode = self._refresh_mode(mode)
+        self._display_raw(frame, mode)
+        self._record_refresh(mode)
+        self._progress_shown = frame
+        self._audit("update", "progress", mode=DisplayMode(mode).name, percent=round(percent, 1))
+        return mode
+    
+    def show_file(self, filename: str, options: Optional[ProcessingOptions] = None,
+                  mode: DisplayMode = DisplayMode.FULL) -> None:
+        """
+        Initialize (if needed), process and display a PNG file in a single library call.
+        
+        Args:
+            filename: Path to PNG file (128x250, or 250x128 when options.rotate is set)
+            options: Processing options, or None for the configured threshold
+            mode: Display refresh mode
+            
+        Raises:
+            DisplayError: With the failing stage and message if any step fails
+        """
+        if not os.path.exists(filename):
+            raise DisplayError(f"PNG file not found: {filename}")
+        if self._idle is not None:
+            self._idle.wake()
+        
+        options_ptr = ctypes.byref(self._processing_options(options))
+        mode = self._refresh_mode(mode)
+        result = self._lib.display_show_file(filename.encode('utf-8'), options_ptr, int(mode))
+        
+        status = DisplayStatus(result.status)
+        if status in _SHOW_FILE_INITIALIZED:
+            self._initialized = True
+        if status != DisplayStatus.OK:
+            message = result.message.decode('utf-8', errors='replace')
+            error = DisplayTimeoutError if status == DisplayStatus.TIMEOUT else DisplayError
+            raise error(f"{status.name}: {message}")
+        self._start_usage_counter()
+        self._record_refresh(mode)
+        self._audit("update", filename, mode=DisplayMode(mode).name)
+    
+    def _display_png(self, filename: str, mode: DisplayMode, rotate: bool = False, flip_horizontal: bool = False, invert_colors: bool = False) -> None:
+        """Display a PNG image file."""
+        if not os.path.exists(filename):
+            raise DisplayError(f"PNG file not found: {filename}")
+        
+        if rotate or flip_horizontal or invert_colors:
+            # For PNG transformations, convert to raw data first
+            raw_data = self.convert_png_to_raw(filename)
+            # Assume PNG is 250x128 landscape format when transforming
+            
+            # Apply transformations in DistillerGUI order: flip, rotate, then invert colors
+            if flip_horizontal:
+                raw_data = flip_bitpacked_horizontal(raw_data, 250, 128)
+                
+            if rotate:
+                raw_data = rotate_bitpacked_ccw_90(raw_data, 250, 128)
+                
+            if invert_colors:
+                raw_data = invert_bitpacked_colors(raw_data)
+                
+            self._display_raw(raw_data, mode)
+        else:
+            # Direct PNG display (must be 128x250)
+            filename_bytes = filename.encode('utf-8')
+            success = self._lib.display_image_png(filename_bytes, int(mode))
+            if not success:
+                raise self._failure(f"Failed to display PNG image: {filename}")
+    
+    def _display_raw(self, data: bytes, mode: DisplayMode) -> None:
+        """Display raw 1-bit image data."""
+        if len(data) != self.ARRAY_SIZE:
+            raise DisplayError(f"Data must be exactly {self.ARRAY_SIZE} bytes, got {len(data)}")
+        
+        # Convert bytes to ctypes array
+        data_array = (ctypes.c_ubyte * len(data))(*data)
+        
+        success = self._lib.display_image_raw(data_array, int(mode))
+        if not success:
+            raise self._failure("Failed to display raw image data")
+    
+    def display_image_bytes(self, data: bytes, mode: DisplayMode = DisplayMode.FULL) -> None:
+        """
+        Display an encoded image (PNG or JPEG) held in memory.
+        
+        The image must be exactly 128x250 pixels. No temporary file is written.
+        
+        Args:
+            data: Encoded image bytes
+            mode: Display refresh mode
+            
+        Raises:
+            DisplayError: If decoding or display fails
+        """
+        self._require_initialized()
+        
+        encoded = _ensure_decodable_bytes(data)
+        buf = (ctypes.c_ubyte * len(encoded)).from_buffer_copy(encoded)
+        mode = self._refresh_mode(mode)
+        
+        success = self._lib.display_image_from_memory(buf, len(encoded), int(mode))
+        if not success:
+            raise self._failure("Failed to display image from memory")
+        self._record_refresh(mode)
+        self._audit("update", "bytes", mode=DisplayMode(mode).name, size=len(data))
+    
+    def display_pixels(self, pixels, width: int, height: int,
+                       pixel_format: PixelFormat = PixelFormat.RGB888,
+                       options: Optional[ProcessingOptions] = None,
+                       mode: DisplayMode = DisplayMode.FULL) -> None:
+        """
+        Display a raw pixel buffer without any image-format encode/decode.
+        
+        Args:
+            pixels: Tightly packed pixel rows (bytes, bytearray, memoryview or numpy array)
+            width: Source width in pixels (128, or 250 when options.rotate is set)
+            height: Source height in pixels (250, or 128 when options.rotate is set)
+            pixel_format: Layout of the buffer
+            options: Processing options, or None for the configured threshold
+            mode: Display refresh mode
+            
+        Raises:
+            DisplayError: If the buffer is invalid or display fails
+        """
+        self._require_initialized()
+        
+        buf = _pixel_buffer(pixels, width, height, pixel_format)
+        options_ptr = ctypes.byref(self._processing_options(options))
+        mode = self._refresh_mode(mode)
+        
+        success = self._lib.display_pixels(buf, width, height, int(pixel_format), options_ptr, int(mode))
+        if not success:
+            raise self._failure("Failed to display pixel buffer")
+        self._record_refresh(mode)
+        self._audit("update", "pixels", mode=DisplayMode(mode).name,
+                    width=width, height=height, format=PixelFormat(pixel_format).name)
+    
+    def clear(self) -> None:
+        """
+        Clear the display (set to white).
+        
+        Raises:
+            DisplayError: If clear operation fails
+        """
+        self._require_initialized()
+        
+        success = self._lib.display_clear()
+        if not success:
+            raise self._failure("Failed to clear display")
+        self._record_refresh(DisplayMode.FULL)
+        self._audit("clear")
+    
+    def deghost(self, cycles: int = 2) -> None:
+        """
+        Clear accumulated ghosting by flushing the panel black and white.
+        
+        Each cycle is a full refresh to black followed by one to white, which
+        drives every pixel through both extremes. The panel is left white, so
+        redraw the content afterwards.
+        
+        Args:
+            cycles: Number of black/white cycles
+            
+        Raises:
+            DisplayError: If an update fails
+        """
+        self._require_initialized()
+        if cycles < 1:
+            raise DisplayError(f"Invalid deghost cycle count {cycles}")
+        
+        for _ in range(cycles):
+            for value in (0x00, 0xFF):
+                self._display_raw(bytes([value]) * self.ARRAY_SIZE, DisplayMode.FULL)
+                self._record_refresh(DisplayMode.FULL)
+        self._audit("deghost", cycles=cycles)
+    
+    def abort(self) -> None:
+        """
+        Abort the update in flight, if any, and refuse further updates.
+        
+        Safe to call from another thread while an update is running: the
+        update raises DisplayError once the panel stops being waited on, as
+        do later updates until wake(). Meant for imminent power loss, where a
+        refresh must not be left waiting out its full duration.
+        """
+        if not hasattr(self._lib, "display_abort"):
+            raise DisplayError("The display library predates display_abort(); rebuild or reinstall the SDK")
+        self._lib.display_abort()
+        audit(AuditCategory.POWER, "display_abort")
+    
+    def emergency_frame(self, image: Optional[bytes] = None) -> None:
+        """
+        Show a final frame ahead of any pending update and put the panel to sleep.
+        
+        Aborts the update in flight, shows image with a full refresh as soon
+        as it has stopped (before any update waiting to run), then enters deep
+        sleep so the image stays without power. Later updates fail until
+        wake(). The ghosting policy is bypassed, and usage counts are flushed.
+        
+        Args:
+            image: Raw 1-bit image data in the current frame, or None to only
+                   stop updates and put the panel to sleep
+            
+        Raises:
+            DisplayError: If the frame could not be shown
+        """
+        if not hasattr(self._lib, "display_emergency_frame"):
+            raise DisplayError("The display library predates display_abort(); rebuild or reinstall the SDK")
+        data_array = None
+        if image is not None:
+            if len(image) != self.ARRAY_SIZE:
+                raise DisplayError(f"Data must be exactly {self.ARRAY_SIZE} bytes, got {len(image)}")
+            data_array = (ctypes.c_ubyte * len(image))(*image)
+        success = self._lib.display_emergency_frame(data_array)
+        if image is not None and success:
+            self._record_refresh(DisplayMode.FULL)
+        if self._usage is not None:
+            self._usage.flush()
+        audit(AuditCategory.POWER, "emergency_frame", shown=bool(image is not None and success))
+        if not success:
+            raise self._failure("Failed to display emergency frame")
+    
+    def sleep(self) -> None:
+        """
+        Put display to sleep for power saving.
+        
+        The image stays on the panel. The next update wakes the panel by
+        itself and keeps the last frame as the reference for partial
+        refreshes, so calling wake() first is optional.
+        """
+        if self._initialized:
+            self._lib.display_sleep()
+            if self._usage is not None:
+                self._usage.flush()
+            if self._idle is not None:
+                self._idle.slept()
+            audit(AuditCategory.POWER, "display_sleep")
+    
+    def wake(self) -> None:
+        """
+        Wake the display after sleep(), and resume updates after abort().
+        
+        The panel only leaves deep sleep through a hardware reset, so this
+        re-runs the initialization sequence. The last frame is restored as
+        the partial-refresh reference; libraries built before display_wake()
+        reopen the display instead and lose it.
+        
+        Raises:
+            DisplayError: If the panel cannot be woken, or its power is off
+        """
+        if self._powered_off:
+            raise DisplayError("Panel power is off. Call power_on() first.")
+        if self._initialized and hasattr(self._lib, "display_wake"):
+            if not self._lib.display_wake():
+                raise self._failure("Failed to wake display")
+            audit(AuditCategory.POWER, "display_wake")
+        else:
+            if self._initialized:
+                self._lib.display_cleanup()
+                self._initialized = False
+            self.initialize()
+        if self._idle is not None:
+            self._idle.woken()
+    
+    def is_asleep(self) -> bool:
+        """Check if the panel is in deep sleep, i.e. after sleep() and before the next update."""
+        if hasattr(self._lib, "display_is_asleep"):
+            return bool(self._lib.display_is_asleep())
+        return self._idle is not None and self._idle.asleep
+    
+    def power_off(self, rail: "PanelPowerRail") -> None:
+        """
+        Cut the panel's power rail for zero-power sleep, e.g. between rare
+        updates of a battery-powered device. The image stays on the panel.
+        
+        The panel enters deep sleep, then the SPI device and GPIO lines are
+        released so none back-powers the controller, then the rail is cut.
+        Updates fail until power_on().
+        
+        Args:
+            rail: Panel power control, e.g. PanelPowerRail(link) for the SAM MCU
+            
+        Raises:
+            DisplayError: If the rail could not be cut; the display is closed
+                          all the same, and power_on() recovers
+        """
+        from ..sam.link import SamLinkError
+        frame = None
+        if self._initialized and hasattr(self._lib, "display_get_framebuffer"):
+            frame = self.get_framebuffer(native=True)
+        self.sleep()
+        if self._initialized:
+            self._lib.display_cleanup()
+            self._initialized = False
+        self._powered_off = True
+        self._unpowered_frame = frame
+        try:
+            rail.off()
+        except SamLinkError as e:
+            raise DisplayError(f"Cannot cut the panel power: {e}")
+        audit(AuditCategory.POWER, "display_power_off", kept_frame=frame is not None)
+    
+    def power_on(self, rail: "PanelPowerRail") -> None:
+        """
+        Restore the panel's power rail after power_off() and initialize the
+        panel again. The frame left on the panel is restored as the
+        partial-refresh reference, so the next update can be partial.
+        
+        Args:
+            rail: The panel power control given to power_off()
+            
+        Raises:
+            DisplayError: If the rail could not be restored or the panel initialized
+        """
+        from ..sam.link import SamLinkError
+        try:
+            rail.on()
+        except SamLinkError as e:
+            raise DisplayError(f"Cannot restore the panel power: {e}")
+        self._powered_off = False
+        self.initialize()
+        frame, self._unpowered_frame = self._unpowered_frame, None
+        restored = frame is not None and hasattr(self._lib, "display_restore_frame")
+        if restored and not self._lib.display_restore_frame((ctypes.c_ubyte * len(frame)).from_buffer_copy(frame)):
+            raise self._failure("Failed to restore the frame after power on")
+        if self._idle is not None:
+            self._idle.woken()
+        audit(AuditCategory.POWER, "display_power_on", restored_frame=restored)
+    
+    def is_powered_off(self) -> bool:
+        """Check if power_off() cut the panel's power and power_on() has not restored it."""
+        return self._powered_off
+    
+    def set_idle_policy(self, timeout_s: Optional[float], action: "IdleAction" = None,
+                        image: Optional[Union[str, bytes]] = None) -> None:
+        """
+        Put the panel to sleep after a period without updates.
+        
+        Once timeout_s seconds pass without an update, the panel is cleared or
+        shows image if the action asks for it, then enters deep sleep. The
+        next update (or sleep() and wake() directly) wakes it again, so
+        callers need not track the sleep. The timeout should be longer than
+        any single refresh.
+        
+        Args:
+            timeout_s: Idle seconds before acting, or None to disable
+            action: IdleAction.SLEEP (default), CLEAR or SHOW_IMAGE
+            image: PNG path or raw 1-bit data in the current frame, for SHOW_IMAGE
+            
+        Raises:
+            DisplayError: If the timeout or image is invalid
+        """
+        from .idle import IdleAction, IdlePolicy
+        action = IdleAction.SLEEP if action is None else IdleAction(action)
+        if timeout_s is not None and timeout_s <= 0:
+            raise DisplayError(f"Invalid idle timeout {timeout_s}")
+        if timeout_s is not None and action == IdleAction.SHOW_IMAGE:
+            if isinstance(image, str):
+                if not os.path.exists(image):
+                    raise DisplayError(f"PNG file not found: {image}")
+            elif not isinstance(image, (bytes, bytearray)) or len(image) != self.ARRAY_SIZE:
+                raise DisplayError(f"Idle image must be a PNG path or {self.ARRAY_SIZE} bytes of raw data")
+        
+        previous, self._idle = self._idle, None
+        asleep = previous is not None and previous.asleep
+        if previous is not None:
+            previous.stop()
+        if timeout_s is None:
+            if asleep:
+                self.wake()
+            return
+        self._idle = IdlePolicy(self, timeout_s, action, image)
+        if asleep:
+            self._idle.slept()
+        self._idle.start()
+    
+    def reload_config(self, path: Optional[str] = None) -> Tuple[List[str], List[str]]:
+        """
+        Reload this display's settings from the config file and apply those
+        that can change while the panel is open (reload.LIVE_SETTINGS):
+        rotation, the processing profile, ghosting, auto-partial and thermal
+        limits. The others are reported and left for the next Display.
+        
+        Emits CONFIG_RELOADED to set_event_callback() when anything changed.
+        
+        Args:
+            path: Config file; if None, the default one (see config.load_config())
+            
+        Returns:
+            The settings applied, and the changed ones that need the display reopened
+            
+        Raises:
+            DisplayError: If the file has an invalid setting; nothing is applied then
+        """
+        from dataclasses import replace
+        from .config import config_values, load_config
+        from .reload import LIVE_SETTINGS
+        
+        current = self.config
+        config = load_config(path, current.name)
+        old, new = config_values(current), config_values(config)
+        changed = sorted(key for key in old.keys() | new.keys() if old.get(key) != new.get(key))
+        if config.custom_panel != current.custom_panel:
+            changed.append("custom_panel")
+        applied = [key for key in changed if key in LIVE_SETTINGS]
+        pending = [key for key in changed if key not in LIVE_SETTINGS]
+        if not changed:
+            return applied, pending
+        
+        if config.rotation != current.rotation:
+            self.set_rotation(config.rotation)
+        if config.auto_partial_max_pixels != current.auto_partial_max_pixels:
+            self.set_auto_threshold(config.auto_partial_max_pixels if config.auto_partial_max_pixels is not None
+                                    else self.WIDTH * self.HEIGHT // 8)
+        self.ghosting.full_every = config.full_refresh_every
+        self.ghosting.full_interval_s = config.full_refresh_interval_s
+        self.thermal.soc_max_c = config.thermal_soc_max_c
+        self.thermal.ambient_max_c = config.thermal_ambient_max_c
+        self.thermal.ambient_min_c = config.thermal_ambient_min_c
+        self.thermal.interval_s = config.thermal_interval_s
+        self.config = replace(
+            current, rotation=config.rotation, image_options=config.image_options,
+            image_cache_dir=config.image_cache_dir, image_cache_max_mb=config.image_cache_max_mb,
+            full_refresh_every=config.full_refresh_every, full_refresh_interval_s=config.full_refresh_interval_s,
+            auto_partial_max_pixels=config.auto_partial_max_pixels,
+            thermal_soc_max_c=config.thermal_soc_max_c, thermal_ambient_max_c=config.thermal_ambient_max_c,
+            thermal_ambient_min_c=config.thermal_ambient_min_c, thermal_interval_s=config.thermal_interval_s)
+        
+        if pending:
+            print(f"Warning: Display settings take effect when the display is reopened: {', '.join(pending)}")
+        audit(AuditCategory.CONFIG, "reload", ", ".join(applied) or "none", pending=", ".join(pending))
+        message = f"applied {', '.join(applied) or 'nothing'}"
+        if pending:
+            message += f"; on reopen {', '.join(pending)}"
+        self._emit(DisplayEventType.CONFIG_RELOADED, message)
+        return applied, pending
+    
+    def watch_config(self, path: Optional[str] = None, sighup: bool = False,
+                     poll_interval_s: Optional[float] = None) -> None:
+        """
+        Call reload_config() whenever the config file is written or replaced,
+        until close() or unwatch_config().
+        
+        Args:
+            path: Config file; if None, the default one (see config.load_config())
+            sighup: Also reload on SIGHUP; only possible from the main thread
+            poll_interval_s: Check the file's modification time this often
+                             instead of using inotify
+            
+        Raises:
+            DisplayError: If the file's directory does not exist or SIGHUP cannot be handled
+        """
+        from .config import _config_path
+        from .reload import ConfigWatcher, ConfigWatcherError
+        
+        self.unwatch_config()
+        # Through a proxy, as for the thermal policy: the watcher thread must not keep the display alive
+        this = weakref.proxy(self)
+        watcher = ConfigWatcher(_config_path(path), lambda: this.reload_config(path))
+        if poll_interval_s is not None:
+            watcher.poll_interval_s, watcher.use_inotify = poll_interval_s, False
+        try:
+            if sighup:
+                watcher.install_sighup()
+            watcher.start()
+        except ConfigWatcherError as e:
+            watcher.stop()
+            raise DisplayError(str(e))
+        self._config_watcher = watcher
+    
+    def unwatch_config(self) -> None:
+        """Stop the reloading started by watch_config()."""
+        if self._config_watcher is not None:
+            self._config_watcher.stop()
+            self._config_watcher = None
+    
+    def set_rotation(self, rotation: RotationMode) -> None:
+        """
+        Set the rotation applied to every frame before it reaches the panel.
+        
+        With a 90-degree rotation, images and raw data are supplied in a
+        250x128 landscape frame; get_dimensions() reports the swapped size.
+        
+        Args:
+            rotation: Rotation from the caller's frame to the panel
+            
+        Raises:
+            DisplayError: If the rotation is invalid
+        """
+        previous = self._lib.display_get_rotation()
+        if not self._lib.display_set_rotation(int(rotation)):
+            raise DisplayError(f"Invalid rotation: {rotation}")
+        if previous != int(rotation):
+            self._progress_base = None  # drawn for the old frame dimensions
+            audit(AuditCategory.CONFIG, "rotation", RotationMode(rotation).name)
+    
+    def set_lut(self, kind: LutKind, lut: Union[LutPreset, bytes, None]) -> None:
+        """
+        Replace the waveform the controller drives full or partial refreshes with.
+        
+        A faster waveform trades ghosting and contrast for speed, e.g.
+        set_lut(LutKind.PARTIAL, LutPreset.FAST_REFRESH) for sub-second menu
+        updates. The LUT is written before every refresh that uses it, so it
+        survives sleep and re-initialization; a wrong one can leave the panel
+        unreadable until a refresh with the OTP waveform.
+        
+        Args:
+            kind: Refreshes to use the waveform for
+            lut: A LutPreset, LUT_SIZE bytes of SSD1680 waveform (command
+                 0x32), LUT_SIZE_VOLTAGES bytes that also set the voltages,
+                 or None (like LutPreset.OTP) to restore the panel's own
+            
+        Raises:
+            DisplayError: If the LUT is invalid or the library predates LUT overrides
+        """
+        if not hasattr(self._lib, "display_set_lut"):
+            raise DisplayError("The display library predates display_set_lut(); rebuild or reinstall the SDK")
+        if lut is None or isinstance(lut, LutPreset):
+            preset = LutPreset.OTP if lut is None else lut
+            if not self._lib.display_set_lut_preset(int(kind), int(preset)):
+                raise self._failure(f"Failed to set {LutKind(kind).name.lower()} LUT")
+            waveform = preset.name
+        else:
+            if len(lut) not in (LUT_SIZE, LUT_SIZE_VOLTAGES):
+                raise DisplayError(f"LUT must be {LUT_SIZE} or {LUT_SIZE_VOLTAGES} bytes, got {len(lut)}")
+            if not self._lib.display_set_lut(int(kind), (ctypes.c_ubyte * len(lut))(*lut), len(lut)):
+                raise self._failure(f"Failed to set {LutKind(kind).name.lower()} LUT")
+            waveform = "CUSTOM"
+        audit(AuditCategory.CONFIG, "lut", LutKind(kind).name, waveform=waveform)
+    
+    def set_temperature(self, celsius: Optional[float]) -> None:
+        """
+        Compensate refreshes for an external temperature reading instead of
+        the panel's internal sensor, e.g. from an ambient sensor on an
+        outdoor enclosure; feed it new readings as they come.
+        
+        The controller drives the OTP waveform for the reading's temperature
+        range. Below COLD_C, set_lut() waveforms are suspended in favour of
+        the OTP one and busy waits get twice the busy policy's timeout
+        (three times below FREEZING_C), as cold panels refresh slowly.
+        
+        Args:
+            celsius: Reading in degrees Celsius, or None to use the panel's sensor again
+            
+        Raises:
+            DisplayError: If the reading is out of range or the library
+                          predates temperature compensation
+        """
+        if not hasattr(self._lib, "display_set_temperature"):
+            raise DisplayError("The display library predates display_set_temperature(); "
+                               "rebuild or reinstall the SDK")
+        if celsius is not None and not TEMPERATURE_MIN_C <= celsius <= TEMPERATURE_MAX_C:
+            raise DisplayError(f"Temperature {celsius:g} °C out of range "
+                               f"({TEMPERATURE_MIN_C:g} to {TEMPERATURE_MAX_C:g})")
+        millicelsius = TEMPERATURE_INTERNAL if celsius is None else round(celsius * 1000)
+        if not self._lib.display_set_temperature(millicelsius):
+            raise self._failure("Failed to set temperature")
+    
+    def get_temperature(self) -> Optional[float]:
+        """
+        Get the temperature refreshes are compensated for, in degrees Celsius.
+        
+        Returns:
+            The set_temperature() reading, else the panel sensor's from the
+            last refresh, or None if the panel's link cannot read it (the
+            native library never can)
+        """
+        if not hasattr(self._lib, "display_get_temperature"):
+            return None
+        millicelsius = ctypes.c_int32()
+        if not self._lib.display_get_temperature(ctypes.byref(millicelsius)):
+            return None
+        return millicelsius.value / 1000
+    
+    def get_rotation(self) -> RotationMode:
+        """Get the active rotation."""
+        return RotationMode(self._lib.display_get_rotation())
+    
+    def get_dimensions(self) -> Tuple[int, int]:
+        """
+        Get display dimensions as seen by callers under the active rotation.
+        
+        Returns:
+            Tuple of (width, height) in pixels
+        """
+        width = c_uint32()
+        height = c_uint32()
+        self._lib.display_get_dimensions(ctypes.byref(width), ctypes.byref(height))
+        return (width.value, height.value)
+    
+    def get_info(self) -> dict:
+        """
+        Describe the display without touching the panel.
+        
+        Returns:
+            Dictionary with backend, panel, description, width and height
+            (under the active rotation), rotation, data_size, the panel's
+            full_refresh_s and partial_refresh_s, and last_update: the tag
+            (see tag_updates()), mode and epoch time of the most recent
+            update, or None before the first, and degraded: why the panel
+            could not be set up when running on the fallback backend, else None
+        """
+        width, height = self.get_dimensions()
+        return {
+            "backend": self.backend,
+            "panel": self.panel.panel_type.value,
+            "description": self.panel.description,
+            "width": width,
+            "height": height,
+            "rotation": self.get_rotation().name.lower(),
+            "data_size": self.ARRAY_SIZE,
+            "full_refresh_s": self.panel.full_refresh_s,
+            "partial_refresh_s": self.panel.partial_refresh_s,
+            "last_update": dict(self._last_update) if self._last_update is not None else None,
+            "degraded": self.degraded,
+        }
+    
+    def get_update_counts(self) -> Dict[str, int]:
+        """
+        Count the updates made since the display was opened, by tag.
+        
+        Returns:
+            Dictionary of tag (see tag_updates()) to number of updates,
+            UNTAGGED for those made without one
+        """
+        return dict(self._update_counts)
+    
+    def get_busy_stats(self) -> dict:
+        """
+        Get measured busy-line wait durations.
+        
+        Returns:
+            Dictionary with the most recent and cumulative busy time in milliseconds
+        """
+        last_ms = c_uint32()
+        total_ms = ctypes.c_uint64()
+        self._lib.display_get_busy_stats(ctypes.byref(last_ms), ctypes.byref(total_ms))
+        return {"last_busy_ms": last_ms.value, "total_busy_ms": total_ms.value}
+    
+    def get_stats(self) -> dict:
+        """
+        Get the driver's refresh counts, busy time and SPI timing since init,
+        e.g. to diagnose slow updates or track panel wear in the field.
+        
+        Returns:
+            Dictionary with full_refreshes, partial_refreshes, failed_refreshes,
+            last_refresh_ms, last_busy_ms, total_busy_ms, spi_transfers,
+            spi_bytes, total_spi_us and average_spi_us
+            
+        Raises:
+            DisplayError: If the display library predates display_get_stats()
+        """
+        if not hasattr(self._lib, "display_get_stats"):
+            raise DisplayError("The display library predates display_get_stats(); rebuild or reinstall the SDK")
+        stats = _DisplayStatsStruct(struct_size=ctypes.sizeof(_DisplayStatsStruct))
+        if not self._lib.display_get_stats(ctypes.byref(stats)):
+            raise self._failure("Failed to read display statistics")
+        return {name: getattr(stats, name) for name, _ in _DisplayStatsStruct._fields_ if name != "struct_size"}
+    
+    def set_event_callback(self, callback: Optional[Callable[[DisplayEvent], None]]) -> None:
+        """
+        Report refresh lifecycle events, e.g. to show a busy indicator or trace update latency.
+        
+        Every REFRESH_STARTED is followed by REFRESH_COMPLETED or ERROR.
+        THROTTLE_STARTED and THROTTLE_ENDED report the thermal policy holding
+        back refreshes (see thermal.py), and CONFIG_RELOADED the settings reload_config() applied.
+        The callback runs on the thread doing the update while the panel is held,
+        so it must return quickly and must not update this display.
+        
+        Args:
+            callback: Called with each DisplayEvent, or None to stop reporting
+            
+        Raises:
+            DisplayError: If the display library predates event callbacks
+        """
+        if not hasattr(self._lib, "display_set_event_callback"):
+            raise DisplayError("The display library predates display_set_event_callback(); "
+                               "rebuild or reinstall the SDK")
+        if callback is None:
+            self._lib.display_set_event_callback(None, None)
+            self._event_callback = self._event_listener = None
+            return
+        
+        def on_event(event_ref, user_data):
+            event = event_ref.contents
+            try:
+                callback(DisplayEvent(DisplayEventType(event.type), event.display_id, DisplayMode(event.mode),
+                                      event.duration_ms, DisplayErrorCode(event.error),
+                                      event.message.decode(errors="replace") if event.message else ""))
+            except Exception as e:
+                # An exception cannot propagate through the library
+                print(f"Warning: Display event callback failed: {e}")
+        
+        c_callback = _EVENT_CALLBACK(on_event)
+        self._lib.display_set_event_callback(c_callback, None)
+        self._event_callback = c_callback
+        self._event_listener = callback
+    
+    def unsafe_registers(self) -> "RegisterAccess":
+        """
+        Get raw access to the panel controller's commands and registers.
+        
+        Intended for panel vendors tuning waveforms and timings. Writes are not
+        validated and can leave the panel in a bad state until wake() re-runs
+        the init sequence.
+        
+        Returns:
+            RegisterAccess for the connected panel
+            
+        Raises:
+            DisplayError: If register access is not enabled in the configuration
+                          (register_access = yes), the backend is native, or
+                          the display is not initialized
+        """
+        from .backends import NATIVE_BACKEND
+        if not self.config.register_access:
+            raise DisplayError("Register access is disabled; set register_access = true in eink.toml "
+                               "or DISTILLER_EINK_REGISTER_ACCESS=1")
+        if self.backend == NATIVE_BACKEND:
+            raise DisplayError("Register access requires the python backend")
+        if not self._initialized:
+            raise DisplayError("Display not initialized. Call initialize() first.")
+        from .registers import RegisterAccess
+        return RegisterAccess(self._lib, self.panel)
+    
+    def get_usage(self) -> Optional["PanelUsage"]:
+        """
+        Get the panel's cumulative refresh counts across boots.
+        
+        Returns:
+            PanelUsage including refreshes not yet written to the usage file,
+            or None if usage tracking is off or the display is not initialized
+        """
+        if self._usage is None:
+            return None
+        return self._usage.get()
+    
+    def convert_png_to_raw(self, filename: str) -> bytes:
+        """
+        Convert PNG file to raw 1-bit data.
+        
+        Args:
+            filename: Path to PNG file (must be exactly 128x250 pixels)
+            
+        Returns:
+            Raw 1-bit packed image data (4000 bytes)
+            
+        Raises:
+            DisplayError: If conversion fails
+        """
+        if not os.path.exists(filename):
+            raise DisplayError(f"PNG file not found: {filename}")
+        
+        # Create output buffer
         output_data = (ctypes.c_ubyte * self.ARRAY_SIZE)()
         filename_bytes = filename.encode('utf-8')
         
         success = self._lib.convert_png_to_1bit(filename_bytes, output_data)
         if not success:
-            raise DisplayError(f"Failed to convert PNG: {filename}")
+            raise self._failure(f"Failed to convert PNG: {filename}")
         
         # Convert ctypes array to bytes
         return bytes(output_data)
     
+    def convert_image_bytes_to_raw(self, data: bytes) -> bytes:
+        """
+        Convert an encoded image (PNG or JPEG) held in memory to raw 1-bit data.
+        
+        Args:
+            data: Encoded image bytes (must decode to exactly 128x250 pixels)
+            
+        Returns:
+            Raw 1-bit packed image data (4000 bytes)
+            
+        Raises:
+            DisplayError: If conversion fails
+        """
+        encoded = _ensure_decodable_bytes(data)
+        buf = (ctypes.c_ubyte * len(encoded)).from_buffer_copy(encoded)
+        output_data = (ctypes.c_ubyte * self.ARRAY_SIZE)()
+        
+        success = self._lib.process_image_from_memory(buf, len(encoded), output_data)
+        if not success:
+            raise self._failure("Failed to convert image from memory")
+        
+        return bytes(output_data)
+    
+    def convert_pixels_to_raw(self, pixels, width: int, height: int,
+                              pixel_format: PixelFormat = PixelFormat.RGB888,
+                              options: Optional[ProcessingOptions] = None) -> bytes:
+        """
+        Convert a raw pixel buffer to raw 1-bit data.
+        
+        Args:
+            pixels: Tightly packed pixel rows
+            width: Source width in pixels
+            height: Source height in pixels
+            pixel_format: Layout of the buffer
+            options: Processing options, or None for the configured threshold
+            
+        Returns:
+            Raw 1-bit packed image data (4000 bytes)
+            
+        Raises:
+            DisplayError: If conversion fails
+        """
+        buf = _pixel_buffer(pixels, width, height, pixel_format)
+        options_ptr = ctypes.byref(self._processing_options(options))
+        output_data = (ctypes.c_ubyte * self.ARRAY_SIZE)()
+        
+        success = self._lib.process_pixels_for_display(buf, width, height, int(pixel_format),
+                                                       options_ptr, output_data)
+        if not success:
+            raise self._failure("Failed to convert pixel buffer")
+        
+        return bytes(output_data)
+    
     def is_initialized(self) -> bool:
         """Check if display is initialized."""
         return self._initialized
     
+    @property
+    def native_id(self) -> Optional[int]:
+        """
+        Id of this display in the C library, for C code in the same process
+        that selects panels with display_get(id). None for other backends and
+        libraries without ids.
+        """
+        if not isinstance(self._lib, _NativeHandle) or not hasattr(self._lib._lib, "display_get_id"):
+            return None
+        return self._lib._lib.display_get_id(self._lib.handle)
+    
     def close(self) -> None:
         """Cleanup display resources."""
+        self.unwatch_config()
+        if self._idle is not None:
+            self._idle.stop()
+            self._idle = None
         if self._initialized:
             self._lib.display_cleanup()
             self._initialized = False
+            audit(AuditCategory.POWER, "display_cleanup")
+        if self._usage is not None:
+            self._usage.flush()
     
     def __enter__(self):
         """Context manager entry."""
@@ -313,6 +2232,77 @@ class Display:
         self.close()
 
 
+PNG_SIGNATURE = b"\x89PNG\r\n\x1a\n"
+JPEG_SIGNATURE = b"\xff\xd8\xff"
+
+
+def _text_width(text: str, scale: int) -> int:
+    return max(len(text) * (FONT_WIDTH + 1) * scale - scale, 0)
+
+
+def _largest_text_scale(text: str, width: int, height: int) -> int:
+    scale = 1
+    while _text_width(text, scale + 1) <= width and FONT_HEIGHT * (scale + 1) <= height:
+        scale += 1
+    return scale
+
+
+def _ensure_decodable_bytes(data: bytes) -> bytes:
+    """
+    Return PNG or JPEG bytes for an in-memory image.
+    
+    The C library decodes PNG and JPEG only, so other formats (e.g. GIF) are
+    re-encoded to PNG in memory with Pillow.
+    """
+    if not isinstance(data, (bytes, bytearray, memoryview)) or len(data) == 0:
+        raise DisplayError("Image data must be a non-empty bytes-like object")
+    
+    data = bytes(data)
+    if data.startswith((PNG_SIGNATURE, JPEG_SIGNATURE)):
+        return data
+    
+    try:
+        import io
+        from PIL import Image
+    except ImportError:
+        raise DisplayError("Pillow is required to display non-PNG image data")
+    
+    try:
+        with Image.open(io.BytesIO(data)) as img:
+            out = io.BytesIO()
+            img.convert("RGBA").save(out, format="PNG")
+            return out.getvalue()
+    except Exception as e:
+        raise DisplayError(f"Failed to decode image data: {e}")
+
+
+def _pixel_buffer(pixels, width: int, height: int, pixel_format: PixelFormat):
+    """
+    Validate a pixel buffer's size and wrap it as a ctypes array.
+    
+    Writable C-contiguous buffers, such as most numpy arrays, are handed to
+    the library in place; read-only buffers are copied once, and strided
+    views (e.g. a numpy slice) are packed row by row first.
+    """
+    if pixel_format not in PIXEL_FORMAT_BYTES:
+        raise DisplayError(f"Unsupported pixel format: {pixel_format}")
+    
+    try:
+        view = memoryview(pixels)
+    except TypeError:
+        raise DisplayError(f"Pixels must be a buffer such as bytes or a numpy array, got {type(pixels).__name__}")
+    if not view.c_contiguous:
+        view = memoryview(view.tobytes())
+    view = view.cast("B")
+    expected = width * height * PIXEL_FORMAT_BYTES[PixelFormat(pixel_format)]
+    if len(view) != expected:
+        raise DisplayError(f"Pixel buffer must be exactly {expected} bytes for "
+                           f"{width}x{height} {PixelFormat(pixel_format).name}, got {len(view)}")
+    
+    array_type = ctypes.c_ubyte * len(view)
+    return array_type.from_buffer_copy(view) if view.readonly else array_type.from_buffer(view)
+
+
 # Convenience functions for simple usage (following SDK pattern)
 def display_png(filename: str, mode: DisplayMode = DisplayMode.FULL, rotate: bool = False) -> None:
     """
@@ -327,6 +2317,23 @@ def display_png(filename: str, mode: DisplayMode = DisplayMode.FULL, rotate: boo
         display.display_image(filename, mode, rotate)
 
 
+def show_file(filename: str, options: Optional[ProcessingOptions] = None,
+              mode: DisplayMode = DisplayMode.FULL) -> None:
+    """
+    Convenience function to process and display a PNG file in one call.
+    
+    Args:
+        filename: Path to PNG file
+        options: Processing options, or None for defaults
+        mode: Display refresh mode
+    """
+    display = Display(auto_init=False)
+    try:
+        display.show_file(filename, options, mode)
+    finally:
+        display.close()
+
+
 def clear_display() -> None:
     """Convenience function to clear the display."""
     with Display() as display:
@@ -404,6 +2411,55 @@ def rotate_bitpacked_ccw_90(src_data: bytes, src_width: int, src_height: int) ->
     return bytes(dst_data)
 
 
+def rotate_bitpacked(src_data: bytes, src_width: int, src_height: int,
+                     rotation: RotationMode) -> bytes:
+    """
+    Rotate 1-bit packed bitmap data by a RotationMode.
+    
+    Args:
+        src_data: Source 1-bit packed image data
+        src_width: Source image width in pixels
+        src_height: Source image height in pixels
+        rotation: Rotation to apply
+        
+    Returns:
+        Rotated 1-bit packed data; 90-degree rotations swap width and height
+        
+    Raises:
+        ValueError: If data size doesn't match expected size
+    """
+    expected_bytes = (src_width * src_height + 7) // 8
+    if len(src_data) < expected_bytes:
+        raise ValueError(f"Input data too small. Expected {expected_bytes} bytes, got {len(src_data)}")
+    
+    rotation = RotationMode(rotation)
+    if rotation == RotationMode.NONE:
+        return bytes(src_data[:expected_bytes])
+    if rotation == RotationMode.CCW_90:
+        return rotate_bitpacked_ccw_90(src_data, src_width, src_height)
+    
+    dst_width = src_height if rotation == RotationMode.CW_90 else src_width
+    dst_data = bytearray(expected_bytes)
+    
+    for src_y in range(src_height):
+        for src_x in range(src_width):
+            src_bit_idx = src_y * src_width + src_x
+            if not (src_data[src_bit_idx // 8] >> (7 - (src_bit_idx % 8))) & 1:  # MSB first
+                continue
+            
+            if rotation == RotationMode.CW_90:
+                dst_x = src_height - 1 - src_y
+                dst_y = src_x
+            else:  # 180 degrees
+                dst_x = src_width - 1 - src_x
+                dst_y = src_height - 1 - src_y
+            
+            dst_bit_idx = dst_y * dst_width + dst_x
+            dst_data[dst_bit_idx // 8] |= 1 << (7 - (dst_bit_idx % 8))  # MSB first
+    
+    return bytes(dst_data)
+
+
 def flip_bitpacked_horizontal(src_data: bytes, src_width: int, src_height: int) -> bytes:
     """
     Flip 1-bit packed bitmap data horizontally (left-right mirror).
@@ -0,0 +1,413 @@
+#!/usr/bin/env python3
+"""
+Flipbooks: short clips prepared for playback with partial refreshes.
+
+A weather radar loop or a short animation cannot be played on e-ink as
+video: a partial refresh takes a few hundred milliseconds, a full one
+seconds, and partial refreshes alone build up ghosting. Conversion runs
+offline, on the device or a workstation, and does the planning once:
+
+- frames are scaled and dithered with the ordered pattern, which does not
+  crawl between frames as error diffusion does
+- frames the panel could not show in time are dropped, and unchanged ones
+  merged into the frame before
+- every frame after the first stores only the window of pixels that
+  changed; a keyframe, shown with a full refresh, stores the whole frame
+  and is placed regularly and wherever most of the frame changes
+- each frame records how long it stays, never shorter than its refresh
+
+    book = convert_video("radar.mp4", fps=2, max_seconds=10)
+    book.save("radar.flip")
+
+    with Display() as display:
+        FlipbookPlayer(display, Flipbook.load("radar.flip")).run(loops=0)
+
+Videos are decoded with ffmpeg; a directory of images (played in name
+order) needs nothing extra. From the command line:
+distiller-eink flipbook radar.mp4 radar.flip --fps 2, then
+distiller-eink play radar.flip --loop.
+"""
+
+import os
+import shutil
+import struct
+import subprocess
+import threading
+import time
+import zlib
+from dataclasses import dataclass, field, replace
+from typing import Callable, Iterator, List, Optional, Sequence, Tuple
+
+from .display import DisplayMode
+from .graphics import PANEL_HEIGHT, PANEL_WIDTH, Framebuffer
+from .imaging import DitherMode, ImageOptions, default_image_options, process_image
+from .panels import PanelSpec, get_panel
+from .pngcodec import PNG_SIGNATURE
+
+
+DEFAULT_FPS = 2.0
+DEFAULT_MAX_SECONDS = 30.0
+DEFAULT_KEYFRAME_EVERY = 20     # partial refreshes between keyframes, against ghosting
+DEFAULT_KEYFRAME_CHANGE = 0.5   # fraction of changed pixels above which a frame becomes a keyframe
+
+IMAGE_EXTENSIONS = (".png", ".jpg", ".jpeg", ".gif", ".bmp", ".webp", ".svg")
+
+# File format: MAGIC, then zlib-compressed _HEADER, and for each frame
+# _FRAME followed by the window's pixels packed 8 per byte, MSB first
+MAGIC = b"EFLIP"
+VERSION = 1
+_HEADER = struct.Struct("<BHHI")         # version, width, height, frame count
+_FRAME = struct.Struct("<BIHHHH")        # flags, duration_ms, x, y, width, height
+_KEYFRAME = 0x01
+
+
+class FlipbookError(Exception):
+    """Custom exception for flipbook errors."""
+    pass
+
+
+@dataclass
+class FlipbookFrame:
+    """One step of a flipbook: the pixels to change and how long it is shown."""
+    keyframe: bool                      # shown with a full refresh; the window is the whole frame
+    duration_ms: int                    # from the start of this refresh to the start of the next
+    window: Tuple[int, int, int, int]   # x, y, width, height of the changed pixels
+    pixels: bytes                       # the window's pixels, packed like a Framebuffer
+
+
+@dataclass
+class Flipbook:
+    """A converted clip, ready for FlipbookPlayer."""
+    width: int
+    height: int
+    frames: List[FlipbookFrame] = field(default_factory=list)
+    dropped: int = 0                    # source frames conversion left out to keep the pace; not saved
+
+    @property
+    def duration_s(self) -> float:
+        """Length of one pass."""
+        return sum(frame.duration_ms for frame in self.frames) / 1000
+
+    def images(self) -> Iterator[Tuple[bytes, DisplayMode, int]]:
+        """Each whole frame, packed, with its refresh mode and duration in milliseconds."""
+        rows = [0] * self.height
+        for frame in self.frames:
+            _apply_window(rows, self.width, frame.window, frame.pixels)
+            mode = DisplayMode.FULL if frame.keyframe else DisplayMode.PARTIAL
+            yield _pack_rows(rows, self.width), mode, frame.duration_ms
+
+    def to_bytes(self) -> bytes:
+        """The flipbook in the .flip file format."""
+        body = [_HEADER.pack(VERSION, self.width, self.height, len(self.frames))]
+        for frame in self.frames:
+            body.append(_FRAME.pack(_KEYFRAME if frame.keyframe else 0, frame.duration_ms, *frame.window))
+            body.append(frame.pixels)
+        return MAGIC + zlib.compress(b"".join(body), 9)
+
+    @classmethod
+    def from_bytes(cls, data: bytes) -> "Flipbook":
+        """
+        Read a flipbook in the .flip file format.
+
+        Raises:
+            FlipbookError: If the data is not a flipbook or is damaged
+        """
+        if not data.startswith(MAGIC):
+            raise FlipbookError("Not a flipbook")
+        try:
+            body = zlib.decompress(data[len(MAGIC):])
+            version, width, height, count = _HEADER.unpack_from(body)
+            if version != VERSION:
+                raise FlipbookError(f"Unsupported flipbook version {version}")
+            book = cls(width, height)
+            offset = _HEADER.size
+            for _ in range(count):
+                flags, duration_ms, x, y, w, h = _FRAME.unpack_from(body, offset)
+                offset += _FRAME.size
+                size = (w * h + 7) // 8
+                if x + w > width or y + h > height or offset + size > len(body):
+                    raise FlipbookError("Damaged flipbook frame")
+                book.frames.append(FlipbookFrame(bool(flags & _KEYFRAME), duration_ms, (x, y, w, h),
+                                                 body[offset:offset + size]))
+                offset += size
+        except (zlib.error, struct.error) as e:
+            raise FlipbookError(f"Damaged flipbook: {e}")
+        if book.frames and not book.frames[0].keyframe:
+            raise FlipbookError("Damaged flipbook: it does not start with a keyframe")
+        return book
+
+    def save(self, path: str) -> None:
+        """Write the flipbook to a .flip file."""
+        with open(path, "wb") as f:
+            f.write(self.to_bytes())
+
+    @classmethod
+    def load(cls, path: str) -> "Flipbook":
+        """
+        Read a .flip file.
+
+        Raises:
+            FlipbookError: If the file is not a flipbook or is damaged
+            OSError: If the file cannot be read
+        """
+        with open(path, "rb") as f:
+            return cls.from_bytes(f.read())
+
+
+# -- Frame arithmetic ---------------------------------------------------------
+# A frame is handled as one integer per row, its leftmost pixel in the highest bit
+
+def _unpack_rows(data: bytes, width: int, height: int) -> List[int]:
+    value = int.from_bytes(data, "big") >> (len(data) * 8 - width * height)
+    mask = (1 << width) - 1
+    return [(value >> ((height - 1 - y) * width)) & mask for y in range(height)]
+
+
+def _pack_rows(rows: Sequence[int], width: int) -> bytes:
+    value = 0
+    for row in rows:
+        value = (value << width) | row
+    bits = width * len(rows)
+    size = (bits + 7) // 8
+    return (value << (size * 8 - bits)).to_bytes(size, "big")
+
+
+def _changed_window(old: Sequence[int], new: Sequence[int],
+                    width: int) -> Tuple[Optional[Tuple[int, int, int, int]], int]:
+    """Window around the pixels that differ, or None if none do, and how many differ."""
+    changed = [y for y in range(len(new)) if old[y] != new[y]]
+    if not changed:
+        return None, 0
+    diff = 0
+    count = 0
+    for y in changed:
+        bits = old[y] ^ new[y]
+        diff |= bits
+        count += bin(bits).count("1")
+    left = width - diff.bit_length()
+    right = width - ((diff & -diff).bit_length() - 1)
+    return (left, changed[0], right - left, changed[-1] - changed[0] + 1), count
+
+
+def _extract_window(rows: Sequence[int], width: int, window: Tuple[int, int, int, int]) -> bytes:
+    x, y, w, h = window
+    mask = (1 << w) - 1
+    return _pack_rows([(rows[row] >> (width - x - w)) & mask for row in range(y, y + h)], w)
+
+
+def _apply_window(rows: List[int], width: int, window: Tuple[int, int, int, int], pixels: bytes) -> None:
+    x, y, w, h = window
+    shift = width - x - w
+    keep = ~(((1 << w) - 1) << shift)
+    for i, part in enumerate(_unpack_rows(pixels, w, h)):
+        rows[y + i] = (rows[y + i] & keep) | (part << shift)
+
+
+# -- Conversion -----------------------------------------------------------------
+
+def build_flipbook(frames: Sequence[Framebuffer], fps: float = DEFAULT_FPS, panel: Optional[PanelSpec] = None,
+                   keyframe_every: int = DEFAULT_KEYFRAME_EVERY,
+                   keyframe_change: float = DEFAULT_KEYFRAME_CHANGE) -> Flipbook:
+    """
+    Plan the playback of frames within the panel's refresh budget.
+
+    Source frame i is due i / fps seconds in. Whenever the panel is still
+    refreshing when the next frame is also due, the earlier one is dropped;
+    frames are never delayed to catch up, so the clip keeps its length.
+
+    Args:
+        frames: 1-bit frames, all the same size
+        fps: Source frame rate
+        panel: Panel whose typical refresh times set the budget; the default panel if None
+        keyframe_every: Partial refreshes after which the next frame is a keyframe; 0 only keys the first
+        keyframe_change: Fraction of changed pixels from which a frame is a keyframe
+
+    Raises:
+        FlipbookError: If there are no frames, they differ in size or fps is not positive
+    """
+    if not frames:
+        raise FlipbookError("No frames to convert")
+    if fps <= 0:
+        raise FlipbookError(f"Invalid frame rate {fps}")
+    panel = panel or get_panel()
+    width, height = frames[0].width, frames[0].height
+    if any((frame.width, frame.height) != (width, height) for frame in frames):
+        raise FlipbookError("Frames differ in size")
+
+    book = Flipbook(width, height)
+    starts: List[float] = []        # when each kept frame's refresh starts
+    shown: Optional[List[int]] = None
+    partials = 0
+    free_at = 0.0                   # when the panel finishes the last refresh
+    for i, frame in enumerate(frames):
+        due = i / fps
+        if i + 1 < len(frames) and (i + 1) / fps <= free_at:
+            book.dropped += 1       # superseded before the panel could show it
+            continue
+        rows = _unpack_rows(frame.data, width, height)
+        if shown is None:
+            window, changed = (0, 0, width, height), width * height
+        else:
+            window, changed = _changed_window(shown, rows, width)
+            if window is None:
+                continue            # nothing to show; the frame before stays longer
+        keyframe = (shown is None or (keyframe_every > 0 and partials >= keyframe_every)
+                    or changed >= keyframe_change * width * height)
+        if keyframe:
+            window = (0, 0, width, height)
+            partials = 0
+        else:
+            partials += 1
+        start = max(due, free_at)
+        book.frames.append(FlipbookFrame(keyframe, 0, window, _extract_window(rows, width, window)))
+        starts.append(start)
+        free_at = start + (panel.full_refresh_s if keyframe else panel.partial_refresh_s)
+        shown = rows
+
+    # Each frame stays until the next starts; the last for a frame interval, or its refresh
+    ends = starts[1:] + [max(len(frames) / fps, free_at)]
+    for frame, start, end in zip(book.frames, starts, ends):
+        frame.duration_ms = round((end - start) * 1000)
+    return book
+
+
+def _split_png_stream(data: bytes) -> List[bytes]:
+    """Separate the PNG files ffmpeg wrote back to back."""
+    images = []
+    offset = 0
+    while data.startswith(PNG_SIGNATURE, offset):
+        end = offset + len(PNG_SIGNATURE)
+        while True:
+            if end + 8 > len(data):
+                raise FlipbookError("Truncated frame from ffmpeg")
+            length, kind = struct.unpack_from(">I4s", data, end)
+            end += 12 + length
+            if kind == b"IEND":
+                break
+        images.append(data[offset:end])
+        offset = end
+    return images
+
+
+def read_video_frames(source: str, fps: float = DEFAULT_FPS,
+                      max_seconds: float = DEFAULT_MAX_SECONDS) -> List[bytes]:
+    """
+    Sample a video, or list the images of a directory, as encoded images.
+
+    Args:
+        source: Video file ffmpeg can read, or a directory of images used in name order
+        fps: Frames per second to sample a video at
+        max_seconds: Longest part of a video to convert
+
+    Raises:
+        FlipbookError: If ffmpeg is missing or fails, or no frames were found
+    """
+    if os.path.isdir(source):
+        names = sorted(name for name in os.listdir(source) if name.lower().endswith(IMAGE_EXTENSIONS))
+        images = []
+        for name in names:
+            with open(os.path.join(source, name), "rb") as f:
+                images.append(f.read())
+    else:
+        if shutil.which("ffmpeg") is None:
+            raise FlipbookError("Converting video needs ffmpeg; install it or pass a directory of images")
+        command = ["ffmpeg", "-v", "error", "-i", source, "-t", f"{max_seconds:g}", "-vf", f"fps={fps:g}",
+                   "-f", "image2pipe", "-vcodec", "png", "-"]
+        try:
+            result = subprocess.run(command, capture_output=True, check=False)
+        except OSError as e:
+            raise FlipbookError(f"Cannot run ffmpeg: {e}")
+        if result.returncode != 0:
+            raise FlipbookError(f"ffmpeg failed: {result.stderr.decode(errors='replace').strip()}")
+        images = _split_png_stream(result.stdout)
+    if not images:
+        raise FlipbookError(f"No frames in {source}")
+    return images
+
+
+def convert_video(source: str, fps: float = DEFAULT_FPS, width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT,
+                  panel: Optional[PanelSpec] = None, options: Optional[ImageOptions] = None,
+                  max_seconds: float = DEFAULT_MAX_SECONDS, keyframe_every: int = DEFAULT_KEYFRAME_EVERY,
+                  on_progress: Optional[Callable[[int, int], None]] = None) -> Flipbook:
+    """
+    Convert a short video, or a directory of images, into a flipbook.
+
+    Args:
+        source: Video file, or a directory of images
+        fps: Frames per second sampled, and the rate a directory's images are played at
+        width: Frame width, e.g. Display.get_dimensions()[0] of the player
+        height: Frame height
+        panel: Panel whose refresh times set the budget; the default panel if None
+        options: Image processing; if None, the device's profile with ordered dithering
+        max_seconds: Longest part of a video to convert
+        keyframe_every: Partial refreshes between keyframes; 0 only keys the first
+        on_progress: Called with the frames processed and their total
+
+    Raises:
+        FlipbookError: If the frames cannot be read
+        ImageError: If a frame cannot be decoded
+    """
+    if options is None:
+        options = replace(default_image_options(), dither=DitherMode.ORDERED)
+    images = read_video_frames(source, fps, max_seconds)
+    frames = []
+    for i, image in enumerate(images):
+        frames.append(process_image(image, options, width, height))
+        if on_progress is not None:
+            on_progress(i + 1, len(images))
+    return build_flipbook(frames, fps, panel, keyframe_every)
+
+
+# -- Playback -------------------------------------------------------------------
+
+class FlipbookPlayer:
+    """Plays a flipbook on a display, on its own schedule."""
+
+    def __init__(self, display, book: Flipbook, clock: Callable[[], float] = time.monotonic):
+        """
+        Args:
+            display: Initialized Display whose get_dimensions() match the flipbook's
+            book: The flipbook
+            clock: Monotonic clock, replaceable for testing
+
+        Raises:
+            FlipbookError: If the flipbook was made for other dimensions or has no frames
+        """
+        if tuple(display.get_dimensions()) != (book.width, book.height):
+            width, height = display.get_dimensions()
+            raise FlipbookError(f"Flipbook is {book.width}x{book.height}, the display {width}x{height}")
+        if not book.frames:
+            raise FlipbookError("Flipbook has no frames")
+        self.display = display
+        self.book = book
+        self.clock = clock
+        self._stop = threading.Event()
+
+    def run(self, loops: int = 1, stop: Optional[threading.Event] = None) -> None:
+        """
+        Play until the last loop ends, stop() is called or the stop event is set.
+
+        Every loop starts with its keyframe, so ghosting does not build up
+        across loops. A refresh that overruns delays the rest of the loop
+        rather than dropping frames.
+
+        Args:
+            loops: Passes through the flipbook; 0 repeats until stopped
+            stop: Optional event that ends playback when set
+        """
+        stop = stop or self._stop
+        played = 0
+        next_start = self.clock()
+        while loops == 0 or played < loops:
+            for image, mode, duration_ms in self.book.images():
+                if stop.wait(max(next_start - self.clock(), 0)):
+                    return
+                started = self.clock()
+                self.display.display_image(image, mode)
+                next_start = max(next_start, started) + duration_ms / 1000
+            played += 1
+        stop.wait(max(next_start - self.clock(), 0))    # the last frame's time
+
+    def stop(self) -> None:
+        """End run() from another thread."""
+        self._stop.set()
@@ -0,0 +1,62 @@
+#!/usr/bin/env python3
+"""
+Ghosting management for CM5 SDK.
+
+Partial refreshes leave faint remnants of earlier frames that build up over
+time. GhostingPolicy turns a requested partial refresh into a full one after
+a number of consecutive partials or once too long has passed since the last
+full refresh, so applications that only ever ask for partial updates still
+get a clean panel regularly:
+
+    [refresh]                        # eink.toml
+    full_refresh_every = 10
+    full_refresh_interval = 600      # seconds
+"""
+
+import time
+from typing import Optional
+
+from .display import DisplayMode
+
+
+class GhostingPolicy:
+    """Decides when a partial refresh is replaced by a full refresh."""
+
+    def __init__(self, full_every: int = 0, full_interval_s: float = 0.0):
+        """
+        Args:
+            full_every: Consecutive partial refreshes after which the next one is
+                        done as a full refresh; 0 disables
+            full_interval_s: Seconds since the last full refresh after which the
+                             next partial is done as a full refresh; 0 disables
+        """
+        self.full_every = full_every
+        self.full_interval_s = full_interval_s
+        self.partials = 0
+        self._last_full = time.monotonic()
+
+    @property
+    def enabled(self) -> bool:
+        return self.full_every > 0 or self.full_interval_s > 0
+
+    def mode_for(self, mode: DisplayMode, now: Optional[float] = None) -> DisplayMode:
+        """Refresh mode to use for a requested update."""
+        if DisplayMode(mode) == DisplayMode.FULL:
+            return DisplayMode.FULL
+        now = time.monotonic() if now is None else now
+        if self.full_every > 0 and self.partials >= self.full_every:
+            return DisplayMode.FULL
+        if self.full_interval_s > 0 and now - self._last_full >= self.full_interval_s:
+            return DisplayMode.FULL
+        return DisplayMode.PARTIAL
+
+    def record(self, mode: DisplayMode, now: Optional[float] = None) -> None:
+        """Count a completed refresh."""
+        if DisplayMode(mode) == DisplayMode.FULL:
+            self.partials = 0
+            self._last_full = time.monotonic() if now is None else now
+        else:
+            self.partials += 1
+
+    def seconds_since_full(self, now: Optional[float] = None) -> float:
+        return (time.monotonic() if now is None else now) - self._last_full
@@ -0,0 +1,336 @@
+#!/usr/bin/env python3
+"""
+Graphics module for CM5 SDK.
+Provides 2D drawing primitives that operate directly on packed 1-bit display data.
+"""
+
+from enum import IntEnum
+from typing import Optional, Sequence, Tuple, Union
+
+from .font import FONT_BASELINE, FONT_HEIGHT, FONT_NAME, FONT_WIDTH, glyph
+
+
+# Panel dimensions (match Display.WIDTH / Display.HEIGHT)
+PANEL_WIDTH = 128
+PANEL_HEIGHT = 250
+
+
+class Color(IntEnum):
+    """1-bit pixel values as understood by the display library."""
+    BLACK = 0
+    WHITE = 1
+
+
+class Framebuffer:
+    """
+    Drawable 1-bit framebuffer.
+
+    Pixels are packed 8 per byte, MSB first, in row-major order with no row
+    padding (bit index = y * width + x), exactly the layout accepted by
+    Display.display_image(). A bit value of 1 is white and 0 is black.
+
+    All drawing operations clip to the framebuffer bounds.
+    """
+
+    def __init__(self, width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT,
+                 data: Optional[Union[bytes, bytearray]] = None, fill: Color = Color.WHITE):
+        """
+        Create a framebuffer.
+
+        Args:
+            width: Width in pixels
+            height: Height in pixels
+            data: Optional existing packed data. A bytearray is drawn into in place;
+                  bytes are copied.
+            fill: Initial color when no data is given
+
+        Raises:
+            ValueError: If dimensions are invalid or data has the wrong size
+        """
+        if width <= 0 or height <= 0:
+            raise ValueError(f"Invalid framebuffer size: {width}x{height}")
+
+        self.width = width
+        self.height = height
+        size = (width * height + 7) // 8
+
+        if data is None:
+            self.data = bytearray([0xFF if fill == Color.WHITE else 0x00]) * size
+        else:
+            if len(data) != size:
+                raise ValueError(f"Data must be exactly {size} bytes for {width}x{height}, got {len(data)}")
+            self.data = data if isinstance(data, bytearray) else bytearray(data)
+
+    def to_bytes(self) -> bytes:
+        """Return the packed data, ready for Display.display_image()."""
+        return bytes(self.data)
+
+    def fill(self, color: Color = Color.WHITE) -> None:
+        """Fill the whole framebuffer with a single color."""
+        value = 0xFF if color == Color.WHITE else 0x00
+        for i in range(len(self.data)):
+            self.data[i] = value
+
+    def get_pixel(self, x: int, y: int) -> int:
+        """
+        Get a pixel value.
+
+        Returns:
+            1 for white, 0 for black
+
+        Raises:
+            IndexError: If the coordinate is outside the framebuffer
+        """
+        if not (0 <= x < self.width and 0 <= y < self.height):
+            raise IndexError(f"Pixel ({x}, {y}) outside {self.width}x{self.height} framebuffer")
+        bit_idx = y * self.width + x
+        return (self.data[bit_idx // 8] >> (7 - (bit_idx % 8))) & 1  # MSB first
+
+    def set_pixel(self, x: int, y: int, color: Color = Color.BLACK) -> None:
+        """Set a pixel; coordinates outside the framebuffer are ignored."""
+        if not (0 <= x < self.width and 0 <= y < self.height):
+            return
+        bit_idx = y * self.width + x
+        mask = 1 << (7 - (bit_idx % 8))  # MSB first
+        if color == Color.WHITE:
+            self.data[bit_idx // 8] |= mask
+        else:
+            self.data[bit_idx // 8] &= ~mask & 0xFF
+
+    def draw_hline(self, x: int, y: int, length: int, color: Color = Color.BLACK) -> None:
+        """Draw a horizontal line starting at (x, y) extending right."""
+        if y < 0 or y >= self.height or length <= 0:
+            return
+        x0 = max(x, 0)
+        x1 = min(x + length, self.width)
+        for px in range(x0, x1):
+            self.set_pixel(px, y, color)
+
+    def draw_vline(self, x: int, y: int, length: int, color: Color = Color.BLACK) -> None:
+        """Draw a vertical line starting at (x, y) extending down."""
+        if x < 0 or x >= self.width or length <= 0:
+            return
+        y0 = max(y, 0)
+        y1 = min(y + length, self.height)
+        for py in range(y0, y1):
+            self.set_pixel(x, py, color)
+
+    def draw_line(self, x0: int, y0: int, x1: int, y1: int, color: Color = Color.BLACK) -> None:
+        """Draw a line between two points (Bresenham)."""
+        dx = abs(x1 - x0)
+        dy = -abs(y1 - y0)
+        sx = 1 if x0 < x1 else -1
+        sy = 1 if y0 < y1 else -1
+        err = dx + dy
+
+        while True:
+            self.set_pixel(x0, y0, color)
+            if x0 == x1 and y0 == y1:
+                break
+            e2 = 2 * err
+            if e2 >= dy:
+                err += dy
+                x0 += sx
+            if e2 <= dx:
+                err += dx
+                y0 += sy
+
+    def draw_rect(self, x: int, y: int, width: int, height: int,
+                  color: Color = Color.BLACK, fill: bool = False) -> None:
+        """Draw a rectangle outline, or a filled rectangle if fill is True."""
+        if width <= 0 or height <= 0:
+            return
+        if fill:
+            for py in range(y, y + height):
+                self.draw_hline(x, py, width, color)
+            return
+        self.draw_hline(x, y, width, color)
+        self.draw_hline(x, y + height - 1, width, color)
+        self.draw_vline(x, y, height, color)
+        self.draw_vline(x + width - 1, y, height, color)
+
+    def draw_circle(self, cx: int, cy: int, radius: int,
+                    color: Color = Color.BLACK, fill: bool = False) -> None:
+        """Draw a circle outline, or a filled circle if fill is True (midpoint algorithm)."""
+        if radius < 0:
+            return
+        x = radius
+        y = 0
+        err = 1 - radius
+
+        while x >= y:
+            if fill:
+                self.draw_hline(cx - x, cy + y, 2 * x + 1, color)
+                self.draw_hline(cx - x, cy - y, 2 * x + 1, color)
+                self.draw_hline(cx - y, cy + x, 2 * y + 1, color)
+                self.draw_hline(cx - y, cy - x, 2 * y + 1, color)
+            else:
+                for px, py in ((x, y), (y, x), (-y, x), (-x, y),
+                               (-x, -y), (-y, -x), (y, -x), (x, -y)):
+                    self.set_pixel(cx + px, cy + py, color)
+            y += 1
+            if err < 0:
+                err += 2 * y + 1
+            else:
+                x -= 1
+                err += 2 * (y - x) + 1
+
+    def draw_polygon(self, points: Sequence[Tuple[int, int]], color: Color = Color.BLACK) -> None:
+        """Draw a closed polygon outline through the given points."""
+        if len(points) < 2:
+            return
+        for i in range(len(points)):
+            x0, y0 = points[i]
+            x1, y1 = points[(i + 1) % len(points)]
+            self.draw_line(x0, y0, x1, y1, color)
+
+    def fill_polygon(self, points: Sequence[Tuple[int, int]], color: Color = Color.BLACK) -> None:
+        """
+        Draw a filled polygon (even-odd rule, scanline fill).
+
+        Args:
+            points: Polygon vertices as (x, y) tuples; the polygon is closed automatically
+            color: Fill color
+        """
+        if len(points) < 3:
+            self.draw_polygon(points, color)
+            return
+
+        min_y = max(min(p[1] for p in points), 0)
+        max_y = min(max(p[1] for p in points), self.height - 1)
+
+        for y in range(min_y, max_y + 1):
+            # Sample at the pixel center to avoid double-counting shared vertices
+            scan_y = y + 0.5
+            crossings = []
+            for i in range(len(points)):
+                x0, y0 = points[i]
+                x1, y1 = points[(i + 1) % len(points)]
+                if (y0 <= scan_y < y1) or (y1 <= scan_y < y0):
+                    crossings.append(x0 + (scan_y - y0) * (x1 - x0) / (y1 - y0))
+            crossings.sort()
+            for left, right in zip(crossings[0::2], crossings[1::2]):
+                start = int(round(left))
+                end = int(round(right))
+                self.draw_hline(start, y, end - start + 1, color)
+
+        # Include the outline so thin or degenerate edges are not lost
+        self.draw_polygon(points, color)
+
+    def draw_text(self, x: int, y: int, text: str, color: Color = Color.BLACK, scale: int = 1) -> None:
+        """
+        Draw text in the built-in 5x8 font.
+
+        Each character advances (FONT_WIDTH + 1) * scale pixels; newlines start
+        a new line FONT_HEIGHT * scale pixels below. Background pixels are left
+        untouched.
+
+        Args:
+            x: Left edge of the first character
+            y: Top edge of the first line
+            text: Text to draw; non-ASCII characters render as '?'
+            color: Text color
+            scale: Integer magnification
+        """
+        cursor_x = x
+        for char in text:
+            if char == "\n":
+                cursor_x = x
+                y += FONT_HEIGHT * scale
+                continue
+            for col, bits in enumerate(glyph(char)):
+                for row in range(FONT_HEIGHT):
+                    if bits & (1 << row):
+                        self.draw_rect(cursor_x + col * scale, y + row * scale,
+                                       scale, scale, color, fill=True)
+            cursor_x += (FONT_WIDTH + 1) * scale
+
+    def blit(self, source: "Framebuffer", x: int, y: int, transparent: Optional[Color] = None) -> None:
+        """
+        Copy another framebuffer onto this one at (x, y).
+
+        Args:
+            source: Framebuffer to copy from
+            x: Destination x of the source's top-left corner
+            y: Destination y of the source's top-left corner
+            transparent: Optional color in the source that is skipped
+        """
+        for sy in range(source.height):
+            for sx in range(source.width):
+                value = source.get_pixel(sx, sy)
+                if transparent is not None and value == transparent:
+                    continue
+                self.set_pixel(x + sx, y + sy, Color(value))
+
+
+
+def wrap_text(text: str, width: int, scale: int = 1) -> str:
+    """
+    Break text into lines that fit width pixels when drawn with draw_text().
+
+    Lines break between words; a word longer than a line is split. Existing
+    newlines are kept.
+
+    Args:
+        text: Text to wrap
+        width: Line width in pixels
+        scale: Integer magnification the text will be drawn at
+
+    Returns:
+        The text with newlines inserted
+    """
+    per_line = max(1, (width + scale) // ((FONT_WIDTH + 1) * scale))
+    lines = []
+    for paragraph in text.split("\n"):
+        line = ""
+        for word in paragraph.split():
+            while len(word) > per_line:
+                if line:
+                    lines.append(line)
+                    line = ""
+                lines.append(word[:per_line])
+                word = word[per_line:]
+            if not line:
+                line = word
+            elif len(line) + 1 + len(word) <= per_line:
+                line += " " + word
+            else:
+                lines.append(line)
+                line = word
+        lines.append(line)
+    return "\n".join(lines)
+
+
+def measure_text(text: str, font: Optional[str] = None, size: int = 1,
+                 max_width: Optional[int] = None) -> Tuple[int, int, int]:
+    """
+    Measure text as draw_text() would draw it, without drawing.
+
+    The width is that of the widest line, from the left edge of its first
+    character to the right edge of its last, without the trailing gap.
+    Every character, whatever its script, takes one cell, and draw_text()
+    renders it as '?' if the font has no glyph for it.
+
+    Args:
+        text: Text to measure; newlines start new lines
+        font: Font name; only the built-in "5x8" font (the default) exists
+        size: Integer magnification, the scale passed to draw_text()
+        max_width: If given, measure the text as wrap_text() wraps it to this width
+
+    Returns:
+        (width, height, baseline) in pixels, baseline being the distance from
+        the top to the baseline of the first line
+
+    Raises:
+        ValueError: If the font is unknown or size is not positive
+    """
+    if font is not None and font != FONT_NAME:
+        raise ValueError(f"Unknown font '{font}', only '{FONT_NAME}' is built in")
+    if size < 1:
+        raise ValueError(f"Invalid text size: {size}")
+    if max_width is not None:
+        text = wrap_text(text, max_width, size)
+    lines = text.split("\n")
+    longest = max(len(line) for line in lines)
+    width = max(longest * (FONT_WIDTH + 1) * size - size, 0)
+    return width, len(lines) * FONT_HEIGHT * size, FONT_BASELINE * size
@@ -0,0 +1,164 @@
+#!/usr/bin/env python3
+"""
+Multiple panels for CM5 SDK.
+
+Carriers with a second panel, e.g. a small status display next to the main
+one, describe each panel in eink.toml (see config.py). DisplayGroup opens
+them together and gives each its own refresh queue, so a slow full refresh
+of one panel never delays updates of the other:
+
+    with DisplayGroup.open() as panels:
+        panels.submit("status", Display.display_image, "wifi.png", DisplayMode.PARTIAL)
+        panels.submit("main", Display.display_image, "page.png")
+        panels.wait()
+"""
+
+from concurrent.futures import Future, ThreadPoolExecutor, wait
+from dataclasses import replace
+from typing import Any, Callable, Dict, Iterator, List, Optional, Sequence
+
+from .backends import NATIVE_BACKEND, PYTHON_BACKEND, resolve_backend_name
+from .config import DisplayConfig, list_displays, load_config
+from .display import Display, DisplayError
+from .panels import DEFAULT_PANEL
+
+
+def check_wiring(configs: Sequence[DisplayConfig]) -> None:
+    """
+    Check that panels on hardware share no SPI device or GPIO line.
+
+    Raises:
+        DisplayError: If two panels would claim the same device or line
+    """
+    owners: Dict[Any, str] = {}
+    for config in configs:
+        try:
+            backend = resolve_backend_name(config.backend)
+        except ValueError as e:
+            raise DisplayError(str(e))
+        if backend not in (NATIVE_BACKEND, PYTHON_BACKEND):
+            continue
+        spi = config.spi
+        resources = [spi.device_path] + [f"{spi.gpio_chip} line {pin}"
+                                         for pin in (spi.dc_pin, spi.reset_pin, spi.busy_pin)]
+        for resource in resources:
+            if resource in owners:
+                raise DisplayError(f"Displays '{owners[resource]}' and '{config.name}' both use {resource}")
+            owners[resource] = config.name
+
+
+def check_panels(configs: Sequence[DisplayConfig]) -> None:
+    """
+    Check that each panel's backend can drive its panel type.
+
+    Every handle of the native library drives the default panel, so another
+    panel type on a native display is refused before any panel is opened.
+
+    Raises:
+        DisplayError: If a native display is configured with another panel type
+    """
+    for config in configs:
+        try:
+            backend = resolve_backend_name(config.backend)
+        except ValueError as e:
+            raise DisplayError(str(e))
+        if backend == NATIVE_BACKEND and config.panel != DEFAULT_PANEL:
+            raise DisplayError(f"Display '{config.name}' is configured for the {config.panel.value} panel, but the "
+                               f"native backend only drives {DEFAULT_PANEL.value}; set backend = \"python\" for it")
+
+
+class DisplayGroup:
+    """Several panels driven side by side, each through its own update queue."""
+
+    def __init__(self, displays: Dict[str, Display]):
+        """
+        Args:
+            displays: Open displays by name
+        """
+        self._displays = dict(displays)
+        self._queues = {name: ThreadPoolExecutor(max_workers=1, thread_name_prefix=f"eink-{name}")
+                        for name in self._displays}
+
+    @classmethod
+    def open(cls, names: Optional[Sequence[str]] = None, path: Optional[str] = None,
+             backend: Optional[str] = None, auto_init: bool = True) -> "DisplayGroup":
+        """
+        Open configured displays.
+
+        Args:
+            names: Displays to open; None for all of list_displays()
+            path: Config file path, as for load_config()
+            backend: Backend for every display; None for each display's configured one
+            auto_init: Whether to initialize the panels
+
+        Raises:
+            DisplayError: If a display is not configured, two share wiring, one's backend cannot
+                          drive its panel type, or one fails to open
+        """
+        configs = [load_config(path, name) for name in (names or list_displays(path))]
+        if backend is not None:
+            configs = [replace(config, backend=backend) for config in configs]
+        check_wiring(configs)
+        check_panels(configs)
+
+        displays: Dict[str, Display] = {}
+        try:
+            for config in configs:
+                displays[config.name] = Display(auto_init=auto_init, config=config)
+        except DisplayError:
+            for display in displays.values():
+                display.close()
+            raise
+        return cls(displays)
+
+    @property
+    def names(self) -> List[str]:
+        return list(self._displays)
+
+    def __getitem__(self, name: str) -> Display:
+        """The display of this name, for direct (synchronous) use."""
+        return self._displays[name]
+
+    def __iter__(self) -> Iterator[str]:
+        return iter(self._displays)
+
+    def __len__(self) -> int:
+        return len(self._displays)
+
+    def submit(self, name: str, update: Callable[..., Any], *args, **kwargs) -> Future:
+        """
+        Queue update(display, *args, **kwargs) on one display.
+
+        Updates of a display run in order, one at a time; updates of different
+        displays run concurrently.
+
+        Returns:
+            Future of the update's result; it raises what the update raised
+
+        Raises:
+            KeyError: If there is no display of this name
+        """
+        return self._queues[name].submit(update, self._displays[name], *args, **kwargs)
+
+    def wait(self, timeout: Optional[float] = None) -> bool:
+        """
+        Wait for every update queued so far.
+
+        Returns:
+            False if the timeout passed first
+        """
+        markers = [queue.submit(lambda: None) for queue in self._queues.values()]
+        return not wait(markers, timeout).not_done
+
+    def close(self) -> None:
+        """Finish the queued updates, then close every display."""
+        for queue in self._queues.values():
+            queue.shutdown(wait=True)
+        for display in self._displays.values():
+            display.close()
+
+    def __enter__(self) -> "DisplayGroup":
+        return self
+
+    def __exit__(self, exc_type, exc_val, exc_tb):
+        self.close()
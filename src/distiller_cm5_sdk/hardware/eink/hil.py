@@ -0,0 +1,338 @@
+#!/usr/bin/env python3
+"""
+Hardware-in-the-loop tests for the e-ink panel protocol.
+
+Qualifies a panel batch or a driver/firmware build on a real device by
+running scripted sequences against the panel and checking each refresh
+against the panel's typical timing:
+
+- init-storm: open, initialize and close the display repeatedly
+- rapid-partials: partial refreshes back to back
+- sleep-wake: deep sleep and wake cycles, each followed by a partial
+  refresh that must still use the frame from before the sleep
+- power-cycle: cut and restore the panel's power, then re-initialize;
+  skipped unless a power control is given
+
+A step fails when it raises DisplayError, takes longer than tolerance
+times the panel's typical time, or leaves the panel showing a different
+frame than was sent. The result is an HilReport, printable as text or
+saved as JSON:
+
+    runner = HilRunner(lambda: Display(backend="native"), iterations=20)
+    report = runner.run()
+    print(report.format_text())
+
+From the command line: python3 -m distiller_cm5_sdk.diagnostics eink-hil --report hil.json
+"""
+
+import platform
+import time
+from dataclasses import asdict, dataclass, field
+from enum import Enum
+from typing import Callable, Dict, List, Optional, Sequence
+
+from .display import DisplayError, DisplayMode
+from .patterns import PatternSpec, generate
+
+
+SCENARIOS = ("init-storm", "rapid-partials", "sleep-wake", "power-cycle")
+
+DEFAULT_ITERATIONS = 10
+DEFAULT_TOLERANCE = 2.0     # times the panel's typical refresh time
+
+# Pixel-walk block size of the frames shown by rapid-partials
+WALK_SIZE = 16
+
+
+class HilError(Exception):
+    """Custom exception for hardware-in-the-loop test errors."""
+    pass
+
+
+class Outcome(Enum):
+    """Result of one scenario."""
+    PASSED = "passed"
+    FAILED = "failed"
+    SKIPPED = "skipped"
+
+
+@dataclass
+class StepResult:
+    """One timed operation in a scenario."""
+    name: str
+    duration_ms: float
+    budget_ms: Optional[float] = None
+    error: Optional[str] = None
+
+    @property
+    def passed(self) -> bool:
+        return self.error is None
+
+
+@dataclass
+class ScenarioResult:
+    """The steps of one scenario and its outcome."""
+    name: str
+    outcome: Outcome = Outcome.PASSED
+    reason: Optional[str] = None            # why it was skipped
+    steps: List[StepResult] = field(default_factory=list)
+
+    @property
+    def failures(self) -> List[StepResult]:
+        return [step for step in self.steps if not step.passed]
+
+    def timings(self) -> Dict[str, Dict[str, float]]:
+        """Count, mean and max duration in milliseconds for each step name."""
+        timings: Dict[str, Dict[str, float]] = {}
+        for step in self.steps:
+            entry = timings.setdefault(step.name, {"count": 0, "mean_ms": 0.0, "max_ms": 0.0})
+            entry["count"] += 1
+            entry["mean_ms"] += (step.duration_ms - entry["mean_ms"]) / entry["count"]
+            entry["max_ms"] = max(entry["max_ms"], step.duration_ms)
+        return timings
+
+
+@dataclass
+class HilReport:
+    """Everything a run measured, with the display and host it ran on."""
+    started: float
+    display: dict
+    library_version: Optional[str]
+    host: str
+    iterations: int
+    tolerance: float
+    scenarios: List[ScenarioResult] = field(default_factory=list)
+
+    @property
+    def passed(self) -> bool:
+        """True if no scenario failed; skipped ones do not count."""
+        return all(scenario.outcome != Outcome.FAILED for scenario in self.scenarios)
+
+    def to_dict(self) -> dict:
+        """The report as JSON-serializable data."""
+        data = asdict(self)
+        data["passed"] = self.passed
+        for scenario, entry in zip(self.scenarios, data["scenarios"]):
+            entry["outcome"] = scenario.outcome.value
+            entry["timings"] = scenario.timings()
+        return data
+
+    def format_text(self) -> str:
+        """A human-readable summary, listing every failed step."""
+        started = time.strftime("%Y-%m-%d %H:%M:%S", time.localtime(self.started))
+        lines = [f"E-ink HIL report {started} on {self.host}",
+                 f"Panel {self.display.get('panel')} ({self.display.get('backend')} backend, "
+                 f"library {self.library_version or 'unknown'}), {self.iterations} iterations, "
+                 f"tolerance {self.tolerance:g}x"]
+        for scenario in self.scenarios:
+            if scenario.outcome == Outcome.SKIPPED:
+                lines.append(f"{scenario.name:<15} SKIPPED  {scenario.reason}")
+                continue
+            lines.append(f"{scenario.name:<15} {scenario.outcome.value.upper()}")
+            for name, timing in scenario.timings().items():
+                lines.append(f"  {name:<20} {timing['count']:>4}x  mean {timing['mean_ms']:>8.1f} ms  "
+                             f"max {timing['max_ms']:>8.1f} ms")
+            for step in scenario.failures:
+                lines.append(f"  FAILED {step.name}: {step.error}")
+        lines.append("PASSED" if self.passed else "FAILED")
+        return "\n".join(lines)
+
+
+def _describe(error: DisplayError) -> str:
+    return f"{error} ({error.code.name})" if getattr(error, "code", None) is not None else str(error)
+
+
+class HilRunner:
+    """Runs the HIL scenarios against displays opened by a factory."""
+
+    def __init__(self, display_factory: Callable[[], object], iterations: int = DEFAULT_ITERATIONS,
+                 tolerance: float = DEFAULT_TOLERANCE, power_cycle: Optional[Callable[[], None]] = None,
+                 clock: Callable[[], float] = time.monotonic):
+        """
+        Args:
+            display_factory: Opens and initializes a Display, e.g.
+                             lambda: Display(backend="native"); called once
+                             per scenario and on every init-storm iteration
+            iterations: Repetitions of each scenario's sequence
+            tolerance: Multiple of the panel's typical refresh time a step may take
+            power_cycle: Cuts and restores the panel's power rail; the
+                         power-cycle scenario is skipped without it
+            clock: Monotonic clock, replaceable for testing
+
+        Raises:
+            HilError: If iterations or tolerance is not positive
+        """
+        if iterations < 1:
+            raise HilError(f"Invalid iteration count {iterations}")
+        if tolerance <= 0:
+            raise HilError(f"Invalid tolerance {tolerance}")
+        self.display_factory = display_factory
+        self.iterations = iterations
+        self.tolerance = tolerance
+        self.power_cycle = power_cycle
+        self._clock = clock
+        # Typical times of the panel under test, read by run()
+        self._full_s: Optional[float] = None
+        self._partial_s: Optional[float] = None
+        self._init_budget_s: Optional[float] = None
+
+    def _step(self, result: ScenarioResult, name: str, budget_s: Optional[float],
+              action: Callable[[], object]) -> bool:
+        """Time action() and record it; returns False if it raised DisplayError or ran over budget."""
+        budget_ms = budget_s * self.tolerance * 1000 if budget_s is not None else None
+        start = self._clock()
+        error = None
+        try:
+            action()
+        except DisplayError as e:
+            error = _describe(e)
+        duration_ms = (self._clock() - start) * 1000
+        if error is None and budget_ms is not None and duration_ms > budget_ms:
+            error = f"took {duration_ms:.0f} ms, budget {budget_ms:.0f} ms"
+        result.steps.append(StepResult(name, round(duration_ms, 3), budget_ms, error))
+        return error is None
+
+    def _show(self, display, frame: bytes, mode: DisplayMode) -> None:
+        """Show frame and check the panel kept it."""
+        display.display_image(frame, mode)
+        if display.get_framebuffer() != frame:
+            raise DisplayError("panel frame differs from the frame sent")
+
+    def _frame(self, display, step: int) -> bytes:
+        width, height = display.get_dimensions()
+        return generate(PatternSpec("pixel-walk", size=WALK_SIZE, step=step), width, height).to_bytes()
+
+    def _check_stats(self, result: ScenarioResult, display) -> None:
+        """Fail on refreshes the driver counted as failed, e.g. retried busy timeouts."""
+        try:
+            failed = display.get_stats()["failed_refreshes"]
+        except DisplayError:
+            return                  # library predates display_get_stats()
+        if failed:
+            result.steps.append(StepResult("driver stats", 0.0, None, f"{failed} failed refreshes"))
+
+    def _open(self, result: ScenarioResult):
+        """Open a display as an "init" step; None if that failed."""
+        opened = []
+        if not self._step(result, "init", self._init_budget_s, lambda: opened.append(self.display_factory())):
+            if opened:
+                opened[0].close()
+            return None
+        return opened[0]
+
+    # -- Scenarios ------------------------------------------------------------
+
+    def _init_storm(self, result: ScenarioResult) -> None:
+        for _ in range(self.iterations):
+            display = self._open(result)
+            if display is None:
+                return
+            display.close()
+        display = self._open(result)
+        if display is not None:
+            try:
+                # The panel must still refresh after the storm
+                self._step(result, "full refresh", self._full_s,
+                           lambda: self._show(display, self._frame(display, 0), DisplayMode.FULL))
+            finally:
+                display.close()
+
+    def _rapid_partials(self, result: ScenarioResult) -> None:
+        display = self._open(result)
+        if display is None:
+            return
+        try:
+            if not self._step(result, "full refresh", self._full_s,
+                              lambda: self._show(display, self._frame(display, 0), DisplayMode.FULL)):
+                return
+            for i in range(1, self.iterations + 1):
+                frame = self._frame(display, i)
+                self._step(result, "partial refresh", self._partial_s,
+                           lambda: self._show(display, frame, DisplayMode.PARTIAL))
+            self._check_stats(result, display)
+        finally:
+            display.close()
+
+    def _sleep_wake(self, result: ScenarioResult) -> None:
+        display = self._open(result)
+        if display is None:
+            return
+        try:
+            if not self._step(result, "full refresh", self._full_s,
+                              lambda: self._show(display, self._frame(display, 0), DisplayMode.FULL)):
+                return
+
+            def sleep():
+                display.sleep()
+                if not display.is_asleep():
+                    raise DisplayError("panel did not report deep sleep")
+
+            for i in range(1, self.iterations + 1):
+                frame = self._frame(display, i)
+                if not (self._step(result, "sleep", None, sleep)
+                        and self._step(result, "wake", self._init_budget_s, display.wake)):
+                    return
+                self._step(result, "partial refresh", self._partial_s,
+                           lambda: self._show(display, frame, DisplayMode.PARTIAL))
+            self._check_stats(result, display)
+        finally:
+            display.close()
+
+    def _power_cycle(self, result: ScenarioResult) -> None:
+        if self.power_cycle is None:
+            result.outcome, result.reason = Outcome.SKIPPED, "no panel power control"
+            return
+        for i in range(self.iterations):
+            display = self._open(result)
+            if display is None:
+                return
+            try:
+                if not self._step(result, "full refresh", self._full_s,
+                                  lambda: self._show(display, self._frame(display, i), DisplayMode.FULL)):
+                    return
+                display.sleep()
+            finally:
+                display.close()
+            if not self._step(result, "power cycle", None, self.power_cycle):
+                return
+
+    # -- Running --------------------------------------------------------------
+
+    def run(self, scenarios: Optional[Sequence[str]] = None) -> HilReport:
+        """
+        Run scenarios in turn; all of SCENARIOS by default.
+
+        Returns:
+            The report; a scenario fails when any of its steps did
+
+        Raises:
+            HilError: If a scenario is unknown or the display cannot be opened at all
+        """
+        scenarios = list(scenarios) if scenarios else list(SCENARIOS)
+        unknown = [name for name in scenarios if name not in SCENARIOS]
+        if unknown:
+            raise HilError(f"Unknown scenario '{unknown[0]}'; expected one of {', '.join(SCENARIOS)}")
+        try:
+            display = self.display_factory()
+        except DisplayError as e:
+            raise HilError(f"Cannot open the display: {_describe(e)}")
+        try:
+            info = display.get_info()
+            library_version = getattr(display, "library_version", None)
+            self._full_s = display.panel.full_refresh_s
+            self._partial_s = display.panel.partial_refresh_s
+            # Initialization and wake reset the panel and load its settings; a full refresh is a generous bound
+            self._init_budget_s = self._full_s
+        finally:
+            display.close()
+
+        report = HilReport(time.time(), info, library_version, platform.node(), self.iterations, self.tolerance)
+        handlers = {"init-storm": self._init_storm, "rapid-partials": self._rapid_partials,
+                    "sleep-wake": self._sleep_wake, "power-cycle": self._power_cycle}
+        for name in scenarios:
+            result = ScenarioResult(name)
+            handlers[name](result)
+            if result.failures:
+                result.outcome = Outcome.FAILED
+            report.scenarios.append(result)
+        return report
@@ -0,0 +1,245 @@
+#!/usr/bin/env python3
+"""
+HTTP endpoint for pushing content to the e-ink display.
+
+For kiosk deployments where a central server pushes content to each unit:
+
+    distiller-eink serve --bind 0.0.0.0 --port 8080 --token s3cret
+
+    curl -H "Authorization: Bearer s3cret" --data-binary @menu.png \\
+         "http://distiller.local:8080/display?scale=crop&mode=partial"
+    curl -H "Authorization: Bearer s3cret" -F image=@menu.jpg http://distiller.local:8080/display
+    curl -H "Authorization: Bearer s3cret" -o shown.png http://distiller.local:8080/screenshot
+    curl -H "Authorization: Bearer s3cret" http://distiller.local:8080/info
+
+POST /display takes the image as the raw request body or as the first file
+of a multipart/form-data upload, of any size, and prepares it like
+distiller-eink show. Uses only the standard library; JPEG and other
+formats need the image extra like everywhere else.
+
+Updates are tagged with the client's address and the "tag" parameter, if
+given, e.g. /display?tag=menu-board (see tag_updates()); GET /info shows the
+last one.
+
+With a screen lock (device.lock), /display and /screenshot answer 423 while
+it is locked. POST /unlock with {"pin": "2580"} opens it and POST /lock
+closes it again. The lock state belongs to the server process, so entering
+the PIN on the panel's menu does not unlock the server.
+"""
+
+import hmac
+import json
+import math
+import threading
+from email.parser import BytesParser
+from email.policy import HTTP
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from typing import Any, Optional, Tuple
+from urllib.parse import parse_qs, urlparse
+
+from ...device.lock import LockError, ScreenLock
+from ...diagnostics.log_levels import get_logger
+from .display import MAX_TAG_LENGTH, DisplayError, DisplayMode, tag_updates
+from .imaging import DitherMode, ImageError, ImageOptions, ScalingMethod, default_image_options, process_image
+from .pngcodec import PNGError, encode_png_1bit
+
+
+DEFAULT_PORT = 8080
+
+# Larger request bodies are refused with 413
+MAX_BODY_SIZE = 10 * 1024 * 1024
+
+
+class _HTTPError(Exception):
+    def __init__(self, status: int, message: str):
+        super().__init__(message)
+        self.status = status
+
+
+def _image_options(query: dict) -> Tuple[ImageOptions, DisplayMode]:
+    def value(name: str, default: str) -> str:
+        return query.get(name, [default])[-1]
+
+    profile = default_image_options()
+    try:
+        options = ImageOptions(ScalingMethod(value("scale", ScalingMethod.LETTERBOX.value)),
+                               DitherMode(value("dither", profile.dither.value)),
+                               int(value("threshold", str(profile.threshold))),
+                               value("invert", "false").lower() in ("1", "true", "yes"), profile.contrast,
+                               profile.parallel, profile.scaling_filter, profile.sharpen,
+                               rotate=float(value("rotate", "0")),
+                               deskew=value("deskew", "false").lower() in ("1", "true", "yes"))
+        mode = DisplayMode[value("mode", "full").upper()]
+    except (KeyError, ValueError) as e:
+        raise _HTTPError(400, f"Invalid parameter: {e}")
+    if not 0 <= options.threshold <= 255:
+        raise _HTTPError(400, "threshold must be 0-255")
+    if not math.isfinite(options.rotate):
+        raise _HTTPError(400, "rotate must be a number of degrees")
+    return options, mode
+
+
+def _multipart_image(content_type: str, body: bytes) -> bytes:
+    message = BytesParser(policy=HTTP).parsebytes(b"Content-Type: " + content_type.encode("latin-1") +
+                                                  b"\r\n\r\n" + body)
+    if not message.is_multipart():
+        raise _HTTPError(400, "Malformed multipart body")
+    parts = [part for part in message.iter_parts() if part.get_filename() is not None]
+    if not parts:
+        raise _HTTPError(400, "No file in the multipart body")
+    return parts[0].get_payload(decode=True) or b""
+
+
+class _Handler(BaseHTTPRequestHandler):
+    server: "DisplayHTTPServer"
+    server_version = "distiller-eink"
+
+    def do_GET(self) -> None:
+        self._dispatch({"/info": self._info, "/screenshot": self._screenshot})
+
+    def do_POST(self) -> None:
+        self._dispatch({"/display": self._display, "/lock": self._lock, "/unlock": self._unlock})
+
+    def _dispatch(self, routes: dict) -> None:
+        url = urlparse(self.path)
+        try:
+            self._authorize()
+            route = routes.get(url.path)
+            if route is None:
+                raise _HTTPError(404, f"No {self.command} {url.path}")
+            route(parse_qs(url.query))
+        except _HTTPError as e:
+            self._send_json(e.status, {"ok": False, "error": str(e)})
+        except (ImageError, PNGError) as e:
+            self._send_json(400, {"ok": False, "error": str(e)})
+        except MemoryError:
+            self._send_json(400, {"ok": False, "error": "Image is too large to decode"})
+        except LockError as e:
+            self._send_json(423, {"ok": False, "error": str(e)})
+        except DisplayError as e:
+            self._send_json(500, {"ok": False, "error": str(e)})
+
+    def _authorize(self) -> None:
+        token = self.server.token
+        if token is None:
+            return
+        header = self.headers.get("Authorization", "")
+        if not hmac.compare_digest(header.encode(), f"Bearer {token}".encode()):
+            raise _HTTPError(401, "Missing or wrong bearer token")
+
+    def _read_body(self) -> bytes:
+        try:
+            length = int(self.headers.get("Content-Length", ""))
+        except ValueError:
+            raise _HTTPError(411, "Content-Length required")
+        if length < 0:
+            raise _HTTPError(400, "Content-Length must not be negative")
+        if length > MAX_BODY_SIZE:
+            raise _HTTPError(413, f"Body is over {MAX_BODY_SIZE} bytes")
+        return self.rfile.read(length)
+
+    def _require_unlocked(self, operation: str) -> None:
+        if self.server.screen_lock is not None:
+            self.server.screen_lock.require_unlocked(operation)
+
+    def _display(self, query: dict) -> None:
+        options, mode = _image_options(query)
+        body = self._read_body()
+        content_type = self.headers.get("Content-Type", "")
+        if content_type.lower().startswith("multipart/form-data"):
+            body = _multipart_image(content_type, body)
+        if not body:
+            raise _HTTPError(400, "No image in the request")
+        self._require_unlocked("Drawing")
+        with self.server.lock:
+            width, height = self.server.display.get_dimensions()
+            try:
+                frame = process_image(body, options, width, height)
+            except ImageError as e:
+                raise _HTTPError(400, str(e))
+            tag = query.get("tag", [""])[-1][:MAX_TAG_LENGTH]
+            peer = f"http {self.client_address[0]}"
+            with tag_updates(f"{tag} ({peer})" if tag else peer):
+                self.server.display.display_image(frame.to_bytes(), mode)
+        self._send_json(200, {"ok": True, "width": width, "height": height})
+
+    def _screenshot(self, query: dict) -> None:
+        self._require_unlocked("Taking a screenshot")
+        with self.server.lock:
+            frame = self.server.display.get_framebuffer()
+            width, height = self.server.display.get_dimensions()
+        if frame is None:
+            raise _HTTPError(404, "Nothing has been displayed yet")
+        self._send(200, "image/png", encode_png_1bit(frame, width, height))
+
+    def _info(self, query: dict) -> None:
+        with self.server.lock:
+            info = self.server.display.get_info()
+        self._send_json(200, info)
+
+    def _lock(self, query: dict) -> None:
+        screen_lock = self._enabled_screen_lock()
+        screen_lock.lock()
+        self._send_json(200, {"ok": True})
+
+    def _unlock(self, query: dict) -> None:
+        try:
+            pin = json.loads(self._read_body() or b"{}").get("pin")
+        except (ValueError, AttributeError):
+            raise _HTTPError(400, 'The body must be JSON like {"pin": "2580"}')
+        if not isinstance(pin, str):
+            raise _HTTPError(400, "No PIN in the request")
+        screen_lock = self._enabled_screen_lock()
+        if not screen_lock.unlock(pin):
+            remaining = screen_lock.lockout_remaining()
+            if remaining > 0:
+                raise _HTTPError(429, f"Too many wrong PINs; try again in {math.ceil(remaining)} s")
+            raise _HTTPError(403, "Wrong PIN")
+        self._send_json(200, {"ok": True})
+
+    def _enabled_screen_lock(self) -> ScreenLock:
+        screen_lock = self.server.screen_lock
+        if screen_lock is None or not screen_lock.enabled:
+            raise _HTTPError(404, "No screen lock is set")
+        return screen_lock
+
+    def _send_json(self, status: int, data: dict) -> None:
+        self._send(status, "application/json", (json.dumps(data) + "\n").encode())
+
+    def _send(self, status: int, content_type: str, body: bytes) -> None:
+        self.send_response(status)
+        if status == 401:
+            self.send_header("WWW-Authenticate", "Bearer")
+        self.send_header("Content-Type", content_type)
+        self.send_header("Content-Length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+
+    def log_message(self, format: str, *args: Any) -> None:
+        get_logger("hardware.eink.http").info("%s %s", self.address_string(), format % args)
+
+
+class DisplayHTTPServer(ThreadingHTTPServer):
+    """Serves a display over HTTP: POST /display, GET /screenshot and GET /info, gated by an optional screen lock."""
+
+    daemon_threads = True
+
+    def __init__(self, display, bind: str = "127.0.0.1", port: int = DEFAULT_PORT, token: Optional[str] = None,
+                 screen_lock: Optional[ScreenLock] = None):
+        """
+        Args:
+            display: Initialized Display, or a DisplayClient to draw through the daemon
+            bind: Address to listen on; "0.0.0.0" for every interface
+            port: TCP port; 0 picks a free one (see server_address)
+            token: Bearer token every request must carry, or None for no authentication
+            screen_lock: Lock that refuses /display and /screenshot while locked,
+                         opened with POST /unlock; None for no lock
+
+        Raises:
+            OSError: If the address cannot be bound
+        """
+        self.display = display
+        self.token = token
+        self.screen_lock = screen_lock
+        self.lock = threading.Lock()
+        super().__init__((bind, port), _Handler)
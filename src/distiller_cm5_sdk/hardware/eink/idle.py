@@ -0,0 +1,123 @@
+#!/usr/bin/env python3
+"""
+Idle handling for CM5 SDK.
+
+A panel that keeps one image for days can retain a faint copy of it, and a
+panel left powered between updates wastes energy. IdlePolicy acts once no
+update has happened for a while: it puts the panel into deep sleep, first
+clearing it or showing a screensaver image if asked to. The next update
+wakes the panel again, so applications need no sleep/wake handling:
+
+    display.set_idle_policy(300, IdleAction.SHOW_IMAGE, "/usr/share/kiosk/idle.png")
+"""
+
+import threading
+import time
+from enum import Enum
+from typing import Callable, Optional, Union
+
+from ...diagnostics.audit import AuditCategory, audit
+
+
+POLL_INTERVAL_S = 1.0
+
+
+class IdleAction(Enum):
+    """What the panel shows while idle; it always ends in deep sleep."""
+    SLEEP = "sleep"             # keep the last image
+    CLEAR = "clear"             # clear to white first
+    SHOW_IMAGE = "show_image"   # show a screensaver image first
+
+
+class IdlePolicy:
+    """Puts a Display to sleep after a period without updates."""
+
+    def __init__(self, display, timeout_s: float, action: IdleAction = IdleAction.SLEEP,
+                 image: Optional[Union[str, bytes]] = None, clock: Callable[[], float] = time.monotonic):
+        """
+        Args:
+            display: Display to watch
+            timeout_s: Seconds without updates before acting
+            action: What to do before sleeping
+            image: PNG path or raw 1-bit data for IdleAction.SHOW_IMAGE
+            clock: Monotonic clock, replaceable for testing
+        """
+        self.display = display
+        self.timeout_s = timeout_s
+        self.action = action
+        self.image = image
+        self._clock = clock
+        self._lock = threading.RLock()
+        self._last_activity = clock()
+        self._asleep = False
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    @property
+    def asleep(self) -> bool:
+        """True while the panel sleeps because of this policy."""
+        return self._asleep
+
+    def touch(self) -> None:
+        """Record display activity, restarting the idle timeout."""
+        self._last_activity = self._clock()
+
+    def wake(self) -> None:
+        """Wake the panel if this policy put it to sleep; called before every update."""
+        with self._lock:
+            self.touch()
+            if self._asleep:
+                self.display.wake()
+
+    def slept(self) -> None:
+        """The display was put to sleep, by check() or directly."""
+        self._asleep = True
+
+    def woken(self) -> None:
+        """The display was woken, by wake() or directly."""
+        self._asleep = False
+        self.touch()
+
+    def check(self, now: Optional[float] = None) -> bool:
+        """
+        Act if the display has been idle for the timeout.
+
+        Returns:
+            True if the panel was put to sleep
+        """
+        with self._lock:
+            now = self._clock() if now is None else now
+            if self._asleep or not self.display.is_initialized() or now - self._last_activity < self.timeout_s:
+                return False
+            try:
+                if self.action == IdleAction.CLEAR:
+                    self.display.clear()
+                elif self.action == IdleAction.SHOW_IMAGE:
+                    self.display.display_image(self.image)
+                self.display.sleep()
+            except Exception as e:
+                print(f"Warning: Idle {self.action.value} failed: {e}")
+                self._last_activity = now  # try again after another timeout
+                return False
+            audit(AuditCategory.POWER, "display_idle", self.action.value, timeout_s=self.timeout_s)
+            return True
+
+    def start(self) -> None:
+        """Check for idleness in a background thread."""
+        if self._thread is not None:
+            return
+        self._stop.clear()
+
+        def run() -> None:
+            while not self._stop.wait(min(POLL_INTERVAL_S, self.timeout_s / 4)):
+                self.check()
+
+        self._thread = threading.Thread(target=run, name="eink-idle", daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop checking; a sleeping panel stays asleep until the next update."""
+        self._stop.set()
+        if self._thread is not None and self._thread is not threading.current_thread():
+            self._thread.join(timeout=2)
+        self._thread = None
@@ -0,0 +1,182 @@
+#!/usr/bin/env python3
+"""
+On-disk cache of processed images for CM5 SDK.
+
+Slideshows and dashboards show the same few assets again and again, and
+scaling and dithering each one again costs far more than the refresh.
+ImageCache keeps the 1-bit frames process_image() produced, keyed by a hash
+of the image's content together with the processing options and frame
+size, so an edited file or a changed profile never returns a stale frame.
+The least recently used frames are removed once the cache outgrows its cap.
+
+Set a directory in eink.toml and process_image() uses it everywhere:
+
+    [processing]
+    cache_dir = "/var/cache/distiller-eink"
+    cache_max_mb = 64
+
+or pass one explicitly:
+
+    cache = ImageCache("/tmp/slides", max_bytes=16 * 1024 * 1024)
+    frame = process_image("slide1.jpg", cache=cache)
+"""
+
+import hashlib
+import os
+import struct
+import tempfile
+from typing import Optional, Tuple
+
+from .graphics import Framebuffer
+
+
+DEFAULT_MAX_MB = 64.0
+
+# Part of every key; bump when processing changes, so old frames are not reused
+CACHE_VERSION = 1
+
+_MAGIC = b"EIC1"
+_HEADER = struct.Struct("<4sHH")     # magic, width, height
+_SUFFIX = ".frame"
+
+
+class ImageCache:
+    """Processed frames stored as files in one directory, evicted least recently used first."""
+
+    def __init__(self, directory: str, max_bytes: int = int(DEFAULT_MAX_MB * 1024 * 1024)):
+        """
+        Args:
+            directory: Where frames are kept; created when the first one is stored
+            max_bytes: Size the directory is trimmed to after each store
+        """
+        self.directory = directory
+        self.max_bytes = max_bytes
+        self.hits = 0
+        self.misses = 0
+
+    @staticmethod
+    def key(content: bytes, options, width: int, height: int) -> str:
+        """
+        Cache key of an image processed with options into a width x height frame.
+
+        Args:
+            content: The encoded image
+            options: The ImageOptions used; its fields are part of the key
+        """
+        digest = hashlib.sha256(content)
+        digest.update(repr((CACHE_VERSION, options, width, height)).encode())
+        return digest.hexdigest()
+
+    def _path(self, key: str) -> str:
+        return os.path.join(self.directory, key + _SUFFIX)
+
+    def get(self, key: str) -> Optional[Framebuffer]:
+        """The frame stored under key, or None; a hit makes it the most recently used."""
+        path = self._path(key)
+        try:
+            with open(path, "rb") as f:
+                data = f.read()
+            magic, width, height = _HEADER.unpack_from(data)
+            if magic != _MAGIC:
+                raise ValueError("not a cached frame")
+            frame = Framebuffer(width, height, data[_HEADER.size:])
+        except FileNotFoundError:
+            self.misses += 1
+            return None
+        except (OSError, ValueError, struct.error):
+            # Damaged, e.g. by a full disk; drop it and process the image again
+            self._remove(path)
+            self.misses += 1
+            return None
+        try:
+            os.utime(path)
+        except OSError:
+            pass
+        self.hits += 1
+        return frame
+
+    def put(self, key: str, frame: Framebuffer) -> None:
+        """
+        Store a frame under key, then trim the cache to max_bytes.
+
+        A cache that cannot be written prints a warning; processing goes on without it.
+        """
+        try:
+            os.makedirs(self.directory, exist_ok=True)
+            fd, tmp_path = tempfile.mkstemp(dir=self.directory, prefix=".tmp.")
+            with os.fdopen(fd, "wb") as f:
+                f.write(_HEADER.pack(_MAGIC, frame.width, frame.height) + frame.to_bytes())
+            os.replace(tmp_path, self._path(key))
+        except OSError as e:
+            print(f"Warning: Cannot write image cache {self.directory}: {e}")
+            return
+        self.trim()
+
+    def _entries(self):
+        """(mtime, size, path) of every stored frame."""
+        entries = []
+        try:
+            names = os.listdir(self.directory)
+        except OSError:
+            return entries
+        for name in names:
+            if not name.endswith(_SUFFIX):
+                continue
+            path = os.path.join(self.directory, name)
+            try:
+                st = os.stat(path)
+            except OSError:
+                continue            # removed by another process meanwhile
+            entries.append((st.st_mtime_ns, st.st_size, path))
+        return entries
+
+    def _remove(self, path: str) -> None:
+        try:
+            os.remove(path)
+        except OSError:
+            pass
+
+    def trim(self) -> None:
+        """Remove the least recently used frames until the cache fits max_bytes."""
+        entries = sorted(self._entries())
+        total = sum(size for _, size, _ in entries)
+        for _, size, path in entries:
+            if total <= self.max_bytes:
+                break
+            self._remove(path)
+            total -= size
+
+    def size(self) -> Tuple[int, int]:
+        """Number of stored frames and their total bytes."""
+        entries = self._entries()
+        return len(entries), sum(size for _, size, _ in entries)
+
+    def clear(self) -> None:
+        """Remove every stored frame."""
+        for _, _, path in self._entries():
+            self._remove(path)
+
+
+_default: Optional[ImageCache] = None
+
+
+def default_image_cache() -> Optional[ImageCache]:
+    """
+    The cache set in the display config (processing.cache_dir), or None
+    if none is. A malformed setting prints a warning and disables it.
+    """
+    global _default
+    from .config import load_cache_settings
+    from .display import DisplayError
+    try:
+        directory, max_mb = load_cache_settings()
+    except DisplayError as e:
+        print(f"Warning: {e}; not caching processed images")
+        return None
+    if directory is None:
+        return None
+    max_bytes = int(max_mb * 1024 * 1024)
+    # One instance per setting, so its hit counts add up across calls
+    if _default is None or (_default.directory, _default.max_bytes) != (directory, max_bytes):
+        _default = ImageCache(directory, max_bytes)
+    return _default
@@ -0,0 +1,738 @@
+#!/usr/bin/env python3
+"""
+Image preparation for the e-ink display.
+
+Turns an image of any size into a 1-bit frame for the panel: it is decoded
+to grayscale, scaled to the frame, then dithered or thresholded to black and
+white. PNG works without extra packages; other formats, and better
+downscaling, need Pillow (the "image" extra). SVG needs resvg (the "svg"
+extra) and is rendered at the size it is shown at, not scaled afterwards.
+
+Lanczos downscaling rings around sharp edges, and dithering turns the
+ringing into speckle that can make small text unreadable. For screenshots
+and text, a softer filter plus a little sharpening reads better:
+
+    options = ImageOptions(scaling_filter=ScalingFilter.CATMULL_ROM, sharpen=0.5)
+
+    frame = process_image("photo.jpg", ImageOptions(scaling=ScalingMethod.CROP))
+    display.display_image(frame.to_bytes())
+"""
+
+import io
+import math
+import os
+import re
+from concurrent.futures import ProcessPoolExecutor
+from concurrent.futures.process import BrokenProcessPool
+from dataclasses import dataclass, replace
+from enum import Enum
+from typing import Optional, Tuple, Union
+from xml.etree import ElementTree
+
+from .graphics import PANEL_HEIGHT, PANEL_WIDTH, Color, Framebuffer
+from .patterns import _BAYER_4X4, PatternError, PatternSpec, generate
+from .pngcodec import PNG_SIGNATURE, PNGError, decode_png
+
+
+WHITE_LEVEL = 255
+
+# Parallel error diffusion (ImageOptions.parallel): the frame is cut into a
+# fixed number of bands, one per CM5 core, so the result does not depend on
+# the machine. Each band first diffuses a few rows above it, which carries
+# error across the seam.
+PARALLEL_BANDS = 4
+PARALLEL_OVERLAP_ROWS = 8
+PARALLEL_MIN_PIXELS = 400 * 300     # smaller frames dither faster than worker processes start
+
+# Deskewing (ImageOptions.deskew) looks for text lines tilted by up to this
+# many degrees, first in whole degrees, then in tenths around the best one.
+# Larger tilts are left alone: the page was most likely meant to be turned.
+MAX_DESKEW_DEGREES = 15
+DESKEW_MAX_POINTS = 5000            # dark pixels sampled; more only slows it down
+
+# SVG lengths in CSS pixels, and the size browsers give an SVG without width, height or viewBox
+_SVG_UNITS = {"": 1.0, "px": 1.0, "pt": 4 / 3, "pc": 16.0, "mm": 96 / 25.4, "cm": 96 / 2.54, "in": 96.0}
+SVG_DEFAULT_SIZE = (300.0, 150.0)
+
+
+class ImageError(Exception):
+    """Custom exception for image processing errors."""
+    pass
+
+
+class ScalingMethod(Enum):
+    """How an image is fitted to the frame."""
+    LETTERBOX = "letterbox"     # whole image, as large as fits; white bars fill the rest
+    CROP = "crop"               # fills the frame; the overflow is cut off evenly
+    SMART_CROP = "smart-crop"   # fills the frame; the crop keeps the most detailed part
+    STRETCH = "stretch"         # fills the frame, aspect ratio not kept
+    NONE = "none"               # pixel for pixel, centered; cut off or padded with white
+
+
+class ScalingFilter(Enum):
+    """How pixels are resampled when an image is scaled."""
+    NEAREST = "nearest"             # nearest pixel; keeps hard edges, aliases on downscaling
+    TRIANGLE = "triangle"           # bilinear; soft, no ringing
+    CATMULL_ROM = "catmull-rom"     # bicubic; sharper than triangle, slight ringing
+    LANCZOS3 = "lanczos3"           # sharpest, rings most around edges
+
+
+class DitherMode(Enum):
+    """How gray levels become black and white."""
+    NONE = "none"                           # threshold only; best for text and line art
+    FLOYD_STEINBERG = "floyd-steinberg"     # error diffusion; best for photos
+    ORDERED = "ordered"                     # 4x4 Bayer pattern; regular texture, no crawling between frames
+
+
+@dataclass(frozen=True)
+class ImageOptions:
+    """How process_image() prepares an image."""
+    scaling: ScalingMethod = ScalingMethod.LETTERBOX
+    dither: DitherMode = DitherMode.FLOYD_STEINBERG
+    threshold: int = 128        # gray level below which a pixel is black; shifts the dither the same way
+    invert: bool = False        # swap black and white
+    contrast: float = 1.0       # gray levels are spread (> 1) or squeezed (< 1) around mid-gray first
+    parallel: bool = False      # Floyd-Steinberg in bands on all cores, for large frames
+    scaling_filter: ScalingFilter = ScalingFilter.LANCZOS3
+    sharpen: float = 0.0        # unsharp-mask amount applied after downscaling; 0 leaves the image as scaled
+    pad: Color = Color.WHITE    # color of the margins LETTERBOX and NONE leave
+    # Fill of those margins instead of pad: an image file path or encoded
+    # bytes, tiled from the top-left corner, or a test pattern of frame size
+    background: Union[str, bytes, PatternSpec, None] = None
+    rotate: float = 0.0         # degrees counter-clockwise the image is turned before it is fitted
+    deskew: bool = False        # straighten tilted text lines first, e.g. in a photographed document
+
+
+def load_grayscale(source: Union[str, bytes]) -> Tuple[int, int, bytes]:
+    """
+    Decode an image to 8-bit grayscale, transparent areas white. An SVG is
+    rendered at its own size (see svg_size()).
+
+    Args:
+        source: Image file path or encoded image bytes
+
+    Returns:
+        (width, height, pixels), one byte per pixel, row-major
+
+    Raises:
+        ImageError: If the image cannot be read or decoded
+    """
+    data = _read_image(source)
+    if is_svg(data):
+        svg_width, svg_height = svg_size(data)
+        width, height = max(1, round(svg_width)), max(1, round(svg_height))
+        return width, height, rasterize_svg(data, width, height)
+
+    try:
+        from PIL import Image
+    except ImportError:
+        Image = None
+
+    if Image is not None:
+        try:
+            with Image.open(io.BytesIO(data)) as img:
+                rgba = img.convert("RGBA")
+                background = Image.new("RGBA", rgba.size, (WHITE_LEVEL,) * 4)
+                gray = Image.alpha_composite(background, rgba).convert("L")
+                return gray.width, gray.height, gray.tobytes()
+        except Exception as e:
+            raise ImageError(f"Failed to decode image: {e}")
+
+    if not data.startswith(PNG_SIGNATURE):
+        raise ImageError("Pillow is required for images other than PNG: pip install distiller-cm5-sdk[image]")
+    try:
+        width, height, rgba = decode_png(data)
+    except PNGError as e:
+        raise ImageError(f"Failed to decode PNG: {e}")
+    gray = bytearray(width * height)
+    for i in range(width * height):
+        r, g, b, a = rgba[4 * i:4 * i + 4]
+        luma = (r * 299 + g * 587 + b * 114) // 1000
+        gray[i] = (luma * a + WHITE_LEVEL * (255 - a)) // 255
+    return width, height, bytes(gray)
+
+
+def _read_image(source: Union[str, bytes]) -> bytes:
+    if not isinstance(source, str):
+        return bytes(source)
+    try:
+        with open(source, "rb") as f:
+            return f.read()
+    except OSError as e:
+        raise ImageError(f"Cannot read image {source}: {e}")
+
+
+def is_svg(data: bytes) -> bool:
+    """True if encoded image bytes are an SVG document rather than a bitmap."""
+    head = data[:4096].lstrip(b"\xef\xbb\xbf \t\r\n")
+    return head.startswith((b"<svg", b"<?xml", b"<!--", b"<!DOCTYPE")) and b"<svg" in head
+
+
+def _svg_length(value: Optional[str]) -> Optional[float]:
+    match = re.fullmatch(r"\s*([0-9.]+(?:[eE][+-]?[0-9]+)?)\s*([a-z]*)\s*", value or "")
+    if not match or match.group(2) not in _SVG_UNITS:
+        return None     # missing, or a percentage of a viewport there is none of
+    try:
+        length = float(match.group(1)) * _SVG_UNITS[match.group(2)]
+    except ValueError:
+        return None
+    return length if length > 0 else None
+
+
+def svg_size(data: bytes) -> Tuple[float, float]:
+    """
+    The size an SVG document is drawn at by default, in CSS pixels.
+
+    Taken from the width and height of the root element; a missing one
+    follows from the viewBox aspect ratio, and with neither the viewBox
+    size is used, as a browser would.
+
+    Raises:
+        ImageError: If data is not an SVG document
+    """
+    try:
+        _, root = next(ElementTree.iterparse(io.BytesIO(data), events=("start",)))
+    except (ElementTree.ParseError, StopIteration) as e:
+        raise ImageError(f"Invalid SVG: {e}")
+    if root.tag.rpartition("}")[2] != "svg":
+        raise ImageError(f"Invalid SVG: root element is {root.tag}, not svg")
+    width, height = _svg_length(root.get("width")), _svg_length(root.get("height"))
+    try:
+        view_box = [float(value) for value in re.split(r"[\s,]+", (root.get("viewBox") or "").strip())]
+    except ValueError:
+        view_box = []
+    if len(view_box) == 4 and view_box[2] > 0 and view_box[3] > 0:
+        aspect = view_box[2] / view_box[3]
+        if width is None and height is None:
+            width, height = view_box[2], view_box[3]
+        elif height is None:
+            height = width / aspect
+        elif width is None:
+            width = height * aspect
+    return width or SVG_DEFAULT_SIZE[0], height or SVG_DEFAULT_SIZE[1]
+
+
+def rasterize_svg(data: bytes, width: int, height: int) -> bytes:
+    """
+    Render an SVG document to 8-bit grayscale pixels of exactly width x height,
+    transparent areas white. Needs resvg (the "svg" extra).
+
+    Raises:
+        ImageError: If resvg is missing or cannot render the document
+    """
+    try:
+        import resvg_py
+    except ImportError:
+        raise ImageError("resvg is required for SVG images: pip install distiller-cm5-sdk[svg]")
+    try:
+        png = bytes(resvg_py.svg_to_bytes(svg_string=data.decode("utf-8", errors="replace"),
+                                          width=width, height=height))
+    except Exception as e:
+        raise ImageError(f"Failed to render SVG: {e}")
+    png_width, png_height, pixels = load_grayscale(png)
+    # resvg keeps the aspect ratio the document asks for; STRETCH asks otherwise
+    return resize_grayscale(pixels, png_width, png_height, width, height)
+
+
+def _svg_render_size(data: bytes, options: "ImageOptions", width: int, height: int) -> Tuple[int, int]:
+    """Size to render an SVG at so that, turned and fitted, it needs no scaling."""
+    svg_width, svg_height = svg_size(data)
+    # Other angles resample the image anyway when it is turned
+    sideways = round(options.rotate / 90) % 2 == 1
+    if sideways:
+        svg_width, svg_height = svg_height, svg_width
+    scaled_width, scaled_height, _, _ = fit(max(1, round(svg_width)), max(1, round(svg_height)),
+                                            width, height, options.scaling)
+    return (scaled_height, scaled_width) if sideways else (scaled_width, scaled_height)
+
+
+def fit(width: int, height: int, frame_width: int, frame_height: int,
+        scaling: ScalingMethod) -> Tuple[int, int, int, int]:
+    """
+    Where an image goes in the frame.
+
+    Returns:
+        (scaled_width, scaled_height, x, y): the size to scale the image to and
+        the frame position of its top-left corner, negative when cropped;
+        centered, as SMART_CROP is until smart_crop_position() sees the pixels
+    """
+    if scaling == ScalingMethod.STRETCH:
+        return frame_width, frame_height, 0, 0
+    if scaling == ScalingMethod.NONE:
+        scaled_width, scaled_height = width, height
+    else:
+        pick = min if scaling == ScalingMethod.LETTERBOX else max
+        factor = pick(frame_width / width, frame_height / height)
+        scaled_width = max(1, round(width * factor))
+        scaled_height = max(1, round(height * factor))
+    return scaled_width, scaled_height, (frame_width - scaled_width) // 2, (frame_height - scaled_height) // 2
+
+
+def smart_crop_position(pixels: bytes, width: int, height: int, frame_width: int,
+                        frame_height: int) -> Tuple[int, int]:
+    """
+    Where to place a scaled image that overflows the frame so the frame
+    keeps its most detailed part: the window along the overflowing side
+    with the most edge energy (sum of differences between neighbouring
+    pixels), closest to the center among equals.
+
+    Returns:
+        The frame position of the image's top-left corner, as from fit()
+    """
+    x, y = (frame_width - width) // 2, (frame_height - height) // 2
+    horizontal = width > frame_width
+    if not horizontal and height <= frame_height:
+        return x, y
+    length, window = (width, frame_width) if horizontal else (height, frame_height)
+    energy = [0] * length
+    for row in range(height):
+        base = row * width
+        below = base + width if row + 1 < height else base
+        for column in range(width):
+            level = pixels[base + column]
+            detail = abs(level - pixels[below + column])
+            if column + 1 < width:
+                detail += abs(level - pixels[base + column + 1])
+            energy[column if horizontal else row] += detail
+
+    total = sum(energy[:window])
+    center = (length - window) // 2
+    best, best_total = 0, total
+    for start in range(1, length - window + 1):
+        total += energy[start + window - 1] - energy[start - 1]
+        if total > best_total or (total == best_total and abs(start - center) < abs(best - center)):
+            best, best_total = start, total
+    return (-best, y) if horizontal else (x, -best)
+
+
+def rotate_grayscale(pixels: bytes, width: int, height: int, angle: float,
+                     fill: int = WHITE_LEVEL) -> Tuple[int, int, bytes]:
+    """
+    Rotate 8-bit grayscale pixels counter-clockwise by any angle in degrees,
+    bilinear (bicubic with Pillow). The result is enlarged to hold the whole
+    image, and the corners that uncovers are the fill gray level. Quarter
+    turns are exact.
+
+    Returns:
+        (width, height, pixels) of the rotated image
+    """
+    rest = angle % 90
+    if min(rest, 90 - rest) < 1e-9:
+        return _rotate_quarters(pixels, width, height, int(round(angle / 90)) % 4)
+    try:
+        from PIL import Image
+    except ImportError:
+        Image = None
+    if Image is not None:
+        image = Image.frombytes("L", (width, height), bytes(pixels))
+        rotated = image.rotate(angle, Image.BICUBIC, expand=True, fillcolor=fill)
+        return rotated.width, rotated.height, rotated.tobytes()
+
+    radians = math.radians(angle)
+    cos, sin = math.cos(radians), math.sin(radians)
+    new_width = max(1, math.ceil(abs(width * cos) + abs(height * sin) - 1e-6))
+    new_height = max(1, math.ceil(abs(width * sin) + abs(height * cos) - 1e-6))
+    out = bytearray([fill]) * (new_width * new_height)
+    for y in range(new_height):
+        dy = y + 0.5 - new_height / 2
+        base = y * new_width
+        for x in range(new_width):
+            dx = x + 0.5 - new_width / 2
+            # Where this pixel comes from in the source, by the inverse rotation
+            fx = dx * cos - dy * sin + width / 2 - 0.5
+            fy = dx * sin + dy * cos + height / 2 - 0.5
+            if fx <= -1 or fy <= -1 or fx >= width or fy >= height:
+                continue
+            x0, y0 = math.floor(fx), math.floor(fy)
+            wx, wy = fx - x0, fy - y0
+            corners = []
+            for sy in (y0, y0 + 1):
+                for sx in (x0, x0 + 1):
+                    inside = 0 <= sx < width and 0 <= sy < height
+                    corners.append(pixels[sy * width + sx] if inside else fill)
+            top = corners[0] + (corners[1] - corners[0]) * wx
+            bottom = corners[2] + (corners[3] - corners[2]) * wx
+            out[base + x] = int(top + (bottom - top) * wy + 0.5)
+    return new_width, new_height, bytes(out)
+
+
+def _rotate_quarters(pixels: bytes, width: int, height: int, quarters: int) -> Tuple[int, int, bytes]:
+    if quarters == 0:
+        return width, height, bytes(pixels)
+    if quarters == 2:
+        return width, height, bytes(reversed(pixels))
+    out = bytearray(width * height)
+    for y in range(height):
+        for x in range(width):
+            # Counter-clockwise: the right edge becomes the top
+            target = (width - 1 - x) * height + y if quarters == 1 else x * height + (height - 1 - y)
+            out[target] = pixels[y * width + x]
+    return height, width, bytes(out)
+
+
+def detect_skew(pixels: bytes, width: int, height: int, max_angle: float = MAX_DESKEW_DEGREES) -> float:
+    """
+    Estimate how far the text lines of a document image are tilted: the
+    angle at which the dark pixels, projected onto rows, bunch into the
+    sharpest lines.
+
+    Returns:
+        Degrees counter-clockwise, within max_angle; 0 for an image without
+        enough dark pixels to tell. Rotating by minus this straightens it.
+    """
+    if width < 1 or height < 1:
+        return 0.0
+    threshold = min(128, sum(pixels) / len(pixels) * 0.75)
+    dark = [i for i, level in enumerate(pixels) if level < threshold]
+    if len(dark) < 16:
+        return 0.0
+    step = max(1, len(dark) // DESKEW_MAX_POINTS)
+    points = [(i % width - width / 2, i // width - height / 2) for i in dark[::step]]
+
+    def score(angle: float) -> float:
+        radians = math.radians(angle)
+        cos, sin = math.cos(radians), math.sin(radians)
+        rows = {}
+        for x, y in points:
+            # Row of the point once the tilt is undone
+            row = int(math.floor(x * sin + y * cos))
+            rows[row] = rows.get(row, 0) + 1
+        return sum(count * count for count in rows.values())
+
+    def best(angles) -> float:
+        # Highest score; the smallest tilt among equals, so noise never tilts a straight page
+        return max(angles, key=lambda angle: (score(angle), -abs(angle)))
+
+    coarse = best(range(-int(max_angle), int(max_angle) + 1))
+    fine = best([coarse + tenth / 10 for tenth in range(-10, 11) if abs(coarse + tenth / 10) <= max_angle])
+    return round(fine, 1)
+
+
+def resize_grayscale(pixels: bytes, width: int, height: int, new_width: int, new_height: int,
+                     scaling_filter: ScalingFilter = ScalingFilter.LANCZOS3) -> bytes:
+    """
+    Resize 8-bit grayscale pixels with a resampling filter. Without Pillow,
+    every filter but NEAREST is bilinear.
+    """
+    if (new_width, new_height) == (width, height):
+        return bytes(pixels)
+    if scaling_filter == ScalingFilter.NEAREST:
+        return _resize_nearest(pixels, width, height, new_width, new_height)
+    try:
+        from PIL import Image
+    except ImportError:
+        return _resize_bilinear(pixels, width, height, new_width, new_height)
+    resample = {ScalingFilter.TRIANGLE: Image.BILINEAR, ScalingFilter.CATMULL_ROM: Image.BICUBIC,
+                ScalingFilter.LANCZOS3: Image.LANCZOS}[scaling_filter]
+    image = Image.frombytes("L", (width, height), bytes(pixels))
+    return image.resize((new_width, new_height), resample).tobytes()
+
+
+def _resize_nearest(pixels: bytes, width: int, height: int, new_width: int, new_height: int) -> bytes:
+    out = bytearray(new_width * new_height)
+    columns = [min(width - 1, (2 * x + 1) * width // (2 * new_width)) for x in range(new_width)]
+    for y in range(new_height):
+        row = min(height - 1, (2 * y + 1) * height // (2 * new_height)) * width
+        base = y * new_width
+        for x, column in enumerate(columns):
+            out[base + x] = pixels[row + column]
+    return bytes(out)
+
+
+def _resize_bilinear(pixels: bytes, width: int, height: int, new_width: int, new_height: int) -> bytes:
+    out = bytearray(new_width * new_height)
+    x_samples = []
+    for x in range(new_width):
+        fx = min(max((x + 0.5) * width / new_width - 0.5, 0.0), width - 1)
+        x0 = int(fx)
+        x_samples.append((x0, min(x0 + 1, width - 1), fx - x0))
+    for y in range(new_height):
+        fy = min(max((y + 0.5) * height / new_height - 0.5, 0.0), height - 1)
+        y0 = int(fy)
+        row0 = y0 * width
+        row1 = min(y0 + 1, height - 1) * width
+        wy = fy - y0
+        base = y * new_width
+        for x, (x0, x1, wx) in enumerate(x_samples):
+            top = pixels[row0 + x0] + (pixels[row0 + x1] - pixels[row0 + x0]) * wx
+            bottom = pixels[row1 + x0] + (pixels[row1 + x1] - pixels[row1 + x0]) * wx
+            out[base + x] = int(top + (bottom - top) * wy + 0.5)
+    return bytes(out)
+
+
+def sharpen_grayscale(pixels: bytes, width: int, height: int, amount: float) -> bytes:
+    """
+    Unsharp mask: push each pixel away from its 3x3 neighbourhood's weighted
+    mean by amount times the difference, e.g. 0.5 to restore edges softened
+    by downscaling. Edge pixels repeat outwards.
+    """
+    if amount <= 0 or width < 1 or height < 1:
+        return bytes(pixels)
+    # Separable 1-2-1 blur, rows then columns; sums are 16 times the mean
+    rows = [0] * (width * height)
+    for y in range(height):
+        base = y * width
+        for x in range(width):
+            rows[base + x] = (pixels[base + max(x - 1, 0)] + 2 * pixels[base + x]
+                              + pixels[base + min(x + 1, width - 1)])
+    out = bytearray(width * height)
+    for y in range(height):
+        above = max(y - 1, 0) * width
+        base = y * width
+        below = min(y + 1, height - 1) * width
+        for x in range(width):
+            blurred = (rows[above + x] + 2 * rows[base + x] + rows[below + x]) / 16
+            level = pixels[base + x]
+            out[base + x] = min(max(int(level + (level - blurred) * amount + 0.5), 0), WHITE_LEVEL)
+    return bytes(out)
+
+
+def background_grayscale(background: Union[str, bytes, PatternSpec], frame_width: int, frame_height: int) -> bytes:
+    """
+    A frame of 8-bit grayscale filled with a background: an image tiled from
+    the top-left corner, or a test pattern generated at the frame's size.
+
+    Raises:
+        ImageError: If the image cannot be decoded or the pattern is invalid
+    """
+    if isinstance(background, PatternSpec):
+        try:
+            fb = generate(background, frame_width, frame_height)
+        except PatternError as e:
+            raise ImageError(f"Invalid background pattern: {e}")
+        return bytes(WHITE_LEVEL if fb.get_pixel(x, y) == Color.WHITE else 0
+                     for y in range(frame_height) for x in range(frame_width))
+    width, height, pixels = load_grayscale(background)
+    frame = bytearray(frame_width * frame_height)
+    for row in range(frame_height):
+        line = pixels[(row % height) * width:(row % height + 1) * width]
+        tiled = line * (frame_width // width + 1)
+        frame[row * frame_width:(row + 1) * frame_width] = tiled[:frame_width]
+    return bytes(frame)
+
+
+def place_grayscale(pixels: bytes, width: int, height: int, frame_width: int, frame_height: int,
+                    x: int, y: int, fill: int = WHITE_LEVEL, background: Optional[bytes] = None) -> bytes:
+    """
+    Put grayscale pixels into a frame with their top-left corner at (x, y),
+    clipped. The rest of the frame is the fill gray level, or background,
+    a frame of grayscale pixels, if given.
+    """
+    if background is not None:
+        frame = bytearray(background)
+    else:
+        frame = bytearray([fill]) * (frame_width * frame_height)
+    left, right = max(0, x), min(frame_width, x + width)
+    if left >= right:
+        return bytes(frame)
+    for row in range(max(0, y), min(frame_height, y + height)):
+        src = (row - y) * width + (left - x)
+        frame[row * frame_width + left:row * frame_width + right] = pixels[src:src + right - left]
+    return bytes(frame)
+
+
+def _diffuse(pixels: bytes, width: int, height: int, threshold: int) -> bytearray:
+    """Floyd-Steinberg error diffusion; 1 where the pixel stays white."""
+    white = bytearray(width * height)
+    errors = [0] * (width + 2)
+    for y in range(height):
+        next_errors = [0] * (width + 2)
+        base = y * width
+        for x in range(width):
+            level = pixels[base + x] + errors[x + 1] // 16
+            value = WHITE_LEVEL if level >= threshold else 0
+            white[base + x] = value != 0
+            error = level - value
+            errors[x + 2] += error * 7
+            next_errors[x] += error * 3
+            next_errors[x + 1] += error * 5
+            next_errors[x + 2] += error
+        errors = next_errors
+    return white
+
+
+def _diffuse_band(band: Tuple[bytes, int, int, int]) -> bytearray:
+    """Diffuse a band whose first lead rows only carry error into it; run in a worker process."""
+    pixels, width, lead, threshold = band
+    white = _diffuse(pixels, width, len(pixels) // width, threshold)
+    return white[lead * width:]
+
+
+def _diffuse_parallel(pixels: bytes, width: int, height: int, threshold: int) -> bytearray:
+    """_diffuse() in PARALLEL_BANDS bands, on as many worker processes as there are cores."""
+    bounds = [height * i // PARALLEL_BANDS for i in range(PARALLEL_BANDS + 1)]
+    bands = []
+    for start, stop in zip(bounds, bounds[1:]):
+        top = max(start - PARALLEL_OVERLAP_ROWS, 0)
+        bands.append((pixels[top * width:stop * width], width, start - top, threshold))
+    try:
+        with ProcessPoolExecutor(min(PARALLEL_BANDS, os.cpu_count() or 1)) as pool:
+            parts = list(pool.map(_diffuse_band, bands))
+    except (OSError, BrokenProcessPool) as e:
+        # The bands are the same either way, so the frame is too
+        print(f"Warning: Cannot start dithering workers ({e}); dithering the bands in turn")
+        parts = [_diffuse_band(band) for band in bands]
+    return bytearray().join(parts)
+
+
+def dither_grayscale(pixels: bytes, width: int, height: int, mode: DitherMode = DitherMode.FLOYD_STEINBERG,
+                     threshold: int = 128, parallel: bool = False) -> Framebuffer:
+    """
+    Reduce 8-bit grayscale pixels to a 1-bit frame.
+
+    Args:
+        pixels: One byte per pixel, row-major, 0 black to 255 white
+        width: Width in pixels
+        height: Height in pixels
+        mode: Dithering method
+        threshold: Gray level below which a pixel is black (128 is neutral)
+        parallel: Diffuse error in bands on worker processes, for frames of
+                  PARALLEL_MIN_PIXELS or more; the pattern differs slightly
+                  from serial dithering near the seams
+    """
+    if mode == DitherMode.FLOYD_STEINBERG:
+        if parallel and width * height >= PARALLEL_MIN_PIXELS:
+            white = _diffuse_parallel(pixels, width, height, threshold)
+        else:
+            white = _diffuse(pixels, width, height, threshold)
+    elif mode == DitherMode.ORDERED:
+        white = bytearray(width * height)   # 1 where the pixel stays white
+        shift = threshold - 128
+        for y in range(height):
+            base = y * width
+            row = _BAYER_4X4[y % 4]
+            for x in range(width):
+                white[base + x] = pixels[base + x] >= (2 * row[x % 4] + 1) * 8 + shift
+    else:
+        white = bytearray(width * height)
+        for i, level in enumerate(pixels):
+            white[i] = level >= threshold
+
+    packed = bytearray((width * height + 7) // 8)
+    for i in range(0, width * height, 8):
+        byte = 0
+        for bit, value in enumerate(white[i:i + 8]):
+            if value:
+                byte |= 0x80 >> bit
+        packed[i // 8] = byte
+    return Framebuffer(width, height, packed)
+
+
+def adjust_contrast(pixels: bytes, contrast: float) -> bytes:
+    """Spread (contrast > 1) or squeeze (< 1) 8-bit gray levels around mid-gray."""
+    if contrast == 1.0:
+        return pixels
+    table = bytes(min(max(round((level - 128) * contrast + 128), 0), WHITE_LEVEL)
+                  for level in range(WHITE_LEVEL + 1))
+    return pixels.translate(table)
+
+
+def default_image_options(handle: Optional["DisplayConfigHandle"] = None) -> ImageOptions:
+    """
+    The device's processing profile: threshold and contrast from the
+    display config, as saved by the calibration wizard, and dither, with the
+    other options at their defaults. A malformed setting prints a warning
+    and is ignored.
+
+    Args:
+        handle: Settings to take the profile from instead; if None, those of
+                the active DisplayConfigHandle.scope(), if any
+    """
+    from .config import current_config_handle, load_image_profile
+    from .display import DisplayError
+    handle = handle if handle is not None else current_config_handle()
+    if handle is not None:
+        return handle.image_options
+    try:
+        return load_image_profile()
+    except DisplayError as e:
+        print(f"Warning: {e}; using the default image processing")
+        return ImageOptions()
+
+
+def process_image(source: Union[str, bytes], options: Optional[ImageOptions] = None,
+                  width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT,
+                  cache: Union["ImageCache", bool, None] = None) -> Framebuffer:
+    """
+    Prepare an image of any size as a 1-bit frame. An SVG is rendered at the
+    size it is fitted to, so its edges stay as sharp as the panel allows.
+
+    Args:
+        source: Image file path or encoded image bytes
+        options: Scaling, dithering and threshold, or None for default_image_options()
+        width: Frame width, e.g. Display.get_dimensions()[0]
+        height: Frame height
+        cache: Where frames already processed are looked up and stored; None
+               for the configured one (see imagecache.default_image_cache()),
+               False to process the image regardless
+
+    Raises:
+        ImageError: If the image cannot be decoded
+    """
+    options = options or default_image_options()
+    if cache is None:
+        from .imagecache import default_image_cache
+        cache = default_image_cache()
+    if not cache:
+        return _process(source, options, width, height)
+    source = _read_image(source)
+    if isinstance(options.background, str):
+        # Keyed by content like the image, so an edited background is not served stale
+        try:
+            with open(options.background, "rb") as f:
+                options = replace(options, background=f.read())
+        except OSError as e:
+            raise ImageError(f"Cannot read background {options.background}: {e}")
+    key = cache.key(source, options, width, height)
+    frame = cache.get(key)
+    if frame is None:
+        frame = _process(source, options, width, height)
+        cache.put(key, frame)
+    return frame
+
+
+def _process(source: Union[str, bytes], options: ImageOptions, width: int, height: int) -> Framebuffer:
+    data = _read_image(source)
+    if is_svg(data):
+        src_width, src_height = _svg_render_size(data, options, width, height)
+        pixels = rasterize_svg(data, src_width, src_height)
+    else:
+        src_width, src_height, pixels = load_grayscale(data)
+    return process_grayscale(pixels, src_width, src_height, options, width, height)
+
+
+def process_grayscale(pixels: bytes, src_width: int, src_height: int, options: Optional[ImageOptions] = None,
+                      width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT) -> Framebuffer:
+    """
+    Prepare already decoded 8-bit grayscale pixels as a 1-bit frame, e.g. a camera frame.
+
+    Args:
+        pixels: One byte per pixel, row-major, as from load_grayscale()
+        src_width: Width of the pixels
+        src_height: Height of the pixels
+        options: As for process_image(); None for default_image_options()
+        width: Frame width
+        height: Frame height
+
+    Raises:
+        ImageError: If pixels is not src_width x src_height, or the background cannot be drawn
+    """
+    options = options or default_image_options()
+    if src_width < 1 or src_height < 1 or len(pixels) != src_width * src_height:
+        raise ImageError(f"Expected {src_width}x{src_height} grayscale pixels, got {len(pixels)} bytes")
+    pad = WHITE_LEVEL if options.pad == Color.WHITE else 0
+    angle = options.rotate - (detect_skew(pixels, src_width, src_height) if options.deskew else 0.0)
+    if angle % 360:
+        src_width, src_height, pixels = rotate_grayscale(pixels, src_width, src_height, angle, pad)
+    scaled_width, scaled_height, x, y = fit(src_width, src_height, width, height, options.scaling)
+    pixels = resize_grayscale(pixels, src_width, src_height, scaled_width, scaled_height, options.scaling_filter)
+    if options.scaling == ScalingMethod.SMART_CROP:
+        x, y = smart_crop_position(pixels, scaled_width, scaled_height, width, height)
+    if options.sharpen > 0 and scaled_width * scaled_height < src_width * src_height:
+        pixels = sharpen_grayscale(pixels, scaled_width, scaled_height, options.sharpen)
+    pixels = adjust_contrast(pixels, options.contrast)     # before the margins are added, so they keep their color
+    background = background_grayscale(options.background, width, height) if options.background is not None else None
+    pixels = place_grayscale(pixels, scaled_width, scaled_height, width, height, x, y, pad, background)
+    if options.invert:
+        pixels = bytes(WHITE_LEVEL - level for level in pixels)
+    return dither_grayscale(pixels, width, height, options.dither, options.threshold, options.parallel)
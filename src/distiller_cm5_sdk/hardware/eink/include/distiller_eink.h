@@ -0,0 +1,713 @@
+/*
+ * distiller_eink.h - Distiller CM5 e-ink display library, SDK 0.1.0, ABI 2
+ *
+ * Generated from lib/distiller_display_sdk.h by lib/gen_header.py; do not edit.
+ * Link with -ldistiller_display_sdk_shared and compare display_sdk_abi_version()
+ * with DISPLAY_SDK_ABI_VERSION before any other call.
+ */
+#ifndef DISTILLER_EINK_H
+#define DISTILLER_EINK_H
+
+#ifndef DISTILLER_DISPLAY_SDK_H
+#define DISTILLER_DISPLAY_SDK_H
+
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * ABI version of this library.
+ *
+ * Policy: public structs are append-only. New fields go at the end and the
+ * library fills defaults for fields a caller's (older) struct_size doesn't
+ * cover. Reordering, removing or retyping a field, or changing a function
+ * signature, requires bumping DISPLAY_SDK_ABI_VERSION.
+ *
+ * Callers should compare display_sdk_abi_version() with the value they were
+ * compiled against before making any other call.
+ *
+ * 2: Functions that succeed or fail return 0 or a negative display_error_t
+ *    instead of bool (see display_get_last_error_message()).
+ */
+#define DISPLAY_SDK_ABI_VERSION 2
+
+/*
+ * Release of the SDK this library belongs to, the version of the Python
+ * package it ships in. Unlike the ABI version it changes with every release;
+ * lib/gen_header.py checks the two stay in step when generating
+ * include/distiller_eink.h.
+ */
+#define DISPLAY_SDK_VERSION "0.1.0"
+
+// Display dimensions for e-ink display. This library drives the 2.13"
+// 128x250 panel only; the other panels in panels.py (2.9" 296x128, 4.2"
+// 400x300) are driven by the Python backend.
+#define EPD_WIDTH   128
+#define EPD_HEIGHT  250
+#define EPD_ARRAY   ((EPD_WIDTH * EPD_HEIGHT) / 8)  // 4000 bytes for 1-bit
+
+// Default wiring of the e-ink display (see display_set_spi_config())
+#define SPI_BUS         0
+#define SPI_CS          0
+#define SPI_SPEED_HZ    40000000
+#define GPIO_CHIP_NAME  "gpiochip0"
+#define SPI_BLOCK_SIZE  4096   // spidev's default bufsiz
+#define DC_PIN    7   // Data/Command control
+#define RST_PIN   13  // Reset
+#define BUSY_PIN  9   // Busy status
+#define CS_PIN    8   // Chip select
+
+// Default time to wait for the busy line to release, and how many times a
+// timed-out update is retried after a hardware reset (see display_set_busy_policy())
+#define BUSY_TIMEOUT_MS 10000
+#define BUSY_RETRIES    1
+
+// Most changed pixels display_image_auto() still refreshes partially (12.5% of the panel)
+#define AUTO_PARTIAL_MAX_PIXELS  ((EPD_WIDTH * EPD_HEIGHT) / 8)
+
+// Display modes
+typedef enum {
+    DISPLAY_MODE_FULL,     // Full refresh (slow, high quality)
+    DISPLAY_MODE_PARTIAL   // Partial refresh (fast, good quality)
+} display_mode_t;
+
+// Rotation applied to caller frames before they reach the panel.
+// With a 90-degree rotation, callers draw in a 250x128 landscape frame.
+typedef enum {
+    DISPLAY_ROTATION_NONE,     // Portrait 128x250, as the panel is wired
+    DISPLAY_ROTATION_CCW_90,   // Landscape frame rotated 90 degrees counter-clockwise
+    DISPLAY_ROTATION_180,      // Portrait frame rotated 180 degrees
+    DISPLAY_ROTATION_CW_90     // Landscape frame rotated 90 degrees clockwise
+} display_rotation_t;
+
+// Image format
+typedef enum {
+    IMAGE_FORMAT_RAW,      // Raw 1-bit packed data
+    IMAGE_FORMAT_PNG       // PNG file (will be converted to 1-bit)
+} image_format_t;
+
+// Pixel layouts accepted for raw pixel-buffer input
+typedef enum {
+    PIXEL_FORMAT_GRAY8,     // 1 byte per pixel, luminance
+    PIXEL_FORMAT_RGB888,    // 3 bytes per pixel, R G B
+    PIXEL_FORMAT_RGBA8888   // 4 bytes per pixel, R G B A (alpha ignored)
+} pixel_format_t;
+
+// Image processing options applied before display
+typedef struct {
+    uint32_t struct_size;    // sizeof(processing_options_t) as compiled by the caller
+    uint8_t threshold;       // Gray levels above this become white (default 128)
+    bool rotate;             // Rotate landscape source (250x128) 90 degrees CCW to portrait
+    bool flip_horizontal;    // Mirror the image left-right
+    bool invert_colors;      // Swap black and white
+} processing_options_t;
+
+// SPI device and GPIO lines used by display_init()
+typedef struct {
+    uint32_t struct_size;    // sizeof(display_spi_config_t) as compiled by the caller
+    uint32_t bus;            // Opens /dev/spidev<bus>.<cs>
+    uint32_t cs;
+    uint32_t speed_hz;       // SPI clock
+    uint32_t dc_pin;         // GPIO line offsets on gpio_chip
+    uint32_t reset_pin;
+    uint32_t busy_pin;
+    char gpio_chip[32];      // GPIO chip name, NUL-terminated
+    uint32_t block_size;     // Largest single SPI transfer; frames are sent in blocks of this size
+    uint32_t chunk_delay_us; // Pause between blocks (default 0)
+} display_spi_config_t;
+
+// Status codes for structured results
+typedef enum {
+    DISPLAY_STATUS_OK = 0,
+    DISPLAY_STATUS_INVALID_ARGUMENT,   // NULL or malformed argument
+    DISPLAY_STATUS_INIT_FAILED,        // Hardware initialization failed
+    DISPLAY_STATUS_LOAD_FAILED,        // Image could not be read or decoded
+    DISPLAY_STATUS_PROCESS_FAILED,     // Image could not be converted for the panel
+    DISPLAY_STATUS_UPDATE_FAILED,      // Panel update failed
+    DISPLAY_STATUS_TIMEOUT,            // Busy line stayed asserted, even after reset and retries
+    DISPLAY_STATUS_ABORTED             // Update cancelled by display_abort() or display_emergency_frame()
+} display_status_t;
+
+// Error codes returned by functions that can fail: DISPLAY_OK (0) on success,
+// a negative value on failure. Test with `< 0`, not as a bool.
+typedef enum {
+    DISPLAY_OK = 0,
+    DISPLAY_ERROR_INVALID_ARGUMENT = -1,   // NULL or malformed argument
+    DISPLAY_ERROR_NOT_INITIALIZED = -2,    // display_init() has not succeeded
+    DISPLAY_ERROR_INVALID_SIZE = -3,       // Image dimensions don't match the frame
+    DISPLAY_ERROR_CONFIG = -4,             // Invalid configuration, or set at the wrong time
+    DISPLAY_ERROR_SPI = -5,                // SPI device could not be opened, configured or written
+    DISPLAY_ERROR_GPIO = -6,               // GPIO chip or lines unavailable
+    DISPLAY_ERROR_TIMEOUT = -7,            // Busy line stayed asserted, even after reset and retries
+    DISPLAY_ERROR_ABORTED = -8,            // Update cancelled by display_abort() or display_emergency_frame()
+    DISPLAY_ERROR_DECODE = -9,             // Image could not be read or decoded
+    DISPLAY_ERROR_NO_MEMORY = -10,         // Allocation failed
+    DISPLAY_ERROR_NO_FRAME = -11,          // No frame displayed to read back
+    DISPLAY_ERROR_UNSUPPORTED = -12        // The panel or its link cannot do this, e.g. read its temperature
+} display_error_t;
+
+// Structured result carrying a status and a human-readable message
+typedef struct {
+    display_status_t status;
+    char message[128];
+} display_result_t;
+
+/**
+ * Get the ABI version the library was built with
+ * @return DISPLAY_SDK_ABI_VERSION of the loaded library
+ */
+uint32_t display_sdk_abi_version(void);
+
+/**
+ * Get the SDK release the library was built from
+ * @return DISPLAY_SDK_VERSION of the loaded library, e.g. "0.1.0"
+ */
+const char* display_sdk_version(void);
+
+/**
+ * Get the error of the most recent failed call on the calling thread. Like
+ * errno, it is only set by failures, so check it right after a call returned
+ * a negative code (or NULL, for display_open()).
+ * @return A negative display_error_t, or DISPLAY_OK if no call has failed
+ */
+int display_get_last_error(void);
+
+/**
+ * Describe the most recent failed call on the calling thread, e.g.
+ * "PNG dimensions (250x128) don't match display (128x250)"
+ * @param buf Receives the NUL-terminated message, truncated to fit; may be
+ *            NULL if len is 0
+ * @param len Size of buf in bytes
+ * @return Length of the whole message, excluding the NUL (as snprintf())
+ */
+size_t display_get_last_error_message(char* buf, size_t len);
+
+/**
+ * Get a short description of an error code, e.g. "busy timeout"
+ * @param error DISPLAY_OK or a negative display_error_t
+ * @return Static string, "unknown error" for other values
+ */
+const char* display_error_string(int error);
+
+/**
+ * Initialize the display SDK
+ * @return DISPLAY_OK on success, or a negative display_error_t
+ */
+int display_init(void);
+
+/**
+ * Display an image from raw 1-bit packed data
+ * @param data Pointer to 1-bit packed image data (EPD_ARRAY bytes)
+ * @param mode Display mode (full or partial refresh)
+ * @return DISPLAY_OK on success, or a negative display_error_t
+ */
+int display_image_raw(const uint8_t* data, display_mode_t mode);
+
+/**
+ * Display an image from PNG file
+ * @param filename Path to PNG file
+ * @param mode Display mode (full or partial refresh)
+ * @return DISPLAY_OK on success, or a negative display_error_t
+ */
+int display_image_png(const char* filename, display_mode_t mode);
+
+/**
+ * Clear the display (set to white)
+ * @return DISPLAY_OK on success, or a negative display_error_t
+ */
+int display_clear(void);
+
+/**
+ * Put display to sleep (power saving). The last frame stays on the panel and
+ * is kept by the library: the next update wakes the panel by itself and
+ * restores that frame as the partial-refresh reference, so display_init()
+ * need not be called again.
+ */
+void display_sleep(void);
+
+/**
+ * Reset the panel and re-run its init sequence, restoring the last frame as
+ * the partial-refresh reference. Wakes the panel from display_sleep() ahead
+ * of the next update (which would otherwise wake it) and resumes updates
+ * stopped by display_abort() or display_emergency_frame(). Runs
+ * display_init() if not initialized.
+ * @return DISPLAY_OK on success, or DISPLAY_ERROR_TIMEOUT if the panel stayed
+ *         busy during the reset
+ */
+int display_wake(void);
+
+/**
+ * Check whether the panel is in deep sleep
+ * @return true between display_sleep() and the next update or display_wake()
+ */
+bool display_is_asleep(void);
+
+/**
+ * Cleanup and shutdown display
+ */
+void display_cleanup(void);
+
+/**
+ * Set the rotation applied to every frame (raw, PNG, memory and pixel input)
+ * @param rotation Rotation from the caller's frame to the panel
+ * @return DISPLAY_OK on success, or DISPLAY_ERROR_INVALID_ARGUMENT if rotation is invalid
+ */
+int display_set_rotation(display_rotation_t rotation);
+
+/**
+ * Get the active rotation
+ * @return Current rotation
+ */
+display_rotation_t display_get_rotation(void);
+
+/**
+ * Get display dimensions as seen by callers (swapped for 90-degree rotations)
+ * @param width Pointer to store width
+ * @param height Pointer to store height
+ */
+void display_get_dimensions(uint32_t* width, uint32_t* height);
+
+/**
+ * Set how long to wait for the busy line and how to recover from a timeout.
+ * A frame update that times out is retried up to retries times, each after a
+ * hardware reset and re-init, before failing with DISPLAY_STATUS_TIMEOUT.
+ * @param timeout_ms Maximum wait for one busy period (must be non-zero)
+ * @param retries Retries after a timeout (0 fails immediately)
+ * @return DISPLAY_OK, or DISPLAY_ERROR_CONFIG if timeout_ms is 0
+ */
+int display_set_busy_policy(uint32_t timeout_ms, uint32_t retries);
+
+/**
+ * Get the status of the most recent display_init() or frame update
+ * @return DISPLAY_STATUS_OK, DISPLAY_STATUS_TIMEOUT if the panel stayed busy,
+ *         or another status describing the failure
+ */
+display_status_t display_get_last_status(void);
+
+/**
+ * Display raw 1-bit data, choosing the refresh mode from how much changed.
+ * The frame is compared with the last frame displayed: if at most the auto
+ * threshold of pixels differ it is refreshed partially, otherwise fully. The
+ * first frame after display_init() is always a full refresh.
+ * @param data Raw image data in the caller's frame (EPD_ARRAY bytes)
+ * @param mode_used Optional pointer to store the mode chosen
+ * @return DISPLAY_OK on success, or a negative display_error_t
+ */
+int display_image_auto(const uint8_t* data, display_mode_t* mode_used);
+
+/**
+ * Set the most changed pixels display_image_auto() refreshes partially
+ * @param max_pixels Changed pixel count threshold (0 always refreshes fully)
+ */
+void display_set_auto_threshold(uint32_t max_pixels);
+
+/**
+ * Copy the last frame successfully sent to the panel
+ * @param out Buffer of EPD_ARRAY bytes, filled in the panel's native orientation
+ * @return DISPLAY_OK if a frame was copied, DISPLAY_ERROR_NO_FRAME if none has
+ *         been displayed since display_init() or the last update failed
+ */
+int display_get_framebuffer(uint8_t* out);
+
+/**
+ * Take data as the frame the panel shows, without refreshing: it becomes
+ * the frame display_get_framebuffer() returns and partial refreshes compare
+ * against, and is written to the controller's RAM. For a panel that kept its
+ * image while its controller lost power, e.g. after its power rail was cut
+ * @param data EPD_ARRAY bytes in the panel's native orientation, as from
+ *             display_get_framebuffer()
+ * @return DISPLAY_OK, DISPLAY_ERROR_ABORTED after display_abort(),
+ *         DISPLAY_ERROR_SPI if writing the RAM failed (the frame is then
+ *         not kept), or another negative display_error_t
+ */
+int display_restore_frame(const uint8_t* data);
+
+// RAM planes of the controller
+typedef enum {
+    DISPLAY_PLANE_NEW,   // the frame the next refresh shows (command 0x24)
+    DISPLAY_PLANE_OLD    // the frame a partial refresh compares against (command 0x26)
+} display_plane_t;
+
+/**
+ * Write a frame to one of the controller's RAM planes without refreshing.
+ * Writing DISPLAY_PLANE_NEW stages the frame for display_flip(), so the next
+ * frame can be sent while the application is idle and shown at once when
+ * needed, e.g. on a menu key press. Writing DISPLAY_PLANE_OLD sets what a
+ * partial refresh compares against. Any other update, display_wake() or
+ * display_restore_frame() discards the staged frame. Wakes a sleeping panel.
+ * @param plane RAM plane to write
+ * @param data EPD_ARRAY bytes in the frame orientation of display_image_raw()
+ * @return DISPLAY_OK, or a negative display_error_t
+ */
+int display_write_plane(display_plane_t plane, const uint8_t* data);
+
+/**
+ * Refresh the panel with the frame staged by display_write_plane(), without
+ * sending it again. A busy timeout resets the controller and sends it as
+ * display_image_raw() would.
+ * @param mode Refresh mode; DISPLAY_MODE_PARTIAL for the quickest flip
+ * @return DISPLAY_OK, DISPLAY_ERROR_NO_FRAME if no frame is staged, or
+ *         another negative display_error_t
+ */
+int display_flip(display_mode_t mode);
+
+/**
+ * Abort the frame update in flight, if any, and refuse further updates.
+ * The aborted update stops waiting on the panel and returns
+ * DISPLAY_ERROR_ABORTED without resetting or retrying; later updates fail
+ * the same way until display_init() or display_wake() runs again. Safe to call from another
+ * thread or a signal handler, e.g. when power loss is imminent.
+ */
+void display_abort(void);
+
+/**
+ * Fast path for imminent power loss: abort any update in flight, show a
+ * final frame with a full refresh ahead of any update waiting to run, and
+ * put the panel into deep sleep. Later updates fail as after display_abort().
+ * @param data Raw image data in the caller's frame (EPD_ARRAY bytes), or
+ *             NULL to only put the panel to sleep
+ * @return DISPLAY_OK if the frame was shown (or data was NULL), or a negative
+ *         display_error_t
+ */
+int display_emergency_frame(const uint8_t* data);
+
+/**
+ * Get measured busy-line wait durations
+ * @param last_ms Pointer to store the duration of the most recent busy wait
+ * @param total_ms Pointer to store the cumulative busy time since init
+ */
+void display_get_busy_stats(uint32_t* last_ms, uint64_t* total_ms);
+
+// Driver counters since display_init(), for diagnosing slow updates and panel aging
+typedef struct {
+    uint32_t struct_size;          // sizeof(display_stats_t) as compiled by the caller
+    uint32_t last_refresh_ms;      // duration of the most recent successful refresh, retries included
+    uint64_t full_refreshes;       // completed full refreshes
+    uint64_t partial_refreshes;    // completed partial refreshes
+    uint64_t failed_refreshes;     // refreshes that failed, timed out or were aborted
+    uint64_t total_busy_ms;        // cumulative busy-line wait, as display_get_busy_stats()
+    uint32_t last_busy_ms;         // most recent busy-line wait
+    uint32_t average_spi_us;       // total_spi_us / spi_transfers, 0 before the first transfer
+    uint64_t spi_transfers;        // SPI transfers, one per command, data byte or block
+    uint64_t spi_bytes;            // bytes sent over SPI
+    uint64_t total_spi_us;         // time spent in SPI transfers
+} display_stats_t;
+
+/**
+ * Get refresh counts, busy time and SPI timing since display_init()
+ * @param stats Filled in up to stats->struct_size, which the caller sets
+ * @return DISPLAY_OK on success, DISPLAY_ERROR_INVALID_ARGUMENT if stats is
+ *         NULL or its struct_size is too small
+ */
+int display_get_stats(display_stats_t* stats);
+
+// Refresh lifecycle events reported to the event callback
+typedef enum {
+    DISPLAY_EVENT_REFRESH_STARTED,     // a frame is about to be sent to the panel
+    DISPLAY_EVENT_REFRESH_COMPLETED,   // the panel finished refreshing
+    DISPLAY_EVENT_PANEL_SLEEP,         // the panel entered deep sleep
+    DISPLAY_EVENT_PANEL_WAKE,          // the panel left deep sleep
+    DISPLAY_EVENT_ERROR,               // a refresh or wake failed
+    DISPLAY_EVENT_THROTTLE_STARTED,    // reserved for the Python SDK's thermal policy; never sent by this library
+    DISPLAY_EVENT_THROTTLE_ENDED,
+    DISPLAY_EVENT_CONFIG_RELOADED      // reserved for the Python SDK's config reload; never sent by this library
+} display_event_type_t;
+
+typedef struct {
+    uint32_t struct_size;          // sizeof(display_event_t) of the library
+    display_event_type_t type;
+    uint32_t display_id;           // display_get_id() of the panel
+    display_mode_t mode;           // refresh mode, for refresh events
+    uint32_t duration_ms;          // time taken, for REFRESH_COMPLETED, PANEL_WAKE and ERROR
+    int error;                     // display_error_t, for ERROR
+    const char* message;           // error message for ERROR, else NULL; valid during the callback only
+} display_event_t;
+
+typedef void (*display_event_callback_t)(const display_event_t* event, void* user_data);
+
+/**
+ * Report refresh lifecycle events, e.g. to show a busy indicator or trace
+ * update latency. Every REFRESH_STARTED is followed by REFRESH_COMPLETED or
+ * ERROR. The callback runs on the thread doing the update while the panel is
+ * held, so it must return quickly and must not call into the same panel.
+ * @param callback Function to call, or NULL to stop reporting
+ * @param user_data Passed to every call of callback
+ */
+void display_set_event_callback(display_event_callback_t callback, void* user_data);
+
+// Refreshes whose waveform display_set_lut() replaces
+typedef enum {
+    DISPLAY_LUT_FULL,      // full refreshes
+    DISPLAY_LUT_PARTIAL    // partial refreshes
+} display_lut_kind_t;
+
+// Built-in waveforms for display_set_lut_preset()
+typedef enum {
+    DISPLAY_LUT_PRESET_OTP,            // the panel's own waveform from its OTP (default)
+    DISPLAY_LUT_PRESET_FAST_REFRESH    // short waveform: sub-second updates, more ghosting
+} display_lut_preset_t;
+
+// Size of an SSD1680 waveform LUT (command 0x32), and of a vendor waveform
+// table that follows it with the EOPT, gate, source and VCOM voltage bytes
+#define DISPLAY_LUT_SIZE            153
+#define DISPLAY_LUT_SIZE_VOLTAGES   159
+
+/**
+ * Replace the waveform the controller drives full or partial refreshes with.
+ * A LUT is written to the controller before every refresh that uses it, so it
+ * survives resets and sleep; such refreshes skip loading the OTP waveform. A
+ * faster waveform trades ghosting and contrast for speed, and a wrong one can
+ * leave the panel unreadable until the next refresh with the OTP waveform.
+ * @param kind Refreshes to use the LUT for
+ * @param lut DISPLAY_LUT_SIZE bytes, or DISPLAY_LUT_SIZE_VOLTAGES bytes to
+ *            also set the voltages; NULL restores the OTP waveform
+ * @param len Length of lut in bytes
+ * @return DISPLAY_OK, or DISPLAY_ERROR_INVALID_ARGUMENT if kind or len is invalid
+ */
+int display_set_lut(display_lut_kind_t kind, const uint8_t* lut, size_t len);
+
+/**
+ * Use a built-in waveform for full or partial refreshes, as display_set_lut()
+ * @param kind Refreshes to use the waveform for
+ * @param preset DISPLAY_LUT_PRESET_FAST_REFRESH, or DISPLAY_LUT_PRESET_OTP to
+ *               restore the panel's own
+ * @return DISPLAY_OK, or DISPLAY_ERROR_INVALID_ARGUMENT if kind or preset is invalid
+ */
+int display_set_lut_preset(display_lut_kind_t kind, display_lut_preset_t preset);
+
+// Temperature compensation, in millidegrees Celsius (see display_set_temperature())
+#define DISPLAY_TEMPERATURE_INTERNAL  INT32_MIN  // use the panel's own sensor
+#define DISPLAY_TEMPERATURE_MIN_MC    (-40000)   // range of the controller's temperature register
+#define DISPLAY_TEMPERATURE_MAX_MC    100000
+#define DISPLAY_COLD_MC               10000      // below: LUT overrides suspended, busy timeout doubled
+#define DISPLAY_FREEZING_MC           0          // below: busy timeout tripled
+
+/**
+ * Drive refreshes for an external temperature reading, e.g. from an ambient
+ * sensor, instead of the panel's internal sensor. The controller picks the
+ * matching range of its OTP waveform. Below DISPLAY_COLD_MC, display_set_lut()
+ * waveforms (tuned for room temperature) are suspended in favour of the OTP
+ * one, and busy waits get twice the busy policy's timeout, three times below
+ * DISPLAY_FREEZING_MC, since the panel refreshes slowly in the cold.
+ * @param millicelsius Reading, or DISPLAY_TEMPERATURE_INTERNAL to use the panel's sensor again
+ * @return DISPLAY_OK, or DISPLAY_ERROR_INVALID_ARGUMENT if the reading is out of range
+ */
+int display_set_temperature(int32_t millicelsius);
+
+/**
+ * Get the temperature refreshes are compensated for
+ * @param millicelsius Pointer to store the display_set_temperature() reading
+ * @return DISPLAY_OK, or DISPLAY_ERROR_UNSUPPORTED if no external reading is
+ *         set: this library cannot read the panel's sensor over a write-only link
+ */
+int display_get_temperature(int32_t* millicelsius);
+
+/**
+ * Convert PNG to 1-bit packed data
+ * @param filename Path to PNG file
+ * @param output_data Pointer to buffer for output data (must be EPD_ARRAY bytes)
+ * @return DISPLAY_OK on success, or a negative display_error_t
+ */
+int convert_png_to_1bit(const char* filename, uint8_t* output_data);
+
+/**
+ * Convert an encoded PNG or JPEG image held in memory to 1-bit packed data.
+ * Other formats must be converted first. CMYK JPEGs are not supported.
+ * @param buf Pointer to the encoded PNG or JPEG bytes
+ * @param len Length of the encoded buffer in bytes
+ * @param output_data Pointer to buffer for output data (must be EPD_ARRAY bytes)
+ * @return DISPLAY_OK on success, DISPLAY_ERROR_INVALID_ARGUMENT if buf is
+ *         neither PNG nor JPEG, or another negative display_error_t
+ */
+int process_image_from_memory(const uint8_t* buf, size_t len, uint8_t* output_data);
+
+/**
+ * Display an encoded PNG or JPEG image held in memory.
+ * Other formats must be converted first. CMYK JPEGs are not supported.
+ * @param buf Pointer to the encoded PNG or JPEG bytes
+ * @param len Length of the encoded buffer in bytes
+ * @param mode Display mode (full or partial refresh)
+ * @return DISPLAY_OK on success, DISPLAY_ERROR_INVALID_ARGUMENT if buf is
+ *         neither PNG nor JPEG, or another negative display_error_t
+ */
+int display_image_from_memory(const uint8_t* buf, size_t len, display_mode_t mode);
+
+/**
+ * Fill an SPI configuration with the default wiring and set struct_size
+ * @param config Pointer to the configuration to initialize
+ */
+void display_spi_config_init(display_spi_config_t* config);
+
+/**
+ * Set the SPI device and GPIO lines used by the next display_init()
+ * @param config Configuration, or NULL to restore the default wiring
+ * @return DISPLAY_OK, or DISPLAY_ERROR_CONFIG if the display is initialized or
+ *         the configuration is invalid
+ */
+int display_set_spi_config(const display_spi_config_t* config);
+
+/**
+ * Get the SPI device and GPIO lines in use
+ * @param config Pointer to store the configuration; struct_size must be set
+ */
+void display_get_spi_config(display_spi_config_t* config);
+
+/**
+ * Fill processing options with defaults (threshold 128, no transforms) and set struct_size
+ * @param options Pointer to options to initialize
+ */
+void processing_options_init(processing_options_t* options);
+
+/**
+ * Initialize (if needed), process and display a PNG file in one call
+ * @param filename Path to PNG file (128x250, or 250x128 when options->rotate is set)
+ * @param options Processing options, or NULL for defaults
+ * @param mode Display mode (full or partial refresh)
+ * @return Result with DISPLAY_STATUS_OK on success, or the failing stage and a message
+ */
+display_result_t display_show_file(const char* filename, const processing_options_t* options, display_mode_t mode);
+
+/**
+ * Convert a raw pixel buffer to 1-bit packed data
+ * @param pixels Pointer to tightly packed pixel rows
+ * @param width Source width in pixels (128, or 250 when options->rotate is set)
+ * @param height Source height in pixels (250, or 128 when options->rotate is set)
+ * @param format Pixel layout of the buffer
+ * @param options Processing options, or NULL for defaults
+ * @param output_data Pointer to buffer for output data (must be EPD_ARRAY bytes)
+ * @return DISPLAY_OK on success, or a negative display_error_t
+ */
+int process_pixels_for_display(const uint8_t* pixels, uint32_t width, uint32_t height,
+                               pixel_format_t format, const processing_options_t* options,
+                               uint8_t* output_data);
+
+/**
+ * Display a raw pixel buffer
+ * @param pixels Pointer to tightly packed pixel rows
+ * @param width Source width in pixels
+ * @param height Source height in pixels
+ * @param format Pixel layout of the buffer
+ * @param options Processing options, or NULL for defaults
+ * @param mode Display mode (full or partial refresh)
+ * @return DISPLAY_OK on success, or a negative display_error_t
+ */
+int display_pixels(const uint8_t* pixels, uint32_t width, uint32_t height,
+                   pixel_format_t format, const processing_options_t* options,
+                   display_mode_t mode);
+
+/*
+ * Handle API
+ *
+ * The functions above drive one panel through a library-wide default handle.
+ * To drive several panels, e.g. on two chip selects, open a handle per panel
+ * with its own wiring and call the display_h_*() variants, which behave like
+ * the function of the same name without the display_ prefix but act on the
+ * given handle only. Handles are independent: each has its own SPI device,
+ * GPIO lines, rotation, busy policy and last frame, and updates on different
+ * handles may run concurrently from different threads. Updates on one handle
+ * are serialized as for the free functions.
+ *
+ * Each open handle also has a small integer id, 0 for the default handle, for
+ * callers that identify panels by number rather than keep pointers, e.g.
+ * display_h_image_raw(display_get(1), frame, DISPLAY_MODE_FULL).
+ */
+typedef struct display_handle display_handle_t;
+
+/**
+ * Create a handle for a panel. The hardware is acquired by display_h_init().
+ * Every handle drives a 128x250 panel (EPD_WIDTH x EPD_HEIGHT); there is no
+ * per-handle panel type.
+ * @param config SPI device and GPIO lines of the panel, or NULL for the default wiring
+ * @return Handle to release with display_close(), or NULL if the configuration is invalid
+ */
+display_handle_t* display_open(const display_spi_config_t* config);
+
+/**
+ * Clean up a handle and free it. Closing the default handle only cleans it up.
+ * @param display Handle from display_open() or display_default_handle(); NULL is ignored
+ */
+void display_close(display_handle_t* display);
+
+/**
+ * Get the handle behind the free functions, e.g. to use a display_h_*()
+ * function on it. It is never freed.
+ * @return The default handle
+ */
+display_handle_t* display_default_handle(void);
+
+/**
+ * Look up an open handle by id.
+ * @param id 0 for the default handle, or an id returned by display_get_id()
+ * @return The handle, or NULL if no open handle has this id
+ */
+display_handle_t* display_get(uint32_t id);
+
+/**
+ * Get the id of a handle. Ids are assigned by display_open() in increasing
+ * order and not reused while the library is loaded.
+ * @param display Handle from display_open() or display_default_handle()
+ * @return The handle's id
+ */
+uint32_t display_get_id(display_handle_t* display);
+
+/**
+ * Enumerate the open handles, the default handle first.
+ * @param ids Receives up to max_ids ids; may be NULL to only count
+ * @param max_ids Capacity of ids
+ * @return Number of open handles, which may exceed max_ids
+ */
+size_t display_list(uint32_t* ids, size_t max_ids);
+
+int display_h_init(display_handle_t* display);
+int display_h_image_raw(display_handle_t* display, const uint8_t* data, display_mode_t mode);
+int display_h_image_png(display_handle_t* display, const char* filename, display_mode_t mode);
+int display_h_clear(display_handle_t* display);
+void display_h_sleep(display_handle_t* display);
+int display_h_wake(display_handle_t* display);
+bool display_h_is_asleep(display_handle_t* display);
+void display_h_cleanup(display_handle_t* display);
+int display_h_set_rotation(display_handle_t* display, display_rotation_t rotation);
+display_rotation_t display_h_get_rotation(display_handle_t* display);
+void display_h_get_dimensions(display_handle_t* display, uint32_t* width, uint32_t* height);
+int display_h_set_busy_policy(display_handle_t* display, uint32_t timeout_ms, uint32_t retries);
+display_status_t display_h_get_last_status(display_handle_t* display);
+int display_h_image_auto(display_handle_t* display, const uint8_t* data, display_mode_t* mode_used);
+void display_h_set_auto_threshold(display_handle_t* display, uint32_t max_pixels);
+int display_h_get_framebuffer(display_handle_t* display, uint8_t* out);
+int display_h_restore_frame(display_handle_t* display, const uint8_t* data);
+int display_h_write_plane(display_handle_t* display, display_plane_t plane, const uint8_t* data);
+int display_h_flip(display_handle_t* display, display_mode_t mode);
+void display_h_abort(display_handle_t* display);
+int display_h_emergency_frame(display_handle_t* display, const uint8_t* data);
+void display_h_get_busy_stats(display_handle_t* display, uint32_t* last_ms, uint64_t* total_ms);
+int display_h_convert_png_to_1bit(display_handle_t* display, const char* filename, uint8_t* output_data);
+int display_h_process_image_from_memory(display_handle_t* display, const uint8_t* buf, size_t len,
+                                        uint8_t* output_data);
+int display_h_image_from_memory(display_handle_t* display, const uint8_t* buf, size_t len, display_mode_t mode);
+int display_h_set_spi_config(display_handle_t* display, const display_spi_config_t* config);
+void display_h_get_spi_config(display_handle_t* display, display_spi_config_t* config);
+display_result_t display_h_show_file(display_handle_t* display, const char* filename,
+                                     const processing_options_t* options, display_mode_t mode);
+int display_h_process_pixels_for_display(display_handle_t* display, const uint8_t* pixels, uint32_t width,
+                                         uint32_t height, pixel_format_t format,
+                                         const processing_options_t* options, uint8_t* output_data);
+int display_h_pixels(display_handle_t* display, const uint8_t* pixels, uint32_t width, uint32_t height,
+                     pixel_format_t format, const processing_options_t* options, display_mode_t mode);
+void display_h_set_event_callback(display_handle_t* display, display_event_callback_t callback, void* user_data);
+int display_h_get_stats(display_handle_t* display, display_stats_t* stats);
+int display_h_set_lut(display_handle_t* display, display_lut_kind_t kind, const uint8_t* lut, size_t len);
+int display_h_set_lut_preset(display_handle_t* display, display_lut_kind_t kind, display_lut_preset_t preset);
+int display_h_set_temperature(display_handle_t* display, int32_t millicelsius);
+int display_h_get_temperature(display_handle_t* display, int32_t* millicelsius);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // DISTILLER_DISPLAY_SDK_H
+
+#endif // DISTILLER_EINK_H
@@ -0,0 +1,356 @@
+#!/usr/bin/env python3
+"""
+Declarative screen layouts for the e-ink display.
+
+A template describes a screen as nested rows and columns of text, images,
+progress bars, QR codes and Markdown. Strings in it can name fields of a data
+dictionary, so a dashboard redraws with new values without any drawing
+code:
+
+    {
+      "type": "column", "padding": 2, "gap": 4,
+      "children": [
+        {"type": "text", "text": "{room}", "size": 2, "align": "center", "invert": true},
+        {"type": "row", "height": 40, "children": [
+          {"type": "image", "src": "/usr/share/myapp/thermometer.png", "width": 40},
+          {"type": "text", "text": "{temperature:.1f} C", "size": 3}
+        ]},
+        {"type": "progress", "value": "{humidity}", "max": 100},
+        {"type": "divider"},
+        {"type": "qr", "text": "{url}"}
+      ]
+    }
+
+    fb = load_layout("dashboard.json").render({"room": "Kitchen", ...})
+    display.display_image(fb.to_bytes())
+
+Every node has a "type" and optionally "width" or "height" in pixels,
+"weight", "padding", "border" and "invert". Children of a row or column
+with a fixed size, and text, Markdown, progress bars and dividers in a
+column, get the space they need; the rest is shared between the others in
+proportion to their weight (default 1). Whatever does not fit in a node's
+box is clipped.
+"""
+
+import json
+import re
+from typing import Any, Dict, List, Mapping, Optional, Tuple
+
+from .graphics import PANEL_HEIGHT, PANEL_WIDTH, Color, Framebuffer, measure_text, wrap_text
+from .imaging import DitherMode, ImageError, ImageOptions, ScalingMethod, default_image_options, process_image
+from .markdown import markdown_height, render_markdown
+from .qr import ERROR_CORRECTION_LEVELS, QRCodeError, draw_qr
+
+
+# Height of a progress bar without an explicit height, in pixels
+PROGRESS_HEIGHT = 8
+
+_COMMON_KEYS = {"type", "width", "height", "weight", "padding", "border", "invert"}
+_NODE_KEYS = {
+    "row": {"children", "gap"},
+    "column": {"children", "gap"},
+    "text": {"text", "size", "align"},
+    "markdown": {"text"},
+    "image": {"src", "scale", "dither", "threshold"},
+    "progress": {"value", "max"},
+    "qr": {"text", "ec"},
+    "divider": set(),
+    "spacer": set(),
+}
+_REQUIRED_STRINGS = {"text": "text", "markdown": "text", "qr": "text", "image": "src"}
+_ALIGNMENTS = ("left", "center", "right")
+_SINGLE_FIELD = re.compile(r"^\{(\w+)\}$")
+
+
+class LayoutError(Exception):
+    """Custom exception for invalid layout templates or missing data."""
+    pass
+
+
+def _check(node: Any, where: str) -> None:
+    if not isinstance(node, dict):
+        raise LayoutError(f"{where}: must be an object")
+    kind = node.get("type")
+    if kind not in _NODE_KEYS:
+        raise LayoutError(f"{where}: type must be one of {', '.join(_NODE_KEYS)}")
+    unknown = set(node) - _COMMON_KEYS - _NODE_KEYS[kind]
+    if unknown:
+        raise LayoutError(f"{where}: unknown key '{sorted(unknown)[0]}' for {kind}")
+
+    for key in ("width", "height", "padding", "gap"):
+        if key in node and (not isinstance(node[key], int) or isinstance(node[key], bool) or node[key] < 0):
+            raise LayoutError(f"{where}: {key} must be a non-negative integer")
+    if "weight" in node and (not isinstance(node["weight"], (int, float)) or node["weight"] <= 0):
+        raise LayoutError(f"{where}: weight must be a positive number")
+    if "size" in node and (not isinstance(node["size"], int) or node["size"] < 1):
+        raise LayoutError(f"{where}: size must be a positive integer")
+    if node.get("align", "left") not in _ALIGNMENTS:
+        raise LayoutError(f"{where}: align must be left, center or right")
+    if node.get("ec", "M") not in ERROR_CORRECTION_LEVELS:
+        raise LayoutError(f"{where}: ec must be one of {', '.join(ERROR_CORRECTION_LEVELS)}")
+    try:
+        ScalingMethod(node.get("scale", ScalingMethod.LETTERBOX.value))
+        DitherMode(node.get("dither", DitherMode.FLOYD_STEINBERG.value))
+    except ValueError as e:
+        raise LayoutError(f"{where}: {e}")
+    threshold = node.get("threshold", 128)
+    if not isinstance(threshold, int) or not 0 <= threshold <= 255:
+        raise LayoutError(f"{where}: threshold must be 0-255")
+
+    key = _REQUIRED_STRINGS.get(kind)
+    if key is not None and not isinstance(node.get(key), str):
+        raise LayoutError(f"{where}: {kind} needs a {key} string")
+    if kind == "progress" and "value" not in node:
+        raise LayoutError(f"{where}: progress needs a value")
+    if kind in ("row", "column"):
+        children = node.get("children")
+        if not isinstance(children, list):
+            raise LayoutError(f"{where}: {kind} needs a children list")
+        for i, child in enumerate(children):
+            _check(child, f"{where}.children[{i}]")
+
+
+def _value(template: str, data: Mapping[str, Any]) -> Any:
+    """The data value itself for a template that is just "{field}", else the filled-in string."""
+    match = _SINGLE_FIELD.match(template)
+    if match is None:
+        return _format(template, data)
+    if match.group(1) not in data:
+        raise LayoutError(f"No data for field '{match.group(1)}'")
+    return data[match.group(1)]
+
+
+def _format(template: str, data: Mapping[str, Any]) -> str:
+    try:
+        return template.format_map(data)
+    except KeyError as e:
+        raise LayoutError(f"No data for field {e}")
+    except (AttributeError, IndexError, TypeError, ValueError) as e:
+        raise LayoutError(f"Cannot fill in '{template}': {e}")
+
+
+def _text_lines(node: dict, data: Mapping[str, Any], width: int) -> List[str]:
+    return wrap_text(_format(node["text"], data), width, node.get("size", 1)).split("\n")
+
+
+def _intrinsic_height(node: dict, data: Mapping[str, Any], width: int) -> Optional[int]:
+    """Height a column child needs at the given width, or None to share the free space."""
+    if "height" in node:
+        return node["height"]
+    padding = 2 * node.get("padding", 0)
+    kind = node["type"]
+    if kind == "text":
+        size = node.get("size", 1)
+        return measure_text(_format(node["text"], data), size=size, max_width=max(width - padding, 1))[1] + padding
+    if kind == "markdown":
+        return markdown_height(_format(node["text"], data), max(width - padding, 1)) + padding
+    if kind == "progress":
+        return PROGRESS_HEIGHT + padding
+    if kind == "divider":
+        return 1 + padding
+    return None
+
+
+def _intrinsic_width(node: dict) -> Optional[int]:
+    """Width a row child needs, or None to share the free space."""
+    if "width" in node:
+        return node["width"]
+    if node["type"] == "divider":
+        return 1 + 2 * node.get("padding", 0)
+    return None
+
+
+def _split(total: int, fixed: List[Optional[int]], weights: List[float], gap: int) -> List[int]:
+    """Lengths along a row or column: fixed ones as given, the rest sharing what is left by weight."""
+    free = max(total - gap * max(len(fixed) - 1, 0) - sum(f for f in fixed if f is not None), 0)
+    flexible = [i for i, f in enumerate(fixed) if f is None]
+    total_weight = sum(weights[i] for i in flexible)
+    lengths = [f or 0 for f in fixed]
+    given = 0
+    for n, i in enumerate(flexible):
+        if n == len(flexible) - 1:
+            lengths[i] = free - given       # the last one takes the rounding remainder
+        else:
+            lengths[i] = int(free * weights[i] / total_weight)
+            given += lengths[i]
+    return lengths
+
+
+class Layout:
+    """A validated layout template, rendered with new data as often as needed."""
+
+    def __init__(self, template: Dict[str, Any]):
+        """
+        Args:
+            template: Root node, as described in the module documentation
+
+        Raises:
+            LayoutError: If the template is invalid
+        """
+        _check(template, "root")
+        self.template = template
+
+    def render(self, data: Optional[Mapping[str, Any]] = None,
+               width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT) -> Framebuffer:
+        """
+        Draw the screen.
+
+        Args:
+            data: Values for the {field} placeholders; image fields may hold
+                  encoded image bytes as well as paths
+            width: Frame width, e.g. Display.get_dimensions()[0]
+            height: Frame height
+
+        Returns:
+            The frame, ready for Display.display_image()
+
+        Raises:
+            LayoutError: If a field is missing, or an image or QR code cannot be drawn
+        """
+        return self._render(self.template, data or {}, width, height)
+
+    def _render(self, node: dict, data: Mapping[str, Any], width: int, height: int) -> Framebuffer:
+        fb = Framebuffer(max(width, 1), max(height, 1))
+        padding = node.get("padding", 0)
+        inner_width, inner_height = width - 2 * padding, height - 2 * padding
+        if inner_width > 0 and inner_height > 0:
+            content = self._draw_content(node, data, inner_width, inner_height)
+            if content is not None:
+                fb.blit(content, padding, padding)
+        if node.get("border"):
+            fb.draw_rect(0, 0, width, height)
+        if node.get("invert"):
+            for i in range(len(fb.data)):
+                fb.data[i] ^= 0xFF
+        return fb
+
+    def _draw_content(self, node: dict, data: Mapping[str, Any], width: int, height: int) -> Optional[Framebuffer]:
+        kind = node["type"]
+        if kind in ("row", "column"):
+            return self._draw_box(node, data, width, height, kind == "row")
+        if kind == "spacer":
+            return None
+        if kind == "markdown":
+            return render_markdown(_format(node["text"], data), width, height)
+
+        fb = Framebuffer(width, height)
+        if kind == "text":
+            size = node.get("size", 1)
+            y = 0
+            for line in _text_lines(node, data, width):
+                line_width, line_height, _ = measure_text(line, size=size)
+                x = {"left": 0, "center": (width - line_width) // 2,
+                     "right": width - line_width}[node.get("align", "left")]
+                fb.draw_text(x, y, line, scale=size)
+                y += line_height
+        elif kind == "divider":
+            fb.fill(Color.BLACK)
+        elif kind == "progress":
+            value = node["value"]
+            if isinstance(value, str):
+                value = _value(value, data)
+            try:
+                fraction = float(value) / float(node.get("max", 100))
+            except (TypeError, ValueError, ZeroDivisionError):
+                raise LayoutError(f"Progress value {value!r} is not a number")
+            fb.draw_rect(0, 0, width, height)
+            fb.draw_rect(0, 0, int(width * min(max(fraction, 0.0), 1.0)), height, fill=True)
+        elif kind == "image":
+            source = _value(node["src"], data)
+            profile = default_image_options()
+            options = ImageOptions(ScalingMethod(node.get("scale", ScalingMethod.LETTERBOX.value)),
+                                   DitherMode(node.get("dither", profile.dither.value)),
+                                   node.get("threshold", profile.threshold), contrast=profile.contrast,
+                                   parallel=profile.parallel, scaling_filter=profile.scaling_filter,
+                                   sharpen=profile.sharpen)
+            try:
+                return process_image(source if isinstance(source, bytes) else str(source), options, width, height)
+            except ImageError as e:
+                raise LayoutError(str(e))
+        elif kind == "qr":
+            size = min(width, height)
+            try:
+                draw_qr(fb, _format(node["text"], data).strip(), (width - size) // 2, (height - size) // 2,
+                        size, node.get("ec", "M"))
+            except QRCodeError as e:
+                raise LayoutError(str(e))
+        return fb
+
+    def _draw_box(self, node: dict, data: Mapping[str, Any], width: int, height: int,
+                  horizontal: bool) -> Framebuffer:
+        children = node["children"]
+        gap = node.get("gap", 0)
+        if horizontal:
+            fixed = [_intrinsic_width(c) for c in children]
+        else:
+            fixed = [_intrinsic_height(c, data, c.get("width", width)) for c in children]
+        lengths = _split(width if horizontal else height, fixed, [c.get("weight", 1) for c in children], gap)
+
+        fb = Framebuffer(width, height)
+        offset = 0
+        for child, length in zip(children, lengths):
+            if length > 0:
+                if horizontal:
+                    child_width, child_height = length, min(child.get("height", height), height)
+                    position: Tuple[int, int] = (offset, 0)
+                else:
+                    child_width, child_height = min(child.get("width", width), width), length
+                    position = (0, offset)
+                fb.blit(self._render(child, data, child_width, child_height), *position)
+            offset += length + gap
+        return fb
+
+
+def load_layout(path: str) -> Layout:
+    """
+    Read a layout template from a .json or .toml file, or Markdown from a .md
+    file as a template of one markdown node.
+
+    Raises:
+        LayoutError: If the file cannot be read or the template is invalid
+    """
+    try:
+        with open(path, "rb") as f:
+            content = f.read()
+    except OSError as e:
+        raise LayoutError(f"Failed to read layout {path}: {e}")
+
+    if path.lower().endswith(".md"):
+        try:
+            template = {"type": "markdown", "text": content.decode("utf-8")}
+        except UnicodeDecodeError as e:
+            raise LayoutError(f"{path}: invalid Markdown: {e}")
+    elif path.lower().endswith(".toml"):
+        try:
+            import tomllib
+        except ImportError:  # Python < 3.11
+            try:
+                import tomli as tomllib
+            except ImportError:
+                raise LayoutError(f"{path}: reading TOML needs Python 3.11+ or the tomli module; "
+                                  "use a .json layout instead")
+        try:
+            template = tomllib.loads(content.decode("utf-8"))
+        except (ValueError, UnicodeDecodeError) as e:
+            raise LayoutError(f"{path}: invalid TOML: {e}")
+    else:
+        try:
+            template = json.loads(content)
+        except ValueError as e:
+            raise LayoutError(f"{path}: invalid JSON: {e}")
+
+    try:
+        return Layout(template)
+    except LayoutError as e:
+        raise LayoutError(f"{path}: {e}")
+
+
+def render_layout(template: Dict[str, Any], data: Optional[Mapping[str, Any]] = None,
+                  width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT) -> Framebuffer:
+    """
+    Validate a template and draw it once; see Layout.render().
+
+    Raises:
+        LayoutError: If the template is invalid or cannot be drawn with data
+    """
+    return Layout(template).render(data, width, height)
@@ -1,49 +1,215 @@
 #include "distiller_display_sdk.h"
+#include <errno.h>
 #include <stdio.h>
+#include <pthread.h>
+#include <setjmp.h>
+#include <signal.h>
+#include <stdarg.h>
+#include <stddef.h>
 #include <stdlib.h>
 #include <string.h>
 #include <unistd.h>
 #include <fcntl.h>
 #include <sys/ioctl.h>
+#include <time.h>
 #include <linux/spi/spidev.h>
 #include <gpiod.h>
+#if defined(__aarch64__) && defined(__ARM_NEON)
+#include <arm_neon.h>
+#endif
 
 // Include lodepng for PNG support
 #include "lodepng.h"
 
-// Private variables
-static int spi_fd = -1;
-static struct gpiod_chip *chip = NULL;
-static struct gpiod_line *dc_line = NULL;
-static struct gpiod_line *rst_line = NULL;
-static struct gpiod_line *busy_line = NULL;
-static bool initialized = false;
+// libjpeg for JPEG held in memory (link with -ljpeg)
+#include <jpeglib.h>
+
+// Existing struct fields must never move (see ABI policy in the header)
+_Static_assert(offsetof(processing_options_t, struct_size) == 0, "processing_options_t ABI break");
+_Static_assert(offsetof(processing_options_t, threshold) == 4, "processing_options_t ABI break");
+_Static_assert(offsetof(processing_options_t, rotate) == 5, "processing_options_t ABI break");
+_Static_assert(offsetof(processing_options_t, flip_horizontal) == 6, "processing_options_t ABI break");
+_Static_assert(offsetof(processing_options_t, invert_colors) == 7, "processing_options_t ABI break");
+_Static_assert(offsetof(display_spi_config_t, busy_pin) == 24, "display_spi_config_t ABI break");
+_Static_assert(offsetof(display_spi_config_t, gpio_chip) == 28, "display_spi_config_t ABI break");
+_Static_assert(offsetof(display_spi_config_t, block_size) == 60, "display_spi_config_t ABI break");
+_Static_assert(offsetof(display_stats_t, full_refreshes) == 8, "display_stats_t ABI break");
+_Static_assert(offsetof(display_stats_t, spi_transfers) == 48, "display_stats_t ABI break");
+
+// State of one panel; the free functions drive default_handle
+struct display_handle {
+    int spi_fd;
+    struct gpiod_chip *chip;
+    struct gpiod_line *dc_line;
+    struct gpiod_line *rst_line;
+    struct gpiod_line *busy_line;
+    bool initialized;
+    display_spi_config_t spi_config;
+    display_rotation_t rotation;
+    bool busy_events;                       // busy line delivers edge events
+    display_stats_t stats;                  // counters since init; average_spi_us is filled in on read
+    uint32_t busy_timeout_ms;
+    uint32_t busy_retries;
+    bool busy_timed_out;                    // a busy wait timed out since last cleared
+    int spi_errno;                          // errno of the first failed SPI transfer since last cleared
+    display_status_t last_status;
+    uint8_t last_frame[EPD_ARRAY];          // last frame sent, in panel orientation
+    bool last_frame_valid;
+    uint8_t staged_frame[EPD_ARRAY];        // frame display_write_plane() put in the new-frame RAM
+    bool staged_valid;                      // it awaits display_flip()
+    uint32_t auto_max_pixels;
+    pthread_mutex_t update_lock;            // one frame update at a time
+    volatile sig_atomic_t abort_requested;  // stop the busy wait in flight
+    volatile sig_atomic_t updates_blocked;  // set by display_abort() until display_init() or display_wake()
+    bool asleep;                            // deep sleep entered by display_sleep()
+    uint32_t id;                            // display_get() id; 0 for default_handle
+    display_event_callback_t event_callback;
+    void* event_user_data;
+    uint8_t lut[2][DISPLAY_LUT_SIZE_VOLTAGES];  // display_set_lut() waveforms by display_lut_kind_t
+    size_t lut_len[2];                      // 0 uses the OTP waveform
+    int32_t temperature_mc;                 // display_set_temperature() reading, or DISPLAY_TEMPERATURE_INTERNAL
+    struct display_handle* next;            // open handles, for display_get()
+};
+
+#define HANDLE_DEFAULTS \
+    .spi_fd = -1, \
+    .spi_config = { \
+        .struct_size = sizeof(display_spi_config_t), \
+        .bus = SPI_BUS, \
+        .cs = SPI_CS, \
+        .speed_hz = SPI_SPEED_HZ, \
+        .dc_pin = DC_PIN, \
+        .reset_pin = RST_PIN, \
+        .busy_pin = BUSY_PIN, \
+        .gpio_chip = GPIO_CHIP_NAME, \
+        .block_size = SPI_BLOCK_SIZE, \
+        .chunk_delay_us = 0, \
+    }, \
+    .rotation = DISPLAY_ROTATION_NONE, \
+    .busy_timeout_ms = BUSY_TIMEOUT_MS, \
+    .busy_retries = BUSY_RETRIES, \
+    .last_status = DISPLAY_STATUS_OK, \
+    .auto_max_pixels = AUTO_PARTIAL_MAX_PIXELS, \
+    .temperature_mc = DISPLAY_TEMPERATURE_INTERNAL
+
+static display_handle_t default_handle = {
+    HANDLE_DEFAULTS,
+    .update_lock = PTHREAD_MUTEX_INITIALIZER,
+};
+
+// Handles by id: default_handle heads the list, display_open() appends
+static pthread_mutex_t handles_lock = PTHREAD_MUTEX_INITIALIZER;
+static uint32_t next_handle_id = 1;
+
+// Longest busy wait slice before checking for an abort
+#define ABORT_CHECK_MS 20
+
+// Display Update Control 2 values: load the OTP waveform, or use the one in the LUT register
+#define UPDATE_FULL             0xF7
+#define UPDATE_PARTIAL          0xFF
+#define UPDATE_FULL_REGISTER    0xC7
+#define UPDATE_PARTIAL_REGISTER 0xCF
+#define UPDATE_LOAD_TEMPERATURE 0x20    // bit reading the sensor; cleared to use the temperature register
+
+// DISPLAY_LUT_PRESET_FAST_REFRESH: one short drive phase per transition, as in
+// the panel vendor's partial refresh waveform, followed by its voltages
+static const uint8_t lut_fast_refresh[DISPLAY_LUT_SIZE_VOLTAGES] = {
+    0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // VS L0 (black to black)
+    0x80, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // VS L1 (black to white)
+    0x40, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // VS L2 (white to black)
+    0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // VS L3 (white to white)
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // VS L4 (VCOM)
+    0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,                                // group 0 timing and repeat
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00, 0x00,                    // frame rate, gate scan
+    0x22, 0x17, 0x41, 0x00, 0x32, 0x36,                                      // EOPT, VGH, VSH1, VSH2, VSL, VCOM
+};
+
+// Most recent failure, per thread like errno (see display_get_last_error())
+static _Thread_local int last_error = DISPLAY_OK;
+static _Thread_local char last_error_message[256];
 
 // Private function declarations
 static void delay_ms(int ms);
-static void gpio_write(int pin, int value);
-static int gpio_read(int pin);
+static uint64_t monotonic_ms(void);
+static int set_error(int error, const char* fmt, ...) __attribute__((format(printf, 2, 3)));
+static int fail(int error, const char* fmt, ...) __attribute__((format(printf, 2, 3)));
+static void gpio_write(display_handle_t* h, int pin, int value);
+static int gpio_read(display_handle_t* h, int pin);
 static void spi_delay(void);
-static void epd_w21_write_cmd(uint8_t cmd);
-static void epd_w21_write_data(uint8_t data);
-static void lcd_chkstatus(void);
-static void epd_init_hardware(void);
-static void epd_init_partial(void);
-static void epd_update(void);
-static void epd_update_partial(void);
+static void epd_w21_write_cmd(display_handle_t* h, uint8_t cmd);
+static void epd_w21_write_data(display_handle_t* h, uint8_t data);
+static void epd_w21_write_data_block(display_handle_t* h, const uint8_t* data, size_t len);
+static void lcd_chkstatus(display_handle_t* h);
+static void epd_init_hardware(display_handle_t* h);
+static void epd_init_partial(display_handle_t* h);
+static void epd_update(display_handle_t* h);
+static void epd_update_partial(display_handle_t* h);
+static int convert_rgba_to_1bit(display_handle_t* h, const unsigned char* rgba, unsigned width, unsigned height, uint8_t* output_data);
+static void rotate_packed(const uint8_t* src, unsigned width, unsigned height,
+                          display_rotation_t rot, uint8_t* dst);
+static void emit_event(display_handle_t* h, display_event_type_t type, display_mode_t mode,
+                       uint32_t duration_ms, int error);
 
 // Implementation
 static void delay_ms(int ms) {
     usleep(ms * 1000);
 }
 
-static void gpio_write(int pin, int value) {
+static uint64_t monotonic_ms(void) {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return (uint64_t)ts.tv_sec * 1000 + (uint64_t)ts.tv_nsec / 1000000;
+}
+
+static uint64_t monotonic_us(void) {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return (uint64_t)ts.tv_sec * 1000000 + (uint64_t)ts.tv_nsec / 1000;
+}
+
+static int vset_error(int error, const char* fmt, va_list args) {
+    vsnprintf(last_error_message, sizeof(last_error_message), fmt, args);
+    last_error = error;
+    return error;
+}
+
+// Record a failure for display_get_last_error(); returns error
+static int set_error(int error, const char* fmt, ...) {
+    va_list args;
+    va_start(args, fmt);
+    vset_error(error, fmt, args);
+    va_end(args);
+    return error;
+}
+
+// Record a failure and report it; returns error
+static int fail(int error, const char* fmt, ...) {
+    va_list args;
+    va_start(args, fmt);
+    vset_error(error, fmt, args);
+    va_end(args);
+    printf("Error: %s\n", last_error_message);
+    return error;
+}
+
+static void gpio_write(display_handle_t* h, int pin, int value) {
     struct gpiod_line *line = NULL;
     
-    if (pin == DC_PIN) {
-        line = dc_line;
-    } else if (pin == RST_PIN) {
-        line = rst_line;
+    if (pin == (int)h->spi_config.dc_pin) {
+        line = h->dc_line;
+    } else if (pin == (int)h->spi_config.reset_pin) {
+        line = h->rst_line;
     }
     
     if (line) {
@@ -53,9 +219,9 @@ static void gpio_write(int pin, int value) {
     }
 }
 
-static int gpio_read(int pin) {
-    if (pin == BUSY_PIN && busy_line) {
-        return gpiod_line_get_value(busy_line);
+static int gpio_read(display_handle_t* h, int pin) {
+    if (pin == (int)h->spi_config.busy_pin && h->busy_line) {
+        return gpiod_line_get_value(h->busy_line);
     }
     return -1;
 }
@@ -64,335 +230,1680 @@ static void spi_delay(void) {
     usleep(10);  // 10 microseconds delay
 }
 
-static void lcd_chkstatus(void) {
-    int watchdog_counter = 0;
-    while (gpio_read(BUSY_PIN) == 1 && watchdog_counter < 1000) {  // =1 BUSY
+static bool busy_wait_poll(display_handle_t* h, uint64_t deadline) {
+    while (gpio_read(h, h->spi_config.busy_pin) == 1) {  // =1 BUSY
+        if (h->abort_requested || monotonic_ms() >= deadline) {
+            return false;
+        }
         delay_ms(10);
-        watchdog_counter++;
     }
-    if (watchdog_counter >= 1000) {
-        printf("Warning: Display busy timeout\n");
+    return true;
+}
+
+static bool busy_wait_events(display_handle_t* h, uint64_t deadline) {
+    while (gpio_read(h, h->spi_config.busy_pin) == 1) {  // =1 BUSY
+        uint64_t now = monotonic_ms();
+        if (h->abort_requested || now >= deadline) {
+            return false;
+        }
+        
+        uint64_t remaining = deadline - now;
+        if (remaining > ABORT_CHECK_MS) {
+            remaining = ABORT_CHECK_MS;
+        }
+        struct timespec timeout = {
+            .tv_sec = remaining / 1000,
+            .tv_nsec = (remaining % 1000) * 1000000,
+        };
+        
+        int ret = gpiod_line_event_wait(h->busy_line, &timeout);
+        if (ret < 0) {
+            perror("Error waiting for busy line event, falling back to polling");
+            h->busy_events = false;
+            return busy_wait_poll(h, deadline);
+        }
+        if (ret > 0) {
+            // Drain the event; the line value is re-checked by the loop
+            struct gpiod_line_event event;
+            gpiod_line_event_read(h->busy_line, &event);
+        }
     }
+    return true;
 }
 
-static void epd_w21_write_cmd(uint8_t cmd) {
-    if (spi_fd < 0) return;
+// Whether the display_set_temperature() reading is below DISPLAY_COLD_MC
+static bool is_cold(display_handle_t* h) {
+    return h->temperature_mc != DISPLAY_TEMPERATURE_INTERNAL && h->temperature_mc < DISPLAY_COLD_MC;
+}
+
+// The busy policy's timeout, stretched for a cold panel
+static uint32_t busy_timeout_ms(display_handle_t* h) {
+    if (!is_cold(h)) {
+        return h->busy_timeout_ms;
+    }
+    uint64_t timeout = (uint64_t)h->busy_timeout_ms * (h->temperature_mc < DISPLAY_FREEZING_MC ? 3 : 2);
+    return timeout > UINT32_MAX ? UINT32_MAX : (uint32_t)timeout;
+}
+
+static void lcd_chkstatus(display_handle_t* h) {
+    uint64_t start = monotonic_ms();
+    uint32_t timeout_ms = busy_timeout_ms(h);
+    uint64_t deadline = start + timeout_ms;
+    
+    bool ready = h->busy_events ? busy_wait_events(h, deadline) : busy_wait_poll(h, deadline);
+    if (!ready) {
+        if (h->abort_requested) {
+            printf("Warning: Display update aborted\n");
+        } else {
+            printf("Warning: Display busy timeout after %u ms\n", timeout_ms);
+        }
+        h->busy_timed_out = true;
+    }
+    
+    h->stats.last_busy_ms = (uint32_t)(monotonic_ms() - start);
+    h->stats.total_busy_ms += h->stats.last_busy_ms;
+}
+
+// One timed SPI transfer, counted in the handle's stats
+static int spi_transfer(display_handle_t* h, struct spi_ioc_transfer* tr) {
+    uint64_t start = monotonic_us();
+    int ret = ioctl(h->spi_fd, SPI_IOC_MESSAGE(1), tr);
+    h->stats.total_spi_us += monotonic_us() - start;
+    h->stats.spi_transfers++;
+    if (ret >= 0) {
+        h->stats.spi_bytes += tr->len;
+    }
+    return ret;
+}
+
+static void epd_w21_write_cmd(display_handle_t* h, uint8_t cmd) {
+    if (h->spi_fd < 0) return;
     
     spi_delay();
-    gpio_write(DC_PIN, 0);
+    gpio_write(h, h->spi_config.dc_pin, 0);
     
     struct spi_ioc_transfer tr = {
         .tx_buf = (unsigned long)&cmd,
         .rx_buf = 0,
         .len = 1,
-        .speed_hz = 40000000,
+        .speed_hz = h->spi_config.speed_hz,
         .bits_per_word = 8,
         .delay_usecs = 0,
         .cs_change = 1,
     };
     
-    if (ioctl(spi_fd, SPI_IOC_MESSAGE(1), &tr) < 0) {
+    if (spi_transfer(h, &tr) < 0) {
+        if (!h->spi_errno) h->spi_errno = errno;
         perror("Error in SPI command transfer");
     }
 }
 
-static void epd_w21_write_data(uint8_t data) {
-    if (spi_fd < 0) return;
+static void epd_w21_write_data(display_handle_t* h, uint8_t data) {
+    if (h->spi_fd < 0) return;
     
     spi_delay();
-    gpio_write(DC_PIN, 1);
+    gpio_write(h, h->spi_config.dc_pin, 1);
     
     struct spi_ioc_transfer tr = {
         .tx_buf = (unsigned long)&data,
         .rx_buf = 0,
         .len = 1,
-        .speed_hz = 40000000,
+        .speed_hz = h->spi_config.speed_hz,
         .bits_per_word = 8,
         .delay_usecs = 0,
         .cs_change = 1,
     };
     
-    if (ioctl(spi_fd, SPI_IOC_MESSAGE(1), &tr) < 0) {
+    if (spi_transfer(h, &tr) < 0) {
+        if (!h->spi_errno) h->spi_errno = errno;
         perror("Error in SPI data transfer");
     }
 }
 
-static void epd_init_hardware(void) {
+// Send a data run in block_size transfers; single transfers larger than
+// spidev's bufsiz fail with EMSGSIZE ("Message too long")
+static void epd_w21_write_data_block(display_handle_t* h, const uint8_t* data, size_t len) {
+    if (h->spi_fd < 0) return;
+    
+    spi_delay();
+    gpio_write(h, h->spi_config.dc_pin, 1);
+    
+    for (size_t offset = 0; offset < len; offset += h->spi_config.block_size) {
+        size_t chunk = len - offset < h->spi_config.block_size ? len - offset : h->spi_config.block_size;
+        if (offset > 0 && h->spi_config.chunk_delay_us > 0) {
+            usleep(h->spi_config.chunk_delay_us);
+        }
+        
+        struct spi_ioc_transfer tr = {
+            .tx_buf = (unsigned long)(data + offset),
+            .rx_buf = 0,
+            .len = chunk,
+            .speed_hz = h->spi_config.speed_hz,
+            .bits_per_word = 8,
+            .delay_usecs = 0,
+            .cs_change = 1,
+        };
+        
+        if (spi_transfer(h, &tr) < 0) {
+            if (!h->spi_errno) h->spi_errno = errno;
+            perror("Error in SPI data transfer");
+            return;
+        }
+    }
+}
+
+static void epd_init_hardware(display_handle_t* h) {
     // Module reset
-    gpio_write(RST_PIN, 0);
+    gpio_write(h, h->spi_config.reset_pin, 0);
     delay_ms(10);
-    gpio_write(RST_PIN, 1);
+    gpio_write(h, h->spi_config.reset_pin, 1);
     delay_ms(10);
     
-    lcd_chkstatus();
-    epd_w21_write_cmd(0x12);  // SWRESET
-    lcd_chkstatus();
+    lcd_chkstatus(h);
+    epd_w21_write_cmd(h, 0x12);  // SWRESET
+    lcd_chkstatus(h);
     
-    epd_w21_write_cmd(0x01);  // Driver output control
-    epd_w21_write_data((EPD_HEIGHT-1) % 256);
-    epd_w21_write_data((EPD_HEIGHT-1) / 256);
-    epd_w21_write_data(0x00);
+    epd_w21_write_cmd(h, 0x01);  // Driver output control
+    epd_w21_write_data(h, (EPD_HEIGHT-1) % 256);
+    epd_w21_write_data(h, (EPD_HEIGHT-1) / 256);
+    epd_w21_write_data(h, 0x00);
 
-    epd_w21_write_cmd(0x11);  // data entry mode
-    epd_w21_write_data(0x01);  // Normal mode
+    epd_w21_write_cmd(h, 0x11);  // data entry mode
+    epd_w21_write_data(h, 0x01);  // Normal mode
 
-    epd_w21_write_cmd(0x44);  // set Ram-X address start/end position
-    epd_w21_write_data(0x00);
-    epd_w21_write_data(EPD_WIDTH/8-1);
+    epd_w21_write_cmd(h, 0x44);  // set Ram-X address start/end position
+    epd_w21_write_data(h, 0x00);
+    epd_w21_write_data(h, EPD_WIDTH/8-1);
 
-    epd_w21_write_cmd(0x45);  // set Ram-Y address start/end position
-    epd_w21_write_data((EPD_HEIGHT-1) % 256);
-    epd_w21_write_data((EPD_HEIGHT-1) / 256);
-    epd_w21_write_data(0x00);
-    epd_w21_write_data(0x00);
+    epd_w21_write_cmd(h, 0x45);  // set Ram-Y address start/end position
+    epd_w21_write_data(h, (EPD_HEIGHT-1) % 256);
+    epd_w21_write_data(h, (EPD_HEIGHT-1) / 256);
+    epd_w21_write_data(h, 0x00);
+    epd_w21_write_data(h, 0x00);
 
-    epd_w21_write_cmd(0x3C);  // BorderWavefrom
-    epd_w21_write_data(0x05);
+    epd_w21_write_cmd(h, 0x3C);  // BorderWavefrom
+    epd_w21_write_data(h, 0x05);
 
-    epd_w21_write_cmd(0x21);  // Display update control
-    epd_w21_write_data(0x00);
-    epd_w21_write_data(0x80);
+    epd_w21_write_cmd(h, 0x21);  // Display update control
+    epd_w21_write_data(h, 0x00);
+    epd_w21_write_data(h, 0x80);
 
-    epd_w21_write_cmd(0x18);  // Read built-in temperature sensor
-    epd_w21_write_data(0x80);
+    epd_w21_write_cmd(h, 0x18);  // Read built-in temperature sensor
+    epd_w21_write_data(h, 0x80);
 
-    epd_w21_write_cmd(0x4E);  // set RAM x address count
-    epd_w21_write_data(0x00);
+    epd_w21_write_cmd(h, 0x4E);  // set RAM x address count
+    epd_w21_write_data(h, 0x00);
         
-    epd_w21_write_cmd(0x4F);  // set RAM y address count
-    epd_w21_write_data((EPD_HEIGHT-1) % 256);
-    epd_w21_write_data((EPD_HEIGHT-1) / 256);
-    lcd_chkstatus();
+    epd_w21_write_cmd(h, 0x4F);  // set RAM y address count
+    epd_w21_write_data(h, (EPD_HEIGHT-1) % 256);
+    epd_w21_write_data(h, (EPD_HEIGHT-1) / 256);
+    lcd_chkstatus(h);
 }
 
-static void epd_init_partial(void) {
+static void epd_init_partial(display_handle_t* h) {
     // For partial refresh, set up partial refresh mode
-    epd_w21_write_cmd(0x3C);  // BorderWavefrom
-    epd_w21_write_data(0x80);  // Partial refresh border setting
+    epd_w21_write_cmd(h, 0x3C);  // BorderWavefrom
+    epd_w21_write_data(h, 0x80);  // Partial refresh border setting
 }
 
-static void epd_update(void) {
-    epd_w21_write_cmd(0x22);  // Display Update Control
-    epd_w21_write_data(0xF7);
-    epd_w21_write_cmd(0x20);  // Activate Display Update Sequence
-    lcd_chkstatus();
+static bool rotation_is_landscape(display_rotation_t rot) {
+    return rot == DISPLAY_ROTATION_CCW_90 || rot == DISPLAY_ROTATION_CW_90;
 }
 
-static void epd_update_partial(void) {
-    epd_w21_write_cmd(0x22);  // Display Update Control
-    epd_w21_write_data(0xFF);
-    epd_w21_write_cmd(0x20);  // Activate Display Update Sequence
-    lcd_chkstatus();
+// Dimensions of the frame callers draw into under the active rotation
+static void logical_dimensions(display_handle_t* h, unsigned* width, unsigned* height) {
+    *width = rotation_is_landscape(h->rotation) ? EPD_HEIGHT : EPD_WIDTH;
+    *height = rotation_is_landscape(h->rotation) ? EPD_WIDTH : EPD_HEIGHT;
+}
+
+// Write a display_set_lut() waveform, and its voltages if it has them
+static void epd_write_lut(display_handle_t* h, display_lut_kind_t kind) {
+    const uint8_t* lut = h->lut[kind];
+    epd_w21_write_cmd(h, 0x32);  // Write LUT register
+    epd_w21_write_data_block(h, lut, DISPLAY_LUT_SIZE);
+    if (h->lut_len[kind] == DISPLAY_LUT_SIZE_VOLTAGES) {
+        const uint8_t* voltages = lut + DISPLAY_LUT_SIZE;
+        epd_w21_write_cmd(h, 0x3F);  // End option (EOPT)
+        epd_w21_write_data(h, voltages[0]);
+        epd_w21_write_cmd(h, 0x03);  // Gate driving voltage
+        epd_w21_write_data(h, voltages[1]);
+        epd_w21_write_cmd(h, 0x04);  // Source driving voltage
+        epd_w21_write_data_block(h, voltages + 2, 3);
+        epd_w21_write_cmd(h, 0x2C);  // VCOM
+        epd_w21_write_data(h, voltages[5]);
+    }
+}
+
+// Run a refresh with a display_set_lut() waveform unless the panel is too
+// cold for it, else with the OTP waveform for the display_set_temperature()
+// reading or, without one, for the panel's own sensor
+static void epd_activate(display_handle_t* h, display_lut_kind_t kind) {
+    uint8_t control = kind == DISPLAY_LUT_FULL ? UPDATE_FULL : UPDATE_PARTIAL;
+    if (h->lut_len[kind] && !is_cold(h)) {
+        epd_write_lut(h, kind);
+        control = kind == DISPLAY_LUT_FULL ? UPDATE_FULL_REGISTER : UPDATE_PARTIAL_REGISTER;
+    } else if (h->temperature_mc != DISPLAY_TEMPERATURE_INTERNAL) {
+        // 12-bit two's complement in 1/16 degrees, most significant bits first
+        int32_t sixteenths = h->temperature_mc * 16 / 1000;
+        epd_w21_write_cmd(h, 0x1A);  // Write temperature register
+        epd_w21_write_data(h, (uint8_t)((sixteenths >> 4) & 0xFF));
+        epd_w21_write_data(h, (uint8_t)((sixteenths & 0x0F) << 4));
+        control &= (uint8_t)~UPDATE_LOAD_TEMPERATURE;
+    }
+    epd_w21_write_cmd(h, 0x22);  // Display Update Control
+    epd_w21_write_data(h, control);
+    epd_w21_write_cmd(h, 0x20);  // Activate Display Update Sequence
+    lcd_chkstatus(h);
+}
+
+static void epd_update(display_handle_t* h) {
+    epd_activate(h, DISPLAY_LUT_FULL);
+}
+
+static void epd_update_partial(display_handle_t* h) {
+    epd_activate(h, DISPLAY_LUT_PARTIAL);
 }
 
 // Public API implementation
-bool display_init(void) {
-    if (initialized) {
-        return true;
+uint32_t display_sdk_abi_version(void) {
+    return DISPLAY_SDK_ABI_VERSION;
+}
+
+const char* display_sdk_version(void) {
+    return DISPLAY_SDK_VERSION;
+}
+
+int display_get_last_error(void) {
+    return last_error;
+}
+
+size_t display_get_last_error_message(char* buf, size_t len) {
+    int n = snprintf(buf, len, "%s", last_error == DISPLAY_OK ? "" : last_error_message);
+    return n < 0 ? 0 : (size_t)n;
+}
+
+const char* display_error_string(int error) {
+    switch (error) {
+        case DISPLAY_OK: return "success";
+        case DISPLAY_ERROR_INVALID_ARGUMENT: return "invalid argument";
+        case DISPLAY_ERROR_NOT_INITIALIZED: return "display not initialized";
+        case DISPLAY_ERROR_INVALID_SIZE: return "image size mismatch";
+        case DISPLAY_ERROR_CONFIG: return "invalid configuration";
+        case DISPLAY_ERROR_SPI: return "SPI error";
+        case DISPLAY_ERROR_GPIO: return "GPIO error";
+        case DISPLAY_ERROR_TIMEOUT: return "busy timeout";
+        case DISPLAY_ERROR_ABORTED: return "update aborted";
+        case DISPLAY_ERROR_DECODE: return "image decode failed";
+        case DISPLAY_ERROR_NO_MEMORY: return "out of memory";
+        case DISPLAY_ERROR_NO_FRAME: return "no frame displayed";
+        case DISPLAY_ERROR_UNSUPPORTED: return "not supported";
+    }
+    return "unknown error";
+}
+
+display_handle_t* display_open(const display_spi_config_t* config) {
+    display_handle_t* h = malloc(sizeof(*h));
+    if (!h) {
+        fail(DISPLAY_ERROR_NO_MEMORY, "Cannot allocate display handle");
+        return NULL;
+    }
+    *h = (display_handle_t){ HANDLE_DEFAULTS };
+    pthread_mutex_init(&h->update_lock, NULL);
+    
+    if (display_h_set_spi_config(h, config) < 0) {
+        pthread_mutex_destroy(&h->update_lock);
+        free(h);
+        return NULL;
+    }
+    
+    pthread_mutex_lock(&handles_lock);
+    h->id = next_handle_id++;
+    display_handle_t* last = &default_handle;
+    while (last->next) {
+        last = last->next;
+    }
+    last->next = h;
+    pthread_mutex_unlock(&handles_lock);
+    return h;
+}
+
+void display_close(display_handle_t* h) {
+    if (!h) {
+        return;
+    }
+    if (h->initialized) {
+        display_h_cleanup(h);
+    }
+    if (h != &default_handle) {
+        pthread_mutex_lock(&handles_lock);
+        for (display_handle_t* prev = &default_handle; prev->next; prev = prev->next) {
+            if (prev->next == h) {
+                prev->next = h->next;
+                break;
+            }
+        }
+        pthread_mutex_unlock(&handles_lock);
+        pthread_mutex_destroy(&h->update_lock);
+        free(h);
+    }
+}
+
+display_handle_t* display_default_handle(void) {
+    return &default_handle;
+}
+
+display_handle_t* display_get(uint32_t id) {
+    pthread_mutex_lock(&handles_lock);
+    display_handle_t* h = &default_handle;
+    while (h && h->id != id) {
+        h = h->next;
+    }
+    pthread_mutex_unlock(&handles_lock);
+    return h;
+}
+
+uint32_t display_get_id(display_handle_t* h) {
+    return h->id;
+}
+
+size_t display_list(uint32_t* ids, size_t max_ids) {
+    size_t count = 0;
+    pthread_mutex_lock(&handles_lock);
+    for (display_handle_t* h = &default_handle; h; h = h->next) {
+        if (ids && count < max_ids) {
+            ids[count] = h->id;
+        }
+        count++;
+    }
+    pthread_mutex_unlock(&handles_lock);
+    return count;
+}
+
+int display_h_init(display_handle_t* h) {
+    if (h->initialized) {
+        return DISPLAY_OK;
     }
     
     // Initialize SPI
-    spi_fd = open("/dev/spidev0.0", O_RDWR);
-    if (spi_fd < 0) {
-        perror("Error opening SPI device");
-        return false;
+    char spi_path[32];
+    snprintf(spi_path, sizeof(spi_path), "/dev/spidev%u.%u", h->spi_config.bus, h->spi_config.cs);
+    h->spi_fd = open(spi_path, O_RDWR);
+    if (h->spi_fd < 0) {
+        return fail(DISPLAY_ERROR_SPI, "Cannot open SPI device %s: %s", spi_path, strerror(errno));
     }
     
     // Configure SPI
     uint8_t mode = SPI_MODE_0;
     uint8_t bits = 8;
-    uint32_t speed = 40000000;
-    
-    if (ioctl(spi_fd, SPI_IOC_WR_MODE, &mode) < 0 ||
-        ioctl(spi_fd, SPI_IOC_WR_BITS_PER_WORD, &bits) < 0 ||
-        ioctl(spi_fd, SPI_IOC_WR_MAX_SPEED_HZ, &speed) < 0) {
-        perror("Error configuring SPI");
-        close(spi_fd);
-        spi_fd = -1;
-        return false;
+    uint32_t speed = h->spi_config.speed_hz;
+    
+    if (ioctl(h->spi_fd, SPI_IOC_WR_MODE, &mode) < 0 ||
+        ioctl(h->spi_fd, SPI_IOC_WR_BITS_PER_WORD, &bits) < 0 ||
+        ioctl(h->spi_fd, SPI_IOC_WR_MAX_SPEED_HZ, &speed) < 0) {
+        int err = errno;
+        close(h->spi_fd);
+        h->spi_fd = -1;
+        return fail(DISPLAY_ERROR_SPI, "Cannot configure SPI device %s: %s", spi_path, strerror(err));
     }
     
     // Initialize GPIO
-    chip = gpiod_chip_open_by_name("gpiochip0");
-    if (!chip) {
-        perror("Error opening GPIO chip");
-        close(spi_fd);
-        spi_fd = -1;
-        return false;
+    h->chip = gpiod_chip_open_by_name(h->spi_config.gpio_chip);
+    if (!h->chip) {
+        int err = errno;
+        close(h->spi_fd);
+        h->spi_fd = -1;
+        return fail(DISPLAY_ERROR_GPIO, "Cannot open GPIO chip %s: %s", h->spi_config.gpio_chip, strerror(err));
     }
     
     // Configure GPIO lines
-    dc_line = gpiod_chip_get_line(chip, DC_PIN);
-    rst_line = gpiod_chip_get_line(chip, RST_PIN);
-    busy_line = gpiod_chip_get_line(chip, BUSY_PIN);
+    h->dc_line = gpiod_chip_get_line(h->chip, h->spi_config.dc_pin);
+    h->rst_line = gpiod_chip_get_line(h->chip, h->spi_config.reset_pin);
+    h->busy_line = gpiod_chip_get_line(h->chip, h->spi_config.busy_pin);
     
-    if (!dc_line || !rst_line || !busy_line) {
-        printf("Error: Failed to get GPIO lines\n");
-        display_cleanup();
-        return false;
+    if (!h->dc_line || !h->rst_line || !h->busy_line) {
+        display_h_cleanup(h);
+        return fail(DISPLAY_ERROR_GPIO, "Failed to get GPIO lines %u, %u and %u of %s",
+                    h->spi_config.dc_pin, h->spi_config.reset_pin, h->spi_config.busy_pin,
+                    h->spi_config.gpio_chip);
     }
     
-    if (gpiod_line_request_output(dc_line, "dc", 0) < 0 ||
-        gpiod_line_request_output(rst_line, "rst", 1) < 0 ||
-        gpiod_line_request_input(busy_line, "busy") < 0) {
-        printf("Error: Failed to configure GPIO lines\n");
-        display_cleanup();
-        return false;
+    if (gpiod_line_request_output(h->dc_line, "dc", 0) < 0 ||
+        gpiod_line_request_output(h->rst_line, "rst", 1) < 0) {
+        int err = errno;
+        display_h_cleanup(h);
+        return fail(DISPLAY_ERROR_GPIO, "Failed to configure GPIO lines: %s", strerror(err));
+    }
+    
+    // Prefer edge events on the busy line so waits sleep in the kernel;
+    // fall back to plain input polling if the driver can't deliver events
+    if (gpiod_line_request_falling_edge_events(h->busy_line, "busy") == 0) {
+        h->busy_events = true;
+    } else if (gpiod_line_request_input(h->busy_line, "busy") == 0) {
+        printf("Warning: Busy line edge events unavailable, using polling\n");
+        h->busy_events = false;
+    } else {
+        int err = errno;
+        display_h_cleanup(h);
+        return fail(DISPLAY_ERROR_GPIO, "Failed to configure GPIO lines: %s", strerror(err));
     }
     
+    memset(&h->stats, 0, sizeof(h->stats));
+    
     // Initialize display hardware
-    epd_init_hardware();
+    h->busy_timed_out = false;
+    h->spi_errno = 0;
+    epd_init_hardware(h);
+    if (h->spi_errno) {
+        int err = h->spi_errno;
+        display_h_cleanup(h);
+        h->last_status = DISPLAY_STATUS_INIT_FAILED;
+        return fail(DISPLAY_ERROR_SPI, "SPI transfer failed during initialization: %s", strerror(err));
+    }
+    if (h->busy_timed_out) {
+        display_h_cleanup(h);
+        h->last_status = DISPLAY_STATUS_TIMEOUT;
+        return fail(DISPLAY_ERROR_TIMEOUT, "Display stayed busy during initialization");
+    }
     
-    initialized = true;
+    h->last_status = DISPLAY_STATUS_OK;
+    h->last_frame_valid = false;
+    h->staged_valid = false;
+    h->abort_requested = 0;
+    h->updates_blocked = 0;
+    h->asleep = false;
+    h->initialized = true;
     printf("Display SDK initialized successfully\n");
-    return true;
+    return DISPLAY_OK;
 }
 
-bool display_image_raw(const uint8_t* data, display_mode_t mode) {
-    if (!initialized || !data) {
-        printf("Error: Display not initialized or invalid data\n");
-        return false;
+// Only a hardware reset brings the controller out of deep sleep, and it
+// clears the controller RAM. The last frame is written back to both RAM
+// banks, so the next partial refresh still has its reference frame.
+static int wake_panel(display_handle_t* h) {
+    uint64_t start = monotonic_ms();
+    h->busy_timed_out = false;
+    epd_init_hardware(h);
+    if (h->busy_timed_out) {
+        h->last_status = DISPLAY_STATUS_TIMEOUT;
+        int err = fail(DISPLAY_ERROR_TIMEOUT, "Display stayed busy while waking");
+        emit_event(h, DISPLAY_EVENT_ERROR, DISPLAY_MODE_FULL, (uint32_t)(monotonic_ms() - start), err);
+        return err;
+    }
+    if (h->last_frame_valid) {
+        epd_w21_write_cmd(h, 0x26);  // previous-frame RAM, compared by partial refresh
+        epd_w21_write_data_block(h, h->last_frame, EPD_ARRAY);
+        epd_w21_write_cmd(h, 0x24);
+        epd_w21_write_data_block(h, h->last_frame, EPD_ARRAY);
+    }
+    h->staged_valid = false;
+    h->asleep = false;
+    h->last_status = DISPLAY_STATUS_OK;
+    emit_event(h, DISPLAY_EVENT_PANEL_WAKE, DISPLAY_MODE_FULL, (uint32_t)(monotonic_ms() - start), DISPLAY_OK);
+    return DISPLAY_OK;
+}
+
+// Argument check shared by the frame updates
+static int check_update(display_handle_t* h, const uint8_t* data) {
+    if (!h->initialized) {
+        h->last_status = DISPLAY_STATUS_INVALID_ARGUMENT;
+        return fail(DISPLAY_ERROR_NOT_INITIALIZED, "Display not initialized");
+    }
+    if (!data) {
+        h->last_status = DISPLAY_STATUS_INVALID_ARGUMENT;
+        return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid image data");
     }
+    return DISPLAY_OK;
+}
+
+// Map a caller's frame onto the panel's native orientation, in rotated if it needs rotating
+static const uint8_t* to_panel_orientation(display_handle_t* h, const uint8_t* data, uint8_t* rotated) {
+    if (h->rotation == DISPLAY_ROTATION_NONE) {
+        return data;
+    }
+    unsigned width, height;
+    logical_dimensions(h, &width, &height);
+    rotate_packed(data, width, height, h->rotation, rotated);
+    return rotated;
+}
+
+// Refresh the panel with a frame in its native orientation. With staged set,
+// the frame is already in the new-frame RAM and is only written again after
+// a reset, which clears the RAM.
+static int refresh_panel(display_handle_t* h, const uint8_t* data, display_mode_t mode, bool staged) {
+    h->staged_valid = false;
     
-    if (mode == DISPLAY_MODE_PARTIAL) {
-        epd_init_partial();
+    // A wedged controller is reset and re-initialized, then the frame is sent again
+    for (uint32_t attempt = 0; ; attempt++) {
+        h->busy_timed_out = false;
+        h->spi_errno = 0;
+        
+        if (mode == DISPLAY_MODE_PARTIAL) {
+            epd_init_partial(h);
+        }
+        
+        // Write image data to display RAM
+        if (!staged || attempt > 0) {
+            epd_w21_write_cmd(h, 0x24);  // write RAM for black(0)/white (1)
+            epd_w21_write_data_block(h, data, EPD_ARRAY);
+        }
+        
+        // Update display
+        if (mode == DISPLAY_MODE_FULL) {
+            epd_update(h);
+        } else {
+            epd_update_partial(h);
+        }
+        
+        if (h->abort_requested && h->busy_timed_out) {
+            // The panel content is undefined; leave the controller as it is
+            h->abort_requested = 0;
+            h->last_frame_valid = false;
+            h->last_status = DISPLAY_STATUS_ABORTED;
+            return set_error(DISPLAY_ERROR_ABORTED, "Display update aborted");
+        }
+        if (h->spi_errno) {
+            h->last_frame_valid = false;
+            h->last_status = DISPLAY_STATUS_UPDATE_FAILED;
+            return fail(DISPLAY_ERROR_SPI, "SPI transfer to the display failed: %s", strerror(h->spi_errno));
+        }
+        if (!h->busy_timed_out) {
+            memcpy(h->last_frame, data, EPD_ARRAY);
+            h->last_frame_valid = true;
+            h->last_status = DISPLAY_STATUS_OK;
+            return DISPLAY_OK;
+        }
+        if (attempt >= h->busy_retries) {
+            break;
+        }
+        
+        printf("Warning: Resetting display after busy timeout (retry %u of %u)\n", attempt + 1, h->busy_retries);
+        epd_init_hardware(h);
     }
     
-    // Write image data to display RAM
-    epd_w21_write_cmd(0x24);  // write RAM for black(0)/white (1)
-    for (size_t i = 0; i < EPD_ARRAY; i++) {
-        epd_w21_write_data(data[i]);
+    h->last_frame_valid = false;
+    h->last_status = DISPLAY_STATUS_TIMEOUT;
+    return fail(DISPLAY_ERROR_TIMEOUT, "Display busy timeout after %u retries, update abandoned", h->busy_retries);
+}
+
+// Refresh with events and stats; frame is in the panel's native orientation
+static int send_frame(display_handle_t* h, const uint8_t* frame, display_mode_t mode, bool staged) {
+    emit_event(h, DISPLAY_EVENT_REFRESH_STARTED, mode, 0, DISPLAY_OK);
+    uint64_t start = monotonic_ms();
+    int err = refresh_panel(h, frame, mode, staged);
+    uint32_t duration_ms = (uint32_t)(monotonic_ms() - start);
+    if (err < 0) {
+        h->stats.failed_refreshes++;
+    } else {
+        if (mode == DISPLAY_MODE_FULL) {
+            h->stats.full_refreshes++;
+        } else {
+            h->stats.partial_refreshes++;
+        }
+        h->stats.last_refresh_ms = duration_ms;
+    }
+    emit_event(h, err < 0 ? DISPLAY_EVENT_ERROR : DISPLAY_EVENT_REFRESH_COMPLETED, mode, duration_ms, err);
+    return err;
+}
+
+// Wake the panel if needed and refresh; frame is in the panel's native orientation. Caller holds update_lock.
+static int write_panel_frame(display_handle_t* h, const uint8_t* frame, display_mode_t mode) {
+    int err;
+    if (h->asleep && (err = wake_panel(h)) < 0) {
+        return err;
+    }
+    return send_frame(h, frame, mode, false);
+}
+
+static int write_frame(display_handle_t* h, const uint8_t* data, display_mode_t mode) {
+    int err = check_update(h, data);
+    if (err < 0) {
+        return err;
+    }
+    uint8_t rotated[EPD_ARRAY];
+    return write_panel_frame(h, to_panel_orientation(h, data, rotated), mode);
+}
+
+int display_h_image_raw(display_handle_t* h, const uint8_t* data, display_mode_t mode) {
+    pthread_mutex_lock(&h->update_lock);
+    int err;
+    if (h->updates_blocked) {
+        h->last_status = DISPLAY_STATUS_ABORTED;
+        err = fail(DISPLAY_ERROR_ABORTED, "Display updates stopped by display_abort()");
+    } else {
+        err = write_frame(h, data, mode);
+    }
+    pthread_mutex_unlock(&h->update_lock);
+    return err;
+}
+
+int display_h_image_auto(display_handle_t* h, const uint8_t* data, display_mode_t* mode_used) {
+    int err = check_update(h, data);
+    if (err < 0) {
+        return err;
     }
     
-    // Update display
-    if (mode == DISPLAY_MODE_FULL) {
-        epd_update();
+    // Choose the mode and refresh under one lock, so no other update changes last_frame in between
+    pthread_mutex_lock(&h->update_lock);
+    if (h->updates_blocked) {
+        h->last_status = DISPLAY_STATUS_ABORTED;
+        err = fail(DISPLAY_ERROR_ABORTED, "Display updates stopped by display_abort()");
+    } else {
+        // Compare in panel orientation, as last_frame is stored
+        uint8_t rotated[EPD_ARRAY];
+        const uint8_t* frame = to_panel_orientation(h, data, rotated);
+        
+        display_mode_t mode = DISPLAY_MODE_FULL;
+        if (h->last_frame_valid) {
+            uint32_t changed = 0;
+            for (size_t i = 0; i < EPD_ARRAY; i++) {
+                changed += (uint32_t)__builtin_popcount(frame[i] ^ h->last_frame[i]);
+            }
+            if (h->auto_max_pixels > 0 && changed <= h->auto_max_pixels) {
+                mode = DISPLAY_MODE_PARTIAL;
+            }
+        }
+        
+        if (mode_used) *mode_used = mode;
+        err = write_panel_frame(h, frame, mode);
+    }
+    pthread_mutex_unlock(&h->update_lock);
+    return err;
+}
+
+void display_h_set_auto_threshold(display_handle_t* h, uint32_t max_pixels) {
+    h->auto_max_pixels = max_pixels;
+}
+
+int display_h_get_framebuffer(display_handle_t* h, uint8_t* out) {
+    if (!out) {
+        return set_error(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid output buffer");
+    }
+    if (!h->last_frame_valid) {
+        return set_error(DISPLAY_ERROR_NO_FRAME, "No frame displayed since initialization or the last failed update");
+    }
+    memcpy(out, h->last_frame, EPD_ARRAY);
+    return DISPLAY_OK;
+}
+
+int display_h_restore_frame(display_handle_t* h, const uint8_t* data) {
+    int err = check_update(h, data);
+    if (err < 0) {
+        return err;
+    }
+    pthread_mutex_lock(&h->update_lock);
+    if (h->updates_blocked) {
+        h->last_status = DISPLAY_STATUS_ABORTED;
+        err = fail(DISPLAY_ERROR_ABORTED, "Display updates stopped by display_abort()");
     } else {
-        epd_update_partial();
+        memcpy(h->last_frame, data, EPD_ARRAY);
+        h->last_frame_valid = true;
+        h->staged_valid = false;
+        // A sleeping panel gets it when it wakes
+        if (!h->asleep) {
+            h->spi_errno = 0;
+            epd_w21_write_cmd(h, 0x26);  // previous-frame RAM, compared by partial refresh
+            epd_w21_write_data_block(h, h->last_frame, EPD_ARRAY);
+            epd_w21_write_cmd(h, 0x24);
+            epd_w21_write_data_block(h, h->last_frame, EPD_ARRAY);
+            if (h->spi_errno) {
+                // The controller's RAM no longer matches, so partial refreshes cannot compare against it
+                h->last_frame_valid = false;
+                err = fail(DISPLAY_ERROR_SPI, "SPI transfer to the display failed: %s", strerror(h->spi_errno));
+            }
+        }
+    }
+    pthread_mutex_unlock(&h->update_lock);
+    return err;
+}
+
+int display_h_write_plane(display_handle_t* h, display_plane_t plane, const uint8_t* data) {
+    int err = check_update(h, data);
+    if (err < 0) {
+        return err;
+    }
+    if (plane != DISPLAY_PLANE_NEW && plane != DISPLAY_PLANE_OLD) {
+        return set_error(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid RAM plane %d", (int)plane);
+    }
+    pthread_mutex_lock(&h->update_lock);
+    if (h->updates_blocked) {
+        h->last_status = DISPLAY_STATUS_ABORTED;
+        err = fail(DISPLAY_ERROR_ABORTED, "Display updates stopped by display_abort()");
+    } else if (!h->asleep || (err = wake_panel(h)) == DISPLAY_OK) {
+        uint8_t rotated[EPD_ARRAY];
+        const uint8_t* frame = to_panel_orientation(h, data, rotated);
+        h->spi_errno = 0;
+        epd_w21_write_cmd(h, plane == DISPLAY_PLANE_NEW ? 0x24 : 0x26);
+        epd_w21_write_data_block(h, frame, EPD_ARRAY);
+        if (h->spi_errno) {
+            h->staged_valid = false;
+            err = fail(DISPLAY_ERROR_SPI, "SPI transfer to the display failed: %s", strerror(h->spi_errno));
+        } else if (plane == DISPLAY_PLANE_NEW) {
+            // Kept to write again should a busy timeout reset the controller during the flip
+            memcpy(h->staged_frame, frame, EPD_ARRAY);
+            h->staged_valid = true;
+        }
+    }
+    pthread_mutex_unlock(&h->update_lock);
+    return err;
+}
+
+int display_h_flip(display_handle_t* h, display_mode_t mode) {
+    if (!h->initialized) {
+        h->last_status = DISPLAY_STATUS_INVALID_ARGUMENT;
+        return fail(DISPLAY_ERROR_NOT_INITIALIZED, "Display not initialized");
+    }
+    pthread_mutex_lock(&h->update_lock);
+    int err;
+    if (h->updates_blocked) {
+        h->last_status = DISPLAY_STATUS_ABORTED;
+        err = fail(DISPLAY_ERROR_ABORTED, "Display updates stopped by display_abort()");
+    } else if (!h->staged_valid) {
+        err = set_error(DISPLAY_ERROR_NO_FRAME, "No frame staged with display_write_plane() since the last update");
+    } else {
+        uint8_t frame[EPD_ARRAY];
+        memcpy(frame, h->staged_frame, EPD_ARRAY);
+        // Waking resets the controller, which clears the RAM
+        bool staged = !h->asleep;
+        if (h->asleep && (err = wake_panel(h)) < 0) {
+            // wake_panel() reported why
+        } else {
+            err = send_frame(h, frame, mode, staged);
+        }
+    }
+    pthread_mutex_unlock(&h->update_lock);
+    return err;
+}
+
+void display_h_abort(display_handle_t* h) {
+    h->updates_blocked = 1;
+    h->abort_requested = 1;
+}
+
+int display_h_emergency_frame(display_handle_t* h, const uint8_t* data) {
+    // Block updates first, so none waiting on the lock runs before this frame
+    display_h_abort(h);
+    pthread_mutex_lock(&h->update_lock);
+    h->abort_requested = 0;
+    int err = DISPLAY_OK;
+    if (data) {
+        err = write_frame(h, data, DISPLAY_MODE_FULL);
+    }
+    display_h_sleep(h);
+    pthread_mutex_unlock(&h->update_lock);
+    return err;
+}
+
+int display_h_image_png(display_handle_t* h, const char* filename, display_mode_t mode) {
+    if (!h->initialized) {
+        return fail(DISPLAY_ERROR_NOT_INITIALIZED, "Display not initialized");
     }
     
-    return true;
+    uint8_t image_data[EPD_ARRAY];
+    int err = display_h_convert_png_to_1bit(h, filename, image_data);
+    if (err < 0) {
+        return err;
+    }
+    
+    return display_h_image_raw(h, image_data, mode);
 }
 
-bool display_image_png(const char* filename, display_mode_t mode) {
-    if (!initialized || !filename) {
-        printf("Error: Display not initialized or invalid filename\n");
-        return false;
+int display_h_image_from_memory(display_handle_t* h, const uint8_t* buf, size_t len, display_mode_t mode) {
+    if (!h->initialized) {
+        return fail(DISPLAY_ERROR_NOT_INITIALIZED, "Display not initialized");
     }
     
     uint8_t image_data[EPD_ARRAY];
-    if (!convert_png_to_1bit(filename, image_data)) {
-        printf("Error: Failed to convert PNG to 1-bit data\n");
-        return false;
+    int err = display_h_process_image_from_memory(h, buf, len, image_data);
+    if (err < 0) {
+        return err;
     }
     
-    return display_image_raw(image_data, mode);
+    return display_h_image_raw(h, image_data, mode);
 }
 
-bool display_clear(void) {
-    if (!initialized) {
-        printf("Error: Display not initialized\n");
-        return false;
+int display_h_clear(display_handle_t* h) {
+    if (!h->initialized) {
+        return fail(DISPLAY_ERROR_NOT_INITIALIZED, "Display not initialized");
     }
     
     // Create white image data (all bits set to 1)
     uint8_t white_data[EPD_ARRAY];
     memset(white_data, 0xFF, EPD_ARRAY);
     
-    return display_image_raw(white_data, DISPLAY_MODE_FULL);
+    return display_h_image_raw(h, white_data, DISPLAY_MODE_FULL);
 }
 
-void display_sleep(void) {
-    if (!initialized) {
+void display_h_sleep(display_handle_t* h) {
+    if (!h->initialized) {
         return;
     }
     
-    epd_w21_write_cmd(0x10);  // Enter deep sleep
-    epd_w21_write_data(0x01);
+    epd_w21_write_cmd(h, 0x10);  // Enter deep sleep
+    epd_w21_write_data(h, 0x01);
     delay_ms(100);
+    h->asleep = true;
+    emit_event(h, DISPLAY_EVENT_PANEL_SLEEP, DISPLAY_MODE_FULL, 0, DISPLAY_OK);
 }
 
-void display_cleanup(void) {
-    if (spi_fd >= 0) {
-        close(spi_fd);
-        spi_fd = -1;
+int display_h_wake(display_handle_t* h) {
+    if (!h->initialized) {
+        return display_h_init(h);
+    }
+    pthread_mutex_lock(&h->update_lock);
+    h->abort_requested = 0;
+    h->updates_blocked = 0;
+    int err = wake_panel(h);
+    pthread_mutex_unlock(&h->update_lock);
+    return err;
+}
+
+bool display_h_is_asleep(display_handle_t* h) {
+    return h->initialized && h->asleep;
+}
+
+void display_h_cleanup(display_handle_t* h) {
+    if (h->spi_fd >= 0) {
+        close(h->spi_fd);
+        h->spi_fd = -1;
     }
     
-    if (dc_line) {
-        gpiod_line_release(dc_line);
-        dc_line = NULL;
+    if (h->dc_line) {
+        gpiod_line_release(h->dc_line);
+        h->dc_line = NULL;
     }
-    if (rst_line) {
-        gpiod_line_release(rst_line);
-        rst_line = NULL;
+    if (h->rst_line) {
+        gpiod_line_release(h->rst_line);
+        h->rst_line = NULL;
     }
-    if (busy_line) {
-        gpiod_line_release(busy_line);
-        busy_line = NULL;
+    if (h->busy_line) {
+        gpiod_line_release(h->busy_line);
+        h->busy_line = NULL;
     }
     
-    if (chip) {
-        gpiod_chip_close(chip);
-        chip = NULL;
+    if (h->chip) {
+        gpiod_chip_close(h->chip);
+        h->chip = NULL;
     }
     
-    initialized = false;
+    h->busy_events = false;
+    h->asleep = false;
+    h->initialized = false;
     printf("Display SDK cleaned up\n");
 }
 
-void display_get_dimensions(uint32_t* width, uint32_t* height) {
-    if (width) *width = EPD_WIDTH;
-    if (height) *height = EPD_HEIGHT;
+int display_h_set_busy_policy(display_handle_t* h, uint32_t timeout_ms, uint32_t retries) {
+    if (timeout_ms == 0) {
+        return fail(DISPLAY_ERROR_CONFIG, "Busy timeout must be non-zero");
+    }
+    h->busy_timeout_ms = timeout_ms;
+    h->busy_retries = retries;
+    return DISPLAY_OK;
 }
 
-bool convert_png_to_1bit(const char* filename, uint8_t* output_data) {
-    if (!filename || !output_data) {
+int display_h_set_lut(display_handle_t* h, display_lut_kind_t kind, const uint8_t* lut, size_t len) {
+    if (kind != DISPLAY_LUT_FULL && kind != DISPLAY_LUT_PARTIAL) {
+        return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid LUT kind %d", (int)kind);
+    }
+    if (lut && len != DISPLAY_LUT_SIZE && len != DISPLAY_LUT_SIZE_VOLTAGES) {
+        return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "LUT must be %d or %d bytes, got %zu",
+                    DISPLAY_LUT_SIZE, DISPLAY_LUT_SIZE_VOLTAGES, len);
+    }
+    // Not while an update is writing the previous LUT
+    pthread_mutex_lock(&h->update_lock);
+    if (lut) {
+        memcpy(h->lut[kind], lut, len);
+    }
+    h->lut_len[kind] = lut ? len : 0;
+    pthread_mutex_unlock(&h->update_lock);
+    return DISPLAY_OK;
+}
+
+int display_h_set_lut_preset(display_handle_t* h, display_lut_kind_t kind, display_lut_preset_t preset) {
+    switch (preset) {
+    case DISPLAY_LUT_PRESET_OTP:
+        return display_h_set_lut(h, kind, NULL, 0);
+    case DISPLAY_LUT_PRESET_FAST_REFRESH:
+        return display_h_set_lut(h, kind, lut_fast_refresh, sizeof(lut_fast_refresh));
+    }
+    return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid LUT preset %d", (int)preset);
+}
+
+int display_h_set_temperature(display_handle_t* h, int32_t millicelsius) {
+    if (millicelsius != DISPLAY_TEMPERATURE_INTERNAL &&
+        (millicelsius < DISPLAY_TEMPERATURE_MIN_MC || millicelsius > DISPLAY_TEMPERATURE_MAX_MC)) {
+        return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Temperature %d m°C out of range", millicelsius);
+    }
+    pthread_mutex_lock(&h->update_lock);
+    h->temperature_mc = millicelsius;
+    pthread_mutex_unlock(&h->update_lock);
+    return DISPLAY_OK;
+}
+
+int display_h_get_temperature(display_handle_t* h, int32_t* millicelsius) {
+    if (!millicelsius) {
+        return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid temperature pointer");
+    }
+    if (h->temperature_mc == DISPLAY_TEMPERATURE_INTERNAL) {
+        return set_error(DISPLAY_ERROR_UNSUPPORTED, "The panel's temperature sensor cannot be read; "
+                         "set a reading with display_set_temperature()");
+    }
+    *millicelsius = h->temperature_mc;
+    return DISPLAY_OK;
+}
+
+display_status_t display_h_get_last_status(display_handle_t* h) {
+    return h->last_status;
+}
+
+void display_h_get_busy_stats(display_handle_t* h, uint32_t* last_ms, uint64_t* total_ms) {
+    if (last_ms) *last_ms = h->stats.last_busy_ms;
+    if (total_ms) *total_ms = h->stats.total_busy_ms;
+}
+
+int display_h_get_stats(display_handle_t* h, display_stats_t* stats) {
+    if (!stats || stats->struct_size < offsetof(display_stats_t, full_refreshes)) {
+        return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid display_stats_t");
+    }
+    display_stats_t current = h->stats;
+    current.struct_size = stats->struct_size;
+    current.average_spi_us = current.spi_transfers ? (uint32_t)(current.total_spi_us / current.spi_transfers) : 0;
+    // Callers built against an older header get the fields they know about
+    memcpy(stats, &current, stats->struct_size < sizeof(current) ? stats->struct_size : sizeof(current));
+    return DISPLAY_OK;
+}
+
+void display_h_set_event_callback(display_handle_t* h, display_event_callback_t callback, void* user_data) {
+    // Not while an update is reporting to the previous callback
+    pthread_mutex_lock(&h->update_lock);
+    h->event_callback = callback;
+    h->event_user_data = user_data;
+    pthread_mutex_unlock(&h->update_lock);
+}
+
+static void emit_event(display_handle_t* h, display_event_type_t type, display_mode_t mode,
+                       uint32_t duration_ms, int error) {
+    if (!h->event_callback) {
+        return;
+    }
+    display_event_t event = {
+        .struct_size = sizeof(display_event_t),
+        .type = type,
+        .display_id = h->id,
+        .mode = mode,
+        .duration_ms = duration_ms,
+        .error = error,
+        .message = type == DISPLAY_EVENT_ERROR ? last_error_message : NULL,
+    };
+    h->event_callback(&event, h->event_user_data);
+}
+
+int display_h_set_rotation(display_handle_t* h, display_rotation_t rot) {
+    if (rot < DISPLAY_ROTATION_NONE || rot > DISPLAY_ROTATION_CW_90) {
+        return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid rotation %d", (int)rot);
+    }
+    h->rotation = rot;
+    return DISPLAY_OK;
+}
+
+display_rotation_t display_h_get_rotation(display_handle_t* h) {
+    return h->rotation;
+}
+
+void display_h_get_dimensions(display_handle_t* h, uint32_t* width, uint32_t* height) {
+    unsigned w, ht;
+    logical_dimensions(h, &w, &ht);
+    if (width) *width = w;
+    if (height) *height = ht;
+}
+
+static inline int get_packed_bit(const uint8_t* data, unsigned width, unsigned x, unsigned y) {
+    unsigned bit_idx = y * width + x;
+    return (data[bit_idx / 8] >> (7 - (bit_idx % 8))) & 1;  // MSB first
+}
+
+static inline void set_packed_bit(uint8_t* data, unsigned width, unsigned x, unsigned y) {
+    unsigned bit_idx = y * width + x;
+    data[bit_idx / 8] |= (1 << (7 - (bit_idx % 8)));  // MSB first
+}
+
+static unsigned pixel_format_bpp(pixel_format_t format) {
+    switch (format) {
+        case PIXEL_FORMAT_GRAY8: return 1;
+        case PIXEL_FORMAT_RGB888: return 3;
+        case PIXEL_FORMAT_RGBA8888: return 4;
+    }
+    return 0;
+}
+
+#if defined(__aarch64__) && defined(__ARM_NEON)
+// 0xFF in each lane whose pixel is white. For color, gray = (r + g + b) / 3 > threshold
+// exactly when r + g + b > 3 * threshold + 2, so no division is needed.
+static inline uint8x16_t white_lanes(const unsigned char* px, unsigned bpp, uint8x16_t threshold8,
+                                     uint16x8_t threshold16) {
+    if (bpp == 1) {
+        return vcgtq_u8(vld1q_u8(px), threshold8);
+    }
+    uint8x16_t r, g, b;
+    if (bpp == 3) {
+        uint8x16x3_t rgb = vld3q_u8(px);
+        r = rgb.val[0]; g = rgb.val[1]; b = rgb.val[2];
+    } else {
+        uint8x16x4_t rgba = vld4q_u8(px);
+        r = rgba.val[0]; g = rgba.val[1]; b = rgba.val[2];
+    }
+    uint16x8_t low = vaddw_u8(vaddl_u8(vget_low_u8(r), vget_low_u8(g)), vget_low_u8(b));
+    uint16x8_t high = vaddw_u8(vaddl_u8(vget_high_u8(r), vget_high_u8(g)), vget_high_u8(b));
+    return vcombine_u8(vmovn_u16(vcgtq_u16(low, threshold16)), vmovn_u16(vcgtq_u16(high, threshold16)));
+}
+
+// Pack 64 pixels per step; returns how many pixels were packed. Each lane keeps its bit
+// (128 for the first pixel of a byte, down to 1), and three rounds of pairwise adds sum
+// every 8 lanes into one byte, in pixel order.
+static size_t pack_pixels_threshold_neon(const unsigned char* pixels, size_t count, unsigned bpp,
+                                         uint8_t threshold, uint8_t* output_data) {
+    static const uint8_t bit_weights[16] = {128, 64, 32, 16, 8, 4, 2, 1, 128, 64, 32, 16, 8, 4, 2, 1};
+    const uint8x16_t weights = vld1q_u8(bit_weights);
+    const uint8x16_t threshold8 = vdupq_n_u8(threshold);
+    const uint16x8_t threshold16 = vdupq_n_u16(3 * threshold + 2);
+    size_t i = 0;
+    for (; i + 64 <= count; i += 64) {
+        const unsigned char* px = pixels + i * bpp;
+        uint8x16_t b0 = vandq_u8(white_lanes(px, bpp, threshold8, threshold16), weights);
+        uint8x16_t b1 = vandq_u8(white_lanes(px + 16 * bpp, bpp, threshold8, threshold16), weights);
+        uint8x16_t b2 = vandq_u8(white_lanes(px + 32 * bpp, bpp, threshold8, threshold16), weights);
+        uint8x16_t b3 = vandq_u8(white_lanes(px + 48 * bpp, bpp, threshold8, threshold16), weights);
+        uint8x16_t quads = vpaddq_u8(vpaddq_u8(b0, b1), vpaddq_u8(b2, b3));
+        vst1_u8(output_data + i / 8, vget_low_u8(vpaddq_u8(quads, quads)));
+    }
+    return i;
+}
+#endif
+
+static void pack_pixels_threshold(const unsigned char* pixels, unsigned width, unsigned height,
+                                  pixel_format_t format, uint8_t threshold, uint8_t* output_data) {
+    unsigned bpp = pixel_format_bpp(format);
+    size_t count = (size_t)width * height;
+    size_t i = 0;
+#if defined(__aarch64__) && defined(__ARM_NEON)
+    i = pack_pixels_threshold_neon(pixels, count, bpp, threshold, output_data);
+#endif
+    
+    // The rest a byte at a time, MSB first; bits past the last pixel stay 0
+    for (; i < count; i += 8) {
+        size_t n = count - i < 8 ? count - i : 8;
+        uint8_t byte = 0;
+        for (size_t bit = 0; bit < n; bit++) {
+            const unsigned char* px = pixels + (i + bit) * bpp;
+            
+            // Convert to grayscale
+            unsigned gray = (bpp == 1) ? px[0] : (px[0] + px[1] + px[2]) / 3;
+            
+            // Threshold to 1-bit (0 = black, 1 = white)
+            if (gray > threshold) {
+                byte |= 0x80 >> bit;
+            }
+        }
+        output_data[i / 8] = byte;
+    }
+}
+
+static void rotate_packed(const uint8_t* src, unsigned width, unsigned height,
+                          display_rotation_t rot, uint8_t* dst) {
+    // 90-degree rotations produce a height x width frame
+    unsigned dst_width = rotation_is_landscape(rot) ? height : width;
+    
+    memset(dst, 0, (width * height + 7) / 8);
+    for (unsigned y = 0; y < height; y++) {
+        for (unsigned x = 0; x < width; x++) {
+            if (!get_packed_bit(src, width, x, y)) {
+                continue;
+            }
+            switch (rot) {
+                case DISPLAY_ROTATION_CCW_90:
+                    set_packed_bit(dst, dst_width, y, width - 1 - x);
+                    break;
+                case DISPLAY_ROTATION_CW_90:
+                    set_packed_bit(dst, dst_width, height - 1 - y, x);
+                    break;
+                case DISPLAY_ROTATION_180:
+                    set_packed_bit(dst, dst_width, width - 1 - x, height - 1 - y);
+                    break;
+                default:
+                    set_packed_bit(dst, dst_width, x, y);
+                    break;
+            }
+        }
+    }
+}
+
+static int convert_rgba_to_1bit(display_handle_t* h, const unsigned char* rgba, unsigned width, unsigned height, uint8_t* output_data) {
+    // Check dimensions against the caller-facing frame
+    unsigned expected_width, expected_height;
+    logical_dimensions(h, &expected_width, &expected_height);
+    if (width != expected_width || height != expected_height) {
+        return fail(DISPLAY_ERROR_INVALID_SIZE, "PNG dimensions (%ux%u) don't match display (%ux%u)",
+                    width, height, expected_width, expected_height);
+    }
+    
+    pack_pixels_threshold(rgba, width, height, PIXEL_FORMAT_RGBA8888, 128, output_data);
+    return DISPLAY_OK;
+}
+
+static void set_result(display_result_t* result, display_status_t status, const char* fmt, ...) {
+    result->status = status;
+    va_list args;
+    va_start(args, fmt);
+    vsnprintf(result->message, sizeof(result->message), fmt, args);
+    va_end(args);
+}
+
+static bool process_pixels_with_options(display_handle_t* h, const unsigned char* pixels, unsigned width, unsigned height,
+                                        pixel_format_t format, const processing_options_t* options,
+                                        uint8_t* output_data, display_result_t* result) {
+    if (pixel_format_bpp(format) == 0) {
+        set_result(result, DISPLAY_STATUS_INVALID_ARGUMENT, "Unsupported pixel format %d", (int)format);
+        return false;
+    }
+    
+    // options->rotate turns a source of swapped dimensions into the caller-facing frame
+    unsigned frame_width, frame_height;
+    logical_dimensions(h, &frame_width, &frame_height);
+    unsigned expected_width = options->rotate ? frame_height : frame_width;
+    unsigned expected_height = options->rotate ? frame_width : frame_height;
+    if (width != expected_width || height != expected_height) {
+        set_result(result, DISPLAY_STATUS_PROCESS_FAILED,
+                   "Image dimensions (%ux%u) don't match expected (%ux%u)",
+                   width, height, expected_width, expected_height);
         return false;
     }
     
+    uint8_t packed[EPD_ARRAY];
+    uint8_t scratch[EPD_ARRAY];
+    pack_pixels_threshold(pixels, width, height, format, options->threshold, packed);
+    
+    // Apply transformations in DistillerGUI order: flip, rotate, then invert colors
+    if (options->flip_horizontal) {
+        memset(scratch, 0, EPD_ARRAY);
+        for (unsigned y = 0; y < height; y++) {
+            for (unsigned x = 0; x < width; x++) {
+                if (get_packed_bit(packed, width, x, y)) {
+                    set_packed_bit(scratch, width, width - 1 - x, y);
+                }
+            }
+        }
+        memcpy(packed, scratch, EPD_ARRAY);
+    }
+    
+    if (options->rotate) {
+        // 90 degrees counter-clockwise: destination is height x width
+        rotate_packed(packed, width, height, DISPLAY_ROTATION_CCW_90, scratch);
+        memcpy(packed, scratch, EPD_ARRAY);
+    }
+    
+    if (options->invert_colors) {
+        for (size_t i = 0; i < EPD_ARRAY; i++) {
+            packed[i] = ~packed[i];
+        }
+    }
+    
+    memcpy(output_data, packed, EPD_ARRAY);
+    return true;
+}
+
+int display_h_convert_png_to_1bit(display_handle_t* h, const char* filename, uint8_t* output_data) {
+    if (!filename || !output_data) {
+        return set_error(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid filename or output buffer");
+    }
+    
     unsigned char* image_data;
     unsigned width, height;
     
     // Load PNG
     unsigned error = lodepng_decode32_file(&image_data, &width, &height, filename);
     if (error) {
-        printf("Error loading PNG: %s\n", lodepng_error_text(error));
-        return false;
+        return fail(DISPLAY_ERROR_DECODE, "Cannot load PNG %s: %s", filename, lodepng_error_text(error));
     }
     
-    // Check dimensions
-    if (width != EPD_WIDTH || height != EPD_HEIGHT) {
-        printf("Error: PNG dimensions (%dx%d) don't match display (%dx%d)\n",
-               width, height, EPD_WIDTH, EPD_HEIGHT);
-        free(image_data);
-        return false;
+    int err = convert_rgba_to_1bit(h, image_data, width, height, output_data);
+    free(image_data);
+    return err;
+}
+
+// libjpeg reports fatal errors through error_exit, which must not return
+struct jpeg_error_jump {
+    struct jpeg_error_mgr mgr;
+    jmp_buf jump;
+    char message[JMSG_LENGTH_MAX];
+};
+
+static void jpeg_error_exit(j_common_ptr cinfo) {
+    struct jpeg_error_jump* err = (struct jpeg_error_jump*)cinfo->err;
+    (*cinfo->err->format_message)(cinfo, err->message);
+    longjmp(err->jump, 1);
+}
+
+// Corrupt data is only a warning to libjpeg; fail on it like lodepng does
+static void jpeg_emit_message(j_common_ptr cinfo, int msg_level) {
+    if (msg_level < 0) {
+        (*cinfo->err->error_exit)(cinfo);
     }
+}
+
+static int decode_jpeg_to_1bit(display_handle_t* h, const uint8_t* buf, size_t len, uint8_t* output_data) {
+    struct jpeg_decompress_struct cinfo;
+    struct jpeg_error_jump jerr;
+    unsigned char* volatile rgba = NULL;
+    unsigned char* volatile row = NULL;
     
-    // Convert RGBA to 1-bit
-    memset(output_data, 0, EPD_ARRAY);
+    cinfo.err = jpeg_std_error(&jerr.mgr);
+    jerr.mgr.error_exit = jpeg_error_exit;
+    jerr.mgr.emit_message = jpeg_emit_message;
+    if (setjmp(jerr.jump)) {
+        jpeg_destroy_decompress(&cinfo);
+        free(rgba);
+        free(row);
+        return fail(DISPLAY_ERROR_DECODE, "Cannot decode JPEG buffer: %s", jerr.message);
+    }
     
-    for (unsigned y = 0; y < height; y++) {
+    jpeg_create_decompress(&cinfo);
+    jpeg_mem_src(&cinfo, (unsigned char*)buf, (unsigned long)len);
+    jpeg_read_header(&cinfo, TRUE);
+    if (cinfo.jpeg_color_space == JCS_CMYK || cinfo.jpeg_color_space == JCS_YCCK) {
+        jpeg_destroy_decompress(&cinfo);
+        return fail(DISPLAY_ERROR_DECODE, "Cannot decode JPEG buffer: CMYK images are not supported");
+    }
+    
+    // Check the size from the header, so a mismatched image is never decoded
+    unsigned width = cinfo.image_width, height = cinfo.image_height;
+    unsigned expected_width, expected_height;
+    logical_dimensions(h, &expected_width, &expected_height);
+    if (width != expected_width || height != expected_height) {
+        jpeg_destroy_decompress(&cinfo);
+        return fail(DISPLAY_ERROR_INVALID_SIZE, "JPEG dimensions (%ux%u) don't match display (%ux%u)",
+                    width, height, expected_width, expected_height);
+    }
+    
+    cinfo.out_color_space = cinfo.num_components == 1 ? JCS_GRAYSCALE : JCS_RGB;
+    jpeg_start_decompress(&cinfo);
+    unsigned components = cinfo.output_components;
+    rgba = malloc((size_t)width * height * 4);
+    row = malloc((size_t)width * components);
+    if (!rgba || !row) {
+        jpeg_destroy_decompress(&cinfo);
+        free(rgba);
+        free(row);
+        return fail(DISPLAY_ERROR_NO_MEMORY, "Cannot allocate JPEG buffers");
+    }
+    
+    // Expand each grayscale or RGB scanline to RGBA
+    while (cinfo.output_scanline < height) {
+        unsigned char* out = rgba + (size_t)cinfo.output_scanline * width * 4;
+        JSAMPROW rows[1] = {row};
+        jpeg_read_scanlines(&cinfo, rows, 1);
         for (unsigned x = 0; x < width; x++) {
-            unsigned pixel_idx = y * width + x;
-            unsigned rgba_idx = pixel_idx * 4;
-            
-            // Convert to grayscale
-            unsigned char gray = (image_data[rgba_idx] + image_data[rgba_idx + 1] + image_data[rgba_idx + 2]) / 3;
-            
-            // Threshold to 1-bit (0 = black, 1 = white)
-            unsigned char bit = (gray > 128) ? 1 : 0;
-            
-            // Pack into output buffer
-            unsigned bit_idx = pixel_idx;
-            unsigned byte_idx = bit_idx / 8;
-            unsigned bit_pos = 7 - (bit_idx % 8);  // MSB first
-            
-            if (bit) {
-                output_data[byte_idx] |= (1 << bit_pos);
-            }
+            const unsigned char* in = row + (size_t)x * components;
+            out[x * 4] = in[0];
+            out[x * 4 + 1] = in[components == 3 ? 1 : 0];
+            out[x * 4 + 2] = in[components == 3 ? 2 : 0];
+            out[x * 4 + 3] = 255;
         }
     }
+    jpeg_finish_decompress(&cinfo);
+    jpeg_destroy_decompress(&cinfo);
+    
+    int err = convert_rgba_to_1bit(h, rgba, width, height, output_data);
+    free(rgba);
+    free(row);
+    return err;
+}
+
+int display_h_process_image_from_memory(display_handle_t* h, const uint8_t* buf, size_t len, uint8_t* output_data) {
+    if (!buf || len == 0 || !output_data) {
+        return set_error(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid image buffer or output buffer");
+    }
+    
+    // PNG and JPEG are decoded here; the Python bindings convert other formats to PNG first
+    static const uint8_t png_signature[8] = {0x89, 'P', 'N', 'G', '\r', '\n', 0x1A, '\n'};
+    static const uint8_t jpeg_signature[3] = {0xFF, 0xD8, 0xFF};
+    if (len >= sizeof(jpeg_signature) && memcmp(buf, jpeg_signature, sizeof(jpeg_signature)) == 0) {
+        return decode_jpeg_to_1bit(h, buf, len, output_data);
+    }
+    if (len < sizeof(png_signature) || memcmp(buf, png_signature, sizeof(png_signature)) != 0) {
+        return set_error(DISPLAY_ERROR_INVALID_ARGUMENT, "Not a PNG or JPEG image; only these are decoded from memory");
+    }
+    
+    unsigned char* image_data;
+    unsigned width, height;
     
+    // Decode PNG straight from the caller's buffer
+    unsigned error = lodepng_decode32(&image_data, &width, &height, buf, len);
+    if (error) {
+        return fail(DISPLAY_ERROR_DECODE, "Cannot decode PNG buffer: %s", lodepng_error_text(error));
+    }
+    
+    int err = convert_rgba_to_1bit(h, image_data, width, height, output_data);
     free(image_data);
+    return err;
+}
+
+void display_spi_config_init(display_spi_config_t* config) {
+    if (!config) {
+        return;
+    }
+    
+    memset(config, 0, sizeof(*config));
+    config->struct_size = sizeof(*config);
+    config->bus = SPI_BUS;
+    config->cs = SPI_CS;
+    config->speed_hz = SPI_SPEED_HZ;
+    config->dc_pin = DC_PIN;
+    config->reset_pin = RST_PIN;
+    config->busy_pin = BUSY_PIN;
+    strncpy(config->gpio_chip, GPIO_CHIP_NAME, sizeof(config->gpio_chip) - 1);
+    config->block_size = SPI_BLOCK_SIZE;
+    config->chunk_delay_us = 0;
+}
+
+int display_h_set_spi_config(display_handle_t* h, const display_spi_config_t* config) {
+    if (h->initialized) {
+        return fail(DISPLAY_ERROR_CONFIG, "SPI configuration must be set before display_init()");
+    }
+    
+    display_spi_config_t resolved;
+    display_spi_config_init(&resolved);
+    if (config) {
+        // Honor callers built against an older, shorter struct
+        if (config->struct_size < offsetof(display_spi_config_t, bus)) {
+            return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid display_spi_config_t struct_size");
+        }
+        size_t size = config->struct_size < sizeof(resolved) ? config->struct_size : sizeof(resolved);
+        memcpy(&resolved, config, size);
+        resolved.struct_size = sizeof(resolved);
+    }
+    
+    if (resolved.speed_hz == 0 || resolved.block_size == 0 ||
+        resolved.dc_pin == resolved.reset_pin || resolved.dc_pin == resolved.busy_pin ||
+        resolved.reset_pin == resolved.busy_pin ||
+        memchr(resolved.gpio_chip, '\0', sizeof(resolved.gpio_chip)) == NULL ||
+        resolved.gpio_chip[0] == '\0') {
+        return fail(DISPLAY_ERROR_CONFIG, "Invalid SPI configuration");
+    }
+    
+    h->spi_config = resolved;
+    return DISPLAY_OK;
+}
+
+void display_h_get_spi_config(display_handle_t* h, display_spi_config_t* config) {
+    if (!config || config->struct_size < offsetof(display_spi_config_t, bus)) {
+        return;
+    }
+    size_t size = config->struct_size < sizeof(h->spi_config) ? config->struct_size : sizeof(h->spi_config);
+    uint32_t caller_size = config->struct_size;
+    memcpy(config, &h->spi_config, size);
+    config->struct_size = caller_size;
+}
+
+void processing_options_init(processing_options_t* options) {
+    if (!options) {
+        return;
+    }
+    
+    memset(options, 0, sizeof(*options));
+    options->struct_size = sizeof(*options);
+    options->threshold = 128;
+}
+
+// Copy caller options over defaults, honoring callers built against an older, shorter struct
+static bool resolve_options(const processing_options_t* options, processing_options_t* resolved) {
+    processing_options_init(resolved);
+    if (!options) {
+        return true;
+    }
+    
+    if (options->struct_size < offsetof(processing_options_t, threshold)) {
+        return false;
+    }
+    
+    size_t size = options->struct_size < sizeof(*resolved) ? options->struct_size : sizeof(*resolved);
+    memcpy(resolved, options, size);
+    resolved->struct_size = sizeof(*resolved);
     return true;
-} 
\ No newline at end of file
+}
+
+display_result_t display_h_show_file(display_handle_t* h, const char* filename, const processing_options_t* options, display_mode_t mode) {
+    display_result_t result;
+    set_result(&result, DISPLAY_STATUS_OK, "OK");
+    
+    if (!filename) {
+        set_result(&result, DISPLAY_STATUS_INVALID_ARGUMENT, "Invalid filename");
+        return result;
+    }
+    
+    processing_options_t resolved;
+    if (!resolve_options(options, &resolved)) {
+        set_result(&result, DISPLAY_STATUS_INVALID_ARGUMENT, "Invalid processing_options_t struct_size");
+        return result;
+    }
+    options = &resolved;
+    
+    if (!h->initialized && display_h_init(h) < 0) {
+        set_result(&result, DISPLAY_STATUS_INIT_FAILED, "Failed to initialize display hardware: %s",
+                   last_error_message);
+        return result;
+    }
+    
+    unsigned char* rgba;
+    unsigned width, height;
+    unsigned error = lodepng_decode32_file(&rgba, &width, &height, filename);
+    if (error) {
+        set_result(&result, DISPLAY_STATUS_LOAD_FAILED, "Failed to load %s: %s",
+                   filename, lodepng_error_text(error));
+        return result;
+    }
+    
+    uint8_t image_data[EPD_ARRAY];
+    bool ok = process_pixels_with_options(h, rgba, width, height, PIXEL_FORMAT_RGBA8888,
+                                          options, image_data, &result);
+    free(rgba);
+    if (!ok) {
+        return result;
+    }
+    
+    if (display_h_image_raw(h, image_data, mode) < 0) {
+        if (h->last_status == DISPLAY_STATUS_TIMEOUT) {
+            set_result(&result, DISPLAY_STATUS_TIMEOUT, "Display stayed busy after reset and %u retries", h->busy_retries);
+        } else {
+            set_result(&result, DISPLAY_STATUS_UPDATE_FAILED, "Failed to update display: %s", last_error_message);
+        }
+        return result;
+    }
+    
+    return result;
+}
+
+int display_h_process_pixels_for_display(display_handle_t* h, const uint8_t* pixels, uint32_t width, uint32_t height,
+                                pixel_format_t format, const processing_options_t* options,
+                                uint8_t* output_data) {
+    if (!pixels || !output_data) {
+        return set_error(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid pixel buffer or output buffer");
+    }
+    
+    processing_options_t resolved;
+    if (!resolve_options(options, &resolved)) {
+        return fail(DISPLAY_ERROR_INVALID_ARGUMENT, "Invalid processing_options_t struct_size");
+    }
+    options = &resolved;
+    
+    display_result_t result;
+    if (!process_pixels_with_options(h, pixels, width, height, format, options, output_data, &result)) {
+        return fail(result.status == DISPLAY_STATUS_INVALID_ARGUMENT ? DISPLAY_ERROR_INVALID_ARGUMENT
+                                                                     : DISPLAY_ERROR_INVALID_SIZE,
+                    "%s", result.message);
+    }
+    return DISPLAY_OK;
+}
+
+int display_h_pixels(display_handle_t* h, const uint8_t* pixels, uint32_t width, uint32_t height,
+                    pixel_format_t format, const processing_options_t* options,
+                    display_mode_t mode) {
+    if (!h->initialized) {
+        return fail(DISPLAY_ERROR_NOT_INITIALIZED, "Display not initialized");
+    }
+    
+    uint8_t image_data[EPD_ARRAY];
+    int err = display_h_process_pixels_for_display(h, pixels, width, height, format, options, image_data);
+    if (err < 0) {
+        return err;
+    }
+    
+    return display_h_image_raw(h, image_data, mode);
+}
+
+// Free functions: the original single-panel API, driving the default handle
+int display_init(void) {
+    return display_h_init(&default_handle);
+}
+
+int display_image_raw(const uint8_t* data, display_mode_t mode) {
+    return display_h_image_raw(&default_handle, data, mode);
+}
+
+int display_image_png(const char* filename, display_mode_t mode) {
+    return display_h_image_png(&default_handle, filename, mode);
+}
+
+int display_clear(void) {
+    return display_h_clear(&default_handle);
+}
+
+void display_sleep(void) {
+    display_h_sleep(&default_handle);
+}
+
+int display_wake(void) {
+    return display_h_wake(&default_handle);
+}
+
+bool display_is_asleep(void) {
+    return display_h_is_asleep(&default_handle);
+}
+
+void display_cleanup(void) {
+    display_h_cleanup(&default_handle);
+}
+
+int display_set_rotation(display_rotation_t rotation) {
+    return display_h_set_rotation(&default_handle, rotation);
+}
+
+display_rotation_t display_get_rotation(void) {
+    return display_h_get_rotation(&default_handle);
+}
+
+void display_get_dimensions(uint32_t* width, uint32_t* height) {
+    display_h_get_dimensions(&default_handle, width, height);
+}
+
+int display_set_busy_policy(uint32_t timeout_ms, uint32_t retries) {
+    return display_h_set_busy_policy(&default_handle, timeout_ms, retries);
+}
+
+display_status_t display_get_last_status(void) {
+    return display_h_get_last_status(&default_handle);
+}
+
+int display_image_auto(const uint8_t* data, display_mode_t* mode_used) {
+    return display_h_image_auto(&default_handle, data, mode_used);
+}
+
+void display_set_auto_threshold(uint32_t max_pixels) {
+    display_h_set_auto_threshold(&default_handle, max_pixels);
+}
+
+int display_get_framebuffer(uint8_t* out) {
+    return display_h_get_framebuffer(&default_handle, out);
+}
+
+int display_restore_frame(const uint8_t* data) {
+    return display_h_restore_frame(&default_handle, data);
+}
+
+int display_write_plane(display_plane_t plane, const uint8_t* data) {
+    return display_h_write_plane(&default_handle, plane, data);
+}
+
+int display_flip(display_mode_t mode) {
+    return display_h_flip(&default_handle, mode);
+}
+
+void display_abort(void) {
+    display_h_abort(&default_handle);
+}
+
+int display_emergency_frame(const uint8_t* data) {
+    return display_h_emergency_frame(&default_handle, data);
+}
+
+void display_get_busy_stats(uint32_t* last_ms, uint64_t* total_ms) {
+    display_h_get_busy_stats(&default_handle, last_ms, total_ms);
+}
+
+void display_set_event_callback(display_event_callback_t callback, void* user_data) {
+    display_h_set_event_callback(&default_handle, callback, user_data);
+}
+
+int display_get_stats(display_stats_t* stats) {
+    return display_h_get_stats(&default_handle, stats);
+}
+
+int display_set_lut(display_lut_kind_t kind, const uint8_t* lut, size_t len) {
+    return display_h_set_lut(&default_handle, kind, lut, len);
+}
+
+int display_set_lut_preset(display_lut_kind_t kind, display_lut_preset_t preset) {
+    return display_h_set_lut_preset(&default_handle, kind, preset);
+}
+
+int display_set_temperature(int32_t millicelsius) {
+    return display_h_set_temperature(&default_handle, millicelsius);
+}
+
+int display_get_temperature(int32_t* millicelsius) {
+    return display_h_get_temperature(&default_handle, millicelsius);
+}
+
+int convert_png_to_1bit(const char* filename, uint8_t* output_data) {
+    return display_h_convert_png_to_1bit(&default_handle, filename, output_data);
+}
+
+int process_image_from_memory(const uint8_t* buf, size_t len, uint8_t* output_data) {
+    return display_h_process_image_from_memory(&default_handle, buf, len, output_data);
+}
+
+int display_image_from_memory(const uint8_t* buf, size_t len, display_mode_t mode) {
+    return display_h_image_from_memory(&default_handle, buf, len, mode);
+}
+
+int display_set_spi_config(const display_spi_config_t* config) {
+    return display_h_set_spi_config(&default_handle, config);
+}
+
+void display_get_spi_config(display_spi_config_t* config) {
+    display_h_get_spi_config(&default_handle, config);
+}
+
+display_result_t display_show_file(const char* filename, const processing_options_t* options, display_mode_t mode) {
+    return display_h_show_file(&default_handle, filename, options, mode);
+}
+
+int process_pixels_for_display(const uint8_t* pixels, uint32_t width, uint32_t height,
+                               pixel_format_t format, const processing_options_t* options,
+                               uint8_t* output_data) {
+    return display_h_process_pixels_for_display(&default_handle, pixels, width, height, format, options, output_data);
+}
+
+int display_pixels(const uint8_t* pixels, uint32_t width, uint32_t height,
+                   pixel_format_t format, const processing_options_t* options,
+                   display_mode_t mode) {
+    return display_h_pixels(&default_handle, pixels, width, height, format, options, mode);
+}
@@ -0,0 +1,103 @@
+#!/usr/bin/env python3
+"""
+Generate include/distiller_eink.h, the installable header of the display library.
+
+The header is distiller_display_sdk.h made self-contained for C and C++
+consumers: wrapped in extern "C", with the SDK and ABI versions in the
+banner. Generation fails if DISPLAY_SDK_VERSION in the source header, the
+Python bindings and pyproject.toml disagree, so a release cannot ship a
+library that reports another version than its package.
+
+    python3 gen_header.py            # write the header (build.sh does this)
+    python3 gen_header.py --check    # fail if it is missing or stale
+"""
+
+import argparse
+import itertools
+import re
+import sys
+from pathlib import Path
+
+LIB_DIR = Path(__file__).resolve().parent
+SOURCE = LIB_DIR / "distiller_display_sdk.h"
+OUTPUT = LIB_DIR.parent / "include" / "distiller_eink.h"
+BINDINGS = LIB_DIR.parent / "display.py"
+PYPROJECT = LIB_DIR.parents[4] / "pyproject.toml"
+
+
+def _find(pattern: str, text: str, where: Path) -> str:
+    match = re.search(pattern, text, re.MULTILINE)
+    if match is None:
+        raise SystemExit(f"Error: No version found in {where}")
+    return match.group(1)
+
+
+def check_versions(source: str) -> str:
+    """Return DISPLAY_SDK_VERSION, exiting if the bindings or package disagree."""
+    version = _find(r'^#define DISPLAY_SDK_VERSION "([^"]+)"', source, SOURCE)
+    others = {BINDINGS: _find(r'^DISPLAY_SDK_VERSION = "([^"]+)"', BINDINGS.read_text(), BINDINGS)}
+    if PYPROJECT.exists():      # absent from installed packages
+        others[PYPROJECT] = _find(r'^version = "([^"]+)"', PYPROJECT.read_text(), PYPROJECT)
+    for path, other in others.items():
+        if other != version:
+            raise SystemExit(f"Error: {SOURCE.name} is version {version} but {path.name} is {other}")
+    return version
+
+
+def generate(source: str) -> str:
+    version = check_versions(source)
+    abi_version = _find(r"^#define DISPLAY_SDK_ABI_VERSION (\d+)", source, SOURCE)
+    body = source.strip()
+    lines = body[body.index("#include"):body.rindex("#endif")].rstrip().splitlines()
+    includes = list(itertools.takewhile(lambda line: line.startswith("#include"), lines))
+    body = "\n".join(lines[len(includes):]).strip()
+    return f"""/*
+ * distiller_eink.h - Distiller CM5 e-ink display library, SDK {version}, ABI {abi_version}
+ *
+ * Generated from lib/{SOURCE.name} by lib/{Path(__file__).name}; do not edit.
+ * Link with -ldistiller_display_sdk_shared and compare display_sdk_abi_version()
+ * with DISPLAY_SDK_ABI_VERSION before any other call.
+ */
+#ifndef DISTILLER_EINK_H
+#define DISTILLER_EINK_H
+
+#ifndef DISTILLER_DISPLAY_SDK_H
+#define DISTILLER_DISPLAY_SDK_H
+
+{chr(10).join(includes)}
+
+#ifdef __cplusplus
+extern "C" {{
+#endif
+
+{body}
+
+#ifdef __cplusplus
+}}
+#endif
+
+#endif // DISTILLER_DISPLAY_SDK_H
+
+#endif // DISTILLER_EINK_H
+"""
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Generate include/distiller_eink.h")
+    parser.add_argument("--check", action="store_true", help="Only check that the header is up to date")
+    args = parser.parse_args(argv)
+
+    header = generate(SOURCE.read_text())
+    if args.check:
+        if not OUTPUT.exists() or OUTPUT.read_text() != header:
+            print(f"Error: {OUTPUT} is out of date; run {Path(__file__).name}", file=sys.stderr)
+            return 1
+        return 0
+    OUTPUT.parent.mkdir(exist_ok=True)
+    OUTPUT.write_text(header)
+    print(f"[INFO] Generated {OUTPUT}")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
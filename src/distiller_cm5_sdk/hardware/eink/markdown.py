@@ -0,0 +1,295 @@
+#!/usr/bin/env python3
+"""
+Markdown rendering for the e-ink display.
+
+Status screens are often quickest to write as text: a heading, a few
+bullet points, a small table. render_markdown() draws a Markdown string in
+the built-in font, word-wrapped to the frame width:
+
+    fb = render_markdown(\"\"\"
+    # Backup
+    **Last run:** 02:00, ok
+    - 12 GB copied
+    - 3 files skipped
+
+    | Disk | Free |
+    |------|-----:|
+    | sda  |  41% |
+    \"\"\", width, height)
+    display.display_image(fb.to_bytes())
+
+Only what a small 1-bit screen can show is supported: ATX headings (#),
+paragraphs, bullet and numbered lists nested by indentation, **bold**,
+horizontal rules and pipe tables with column alignment. Anything else,
+e.g. links or *emphasis*, is drawn as written. In a layout template, a
+"markdown" node draws the same in its box, with {field} placeholders
+filled in first.
+"""
+
+import re
+from dataclasses import dataclass, field
+from itertools import groupby
+from typing import List, Optional, Tuple
+
+from .font import FONT_HEIGHT, FONT_WIDTH
+from .graphics import PANEL_WIDTH, Framebuffer, wrap_text
+
+
+# Spacing, in pixels at text size 1
+BLOCK_GAP = 4                   # between headings, paragraphs, lists, rules and tables
+ITEM_GAP = 1                    # between the items of a list, and the rows of a table
+LIST_INDENT = 2 * (FONT_WIDTH + 1)  # per nesting level
+TABLE_GAP = FONT_WIDTH + 1      # between table columns
+
+_CELL = FONT_WIDTH + 1
+_HEADING = re.compile(r"^ {0,3}(#{1,6})(?:[ \t]+(.*?))?(?:[ \t]+#+)?[ \t]*$")
+_RULE = re.compile(r"^ {0,3}([-*_])(?:[ \t]*\1){2,}[ \t]*$")
+_ITEM = re.compile(r"^( *)([-*+]|\d{1,9}[.)])(?:[ \t]+(.*))?$")
+_TABLE_SEPARATOR = re.compile(r"^ *\|? *:?-+:? *(?:\| *:?-+:? *)*\|? *$")
+_ESCAPABLE = set("\\`*_{}[]()#+-.!|")
+
+# A draw operation: ("text", x, y, text, bold, scale) or ("rule", x, y, length)
+_Op = tuple
+
+
+@dataclass
+class _Block:
+    kind: str                   # "heading", "paragraph", "item", "rule" or "table"
+    text: str = ""
+    level: int = 0              # heading level, or list nesting depth
+    marker: str = ""            # list item marker as drawn, "-" or e.g. "3."
+    rows: List[List[str]] = field(default_factory=list)     # table cells, header row first
+    alignments: List[str] = field(default_factory=list)
+
+
+def _cells(line: str) -> List[str]:
+    line = line.strip()
+    if line.startswith("|"):
+        line = line[1:]
+    if line.endswith("|") and not line.endswith("\\|"):
+        line = line[:-1]
+    return [cell.strip() for cell in re.split(r"(?<!\\)\|", line)]
+
+
+def _alignment(separator: str) -> str:
+    if separator.startswith(":") and separator.endswith(":"):
+        return "center"
+    return "right" if separator.endswith(":") else "left"
+
+
+def _parse(text: str) -> List[_Block]:
+    blocks = []
+    lines = text.expandtabs(4).replace("\r", "").split("\n")
+    paragraph = None            # the paragraph or list item that following lines continue
+    hard_break = False          # the last line of it ended in two spaces or a backslash
+    i = 0
+    while i < len(lines):
+        line = lines[i]
+        i += 1
+        if not line.strip():
+            paragraph = None
+            continue
+        heading, item = _HEADING.match(line), _ITEM.match(line)
+        if heading:
+            blocks.append(_Block("heading", heading.group(2) or "", len(heading.group(1))))
+            paragraph = None
+        elif _RULE.match(line):
+            blocks.append(_Block("rule"))
+            paragraph = None
+        elif ("|" in line and i < len(lines) and _TABLE_SEPARATOR.match(lines[i])
+              and len(_cells(lines[i])) == len(_cells(line))):
+            header = _cells(line)
+            table = _Block("table", rows=[header], alignments=[_alignment(cell) for cell in _cells(lines[i])])
+            i += 1
+            while i < len(lines) and "|" in lines[i]:
+                row = _cells(lines[i])
+                table.rows.append((row + [""] * len(header))[:len(header)])
+                i += 1
+            blocks.append(table)
+            paragraph = None
+        elif item:
+            marker = item.group(2) if item.group(2)[0].isdigit() else "-"
+            paragraph = _Block("item", (item.group(3) or "").strip(), len(item.group(1)) // 2, marker)
+            blocks.append(paragraph)
+        elif paragraph is not None:
+            if hard_break:
+                paragraph.text = paragraph.text[:-1] if paragraph.text.endswith("\\") else paragraph.text
+            paragraph.text += ("\n" if hard_break else " ") + line.strip()
+        else:
+            paragraph = _Block("paragraph", line.strip())
+            blocks.append(paragraph)
+        hard_break = line.endswith("  ") or line.rstrip().endswith("\\")
+    return blocks
+
+
+def _inline(text: str) -> Tuple[str, List[bool]]:
+    """Text without its bold markers and backslash escapes, and which of its characters are bold."""
+    chars, bold = [], []
+    opener = None
+    i = 0
+    while i < len(text):
+        pair = text[i:i + 2]
+        if text[i] == "\\" and i + 1 < len(text) and text[i + 1] in _ESCAPABLE:
+            chars.append(text[i + 1])
+            bold.append(opener is not None)
+            i += 2
+        elif pair in ("**", "__") and (pair == opener or opener is None and pair in text[i + 2:]):
+            opener = None if opener else pair
+            i += 2
+        else:
+            chars.append(text[i])
+            bold.append(opener is not None)
+            i += 1
+    return "".join(chars), bold
+
+
+def _wrap(text: str, bold: List[bool], width: int, scale: int) -> List[Tuple[str, List[bool]]]:
+    """Lines of text as wrap_text() wraps it, each with the bold flags of its characters."""
+    # Whitespace is collapsed first, so that wrap_text() only turns spaces into
+    # line breaks, or breaks a long word, and every character maps back
+    plain: List[str] = []
+    flags: List[bool] = []
+    for char, strong in zip(text, bold):
+        if char != "\n" and char.isspace():
+            if not plain or plain[-1] in " \n":
+                continue
+            char = " "
+        elif char == "\n" and plain and plain[-1] == " ":
+            plain.pop()
+            flags.pop()
+        plain.append(char)
+        flags.append(strong)
+    if plain and plain[-1] == " ":
+        plain.pop()
+
+    lines = []
+    line, line_flags = "", []
+    i = 0
+    for char in wrap_text("".join(plain), width, scale):
+        if char == "\n":
+            lines.append((line, line_flags))
+            line, line_flags = "", []
+            if i < len(plain) and plain[i] in " \n":
+                i += 1
+            continue
+        line += char
+        line_flags.append(flags[i])
+        i += 1
+    lines.append((line, line_flags))
+    return lines
+
+
+def _text(text: str, x: int, y: int, width: int, scale: int = 1, bold: bool = False,
+          align: str = "left") -> Tuple[List[_Op], int]:
+    """Draw operations of inline Markdown wrapped to width, and the height they take."""
+    plain, flags = _inline(text)
+    lines = _wrap(plain, [strong or bold for strong in flags], width, scale)
+    ops = []
+    for row, (line, line_flags) in enumerate(lines):
+        line_width = max(len(line) * _CELL * scale - scale, 0)
+        left = x + {"left": 0, "center": (width - line_width) // 2, "right": width - line_width}[align]
+        start = 0
+        for strong, run in groupby(line_flags):
+            length = len(list(run))
+            ops.append(("text", left + start * _CELL * scale, y + row * FONT_HEIGHT * scale,
+                        line[start:start + length], strong, scale))
+            start += length
+    return ops, len(lines) * FONT_HEIGHT * scale
+
+
+def _column_widths(natural: List[int], available: int) -> List[int]:
+    """Natural column widths if they fit, else the widest columns narrowed to share what is left evenly."""
+    if sum(natural) <= available:
+        return list(natural)
+    widths = list(natural)
+    left = available
+    order = sorted(range(len(natural)), key=natural.__getitem__)
+    for n, i in enumerate(order):
+        widths[i] = max(min(natural[i], left // (len(order) - n)), FONT_WIDTH)   # at least one character
+        left -= widths[i]
+    return widths
+
+
+def _table(block: _Block, y: int, width: int) -> Tuple[List[_Op], int]:
+    cells = [[_inline(cell)[0] for cell in row] for row in block.rows]
+    natural = [max(max(len(row[column]) for row in cells) * _CELL - 1, FONT_WIDTH)
+               for column in range(len(block.alignments))]
+    widths = _column_widths(natural, width - TABLE_GAP * (len(natural) - 1))
+    ops = []
+    top = y
+    for n, row in enumerate(block.rows):
+        x, height = 0, FONT_HEIGHT
+        for cell, column_width, align in zip(row, widths, block.alignments):
+            cell_ops, cell_height = _text(cell, x, y, column_width, bold=n == 0, align=align)
+            ops += cell_ops
+            height = max(height, cell_height)
+            x += column_width + TABLE_GAP
+        y += height
+        if n == 0:
+            ops.append(("rule", 0, y + 1, min(x - TABLE_GAP, width)))
+            y += 3
+        elif n < len(block.rows) - 1:
+            y += ITEM_GAP
+    return ops, y - top
+
+
+def _layout(blocks: List[_Block], width: int) -> Tuple[List[_Op], int]:
+    """Draw operations of blocks stacked from the top, and the height they take."""
+    ops: List[_Op] = []
+    y = 0
+    previous = None
+    for block in blocks:
+        if previous is not None:
+            y += ITEM_GAP if previous.kind == block.kind == "item" else BLOCK_GAP
+        if block.kind == "heading":
+            block_ops, height = _text(block.text, 0, y, width, 2 if block.level == 1 else 1, bold=True)
+            if block.level == 2:
+                block_ops.append(("rule", 0, y + height + 1, width))
+                height += 2
+        elif block.kind == "rule":
+            block_ops, height = [("rule", 0, y, width)], 1
+        elif block.kind == "table":
+            block_ops, height = _table(block, y, width)
+        elif block.kind == "item":
+            x = min(block.level * LIST_INDENT, max(width - 2 * _CELL, 0))
+            text_x = x + (len(block.marker) + 1) * _CELL
+            block_ops, height = _text(block.text, text_x, y, max(width - text_x, FONT_WIDTH))
+            block_ops.append(("text", x, y, block.marker, False, 1))
+            height = max(height, FONT_HEIGHT)
+        else:
+            block_ops, height = _text(block.text, 0, y, width)
+        ops += block_ops
+        y += height
+        previous = block
+    return ops, y
+
+
+def markdown_height(text: str, width: int = PANEL_WIDTH) -> int:
+    """Height in pixels render_markdown() needs to draw text at width without clipping."""
+    return _layout(_parse(text), width)[1]
+
+
+def render_markdown(text: str, width: int = PANEL_WIDTH, height: Optional[int] = None) -> Framebuffer:
+    """
+    Draw Markdown text, word-wrapped to the frame width.
+
+    Args:
+        text: Markdown; see the module documentation for what is supported
+        width: Frame width, e.g. Display.get_dimensions()[0]
+        height: Frame height, clipping what does not fit; None for the
+                height of the text (see markdown_height())
+
+    Returns:
+        The frame, ready for Display.display_image() if it is the panel's size
+    """
+    ops, content_height = _layout(_parse(text), width)
+    fb = Framebuffer(max(width, 1), max(content_height if height is None else height, 1))
+    for op in ops:
+        if op[0] == "rule":
+            fb.draw_hline(op[1], op[2], op[3])
+            continue
+        _, x, y, run, bold, scale = op
+        fb.draw_text(x, y, run, scale=scale)
+        if bold:
+            fb.draw_text(x + 1, y, run, scale=scale)
+    return fb
@@ -0,0 +1,247 @@
+#!/usr/bin/env python3
+"""
+Mirroring a Linux framebuffer or text console on the e-ink display.
+
+A headless CM5 has no monitor to show its console. These sources capture
+what a monitor would show, and a TileBoard keeps the panel up to date, so
+it becomes a low-rate console monitor for debugging boot, login or a
+kiosk app:
+
+    board = mirror_board(display, ConsoleSource())
+    board.run()
+
+- ConsoleSource: a text console (tty) read from /dev/vcsaN and drawn in the
+  built-in font, one character cell per cell. A console larger than the
+  panel is shown from the window that follows the cursor.
+- FramebufferSource: the pixels of a framebuffer device such as /dev/fb0,
+  scaled and dithered to the frame.
+
+A capture identical to the last one, or that looks the same once scaled
+and dithered, changes nothing; otherwise display_image_auto() compares the
+frame with the panel and refreshes partially when few pixels changed.
+From the command line:
+
+    distiller-eink mirror --console 1 --partial
+    distiller-eink mirror --fb /dev/fb0 --interval 5
+"""
+
+import hashlib
+import os
+from typing import Optional, Tuple
+
+from .font import FONT_HEIGHT, FONT_WIDTH
+from .graphics import Color, Framebuffer
+from .imaging import ImageOptions, default_image_options, process_grayscale
+from .tiles import ImageSource, TileBoard, TileError
+
+
+FRAMEBUFFER_DEVICE = "/dev/fb0"
+SYSFS_GRAPHICS = "/sys/class/graphics"
+
+# Framebuffers are sampled down to at most this many times the frame size
+# before they are scaled, which keeps a 1080p capture quick without Pillow
+SAMPLE_FACTOR = 2
+
+_CELL = FONT_WIDTH + 1
+
+
+def console_device(console: int = 0) -> str:
+    """The vcsa device of a virtual console; 0 for the one in the foreground."""
+    return f"/dev/vcsa{console}" if console else "/dev/vcsa"
+
+
+def _brightness(color: int) -> int:
+    """Luma of a 4-bit VGA text color: bit 0 blue, 1 green, 2 red, 3 bright."""
+    luma = 114 * (color & 1) + 587 * (color >> 1 & 1) + 299 * (color >> 2 & 1)
+    return 170 * luma + (85 * 1000 if color & 8 else 0)
+
+
+def render_console(dump: bytes, width: int, height: int, cursor: bool = True) -> Framebuffer:
+    """
+    Draw a console screen dump as read from /dev/vcsaN.
+
+    Cells whose background is brighter than their text, e.g. reverse video,
+    are drawn white on black. Characters outside printable ASCII are drawn
+    as '?'.
+
+    Args:
+        dump: Header of lines, columns and cursor position, then a character
+              and attribute byte per cell
+        width: Frame width
+        height: Frame height
+        cursor: Draw the cursor cell inverted
+
+    Raises:
+        TileError: If the dump is truncated
+    """
+    if len(dump) < 4:
+        raise TileError("Truncated console dump")
+    lines, columns, cursor_x, cursor_y = dump[:4]
+    cells = dump[4:4 + 2 * lines * columns]
+    if len(cells) < 2 * lines * columns:
+        raise TileError(f"Truncated console dump: {len(cells)} bytes for {columns}x{lines} cells")
+
+    rows, cols = max(height // FONT_HEIGHT, 1), max((width + 1) // _CELL, 1)
+    # The window that follows the cursor, where new output appears
+    top = max(min(cursor_y + 1, lines) - rows, 0)
+    left = max(cursor_x - cols + 1, 0)
+    fb = Framebuffer(width, height)
+    for row in range(min(rows, lines - top)):
+        for col in range(min(cols, columns - left)):
+            i = 2 * ((top + row) * columns + left + col)
+            char, attribute = cells[i], cells[i + 1]
+            inverse = _brightness(attribute >> 4) > _brightness(attribute & 0x0F)
+            if cursor and (left + col, top + row) == (cursor_x, cursor_y):
+                inverse = not inverse
+            x, y = col * _CELL, row * FONT_HEIGHT
+            if inverse:
+                fb.draw_rect(x, y, _CELL, FONT_HEIGHT, fill=True)
+            if char not in (0, 32):
+                fb.draw_text(x, y, chr(char) if 32 < char < 127 else "?", Color.WHITE if inverse else Color.BLACK)
+    return fb
+
+
+class ConsoleSource(ImageSource):
+    """A text console, drawn in the built-in font from its screen dump."""
+
+    def __init__(self, console: int = 0, poll_interval_s: float = 1.0, cursor: bool = True,
+                 device: Optional[str] = None):
+        """
+        Args:
+            console: Virtual console number, as in /dev/ttyN; 0 for the one in the foreground
+            cursor: Draw the cursor cell inverted
+            device: Screen dump device to read instead, e.g. a copy for testing
+        """
+        self.device = device or console_device(console)
+        self.poll_interval_s = poll_interval_s
+        self.cursor = cursor
+        self._dump: Optional[bytes] = None
+
+    def fetch(self, width: int, height: int) -> Optional[Framebuffer]:
+        try:
+            with open(self.device, "rb") as f:
+                dump = f.read()
+        except OSError as e:
+            raise TileError(f"Cannot read console {self.device}: {e}")
+        if dump == self._dump:
+            return None
+        frame = render_console(dump, width, height, self.cursor)
+        self._dump = dump
+        return frame
+
+
+def framebuffer_geometry(device: str = FRAMEBUFFER_DEVICE,
+                         sysfs: str = SYSFS_GRAPHICS) -> Tuple[int, int, int, int]:
+    """
+    Size and pixel format of a framebuffer device, from sysfs.
+
+    Returns:
+        (width, height, bits_per_pixel, stride), stride in bytes per row
+
+    Raises:
+        TileError: If the geometry cannot be read
+    """
+    path = os.path.join(sysfs, os.path.basename(device))
+    try:
+        with open(os.path.join(path, "virtual_size"), "r") as f:
+            width, height = (int(value) for value in f.read().strip().split(","))
+        with open(os.path.join(path, "bits_per_pixel"), "r") as f:
+            bits_per_pixel = int(f.read().strip())
+        try:
+            with open(os.path.join(path, "stride"), "r") as f:
+                stride = int(f.read().strip())
+        except OSError:
+            stride = width * bits_per_pixel // 8
+    except (OSError, ValueError) as e:
+        raise TileError(f"Cannot read the geometry of {device} in {path}: {e}")
+    return width, height, bits_per_pixel, stride
+
+
+def framebuffer_grayscale(data: bytes, width: int, height: int, bits_per_pixel: int, stride: int,
+                          step: int = 1) -> Tuple[int, int, bytes]:
+    """
+    Framebuffer pixels as 8-bit grayscale, taking every step-th pixel of every step-th row.
+
+    Supports 32-bit XRGB8888, 24-bit RGB888 and 16-bit RGB565, little-endian
+    as on the CM5, and 8-bit pixels taken as gray levels.
+
+    Returns:
+        (width, height, pixels) of the sampled image
+
+    Raises:
+        TileError: If the pixel format is not supported or data is too short
+    """
+    if bits_per_pixel not in (8, 16, 24, 32):
+        raise TileError(f"Unsupported framebuffer format: {bits_per_pixel} bits per pixel")
+    size = bits_per_pixel // 8
+    if len(data) < stride * (height - 1) + width * size:
+        raise TileError(f"Framebuffer capture of {len(data)} bytes is too short for {width}x{height}")
+    gray = bytearray()
+    for y in range(0, height, step):
+        row = data[y * stride:y * stride + width * size]
+        if size == 1:
+            gray += row[::step]
+        elif size == 2:
+            for low, high in zip(row[0::2 * step], row[1::2 * step]):
+                value = low | high << 8
+                red = (value >> 11) * 255 // 31
+                green = (value >> 5 & 0x3F) * 255 // 63
+                blue = (value & 0x1F) * 255 // 31
+                gray.append((red * 299 + green * 587 + blue * 114) // 1000)
+        else:
+            gray += bytes((red * 299 + green * 587 + blue * 114) // 1000 for blue, green, red
+                          in zip(row[0::size * step], row[1::size * step], row[2::size * step]))
+    return (width + step - 1) // step, (height + step - 1) // step, bytes(gray)
+
+
+class FramebufferSource(ImageSource):
+    """The pixels of a framebuffer device, scaled and dithered to the tile."""
+
+    def __init__(self, device: str = FRAMEBUFFER_DEVICE, poll_interval_s: float = 2.0,
+                 options: Optional[ImageOptions] = None, sysfs: str = SYSFS_GRAPHICS):
+        """
+        Args:
+            device: Framebuffer device, e.g. /dev/fb0
+            options: How the capture is scaled and dithered; None for the device's processing profile
+            sysfs: Where the device's geometry is read, replaceable for testing
+        """
+        self.device = device
+        self.poll_interval_s = poll_interval_s
+        self.options = options
+        self.sysfs = sysfs
+        self._digest: Optional[bytes] = None
+        self._frame: Optional[bytes] = None
+
+    def fetch(self, width: int, height: int) -> Optional[Framebuffer]:
+        src_width, src_height, bits_per_pixel, stride = framebuffer_geometry(self.device, self.sysfs)
+        try:
+            with open(self.device, "rb") as f:
+                data = f.read(stride * src_height)
+        except OSError as e:
+            raise TileError(f"Cannot read framebuffer {self.device}: {e}")
+        digest = hashlib.sha256(data).digest()
+        if digest == self._digest:
+            return None
+        step = max(min(src_width // (SAMPLE_FACTOR * width), src_height // (SAMPLE_FACTOR * height)), 1)
+        sampled = framebuffer_grayscale(data, src_width, src_height, bits_per_pixel, stride, step)
+        frame = process_grayscale(sampled[2], sampled[0], sampled[1], self.options or default_image_options(),
+                                  width, height)
+        self._digest = digest
+        # A change too small to survive scaling, e.g. a blinking cursor, is not a change
+        if frame.to_bytes() == self._frame:
+            return None
+        self._frame = frame.to_bytes()
+        return frame
+
+
+def mirror_board(display, source: ImageSource) -> TileBoard:
+    """
+    A TileBoard showing one source on the whole screen; run() it to keep mirroring.
+
+    Args:
+        display: Initialized Display
+        source: ConsoleSource, FramebufferSource, or any ImageSource
+    """
+    board = TileBoard(display)
+    board.add(source, 0, 0, *display.get_dimensions())
+    return board
@@ -0,0 +1,440 @@
+#!/usr/bin/env python3
+"""
+E-ink panel definitions for CM5 SDK.
+
+Each supported panel is described by a PanelSpec: its native geometry, the
+controller commands that initialize it and the update sequences for full and
+partial refreshes. The pure-Python, mock and simulator backends drive any
+panel listed here; the compiled C library drives EPD128x250 only.
+
+Panels can also be identified at init from the 10-byte User ID that module
+makers program into the controller's OTP (read with command 0x2E). IDs are
+matched by prefix against PANEL_IDS and the panel_ids setting in eink.toml.
+
+Modules not listed here can be described in a TOML or JSON file and selected
+with panel = custom:/path/to/panel.toml (see load_panel_descriptor()).
+"""
+
+import json
+from dataclasses import dataclass
+from enum import Enum
+from typing import Any, Dict, List, Optional, Tuple
+
+from .display import DisplayError
+
+
+Command = Tuple[int, bytes]
+
+
+class PanelType(Enum):
+    """Supported panels, named by native resolution."""
+    EPD128x250 = "epd128x250"   # 2.13" (SSD1680), the default
+    EPD296x128 = "epd296x128"   # 2.9" (SSD1680)
+    EPD400x300 = "epd400x300"   # 4.2" (SSD1683)
+    CUSTOM = "custom"           # loaded from a descriptor file
+
+
+@dataclass(frozen=True)
+class PanelSpec:
+    """
+    Geometry and command sequences of a panel.
+
+    width and height are the native frame size as scanned by the controller:
+    width is the source (X) direction and must be a multiple of 8.
+    """
+    panel_type: PanelType
+    description: str
+    width: int
+    height: int
+    init_commands: Tuple[Command, ...]      # sent after reset and SWRESET
+    partial_setup: Tuple[Command, ...]      # sent before a partial refresh's RAM write
+    full_update: int                        # Display Update Control 2 (0x22) value
+    partial_update: int
+    full_refresh_s: float                   # typical refresh times
+    partial_refresh_s: float
+    lut: bytes = b""                        # waveform LUT written with 0x32 after init; empty keeps OTP
+    busy_pin: Optional[int] = None          # GPIO overrides for modules wired differently
+    reset_pin: Optional[int] = None
+
+    @property
+    def array_size(self) -> int:
+        """Bytes in a packed 1-bit frame."""
+        return self.width * self.height // 8
+
+
+def _window(width: int, height: int) -> Tuple[Command, ...]:
+    """RAM window and cursor for data entry mode 0x03 (X and Y incrementing)."""
+    y_end = height - 1
+    return (
+        (0x11, bytes([0x03])),                                        # data entry mode
+        (0x44, bytes([0x00, width // 8 - 1])),                        # RAM X start/end
+        (0x45, bytes([0x00, 0x00, y_end % 256, y_end // 256])),       # RAM Y start/end
+        (0x4E, bytes([0x00])),                                        # RAM X counter
+        (0x4F, bytes([0x00, 0x00])),                                  # RAM Y counter
+    )
+
+
+def _epd128x250() -> PanelSpec:
+    # Data entry mode 0x01 (Y decrementing), matching distiller_display_sdk.c
+    width, height = 128, 250
+    y_end = height - 1
+    return PanelSpec(
+        panel_type=PanelType.EPD128x250,
+        description='2.13" 128x250',
+        width=width,
+        height=height,
+        init_commands=(
+            (0x01, bytes([y_end % 256, y_end // 256, 0x00])),             # driver output control
+            (0x11, bytes([0x01])),                                        # data entry mode
+            (0x44, bytes([0x00, width // 8 - 1])),                        # RAM X start/end
+            (0x45, bytes([y_end % 256, y_end // 256, 0x00, 0x00])),       # RAM Y start/end
+            (0x3C, bytes([0x05])),                                        # border waveform
+            (0x21, bytes([0x00, 0x80])),                                  # display update control
+            (0x18, bytes([0x80])),                                        # internal temperature sensor
+            (0x4E, bytes([0x00])),                                        # RAM X counter
+            (0x4F, bytes([y_end % 256, y_end // 256])),                   # RAM Y counter
+        ),
+        partial_setup=((0x3C, bytes([0x80])),),
+        full_update=0xF7,
+        partial_update=0xFF,
+        full_refresh_s=1.5,
+        partial_refresh_s=0.3,
+    )
+
+
+def _epd296x128() -> PanelSpec:
+    width, height = 128, 296
+    y_end = height - 1
+    return PanelSpec(
+        panel_type=PanelType.EPD296x128,
+        description='2.9" 296x128',
+        width=width,
+        height=height,
+        init_commands=(
+            (0x01, bytes([y_end % 256, y_end // 256, 0x00])),
+            (0x21, bytes([0x00, 0x80])),
+            (0x3C, bytes([0x05])),
+            (0x18, bytes([0x80])),
+        ) + _window(width, height),
+        partial_setup=((0x3C, bytes([0x80])),),
+        full_update=0xF7,
+        partial_update=0xFF,
+        full_refresh_s=2.0,
+        partial_refresh_s=0.4,
+    )
+
+
+def _epd400x300() -> PanelSpec:
+    width, height = 400, 300
+    return PanelSpec(
+        panel_type=PanelType.EPD400x300,
+        description='4.2" 400x300',
+        width=width,
+        height=height,
+        init_commands=(
+            (0x21, bytes([0x40, 0x00])),    # bypass red RAM
+            (0x3C, bytes([0x05])),
+            (0x18, bytes([0x80])),
+        ) + _window(width, height),
+        partial_setup=((0x3C, bytes([0x80])),),
+        full_update=0xF7,
+        partial_update=0xFF,
+        full_refresh_s=3.5,
+        partial_refresh_s=0.6,
+    )
+
+
+PANELS: Dict[PanelType, PanelSpec] = {
+    spec.panel_type: spec for spec in (_epd128x250(), _epd296x128(), _epd400x300())
+}
+
+DEFAULT_PANEL = PanelType.EPD128x250
+
+# Prefix of a panel setting naming a descriptor file
+CUSTOM_PREFIX = "custom:"
+
+
+def get_panel(panel_type: PanelType = DEFAULT_PANEL) -> PanelSpec:
+    """
+    Get the spec of a built-in panel type.
+
+    Raises:
+        DisplayError: For PanelType.CUSTOM, whose spec comes from load_panel_descriptor()
+    """
+    if panel_type not in PANELS:
+        raise DisplayError(f"Panel '{panel_type.value}' has no built-in definition")
+    return PANELS[panel_type]
+
+
+def parse_panel(value: str) -> PanelType:
+    """
+    Parse a panel setting such as "epd296x128" (case-insensitive).
+
+    Raises:
+        DisplayError: If the value is not a supported panel
+    """
+    try:
+        panel_type = PanelType(value.strip().lower())
+    except ValueError:
+        panel_type = None
+    if panel_type is None or panel_type == PanelType.CUSTOM:
+        names = ", ".join(p.value for p in PANELS)
+        raise DisplayError(f"Unknown panel '{value}'; expected one of {names} or {CUSTOM_PREFIX}<path>")
+    return panel_type
+
+
+# Command writing the waveform LUT
+CMD_WRITE_LUT = 0x32
+
+# Display Update Control 2 (0x22) values driving a refresh with the waveform
+# written by Display.set_lut() instead of loading the OTP one
+LUT_FULL_UPDATE = 0xC7
+LUT_PARTIAL_UPDATE = 0xCF
+
+# Temperature register, 12-bit two's complement in 1/16 °C: written with an
+# external reading (see Display.set_temperature()) and read back after a
+# refresh loaded the panel's own sensor
+CMD_WRITE_TEMPERATURE = 0x1A
+CMD_READ_TEMPERATURE = 0x1B
+
+# Display Update Control 2 bit loading the temperature from the sensor;
+# cleared to use the register's value instead
+UPDATE_LOAD_TEMPERATURE = 0x20
+
+
+def encode_temperature(millicelsius: int) -> bytes:
+    """Temperature register bytes for a reading in millidegrees Celsius."""
+    sixteenths = int(millicelsius * 16 / 1000) & 0xFFF
+    return bytes([sixteenths >> 4, (sixteenths & 0x0F) << 4])
+
+
+def decode_temperature(data: bytes) -> int:
+    """Millidegrees Celsius from temperature register bytes."""
+    sixteenths = (data[0] << 4) | (data[1] >> 4)
+    if sixteenths & 0x800:
+        sixteenths -= 0x1000
+    return int(sixteenths * 1000 / 16)
+
+
+# Commands setting the voltages that follow a LUT_SIZE_VOLTAGES waveform:
+# end option, gate, source (3 bytes) and VCOM
+LUT_VOLTAGE_COMMANDS: Tuple[Tuple[int, int], ...] = ((0x3F, 1), (0x03, 1), (0x04, 3), (0x2C, 1))
+
+# LutPreset.FAST_REFRESH, as in distiller_display_sdk.c: one short drive phase
+# per transition, from the panel vendor's partial refresh waveform
+FAST_REFRESH_LUT = bytes(
+    [0x00, 0x40] + [0x00] * 10 +            # VS L0 (black to black)
+    [0x80, 0x80] + [0x00] * 10 +            # VS L1 (black to white)
+    [0x40, 0x40] + [0x00] * 10 +            # VS L2 (white to black)
+    [0x00, 0x80] + [0x00] * 10 +            # VS L3 (white to white)
+    [0x00] * 12 +                           # VS L4 (VCOM)
+    [0x14] + [0x00] * 6 +                   # group timing and repeat
+    ([0x01] + [0x00] * 6) * 2 +
+    [0x00] * 7 * 9 +
+    [0x22] * 6 + [0x00] * 3 +               # frame rate, gate scan
+    [0x22, 0x17, 0x41, 0x00, 0x32, 0x36]    # EOPT, VGH, VSH1, VSH2, VSL, VCOM
+)
+
+
+def _load_descriptor_file(path: str) -> Dict[str, Any]:
+    try:
+        with open(path, "rb") as f:
+            content = f.read()
+    except OSError as e:
+        raise DisplayError(f"Failed to read panel descriptor {path}: {e}")
+
+    if path.lower().endswith(".json"):
+        try:
+            return json.loads(content)
+        except ValueError as e:
+            raise DisplayError(f"{path}: invalid JSON: {e}")
+
+    try:
+        import tomllib
+    except ImportError:  # Python < 3.11
+        try:
+            import tomli as tomllib
+        except ImportError:
+            raise DisplayError(f"{path}: reading TOML needs Python 3.11+ or the tomli module; "
+                               "use a .json descriptor instead")
+    try:
+        return tomllib.loads(content.decode("utf-8"))
+    except (ValueError, UnicodeDecodeError) as e:
+        raise DisplayError(f"{path}: invalid TOML: {e}")
+
+
+def _parse_byte(value: Any, where: str) -> int:
+    # Bytes may be given as integers or, since JSON has no hex literals, as "0x3C" strings
+    try:
+        number = int(value, 16) if isinstance(value, str) else value
+    except ValueError:
+        number = None
+    if not isinstance(number, int) or isinstance(number, bool) or not 0 <= number <= 0xFF:
+        raise DisplayError(f"{where}: expected a byte, got {value!r}")
+    return number
+
+
+def _parse_bytes(value: Any, where: str) -> bytes:
+    # A list of bytes, or a string of hex digits ("01 27 01 00")
+    if isinstance(value, str):
+        try:
+            return bytes.fromhex(value.replace("0x", ""))
+        except ValueError:
+            raise DisplayError(f"{where}: invalid hex string")
+    if not isinstance(value, list):
+        raise DisplayError(f"{where}: expected a list of bytes or a hex string")
+    return bytes(_parse_byte(b, where) for b in value)
+
+
+def _parse_commands(value: Any, where: str) -> Tuple[Command, ...]:
+    # Each command is its command byte followed by its data bytes
+    if not isinstance(value, list):
+        raise DisplayError(f"{where}: expected a list of commands")
+    commands: List[Command] = []
+    for index, entry in enumerate(value):
+        raw = _parse_bytes(entry, f"{where}[{index}]")
+        if not raw:
+            raise DisplayError(f"{where}[{index}]: empty command")
+        commands.append((raw[0], raw[1:]))
+    return tuple(commands)
+
+
+def _parse_number(data: Dict[str, Any], key: str, default: Any, path: str, kind: type = int) -> Any:
+    value = data.get(key, default)
+    if isinstance(value, bool) or not isinstance(value, (int, float) if kind is float else int):
+        raise DisplayError(f"{path}: {key} must be a{'' if kind is float else 'n integer'} number")
+    return kind(value)
+
+
+def load_panel_descriptor(path: str) -> PanelSpec:
+    """
+    Load a panel definition from a TOML (or, for *.json files, JSON) descriptor.
+
+    Example:
+
+        description = "1.54in 200x200"
+        width = 200                 # source (X) direction, multiple of 8
+        height = 200
+        init = [                    # sent after reset and SWRESET
+            [0x01, 0xC7, 0x00, 0x00],
+            "11 03",
+            "44 00 18",
+            "45 00 00 C7 00",
+        ]
+        partial_setup = ["3C 80"]
+        full_update = 0xF7          # optional; these are the defaults
+        partial_update = 0xFF
+        full_refresh_s = 2.0
+        partial_refresh_s = 0.5
+        lut = "80 48 40 ..."        # optional waveform LUT
+
+        [pins]                      # optional GPIO overrides
+        busy = 9
+        reset = 13
+
+    Commands and LUT are lists of bytes or strings of hex digits; bytes may be
+    written as "0x3C" strings in JSON.
+
+    Raises:
+        DisplayError: If the file cannot be read or the definition is invalid
+    """
+    data = _load_descriptor_file(path)
+    if not isinstance(data, dict):
+        raise DisplayError(f"{path}: expected a table of panel settings")
+    for key in ("width", "height", "init"):
+        if key not in data:
+            raise DisplayError(f"{path}: missing required setting '{key}'")
+
+    width = _parse_number(data, "width", None, path)
+    height = _parse_number(data, "height", None, path)
+    if width <= 0 or width % 8 or height <= 0:
+        raise DisplayError(f"{path}: width must be a positive multiple of 8 and height positive")
+
+    pins = data.get("pins", {})
+    if not isinstance(pins, dict):
+        raise DisplayError(f"{path}: pins must be a table")
+    for name in pins:
+        if name not in ("busy", "reset"):
+            raise DisplayError(f"{path}: unknown pin '{name}'; expected busy or reset")
+        if isinstance(pins[name], bool) or not isinstance(pins[name], int) or pins[name] < 0:
+            raise DisplayError(f"{path}: pin '{name}' must be a GPIO line number")
+
+    return PanelSpec(
+        panel_type=PanelType.CUSTOM,
+        description=str(data.get("description", f"custom {width}x{height}")),
+        width=width,
+        height=height,
+        init_commands=_parse_commands(data["init"], f"{path}: init"),
+        partial_setup=_parse_commands(data.get("partial_setup", []), f"{path}: partial_setup"),
+        full_update=_parse_byte(data.get("full_update", 0xF7), f"{path}: full_update"),
+        partial_update=_parse_byte(data.get("partial_update", 0xFF), f"{path}: partial_update"),
+        full_refresh_s=_parse_number(data, "full_refresh_s", 2.0, path, float),
+        partial_refresh_s=_parse_number(data, "partial_refresh_s", 0.5, path, float),
+        lut=_parse_bytes(data.get("lut", []), f"{path}: lut"),
+        busy_pin=pins.get("busy"),
+        reset_pin=pins.get("reset"),
+    )
+
+
+# Command reading the controller's 10-byte OTP User ID
+CMD_READ_USER_ID = 0x2E
+USER_ID_LENGTH = 10
+
+# Known User ID prefixes; extend with register_panel_id() or panel_ids in eink.toml
+PANEL_IDS: Dict[bytes, PanelType] = {}
+
+
+def register_panel_id(user_id_prefix: bytes, panel_type: PanelType) -> None:
+    """Identify modules whose User ID starts with user_id_prefix as panel_type."""
+    if not user_id_prefix:
+        raise DisplayError("Panel ID prefix must not be empty")
+    PANEL_IDS[bytes(user_id_prefix)] = panel_type
+
+
+def identify_panel(user_id: Optional[bytes],
+                   extra_ids: Optional[Dict[bytes, PanelType]] = None) -> Optional[PanelType]:
+    """
+    Match a User ID read from the controller against the known prefixes.
+
+    Args:
+        user_id: Bytes read with CMD_READ_USER_ID, or None if reading failed
+        extra_ids: Additional prefixes (e.g. from eink.toml), checked first
+
+    Returns:
+        The panel type, or None if the ID is blank or unknown. The longest
+        matching prefix wins.
+    """
+    if not user_id or all(b == 0x00 for b in user_id) or all(b == 0xFF for b in user_id):
+        return None  # unprogrammed OTP, or no readback wiring
+    candidates = dict(PANEL_IDS)
+    candidates.update(extra_ids or {})
+    matches = [prefix for prefix in candidates if user_id.startswith(prefix)]
+    if not matches:
+        return None
+    return candidates[max(matches, key=len)]
+
+
+def parse_panel_ids(value: str) -> Dict[bytes, PanelType]:
+    """
+    Parse a panel_ids setting: comma-separated <hex prefix>:<panel> pairs,
+    e.g. "a1b2:epd296x128, a1c0:epd400x300".
+
+    Raises:
+        DisplayError: If an entry is malformed
+    """
+    ids = {}
+    for entry in value.split(","):
+        entry = entry.strip()
+        if not entry:
+            continue
+        prefix, sep, panel = entry.partition(":")
+        try:
+            if not sep:
+                raise ValueError("expected <hex prefix>:<panel>")
+            key = bytes.fromhex(prefix.strip().lower().replace("0x", ""))
+            if not key:
+                raise ValueError("empty prefix")
+        except ValueError as e:
+            raise DisplayError(f"Invalid panel_ids entry '{entry}': {e}")
+        ids[key] = parse_panel(panel)
+    return ids
@@ -0,0 +1,183 @@
+#!/usr/bin/env python3
+"""
+Display test patterns for CM5 SDK.
+
+Generates standard frames for checking a panel: alignment, contrast, ghosting,
+resolution and dead pixels. Every pattern is a Framebuffer sized for a panel,
+so it can be shown with Display.display_image(pattern.to_bytes()).
+
+    from distiller_cm5_sdk.hardware.eink.patterns import PatternSpec, generate
+    fb = generate(PatternSpec("checkerboard", size=16), width=128, height=250)
+
+From the command line: python3 -m distiller_cm5_sdk.diagnostics eink-pattern checkerboard
+"""
+
+from dataclasses import dataclass
+from typing import Callable, Dict, Optional
+
+from .graphics import PANEL_HEIGHT, PANEL_WIDTH, Color, Framebuffer
+
+
+class PatternError(Exception):
+    """Custom exception for test pattern errors."""
+    pass
+
+
+# 4x4 Bayer matrix, thresholds 0-15
+_BAYER_4X4 = (
+    (0, 8, 2, 10),
+    (12, 4, 14, 6),
+    (3, 11, 1, 9),
+    (15, 7, 13, 5),
+)
+
+
+@dataclass(frozen=True)
+class PatternSpec:
+    """
+    A test pattern and its parameters.
+
+    size is the pattern's cell, stripe, line or block size in pixels (None
+    for the pattern's default); vertical selects vertical stripes and
+    top-to-bottom gradients; step selects the pixel-walk position; invert
+    swaps black and white.
+    """
+    name: str
+    size: Optional[int] = None
+    vertical: bool = False
+    step: int = 0
+    invert: bool = False
+
+
+def checkerboard(width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT, size: int = 8) -> Framebuffer:
+    """Squares of size pixels, black at the top-left corner."""
+    fb = Framebuffer(width, height)
+    for y in range(height):
+        for x in range(width):
+            if (x // size + y // size) % 2 == 0:
+                fb.set_pixel(x, y)
+    return fb
+
+
+def stripes(width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT, size: int = 8,
+            vertical: bool = False) -> Framebuffer:
+    """Alternating black and white stripes of size pixels, horizontal unless vertical."""
+    fb = Framebuffer(width, height)
+    if vertical:
+        for x in range(0, width, 2 * size):
+            fb.draw_rect(x, 0, size, height, fill=True)
+    else:
+        for y in range(0, height, 2 * size):
+            fb.draw_rect(0, y, width, size, fill=True)
+    return fb
+
+
+def gradient(width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT, vertical: bool = False) -> Framebuffer:
+    """White to black ramp, left to right (top to bottom if vertical), ordered-dithered."""
+    fb = Framebuffer(width, height)
+    length = height if vertical else width
+    for y in range(height):
+        for x in range(width):
+            position = y if vertical else x
+            level = position * 16 // length             # 0 (white) .. 15 (black)
+            if _BAYER_4X4[y % 4][x % 4] < level:
+                fb.set_pixel(x, y)
+    return fb
+
+
+def crosshatch(width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT, size: int = 16) -> Framebuffer:
+    """One-pixel grid lines every size pixels, including the last row and column."""
+    fb = Framebuffer(width, height)
+    for x in list(range(0, width, size)) + [width - 1]:
+        fb.draw_vline(x, 0, height)
+    for y in list(range(0, height, size)) + [height - 1]:
+        fb.draw_hline(0, y, width)
+    return fb
+
+
+def border(width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT, size: int = 1) -> Framebuffer:
+    """Frame of size pixels around the edge with corner diagonals, for alignment and cropping."""
+    fb = Framebuffer(width, height)
+    for inset in range(size):
+        fb.draw_rect(inset, inset, width - 2 * inset, height - 2 * inset)
+    fb.draw_line(0, 0, width - 1, height - 1)
+    fb.draw_line(width - 1, 0, 0, height - 1)
+    return fb
+
+
+def resolution_wedges(width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT) -> Framebuffer:
+    """
+    Bands of line pairs 1, 2, 3 and 4 pixels wide, each split into vertical and
+    horizontal lines, to show the finest detail the panel resolves.
+    """
+    fb = Framebuffer(width, height)
+    widths = (1, 2, 3, 4)
+    band = height // len(widths)
+    half = width // 2
+    for index, line in enumerate(widths):
+        top = index * band
+        bottom = height if index == len(widths) - 1 else top + band
+        for x in range(0, half, 2 * line):
+            fb.draw_rect(x, top, min(line, half - x), bottom - top, fill=True)
+        for y in range(top, bottom, 2 * line):
+            fb.draw_rect(half, y, width - half, min(line, bottom - y), fill=True)
+    return fb
+
+
+def pixel_walk(width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT, step: int = 0, size: int = 1) -> Framebuffer:
+    """
+    A single black block of size pixels at position step, counting row by row.
+
+    Showing steps 0, 1, 2, ... with partial refreshes walks the block over the
+    panel to find dead or stuck pixels. The position wraps around.
+    """
+    fb = Framebuffer(width, height)
+    columns = (width + size - 1) // size
+    rows = (height + size - 1) // size
+    step %= columns * rows
+    fb.draw_rect((step % columns) * size, (step // columns) * size, size, size, fill=True)
+    return fb
+
+
+def pixel_walk_steps(width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT, size: int = 1) -> int:
+    """Number of pixel_walk steps covering the whole panel once."""
+    return ((width + size - 1) // size) * ((height + size - 1) // size)
+
+
+PATTERNS: Dict[str, Callable[[int, int, PatternSpec], Framebuffer]] = {
+    "checkerboard": lambda w, h, spec: checkerboard(w, h, spec.size or 8),
+    "stripes": lambda w, h, spec: stripes(w, h, spec.size or 8, spec.vertical),
+    "gradient": lambda w, h, spec: gradient(w, h, spec.vertical),
+    "crosshatch": lambda w, h, spec: crosshatch(w, h, spec.size or 16),
+    "border": lambda w, h, spec: border(w, h, spec.size or 1),
+    "resolution": lambda w, h, spec: resolution_wedges(w, h),
+    "pixel-walk": lambda w, h, spec: pixel_walk(w, h, spec.step, spec.size or 1),
+    "black": lambda w, h, spec: Framebuffer(w, h, fill=Color.BLACK),
+    "white": lambda w, h, spec: Framebuffer(w, h),
+}
+
+
+def generate(spec: PatternSpec, width: int = PANEL_WIDTH, height: int = PANEL_HEIGHT,
+             panel=None) -> Framebuffer:
+    """
+    Generate a test pattern.
+
+    Args:
+        spec: Pattern name and parameters
+        width: Frame width in pixels
+        height: Frame height in pixels
+        panel: Optional PanelSpec whose size is used instead of width and height
+
+    Raises:
+        PatternError: If the pattern is unknown or its parameters are invalid
+    """
+    if spec.name not in PATTERNS:
+        raise PatternError(f"Unknown pattern '{spec.name}'; expected one of {', '.join(PATTERNS)}")
+    if panel is not None:
+        width, height = panel.width, panel.height
+    if width <= 0 or height <= 0 or (spec.size is not None and spec.size <= 0):
+        raise PatternError(f"Invalid pattern size {spec.size} for a {width}x{height} frame")
+    fb = PATTERNS[spec.name](width, height, spec)
+    if spec.invert:
+        fb.data[:] = bytes(b ^ 0xFF for b in fb.data)
+    return fb
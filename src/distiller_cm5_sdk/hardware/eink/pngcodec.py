@@ -0,0 +1,221 @@
+#!/usr/bin/env python3
+"""
+Pure-Python PNG decoding and encoding for CM5 SDK.
+Used where the compiled display library (and its bundled lodepng) is unavailable.
+"""
+
+import struct
+import zlib
+from typing import Tuple
+
+
+PNG_SIGNATURE = b"\x89PNG\r\n\x1a\n"
+
+# Samples per pixel for each PNG color type
+_CHANNELS = {0: 1, 2: 3, 3: 1, 4: 2, 6: 4}
+
+# Larger images are refused before anything is decompressed
+MAX_DIMENSION = 16384
+MAX_PIXELS = 16 * 1024 * 1024
+
+
+class PNGError(Exception):
+    """Raised for malformed or unsupported PNG data."""
+    pass
+
+
+def _paeth(a: int, b: int, c: int) -> int:
+    p = a + b - c
+    pa = abs(p - a)
+    pb = abs(p - b)
+    pc = abs(p - c)
+    if pa <= pb and pa <= pc:
+        return a
+    if pb <= pc:
+        return b
+    return c
+
+
+def _unfilter(raw: bytes, width: int, height: int, bits_per_pixel: int) -> bytearray:
+    """Reverse PNG scanline filters, returning the packed scanlines without filter bytes."""
+    stride = (width * bits_per_pixel + 7) // 8
+    bpp = max(1, bits_per_pixel // 8)
+    out = bytearray(stride * height)
+    prev = bytearray(stride)
+    pos = 0
+
+    for y in range(height):
+        if pos + 1 + stride > len(raw):
+            raise PNGError("Truncated image data")
+        filter_type = raw[pos]
+        line = bytearray(raw[pos + 1:pos + 1 + stride])
+        pos += 1 + stride
+
+        if filter_type == 1:    # Sub
+            for i in range(bpp, stride):
+                line[i] = (line[i] + line[i - bpp]) & 0xFF
+        elif filter_type == 2:  # Up
+            for i in range(stride):
+                line[i] = (line[i] + prev[i]) & 0xFF
+        elif filter_type == 3:  # Average
+            for i in range(stride):
+                left = line[i - bpp] if i >= bpp else 0
+                line[i] = (line[i] + ((left + prev[i]) >> 1)) & 0xFF
+        elif filter_type == 4:  # Paeth
+            for i in range(stride):
+                left = line[i - bpp] if i >= bpp else 0
+                up_left = prev[i - bpp] if i >= bpp else 0
+                line[i] = (line[i] + _paeth(left, prev[i], up_left)) & 0xFF
+        elif filter_type != 0:
+            raise PNGError(f"Unknown filter type {filter_type}")
+
+        out[y * stride:(y + 1) * stride] = line
+        prev = line
+
+    return out
+
+
+def decode_png(data: bytes) -> Tuple[int, int, bytes]:
+    """
+    Decode a PNG image to 8-bit RGBA.
+
+    Supports all standard color types at bit depths up to 8 (16-bit samples
+    are truncated to their high byte). Interlaced images are not supported,
+    nor are images over MAX_DIMENSION pixels on a side or MAX_PIXELS in all.
+
+    Args:
+        data: Encoded PNG bytes
+
+    Returns:
+        Tuple of (width, height, rgba) where rgba holds 4 bytes per pixel
+
+    Raises:
+        PNGError: If the data is not a supported PNG
+    """
+    if not data.startswith(PNG_SIGNATURE):
+        raise PNGError("Missing PNG signature")
+
+    pos = len(PNG_SIGNATURE)
+    header = None
+    palette = b""
+    transparency = b""
+    idat = bytearray()
+
+    while pos + 8 <= len(data):
+        length, chunk_type = struct.unpack(">I4s", data[pos:pos + 8])
+        chunk = data[pos + 8:pos + 8 + length]
+        pos += 12 + length
+        if chunk_type == b"IHDR":
+            if len(chunk) != 13:
+                raise PNGError(f"IHDR chunk is {len(chunk)} bytes, not 13")
+            header = struct.unpack(">IIBBBBB", chunk)
+        elif chunk_type == b"PLTE":
+            palette = chunk
+        elif chunk_type == b"tRNS":
+            transparency = chunk
+        elif chunk_type == b"IDAT":
+            idat += chunk
+        elif chunk_type == b"IEND":
+            break
+
+    if header is None:
+        raise PNGError("Missing IHDR chunk")
+
+    width, height, depth, color_type, _, _, interlace = header
+    if not 0 < width <= MAX_DIMENSION or not 0 < height <= MAX_DIMENSION or width * height > MAX_PIXELS:
+        raise PNGError(f"Unsupported image size {width}x{height}")
+    if color_type not in _CHANNELS:
+        raise PNGError(f"Unsupported color type {color_type}")
+    if depth not in (1, 2, 4, 8, 16):
+        raise PNGError(f"Unsupported bit depth {depth}")
+    if interlace:
+        raise PNGError("Interlaced PNGs are not supported")
+    if color_type == 3 and not palette:
+        raise PNGError("Palette image without PLTE chunk")
+
+    channels = _CHANNELS[color_type]
+    bits_per_pixel = channels * depth
+    stride = (width * bits_per_pixel + 7) // 8
+
+    # Inflate no more than the scanlines need, so a small file cannot expand without bound
+    try:
+        raw = zlib.decompressobj().decompress(bytes(idat), height * (1 + stride))
+    except zlib.error as e:
+        raise PNGError(f"Corrupt image data: {e}")
+
+    scanlines = _unfilter(raw, width, height, bits_per_pixel)
+
+    rgba = bytearray(width * height * 4)
+    max_value = (1 << depth) - 1 if depth < 8 else 255
+
+    for y in range(height):
+        row = scanlines[y * stride:(y + 1) * stride]
+        for x in range(width):
+            if depth < 8:
+                bit = x * depth
+                sample = (row[bit // 8] >> (8 - depth - (bit % 8))) & ((1 << depth) - 1)
+                samples = (sample,)
+            else:
+                size = depth // 8
+                start = x * channels * size
+                samples = tuple(row[start + i * size] for i in range(channels))
+
+            if color_type == 3:
+                index = samples[0]
+                r, g, b = palette[index * 3:index * 3 + 3]
+                a = transparency[index] if index < len(transparency) else 255
+            elif color_type == 0:
+                v = samples[0] * 255 // max_value
+                r = g = b = v
+                a = 255
+            elif color_type == 4:
+                r = g = b = samples[0]
+                a = samples[1]
+            elif color_type == 2:
+                r, g, b = samples
+                a = 255
+            else:
+                r, g, b, a = samples
+
+            i = (y * width + x) * 4
+            rgba[i:i + 4] = bytes((r, g, b, a))
+
+    return width, height, bytes(rgba)
+
+
+def _chunk(kind: bytes, data: bytes) -> bytes:
+    return struct.pack(">I", len(data)) + kind + data + struct.pack(">I", zlib.crc32(kind + data))
+
+
+def encode_png_1bit(data: bytes, width: int, height: int) -> bytes:
+    """
+    Encode packed 1-bit display data as a 1-bit grayscale PNG.
+
+    Args:
+        data: Packed 1-bit data, MSB first with no row padding (1 = white)
+        width: Image width in pixels
+        height: Image height in pixels
+
+    Returns:
+        Encoded PNG bytes
+
+    Raises:
+        PNGError: If data is too small for the given dimensions
+    """
+    if len(data) < (width * height + 7) // 8:
+        raise PNGError(f"Data too small for {width}x{height} image")
+
+    stride = (width + 7) // 8
+    raw = bytearray()
+    for y in range(height):
+        row = bytearray(stride)
+        for x in range(width):
+            bit_idx = y * width + x
+            if (data[bit_idx // 8] >> (7 - (bit_idx % 8))) & 1:  # MSB first
+                row[x // 8] |= 1 << (7 - (x % 8))
+        raw.append(0)  # filter: none
+        raw.extend(row)
+
+    header = struct.pack(">IIBBBBB", width, height, 1, 0, 0, 0, 0)
+    return (PNG_SIGNATURE + _chunk(b"IHDR", header) +
+            _chunk(b"IDAT", zlib.compress(bytes(raw))) + _chunk(b"IEND", b""))
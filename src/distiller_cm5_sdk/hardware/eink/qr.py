@@ -0,0 +1,91 @@
+#!/usr/bin/env python3
+"""
+QR codes for the e-ink display.
+
+Encoding uses the qrcode package (the "qr" extra:
+pip install distiller-cm5-sdk[qr]); drawing is done here, with whole pixels
+per module so codes stay scannable on the panel.
+"""
+
+from typing import List
+
+from .graphics import Color, Framebuffer
+
+
+# Quiet zone around a code, in modules; the standard asks for 4, phones read 2
+QUIET_ZONE = 2
+
+ERROR_CORRECTION_LEVELS = ("L", "M", "Q", "H")
+
+
+class QRCodeError(Exception):
+    """Custom exception for QR code errors."""
+    pass
+
+
+def qr_matrix(text: str, error_correction: str = "M") -> List[List[bool]]:
+    """
+    Encode text as a QR code.
+
+    Args:
+        text: Text or URL to encode
+        error_correction: "L", "M", "Q" or "H"; higher levels survive more damage
+                          but need more modules
+
+    Returns:
+        Rows of modules, True where dark, without a quiet zone
+
+    Raises:
+        QRCodeError: If the qrcode package is missing or the text is too long
+    """
+    if error_correction not in ERROR_CORRECTION_LEVELS:
+        raise QRCodeError(f"Invalid error correction level '{error_correction}'; expected L, M, Q or H")
+    try:
+        import qrcode
+        import qrcode.constants
+    except ImportError:
+        raise QRCodeError("QR codes need the qrcode package: pip install distiller-cm5-sdk[qr]")
+    level = getattr(qrcode.constants, f"ERROR_CORRECT_{error_correction}")
+    code = qrcode.QRCode(error_correction=level, border=0)
+    try:
+        code.add_data(text)
+        code.make(fit=True)
+    except Exception as e:
+        raise QRCodeError(f"Cannot encode text as a QR code: {e}")
+    return [list(row) for row in code.get_matrix()]
+
+
+def draw_qr(fb: Framebuffer, text: str, x: int, y: int, size: int, error_correction: str = "M") -> int:
+    """
+    Draw a QR code, as large as fits, centered in a size x size square.
+
+    The square is cleared to white first, which provides the quiet zone.
+
+    Args:
+        fb: Framebuffer to draw into
+        text: Text or URL to encode
+        x: Left edge of the square
+        y: Top edge of the square
+        size: Side of the square in pixels
+        error_correction: "L", "M", "Q" or "H"
+
+    Returns:
+        Pixels per module
+
+    Raises:
+        QRCodeError: If the code cannot be encoded or does not fit at one pixel per module
+    """
+    matrix = qr_matrix(text, error_correction)
+    modules = len(matrix) + 2 * QUIET_ZONE
+    scale = size // modules
+    if scale < 1:
+        raise QRCodeError(f"A {len(matrix)}x{len(matrix)} QR code does not fit in {size} pixels; "
+                          f"shorten the text or lower the error correction")
+    fb.draw_rect(x, y, size, size, Color.WHITE, fill=True)
+    origin_x = x + (size - len(matrix) * scale) // 2
+    origin_y = y + (size - len(matrix) * scale) // 2
+    for row, modules_in_row in enumerate(matrix):
+        for col, dark in enumerate(modules_in_row):
+            if dark:
+                fb.draw_rect(origin_x + col * scale, origin_y + row * scale, scale, scale, Color.BLACK, fill=True)
+    return scale
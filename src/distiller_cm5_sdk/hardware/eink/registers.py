@@ -0,0 +1,125 @@
+#!/usr/bin/env python3
+"""
+Low-level controller register access for CM5 SDK.
+
+Lets panel vendors send arbitrary controller commands and data, e.g. to try
+waveform LUTs, voltages or timing settings, without forking the panel logic.
+Nothing checks what is sent: a wrong value can leave the panel showing
+garbage until the next init, and repeated abuse (such as overdriving voltages)
+can damage it. Access is therefore off unless enabled with
+
+    register_access = true                      # [display] in eink.toml
+    DISTILLER_EINK_REGISTER_ACCESS=1            # environment
+
+and is only available on the pure-Python backends. Every operation is logged
+to "distiller_cm5_sdk.hardware.eink.registers" and recorded in the audit log.
+"""
+
+import time
+from typing import Iterable, Optional, Union
+
+from ...diagnostics.audit import AuditCategory, audit
+from ...diagnostics.log_levels import get_logger
+from .display import DisplayError, DisplayMode
+
+
+REGISTER_ACCESS_ENV_VAR = "DISTILLER_EINK_REGISTER_ACCESS"
+
+# Controller commands used by the convenience methods
+CMD_DISPLAY_UPDATE_CONTROL_2 = 0x22
+CMD_MASTER_ACTIVATION = 0x20
+
+DEFAULT_BUSY_TIMEOUT_MS = 10000
+
+Data = Union[bytes, bytearray, Iterable[int]]
+
+
+class RegisterAccess:
+    """
+    Raw command interface to the panel controller.
+
+    Obtain one with Display.unsafe_registers(). Register writes bypass the
+    library's state: call Display.wake() afterwards to re-run the panel's
+    normal init sequence.
+    """
+
+    def __init__(self, library, panel):
+        """
+        Args:
+            library: Initialized PythonDisplayLibrary
+            panel: PanelSpec of the connected panel, used for update defaults
+        """
+        self._lib = library
+        self._transport = library.transport
+        self.panel = panel
+        self._log = get_logger("hardware.eink.registers")
+
+    def write(self, cmd: int, data: Data = b"") -> None:
+        """
+        Send a command byte followed by its data bytes.
+
+        Raises:
+            DisplayError: If a value is not a byte
+        """
+        payload = _to_bytes(data)
+        if not 0 <= cmd <= 0xFF:
+            raise DisplayError(f"Command 0x{cmd:X} is not a byte")
+        self._log.warning("write 0x%02X %s", cmd, payload.hex(" ") or "(no data)")
+        audit(AuditCategory.CONFIG, "register_write", f"0x{cmd:02X}", data=payload.hex())
+        self._transport.write_command(cmd)
+        if payload:
+            self._transport.write_data(payload)
+
+    def read(self, cmd: int, length: int) -> Optional[bytes]:
+        """
+        Send a read command and read its response.
+
+        Returns:
+            The bytes read, or None if the transport cannot read (see the README's
+            Autodetection section for the wiring this needs)
+        """
+        if not 0 <= cmd <= 0xFF or length <= 0:
+            raise DisplayError("Invalid register read")
+        self._transport.write_command(cmd)
+        data = self._transport.read_data(length)
+        self._log.info("read 0x%02X -> %s", cmd, data.hex(" ") if data is not None else "(unreadable)")
+        return data
+
+    def wait_busy(self, timeout_ms: int = DEFAULT_BUSY_TIMEOUT_MS) -> float:
+        """
+        Wait for the controller to release its busy line.
+
+        Returns:
+            Seconds waited
+
+        Raises:
+            DisplayError: On timeout
+        """
+        start = time.monotonic()
+        if not self._transport.wait_busy(timeout_ms):
+            raise DisplayError(f"Display busy after {timeout_ms} ms")
+        return time.monotonic() - start
+
+    def refresh(self, mode: DisplayMode = DisplayMode.FULL, update_control: Optional[int] = None) -> float:
+        """
+        Refresh the panel from its RAM, e.g. after writing a trial LUT.
+
+        Args:
+            mode: Selects the panel's full or partial Display Update Control 2 value
+            update_control: Explicit Display Update Control 2 (0x22) value instead
+
+        Returns:
+            Seconds the refresh took
+        """
+        if update_control is None:
+            update_control = self.panel.full_update if mode == DisplayMode.FULL else self.panel.partial_update
+        self.write(CMD_DISPLAY_UPDATE_CONTROL_2, [update_control])
+        self.write(CMD_MASTER_ACTIVATION)
+        return self.wait_busy()
+
+
+def _to_bytes(data: Data) -> bytes:
+    try:
+        return bytes(data)
+    except (TypeError, ValueError):
+        raise DisplayError(f"Register data must be bytes 0-255, got {data!r}")
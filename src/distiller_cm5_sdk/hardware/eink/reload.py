@@ -0,0 +1,212 @@
+#!/usr/bin/env python3
+"""
+Hot reload of the display config for CM5 SDK.
+
+A kiosk's rotation or dithering is often tuned on the device, and
+restarting the application after every edit of eink.toml is tedious.
+ConfigWatcher notices the config file changing, through inotify where the
+kernel has it and by polling otherwise, and Display.watch_config() then
+reloads the file and applies the settings that can change without
+reopening the panel:
+
+    display.set_event_callback(on_event)    # receives CONFIG_RELOADED
+    display.watch_config(sighup=True)       # also reload on SIGHUP
+
+Settings in LIVE_SETTINGS take effect on the next update. The others
+(panel, wiring, backend, busy policy...) are reported and applied the
+next time the display is opened.
+"""
+
+import ctypes
+import ctypes.util
+import os
+import select
+import signal
+import struct
+import threading
+from typing import Callable, Optional, Tuple
+
+
+# Settings, by their key in the key=value format, Display.reload_config() applies live
+LIVE_SETTINGS = (
+    "rotation", "threshold", "contrast", "dither", "parallel", "scaling_filter", "sharpen", "cache_dir", "cache_max_mb",
+    "full_refresh_every", "full_refresh_interval", "auto_partial_max_pixels",
+    "thermal_soc_max", "thermal_ambient_max", "thermal_ambient_min", "thermal_interval",
+)
+
+POLL_INTERVAL_S = 2.0   # checks of the file's modification time without inotify
+SETTLE_S = 0.2          # editors write in steps; changes this close together reload once
+
+# From <sys/inotify.h>
+_IN_CLOSE_WRITE = 0x00000008
+_IN_MOVED_TO = 0x00000080
+_IN_CREATE = 0x00000100
+_IN_NONBLOCK = 0o4000
+_IN_CLOEXEC = 0o2000000
+_EVENT_HEADER = struct.Struct("iIII")   # wd, mask, cookie, len
+
+
+class ConfigWatcherError(Exception):
+    """Custom exception for config watcher errors."""
+    pass
+
+
+def _inotify_watch(directory: str) -> Optional[int]:
+    """An inotify descriptor watching directory for written or replaced files, or None without inotify."""
+    name = ctypes.util.find_library("c")
+    if name is None:
+        return None
+    try:
+        libc = ctypes.CDLL(name, use_errno=True)
+        fd = libc.inotify_init1(_IN_NONBLOCK | _IN_CLOEXEC)
+    except (OSError, AttributeError):
+        return None
+    if fd < 0:
+        return None
+    # The directory, not the file: editors and save_config() replace the file by renaming over it
+    if libc.inotify_add_watch(fd, os.fsencode(directory), _IN_CLOSE_WRITE | _IN_MOVED_TO | _IN_CREATE) < 0:
+        os.close(fd)
+        return None
+    return fd
+
+
+def _names(data: bytes):
+    """File names of the inotify events in data."""
+    offset = 0
+    while offset + _EVENT_HEADER.size <= len(data):
+        _, _, _, length = _EVENT_HEADER.unpack_from(data, offset)
+        offset += _EVENT_HEADER.size
+        yield data[offset:offset + length].rstrip(b"\0")
+        offset += length
+
+
+class ConfigWatcher:
+    """Calls back when a config file is written or replaced."""
+
+    def __init__(self, path: str, on_change: Callable[[], None], poll_interval_s: float = POLL_INTERVAL_S,
+                 use_inotify: bool = True):
+        """
+        Args:
+            path: Config file to watch; it need not exist yet
+            on_change: Called on the watcher thread after each change, and after trigger()
+            poll_interval_s: Seconds between checks when polling
+            use_inotify: False to always poll, e.g. on filesystems inotify misses changes on
+        """
+        self.path = os.path.abspath(path)
+        self.on_change = on_change
+        self.poll_interval_s = poll_interval_s
+        self.use_inotify = use_inotify
+        self._stop = threading.Event()
+        self._triggered = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+        self._previous_sighup = None
+
+    @property
+    def running(self) -> bool:
+        return self._thread is not None
+
+    def _stat(self) -> Optional[Tuple[int, int, int]]:
+        try:
+            st = os.stat(self.path)
+        except OSError:
+            return None
+        return st.st_mtime_ns, st.st_ino, st.st_size
+
+    def _notify(self) -> None:
+        try:
+            self.on_change()
+        except Exception as e:
+            print(f"Warning: Config reload failed: {e}")
+
+    def _wait(self, timeout: float) -> bool:
+        """Wait for stop() or trigger(); True if triggered."""
+        if self._triggered.wait(timeout):
+            self._triggered.clear()
+            return not self._stop.is_set()
+        return False
+
+    def _run_inotify(self, fd: int) -> None:
+        name = os.fsencode(os.path.basename(self.path))
+        try:
+            while not self._stop.is_set():
+                if self._wait(0):
+                    self._notify()
+                    continue
+                ready, _, _ = select.select([fd], [], [], 0.5)
+                if not ready:
+                    continue
+                changed = False
+                while ready:
+                    try:
+                        changed |= name in _names(os.read(fd, 4096))
+                    except BlockingIOError:
+                        pass
+                    ready, _, _ = select.select([fd], [], [], SETTLE_S)
+                if changed and not self._stop.is_set():
+                    self._notify()
+        finally:
+            os.close(fd)
+
+    def _run_polling(self, last: Optional[Tuple[int, int, int]]) -> None:
+        while not self._stop.is_set():
+            triggered = self._wait(self.poll_interval_s)
+            if self._stop.is_set():
+                return
+            current = self._stat()
+            if triggered or current != last:
+                last = current
+                self._notify()
+
+    def start(self) -> None:
+        """
+        Watch the file on a background thread.
+
+        Raises:
+            ConfigWatcherError: If the file's directory does not exist
+        """
+        if self._thread is not None:
+            return
+        directory = os.path.dirname(self.path)
+        if not os.path.isdir(directory):
+            raise ConfigWatcherError(f"Config directory {directory} does not exist")
+        self._stop.clear()
+        fd = _inotify_watch(directory) if self.use_inotify else None
+        if fd is not None:
+            target = lambda: self._run_inotify(fd)
+        else:
+            last = self._stat()     # before returning, so changes right after start() are seen
+            target = lambda: self._run_polling(last)
+        self._thread = threading.Thread(target=target, name="eink-config-watch", daemon=True)
+        self._thread.start()
+
+    def trigger(self) -> None:
+        """Reload as if the file had changed; safe to call from a signal handler."""
+        self._triggered.set()
+
+    def install_sighup(self) -> None:
+        """
+        Call trigger() on SIGHUP, as daemons conventionally reload their config.
+
+        Raises:
+            ConfigWatcherError: If not called from the main thread, which alone may set signal handlers
+        """
+        try:
+            self._previous_sighup = signal.signal(signal.SIGHUP, lambda signum, frame: self.trigger())
+        except ValueError as e:
+            raise ConfigWatcherError(f"Cannot handle SIGHUP: {e}")
+
+    def stop(self) -> None:
+        """Stop watching and restore the SIGHUP handler replaced by install_sighup()."""
+        self._stop.set()
+        self._triggered.set()
+        if self._thread is not None:
+            if self._thread is not threading.current_thread():
+                self._thread.join(timeout=2.0)
+            self._thread = None
+        self._triggered.clear()
+        if self._previous_sighup is not None:
+            try:
+                signal.signal(signal.SIGHUP, self._previous_sighup)
+            except ValueError:
+                pass        # off the main thread; the handler only triggers a stopped watcher
+            self._previous_sighup = None
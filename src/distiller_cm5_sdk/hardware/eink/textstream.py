@@ -0,0 +1,155 @@
+#!/usr/bin/env python3
+"""
+Streaming text on the e-ink display.
+
+Shows text that arrives a little at a time, e.g. tokens from a language
+model, without the caller waiting for the panel:
+
+    with StreamingTextRenderer(display, scale=2) as renderer:
+        for token in tokens:
+            renderer.append(token)
+        renderer.flush()
+
+append() returns at once. A background thread redraws the whole text with
+a partial refresh whenever it has changed, at most every min_interval_s
+seconds, so text that arrives faster than the panel refreshes is coalesced
+into fewer refreshes instead of queuing up behind them. When the text no
+longer fits, its last lines are shown.
+"""
+
+import threading
+import time
+from typing import Optional
+
+from .display import Display, DisplayError, DisplayMode
+from .font import FONT_HEIGHT
+from .graphics import Framebuffer, wrap_text
+
+
+class StreamingTextRenderer:
+    """Word-wrapped text on the display, redrawn in the background as it grows."""
+
+    def __init__(self, display: Display, scale: int = 1, margin: int = 2, min_interval_s: float = 0.3,
+                 mode: DisplayMode = DisplayMode.PARTIAL):
+        """
+        Args:
+            display: Initialized Display to draw on
+            scale: Font magnification
+            margin: Blank border around the text, in pixels
+            min_interval_s: Shortest time between two refreshes
+            mode: Refresh mode of each redraw; the display's ghosting policy
+                  may still turn one into a full refresh
+        """
+        if scale < 1:
+            raise ValueError("scale must be at least 1")
+        self.display = display
+        self.scale = scale
+        self.margin = margin
+        self.min_interval_s = min_interval_s
+        self.mode = mode
+        self._text = ""
+        self._version = 0           # bumped by every change
+        self._shown = 0             # version last drawn, or given up on after an error
+        self._last_refresh = 0.0
+        self._closed = False
+        self._cond = threading.Condition()
+        self._thread: Optional[threading.Thread] = None
+
+    @property
+    def text(self) -> str:
+        """The whole text, including any not yet drawn."""
+        with self._cond:
+            return self._text
+
+    def append(self, text: str) -> None:
+        """Add text to the end; it is drawn by the next refresh."""
+        with self._cond:
+            self._change(self._text + text)
+
+    def set_text(self, text: str) -> None:
+        """Replace the whole text."""
+        with self._cond:
+            self._change(text)
+
+    def _change(self, text: str) -> None:
+        if self._closed:
+            raise RuntimeError("StreamingTextRenderer is closed")
+        if text != self._text:
+            self._text = text
+            self._version += 1
+            if self._thread is None:
+                self._thread = threading.Thread(target=self._run, name="eink-text-stream", daemon=True)
+                self._thread.start()
+            self._cond.notify_all()
+
+    def clear(self) -> None:
+        """Remove all text; the panel is redrawn blank."""
+        self.set_text("")
+
+    def flush(self, timeout: Optional[float] = None) -> bool:
+        """
+        Wait until the current text has been drawn.
+
+        Returns:
+            True once it is on the panel, False on timeout
+        """
+        deadline = None if timeout is None else time.monotonic() + timeout
+        with self._cond:
+            while self._shown != self._version:
+                remaining = None if deadline is None else deadline - time.monotonic()
+                if remaining is not None and remaining <= 0:
+                    return False
+                self._cond.wait(remaining)
+            return True
+
+    def close(self, flush: bool = True) -> None:
+        """Stop the background thread, after drawing the current text unless flush is False."""
+        if flush:
+            self.flush()
+        with self._cond:
+            self._closed = True
+            if not flush:
+                self._shown = self._version
+            self._cond.notify_all()
+            thread = self._thread
+        if thread is not None:
+            thread.join()
+
+    def render(self, text: str) -> Framebuffer:
+        """The frame showing text: wrapped to the width, and its last lines if it is too long."""
+        width, height = self.display.get_dimensions()
+        fb = Framebuffer(width, height)
+        lines = wrap_text(text, width - 2 * self.margin, self.scale).split("\n")
+        fitting = max(1, (height - 2 * self.margin) // (FONT_HEIGHT * self.scale))
+        fb.draw_text(self.margin, self.margin, "\n".join(lines[-fitting:]), scale=self.scale)
+        return fb
+
+    def _run(self) -> None:
+        while True:
+            with self._cond:
+                while self._shown == self._version and not self._closed:
+                    self._cond.wait()
+                if self._shown == self._version:
+                    return
+                wait = self._last_refresh + self.min_interval_s - time.monotonic()
+                if wait > 0:
+                    # Let more text arrive; it is drawn by the same refresh
+                    self._cond.wait(wait)
+                    continue
+                text, version = self._text, self._version
+
+            try:
+                self.display.display_image(self.render(text).to_bytes(), self.mode)
+            except DisplayError as e:
+                print(f"Warning: Failed to draw streaming text: {e}")
+
+            with self._cond:
+                self._last_refresh = time.monotonic()
+                self._shown = max(self._shown, version)
+                self._cond.notify_all()
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, exc_type, exc_val, exc_tb):
+        self.close(flush=exc_type is None)
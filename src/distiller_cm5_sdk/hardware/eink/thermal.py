@@ -0,0 +1,156 @@
+#!/usr/bin/env python3
+"""
+Thermal-aware refresh throttling for CM5 SDK.
+
+Refreshes degrade and wear the panel faster at temperature extremes, and a
+hot SoC should not be kept busy redrawing. ThermalPolicy watches the SoC
+temperature and the ambient one the panel is compensated for (see
+Display.set_temperature()). Beyond a limit it throttles: refreshes are held
+at least a minimum interval apart and requested full refreshes are done as
+partial ones, until the temperature is a few degrees back inside the
+limits. Every Display applies it, set from eink.toml:
+
+    [thermal]
+    soc_max = 80                    # °C
+    ambient_max = 45
+    ambient_min = 0
+    interval = 10                   # seconds between throttled refreshes; 0 disables
+"""
+
+import time
+from enum import Enum
+from typing import Callable, Optional, Tuple
+
+from .display import DisplayMode
+
+
+SOC_TEMPERATURE_PATH = "/sys/class/thermal/thermal_zone0/temp"
+
+# Temperatures are read at most this often, not on every refresh
+CHECK_INTERVAL_S = 5.0
+
+# Degrees back inside a limit before throttling ends
+HYSTERESIS_C = 3.0
+
+DEFAULT_SOC_MAX_C = 80.0
+DEFAULT_AMBIENT_MAX_C = 45.0
+DEFAULT_AMBIENT_MIN_C = 0.0
+DEFAULT_INTERVAL_S = 10.0
+
+
+class ThrottleReason(Enum):
+    """Limit that started throttling."""
+    SOC_HOT = "soc_hot"
+    AMBIENT_HOT = "ambient_hot"
+    AMBIENT_COLD = "ambient_cold"
+
+
+def read_soc_temperature(path: str = SOC_TEMPERATURE_PATH) -> Optional[float]:
+    """SoC temperature in degrees Celsius, or None if the thermal zone cannot be read."""
+    try:
+        with open(path) as f:
+            return int(f.read().strip()) / 1000
+    except (OSError, ValueError):
+        return None
+
+
+class ThermalPolicy:
+    """Decides when refreshes are throttled, and for how long each waits."""
+
+    def __init__(self, soc_max_c: float = DEFAULT_SOC_MAX_C, ambient_max_c: float = DEFAULT_AMBIENT_MAX_C,
+                 ambient_min_c: float = DEFAULT_AMBIENT_MIN_C, interval_s: float = DEFAULT_INTERVAL_S,
+                 read_soc: Callable[[], Optional[float]] = read_soc_temperature,
+                 read_ambient: Callable[[], Optional[float]] = lambda: None,
+                 on_change: Optional[Callable[[Optional[ThrottleReason], Optional[float]], None]] = None,
+                 clock: Callable[[], float] = time.monotonic, sleep: Callable[[float], None] = time.sleep):
+        """
+        Args:
+            soc_max_c: SoC temperature above which refreshes are throttled
+            ambient_max_c: Ambient temperature above which refreshes are throttled
+            ambient_min_c: Ambient temperature below which refreshes are throttled
+            interval_s: Shortest time between two throttled refreshes; 0 disables throttling
+            read_soc: Returns the SoC temperature, or None if unknown
+            read_ambient: Returns the ambient temperature, or None if unknown
+            on_change: Called with the reason and temperature when throttling
+                       starts, and with (None, temperature) when it ends
+            clock: Monotonic clock, replaceable for testing
+            sleep: Sleeps for a number of seconds, replaceable for testing
+        """
+        self.soc_max_c = soc_max_c
+        self.ambient_max_c = ambient_max_c
+        self.ambient_min_c = ambient_min_c
+        self.interval_s = interval_s
+        self.read_soc = read_soc
+        self.read_ambient = read_ambient
+        self.on_change = on_change
+        self.reason: Optional[ThrottleReason] = None
+        self._clock = clock
+        self._sleep = sleep
+        self._checked: Optional[float] = None
+        self._last_refresh: Optional[float] = None
+
+    @property
+    def enabled(self) -> bool:
+        return self.interval_s > 0
+
+    @property
+    def throttled(self) -> bool:
+        return self.reason is not None
+
+    def _exceeded(self, margin: float) -> Tuple[Optional[ThrottleReason], Optional[float]]:
+        """The limit exceeded, or come within margin degrees of, and the temperature concerned."""
+        soc = self.read_soc()
+        if soc is not None and soc > self.soc_max_c - margin:
+            return ThrottleReason.SOC_HOT, soc
+        ambient = self.read_ambient()
+        if ambient is not None and ambient > self.ambient_max_c - margin:
+            return ThrottleReason.AMBIENT_HOT, ambient
+        if ambient is not None and ambient < self.ambient_min_c + margin:
+            return ThrottleReason.AMBIENT_COLD, ambient
+        return None, soc if ambient is None else ambient
+
+    def check(self, now: Optional[float] = None) -> bool:
+        """
+        Read the temperatures if CHECK_INTERVAL_S has passed since the last check.
+
+        Returns:
+            True if refreshes are throttled
+        """
+        if not self.enabled:
+            return False
+        now = self._clock() if now is None else now
+        if self._checked is not None and now - self._checked < CHECK_INTERVAL_S:
+            return self.throttled
+        self._checked = now
+        # Once throttled, stay so until HYSTERESIS_C inside the limits
+        reason, temperature = self._exceeded(HYSTERESIS_C if self.throttled else 0.0)
+        if reason != self.reason:
+            if self.throttled and reason is not None:
+                self.reason = reason        # another limit took over; still throttled
+                return True
+            self.reason = reason
+            if self.on_change is not None:
+                self.on_change(reason, temperature)
+        return self.throttled
+
+    def mode_for(self, mode: DisplayMode) -> DisplayMode:
+        """Refresh mode to use for a requested update: partial while throttled."""
+        return DisplayMode.PARTIAL if self.throttled else DisplayMode(mode)
+
+    def delay(self, now: Optional[float] = None) -> float:
+        """Seconds the next refresh must wait; 0 unless throttled."""
+        if not self.throttled or self._last_refresh is None:
+            return 0.0
+        now = self._clock() if now is None else now
+        return max(0.0, self._last_refresh + self.interval_s - now)
+
+    def wait(self) -> None:
+        """Check the temperatures and wait as long as the next refresh must."""
+        self.check()
+        delay = self.delay()
+        if delay > 0:
+            self._sleep(delay)
+
+    def record(self, now: Optional[float] = None) -> None:
+        """Note a completed refresh."""
+        self._last_refresh = self._clock() if now is None else now
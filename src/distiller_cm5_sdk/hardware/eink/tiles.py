@@ -0,0 +1,439 @@
+#!/usr/bin/env python3
+"""
+Live tiles: dashboards composed from data sources.
+
+An ImageSource produces the content of one rectangle of the screen and says
+how often it should be asked for it. A TileBoard hosts sources as tiles,
+polls each when it is due, and refreshes the display when any tile changed,
+so a dashboard is a list of sources rather than a hand-written loop:
+
+    board = TileBoard(display)
+    board.add(FileSource("/var/lib/myapp/plot.png"), 0, 0, 128, 125)
+    board.add(MetricChartSource(read_temperature, "Temp", poll_interval_s=60), 0, 125, 128, 125,
+              border=True)
+    board.run()
+
+Built-in sources:
+
+- FileSource: an image file, shown again whenever it changes on disk
+- UrlSource: an image fetched over HTTP, e.g. a weather map
+- CameraSource: a still from the camera module
+- MetricChartSource: a line chart of a number sampled on every poll
+
+A source of its own subclasses ImageSource and implements fetch(), which
+returns an image file path, encoded image bytes or a Framebuffer, or None
+when nothing changed. Images are prepared for the tile with the source's
+options, its render hints, or with the device's processing profile.
+
+A source that fails prints a warning, and its tile shows a placeholder, a
+warning sign with the time of the last successful fetch, until it recovers.
+It is asked again after a growing delay set by the tile's RetryPolicy:
+
+    board.add(UrlSource(url), 0, 0, 128, 125, retry=RetryPolicy(initial_delay_s=30, max_delay_s=3600))
+"""
+
+import hashlib
+import os
+import threading
+import time
+import urllib.error
+import urllib.request
+from collections import deque
+from dataclasses import dataclass, field
+from typing import Callable, Deque, List, Optional, Union
+
+from .display import DisplayMode
+from .font import FONT_HEIGHT
+from .graphics import Framebuffer, measure_text
+from .imaging import ImageError, ImageOptions, process_grayscale, process_image
+
+
+# Largest image UrlSource downloads
+MAX_DOWNLOAD_BYTES = 16 * 1024 * 1024
+
+# Shortest poll interval, and shortest retry delay; e-ink refreshes take longer than this anyway
+MIN_POLL_INTERVAL_S = 1.0
+
+# Size of the warning sign on a failed tile's placeholder, in pixels
+PLACEHOLDER_GLYPH_SIZE = 15
+
+
+class TileError(Exception):
+    """Custom exception for live tile and image source errors."""
+    pass
+
+
+class ImageSource:
+    """
+    Base class of the content of a live tile.
+
+    Attributes:
+        poll_interval_s: Seconds between fetches
+        options: Render hints, how fetched images are scaled and dithered;
+                 None for the device's processing profile
+    """
+
+    poll_interval_s: float = 60.0
+    options: Optional[ImageOptions] = None
+
+    def fetch(self, width: int, height: int) -> Union[str, bytes, Framebuffer, None]:
+        """
+        Fetch the tile's content.
+
+        Args:
+            width: Tile width in pixels
+            height: Tile height in pixels
+
+        Returns:
+            An image file path, encoded image bytes or a Framebuffer (cut off
+            or padded with white to the tile), or None if nothing changed
+
+        Raises:
+            TileError: If the content cannot be fetched
+        """
+        raise NotImplementedError
+
+
+class FileSource(ImageSource):
+    """An image file, shown again whenever its modification time or size changes."""
+
+    def __init__(self, path: str, poll_interval_s: float = 5.0, options: Optional[ImageOptions] = None):
+        self.path = path
+        self.poll_interval_s = poll_interval_s
+        self.options = options
+        self._stamp = None
+
+    def fetch(self, width: int, height: int) -> Optional[str]:
+        try:
+            info = os.stat(self.path)
+        except OSError as e:
+            raise TileError(f"Cannot read {self.path}: {e}")
+        stamp = (info.st_mtime_ns, info.st_size)
+        if stamp == self._stamp:
+            return None
+        self._stamp = stamp
+        return self.path
+
+
+class UrlSource(ImageSource):
+    """An image fetched over HTTP; unchanged responses, by validator or content, are skipped."""
+
+    def __init__(self, url: str, poll_interval_s: float = 300.0, options: Optional[ImageOptions] = None,
+                 timeout_s: float = 10.0):
+        self.url = url
+        self.poll_interval_s = poll_interval_s
+        self.options = options
+        self.timeout_s = timeout_s
+        self._etag: Optional[str] = None
+        self._modified: Optional[str] = None
+        self._digest: Optional[bytes] = None
+
+    def fetch(self, width: int, height: int) -> Optional[bytes]:
+        request = urllib.request.Request(self.url)
+        if self._etag:
+            request.add_header("If-None-Match", self._etag)
+        if self._modified:
+            request.add_header("If-Modified-Since", self._modified)
+        try:
+            with urllib.request.urlopen(request, timeout=self.timeout_s) as response:
+                body = response.read(MAX_DOWNLOAD_BYTES + 1)
+                etag, modified = response.headers.get("ETag"), response.headers.get("Last-Modified")
+        except urllib.error.HTTPError as e:
+            if e.code == 304:
+                return None
+            raise TileError(f"Cannot fetch {self.url}: HTTP {e.code}")
+        except (urllib.error.URLError, OSError) as e:
+            raise TileError(f"Cannot fetch {self.url}: {e}")
+        if len(body) > MAX_DOWNLOAD_BYTES:
+            raise TileError(f"{self.url} is larger than {MAX_DOWNLOAD_BYTES} bytes")
+
+        self._etag, self._modified = etag, modified
+        digest = hashlib.sha256(body).digest()
+        if digest == self._digest:
+            return None
+        self._digest = digest
+        return body
+
+
+class CameraSource(ImageSource):
+    """A still from the camera module on every poll."""
+
+    def __init__(self, camera=None, poll_interval_s: float = 60.0, options: Optional[ImageOptions] = None):
+        """
+        Args:
+            camera: Open hardware.camera Camera; None opens one on the first fetch
+            poll_interval_s: Seconds between stills
+            options: Render hints
+        """
+        self.camera = camera
+        self.poll_interval_s = poll_interval_s
+        self.options = options
+
+    def fetch(self, width: int, height: int) -> Framebuffer:
+        try:
+            if self.camera is None:
+                from ..camera.camera import Camera
+                self.camera = Camera()
+            frame = self.camera.get_frame()
+        except Exception as e:      # CameraError, or the camera packages missing
+            raise TileError(f"Cannot capture from the camera: {e}")
+
+        if frame.ndim == 3:
+            red, blue = (0, 2) if getattr(self.camera, "format", "bgr") == "rgb" else (2, 0)
+            frame = (frame[..., red].astype("uint32") * 299 + frame[..., 1].astype("uint32") * 587
+                     + frame[..., blue].astype("uint32") * 114) // 1000
+        frame_height, frame_width = frame.shape[:2]
+        try:
+            return process_grayscale(frame.astype("uint8").tobytes(), frame_width, frame_height, self.options,
+                                     width, height)
+        except ImageError as e:
+            raise TileError(str(e))
+
+
+class MetricChartSource(ImageSource):
+    """A line chart of a number, sampled on every poll, under its label and latest value."""
+
+    def __init__(self, metric: Callable[[], float], label: str = "", poll_interval_s: float = 60.0,
+                 history: Optional[int] = None, minimum: Optional[float] = None,
+                 maximum: Optional[float] = None, value_format: str = "g"):
+        """
+        Args:
+            metric: Called for a new sample on every poll
+            label: Drawn before the latest value
+            poll_interval_s: Seconds between samples
+            history: Samples across the chart; None for one per pixel column
+            minimum: Value at the bottom of the chart; None for the lowest sample
+            maximum: Value at the top; None for the highest sample
+            value_format: Format spec of the latest value, e.g. ".1f"
+        """
+        self.metric = metric
+        self.label = label
+        self.poll_interval_s = poll_interval_s
+        self.history = history
+        self.minimum = minimum
+        self.maximum = maximum
+        self.value_format = value_format
+        self.samples: Deque[float] = deque(maxlen=history)
+
+    def fetch(self, width: int, height: int) -> Framebuffer:
+        try:
+            value = float(self.metric())
+        except Exception as e:
+            raise TileError(f"Metric {self.label or self.metric!r} failed: {e}")
+        if self.samples.maxlen is None:
+            self.samples = deque(self.samples, maxlen=width)
+        self.samples.append(value)
+        return self.render(width, height)
+
+    def render(self, width: int, height: int) -> Framebuffer:
+        """Draw the chart of the samples so far."""
+        fb = Framebuffer(width, height)
+        if not self.samples:
+            return fb
+        fb.draw_text(0, 0, f"{self.label} {self.samples[-1]:{self.value_format}}".strip())
+        top = FONT_HEIGHT + 2
+        chart_height = height - top
+        if chart_height < 2:
+            return fb
+
+        low = min(self.samples) if self.minimum is None else self.minimum
+        high = max(self.samples) if self.maximum is None else self.maximum
+        span = (high - low) or 1.0
+        step = (width - 1) / max(self.samples.maxlen - 1, 1)
+        points = []
+        for age, sample in enumerate(reversed(self.samples)):
+            level = (min(max(sample, low), high) - low) / span
+            points.append((round(width - 1 - age * step), top + round((1 - level) * (chart_height - 1))))
+        if len(points) == 1:
+            fb.set_pixel(*points[0])
+        for start, end in zip(points, points[1:]):
+            fb.draw_line(*start, *end)
+        return fb
+
+
+@dataclass(frozen=True)
+class RetryPolicy:
+    """
+    When a tile whose source failed is fetched again: initial_delay_s after
+    the first failure, factor times longer after each further one, but never
+    longer than max_delay_s. The poll interval applies again once a fetch succeeds.
+    """
+    initial_delay_s: float = 5.0
+    factor: float = 2.0
+    max_delay_s: float = 600.0
+
+    def delay(self, failures: int) -> float:
+        """Seconds before the next fetch after this many failures in a row."""
+        return min(self.initial_delay_s * self.factor ** min(failures - 1, 64), self.max_delay_s)
+
+
+@dataclass
+class Tile:
+    """An image source placed on the screen."""
+    source: ImageSource
+    x: int
+    y: int
+    width: int
+    height: int
+    border: bool = False                    # outline drawn around the tile
+    retry: RetryPolicy = field(default_factory=RetryPolicy)
+    frame: Optional[Framebuffer] = None     # last content, None until the first fetch succeeds
+    due: float = 0.0                        # clock time of the next fetch
+    failures: int = 0                       # failed fetches in a row; the placeholder shows while not 0
+    updated: Optional[float] = None         # wall clock time of the last successful fetch
+
+
+def render_placeholder(width: int, height: int, updated: Optional[float] = None) -> Framebuffer:
+    """
+    The placeholder of a tile whose source failed: a warning sign above the
+    time of the last successful fetch, or "No data" if there was none. A
+    tile too small for both shows only the sign, as much of it as fits.
+    """
+    fb = Framebuffer(width, height)
+    if updated is None:
+        label = "No data"
+    elif time.strftime("%Y-%m-%d", time.localtime(updated)) == time.strftime("%Y-%m-%d"):
+        label = time.strftime("Updated %H:%M", time.localtime(updated))
+    else:
+        label = time.strftime("Updated %b %d", time.localtime(updated))
+    label_width, label_height, _ = measure_text(label)
+    glyph = PLACEHOLDER_GLYPH_SIZE
+    show_label = label_width <= width and glyph + 2 + label_height <= height
+    top = (height - glyph - (2 + label_height if show_label else 0)) // 2
+    left = (width - glyph) // 2
+    fb.draw_polygon([(left + glyph // 2, top), (left + glyph - 1, top + glyph - 1), (left, top + glyph - 1)])
+    fb.draw_vline(left + glyph // 2, top + 5, glyph - 10)
+    fb.set_pixel(left + glyph // 2, top + glyph - 3)
+    if show_label:
+        fb.draw_text((width - label_width) // 2, top + glyph + 2, label)
+    return fb
+
+
+class TileBoard:
+    """Hosts image sources as auto-updating tiles of the screen."""
+
+    def __init__(self, display, clock: Callable[[], float] = time.monotonic,
+                 wall_clock: Callable[[], float] = time.time):
+        """
+        Args:
+            display: Initialized Display
+            clock: Clock in seconds that schedules fetches, replaceable for testing
+            wall_clock: Seconds since the epoch, for the time placeholders show
+        """
+        self.display = display
+        self.clock = clock
+        self.wall_clock = wall_clock
+        self.tiles: List[Tile] = []
+        self._shown = False
+        self._stop = threading.Event()
+
+    def add(self, source: ImageSource, x: int, y: int, width: int, height: int, border: bool = False,
+            retry: Optional[RetryPolicy] = None) -> Tile:
+        """
+        Place a source on the screen, fetched on the next poll.
+
+        Args:
+            retry: When to fetch again after the source fails; None for RetryPolicy()
+
+        Raises:
+            TileError: If the tile does not lie within the frame, or the poll
+                       interval or retry policy is invalid
+        """
+        frame_width, frame_height = self.display.get_dimensions()
+        if width < 1 or height < 1 or x < 0 or y < 0 or x + width > frame_width or y + height > frame_height:
+            raise TileError(f"Tile {width}x{height} at ({x}, {y}) is outside the {frame_width}x{frame_height} frame")
+        if source.poll_interval_s < MIN_POLL_INTERVAL_S:
+            raise TileError(f"Poll interval must be at least {MIN_POLL_INTERVAL_S:g} seconds")
+        retry = retry or RetryPolicy()
+        if retry.initial_delay_s < MIN_POLL_INTERVAL_S or retry.factor < 1 or retry.max_delay_s < retry.initial_delay_s:
+            raise TileError(f"Invalid retry policy {retry}: delays must be at least {MIN_POLL_INTERVAL_S:g} "
+                            "seconds and grow")
+        tile = Tile(source, x, y, width, height, border, retry)
+        self.tiles.append(tile)
+        return tile
+
+    def poll(self) -> bool:
+        """
+        Fetch every tile that is due.
+
+        Returns:
+            True if any tile's content changed, or it failed or recovered
+        """
+        now = self.clock()
+        changed = False
+        for i, tile in enumerate(self.tiles):
+            if now < tile.due:
+                continue
+            try:
+                content = tile.source.fetch(tile.width, tile.height)
+                if content is not None:
+                    tile.frame = self._prepare(tile, content)
+            except (TileError, ImageError) as e:
+                tile.failures += 1
+                delay = tile.retry.delay(tile.failures)
+                tile.due = now + delay
+                print(f"Warning: Tile {i} ({type(tile.source).__name__}) not updated: {e}; "
+                      f"retrying in {delay:g} s")
+                # The placeholder replaces the content once; further failures change nothing on screen
+                changed = changed or tile.failures == 1
+                continue
+            tile.due = now + tile.source.poll_interval_s
+            tile.updated = self.wall_clock()
+            changed = changed or content is not None or tile.failures > 0
+            tile.failures = 0
+        return changed
+
+    def _prepare(self, tile: Tile, content: Union[str, bytes, Framebuffer]) -> Framebuffer:
+        if isinstance(content, Framebuffer):
+            if (content.width, content.height) == (tile.width, tile.height):
+                return content
+            frame = Framebuffer(tile.width, tile.height)
+            frame.blit(content, 0, 0)
+            return frame
+        return process_image(content, tile.source.options, tile.width, tile.height)
+
+    def render(self) -> Framebuffer:
+        """Draw every tile's last content at the display's current dimensions."""
+        fb = Framebuffer(*self.display.get_dimensions())
+        for tile in self.tiles:
+            if tile.failures:
+                fb.blit(render_placeholder(tile.width, tile.height, tile.updated), tile.x, tile.y)
+            elif tile.frame is not None:
+                fb.blit(tile.frame, tile.x, tile.y)
+            if tile.border:
+                fb.draw_rect(tile.x, tile.y, tile.width, tile.height)
+        return fb
+
+    def update(self) -> Optional[DisplayMode]:
+        """
+        Poll the tiles and refresh the display if any changed, or on the first update.
+
+        Returns:
+            The refresh mode used, or None if nothing changed
+        """
+        if not self.poll() and self._shown:
+            return None
+        self._shown = True
+        return self.display.display_image_auto(self.render().to_bytes())
+
+    def next_due(self) -> Optional[float]:
+        """Clock time of the next fetch, or None without tiles."""
+        return min((tile.due for tile in self.tiles), default=None)
+
+    def run(self, stop: Optional[threading.Event] = None) -> None:
+        """
+        Keep the tiles up to date until stop() is called or the stop event is set.
+
+        Args:
+            stop: Optional event that ends the loop when set
+        """
+        stop = stop or self._stop
+        while not stop.is_set():
+            self.update()
+            due = self.next_due()
+            if stop.wait(None if due is None else max(due - self.clock(), 0)):
+                break
+
+    def stop(self) -> None:
+        """End run() from another thread."""
+        self._stop.set()
@@ -0,0 +1,158 @@
+#!/usr/bin/env python3
+"""
+Panel aging counters for CM5 SDK.
+
+Cumulative full and partial refresh counts are kept per physical panel in a
+JSON file that survives reboots, so warranty analysis can correlate failures
+with usage. A panel is keyed by its OTP User ID where it can be read (python
+backend with SDA readback wiring), and otherwise by its panel type.
+
+Counts are buffered in memory and merged into the file every FLUSH_EVERY
+refreshes, at most FLUSH_INTERVAL_S apart, and when the display sleeps or is
+closed, which keeps flash writes rare. Several processes may share the file.
+"""
+
+import fcntl
+import json
+import os
+import tempfile
+import time
+from dataclasses import asdict, dataclass
+from typing import Dict, Optional
+
+from .display import DisplayMode
+
+
+USAGE_PATH = "/var/lib/distiller-cm5-sdk/panel-usage.json"
+USAGE_PATH_ENV_VAR = "DISTILLER_EINK_USAGE_FILE"
+
+FLUSH_EVERY = 50
+FLUSH_INTERVAL_S = 300.0
+
+
+@dataclass
+class PanelUsage:
+    """Cumulative refresh counts of one panel."""
+    panel_key: str                  # "uid:<User ID hex>" or "type:<panel type>"
+    panel: str                      # panel type, e.g. "epd128x250"
+    full_refreshes: int = 0
+    partial_refreshes: int = 0
+    first_seen: float = 0.0         # seconds since the epoch
+    last_refresh: float = 0.0
+
+    @property
+    def total_refreshes(self) -> int:
+        return self.full_refreshes + self.partial_refreshes
+
+
+def panel_key(panel: str, user_id: Optional[bytes] = None) -> str:
+    """Key identifying a panel: its User ID if readable, otherwise its type."""
+    if user_id and any(b != 0x00 for b in user_id) and any(b != 0xFF for b in user_id):
+        return f"uid:{user_id.hex()}"
+    return f"type:{panel}"
+
+
+def usage_path() -> str:
+    """Path of the usage file (DISTILLER_EINK_USAGE_FILE overrides the default)."""
+    return os.environ.get(USAGE_PATH_ENV_VAR, USAGE_PATH)
+
+
+def load_usage(path: Optional[str] = None) -> Dict[str, PanelUsage]:
+    """
+    Read the counters of every panel seen on this device.
+
+    A missing or unreadable file yields an empty dictionary.
+    """
+    path = path or usage_path()
+    try:
+        with open(path, "r") as f:
+            data = json.load(f)
+    except (OSError, ValueError):
+        return {}
+    usage = {}
+    for key, entry in data.get("panels", {}).items():
+        try:
+            usage[key] = PanelUsage(panel_key=key, **{k: v for k, v in entry.items() if k != "panel_key"})
+        except TypeError:
+            continue  # written by a newer SDK with fields this one doesn't know
+    return usage
+
+
+class UsageCounter:
+    """Counts one panel's refreshes and merges them into the usage file."""
+
+    def __init__(self, panel: str, user_id: Optional[bytes] = None, path: Optional[str] = None,
+                 flush_every: int = FLUSH_EVERY, flush_interval: float = FLUSH_INTERVAL_S):
+        """
+        Args:
+            panel: Panel type value, e.g. "epd128x250"
+            user_id: Panel OTP User ID, or None if it cannot be read
+            path: Usage file; defaults to DISTILLER_EINK_USAGE_FILE or
+                  /var/lib/distiller-cm5-sdk/panel-usage.json
+            flush_every: Refreshes buffered before writing the file
+            flush_interval: Maximum seconds between writes while refreshing
+        """
+        self.panel = panel
+        self.key = panel_key(panel, user_id)
+        self.path = path or usage_path()
+        self.flush_every = flush_every
+        self.flush_interval = flush_interval
+        self._pending_full = 0
+        self._pending_partial = 0
+        self._last_refresh = 0.0
+        self._last_flush = time.monotonic()
+        self._warned = False
+
+    def record(self, mode: DisplayMode) -> None:
+        """Count one refresh."""
+        if DisplayMode(mode) == DisplayMode.FULL:
+            self._pending_full += 1
+        else:
+            self._pending_partial += 1
+        self._last_refresh = time.time()
+        pending = self._pending_full + self._pending_partial
+        if pending >= self.flush_every or time.monotonic() - self._last_flush >= self.flush_interval:
+            self.flush()
+
+    def get(self) -> PanelUsage:
+        """This panel's counters, including refreshes not yet written."""
+        usage = load_usage(self.path).get(self.key) or PanelUsage(self.key, self.panel)
+        return self._merged(usage)
+
+    def _merged(self, usage: PanelUsage) -> PanelUsage:
+        usage.full_refreshes += self._pending_full
+        usage.partial_refreshes += self._pending_partial
+        if self._pending_full or self._pending_partial:
+            usage.first_seen = usage.first_seen or self._last_refresh
+            usage.last_refresh = max(usage.last_refresh, self._last_refresh)
+        return usage
+
+    def flush(self) -> None:
+        """Merge buffered counts into the file. Failures print one warning and keep the counts."""
+        self._last_flush = time.monotonic()
+        if not (self._pending_full or self._pending_partial):
+            return
+        directory = os.path.dirname(self.path) or "."
+        try:
+            os.makedirs(directory, exist_ok=True)
+            # Serialize the read-modify-write with other processes sharing the file
+            with open(self.path + ".lock", "w") as lock:
+                fcntl.flock(lock, fcntl.LOCK_EX)
+                usage = load_usage(self.path)
+                usage[self.key] = self._merged(usage.get(self.key) or PanelUsage(self.key, self.panel))
+                panels = {key: {k: v for k, v in asdict(entry).items() if k != "panel_key"}
+                          for key, entry in sorted(usage.items())}
+                fd, tmp_path = tempfile.mkstemp(dir=directory, prefix=".panel-usage.")
+                with os.fdopen(fd, "w") as f:
+                    json.dump({"version": 1, "panels": panels}, f, indent=2)
+                    f.flush()
+                    os.fsync(f.fileno())
+                os.chmod(tmp_path, 0o644)
+                os.replace(tmp_path, self.path)
+        except OSError as e:
+            if not self._warned:
+                print(f"Warning: Failed to write panel usage {self.path}: {e}")
+                self._warned = True
+            return
+        self._pending_full = 0
+        self._pending_partial = 0
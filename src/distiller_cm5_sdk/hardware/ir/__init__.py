@@ -0,0 +1,3 @@
+from .receiver import (IrButtons, IrCode, IrError, IrEvent, IrProtocol, IrReceiver, learn_keymap,
+                       load_keymap, save_keymap)
+from .transmitter import IrSignal, IrTransmitter, encode, parse_timings
@@ -0,0 +1,84 @@
+#!/usr/bin/env python3
+"""
+IR remote tools: python3 -m distiller_cm5_sdk.hardware.ir monitor | learn [PATH] | send CODE | send-raw TIMINGS
+"""
+
+import argparse
+import sys
+
+from .receiver import KEYMAP_PATH, IrCode, IrError, IrReceiver, learn_keymap, save_keymap
+from .transmitter import IrSignal, IrTransmitter, parse_timings
+
+
+def _monitor(args) -> int:
+    receiver = IrReceiver(args.device)
+    try:
+        print("Press buttons on the remote (Ctrl+C to stop)")
+        while True:
+            event = receiver.read()
+            print(f"{event.code}{' (repeat)' if event.repeat else ''}")
+    finally:
+        receiver.close()
+
+
+def _learn(args) -> int:
+    receiver = IrReceiver(args.device)
+    try:
+        keymap = learn_keymap(receiver, timeout=args.timeout)
+    finally:
+        receiver.close()
+    save_keymap(keymap, args.path)
+    print(f"Saved {args.path}")
+    return 0
+
+
+def _send(args) -> int:
+    if args.command_name == "send":
+        signal = IrCode.parse(args.code)
+    else:
+        signal = IrSignal(parse_timings(args.timings), args.carrier, args.duty_cycle)
+    transmitter = IrTransmitter(args.device)
+    try:
+        transmitter.send(signal, repeats=args.repeats)
+    finally:
+        transmitter.close()
+    return 0
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Distiller CM5 IR remote tools")
+    parser.add_argument("--device", help="LIRC device (default: the first gpio-ir receiver or transmitter)")
+    commands = parser.add_subparsers(dest="command_name", required=True)
+
+    monitor = commands.add_parser("monitor", help="Print the code of every button pressed")
+    monitor.set_defaults(handler=_monitor)
+
+    learn = commands.add_parser("learn", help="Learn UP, DOWN and SELECT of a remote")
+    learn.add_argument("path", nargs="?", default=KEYMAP_PATH, help=f"Key map file (default: {KEYMAP_PATH})")
+    learn.add_argument("--timeout", type=float, default=30.0, help="Seconds to wait for each press (default: 30)")
+    learn.set_defaults(handler=_learn)
+
+    send = commands.add_parser("send", help="Transmit an NEC or RC-5 code, e.g. nec:0x40bf")
+    send.add_argument("code", help="protocol:scancode as printed by monitor")
+    send.add_argument("--repeats", type=int, default=0, help="Extra frames, as if the button were held")
+    send.set_defaults(handler=_send)
+
+    send_raw = commands.add_parser("send-raw", help="Transmit raw pulse/space timings in microseconds")
+    send_raw.add_argument("timings", help='e.g. "+9000 -4500 +560 ..." (mode2 style) or "9000,4500,560,..."')
+    send_raw.add_argument("--carrier", type=int, default=38000, help="Carrier frequency in Hz (default: 38000)")
+    send_raw.add_argument("--duty-cycle", type=int, help="Carrier duty cycle in percent")
+    send_raw.add_argument("--repeats", type=int, default=0, help="Extra frames, as if the button were held")
+    send_raw.set_defaults(handler=_send)
+
+    args = parser.parse_args(argv)
+    try:
+        return args.handler(args)
+    except IrError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    except KeyboardInterrupt:
+        return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
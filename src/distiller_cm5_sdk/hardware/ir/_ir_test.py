@@ -0,0 +1,139 @@
+#!/usr/bin/env python3
+"""
+IR receiver unit tests for CM5 SDK.
+"""
+
+import os
+import struct
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.ir.receiver import (
+    IrButtons, IrCode, IrError, IrProtocol, IrReceiver, learn_keymap, load_keymap, save_keymap,
+)
+from distiller_cm5_sdk.hardware.ir.transmitter import IrSignal, IrTransmitter, encode, parse_timings
+from distiller_cm5_sdk.recovery.buttons import Button
+
+
+def scancode(proto, code, t, repeat=False):
+    """A struct lirc_scancode as read from /dev/lircN."""
+    return struct.pack("QHHIQ", int(t * 1e9), 2 if repeat else 0, proto.value, 0, code)
+
+
+def fake_ioctl(fd, request, arg=0):
+    """LIRC_GET_FEATURES reports scancode receive, pulse send and carrier support; other requests succeed."""
+    return struct.pack("I", 0x80102) if request == 0x80046900 else 0
+
+
+def decode_nec(timings):
+    """The four bytes of an NEC frame, each sent LSB first."""
+    bits = [1 if space > 1000 else 0 for space in timings[3:-1:2]]
+    return [sum(bit << i for i, bit in enumerate(bits[n:n + 8])) for n in range(0, 32, 8)]
+
+
+def decode_rc5(timings):
+    """The 14 bits of an RC-5 frame, from the level in the second half of each bit."""
+    levels = [False]                # the leading space of the first bit is not sent
+    for i, t in enumerate(timings):
+        levels += [i % 2 == 0] * round(t / 889)
+    levels += [False] * (28 - len(levels))
+    return [int(levels[i + 1]) for i in range(0, 28, 2)]
+
+
+class TestIrReceiver(unittest.TestCase):
+    """Test cases for decoding, learning and mapping remote codes."""
+
+    def setUp(self):
+        self.tmp = tempfile.TemporaryDirectory()
+        path = os.path.join(self.tmp.name, "lirc0")
+        os.mkfifo(path)
+        with patch("distiller_cm5_sdk.hardware.ir.receiver.fcntl.ioctl", side_effect=fake_ioctl):
+            self.receiver = IrReceiver(path)
+        self.writer = os.open(path, os.O_WRONLY)
+
+    def tearDown(self):
+        os.close(self.writer)
+        self.receiver.close()
+        self.tmp.cleanup()
+
+    def test_learn_and_map(self):
+        """Learning records one code per button; held buttons repeat after a delay."""
+        up, down, select = (IrCode(IrProtocol.NEC, 0x40BF), IrCode(IrProtocol.RC5, 0x1010),
+                            IrCode(IrProtocol.NEC, 0x40AA))
+        os.write(self.writer, scancode(up.protocol, up.scancode, 1.0) + scancode(up.protocol, up.scancode, 1.1, True)
+                 + scancode(up.protocol, up.scancode, 2.0)        # pressed again while learning DOWN
+                 + scancode(down.protocol, down.scancode, 3.0) + scancode(select.protocol, select.scancode, 4.0))
+        prompts = []
+        keymap = learn_keymap(self.receiver, prompt=prompts.append, timeout=1)
+        self.assertEqual(keymap, {up: Button.UP, down: Button.DOWN, select: Button.SELECT})
+        self.assertIn("already UP", prompts[3])
+
+        with tempfile.NamedTemporaryFile(suffix=".json") as f:
+            save_keymap(keymap, f.name)
+            self.assertEqual(load_keymap(f.name), keymap)
+
+        buttons = IrButtons(self.receiver, keymap)
+        os.write(self.writer, scancode(IrProtocol.NEC, 0x1234, 5.0)   # not mapped
+                 + scancode(down.protocol, down.scancode, 6.0)
+                 + scancode(down.protocol, down.scancode, 6.1, True)
+                 + scancode(down.protocol, down.scancode, 6.6, True))
+        self.assertEqual(buttons.read(timeout=1), Button.DOWN)
+        self.assertEqual(buttons.read(timeout=1), Button.DOWN)      # repeat after 0.5 s
+        self.assertIsNone(buttons.read(timeout=0.05))
+
+        with self.assertRaises(IrError):
+            learn_keymap(self.receiver, [Button.UP], prompt=prompts.append, timeout=0.05)
+
+    def test_encoders(self):
+        """NEC variants and RC-5 encode to the frames the kernel decoders report as the same scancode."""
+        nec = encode(IrCode.parse("nec:0x40bf"))
+        self.assertEqual((len(nec.timings), nec.timings[:2], nec.carrier_hz), (67, [9000, 4500], 38000))
+        self.assertEqual(decode_nec(nec.timings), [0x40, 0xBF, 0xBF, 0x40])
+        self.assertEqual(nec.repeat, [9000, 2250, 560])
+        self.assertEqual(decode_nec(encode(IrCode.parse("necx:0x86b547")).timings), [0x86, 0xB5, 0x47, 0xB8])
+        self.assertEqual(decode_nec(encode(IrCode.parse("nec32:0x12345678")).timings), [0x34, 0x12, 0x78, 0x56])
+
+        rc5 = encode(IrCode.parse("rc5:0x0510"), toggle=True)
+        self.assertEqual((rc5.carrier_hz, len(rc5.timings) % 2), (36000, 1))
+        self.assertEqual(decode_rc5(rc5.timings), [1, 1, 1, 0, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0])
+        self.assertEqual(decode_rc5(encode(IrCode.parse("rc5:0x1f7f")).timings)[:2], [1, 0])   # extended command
+
+        for text in ("nec:0x10000", "rc5:0x2000", "sony12:0x1"):
+            with self.assertRaises(IrError):
+                encode(IrCode.parse(text))
+
+    def test_transmit(self):
+        """Frames reach the device as 32-bit timings; repeats use the repeat frame."""
+        with tempfile.NamedTemporaryFile() as device:
+            with patch("distiller_cm5_sdk.hardware.ir.transmitter.fcntl.ioctl", side_effect=fake_ioctl) as ioctl:
+                tx = IrTransmitter(device.name)
+                tx.send(IrCode.parse("nec:0x40bf"), repeats=1)
+                tx.send(IrSignal(parse_timings("+500 -500 +1000"), carrier_hz=40000))
+                self.assertIn(struct.pack("I", 40000), [call.args[2] for call in ioctl.call_args_list])
+            tx.close()
+            data = Path(device.name).read_bytes()
+        timings = list(struct.unpack(f"{len(data) // 4}I", data))
+        self.assertEqual(timings[67:], [9000, 2250, 560, 500, 500, 1000])
+
+        for timings in ([], [500, 500], [500, 0, 500]):
+            with self.assertRaises(IrError):
+                IrSignal(timings).validate()
+
+    def test_code_parsing(self):
+        """Codes round-trip through their text form; malformed ones are rejected."""
+        code = IrCode(IrProtocol.SONY12, 0x10015)
+        self.assertEqual(str(code), "sony12:0x10015")
+        self.assertEqual(IrCode.parse("SONY12:65557"), code)
+        for text in ("nec", "foo:0x1", "nec:zz"):
+            with self.assertRaises(IrError):
+                IrCode.parse(text)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,380 @@
+#!/usr/bin/env python3
+"""
+IR remote-control receiver for CM5 SDK.
+
+An IR receiver on a GPIO line is handled by the kernel: the gpio-ir overlay
+(dtoverlay=gpio-ir,gpio_pin=N) registers an rc-core device whose protocol
+decoders turn pulses into scancodes. IrReceiver enables the decoders for
+common remote protocols and reads the decoded scancodes from the device's
+LIRC node, so no lircd setup is needed. IrButtons maps scancodes to the
+SDK's buttons, making any remote an input source like the GPIO buttons or a
+keyboard.
+
+Remotes differ, so the scancode of each button is learned once:
+
+    receiver = IrReceiver()
+    keymap = learn_keymap(receiver)     # press UP, DOWN, SELECT on the remote
+    save_keymap(keymap)
+    buttons = IrButtons(receiver, keymap)
+"""
+
+import errno
+import fcntl
+import glob
+import json
+import os
+import select
+import struct
+import time
+from dataclasses import dataclass
+from enum import Enum
+from typing import Callable, Dict, Iterable, List, Optional
+
+from ...recovery.buttons import Button, ButtonSource
+
+
+RC_SYSFS = "/sys/class/rc"
+TX_DRIVERS = ("gpio-ir-tx", "pwm-ir-tx")     # transmit-only rc-core drivers
+KEYMAP_PATH = "/var/lib/distiller-cm5-sdk/ir-keymap.json"
+
+# rc-core decoder names (/sys/class/rc/rcN/protocols) enabled by default
+DEFAULT_PROTOCOLS = ("nec", "rc-5", "rc-6", "sony", "jvc", "sanyo", "sharp")
+
+REPEAT_DELAY_S = 0.5            # hold time before a held button starts repeating
+
+_SCANCODE = struct.Struct("QHHIQ")  # struct lirc_scancode: timestamp, flags, rc_proto, keycode, scancode
+_FLAG_TOGGLE = 1
+_FLAG_REPEAT = 2
+_LIRC_GET_FEATURES = 0x80046900
+_LIRC_SET_REC_MODE = 0x40046912
+_LIRC_MODE_SCANCODE = 0x8
+_LIRC_CAN_REC_SCANCODE = _LIRC_MODE_SCANCODE << 16
+
+
+class IrError(Exception):
+    """Custom exception for IR receiver errors."""
+    pass
+
+
+class IrProtocol(Enum):
+    """Remote protocols as numbered by the kernel (enum rc_proto)."""
+    UNKNOWN = 0
+    OTHER = 1
+    RC5 = 2
+    RC5X_20 = 3
+    RC5_SZ = 4
+    JVC = 5
+    SONY12 = 6
+    SONY15 = 7
+    SONY20 = 8
+    NEC = 9
+    NECX = 10
+    NEC32 = 11
+    SANYO = 12
+    MCIR2_KBD = 13
+    MCIR2_MSE = 14
+    RC6_0 = 15
+    RC6_6A_20 = 16
+    RC6_6A_24 = 17
+    RC6_6A_32 = 18
+    RC6_MCE = 19
+    SHARP = 20
+    XMP = 21
+    CEC = 22
+    IMON = 23
+    RCMM12 = 24
+    RCMM24 = 25
+    RCMM32 = 26
+    XBOX_DVD = 27
+
+
+@dataclass(frozen=True)
+class IrCode:
+    """A button of a remote: protocol and scancode."""
+    protocol: IrProtocol
+    scancode: int
+
+    def __str__(self) -> str:
+        return f"{self.protocol.name.lower()}:{self.scancode:#x}"
+
+    @classmethod
+    def parse(cls, text: str) -> "IrCode":
+        """
+        Parse "protocol:scancode", e.g. "nec:0x40bf".
+
+        Raises:
+            IrError: If the text is malformed
+        """
+        name, _, scancode = text.strip().partition(":")
+        try:
+            return cls(IrProtocol[name.upper()], int(scancode, 0))
+        except (KeyError, ValueError):
+            raise IrError(f"Invalid IR code '{text}' (expected protocol:scancode, e.g. nec:0x40bf)")
+
+
+@dataclass
+class IrEvent:
+    """One decoded IR frame."""
+    code: IrCode
+    repeat: bool            # the button is being held (repeat frame)
+    toggle: bool            # toggle bit (RC5/RC6), flips on every new press
+    timestamp: float        # seconds, CLOCK_MONOTONIC
+
+
+def find_receivers() -> List[str]:
+    """rc-core devices with a LIRC node, gpio-ir receivers first, e.g. ["/sys/class/rc/rc0"]."""
+    found = []
+    for rc_dir in sorted(glob.glob(os.path.join(RC_SYSFS, "rc*"))):
+        if not glob.glob(os.path.join(rc_dir, "lirc*")):
+            continue  # keyboard-style device
+        driver = os.path.basename(os.path.realpath(os.path.join(rc_dir, "device", "driver")))
+        if driver in TX_DRIVERS:
+            continue
+        found.append((driver != "gpio_ir_recv", rc_dir))
+    return [rc_dir for _, rc_dir in sorted(found)]
+
+
+def enable_protocols(rc_dir: str, protocols: Iterable[str] = DEFAULT_PROTOCOLS) -> List[str]:
+    """
+    Enable kernel decoders on an rc-core device. Decoders the kernel lacks are skipped.
+
+    Returns:
+        The protocols that were enabled
+
+    Raises:
+        IrError: If the protocols file cannot be written (usually needs root)
+    """
+    path = os.path.join(rc_dir, "protocols")
+    try:
+        with open(path) as f:
+            available = {name.strip("[]") for name in f.read().split()}
+    except OSError as e:
+        raise IrError(f"Failed to read {path}: {e}")
+    enabled = [name for name in protocols if name in available]
+    try:
+        for name in enabled:
+            with open(path, "w") as f:
+                f.write(f"+{name}\n")
+    except OSError as e:
+        raise IrError(f"Failed to enable IR protocols in {path}: {e}")
+    return enabled
+
+
+class IrReceiver:
+    """Decoded scancodes from a kernel IR receiver."""
+
+    def __init__(self, device: Optional[str] = None, protocols: Optional[Iterable[str]] = DEFAULT_PROTOCOLS):
+        """
+        Open the receiver.
+
+        Args:
+            device: LIRC device, e.g. /dev/lirc0; None uses the first gpio-ir receiver
+            protocols: Decoders to enable on the device, or None to leave them as
+                       configured (e.g. by ir-keytable)
+
+        Raises:
+            IrError: If there is no receiver or it cannot be opened
+        """
+        if device is None:
+            receivers = find_receivers()
+            if not receivers:
+                raise IrError("No IR receiver found (is dtoverlay=gpio-ir enabled?)")
+            rc_dir = receivers[0]
+            device = os.path.join("/dev", os.path.basename(sorted(glob.glob(os.path.join(rc_dir, "lirc*")))[0]))
+            if protocols is not None:
+                try:
+                    enable_protocols(rc_dir, protocols)
+                except IrError as e:
+                    print(f"Warning: {e}")
+        self.device = device
+
+        try:
+            self._fd = os.open(device, os.O_RDONLY | os.O_NONBLOCK)
+        except OSError as e:
+            raise IrError(f"Failed to open IR receiver {device}: {e}")
+        try:
+            features = struct.unpack("I", fcntl.ioctl(self._fd, _LIRC_GET_FEATURES, b"\0" * 4))[0]
+            if not features & _LIRC_CAN_REC_SCANCODE:
+                raise IrError(f"{device} cannot report scancodes (kernel without rc-core decoders?)")
+            fcntl.ioctl(self._fd, _LIRC_SET_REC_MODE, struct.pack("I", _LIRC_MODE_SCANCODE))
+        except OSError as e:
+            self.close()
+            raise IrError(f"{device} is not a LIRC receiver: {e}")
+        except IrError:
+            self.close()
+            raise
+        self._pending: List[IrEvent] = []
+
+    def read(self, timeout: Optional[float] = None) -> Optional[IrEvent]:
+        """
+        Wait for the next decoded frame.
+
+        Args:
+            timeout: Seconds to wait, or None to wait indefinitely
+
+        Returns:
+            The event, or None on timeout
+
+        Raises:
+            IrError: If the receiver is gone
+        """
+        deadline = None if timeout is None else time.monotonic() + timeout
+        while not self._pending:
+            wait_s = None if deadline is None else max(0.0, deadline - time.monotonic())
+            ready, _, _ = select.select([self._fd], [], [], wait_s)
+            if ready:
+                try:
+                    data = os.read(self._fd, _SCANCODE.size * 16)
+                except BlockingIOError:
+                    data = b""
+                except OSError as e:
+                    if e.errno == errno.ENODEV:
+                        raise IrError(f"IR receiver {self.device} was removed")
+                    raise IrError(f"Failed to read {self.device}: {e}")
+                for offset in range(0, len(data) - _SCANCODE.size + 1, _SCANCODE.size):
+                    timestamp, flags, proto, _, scancode = _SCANCODE.unpack_from(data, offset)
+                    try:
+                        protocol = IrProtocol(proto)
+                    except ValueError:
+                        protocol = IrProtocol.OTHER
+                    self._pending.append(IrEvent(IrCode(protocol, scancode), bool(flags & _FLAG_REPEAT),
+                                                 bool(flags & _FLAG_TOGGLE), timestamp / 1e9))
+            if not self._pending and deadline is not None and time.monotonic() >= deadline:
+                return None
+        return self._pending.pop(0)
+
+    def learn(self, timeout: Optional[float] = None) -> Optional[IrCode]:
+        """
+        Wait for a button press and return its code (learning mode).
+
+        Repeat frames of a held button are skipped.
+
+        Returns:
+            The code, or None on timeout
+        """
+        deadline = None if timeout is None else time.monotonic() + timeout
+        while True:
+            wait_s = None if deadline is None else max(0.0, deadline - time.monotonic())
+            event = self.read(wait_s)
+            if event is None:
+                return None
+            if not event.repeat:
+                return event.code
+
+    def close(self) -> None:
+        if self._fd is not None:
+            os.close(self._fd)
+            self._fd = None
+
+
+class IrButtons(ButtonSource):
+    """Buttons from an IR remote, using a learned key map."""
+
+    def __init__(self, receiver: IrReceiver, keymap: Dict[IrCode, Button], repeat: bool = True):
+        """
+        Args:
+            receiver: Open IR receiver, closed by close()
+            keymap: Remote code to button (see learn_keymap() and load_keymap())
+            repeat: Held buttons repeat after REPEAT_DELAY_S, like a keyboard;
+                    False reports each press once
+        """
+        self.receiver = receiver
+        self._keymap = dict(keymap)
+        self._repeat = repeat
+        self._pressed_at = 0.0
+
+    def read(self, timeout: Optional[float] = None) -> Optional[Button]:
+        deadline = None if timeout is None else time.monotonic() + timeout
+        while True:
+            wait_s = None if deadline is None else max(0.0, deadline - time.monotonic())
+            event = self.receiver.read(wait_s)
+            if event is None:
+                return None
+            button = self._keymap.get(event.code)
+            if button is None:
+                continue
+            if not event.repeat:
+                self._pressed_at = event.timestamp
+                return button
+            if self._repeat and event.timestamp - self._pressed_at >= REPEAT_DELAY_S:
+                return button
+
+    def close(self) -> None:
+        self.receiver.close()
+
+
+def learn_keymap(receiver: IrReceiver, buttons: Iterable[Button] = tuple(Button),
+                 prompt: Callable[[str], None] = print, timeout: float = 30.0) -> Dict[IrCode, Button]:
+    """
+    Interactively learn a remote: prompt for each button and record its code.
+
+    Args:
+        receiver: Open IR receiver
+        buttons: Buttons to learn, in order
+        prompt: Shows an instruction to the user
+        timeout: Seconds to wait for each press
+
+    Returns:
+        Mapping of remote code to button
+
+    Raises:
+        IrError: If a press does not arrive in time
+    """
+    keymap: Dict[IrCode, Button] = {}
+    for button in buttons:
+        prompt(f"Press {button.value.upper()} on the remote")
+        while True:
+            code = receiver.learn(timeout)
+            if code is None:
+                raise IrError(f"No IR code received for {button.value.upper()} within {timeout:g} s")
+            if code in keymap:
+                prompt(f"{code} is already {keymap[code].value.upper()}; press another button")
+                continue
+            keymap[code] = button
+            prompt(f"{button.value.upper()} = {code}")
+            break
+    return keymap
+
+
+def save_keymap(keymap: Dict[IrCode, Button], path: str = KEYMAP_PATH) -> None:
+    """
+    Store a key map as JSON, e.g. {"up": ["nec:0x40bf"], ...}.
+
+    Raises:
+        IrError: If the file cannot be written
+    """
+    data: Dict[str, List[str]] = {button.value: [] for button in Button}
+    for code, button in keymap.items():
+        data[button.value].append(str(code))
+    try:
+        os.makedirs(os.path.dirname(path) or ".", exist_ok=True)
+        with open(path, "w") as f:
+            json.dump(data, f, indent=2)
+            f.write("\n")
+    except OSError as e:
+        raise IrError(f"Failed to write IR key map {path}: {e}")
+
+
+def load_keymap(path: str = KEYMAP_PATH) -> Dict[IrCode, Button]:
+    """
+    Load a key map stored by save_keymap().
+
+    Raises:
+        IrError: If the file is missing or malformed
+    """
+    try:
+        with open(path) as f:
+            data = json.load(f)
+    except (OSError, ValueError) as e:
+        raise IrError(f"Failed to read IR key map {path}: {e}")
+    if not isinstance(data, dict):
+        raise IrError(f"IR key map {path} must be a JSON object")
+    keymap = {}
+    for name, codes in data.items():
+        try:
+            button = Button(name)
+        except ValueError:
+            raise IrError(f"Unknown button '{name}' in IR key map {path}")
+        for code in codes if isinstance(codes, list) else [codes]:
+            keymap[IrCode.parse(str(code))] = button
+    return keymap
@@ -0,0 +1,264 @@
+#!/usr/bin/env python3
+"""
+IR blaster (transmitter) for CM5 SDK.
+
+An IR LED on a GPIO line is driven by the kernel's gpio-ir-tx overlay
+(dtoverlay=gpio-ir-tx,gpio_pin=N), which modulates pulse/space timings
+written to its LIRC node onto the carrier. IrTransmitter encodes NEC and
+RC-5 codes in the same protocol:scancode form IrReceiver reports, so a
+button learned from a TV remote can be sent straight back, and sends raw
+timings for anything else, e.g. air conditioners whose frames carry the
+whole device state:
+
+    tx = IrTransmitter()
+    tx.send(IrCode.parse("nec:0x40bf"))
+    tx.send(IrSignal([9000, 4500, 560, ...], carrier_hz=38000))
+"""
+
+import fcntl
+import glob
+import os
+import struct
+import time
+from dataclasses import dataclass
+from typing import List, Optional, Union
+
+from .receiver import RC_SYSFS, TX_DRIVERS, IrCode, IrError, IrProtocol
+
+
+# NEC: 9 ms + 4.5 ms header, 32 bits LSB first as 560 us pulse + 560/1690 us space
+NEC_CARRIER_HZ = 38000
+NEC_HEADER_PULSE_US = 9000
+NEC_HEADER_SPACE_US = 4500
+NEC_BIT_PULSE_US = 560
+NEC_ZERO_SPACE_US = 560
+NEC_ONE_SPACE_US = 1690
+NEC_REPEAT_SPACE_US = 2250      # repeat frame: header pulse, this space, one bit pulse
+NEC_PERIOD_US = 108000
+
+# RC-5: 14 Manchester-coded bits of 1.778 ms, MSB first
+RC5_CARRIER_HZ = 36000
+RC5_HALF_BIT_US = 889
+RC5_PERIOD_US = 113778
+
+DEFAULT_GAP_US = 40000          # space after a raw frame before it is repeated
+MAX_TIMINGS = 1024              # LIRCBUF_SIZE: most timings the kernel takes in one write
+
+_LIRC_GET_FEATURES = 0x80046900
+_LIRC_SET_SEND_CARRIER = 0x40046913
+_LIRC_SET_SEND_DUTY_CYCLE = 0x40046915
+_LIRC_CAN_SEND_PULSE = 0x2
+_LIRC_CAN_SET_SEND_CARRIER = 0x100
+_LIRC_CAN_SET_SEND_DUTY_CYCLE = 0x200
+
+
+@dataclass
+class IrSignal:
+    """A frame to transmit as raw timings."""
+    timings: List[int]                  # microseconds, alternating pulse and space, pulse first and last
+    carrier_hz: int = 38000
+    duty_cycle: Optional[int] = None    # percent; None leaves the driver default
+    repeat: Optional[List[int]] = None  # frame sent while held, if not the full frame again
+    period_us: int = 0                  # frame start to next frame start; 0 adds DEFAULT_GAP_US
+
+    def validate(self) -> None:
+        """
+        Raises:
+            IrError: If the timings cannot be sent
+        """
+        for timings in (self.timings, self.repeat):
+            if timings is None:
+                continue
+            if not timings or len(timings) % 2 == 0:
+                raise IrError(f"IR timings must start and end with a pulse (odd count), got {len(timings)}")
+            if len(timings) > MAX_TIMINGS:
+                raise IrError(f"At most {MAX_TIMINGS} IR timings can be sent at once, got {len(timings)}")
+            if any(t <= 0 for t in timings):
+                raise IrError("IR timings must be positive microseconds")
+        if self.carrier_hz <= 0:
+            raise IrError(f"Invalid IR carrier frequency {self.carrier_hz}")
+        if self.duty_cycle is not None and not 0 < self.duty_cycle < 100:
+            raise IrError(f"Invalid IR duty cycle {self.duty_cycle}%")
+
+
+def encode_nec(code: IrCode) -> IrSignal:
+    """
+    Encode an NEC, NECX or NEC32 scancode, as the kernel's NEC decoder reports them.
+
+    Raises:
+        IrError: If the scancode is out of range for the protocol
+    """
+    scancode = code.scancode
+    if code.protocol == IrProtocol.NEC and scancode <= 0xFFFF:
+        address, command = scancode >> 8, scancode & 0xFF
+        data = [address, address ^ 0xFF, command, command ^ 0xFF]
+    elif code.protocol == IrProtocol.NECX and scancode <= 0xFFFFFF:
+        data = [scancode >> 16, (scancode >> 8) & 0xFF, scancode & 0xFF, (scancode & 0xFF) ^ 0xFF]
+    elif code.protocol == IrProtocol.NEC32 and scancode <= 0xFFFFFFFF:
+        data = [(scancode >> 16) & 0xFF, scancode >> 24, scancode & 0xFF, (scancode >> 8) & 0xFF]
+    else:
+        raise IrError(f"Cannot encode {code} as NEC")
+
+    timings = [NEC_HEADER_PULSE_US, NEC_HEADER_SPACE_US]
+    for byte in data:
+        for bit in range(8):
+            timings += [NEC_BIT_PULSE_US, NEC_ONE_SPACE_US if byte >> bit & 1 else NEC_ZERO_SPACE_US]
+    timings.append(NEC_BIT_PULSE_US)
+    return IrSignal(timings, NEC_CARRIER_HZ, repeat=[NEC_HEADER_PULSE_US, NEC_REPEAT_SPACE_US, NEC_BIT_PULSE_US],
+                    period_us=NEC_PERIOD_US)
+
+
+def encode_rc5(code: IrCode, toggle: bool = False) -> IrSignal:
+    """
+    Encode an RC-5 scancode (address << 8 | command, commands above 63 as RC-5 extended).
+
+    Args:
+        code: Code with protocol RC5
+        toggle: Toggle bit, flipped by the sender for each new press
+
+    Raises:
+        IrError: If the scancode is out of range
+    """
+    address, command = code.scancode >> 8, code.scancode & 0xFF
+    if code.protocol != IrProtocol.RC5 or address > 0x1F or command > 0x7F:
+        raise IrError(f"Cannot encode {code} as RC-5")
+    bits = [1, 0 if command & 0x40 else 1, int(toggle)]
+    bits += [address >> i & 1 for i in range(4, -1, -1)]
+    bits += [command >> i & 1 for i in range(5, -1, -1)]
+
+    # A one is a space then a pulse, a zero a pulse then a space
+    levels = [level for bit in bits for level in ((False, True) if bit else (True, False))]
+    while not levels[0]:
+        levels.pop(0)
+    while not levels[-1]:
+        levels.pop()
+    timings = []
+    for i, level in enumerate(levels):
+        if i and level == levels[i - 1]:
+            timings[-1] += RC5_HALF_BIT_US
+        else:
+            timings.append(RC5_HALF_BIT_US)
+    return IrSignal(timings, RC5_CARRIER_HZ, period_us=RC5_PERIOD_US)
+
+
+def encode(code: IrCode, toggle: bool = False) -> IrSignal:
+    """
+    Encode a code for transmission.
+
+    Raises:
+        IrError: If the protocol has no encoder; send its raw timings instead
+    """
+    if code.protocol in (IrProtocol.NEC, IrProtocol.NECX, IrProtocol.NEC32):
+        return encode_nec(code)
+    if code.protocol == IrProtocol.RC5:
+        return encode_rc5(code, toggle)
+    raise IrError(f"No encoder for {code.protocol.name} codes; send raw timings with an IrSignal")
+
+
+def parse_timings(text: str) -> List[int]:
+    """
+    Parse raw timings as written by mode2 or copied from IR databases:
+    "+9000 -4500 +560 ...", "9000 4500 560" or "9000,4500,560".
+
+    Raises:
+        IrError: If the text is malformed
+    """
+    try:
+        return [abs(int(value)) for value in text.replace(",", " ").split()]
+    except ValueError:
+        raise IrError(f"Invalid IR timings '{text}'")
+
+
+def find_transmitters() -> List[str]:
+    """rc-core devices of IR transmitters, e.g. ["/sys/class/rc/rc1"]."""
+    found = []
+    for rc_dir in sorted(glob.glob(os.path.join(RC_SYSFS, "rc*"))):
+        driver = os.path.basename(os.path.realpath(os.path.join(rc_dir, "device", "driver")))
+        if driver in TX_DRIVERS and glob.glob(os.path.join(rc_dir, "lirc*")):
+            found.append(rc_dir)
+    return found
+
+
+class IrTransmitter:
+    """Sends IR frames through a kernel IR transmitter."""
+
+    def __init__(self, device: Optional[str] = None):
+        """
+        Open the transmitter.
+
+        Args:
+            device: LIRC device, e.g. /dev/lirc1; None uses the first gpio-ir-tx
+                    or pwm-ir-tx transmitter
+
+        Raises:
+            IrError: If there is no transmitter or it cannot be opened
+        """
+        if device is None:
+            transmitters = find_transmitters()
+            if not transmitters:
+                raise IrError("No IR transmitter found (is dtoverlay=gpio-ir-tx enabled?)")
+            device = os.path.join("/dev", os.path.basename(sorted(glob.glob(os.path.join(transmitters[0],
+                                                                                         "lirc*")))[0]))
+        self.device = device
+        self._toggle = False
+
+        try:
+            self._fd = os.open(device, os.O_WRONLY)
+        except OSError as e:
+            raise IrError(f"Failed to open IR transmitter {device}: {e}")
+        try:
+            self._features = struct.unpack("I", fcntl.ioctl(self._fd, _LIRC_GET_FEATURES, b"\0" * 4))[0]
+        except OSError as e:
+            self.close()
+            raise IrError(f"{device} is not a LIRC device: {e}")
+        if not self._features & _LIRC_CAN_SEND_PULSE:
+            self.close()
+            raise IrError(f"{device} cannot transmit")
+
+    def _configure(self, signal: IrSignal) -> None:
+        try:
+            if self._features & _LIRC_CAN_SET_SEND_CARRIER:
+                fcntl.ioctl(self._fd, _LIRC_SET_SEND_CARRIER, struct.pack("I", signal.carrier_hz))
+            if signal.duty_cycle is not None and self._features & _LIRC_CAN_SET_SEND_DUTY_CYCLE:
+                fcntl.ioctl(self._fd, _LIRC_SET_SEND_DUTY_CYCLE, struct.pack("I", signal.duty_cycle))
+        except OSError as e:
+            raise IrError(f"{self.device} rejected carrier {signal.carrier_hz} Hz: {e}")
+
+    def _write(self, timings: List[int]) -> None:
+        try:
+            os.write(self._fd, struct.pack(f"{len(timings)}I", *timings))
+        except OSError as e:
+            raise IrError(f"Failed to transmit on {self.device}: {e}")
+
+    def send(self, signal: Union[IrCode, IrSignal], repeats: int = 0) -> None:
+        """
+        Transmit a code or raw signal, blocking until it has been sent.
+
+        Args:
+            signal: Code to encode (NEC or RC-5), or raw timings
+            repeats: Extra frames sent as if the button were held
+
+        Raises:
+            IrError: If the signal cannot be encoded or sent
+        """
+        if isinstance(signal, IrCode):
+            self._toggle = not self._toggle
+            signal = encode(signal, self._toggle)
+        signal.validate()
+        self._configure(signal)
+
+        frame = signal.timings
+        for i in range(repeats + 1):
+            start = time.monotonic()
+            self._write(frame)
+            if i == repeats:
+                break
+            elapsed_us = (time.monotonic() - start) * 1e6
+            gap_us = signal.period_us - elapsed_us if signal.period_us else DEFAULT_GAP_US
+            time.sleep(max(0.0, gap_us) / 1e6)
+            frame = signal.repeat or signal.timings
+
+    def close(self) -> None:
+        if self._fd is not None:
+            os.close(self._fd)
+            self._fd = None
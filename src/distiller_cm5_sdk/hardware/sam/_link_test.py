@@ -0,0 +1,174 @@
+#!/usr/bin/env python3
+"""
+SAM link supervision unit tests for CM5 SDK.
+"""
+
+import json
+import sys
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.device.power_state import PowerState, PowerStateMachine
+from distiller_cm5_sdk.hardware.sam.led import LED
+from distiller_cm5_sdk.hardware.sam.link import LinkState, SamLink, SamLinkError
+from distiller_cm5_sdk.hardware.sam.panel_power import PanelPowerRail
+from distiller_cm5_sdk.hardware.sam.protocol import PROTOCOL_VERSION, NeoPixel
+
+
+class FakeChannel:
+    """In-memory stand-in for the UART that answers heartbeats while alive."""
+
+    def __init__(self):
+        self.is_open = False
+        self.alive = True           # MCU answers heartbeats
+        self.openable = True
+        self.opens = 0
+        self.written = []
+        self.incoming = []
+        self.hello = None           # Hello reply, or None for firmware without negotiation
+        self.rail_stuck = False     # PanelPower answered with the rail unchanged
+
+    def open(self):
+        if not self.openable:
+            raise OSError("no such device")
+        self.is_open = True
+        self.opens += 1
+
+    def close(self):
+        self.is_open = False
+
+    def write_line(self, line):
+        message = json.loads(line)
+        self.written.append(message)
+        if self.alive and message.get("Function") == "Heartbeat":
+            self.incoming.append(json.dumps({"Function": "Heartbeat", "seq": message["seq"]}))
+        if self.hello is not None and message.get("Function") == "Hello":
+            self.incoming.append(json.dumps(self.hello))
+        if self.alive and message.get("Function") == "PanelPower":
+            on = message["on"] if not self.rail_stuck else not message["on"]
+            self.incoming.append(json.dumps({"Function": "PanelPower", "on": on}))
+
+    def read_lines(self, timeout):
+        lines, self.incoming = self.incoming, []
+        return lines
+
+
+class TestSamLink(unittest.TestCase):
+    """Test cases for heartbeats, loss and recovery."""
+
+    def setUp(self):
+        self.now = 0.0
+        self.channel = FakeChannel()
+        self.link = SamLink(self.channel, interval_s=1.0, missed=3, backoff_min_s=1.0,
+                            backoff_max_s=4.0, clock=lambda: self.now)
+        self.events = []
+        self.link.add_listener(self.events.append)
+
+    def advance(self, seconds):
+        """Poll once per second of fake time."""
+        for _ in range(int(seconds)):
+            self.now += 1.0
+            self.link.poll()
+
+    def test_loss_and_recovery(self):
+        """Missed heartbeats mark the link lost; reopening with backoff recovers it."""
+        self.link.poll()
+        self.assertEqual(self.link.state, LinkState.UP)
+
+        self.channel.alive = False
+        self.advance(3)
+        self.assertEqual(self.link.state, LinkState.UP)
+        self.advance(1)
+        self.assertEqual(self.link.state, LinkState.LOST)
+        self.assertEqual(self.events[-1].reason, "timeout")
+        self.assertFalse(self.channel.is_open)
+        self.assertFalse(self.link.send(NeoPixel()))
+
+        # The device is gone: reopen attempts back off 1, 2, 4, 4 s
+        self.channel.openable = False
+        attempts = []
+        with patch.object(self.channel, "open", side_effect=lambda: attempts.append(self.now) or
+                          FakeChannel.open(self.channel)):
+            self.advance(12)
+        self.assertEqual([b - a for a, b in zip(attempts, attempts[1:])], [2.0, 4.0, 4.0])
+
+        self.channel.openable = True
+        self.channel.alive = True
+        self.advance(4)
+        self.assertEqual(self.link.state, LinkState.UP)
+        self.assertEqual(self.events[-1].reason, "recovered")
+        self.assertTrue(self.link.send(NeoPixel()))
+
+    def test_silent_firmware_stays_unknown(self):
+        """Firmware without heartbeats never degrades the link."""
+        self.channel.alive = False
+        self.advance(10)
+        self.assertEqual(self.link.state, LinkState.UNKNOWN)
+        self.assertEqual(self.events, [])
+        self.assertTrue(self.link.send(NeoPixel()))
+
+    def test_degraded_modules(self):
+        """The LED drops commands and suspend is vetoed while the link is lost."""
+        with patch("distiller_cm5_sdk.hardware.sam.led.os.path.exists", return_value=True), \
+             patch("distiller_cm5_sdk.hardware.sam.led.os.access", return_value=True):
+            led = LED(link=self.link)
+        machine = PowerStateMachine(None, dim_after=None, sleep_after=None, suspend_command=None)
+        self.link.attach_power_state(machine)
+
+        self.link.poll()
+        self.assertTrue(led.set_led_color(255, 0, 0))
+        self.assertEqual(self.channel.written[-1]["Function"], "NeoPixel")
+
+        self.channel.alive = False
+        self.advance(4)
+        self.assertTrue(self.link.is_lost)
+        written = len(self.channel.written)
+        self.assertFalse(led.set_led_color(0, 255, 0))
+        self.assertEqual(len(self.channel.written), written)
+        self.assertFalse(machine.request(PowerState.SUSPEND))
+        self.assertTrue(machine.request(PowerState.PANEL_SLEEP))
+
+    def test_protocol_negotiation(self):
+        """A Hello reply sets the protocol version; other messages reach listeners typed."""
+        self.channel.hello = {"Function": "Hello", "version": 7, "min_version": 1, "firmware": "2.1.0"}
+        received = []
+        self.link.add_message_listener(received.append)
+        self.link.poll()
+        self.assertEqual(self.channel.written[0]["Function"], "Hello")
+        self.assertEqual(self.link.protocol_version, PROTOCOL_VERSION)
+        self.assertEqual(self.link.firmware, "2.1.0")
+
+        self.channel.incoming = ['{"Function": "Battery", "percent": 80}', "boot: ok"]
+        self.link.poll()
+        self.assertEqual([(m.function, m.extra) for m in received], [("Battery", {"percent": 80})])
+
+    def test_panel_power(self):
+        """The panel rail switches are confirmed by the MCU; silence, a stuck rail and old firmware raise."""
+        self.channel.hello = {"Function": "Hello", "version": PROTOCOL_VERSION, "min_version": 1}
+        self.link.poll()
+        sleeps = []
+        rail = PanelPowerRail(self.link, timeout_s=0.2, sleep=sleeps.append)
+        rail.cycle(off_s=0.3)
+        self.assertEqual([m["on"] for m in self.channel.written if m["Function"] == "PanelPower"], [False, True])
+        self.assertTrue(rail.is_on)
+        self.assertEqual(sleeps, [0.3, rail.settle_s])
+
+        self.channel.rail_stuck = True
+        with self.assertRaisesRegex(SamLinkError, "left the panel power on"):
+            rail.off()
+        self.channel.rail_stuck = False
+        self.channel.alive = False
+        with self.assertRaisesRegex(SamLinkError, "did not confirm"):
+            rail.off()
+
+        self.link.protocol_version = 1
+        with patch("builtins.print"), self.assertRaises(SamLinkError):
+            rail.on()
+
+
+if __name__ == "__main__":
+    unittest.main()
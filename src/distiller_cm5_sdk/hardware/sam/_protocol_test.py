@@ -0,0 +1,53 @@
+#!/usr/bin/env python3
+"""
+SAM message protocol unit tests for CM5 SDK.
+"""
+
+import json
+import sys
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.sam.protocol import (
+    PROTOCOL_VERSION, Heartbeat, Hello, LedStep, NeoPixel, ProtocolError, UnknownMessage,
+    decode, encode, negotiate)
+
+
+class TestProtocol(unittest.TestCase):
+    """Test cases for encoding, decoding and version negotiation."""
+
+    def test_round_trip(self):
+        """Typed messages encode to the firmware's JSON and decode back."""
+        message = NeoPixel([LedStep(255, 0, 0, 0.5, 1.0), LedStep(0, 0, 0)])
+        self.assertEqual(json.loads(encode(message)),
+                         {"Function": "NeoPixel", "colors": {"0": [255, 0, 0, 0.5, 1.0], "1": [0, 0, 0, 0.5, 0.0]}})
+        self.assertEqual(decode(encode(message)), message)
+        self.assertEqual(decode('{"Function": "Heartbeat", "seq": 4}'), Heartbeat(4))
+        self.assertNotIn("firmware", json.loads(encode(Hello())))
+
+    def test_forward_compatibility(self):
+        """Unknown messages and fields are kept; malformed lines are rejected."""
+        unknown = decode('{"Function": "Thermal", "celsius": 41.5}')
+        self.assertIsInstance(unknown, UnknownMessage)
+        self.assertEqual(json.loads(encode(unknown)), {"Function": "Thermal", "celsius": 41.5})
+
+        beat = decode('{"Function": "Heartbeat", "seq": 9, "uptime_ms": 1234}')
+        self.assertEqual((beat.seq, beat.extra), (9, {"uptime_ms": 1234}))
+
+        for line in ("not json", "[1, 2]", '{"seq": 1}', '{"Function": "NeoPixel"}'):
+            with self.assertRaises(ProtocolError):
+                decode(line)
+
+    def test_negotiate(self):
+        """The newest common version wins; disjoint ranges fail."""
+        self.assertEqual(negotiate(Hello(version=PROTOCOL_VERSION + 3, min_version=1)), PROTOCOL_VERSION)
+        self.assertEqual(negotiate(Hello(version=1, min_version=1)), 1)
+        with self.assertRaises(ProtocolError):
+            negotiate(Hello(version=PROTOCOL_VERSION + 3, min_version=PROTOCOL_VERSION + 1))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -15,15 +15,21 @@ class LED:
     it does not track command completion.
     """
 
-    def __init__(self):
+    def __init__(self, link=None):
         """
         Initialize the LED module interface.
 
+        Args:
+            link: Optional supervised SamLink. Commands then go through the
+                  link, and are dropped (returning False) while it is lost.
+
         Raises:
             RuntimeError: If the device /dev/pamir-uart does not exist
                           or is not writable.
         """
         self._device_path = "/dev/pamir-uart"
+        self._link = link
+        self._degraded_warned = False
 
         # Check if the required device exists and is writable
         if not os.path.exists(self._device_path):
@@ -119,6 +125,17 @@ class LED:
             "colors": sequence
         }
         # Ensure command ends with a newline for the driver
+        if self._link is not None:
+            if self._link.is_lost:
+                # Degraded mode: skip the dead UART instead of blocking on it
+                if not self._degraded_warned:
+                    print("Warning: SAM link lost; LED commands are dropped until it recovers")
+                    self._degraded_warned = True
+                return False
+            self._degraded_warned = False
+            # Imported here so led.py still runs standalone (see _led_unit_test.py)
+            from .protocol import decode
+            return self._link.send(decode(command))
         cmd_string = "\n" + json.dumps(command) + "\n"
         print(f"Writing command: {cmd_string}")
         # Write the command to the device file
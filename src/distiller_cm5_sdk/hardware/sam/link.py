@@ -0,0 +1,366 @@
+#!/usr/bin/env python3
+"""
+SAM MCU link supervision for CM5 SDK.
+
+The RP2040 ("SAM") drives the LED and wakes the CM5 from suspend, and is
+reached by JSON lines over /dev/pamir-uart. SamLink sends a heartbeat every
+few seconds; once the MCU has answered one, missing several in a row marks
+the link LOST. Listeners are told about every change, modules holding the
+link switch to a degraded mode (the LED drops commands instead of writing
+to a dead UART, suspend is vetoed because nothing could wake the CM5), and
+the link is reopened with exponential backoff until the MCU answers again.
+
+Firmware that never answers heartbeats leaves the link UNKNOWN, in which
+modules behave as they did before supervision existed.
+
+Each time the device is opened the link also sends a Hello to negotiate
+the protocol version (see protocol.py).
+"""
+
+import os
+import select
+import threading
+import time
+from dataclasses import dataclass
+from enum import Enum
+from typing import Callable, List, Optional
+
+from ...diagnostics.audit import AuditCategory, audit
+from ...diagnostics.log_levels import get_logger
+from .protocol import (LEGACY_VERSION, Heartbeat, Hello, ProtocolError, SamMessage,
+                       decode, encode, negotiate)
+
+
+DEVICE_PATH = "/dev/pamir-uart"
+
+HEARTBEAT_INTERVAL_S = 2.0
+MISSED_HEARTBEATS = 3           # consecutive unanswered heartbeats before the link is LOST
+BACKOFF_MIN_S = 1.0             # first reconnect delay, doubled after each failure
+BACKOFF_MAX_S = 30.0
+REQUEST_TIMEOUT_S = 1.0         # default wait for the reply to request()
+
+
+class SamLinkError(Exception):
+    """Custom exception for SAM link errors."""
+    pass
+
+
+class LinkState(Enum):
+    """Health of the UART link to the SAM MCU."""
+    UNKNOWN = "unknown"     # no heartbeat answered yet (or firmware without heartbeats)
+    UP = "up"
+    LOST = "lost"
+
+
+@dataclass
+class LinkEvent:
+    """A change of link state."""
+    previous: LinkState
+    state: LinkState
+    reason: str             # "heartbeat", "timeout", "write_failed", "recovered", ...
+    timestamp: float        # seconds since the epoch
+
+
+class UartChannel:
+    """Line-oriented access to the SAM UART device."""
+
+    def __init__(self, path: str = DEVICE_PATH):
+        self.path = path
+        self._fd: Optional[int] = None
+        self._pending = b""
+
+    @property
+    def is_open(self) -> bool:
+        return self._fd is not None
+
+    def open(self) -> None:
+        """
+        Raises:
+            OSError: If the device cannot be opened
+        """
+        self._fd = os.open(self.path, os.O_RDWR | os.O_NOCTTY | os.O_NONBLOCK)
+        self._pending = b""
+
+    def close(self) -> None:
+        if self._fd is not None:
+            try:
+                os.close(self._fd)
+            finally:
+                self._fd = None
+
+    def write_line(self, line: str) -> None:
+        """
+        Raises:
+            OSError: If the write fails
+        """
+        if self._fd is None:
+            raise OSError(f"{self.path} is not open")
+        os.write(self._fd, ("\n" + line + "\n").encode())
+
+    def read_lines(self, timeout: float) -> List[str]:
+        """Lines received within timeout seconds (possibly none)."""
+        if self._fd is None:
+            return []
+        ready, _, _ = select.select([self._fd], [], [], timeout)
+        if not ready:
+            return []
+        try:
+            self._pending += os.read(self._fd, 4096)
+        except BlockingIOError:
+            return []
+        *lines, self._pending = self._pending.split(b"\n")
+        return [line.decode(errors="replace").strip() for line in lines if line.strip()]
+
+
+class SamLink:
+    """Heartbeat supervision and recovery of the SAM UART link."""
+
+    def __init__(self, channel=None, interval_s: float = HEARTBEAT_INTERVAL_S,
+                 missed: int = MISSED_HEARTBEATS, backoff_min_s: float = BACKOFF_MIN_S,
+                 backoff_max_s: float = BACKOFF_MAX_S, clock: Callable[[], float] = time.monotonic):
+        """
+        Args:
+            channel: Line channel to the MCU; None uses UartChannel on /dev/pamir-uart
+            interval_s: Seconds between heartbeats
+            missed: Consecutive unanswered heartbeats before the link is LOST
+            backoff_min_s: First delay before reopening a lost link
+            backoff_max_s: Longest delay between reopen attempts
+            clock: Monotonic clock, replaceable for testing
+        """
+        self.channel = channel or UartChannel()
+        self.interval_s = interval_s
+        self.missed = missed
+        self.backoff_min_s = backoff_min_s
+        self.backoff_max_s = backoff_max_s
+        self._clock = clock
+        self._log = get_logger("hardware.sam.link")
+        self._lock = threading.RLock()
+        self._state = LinkState.UNKNOWN
+        self._listeners: List[Callable[[LinkEvent], None]] = []
+        self._message_listeners: List[Callable[[SamMessage], None]] = []
+        self.protocol_version = LEGACY_VERSION
+        self.firmware: Optional[str] = None
+        self._hello_sent = False
+        self._seq = 0
+        self._unanswered = 0
+        self._next_heartbeat = 0.0
+        self._backoff = backoff_min_s
+        self._next_attempt = 0.0
+        self._thread: Optional[threading.Thread] = None
+        self._stop = threading.Event()
+
+    @property
+    def state(self) -> LinkState:
+        return self._state
+
+    @property
+    def is_lost(self) -> bool:
+        """True while modules should run degraded."""
+        return self._state == LinkState.LOST
+
+    def add_listener(self, callback: Callable[[LinkEvent], None]) -> None:
+        """Call callback(event) on every link state change."""
+        self._listeners.append(callback)
+
+    def remove_listener(self, callback: Callable[[LinkEvent], None]) -> None:
+        self._listeners.remove(callback)
+
+    def add_message_listener(self, callback: Callable[[SamMessage], None]) -> None:
+        """Call callback(message) for every message from the MCU other than heartbeats and Hello."""
+        self._message_listeners.append(callback)
+
+    def attach_power_state(self, machine) -> None:
+        """Veto suspend on a PowerStateMachine while the link is lost, since the MCU wakes the CM5."""
+        from ...device.power_state import PowerState
+        machine.add_veto(lambda transition: not (transition.state == PowerState.SUSPEND and self.is_lost))
+
+    def send(self, message: SamMessage) -> bool:
+        """
+        Send a message to the MCU.
+
+        Returns:
+            False without writing while the link is lost, if the negotiated
+            protocol predates the message type, or if the write fails
+        """
+        with self._lock:
+            if self.is_lost:
+                return False
+            if message.SINCE > self.protocol_version:
+                print(f"Warning: SAM firmware (protocol {self.protocol_version}) does not support "
+                      f"{message.FUNCTION} messages")
+                return False
+            try:
+                if not self.channel.is_open:
+                    self.channel.open()
+                self.channel.write_line(encode(message))
+                return True
+            except OSError as e:
+                self._lose(f"write_failed: {e}")
+                return False
+
+    def request(self, message: SamMessage, accept: Callable[[SamMessage], bool],
+                timeout_s: float = REQUEST_TIMEOUT_S) -> Optional[SamMessage]:
+        """
+        Send a message and wait for the MCU's reply to it.
+
+        Replies are read by the supervision thread after start(), else by
+        polling here.
+
+        Args:
+            message: Message to send
+            accept: Returns True for the reply to wait for
+            timeout_s: Seconds to wait for it
+
+        Returns:
+            The reply, or None if sending failed or no reply came in time
+        """
+        replies: List[SamMessage] = []
+        received = threading.Event()
+
+        def on_message(reply: SamMessage) -> None:
+            if not received.is_set() and accept(reply):
+                replies.append(reply)
+                received.set()
+
+        self.add_message_listener(on_message)
+        try:
+            if not self.send(message):
+                return None
+            deadline = time.monotonic() + timeout_s
+            while not received.is_set():
+                remaining = deadline - time.monotonic()
+                if remaining <= 0:
+                    return None
+                if self._thread is not None:
+                    received.wait(min(remaining, 0.1))
+                else:
+                    self.poll(timeout=min(remaining, 0.1))
+            return replies[0]
+        finally:
+            self._message_listeners.remove(on_message)
+
+    # -- supervision ------------------------------------------------------------
+
+    def _set_state(self, state: LinkState, reason: str) -> None:
+        if state == self._state:
+            return
+        event = LinkEvent(self._state, state, reason, time.time())
+        self._state = state
+        log = self._log.warning if state == LinkState.LOST else self._log.info
+        log("SAM link %s -> %s (%s)", event.previous.value, state.value, reason)
+        audit(AuditCategory.POWER, "sam_link", state.value, reason=reason)
+        for callback in list(self._listeners):
+            try:
+                callback(event)
+            except Exception as e:
+                print(f"Warning: SAM link listener failed: {e}")
+
+    def _lose(self, reason: str) -> None:
+        self.channel.close()
+        self._hello_sent = False
+        self._backoff = self.backoff_min_s
+        self._next_attempt = self._clock() + self._backoff
+        self._set_state(LinkState.LOST, reason)
+
+    def _handle_line(self, line: str) -> None:
+        try:
+            message = decode(line)
+        except ProtocolError as e:
+            self._log.debug("ignored line from SAM: %s (%s)", line, e)  # e.g. firmware debug output
+            return
+        if isinstance(message, Heartbeat):
+            self._unanswered = 0
+            self._set_state(LinkState.UP, "recovered" if self._state == LinkState.LOST else "heartbeat")
+            return
+        if isinstance(message, Hello):
+            self.firmware = message.firmware
+            try:
+                self.protocol_version = negotiate(message)
+            except ProtocolError as e:
+                print(f"Warning: {e}")
+                self.protocol_version = LEGACY_VERSION
+            self._log.info("SAM protocol %d (firmware %s)", self.protocol_version, self.firmware or "unknown")
+            return
+        for callback in list(self._message_listeners):
+            callback(message)
+
+    def poll(self, timeout: float = 0.0) -> LinkState:
+        """
+        Run one supervision step: read replies, send a heartbeat when due and
+        reopen a lost link when its backoff has passed.
+
+        Args:
+            timeout: Seconds to wait for incoming lines
+
+        Returns:
+            The link state
+        """
+        with self._lock:
+            now = self._clock()
+            if self._state == LinkState.LOST and not self.channel.is_open:
+                if now < self._next_attempt:
+                    return self._state
+                try:
+                    self.channel.open()
+                    self._hello_sent = False
+                    self._unanswered = 0
+                    self._next_heartbeat = now
+                except OSError as e:
+                    self._log.info("SAM link reopen failed: %s", e)
+                    self._backoff = min(self._backoff * 2, self.backoff_max_s)
+                    self._next_attempt = now + self._backoff
+                    return self._state
+
+            if now >= self._next_heartbeat:
+                if self._unanswered >= self.missed:
+                    if self._state == LinkState.LOST:
+                        # Reopened but still silent; try again after a longer pause
+                        self.channel.close()
+                        self._backoff = min(self._backoff * 2, self.backoff_max_s)
+                        self._next_attempt = now + self._backoff
+                        return self._state
+                    if self._state == LinkState.UP:
+                        self._lose("timeout")
+                        return self._state
+                self._seq += 1
+                try:
+                    if not self.channel.is_open:
+                        self.channel.open()
+                    if not self._hello_sent:
+                        self.channel.write_line(encode(Hello()))
+                        self._hello_sent = True
+                    self.channel.write_line(encode(Heartbeat(self._seq)))
+                    self._unanswered += 1
+                except OSError as e:
+                    if self._state != LinkState.LOST:
+                        self._lose(f"write_failed: {e}")
+                    return self._state
+                self._next_heartbeat = now + self.interval_s
+
+        for line in self.channel.read_lines(timeout):
+            with self._lock:
+                self._handle_line(line)
+        return self._state
+
+    def start(self) -> None:
+        """Supervise the link in a background thread."""
+        if self._thread is not None:
+            return
+        self._stop.clear()
+        self._thread = threading.Thread(target=self._run, name="sam-link", daemon=True)
+        self._thread.start()
+
+    def _run(self) -> None:
+        while not self._stop.is_set():
+            self.poll(timeout=min(self.interval_s, 0.5))
+            if not self.channel.is_open:
+                self._stop.wait(0.5)
+
+    def stop(self) -> None:
+        """Stop supervision and close the channel."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join(timeout=2)
+            self._thread = None
+        with self._lock:
+            self.channel.close()
@@ -0,0 +1,84 @@
+#!/usr/bin/env python3
+"""
+E-ink panel power rail control through the SAM MCU for CM5 SDK.
+
+Controller deep sleep still draws current, which adds up over a multi-week
+battery deployment. The SAM can cut the panel's power rail entirely; the
+e-ink keeps its image without power. Display.power_off() and power_on()
+wrap the rail in the safe sequence: deep sleep and releasing the SPI and
+GPIO lines before cutting it, re-initializing and restoring the frame as
+the partial-refresh reference after restoring it:
+
+    rail = PanelPowerRail(link)
+    display.power_off(rail)
+    ...
+    display.power_on(rail)
+
+Needs SAM firmware speaking protocol 2 (PanelPower messages).
+"""
+
+import time
+from typing import Callable, Optional
+
+from ...diagnostics.audit import AuditCategory, audit
+from .link import REQUEST_TIMEOUT_S, SamLink, SamLinkError
+from .protocol import PanelPower, SamMessage
+
+
+SETTLE_S = 0.05         # rail rise time before the controller may be reset
+CYCLE_OFF_S = 0.5       # time off in cycle(), long enough to drain the panel's capacitors
+
+
+class PanelPowerRail:
+    """Switches the e-ink panel's power rail through the SAM MCU."""
+
+    def __init__(self, link: SamLink, timeout_s: float = REQUEST_TIMEOUT_S, settle_s: float = SETTLE_S,
+                 sleep: Callable[[float], None] = time.sleep):
+        """
+        Args:
+            link: Link to the SAM MCU
+            timeout_s: Seconds to wait for the MCU to confirm a switch
+            settle_s: Seconds to wait after switching on, before the panel is used
+            sleep: Sleeps for a number of seconds, replaceable for testing
+        """
+        self.link = link
+        self.timeout_s = timeout_s
+        self.settle_s = settle_s
+        self._sleep = sleep
+        self.is_on: Optional[bool] = None   # unknown until switched
+
+    def set(self, on: bool) -> None:
+        """
+        Switch the rail and wait for the MCU to confirm it.
+
+        Raises:
+            SamLinkError: If the MCU did not answer, or left the rail in the other state
+        """
+        state = "on" if on else "off"
+
+        def accept(message: SamMessage) -> bool:
+            return isinstance(message, PanelPower)
+
+        reply = self.link.request(PanelPower(on), accept, self.timeout_s)
+        if reply is None:
+            raise SamLinkError(f"SAM did not confirm switching the panel power {state}")
+        self.is_on = bool(reply.on)
+        if self.is_on != on:
+            raise SamLinkError(f"SAM left the panel power {'on' if self.is_on else 'off'}")
+        audit(AuditCategory.POWER, "panel_rail", state)
+        if on:
+            self._sleep(self.settle_s)
+
+    def on(self) -> None:
+        """Restore the rail; see set()."""
+        self.set(True)
+
+    def off(self) -> None:
+        """Cut the rail; see set()."""
+        self.set(False)
+
+    def cycle(self, off_s: float = CYCLE_OFF_S) -> None:
+        """Cut the rail and restore it after off_s seconds, e.g. for hardware-in-the-loop tests."""
+        self.off()
+        self._sleep(off_s)
+        self.on()
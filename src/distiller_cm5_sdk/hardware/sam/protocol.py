@@ -0,0 +1,186 @@
+#!/usr/bin/env python3
+"""
+SAM MCU message protocol for CM5 SDK.
+
+Every message on /dev/pamir-uart is a JSON object on its own line whose
+"Function" key names its type. This module gives each type a dataclass,
+so callers never build or pick apart raw dicts:
+
+    line = encode(NeoPixel([LedStep(255, 0, 0)]))
+    message = decode(line)          # -> NeoPixel(steps=[LedStep(...)])
+
+Decoding tolerates newer firmware: an unknown "Function" decodes to
+UnknownMessage instead of failing, and fields a known type does not define
+are kept in its `extra` dict. Only a message missing a required field, or a
+line that is not a JSON object, raises ProtocolError.
+
+Versions are negotiated with a Hello exchange. The host announces the
+newest and oldest protocol versions it speaks; firmware that answers picks
+a version both sides support. Firmware that does not answer is treated as
+LEGACY_VERSION. Each message type records the version that introduced it,
+and SamLink refuses to send a type the MCU cannot understand.
+"""
+
+import json
+from dataclasses import dataclass, field, fields
+from typing import Any, ClassVar, Dict, List, Optional, Type, Union
+
+
+PROTOCOL_VERSION = 2        # newest version this SDK speaks (2: PanelPower)
+MIN_PROTOCOL_VERSION = 1    # oldest version this SDK still speaks
+LEGACY_VERSION = 1          # assumed for firmware that does not answer Hello
+
+
+class ProtocolError(Exception):
+    """Custom exception for malformed or incompatible SAM messages."""
+    pass
+
+
+@dataclass
+class SamMessage:
+    """Base of all SAM messages."""
+    FUNCTION: ClassVar[str] = ""
+    SINCE: ClassVar[int] = 1            # protocol version that introduced the message
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {"Function": self.FUNCTION, **self._fields()}
+
+    def _fields(self) -> Dict[str, Any]:
+        data = {f.name: getattr(self, f.name) for f in fields(self) if f.name != "extra"}
+        return {key: value for key, value in data.items() if value is not None}
+
+    @classmethod
+    def from_dict(cls, data: Dict[str, Any]) -> "SamMessage":
+        names = {f.name for f in fields(cls)} - {"extra"}
+        try:
+            message = cls(**{key: data[key] for key in names if key in data})
+        except TypeError as e:
+            raise ProtocolError(f"Malformed {cls.FUNCTION} message: {e}")
+        message.extra = {key: value for key, value in data.items() if key not in names and key != "Function"}
+        return message
+
+
+@dataclass
+class LedStep:
+    """One step of an LED sequence."""
+    r: int
+    g: int
+    b: int
+    brightness: float = 0.5
+    delay: float = 0.0              # seconds this step is shown
+
+
+@dataclass
+class NeoPixel(SamMessage):
+    """Host -> MCU: play an LED sequence."""
+    FUNCTION: ClassVar[str] = "NeoPixel"
+    steps: List[LedStep] = field(default_factory=list)
+    extra: Dict[str, Any] = field(default_factory=dict, compare=False)
+
+    def _fields(self) -> Dict[str, Any]:
+        # The firmware takes {"0": [r, g, b, brightness, delay], "1": ...}
+        return {"colors": {str(i): [s.r, s.g, s.b, float(s.brightness), float(s.delay)]
+                           for i, s in enumerate(self.steps)}}
+
+    @classmethod
+    def from_dict(cls, data: Dict[str, Any]) -> "NeoPixel":
+        colors = data.get("colors")
+        if not isinstance(colors, dict):
+            raise ProtocolError("Malformed NeoPixel message: missing colors")
+        try:
+            steps = [LedStep(*colors[key][:5]) for key in sorted(colors, key=int)]
+        except (TypeError, ValueError) as e:
+            raise ProtocolError(f"Malformed NeoPixel message: {e}")
+        extra = {key: value for key, value in data.items() if key not in ("Function", "colors")}
+        return cls(steps, extra)
+
+
+@dataclass
+class Heartbeat(SamMessage):
+    """Both directions: link liveness probe; the MCU echoes seq back."""
+    FUNCTION: ClassVar[str] = "Heartbeat"
+    seq: int = 0
+    extra: Dict[str, Any] = field(default_factory=dict, compare=False)
+
+
+@dataclass
+class Hello(SamMessage):
+    """Both directions: protocol version negotiation."""
+    FUNCTION: ClassVar[str] = "Hello"
+    version: int = PROTOCOL_VERSION
+    min_version: int = MIN_PROTOCOL_VERSION
+    firmware: Optional[str] = None      # firmware build, sent by the MCU only
+    extra: Dict[str, Any] = field(default_factory=dict, compare=False)
+
+
+@dataclass
+class PanelPower(SamMessage):
+    """Both directions: switch the e-ink panel's power rail; the MCU answers with the rail's state."""
+    FUNCTION: ClassVar[str] = "PanelPower"
+    SINCE: ClassVar[int] = 2
+    on: bool = True
+    extra: Dict[str, Any] = field(default_factory=dict, compare=False)
+
+
+@dataclass
+class UnknownMessage(SamMessage):
+    """A message type this SDK does not know, e.g. from newer firmware."""
+    function: str = ""
+    extra: Dict[str, Any] = field(default_factory=dict, compare=False)
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {"Function": self.function, **self.extra}
+
+
+MESSAGE_TYPES: Dict[str, Type[SamMessage]] = {cls.FUNCTION: cls for cls in (NeoPixel, Heartbeat, Hello, PanelPower)}
+
+
+def encode(message: SamMessage) -> str:
+    """Serialize a message to one JSON line (without the newline)."""
+    return json.dumps(message.to_dict())
+
+
+def decode(line: Union[str, Dict[str, Any]]) -> SamMessage:
+    """
+    Parse a line (or already-parsed dict) from the MCU.
+
+    Returns:
+        The typed message, or UnknownMessage for an unknown Function
+
+    Raises:
+        ProtocolError: If the line is not a JSON object with a Function, or
+                       a known message lacks a required field
+    """
+    if isinstance(line, str):
+        try:
+            data = json.loads(line)
+        except ValueError as e:
+            raise ProtocolError(f"Not a JSON message: {e}")
+    else:
+        data = line
+    if not isinstance(data, dict) or not isinstance(data.get("Function"), str):
+        raise ProtocolError("SAM messages must be JSON objects with a Function")
+    cls = MESSAGE_TYPES.get(data["Function"])
+    if cls is None:
+        return UnknownMessage(data["Function"],
+                              {key: value for key, value in data.items() if key != "Function"})
+    return cls.from_dict(data)
+
+
+def negotiate(remote: Hello) -> int:
+    """
+    Pick the protocol version to use with the MCU.
+
+    Returns:
+        The newest version both sides speak
+
+    Raises:
+        ProtocolError: If the version ranges do not overlap
+    """
+    version = min(PROTOCOL_VERSION, remote.version)
+    if version < max(MIN_PROTOCOL_VERSION, remote.min_version):
+        raise ProtocolError(
+            f"SAM firmware speaks protocol {remote.min_version}-{remote.version}, "
+            f"this SDK {MIN_PROTOCOL_VERSION}-{PROTOCOL_VERSION}; update the "
+            f"{'SDK' if remote.min_version > PROTOCOL_VERSION else 'firmware'}")
+    return version
@@ -0,0 +1,2 @@
+from .controller import CHIP_NAMES, Ft6336, I2cDevice, TouchAction, TouchError, TouchPoint
+from .gestures import GestureType, SwipeDirection, TouchButtons, TouchGesture, TouchPanel, to_frame
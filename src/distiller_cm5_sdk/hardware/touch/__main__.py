@@ -0,0 +1,82 @@
+#!/usr/bin/env python3
+"""
+Touch panel tools: python3 -m distiller_cm5_sdk.hardware.touch monitor [--rotation R] | points
+"""
+
+import argparse
+import sys
+import time
+
+from ..eink.config import load_config, parse_rotation
+from ..eink.display import DisplayError
+from ..eink.panels import PanelType, get_panel
+from .controller import FT6336_ADDRESS, I2C_BUS, Ft6336, TouchError
+from .gestures import TouchPanel
+
+
+def _panel_size(config):
+    spec = config.custom_panel if config.panel == PanelType.CUSTOM else get_panel(config.panel)
+    return (spec.width, spec.height) if spec is not None else None
+
+
+def _monitor(args, controller) -> int:
+    config = load_config()
+    rotation = parse_rotation(args.rotation) if args.rotation else config.rotation
+    panel = TouchPanel(controller, rotation=rotation, size=_panel_size(config), swap_xy=args.swap_xy,
+                       invert_x=args.invert_x, invert_y=args.invert_y)
+    print(f"Touch the panel (rotation {rotation.name}; Ctrl+C to stop)")
+    while True:
+        for gesture in panel.poll():
+            where = f"{gesture.x},{gesture.y}"
+            if gesture.direction is not None:
+                where += f" -> {gesture.end_x},{gesture.end_y} {gesture.direction.value}"
+            print(f"{gesture.type.value} {where} ({gesture.duration_s:.2f} s)")
+        time.sleep(panel.poll_interval_s)
+
+
+def _points(args, controller) -> int:
+    print("Touch the panel (Ctrl+C to stop)")
+    while True:
+        for point in controller.read_points():
+            print(f"{point.id}: {point.x},{point.y} {point.action.name.lower()}")
+        time.sleep(args.interval)
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Distiller CM5 touch panel tools")
+    parser.add_argument("--bus", type=int, default=I2C_BUS, help=f"I2C bus, as in /dev/i2c-N (default: {I2C_BUS})")
+    parser.add_argument("--address", type=lambda value: int(value, 0), default=FT6336_ADDRESS,
+                        help=f"I2C address of the controller (default: {FT6336_ADDRESS:#04x})")
+    commands = parser.add_subparsers(dest="command_name", required=True)
+
+    monitor = commands.add_parser("monitor", help="Print taps, long presses and swipes in display coordinates")
+    monitor.add_argument("--rotation", help="none, ccw90, 180 or cw90 (default: the display's configured rotation)")
+    monitor.add_argument("--swap-xy", action="store_true", help="The controller's axes are swapped")
+    monitor.add_argument("--invert-x", action="store_true", help="The controller's x axis is reversed")
+    monitor.add_argument("--invert-y", action="store_true", help="The controller's y axis is reversed")
+    monitor.set_defaults(handler=_monitor)
+
+    points = commands.add_parser("points", help="Print raw controller points, e.g. to find the axes")
+    points.add_argument("--interval", type=float, default=0.1, help="Seconds between reads (default: 0.1)")
+    points.set_defaults(handler=_points)
+
+    args = parser.parse_args(argv)
+    try:
+        controller = Ft6336(args.bus, args.address)
+    except TouchError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    try:
+        print(f"{controller.chip_name()} at {args.address:#04x} on /dev/i2c-{args.bus}")
+        return args.handler(args, controller)
+    except (TouchError, DisplayError) as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    except KeyboardInterrupt:
+        return 0
+    finally:
+        controller.close()
+
+
+if __name__ == "__main__":
+    sys.exit(main())
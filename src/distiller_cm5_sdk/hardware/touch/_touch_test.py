@@ -0,0 +1,179 @@
+#!/usr/bin/env python3
+"""
+Touch panel unit tests for CM5 SDK.
+"""
+
+import io
+import sys
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[3]))
+
+from distiller_cm5_sdk.hardware.eink.display import Display, DisplayMode, RotationMode
+from distiller_cm5_sdk.hardware.eink.graphics import Color, Framebuffer
+from distiller_cm5_sdk.hardware.touch.__main__ import main
+from distiller_cm5_sdk.hardware.touch.controller import Ft6336, TouchAction, TouchError, TouchPoint
+from distiller_cm5_sdk.hardware.touch.gestures import (
+    GestureType, SwipeDirection, TouchButtons, TouchPanel, to_frame,
+)
+from distiller_cm5_sdk.recovery.buttons import Button
+
+
+def touch_status(*points):
+    """TD_STATUS and the two point records of an FT6336 for points of (id, x, y, action)."""
+    data = bytearray([len(points)]) + bytearray(12)
+    for n, (point_id, x, y, action) in enumerate(points):
+        data[1 + 6 * n:5 + 6 * n] = bytes([action.value << 6 | x >> 8, x & 0xFF, point_id << 4 | y >> 8, y & 0xFF])
+    return bytes(data)
+
+
+class FakeI2c:
+    """FT6336 registers; each read of TD_STATUS takes the next of frames, then KeyboardInterrupt."""
+
+    def __init__(self, frames=(), vendor=0x11, chip=0x64):
+        self.registers = {0xA8: bytes([vendor]), 0xA3: bytes([chip])}
+        self.frames = list(frames)
+        self.closed = False
+
+    def read(self, register, length):
+        if register == 0x02:
+            if not self.frames:
+                raise KeyboardInterrupt
+            return self.frames.pop(0)
+        return self.registers[register][:length]
+
+    def close(self):
+        self.closed = True
+
+
+class FakeController:
+    """Controller returning scripted points, one list per read."""
+
+    def __init__(self, frames=()):
+        self.frames = list(frames)
+        self.closed = False
+
+    def read_points(self):
+        return self.frames.pop(0) if self.frames else []
+
+    def close(self):
+        self.closed = True
+
+
+def down(x, y, point_id=0):
+    return [TouchPoint(point_id, x, y, TouchAction.DOWN)]
+
+
+def contact(x, y, point_id=0):
+    return [TouchPoint(point_id, x, y, TouchAction.CONTACT)]
+
+
+class TestTouch(unittest.TestCase):
+    """Test cases for reading the controller and recognizing gestures."""
+
+    def test_controller(self):
+        """Test points are decoded from the registers, and the chip is identified."""
+        controller = Ft6336(device=FakeI2c([
+            touch_status((1, 0x123, 0x0F6, TouchAction.DOWN), (2, 5, 6, TouchAction.CONTACT)),
+            touch_status((1, 0x123, 0x0F6, TouchAction.UP)),
+            b"\x0f" + bytes(12),                # not ready
+            bytes([1, 0xC0]) + bytes(11),       # no event
+        ]))
+        self.assertEqual(controller.chip_name(), "FT6336U")
+        self.assertEqual(controller.read_points(), [TouchPoint(1, 0x123, 0x0F6, TouchAction.DOWN),
+                                                    TouchPoint(2, 5, 6, TouchAction.CONTACT)])
+        self.assertEqual(controller.read_points(), [TouchPoint(1, 0x123, 0x0F6, TouchAction.UP)])
+        self.assertEqual(controller.read_points(), [])
+        self.assertEqual(controller.read_points(), [])
+
+        self.assertEqual(Ft6336(device=FakeI2c(chip=0x99)).chip_name(), "FT6x36 (chip ID 0x99)")
+        with self.assertRaises(TouchError):
+            Ft6336(device=FakeI2c(vendor=0xFF)).chip_name()
+
+    def test_rotation(self):
+        """Test a touch maps to the pixel the caller drew under it, at every rotation the display applies."""
+        display = Display(backend="mock")
+        try:
+            panel = TouchPanel(FakeController(), display)
+            for rotation in RotationMode:
+                display.set_rotation(rotation)
+                width, height = display.get_dimensions()
+                fb = Framebuffer(width, height)
+                fb.set_pixel(7, 3, Color.BLACK)
+                display.display_image(fb.to_bytes(), DisplayMode.FULL)
+                native = Framebuffer(display.WIDTH, display.HEIGHT, display.get_framebuffer(native=True))
+                touched = [(x, y) for y in range(native.height) for x in range(native.width)
+                           if native.get_pixel(x, y) == Color.BLACK]
+                self.assertEqual(len(touched), 1)
+                self.assertEqual(to_frame(*touched[0], rotation), (7, 3), rotation)
+                self.assertEqual(panel.map_point(*touched[0]), (7, 3), rotation)
+        finally:
+            display.close()
+
+        # A controller reporting 0..1023 along swapped, reversed axes
+        panel = TouchPanel(FakeController(), rotation=RotationMode.CW_90, resolution=(1024, 1024), swap_xy=True,
+                           invert_x=True)
+        self.assertEqual(panel.map_point(0, 1023), (0, 127))
+        self.assertEqual(panel.map_point(512, 0), (125, 0))
+
+    def test_gestures(self):
+        """Test taps, swipes and long presses of the first finger are reported to listeners."""
+        panel = TouchPanel(FakeController([
+            down(10, 20), contact(12, 22), [],                                  # tap
+            down(60, 200), contact(60, 150), contact(61, 100), [],              # swipe up
+            down(10, 100), contact(50, 105), [],                                # swipe right
+            down(30, 30), contact(30, 31), contact(31, 31), [],                 # long press, held
+            down(40, 40), [],                                                   # long press, seen on lift
+            down(5, 5), contact(5, 5) + down(90, 90, 1), contact(90, 90, 1), contact(90, 90, 1), [],
+        ]))
+        gestures = []
+        panel.add_listener(gestures.append)
+        panel.add_listener(lambda gesture: 1 / 0)
+        times = [0.0, 0.1, 0.2, 1.0, 1.1, 1.2, 1.3, 2.0, 2.1, 2.2, 3.0, 3.5, 3.9, 4.0, 5.0, 6.0,
+                 7.0, 7.1, 7.2, 7.3, 7.4]
+        with patch("sys.stdout", io.StringIO()) as out:
+            polled = [gesture for now in times for gesture in panel.poll(now)]
+        self.assertEqual(polled, gestures)
+        self.assertIn("Warning: Touch listener failed", out.getvalue())
+
+        self.assertEqual([(g.type, g.direction, g.x, g.y, g.end_x, g.end_y) for g in gestures], [
+            (GestureType.TAP, None, 10, 20, 12, 22),
+            (GestureType.SWIPE, SwipeDirection.UP, 60, 200, 61, 100),
+            (GestureType.SWIPE, SwipeDirection.RIGHT, 10, 100, 50, 105),
+            (GestureType.LONG_PRESS, None, 30, 30, 31, 31),
+            (GestureType.LONG_PRESS, None, 40, 40, 40, 40),
+            (GestureType.TAP, None, 5, 5, 5, 5),                # the second finger is not a gesture
+            (GestureType.TAP, None, 90, 90, 90, 90),            # until the first is lifted
+        ])
+        self.assertAlmostEqual(gestures[3].duration_s, 0.9)
+
+    def test_touch_buttons(self):
+        """Test swipes up and down and taps are read as buttons."""
+        controller = FakeController([down(60, 200), contact(60, 100), [], down(10, 10), [],
+                                     down(60, 10), contact(60, 100), [], down(10, 10), contact(100, 10), []])
+        buttons = TouchButtons(TouchPanel(controller, poll_interval_s=0.001))
+        self.assertEqual([buttons.read(1), buttons.read(1), buttons.read(1)], [Button.UP, Button.SELECT, Button.DOWN])
+        self.assertIsNone(buttons.read(0.05))               # a swipe to the right is not a button
+        buttons.close()
+        self.assertTrue(controller.closed)
+
+    def test_cli(self):
+        """Test the monitor command prints gestures mapped through the rotation, and errors are reported."""
+        device = FakeI2c([touch_status((0, 10, 20, TouchAction.DOWN)), touch_status()])
+        with patch("distiller_cm5_sdk.hardware.touch.__main__.Ft6336", return_value=Ft6336(device=device)):
+            with patch("sys.stdout", io.StringIO()) as out:
+                self.assertEqual(main(["monitor", "--rotation", "cw90"]), 0)
+        self.assertIn("FT6336U at 0x38 on /dev/i2c-1", out.getvalue())
+        self.assertIn("tap 20,117", out.getvalue())
+        self.assertTrue(device.closed)
+
+        with patch("sys.stderr", io.StringIO()) as err:
+            self.assertEqual(main(["--bus", "99", "points"]), 1)
+        self.assertIn("/dev/i2c-99", err.getvalue())
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,159 @@
+#!/usr/bin/env python3
+"""
+Capacitive touch controller on I2C for CM5 SDK.
+
+Touch-capable Distiller panels carry a FocalTech FT6336 (or the FT6206 and
+FT6236 it replaces) on the I2C bus, default address 0x38. The controller is
+read directly through /dev/i2c-N, so no kernel touchscreen driver or evdev
+setup is needed: each read returns the up to two points in contact, in the
+controller's own coordinates.
+
+    controller = Ft6336()
+    print(controller.chip_name())       # FT6336U
+    for point in controller.read_points():
+        print(point.id, point.x, point.y, point.action)
+"""
+
+import fcntl
+import os
+from dataclasses import dataclass
+from enum import Enum
+from typing import List, Optional
+
+
+I2C_BUS = 1
+FT6336_ADDRESS = 0x38
+
+_I2C_SLAVE = 0x0703             # ioctl selecting the address /dev/i2c-N reads and writes
+
+# FT6x36 registers
+_REG_TD_STATUS = 0x02           # number of points in contact, then 6 bytes per point
+_REG_CHIP_ID = 0xA3
+_REG_VENDOR_ID = 0xA8
+_POINT_SIZE = 6
+_FOCALTECH_VENDOR_ID = 0x11
+
+CHIP_NAMES = {0x06: "FT6206", 0x36: "FT6236", 0x64: "FT6336U"}
+
+
+class TouchError(Exception):
+    """Custom exception for touch controller errors."""
+    pass
+
+
+class TouchAction(Enum):
+    """What a point is doing, as flagged by the controller."""
+    DOWN = 0            # first report of a new contact
+    UP = 1              # the finger was lifted
+    CONTACT = 2         # still in contact
+
+
+@dataclass
+class TouchPoint:
+    """A point in contact with the panel, in controller coordinates."""
+    id: int             # tracking ID, the same for as long as the finger stays down
+    x: int
+    y: int
+    action: TouchAction
+
+
+class I2cDevice:
+    """Register reads from one address on a Linux I2C bus."""
+
+    def __init__(self, bus: int = I2C_BUS, address: int = FT6336_ADDRESS):
+        """
+        Open the bus and select the address.
+
+        Raises:
+            TouchError: If the bus cannot be opened or the address is claimed by a kernel driver
+        """
+        self.path = f"/dev/i2c-{bus}"
+        self.address = address
+        try:
+            self._fd: Optional[int] = os.open(self.path, os.O_RDWR)
+        except OSError as e:
+            raise TouchError(f"Failed to open {self.path}: {e} (is I2C enabled with dtparam=i2c_arm=on?)")
+        try:
+            fcntl.ioctl(self._fd, _I2C_SLAVE, address)
+        except OSError as e:
+            self.close()
+            raise TouchError(f"Cannot use address {address:#04x} on {self.path}: {e} "
+                             "(is a kernel touchscreen driver such as edt-ft5x06 bound to it?)")
+
+    def read(self, register: int, length: int) -> bytes:
+        """
+        Read length bytes starting at a register.
+
+        Raises:
+            TouchError: If the device does not acknowledge
+        """
+        try:
+            os.write(self._fd, bytes([register]))
+            data = os.read(self._fd, length)
+        except OSError as e:
+            raise TouchError(f"Failed to read register {register:#04x} at {self.address:#04x} on {self.path}: {e}")
+        if len(data) < length:
+            raise TouchError(f"Short read of register {register:#04x}: {len(data)} of {length} bytes")
+        return data
+
+    def close(self) -> None:
+        if self._fd is not None:
+            os.close(self._fd)
+            self._fd = None
+
+
+class Ft6336:
+    """A FocalTech FT6336 (or FT6206/FT6236) self-capacitive touch controller."""
+
+    MAX_POINTS = 2
+
+    def __init__(self, bus: int = I2C_BUS, address: int = FT6336_ADDRESS, device=None):
+        """
+        Open the controller.
+
+        Args:
+            bus: I2C bus number, as in /dev/i2c-N
+            address: 7-bit I2C address
+            device: Object with read(register, length) and close() to use
+                    instead of the bus, e.g. for testing
+
+        Raises:
+            TouchError: If the bus cannot be opened
+        """
+        self.device = device if device is not None else I2cDevice(bus, address)
+
+    def chip_name(self) -> str:
+        """
+        The controller model, e.g. "FT6336U", from its ID registers.
+
+        Raises:
+            TouchError: If the controller does not respond or is not a FocalTech part
+        """
+        vendor = self.device.read(_REG_VENDOR_ID, 1)[0]
+        if vendor != _FOCALTECH_VENDOR_ID:
+            raise TouchError(f"Unexpected touch controller vendor ID {vendor:#04x} (not a FocalTech FT6x36?)")
+        chip = self.device.read(_REG_CHIP_ID, 1)[0]
+        return CHIP_NAMES.get(chip, f"FT6x36 (chip ID {chip:#04x})")
+
+    def read_points(self) -> List[TouchPoint]:
+        """
+        The points in contact now, or lifted since the last read.
+
+        Raises:
+            TouchError: If the controller does not respond
+        """
+        data = self.device.read(_REG_TD_STATUS, 1 + self.MAX_POINTS * _POINT_SIZE)
+        count = data[0] & 0x0F
+        if count > self.MAX_POINTS:     # 0x0F while the controller is starting up
+            return []
+        points = []
+        for n in range(count):
+            xh, xl, yh, yl = data[1 + n * _POINT_SIZE:5 + n * _POINT_SIZE]
+            flag = xh >> 6
+            if flag == 3:               # no event
+                continue
+            points.append(TouchPoint(yh >> 4, (xh & 0x0F) << 8 | xl, (yh & 0x0F) << 8 | yl, TouchAction(flag)))
+        return points
+
+    def close(self) -> None:
+        self.device.close()
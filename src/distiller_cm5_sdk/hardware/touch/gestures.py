@@ -0,0 +1,294 @@
+#!/usr/bin/env python3
+"""
+Touch gestures on the e-ink display for CM5 SDK.
+
+TouchPanel polls a touch controller and turns the contacts of one finger
+into taps, long presses and swipes. Their coordinates are in the frame the
+application draws, mapped through the display's active RotationMode, so a
+tap lands on what was drawn under it whichever way the unit is held:
+
+    panel = TouchPanel(Ft6336(), display)
+    panel.add_listener(lambda gesture: print(gesture.type, gesture.x, gesture.y, gesture.direction))
+    panel.start()
+
+TouchButtons drives anything that reads a ButtonSource, such as the
+recovery menu, with swipes up and down and taps.
+"""
+
+import math
+import threading
+import time
+from dataclasses import dataclass
+from enum import Enum
+from typing import Callable, List, Optional, Tuple
+
+from ...recovery.buttons import Button, ButtonSource
+from ..eink.display import RotationMode
+from ..eink.graphics import PANEL_HEIGHT, PANEL_WIDTH
+from .controller import TouchAction, TouchError
+
+
+POLL_INTERVAL_S = 0.02          # 50 Hz, quick enough to see the shortest taps
+SWIPE_MIN_DISTANCE = 30         # pixels a finger travels before a touch is a swipe
+LONG_PRESS_S = 0.8              # hold time before a touch that stays put is a long press
+
+
+class GestureType(Enum):
+    """Kinds of gesture."""
+    TAP = "tap"
+    LONG_PRESS = "long_press"
+    SWIPE = "swipe"
+
+
+class SwipeDirection(Enum):
+    """Direction of a swipe in the caller's frame; y grows downwards."""
+    UP = "up"
+    DOWN = "down"
+    LEFT = "left"
+    RIGHT = "right"
+
+
+@dataclass
+class TouchGesture:
+    """A gesture, in the caller's frame under the rotation active when it happened."""
+    type: GestureType
+    x: int                      # where the finger went down
+    y: int
+    end_x: int                  # where it was lifted, or is while a long press is held
+    end_y: int
+    duration_s: float
+    direction: Optional[SwipeDirection] = None     # swipes only
+
+
+def to_frame(x: int, y: int, rotation: RotationMode, width: int = PANEL_WIDTH,
+             height: int = PANEL_HEIGHT) -> Tuple[int, int]:
+    """
+    Map a point on the panel, in its native orientation, into the caller's frame.
+
+    This undoes the rotation Display applies to frames on their way to the
+    panel, so the point lands on the pixel the caller drew there.
+
+    Args:
+        x, y: Point in the panel's native orientation
+        rotation: Rotation from the caller's frame to the panel
+        width, height: Native panel size, e.g. Display.WIDTH and Display.HEIGHT
+    """
+    if rotation == RotationMode.CCW_90:
+        return height - 1 - y, x
+    if rotation == RotationMode.CW_90:
+        return y, width - 1 - x
+    if rotation == RotationMode.ROTATE_180:
+        return width - 1 - x, height - 1 - y
+    return x, y
+
+
+@dataclass
+class _Contact:
+    id: int
+    started: float
+    x: int
+    y: int
+    last_x: int
+    last_y: int
+    long_pressed: bool = False
+
+
+class TouchPanel:
+    """Gestures from a touch controller laid over the display."""
+
+    def __init__(self, controller, display=None, rotation: RotationMode = RotationMode.NONE,
+                 size: Optional[Tuple[int, int]] = None, resolution: Optional[Tuple[int, int]] = None,
+                 swap_xy: bool = False, invert_x: bool = False, invert_y: bool = False,
+                 poll_interval_s: float = POLL_INTERVAL_S):
+        """
+        Args:
+            controller: Touch controller, e.g. Ft6336(); closed by close()
+            display: Display the overlay covers, whose active rotation is
+                     followed; None to map through a fixed rotation
+            rotation: Rotation to map through when there is no display
+            size: Native panel size when there is no display; None for 128x250
+            resolution: Range of the controller's coordinates after swap_xy,
+                        (width, height); None when it reports panel pixels
+            swap_xy: The controller's axes are swapped relative to the panel's
+            invert_x: The controller's x axis runs opposite to the panel's
+            invert_y: The controller's y axis runs opposite to the panel's
+            poll_interval_s: Seconds between reads while started
+        """
+        self.controller = controller
+        self.display = display
+        self._rotation = rotation
+        if display is not None:
+            size = (display.WIDTH, display.HEIGHT)
+        self.size = size or (PANEL_WIDTH, PANEL_HEIGHT)
+        self.resolution = resolution or self.size
+        self.swap_xy = swap_xy
+        self.invert_x = invert_x
+        self.invert_y = invert_y
+        self.poll_interval_s = poll_interval_s
+        self._contact: Optional[_Contact] = None
+        self._listeners: List[Callable[[TouchGesture], None]] = []
+        self._stop = threading.Event()
+        self._thread: Optional[threading.Thread] = None
+
+    @property
+    def rotation(self) -> RotationMode:
+        """The display's active rotation, or the fixed one without a display."""
+        return self.display.get_rotation() if self.display is not None else self._rotation
+
+    @rotation.setter
+    def rotation(self, rotation: RotationMode) -> None:
+        self._rotation = rotation
+
+    def map_point(self, x: int, y: int) -> Tuple[int, int]:
+        """Map a point in controller coordinates into the caller's frame."""
+        if self.swap_xy:
+            x, y = y, x
+        width, height = self.size
+        x = min(max(x * width // self.resolution[0], 0), width - 1)
+        y = min(max(y * height // self.resolution[1], 0), height - 1)
+        if self.invert_x:
+            x = width - 1 - x
+        if self.invert_y:
+            y = height - 1 - y
+        return to_frame(x, y, self.rotation, width, height)
+
+    def add_listener(self, callback: Callable[[TouchGesture], None]) -> None:
+        """Call callback with every gesture."""
+        self._listeners.append(callback)
+
+    def remove_listener(self, callback: Callable[[TouchGesture], None]) -> None:
+        """Stop calling a listener."""
+        self._listeners.remove(callback)
+
+    def poll(self, now: Optional[float] = None) -> List[TouchGesture]:
+        """
+        Read the controller once and report any gesture it completes to the listeners.
+
+        Only the first finger down is followed; a second finger is ignored
+        until the first is lifted.
+
+        Args:
+            now: Monotonic time of the read; None for now
+
+        Returns:
+            The gestures completed by this read, usually none
+
+        Raises:
+            TouchError: If the controller does not respond
+        """
+        now = time.monotonic() if now is None else now
+        points = [point for point in self.controller.read_points() if point.action != TouchAction.UP]
+        contact = self._contact
+        gestures = []
+        if contact is None:
+            if points:
+                x, y = self.map_point(points[0].x, points[0].y)
+                self._contact = _Contact(points[0].id, now, x, y, x, y)
+            return gestures
+
+        point = next((point for point in points if point.id == contact.id), None)
+        if point is None:
+            gesture = self._lifted(contact, now)
+            if gesture is not None:
+                gestures.append(gesture)
+            self._contact = None
+        else:
+            contact.last_x, contact.last_y = self.map_point(point.x, point.y)
+            if (not contact.long_pressed and now - contact.started >= LONG_PRESS_S
+                    and self._distance(contact) < SWIPE_MIN_DISTANCE):
+                contact.long_pressed = True
+                gestures.append(self._gesture(GestureType.LONG_PRESS, contact, now))
+
+        for gesture in gestures:
+            for listener in list(self._listeners):
+                try:
+                    listener(gesture)
+                except Exception as e:
+                    print(f"Warning: Touch listener failed: {e}")
+        return gestures
+
+    @staticmethod
+    def _distance(contact: _Contact) -> float:
+        return math.hypot(contact.last_x - contact.x, contact.last_y - contact.y)
+
+    @staticmethod
+    def _gesture(kind: GestureType, contact: _Contact, now: float,
+                 direction: Optional[SwipeDirection] = None) -> TouchGesture:
+        return TouchGesture(kind, contact.x, contact.y, contact.last_x, contact.last_y, now - contact.started,
+                            direction)
+
+    def _lifted(self, contact: _Contact, now: float) -> Optional[TouchGesture]:
+        if contact.long_pressed:
+            return None
+        if self._distance(contact) >= SWIPE_MIN_DISTANCE:
+            dx, dy = contact.last_x - contact.x, contact.last_y - contact.y
+            if abs(dx) >= abs(dy):
+                direction = SwipeDirection.RIGHT if dx > 0 else SwipeDirection.LEFT
+            else:
+                direction = SwipeDirection.DOWN if dy > 0 else SwipeDirection.UP
+            return self._gesture(GestureType.SWIPE, contact, now, direction)
+        # A hold that was lifted before a poll saw it reach LONG_PRESS_S is still a long press
+        kind = GestureType.TAP if now - contact.started < LONG_PRESS_S else GestureType.LONG_PRESS
+        return self._gesture(kind, contact, now)
+
+    def start(self) -> None:
+        """Poll the controller every poll_interval_s seconds on a background thread, calling the listeners."""
+        if self._thread is not None:
+            return
+        self._stop.clear()
+
+        def loop():
+            failing = False
+            while not self._stop.wait(self.poll_interval_s):
+                try:
+                    self.poll()
+                    failing = False
+                except TouchError as e:
+                    if not failing:
+                        print(f"Warning: Touch controller read failed: {e}")
+                    failing = True
+
+        self._thread = threading.Thread(target=loop, name="touch-panel", daemon=True)
+        self._thread.start()
+
+    def stop(self) -> None:
+        """Stop polling."""
+        self._stop.set()
+        if self._thread is not None:
+            self._thread.join()
+            self._thread = None
+
+    def close(self) -> None:
+        self.stop()
+        self.controller.close()
+
+
+class TouchButtons(ButtonSource):
+    """Buttons from touch gestures: swipe up for UP, swipe down for DOWN and tap for SELECT."""
+
+    GESTURE_BUTTONS = {SwipeDirection.UP: Button.UP, SwipeDirection.DOWN: Button.DOWN}
+
+    def __init__(self, panel: TouchPanel):
+        """
+        Args:
+            panel: Touch panel to poll, closed by close(); do not start() it as well
+        """
+        self.panel = panel
+
+    def read(self, timeout: Optional[float] = None) -> Optional[Button]:
+        deadline = None if timeout is None else time.monotonic() + timeout
+        while True:
+            for gesture in self.panel.poll():
+                if gesture.type == GestureType.TAP:
+                    return Button.SELECT
+                if gesture.type == GestureType.SWIPE and gesture.direction in self.GESTURE_BUTTONS:
+                    return self.GESTURE_BUTTONS[gesture.direction]
+            if deadline is not None and time.monotonic() >= deadline:
+                return None
+            wait_s = self.panel.poll_interval_s
+            if deadline is not None:
+                wait_s = min(wait_s, max(0.0, deadline - time.monotonic()))
+            time.sleep(wait_s)
+
+    def close(self) -> None:
+        self.panel.close()
@@ -0,0 +1,4 @@
+from .formatting import (
+    Formatter, LocaleConventions, LocaleError, device_locale, format_date, format_number,
+    format_temperature, format_time, get_formatter, set_device_locale,
+)
@@ -0,0 +1,71 @@
+#!/usr/bin/env python3
+"""
+Locale formatting unit tests for CM5 SDK.
+"""
+
+import datetime
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.i18n.formatting import (
+    Formatter, LocaleError, device_locale, normalize_locale, set_device_locale,
+)
+
+
+MOMENT = datetime.datetime(2025, 3, 7, 14, 5, 9)
+
+
+class TestFormatting(unittest.TestCase):
+    """Test cases for locale-aware formatting."""
+
+    def test_dates(self):
+        """Test date order, separators and names follow the locale."""
+        self.assertEqual(Formatter("en_US").date(MOMENT), "3/7/2025")
+        self.assertEqual(Formatter("en_GB").date(MOMENT), "07/03/2025")
+        self.assertEqual(Formatter("de_DE").date(MOMENT), "07.03.2025")
+        self.assertEqual(Formatter("ja_JP").date(MOMENT), "2025/03/07")
+        self.assertEqual(Formatter("en_US").date(MOMENT, "medium"), "Mar 7, 2025")
+        self.assertEqual(Formatter("de_DE").date(MOMENT, "long"), "Fr, 7. März 2025")
+        with self.assertRaises(LocaleError):
+            Formatter("en_US").date(MOMENT, "huge")
+
+    def test_times(self):
+        """Test 12- and 24-hour clocks."""
+        self.assertEqual(Formatter("en_US").time(MOMENT), "2:05 PM")
+        self.assertEqual(Formatter("en_US").time(datetime.time(0, 30)), "12:30 AM")
+        self.assertEqual(Formatter("fr_FR").time(MOMENT, seconds=True), "14:05:09")
+        self.assertEqual(Formatter("en_US").time(MOMENT, hour24=True), "14:05")
+
+    def test_numbers_and_temperatures(self):
+        """Test separators and temperature units."""
+        self.assertEqual(Formatter("en_US").number(1234567.891, 2), "1,234,567.89")
+        self.assertEqual(Formatter("de_DE").number(-1234.5), "-1.234,5")
+        self.assertEqual(Formatter("fr_FR").number(12345), "12 345")
+        self.assertEqual(Formatter("en_US").temperature(21.5), "71°F")
+        self.assertEqual(Formatter("de_DE").temperature(21.46, 1), "21,5°C")
+        self.assertEqual(Formatter("en_GB").temperature(-0.2), "0°C")
+
+    def test_device_locale(self):
+        """Test the SDK setting beats the system locale, and regions fall back by language."""
+        self.assertEqual(normalize_locale("de_AT.UTF-8@euro"), "de_AT")
+        self.assertEqual(Formatter("de_AT").conventions.tag, "de_DE")
+        self.assertEqual(Formatter("xx_YY").conventions.tag, "en_US")
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "locale.conf")
+            with patch.dict(os.environ, {"LANG": "fr_FR.UTF-8"}, clear=True):
+                self.assertEqual(device_locale(path), "fr_FR")
+                set_device_locale("en-gb", path)
+                self.assertEqual(device_locale(path), "en_GB")
+            with patch.dict(os.environ, {"LANG": "C.UTF-8"}, clear=True):
+                self.assertEqual(device_locale(os.path.join(tmpdir, "missing")), "en_US")
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
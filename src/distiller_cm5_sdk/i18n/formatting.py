@@ -0,0 +1,285 @@
+#!/usr/bin/env python3
+"""
+Locale-aware formatting for CM5 SDK.
+
+Formats dates, times, numbers and temperatures following the device locale, so
+clock and dashboard layouts do not hardcode US conventions. The conventions
+are built in rather than taken from the C library's locale database, which is
+often not generated on minimal device images.
+
+The device locale is, in order of precedence:
+
+    DISTILLER_LOCALE=de_DE                 # per process
+    locale = de_DE                         # /opt/distiller-cm5-sdk/locale.conf
+    LC_ALL / LC_TIME / LANG                # system locale
+    en_US
+"""
+
+import datetime
+import os
+from dataclasses import dataclass
+from typing import Dict, Optional, Tuple, Union
+
+
+LOCALE_ENV_VAR = "DISTILLER_LOCALE"
+LOCALE_CONFIG_PATH = "/opt/distiller-cm5-sdk/locale.conf"
+DEFAULT_LOCALE = "en_US"
+
+
+class LocaleError(Exception):
+    """Custom exception for locale errors."""
+    pass
+
+
+@dataclass(frozen=True)
+class LocaleConventions:
+    """
+    Formatting conventions of a locale.
+
+    Date patterns use {d}, {dd}, {m}, {mm}, {y}, {month} (abbreviated name),
+    {month_full} and {weekday} (abbreviated name) placeholders.
+    """
+    tag: str
+    short_date: str
+    medium_date: str
+    long_date: str
+    hour24: bool
+    decimal: str
+    thousands: str
+    fahrenheit: bool
+    months: Tuple[str, ...]             # abbreviated, January first
+    months_full: Tuple[str, ...]
+    weekdays: Tuple[str, ...]           # abbreviated, Monday first
+    am_pm: Tuple[str, str] = ("AM", "PM")
+
+
+_EN_MONTHS = ("Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec")
+_EN_MONTHS_FULL = ("January", "February", "March", "April", "May", "June", "July", "August",
+                   "September", "October", "November", "December")
+_EN_WEEKDAYS = ("Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun")
+
+LOCALES: Dict[str, LocaleConventions] = {c.tag: c for c in (
+    LocaleConventions("en_US", "{m}/{d}/{y}", "{month} {d}, {y}", "{weekday}, {month_full} {d}, {y}",
+                      False, ".", ",", True, _EN_MONTHS, _EN_MONTHS_FULL, _EN_WEEKDAYS),
+    LocaleConventions("en_GB", "{dd}/{mm}/{y}", "{d} {month} {y}", "{weekday} {d} {month_full} {y}",
+                      True, ".", ",", False, _EN_MONTHS, _EN_MONTHS_FULL, _EN_WEEKDAYS),
+    LocaleConventions("de_DE", "{dd}.{mm}.{y}", "{d}. {month} {y}", "{weekday}, {d}. {month_full} {y}",
+                      True, ",", ".", False,
+                      ("Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez"),
+                      ("Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August",
+                       "September", "Oktober", "November", "Dezember"),
+                      ("Mo", "Di", "Mi", "Do", "Fr", "Sa", "So")),
+    LocaleConventions("fr_FR", "{dd}/{mm}/{y}", "{d} {month} {y}", "{weekday} {d} {month_full} {y}",
+                      True, ",", " ", False,
+                      ("janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.",
+                       "nov.", "déc."),
+                      ("janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août",
+                       "septembre", "octobre", "novembre", "décembre"),
+                      ("lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim.")),
+    LocaleConventions("es_ES", "{dd}/{mm}/{y}", "{d} {month} {y}", "{weekday}, {d} de {month_full} de {y}",
+                      True, ",", ".", False,
+                      ("ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic"),
+                      ("enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto",
+                       "septiembre", "octubre", "noviembre", "diciembre"),
+                      ("lun", "mar", "mié", "jue", "vie", "sáb", "dom")),
+    LocaleConventions("ja_JP", "{y}/{mm}/{dd}", "{y}年{m}月{d}日", "{y}年{m}月{d}日({weekday})",
+                      True, ".", ",", False,
+                      tuple(f"{n}月" for n in range(1, 13)), tuple(f"{n}月" for n in range(1, 13)),
+                      ("月", "火", "水", "木", "金", "土", "日"), ("午前", "午後")),
+    LocaleConventions("zh_CN", "{y}/{m}/{d}", "{y}年{m}月{d}日", "{y}年{m}月{d}日 {weekday}",
+                      True, ".", ",", False,
+                      tuple(f"{n}月" for n in range(1, 13)), tuple(f"{n}月" for n in range(1, 13)),
+                      ("周一", "周二", "周三", "周四", "周五", "周六", "周日"), ("上午", "下午")),
+)}
+
+# Language fallbacks for locales without their own table, e.g. de_AT -> de_DE
+_LANGUAGE_DEFAULTS = {tag.split("_")[0]: tag for tag in reversed(list(LOCALES))}
+
+
+def normalize_locale(tag: str) -> str:
+    """Reduce a POSIX locale name such as "de_AT.UTF-8@euro" or "en-GB" to "de_AT" / "en_GB"."""
+    tag = tag.split(".")[0].split("@")[0].replace("-", "_").strip()
+    if "_" in tag:
+        language, region = tag.split("_", 1)
+        return f"{language.lower()}_{region.upper()}"
+    return tag.lower()
+
+
+def resolve_conventions(tag: str) -> LocaleConventions:
+    """
+    Get the conventions for a locale, falling back to another region of the
+    same language, then to en_US.
+    """
+    tag = normalize_locale(tag)
+    if tag in LOCALES:
+        return LOCALES[tag]
+    language = tag.split("_")[0]
+    if language in _LANGUAGE_DEFAULTS:
+        return LOCALES[_LANGUAGE_DEFAULTS[language]]
+    return LOCALES[DEFAULT_LOCALE]
+
+
+def _read_locale_setting(path: str) -> Optional[str]:
+    try:
+        with open(path, "r") as f:
+            lines = f.readlines()
+    except OSError:
+        return None
+    for line in lines:
+        key, sep, value = line.partition("=")
+        if sep and key.strip().lower() == "locale" and value.strip():
+            return value.strip()
+    return None
+
+
+def device_locale(config_path: str = LOCALE_CONFIG_PATH) -> str:
+    """Device locale tag, resolved as described in the module docstring."""
+    for candidate in (os.environ.get(LOCALE_ENV_VAR), _read_locale_setting(config_path),
+                      os.environ.get("LC_ALL"), os.environ.get("LC_TIME"), os.environ.get("LANG")):
+        if candidate and candidate not in ("C", "POSIX") and not candidate.startswith("C."):
+            return normalize_locale(candidate)
+    return DEFAULT_LOCALE
+
+
+def set_device_locale(tag: str, config_path: str = LOCALE_CONFIG_PATH) -> None:
+    """
+    Persist the device locale.
+
+    Raises:
+        LocaleError: If the file cannot be written
+    """
+    try:
+        with open(config_path, "w") as f:
+            f.write(f"locale = {normalize_locale(tag)}\n")
+    except OSError as e:
+        raise LocaleError(f"Failed to write {config_path}: {e}")
+
+
+Number = Union[int, float]
+
+
+class Formatter:
+    """Formats values following one locale's conventions."""
+
+    def __init__(self, tag: Optional[str] = None):
+        """
+        Create a formatter.
+
+        Args:
+            tag: Locale such as "de_DE"; if None, the device locale
+        """
+        self.conventions = resolve_conventions(tag if tag is not None else device_locale())
+
+    def number(self, value: Number, decimals: Optional[int] = None, grouping: bool = True) -> str:
+        """
+        Format a number with the locale's decimal and thousands separators.
+
+        Args:
+            value: Number to format
+            decimals: Digits after the decimal separator; None keeps ints whole
+                      and shows floats with up to 2 significant decimals
+            grouping: Insert thousands separators
+        """
+        if decimals is None:
+            text = str(value) if isinstance(value, int) else f"{value:.2f}".rstrip("0").rstrip(".")
+        else:
+            text = f"{value:.{decimals}f}"
+        sign = ""
+        if text.startswith("-"):
+            sign, text = "-", text[1:]
+        whole, _, fraction = text.partition(".")
+        if grouping and len(whole) > 3:
+            groups = []
+            while len(whole) > 3:
+                groups.insert(0, whole[-3:])
+                whole = whole[:-3]
+            whole = self.conventions.thousands.join([whole] + groups)
+        result = sign + whole
+        if fraction:
+            result += self.conventions.decimal + fraction
+        return result
+
+    def date(self, value: Union[datetime.date, datetime.datetime], style: str = "short") -> str:
+        """
+        Format a date.
+
+        Args:
+            value: Date to format
+            style: "short" (numeric), "medium" (abbreviated month) or "long"
+                   (weekday and full month name)
+        """
+        patterns = {"short": self.conventions.short_date, "medium": self.conventions.medium_date,
+                    "long": self.conventions.long_date}
+        if style not in patterns:
+            raise LocaleError(f"Invalid date style '{style}'; expected short, medium or long")
+        return patterns[style].format(
+            d=value.day, dd=f"{value.day:02d}", m=value.month, mm=f"{value.month:02d}", y=value.year,
+            month=self.conventions.months[value.month - 1],
+            month_full=self.conventions.months_full[value.month - 1],
+            weekday=self.conventions.weekdays[value.weekday()],
+        )
+
+    def time(self, value: Union[datetime.time, datetime.datetime], seconds: bool = False,
+             hour24: Optional[bool] = None) -> str:
+        """
+        Format a time of day.
+
+        Args:
+            value: Time to format
+            seconds: Include seconds
+            hour24: Force 24-hour (True) or 12-hour (False) clock; None follows the locale
+        """
+        if hour24 is None:
+            hour24 = self.conventions.hour24
+        suffix = f":{value.second:02d}" if seconds else ""
+        if hour24:
+            return f"{value.hour:02d}:{value.minute:02d}{suffix}"
+        hour = value.hour % 12 or 12
+        am_pm = self.conventions.am_pm[value.hour >= 12]
+        return f"{hour}:{value.minute:02d}{suffix} {am_pm}"
+
+    def weekday(self, value: Union[datetime.date, datetime.datetime]) -> str:
+        """Abbreviated weekday name."""
+        return self.conventions.weekdays[value.weekday()]
+
+    def temperature(self, celsius: Number, decimals: int = 0, fahrenheit: Optional[bool] = None) -> str:
+        """
+        Format a temperature given in Celsius.
+
+        Args:
+            celsius: Temperature in degrees Celsius
+            decimals: Digits after the decimal separator
+            fahrenheit: Force the unit; None follows the locale (Fahrenheit for en_US)
+        """
+        if fahrenheit is None:
+            fahrenheit = self.conventions.fahrenheit
+        value = celsius * 9 / 5 + 32 if fahrenheit else celsius
+        value = round(value, decimals) + 0.0  # avoid "-0"
+        return f"{self.number(value, decimals, grouping=False)}°{'F' if fahrenheit else 'C'}"
+
+
+def get_formatter(tag: Optional[str] = None) -> Formatter:
+    """Get a Formatter for a locale, or for the device locale when tag is None."""
+    return Formatter(tag)
+
+
+def format_number(value: Number, decimals: Optional[int] = None, tag: Optional[str] = None) -> str:
+    """Format a number for the device locale (or tag)."""
+    return Formatter(tag).number(value, decimals)
+
+
+def format_date(value: Union[datetime.date, datetime.datetime], style: str = "short",
+                tag: Optional[str] = None) -> str:
+    """Format a date for the device locale (or tag)."""
+    return Formatter(tag).date(value, style)
+
+
+def format_time(value: Union[datetime.time, datetime.datetime], seconds: bool = False,
+                tag: Optional[str] = None) -> str:
+    """Format a time of day for the device locale (or tag)."""
+    return Formatter(tag).time(value, seconds)
+
+
+def format_temperature(celsius: Number, decimals: int = 0, tag: Optional[str] = None) -> str:
+    """Format a Celsius temperature in the device locale's unit (or tag's)."""
+    return Formatter(tag).temperature(celsius, decimals)
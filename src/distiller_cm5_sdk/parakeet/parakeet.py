@@ -11,6 +11,8 @@ import numpy as np
 import soundfile as sf
 import sounddevice as sd
 
+from distiller_cm5_sdk.device.privacy import capture_allowed, is_mic_muted
+from distiller_cm5_sdk.diagnostics.tracing import STAGE_STT, stage
 from distiller_cm5_sdk.hardware.audio.audio import Audio
 
 logging.basicConfig(level=logging.INFO,
@@ -116,10 +118,11 @@ class Parakeet:
         assert sample_rate == 16000, "Audio must be 16kHz"
         assert wave.ndim == 1, "Audio must be mono"
 
-        s = self.recognizer.create_stream()
-        s.accept_waveform(sample_rate, wave)
-        self.recognizer.decode_stream(s)
-        result = s.result.text.strip()
+        with stage(STAGE_STT, model="parakeet"):
+            s = self.recognizer.create_stream()
+            s.accept_waveform(sample_rate, wave)
+            self.recognizer.decode_stream(s)
+            result = s.result.text.strip()
 
         logging.info(f"Transcribed audio from '{audio_path}' (sample rate: {sample_rate} Hz): '{result}'")
 
@@ -144,9 +147,10 @@ class Parakeet:
         assert sample_rate == self.audio_config["rate"], f"Expected sample rate {self.audio_config['rate']}, got {sample_rate}"
         assert wave.ndim == 1, "Audio must be mono for transcribe_buffer"
 
-        s.accept_waveform(self.audio_config["rate"], wave)
-        self.recognizer.decode_stream(s)
-        result = s.result.text.strip()
+        with stage(STAGE_STT, model="parakeet"):
+            s.accept_waveform(self.audio_config["rate"], wave)
+            self.recognizer.decode_stream(s)
+            result = s.result.text.strip()
         logging.info(f"Transcribed audio from buffer (sample rate: {self.audio_config['rate']} Hz): '{result}'")
         yield result
 
@@ -172,6 +176,9 @@ class Parakeet:
     def _recording_thread(self):
         """Thread function for audio recording"""
         while self._is_recording:
+            if is_mic_muted():
+                logging.warning("Microphone muted, recording stopped")
+                break
             try:
                 data = self._stream.read(self.audio_config["chunk"])
                 self._audio_frames.append(data)
@@ -190,6 +197,10 @@ class Parakeet:
             logging.warning("Already recording")
             return False
             
+        if not capture_allowed(self.__class__.__name__.lower()):
+            logging.warning("Microphone is muted by the privacy switch")
+            return False
+            
         try:
             self._init_audio()
             
@@ -315,9 +326,16 @@ class Parakeet:
                 logging.error("Failed to load VAD model. Cannot proceed with auto_record_and_transcribe.")
                 return
 
+        if not capture_allowed("parakeet"):
+            logging.warning("Microphone is muted by the privacy switch")
+            return
+
         _buffer = []
         with sd.InputStream(channels=1, dtype="float32", samplerate=self.audio_config["rate"]) as s:
             while True:
+                if is_mic_muted():
+                    logging.warning("Microphone muted, speech recognition stopped")
+                    return
                 _samples, _ = s.read(int(0.1 * self.audio_config['rate']))
                 _samples = _samples.reshape(-1)
 
@@ -2,7 +2,10 @@ import subprocess
 import os
 import logging
 import re
+import signal
+import threading
 
+from distiller_cm5_sdk.diagnostics.tracing import STAGE_TTS, stage
 from distiller_cm5_sdk.hardware.audio.audio import Audio
 
 logging.basicConfig(
@@ -24,6 +27,9 @@ class Piper:
         self.voice_onnx = os.path.join(self.model_path, "en_US-amy-medium.onnx")
         self.voice_json = os.path.join(self.model_path, "en_US-amy-medium.onnx.json")
         self.piper = os.path.join(self.piper_path, "piper")
+        self._process = None
+        self._stopped = False
+        self._lock = threading.Lock()
 
         # Check if the model file exists
         if not os.path.exists(self.voice_onnx):
@@ -44,7 +50,8 @@ class Piper:
         command = f"""echo '{escaped_text}' | {self.piper} --model {self.voice_onnx} --config {self.voice_json} --output_file {output_file_path}"""
         logger.info(f"Piper exec command: {command}")
         try:
-            subprocess.run(command, shell=True, check=True, text=True, capture_output=True)
+            with stage(STAGE_TTS, model="piper", chars=len(text)):
+                subprocess.run(command, shell=True, check=True, text=True, capture_output=True)
             logger.info(f"Piper: Text '{text}' spoken successfully and saved to {output_file_path}")
             return output_file_path
         except subprocess.CalledProcessError as e:
@@ -86,12 +93,34 @@ class Piper:
         escaped_text = text.replace("'", "'\\''")
         command = f"""echo '{escaped_text}' | sudo {self.piper} --model {self.voice_onnx} --config {self.voice_json} --output-raw | aplay -D plughw:{hw_num} -r 22050 -f S16_LE -t raw"""
         logger.info(f"Piper: Piper exec command {command}")
-        try:
-            subprocess.run(command, shell=True, check=True)
+        # Synthesis and playback run as one pipeline, so the stage covers both
+        with stage(STAGE_TTS, model="piper", chars=len(text), playback=True):
+            with self._lock:
+                self._stopped = False
+                # A session of its own, so stop() can end the whole pipeline
+                self._process = subprocess.Popen(command, shell=True, start_new_session=True)
+            returncode = self._process.wait()
+            with self._lock:
+                self._process = None
+                stopped = self._stopped
+        if stopped:
+            logger.info("Piper: Streaming stopped")
+        elif returncode != 0:
+            logger.error(f"Piper: Error streaming audio: exit status {returncode}")
+            raise ValueError(f"Piper: Error streaming audio: exit status {returncode}")
+        else:
             logger.info(f"Piper: Text '{text}' streamed successfully")
-        except subprocess.CalledProcessError as e:
-            logger.error(f"Piper: Error streaming audio: {str(e)}")
-            raise ValueError(f"Piper: Error streaming audio: {str(e)}")
+
+    def stop(self):
+        """Stop speak_stream() playback in progress, from another thread; speak_stream() then returns normally."""
+        with self._lock:
+            if self._process is None:
+                return
+            self._stopped = True
+            try:
+                os.killpg(self._process.pid, signal.SIGTERM)
+            except (ProcessLookupError, PermissionError) as e:
+                logger.warning(f"Piper: Could not stop streaming: {e}")
 
 
 if __name__ == '__main__':
@@ -0,0 +1,4 @@
+from .buttons import Button, ButtonError, ButtonSource, EvdevButtons, GpioButtons, KeyboardButtons, parse_keymap
+from .calibration import Calibration, CalibrationWizard
+from .morse import GpioPressButton, KeyboardPresses, PressSource, SingleButton, read_morse_code
+from .recovery import MenuItem, RecoveryUI
@@ -0,0 +1,83 @@
+#!/usr/bin/env python3
+"""
+Entry point for the recovery UI: python3 -m distiller_cm5_sdk.recovery
+(or the bundled distiller-recovery.pyz).
+"""
+
+import argparse
+import os
+import shlex
+import sys
+
+from ..device.lock import LOCK_PATH, LockError, ScreenLock
+from ..hardware.eink.backends import BACKEND_ENV_VAR, PYTHON_BACKEND
+from ..hardware.eink.display import Display, DisplayError
+from .calibration import CalibrationWizard
+from .buttons import ButtonError, EvdevButtons, GpioButtons, KeyboardButtons, parse_keymap, parse_pins
+from .morse import GpioPressButton, KeyboardPresses, SingleButton
+from .recovery import RecoveryUI
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Distiller CM5 recovery UI")
+    parser.add_argument("--backend", default=os.environ.get(BACKEND_ENV_VAR, PYTHON_BACKEND),
+                        help="Display backend (default: python, which needs no compiled library)")
+    parser.add_argument("--gpio-pins", metavar="UP,DOWN,SELECT",
+                        help="GPIO line offsets of the buttons; reads stdin when omitted")
+    parser.add_argument("--gpio-chip", default="gpiochip0", help="GPIO chip of the button lines")
+    parser.add_argument("--evdev", nargs="*", metavar="DEVICE",
+                        help="Read USB keyboards and remotes (all of them when no devices are given)")
+    parser.add_argument("--keymap", metavar="SPEC",
+                        help='Key map for --evdev, e.g. "up=KEY_PAGEUP;down=KEY_PAGEDOWN;select=KEY_ENTER"')
+    parser.add_argument("--single-button", nargs="?", type=int, const=-1, metavar="GPIO",
+                        help="One button on this GPIO line (stdin when omitted): short press is next, "
+                             "long press select, and PINs are tapped in Morse code")
+    parser.add_argument("--factory-reset-cmd", metavar="COMMAND",
+                        help="Command run by the factory reset entry instead of the built-in reset")
+    parser.add_argument("--lock-file", default=LOCK_PATH,
+                        help="Screen lock configuration; the menu asks for a PIN when it sets one")
+    parser.add_argument("--calibrate", action="store_true",
+                        help="Run the display calibration wizard instead of the menu")
+    args = parser.parse_args(argv)
+
+    morse = None
+    try:
+        if args.single_button is not None:
+            morse = (KeyboardPresses() if args.single_button < 0
+                     else GpioPressButton(args.single_button, chip_name=args.gpio_chip))
+            buttons = SingleButton(morse)
+        elif args.gpio_pins:
+            buttons = GpioButtons(parse_pins(args.gpio_pins), chip_name=args.gpio_chip)
+        elif args.evdev is not None:
+            keymap = parse_keymap(args.keymap) if args.keymap else None
+            buttons = EvdevButtons(args.evdev or None, keymap=keymap)
+        else:
+            buttons = KeyboardButtons()
+    except ButtonError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+
+    reset_command = shlex.split(args.factory_reset_cmd) if args.factory_reset_cmd else None
+
+    try:
+        lock = ScreenLock.from_file(args.lock_file)
+    except LockError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        buttons.close()
+        return 1
+
+    try:
+        with Display(backend=args.backend) as display:
+            if args.calibrate:
+                return 0 if CalibrationWizard(display, buttons).run() is not None else 1
+            RecoveryUI(display, buttons, factory_reset_command=reset_command, lock=lock, morse=morse).run()
+    except DisplayError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    finally:
+        buttons.close()
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
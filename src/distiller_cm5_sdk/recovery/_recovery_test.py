@@ -0,0 +1,306 @@
+#!/usr/bin/env python3
+"""
+Recovery UI unit tests for CM5 SDK.
+"""
+
+import io
+import os
+import shutil
+import struct
+import subprocess
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import Mock, patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.device.lock import ScreenLock, hash_pin
+from distiller_cm5_sdk.hardware.eink.config import load_config
+from distiller_cm5_sdk.hardware.eink.display import DisplayMode, RotationMode
+from distiller_cm5_sdk.recovery.buttons import (
+    KEY_CODES, Button, ButtonError, ButtonSource, EvdevButtons, KeyboardButtons, parse_keymap, parse_pins,
+)
+from distiller_cm5_sdk.recovery.calibration import Calibration, CalibrationWizard
+from distiller_cm5_sdk.recovery.morse import KeyboardPresses, MorseDecoder, PressSource, SingleButton, read_morse_code
+from distiller_cm5_sdk.recovery.recovery import RecoveryUI
+
+
+class ScriptedButtons(ButtonSource):
+    """Replays a fixed sequence of presses, then reports end of input."""
+
+    def __init__(self, *presses):
+        self.presses = list(presses)
+
+    def read(self, timeout=None):
+        return self.presses.pop(0) if self.presses else None
+
+
+class ScriptedPresses(PressSource):
+    """Replays press durations, None for a pause, then reports end of input."""
+
+    def __init__(self, *durations):
+        self.durations = list(durations)
+
+    def read_press(self, timeout=None):
+        if not self.durations:
+            raise EOFError
+        return self.durations.pop(0)
+
+
+class TestRecoveryUI(unittest.TestCase):
+    """Test cases for the recovery menu."""
+
+    def setUp(self):
+        """Set up a mock display reporting the panel's dimensions."""
+        self.display = Mock()
+        self.display.get_dimensions.return_value = (128, 250)
+
+    def make_ui(self, *presses, **kwargs):
+        return RecoveryUI(self.display, ScriptedButtons(*presses), **kwargs)
+
+    def test_navigation_wraps(self):
+        """Test UP from the first entry wraps to the last."""
+        ui = self.make_ui(Button.UP)
+        ui.run()
+        self.assertEqual(ui.selected, len(ui.items) - 1)
+        # Splash, initial menu and one redraw
+        self.assertEqual(self.display.display_image.call_count, 3)
+        frame = self.display.display_image.call_args[0][0]
+        self.assertEqual(len(frame), 128 * 250 // 8)
+
+    @patch("distiller_cm5_sdk.recovery.recovery.subprocess.run")
+    def test_factory_reset_requires_confirmation(self, mock_run):
+        """Test the reset command only runs after SELECT confirms."""
+        mock_run.return_value = Mock(returncode=0, stdout="", stderr="")
+
+        self.make_ui(Button.SELECT, Button.DOWN, factory_reset_command=["reset"]).run()
+        mock_run.assert_not_called()
+
+        self.make_ui(Button.SELECT, Button.SELECT, Button.SELECT, factory_reset_command=["reset"]).run()
+        mock_run.assert_called_once()
+        self.assertEqual(mock_run.call_args[0][0], ["reset"])
+
+    @patch("distiller_cm5_sdk.recovery.recovery.factory_reset")
+    def test_builtin_factory_reset(self, mock_reset):
+        """Test the menu drives device.factory_reset() with panel confirmation."""
+        def reset(confirm, progress):
+            return confirm()
+        mock_reset.side_effect = reset
+
+        self.make_ui(Button.SELECT, Button.SELECT, Button.SELECT).run()
+        mock_reset.assert_called_once()
+        # Splash, menu, confirmation, result and the menu again
+        self.assertEqual(self.display.display_image.call_count, 5)
+
+    def test_locked_menu_requires_pin(self):
+        """Test the menu only appears after the PIN is entered with the buttons."""
+        lock = ScreenLock(hash_pin("10", iterations=1000), pin_length=2)
+        # Wrong PIN 00, then 1 (UP, SELECT) and 0 (SELECT)
+        ui = self.make_ui(Button.SELECT, Button.SELECT,
+                          Button.UP, Button.SELECT, Button.SELECT, Button.DOWN, lock=lock)
+        self.assertEqual(ui.items[-1].label, "Lock")
+        ui.run()
+        self.assertFalse(lock.locked)
+        self.assertEqual(ui.selected, 1)
+
+        lock.lock()
+        ui = self.make_ui(Button.DOWN, lock=lock)
+        ui.run()
+        self.assertEqual(ui.selected, 0)
+        self.assertTrue(lock.locked)
+
+    def test_single_button_pin(self):
+        """Test one button navigates the menu and enters the PIN in Morse code."""
+        lock = ScreenLock(hash_pin("10", iterations=1000), pin_length=2)
+        presses = ScriptedPresses(0.1, 0.5, 0.5, 0.5, 0.5, None,          # 1
+                                  0.5, 0.5, 0.5, 0.5, 0.5, None,          # 0
+                                  0.1, 0.1)                               # two entries down
+        ui = RecoveryUI(self.display, SingleButton(presses), lock=lock, morse=presses)
+        ui.run()
+        self.assertFalse(lock.locked)
+        self.assertEqual(ui.selected, 2)
+
+    @patch("distiller_cm5_sdk.recovery.recovery.subprocess.run")
+    def test_update_from_usb(self, mock_run):
+        """Test the newest wheel on a USB mount is installed."""
+        mock_run.return_value = Mock(returncode=0, stdout="", stderr="")
+        with tempfile.TemporaryDirectory() as root:
+            drive = os.path.join(root, "usb0")
+            os.mkdir(drive)
+            for name in ("distiller_cm5_sdk-0.1.0-py3-none-any.whl", "distiller_cm5_sdk-0.2.0-py3-none-any.whl"):
+                open(os.path.join(drive, name), "w").close()
+
+            ui = self.make_ui(Button.DOWN, Button.SELECT, Button.SELECT, Button.SELECT, usb_roots=[root])
+            ui.run()
+
+        command = mock_run.call_args[0][0]
+        self.assertIn("install", command)
+        self.assertTrue(command[-1].endswith("distiller_cm5_sdk-0.2.0-py3-none-any.whl"))
+
+    def test_show_logs_pages(self):
+        """Test the log viewer opens on the newest page and scrolls back."""
+        ui = self.make_ui()
+        ui.buttons = ScriptedButtons(Button.UP, Button.SELECT)
+        with patch.object(ui, "read_logs", return_value=[f"line {i}" for i in range(100)]):
+            ui.show_logs()
+        self.assertEqual(self.display.display_image.call_count, 2)
+
+
+class TestCalibrationWizard(unittest.TestCase):
+    """Test cases for the display calibration wizard."""
+
+    def setUp(self):
+        """Set up a mock display whose frame turns with the rotation."""
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.path = os.path.join(self.tmpdir.name, "eink.conf")
+        with open(self.path, "w") as f:
+            f.write("# Kitchen unit\nthreshold = 120\n")
+        self.rotation = RotationMode.NONE
+        self.display = Mock()
+        self.display.get_rotation.side_effect = lambda: self.rotation
+        self.display.set_rotation.side_effect = lambda rotation: setattr(self, "rotation", rotation)
+        self.display.get_dimensions.side_effect = lambda: (
+            (250, 128) if self.rotation in (RotationMode.CCW_90, RotationMode.CW_90) else (128, 250))
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def run_wizard(self, *presses):
+        return CalibrationWizard(self.display, ScriptedButtons(*presses), config_path=self.path).run()
+
+    def test_save(self):
+        """Test each step adjusts its value and the result is saved to eink.conf."""
+        result = self.run_wizard(Button.UP, Button.SELECT,                   # rotation ccw90
+                                 Button.DOWN, Button.DOWN, Button.SELECT,     # threshold 120 - 16
+                                 Button.UP, Button.UP, Button.SELECT,         # contrast 1.2
+                                 Button.SELECT)
+        self.assertEqual(result, Calibration(RotationMode.CCW_90, 104, 1.2))
+        config = load_config(self.path)
+        self.assertEqual((config.rotation, config.image_options.threshold, config.image_options.contrast),
+                         (RotationMode.CCW_90, 104, 1.2))
+        with open(self.path) as f:
+            self.assertTrue(f.read().startswith("# Kitchen unit\n"))
+
+        # The new rotation gets a full refresh, other changes partial ones
+        frames = self.display.display_image.call_args_list
+        self.assertEqual([call[0][1] for call in frames[:3]],
+                         [DisplayMode.FULL, DisplayMode.FULL, DisplayMode.PARTIAL])
+
+    def test_discard(self):
+        """Test discarding, or input ending, puts the rotation back and saves nothing."""
+        self.assertIsNone(self.run_wizard(Button.DOWN, Button.SELECT, Button.SELECT, Button.SELECT, Button.DOWN))
+        self.assertEqual(self.rotation, RotationMode.NONE)
+        self.assertIsNone(self.run_wizard(Button.UP))
+        self.assertEqual(self.rotation, RotationMode.NONE)
+        with open(self.path) as f:
+            self.assertEqual(f.read(), "# Kitchen unit\nthreshold = 120\n")
+
+    def test_limits(self):
+        """Test the threshold stops at its range, and UP on the last screen starts over."""
+        presses = [Button.SELECT] + [Button.UP] * 20 + [Button.SELECT, Button.SELECT, Button.UP,
+                                                          Button.SELECT, Button.SELECT, Button.SELECT, Button.SELECT]
+        self.assertEqual(self.run_wizard(*presses), Calibration(RotationMode.NONE, 255, 1.0))
+
+    def test_menu_entry(self):
+        """Test the recovery menu lays itself out again for the chosen rotation."""
+        ui = RecoveryUI(self.display, ScriptedButtons(Button.UP, Button.SELECT, Button.SELECT, Button.SELECT,
+                                                      Button.SELECT, Button.SELECT))
+        with patch("distiller_cm5_sdk.recovery.recovery.CalibrationWizard",
+                   lambda display, buttons: CalibrationWizard(display, buttons, config_path=self.path)):
+            ui.items[3].action()
+        self.assertEqual((ui.width, ui.height), (250, 128))
+
+
+class TestButtons(unittest.TestCase):
+    """Test cases for button input helpers."""
+
+    def test_keyboard_buttons(self):
+        """Test typed lines map to buttons and EOF ends input."""
+        buttons = KeyboardButtons(io.StringIO("u\nx\nj\n\n"))
+        self.assertEqual([buttons.read() for _ in range(4)],
+                         [Button.UP, Button.DOWN, Button.SELECT, None])
+
+    def test_morse_code(self):
+        """Test presses decode to digits, pauses end them, and long holds erase."""
+        decoder = MorseDecoder()
+        for duration in (0.5, 0.1, 0.1, 0.1, 0.1):
+            decoder.add(duration)
+        self.assertEqual((decoder.symbols, decoder.finish(), decoder.symbols), ("-....", "6", ""))
+        decoder.add(0.1)
+        self.assertIsNone(decoder.finish())
+
+        screens = []
+        presses = ScriptedPresses(0.1, 0.1, 0.1, 0.1, 0.1, None,         # 5
+                                  0.1, 0.5, None,                        # not a digit
+                                  0.5, 0.5, 0.5, 0.5, 0.1, None,         # 9
+                                  0.5, 2.5,                              # erases the dash, then the 9
+                                  2.5,
+                                  0.1, 0.1, 0.5, 0.5, 0.5, None,         # 2
+                                  0.5, 0.5, 0.1, 0.1, 0.1, None)         # 7
+        self.assertEqual(read_morse_code(presses, 3, lambda *screen: screens.append(screen)), "527")
+        self.assertIn(("59", "-", ""), screens)
+        self.assertIn(("5", "", "Not a digit: .-"), screens)
+        self.assertIn(("5", "--", ""), screens)
+        self.assertIsNone(read_morse_code(ScriptedPresses(0.1, None), 2))
+
+        presses = KeyboardPresses(io.StringIO("..---\n.-\nx\n"))
+        self.assertEqual([presses.read_press() for _ in range(11)],
+                         [0.15, 0.15, 0.6, 0.6, 0.6, None, 0.15, 0.6, None, 2.0, None])
+        with self.assertRaises(EOFError):
+            presses.read_press()
+        buttons = SingleButton(ScriptedPresses(0.2, 0.8, None))
+        self.assertEqual([buttons.read() for _ in range(4)], [Button.DOWN, Button.SELECT, None, None])
+
+    def test_parse_pins(self):
+        """Test GPIO pin specifications."""
+        self.assertEqual(parse_pins("16,20,26"), {Button.UP: 16, Button.DOWN: 20, Button.SELECT: 26})
+        with self.assertRaises(ButtonError):
+            parse_pins("16,20")
+
+    def test_evdev_buttons(self):
+        """Test key presses from an input device map to buttons; releases and repeats are ignored."""
+        def event(code, value, ev_type=1):
+            return struct.pack("llHHi", 0, 0, ev_type, code, value)
+
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "event0")
+            os.mkfifo(path)
+            buttons = EvdevButtons([path], keymap=parse_keymap("up=KEY_PAGEUP;select=KEY_ENTER,57"))
+            writer = os.open(path, os.O_WRONLY)
+            try:
+                os.write(writer, event(KEY_CODES["KEY_PAGEUP"], 1) + event(KEY_CODES["KEY_PAGEUP"], 0)
+                         + event(KEY_CODES["KEY_PAGEUP"], 2) + event(4, 0x70028, ev_type=4)
+                         + event(KEY_CODES["KEY_PAGEDOWN"], 1) + event(57, 1))
+                self.assertEqual(buttons.read(timeout=1), Button.UP)
+                self.assertEqual(buttons.read(timeout=1), Button.SELECT)
+                self.assertIsNone(buttons.read(timeout=0.05))
+            finally:
+                os.close(writer)
+                buttons.close()
+
+        with self.assertRaises(ButtonError):
+            parse_keymap("left=KEY_LEFT")
+        with self.assertRaises(ButtonError):
+            parse_keymap("up=KEY_NOPE")
+
+
+class TestBundle(unittest.TestCase):
+    """Test cases for the single-file recovery bundle."""
+
+    def test_bundle_imports(self):
+        """Test build.sh --recovery stages every module the bundle imports."""
+        repo = Path(__file__).resolve().parents[3]
+        with tempfile.TemporaryDirectory() as tmp:
+            shutil.copy(repo / "build.sh", tmp)
+            shutil.copytree(repo / "src" / "distiller_cm5_sdk", Path(tmp) / "src" / "distiller_cm5_sdk",
+                            ignore=shutil.ignore_patterns("__pycache__", "models", "piper"))
+            result = subprocess.run(["bash", "build.sh", "--recovery"], cwd=tmp, capture_output=True, text=True)
+            self.assertEqual(result.returncode, 0, result.stdout + result.stderr)
+            self.assertTrue((Path(tmp) / "dist" / "distiller-recovery.pyz").is_file())
+
+
+if __name__ == '__main__':
+    unittest.main(verbosity=2)
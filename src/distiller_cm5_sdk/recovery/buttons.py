@@ -0,0 +1,314 @@
+#!/usr/bin/env python3
+"""
+Button input for the CM5 recovery UI.
+
+Buttons are read from GPIO lines (libgpiod v1 Python bindings), from USB
+keyboards and presentation remotes (Linux evdev), or, for bench use over a
+serial console, from lines typed on stdin.
+"""
+
+import errno
+import fcntl
+import glob
+import os
+import select
+import struct
+import sys
+import time
+from enum import Enum
+from typing import Dict, List, Optional, TextIO
+
+
+class Button(Enum):
+    """Logical buttons used to drive the recovery menu."""
+    UP = "up"
+    DOWN = "down"
+    SELECT = "select"
+
+
+class ButtonError(Exception):
+    """Custom exception for button input errors."""
+    pass
+
+
+class ButtonSource:
+    """Source of button presses."""
+
+    def read(self, timeout: Optional[float] = None) -> Optional[Button]:
+        """
+        Wait for the next button press.
+
+        Args:
+            timeout: Seconds to wait, or None to wait indefinitely
+
+        Returns:
+            The pressed button, or None on timeout
+        """
+        raise NotImplementedError
+
+    def close(self) -> None:
+        """Release any resources held by the source."""
+        pass
+
+
+class GpioButtons(ButtonSource):
+    """Buttons wired to GPIO lines, pressed on a falling edge (active low)."""
+
+    def __init__(self, pins: Dict[Button, int], chip_name: str = "gpiochip0", debounce_ms: int = 50):
+        """
+        Request the button lines.
+
+        Args:
+            pins: GPIO line offset for each button
+            chip_name: GPIO chip the lines belong to
+            debounce_ms: Presses closer together than this on the same line are ignored
+
+        Raises:
+            ButtonError: If the gpiod module is missing or the lines can't be requested
+        """
+        try:
+            import gpiod
+        except ImportError as e:
+            raise ButtonError(f"GPIO buttons require the gpiod module: {e}")
+
+        self._buttons = {offset: button for button, offset in pins.items()}
+        self._debounce_s = debounce_ms / 1000.0
+        self._last_press = {}
+
+        try:
+            self._chip = gpiod.Chip(chip_name)
+            self._lines = self._chip.get_lines(list(self._buttons))
+            self._lines.request(consumer="recovery", type=gpiod.LINE_REQ_EV_FALLING_EDGE)
+        except (OSError, AttributeError) as e:
+            raise ButtonError(f"Failed to request button lines: {e}")
+
+    def read(self, timeout: Optional[float] = None) -> Optional[Button]:
+        deadline = None if timeout is None else time.monotonic() + timeout
+        while True:
+            wait_s = 1.0 if deadline is None else max(0.0, deadline - time.monotonic())
+            ready = self._lines.event_wait(sec=int(wait_s), nsec=int((wait_s % 1) * 1e9))
+            if ready:
+                for line in ready:
+                    line.event_read()
+                    offset = line.offset()
+                    now = time.monotonic()
+                    if now - self._last_press.get(offset, 0.0) < self._debounce_s:
+                        continue
+                    self._last_press[offset] = now
+                    return self._buttons[offset]
+            if deadline is not None and time.monotonic() >= deadline:
+                return None
+
+    def close(self) -> None:
+        self._lines.release()
+        self._chip.close()
+
+
+class KeyboardButtons(ButtonSource):
+    """
+    Buttons typed as lines on a text stream (a serial console by default).
+
+    'u' or 'k' is UP, 'd' or 'j' is DOWN and an empty line is SELECT. Reads
+    block until a line arrives, so the timeout is ignored.
+    """
+
+    _KEYS = {"u": Button.UP, "k": Button.UP, "d": Button.DOWN, "j": Button.DOWN, "": Button.SELECT}
+
+    def __init__(self, stream: TextIO = sys.stdin):
+        self._stream = stream
+
+    def read(self, timeout: Optional[float] = None) -> Optional[Button]:
+        while True:
+            line = self._stream.readline()
+            if not line:
+                return None  # end of input
+            button = self._KEYS.get(line.strip().lower())
+            if button is not None:
+                return button
+
+
+# Linux input event codes (linux/input-event-codes.h) accepted in key maps
+KEY_CODES = {
+    "KEY_ESC": 1, "KEY_ENTER": 28, "KEY_B": 48, "KEY_DOT": 52, "KEY_SPACE": 57, "KEY_F5": 63,
+    "KEY_KPENTER": 96, "KEY_HOME": 102, "KEY_UP": 103, "KEY_PAGEUP": 104, "KEY_LEFT": 105,
+    "KEY_RIGHT": 106, "KEY_END": 107, "KEY_DOWN": 108, "KEY_PAGEDOWN": 109, "KEY_VOLUMEDOWN": 114,
+    "KEY_VOLUMEUP": 115, "KEY_PLAYPAUSE": 164, "KEY_NEXTSONG": 163, "KEY_PREVIOUSSONG": 165,
+    "KEY_SELECT": 0x161,
+}
+
+# Arrow keys and what presentation remotes send (page up/down, F5, B/period for blank)
+DEFAULT_KEYMAP = {
+    KEY_CODES[name]: button
+    for names, button in (
+        (("KEY_UP", "KEY_LEFT", "KEY_PAGEUP", "KEY_PREVIOUSSONG", "KEY_VOLUMEUP"), Button.UP),
+        (("KEY_DOWN", "KEY_RIGHT", "KEY_PAGEDOWN", "KEY_NEXTSONG", "KEY_VOLUMEDOWN"), Button.DOWN),
+        (("KEY_ENTER", "KEY_KPENTER", "KEY_SPACE", "KEY_SELECT", "KEY_PLAYPAUSE", "KEY_F5", "KEY_B",
+          "KEY_DOT"), Button.SELECT),
+    )
+    for name in names
+}
+
+EVDEV_GLOB = "/dev/input/by-id/*-event-kbd"
+
+_EV_KEY = 1
+_KEY_PRESS = 1
+_EVENT = struct.Struct("llHHi")     # struct input_event: timeval, type, code, value
+_EVIOCGRAB = 0x40044590
+
+
+class EvdevButtons(ButtonSource):
+    """
+    Buttons from USB keyboards and presentation remotes (Linux evdev).
+
+    Key presses are mapped to buttons; auto-repeat and releases are ignored.
+    Without explicit devices every keyboard under /dev/input/by-id is used,
+    and devices plugged in later are picked up while reading.
+    """
+
+    RESCAN_S = 2.0
+
+    def __init__(self, devices: Optional[List[str]] = None, keymap: Optional[Dict[int, Button]] = None,
+                 grab: bool = True):
+        """
+        Open the input devices.
+
+        Args:
+            devices: Event device paths; None discovers keyboards and remotes
+            keymap: Key code to button; None uses DEFAULT_KEYMAP (see parse_keymap())
+            grab: Take the devices exclusively, so key presses do not also reach
+                  the console or other programs
+
+        Raises:
+            ButtonError: If explicit devices can't be opened
+        """
+        self._keymap = dict(DEFAULT_KEYMAP if keymap is None else keymap)
+        self._grab = grab
+        self._discover = devices is None
+        self._fds: Dict[str, int] = {}
+        self._pending: List[Button] = []     # presses read together with an earlier one
+        self._last_scan = 0.0
+        for path in devices or []:
+            try:
+                self._open(path)
+            except OSError as e:
+                self.close()
+                raise ButtonError(f"Failed to open input device {path}: {e}")
+        self._rescan()
+
+    def _open(self, path: str) -> None:
+        fd = os.open(path, os.O_RDONLY | os.O_NONBLOCK)
+        if self._grab:
+            try:
+                fcntl.ioctl(fd, _EVIOCGRAB, 1)
+            except OSError:
+                pass  # not an evdev node, or grabbed elsewhere; still readable
+        self._fds[path] = fd
+
+    def _drop(self, path: str) -> None:
+        os.close(self._fds.pop(path))
+
+    def _rescan(self) -> None:
+        self._last_scan = time.monotonic()
+        if not self._discover:
+            return
+        for path in sorted(glob.glob(EVDEV_GLOB)):
+            if path not in self._fds:
+                try:
+                    self._open(path)
+                except OSError:
+                    continue
+
+    def read(self, timeout: Optional[float] = None) -> Optional[Button]:
+        deadline = None if timeout is None else time.monotonic() + timeout
+        while True:
+            if self._pending:
+                return self._pending.pop(0)
+            if self._discover and time.monotonic() - self._last_scan >= self.RESCAN_S:
+                self._rescan()
+            wait_s = self.RESCAN_S if deadline is None else max(0.0, min(deadline - time.monotonic(), self.RESCAN_S))
+            paths = {fd: path for path, fd in self._fds.items()}
+            if paths:
+                ready, _, _ = select.select(list(paths), [], [], wait_s)
+            else:
+                if not self._discover:
+                    return None  # every device is gone
+                time.sleep(wait_s)
+                ready = []
+            for fd in ready:
+                try:
+                    data = os.read(fd, _EVENT.size * 64)
+                except BlockingIOError:
+                    continue
+                except OSError as e:
+                    if e.errno == errno.ENODEV:
+                        self._drop(paths[fd])  # unplugged
+                        continue
+                    raise ButtonError(f"Failed to read {paths[fd]}: {e}")
+                for offset in range(0, len(data) - _EVENT.size + 1, _EVENT.size):
+                    _, _, ev_type, code, value = _EVENT.unpack_from(data, offset)
+                    if ev_type == _EV_KEY and value == _KEY_PRESS and code in self._keymap:
+                        self._pending.append(self._keymap[code])
+            if self._pending:
+                continue
+            if deadline is not None and time.monotonic() >= deadline:
+                return None
+
+    def close(self) -> None:
+        for path in list(self._fds):
+            self._drop(path)
+
+
+def parse_keymap(spec: str) -> Dict[int, Button]:
+    """
+    Parse a key map specification.
+
+    Args:
+        spec: Semicolon-separated button=keys entries, keys separated by
+              commas, as KEY_* names or numeric codes, e.g.
+              "up=KEY_PAGEUP,KEY_LEFT;down=KEY_PAGEDOWN;select=KEY_ENTER,28"
+
+    Returns:
+        Mapping of key code to button
+
+    Raises:
+        ButtonError: If the specification is malformed
+    """
+    keymap = {}
+    for entry in filter(None, (part.strip() for part in spec.split(";"))):
+        name, _, keys = entry.partition("=")
+        try:
+            button = Button(name.strip().lower())
+        except ValueError:
+            raise ButtonError(f"Unknown button '{name.strip()}' in key map")
+        for key in filter(None, (k.strip() for k in keys.split(","))):
+            if key.isdigit():
+                keymap[int(key)] = button
+            elif key.upper() in KEY_CODES:
+                keymap[KEY_CODES[key.upper()]] = button
+            else:
+                raise ButtonError(f"Unknown key '{key}' in key map")
+    if not keymap:
+        raise ButtonError(f"Empty key map '{spec}'")
+    return keymap
+
+
+def parse_pins(spec: str) -> Dict[Button, int]:
+    """
+    Parse a GPIO pin specification.
+
+    Args:
+        spec: Comma-separated line offsets in UP,DOWN,SELECT order, e.g. "16,20,26"
+
+    Returns:
+        Mapping of button to line offset
+
+    Raises:
+        ButtonError: If the specification is malformed
+    """
+    try:
+        offsets = [int(part) for part in spec.split(",")]
+    except ValueError:
+        raise ButtonError(f"Invalid pin specification '{spec}'")
+    if len(offsets) != 3:
+        raise ButtonError(f"Expected 3 pins (UP,DOWN,SELECT), got {len(offsets)}")
+    return dict(zip((Button.UP, Button.DOWN, Button.SELECT), offsets))
@@ -0,0 +1,218 @@
+#!/usr/bin/env python3
+"""
+Display calibration wizard for CM5 SDK.
+
+Panels differ from batch to batch, and a device may be mounted in any
+orientation. The wizard steps through the rotation, the threshold and the
+contrast with the device's buttons, previewing each change on the panel,
+and saves the result in the display config (eink.toml):
+
+    [display]
+    rotation = "ccw90"
+
+    [processing]
+    threshold = 136
+    contrast = 1.2
+
+Displays opened afterwards use the rotation, and process_image() and the
+distiller-eink commands use the threshold and contrast as their defaults.
+"""
+
+import textwrap
+from dataclasses import dataclass, replace
+from typing import Dict, Optional, Tuple
+
+from ..diagnostics.audit import AuditCategory, audit
+from ..hardware.eink.config import MAIN_DISPLAY, MAX_CONTRAST, MIN_CONTRAST, load_image_profile, save_config_values
+from ..hardware.eink.display import DisplayError, DisplayMode, RotationMode
+from ..hardware.eink.font import FONT_HEIGHT, FONT_WIDTH
+from ..hardware.eink.graphics import Color, Framebuffer
+from ..hardware.eink.imaging import WHITE_LEVEL, DitherMode, adjust_contrast, dither_grayscale
+from .buttons import Button, ButtonSource
+
+
+THRESHOLD_STEP = 8
+CONTRAST_STEP = 0.1
+
+# Gray levels in the step wedge of the preview
+WEDGE_STEPS = 8
+
+CHAR_WIDTH = FONT_WIDTH + 1
+LINE_HEIGHT = FONT_HEIGHT + 2
+MARGIN = 4
+
+_ROTATIONS = [RotationMode.NONE, RotationMode.CCW_90, RotationMode.ROTATE_180, RotationMode.CW_90]
+_ROTATION_SETTINGS = {RotationMode.NONE: "none", RotationMode.CCW_90: "ccw90",
+                      RotationMode.ROTATE_180: "180", RotationMode.CW_90: "cw90"}
+_STEPS = ("Rotation", "Threshold", "Contrast")
+
+
+@dataclass
+class Calibration:
+    """Display settings chosen in the wizard."""
+    rotation: RotationMode = RotationMode.NONE
+    threshold: int = 128
+    contrast: float = 1.0
+
+    def config_values(self) -> Dict[str, str]:
+        """The settings as keys and values for save_config_values()."""
+        return {"rotation": _ROTATION_SETTINGS[self.rotation], "threshold": str(self.threshold),
+                "contrast": f"{self.contrast:g}"}
+
+
+def _sample(width: int, height: int) -> bytes:
+    """Grayscale test image: a step wedge above a smooth ramp, both from black to white."""
+    pixels = bytearray(width * height)
+    wedge_height = height // 2
+    for y in range(height):
+        for x in range(width):
+            if y < wedge_height:
+                step = min(x * WEDGE_STEPS // width, WEDGE_STEPS - 1)
+                level = step * WHITE_LEVEL // (WEDGE_STEPS - 1)
+            else:
+                level = x * WHITE_LEVEL // max(width - 1, 1)
+            pixels[y * width + x] = level
+    return bytes(pixels)
+
+
+class CalibrationWizard:
+    """
+    Interactive rotation, threshold and contrast tuning.
+
+    On each step UP/DOWN change the value and SELECT moves on. The last
+    screen saves with SELECT, starts over with UP and discards with DOWN.
+    Discarding, or the button source closing, puts the rotation back.
+    """
+
+    def __init__(self, display, buttons: ButtonSource, config_path: Optional[str] = None,
+                 display_name: str = MAIN_DISPLAY):
+        """
+        Args:
+            display: Initialized Display to calibrate
+            buttons: Source of button presses
+            config_path: Config file to save to; defaults as for load_config()
+            display_name: Display section the settings belong to
+        """
+        self.display = display
+        self.buttons = buttons
+        self.config_path = config_path
+        self.display_name = display_name
+
+    # -- Drawing --------------------------------------------------------------
+
+    def _screen(self, title: str, text: str) -> Tuple[Framebuffer, int]:
+        """A frame with an inverted title bar and wrapped text; returns it and the y below the text."""
+        width, height = self.display.get_dimensions()
+        fb = Framebuffer(width, height)
+        chars_per_line = (width - 2 * MARGIN) // CHAR_WIDTH
+        fb.draw_rect(0, 0, width, LINE_HEIGHT + 2, fill=True)
+        fb.draw_text(MARGIN, 2, title[:chars_per_line], Color.WHITE)
+        y = LINE_HEIGHT + 2 + MARGIN
+        for line in textwrap.wrap(text, chars_per_line):
+            fb.draw_text(MARGIN, y, line)
+            y += LINE_HEIGHT
+        return fb, y
+
+    def render_step(self, step: int, calibration: Calibration, mode: DisplayMode = DisplayMode.PARTIAL) -> None:
+        """Draw one step with its current value and a preview."""
+        value = {0: _ROTATION_SETTINGS[calibration.rotation], 1: str(calibration.threshold),
+                 2: f"{calibration.contrast:.1f}"}[step]
+        fb, y = self._screen(f"Calibrate {step + 1}/{len(_STEPS)}",
+                             f"{_STEPS[step]}: {value}. UP/DOWN change, SELECT next.")
+        width, height = fb.width, fb.height
+        if step == 0:
+            # The frame should touch every edge of the panel, and the title read the right way up
+            fb.draw_rect(0, 0, width, height)
+            fb.draw_text(MARGIN, height - MARGIN - FONT_HEIGHT, "BOTTOM")
+        else:
+            preview_width = width - 2 * MARGIN
+            preview_height = height - y - MARGIN
+            if preview_width > 0 and preview_height > 1:
+                pixels = adjust_contrast(_sample(preview_width, preview_height), calibration.contrast)
+                wedge_height = preview_height // 2
+                split = preview_width * wedge_height
+                # Thresholded wedge shows which grays turn black; the dithered ramp shows the tone curve
+                wedge = dither_grayscale(pixels[:split], preview_width, wedge_height, DitherMode.NONE,
+                                         calibration.threshold)
+                ramp = dither_grayscale(pixels[split:], preview_width, preview_height - wedge_height,
+                                        DitherMode.FLOYD_STEINBERG, calibration.threshold)
+                fb.blit(wedge, MARGIN, y)
+                fb.blit(ramp, MARGIN, y + wedge_height)
+        self.display.display_image(fb.to_bytes(), mode)
+
+    def render_summary(self, calibration: Calibration) -> None:
+        """Draw the chosen settings and the save, restart and discard choices."""
+        fb, _ = self._screen("Calibration", f"Rotation {_ROTATION_SETTINGS[calibration.rotation]}, "
+                                            f"threshold {calibration.threshold}, contrast {calibration.contrast:.1f}. "
+                                            "SELECT to save, UP to start over, DOWN to discard.")
+        self.display.display_image(fb.to_bytes(), DisplayMode.PARTIAL)
+
+    def show_message(self, title: str, text: str) -> None:
+        """Show a titled, word-wrapped message."""
+        fb, _ = self._screen(title, text)
+        self.display.display_image(fb.to_bytes(), DisplayMode.PARTIAL)
+
+    # -- Wizard ---------------------------------------------------------------
+
+    def _adjust(self, step: int, calibration: Calibration, direction: int) -> Calibration:
+        if step == 0:
+            index = _ROTATIONS.index(calibration.rotation)
+            rotation = _ROTATIONS[(index + direction) % len(_ROTATIONS)]
+            self.display.set_rotation(rotation)
+            return replace(calibration, rotation=rotation)
+        if step == 1:
+            return replace(calibration, threshold=min(max(calibration.threshold + direction * THRESHOLD_STEP, 0), 255))
+        contrast = round(calibration.contrast + direction * CONTRAST_STEP, 2)
+        return replace(calibration, contrast=min(max(contrast, MIN_CONTRAST), MAX_CONTRAST))
+
+    def run(self) -> Optional[Calibration]:
+        """
+        Run the wizard until the settings are saved or discarded.
+
+        Returns:
+            The saved settings, or None if they were discarded, could not be
+            saved or the button source closed
+        """
+        try:
+            profile = load_image_profile(self.config_path)
+            start = Calibration(RotationMode(self.display.get_rotation()), profile.threshold, profile.contrast)
+        except DisplayError as e:
+            print(f"Warning: {e}; starting from the default settings")
+            start = Calibration(RotationMode(self.display.get_rotation()))
+
+        calibration = start
+        step = 0
+        mode = DisplayMode.FULL
+        while True:
+            if step < len(_STEPS):
+                self.render_step(step, calibration, mode)
+            else:
+                self.render_summary(calibration)
+            mode = DisplayMode.PARTIAL
+
+            button = self.buttons.read()
+            if button is None or (step == len(_STEPS) and button == Button.DOWN):
+                if calibration.rotation != start.rotation:
+                    self.display.set_rotation(start.rotation)
+                return None
+            if step == len(_STEPS):
+                if button == Button.UP:
+                    step = 0
+                    continue
+                return self._save(calibration)
+            if button == Button.SELECT:
+                step += 1
+            else:
+                calibration = self._adjust(step, calibration, 1 if button == Button.UP else -1)
+                if step == 0:
+                    mode = DisplayMode.FULL         # the whole frame moved
+
+    def _save(self, calibration: Calibration) -> Optional[Calibration]:
+        try:
+            save_config_values(calibration.config_values(), self.config_path, self.display_name)
+        except DisplayError as e:
+            self.show_message("Not saved", f"{e}. Press any button.")
+            self.buttons.read()
+            return None
+        audit(AuditCategory.CONFIG, "calibration", self.display_name, **calibration.config_values())
+        return calibration
@@ -0,0 +1,236 @@
+#!/usr/bin/env python3
+"""
+Morse code digit input from a single button for CM5 SDK.
+
+The most stripped-down SKU has one button and no touch panel or encoder.
+It can still take a short PIN or pairing code: digits are tapped in Morse
+code, a short press for a dot and a long one for a dash, and a pause ends
+the digit. A very long press erases the last digit.
+
+    1 .----    2 ..---    3 ...--    4 ....-    5 .....
+    6 -....    7 --...    8 ---..    9 ----.    0 -----
+
+The same button also drives the recovery menu through SingleButton: a short
+press moves to the next entry and a long press selects it.
+"""
+
+import sys
+import time
+from typing import Callable, List, Optional, TextIO
+
+from .buttons import Button, ButtonError, ButtonSource
+
+
+MORSE_DIGITS = {
+    "-----": "0", ".----": "1", "..---": "2", "...--": "3", "....-": "4",
+    ".....": "5", "-....": "6", "--...": "7", "---..": "8", "----.": "9",
+}
+
+DOT_MAX_S = 0.3         # presses up to this long are dots, longer ones dashes
+DIGIT_GAP_S = 1.2       # a pause this long after a press ends the digit
+ERASE_S = 2.0           # presses at least this long erase the last digit
+
+
+class PressSource:
+    """Source of presses of a single button, with how long each was held."""
+
+    def read_press(self, timeout: Optional[float] = None) -> Optional[float]:
+        """
+        Wait for the next press and release.
+
+        Args:
+            timeout: Seconds to wait for a press to start, or None to wait indefinitely
+
+        Returns:
+            Seconds the button was held, or None on timeout
+
+        Raises:
+            EOFError: If the input ended
+        """
+        raise NotImplementedError
+
+    def close(self) -> None:
+        """Release any resources held by the source."""
+        pass
+
+
+class GpioPressButton(PressSource):
+    """A button wired to a GPIO line, active low, timed from its edges."""
+
+    def __init__(self, pin: int, chip_name: str = "gpiochip0", debounce_ms: int = 20):
+        """
+        Request the button line.
+
+        Args:
+            pin: GPIO line offset
+            chip_name: GPIO chip the line belongs to
+            debounce_ms: Edges closer together than this are ignored
+
+        Raises:
+            ButtonError: If the gpiod module is missing or the line can't be requested
+        """
+        try:
+            import gpiod
+        except ImportError as e:
+            raise ButtonError(f"GPIO buttons require the gpiod module: {e}")
+
+        self._falling = gpiod.LineEvent.FALLING_EDGE
+        self._debounce_s = debounce_ms / 1000.0
+        try:
+            self._chip = gpiod.Chip(chip_name)
+            self._line = self._chip.get_line(pin)
+            self._line.request(consumer="recovery", type=gpiod.LINE_REQ_EV_BOTH_EDGES)
+        except (OSError, AttributeError) as e:
+            raise ButtonError(f"Failed to request button line: {e}")
+
+    def _edge(self, timeout: Optional[float]):
+        """The next edge as (pressed, time in seconds), or None on timeout."""
+        deadline = None if timeout is None else time.monotonic() + timeout
+        while True:
+            wait_s = 1.0 if deadline is None else max(0.0, deadline - time.monotonic())
+            if self._line.event_wait(sec=int(wait_s), nsec=int((wait_s % 1) * 1e9)):
+                event = self._line.event_read()
+                return event.type == self._falling, event.sec + event.nsec / 1e9
+            if deadline is not None and time.monotonic() >= deadline:
+                return None
+
+    def read_press(self, timeout: Optional[float] = None) -> Optional[float]:
+        while True:
+            edge = self._edge(timeout)
+            if edge is None:
+                return None
+            pressed, start = edge
+            if not pressed:
+                continue                # release of a press that started before
+            while True:
+                pressed, end = self._edge(None)
+                if not pressed and end - start >= self._debounce_s:
+                    return end - start
+
+    def close(self) -> None:
+        self._line.release()
+        self._chip.close()
+
+
+class KeyboardPresses(PressSource):
+    """
+    Presses typed as lines on a text stream, for bench use over a serial console.
+
+    Each '.' is a short press and each '-' a long one; the end of the line is
+    the pause ending a digit, so ".----" followed by Enter enters 1. 'x'
+    erases the last digit. Reads block until a line arrives.
+    """
+
+    _DURATIONS = {".": DOT_MAX_S / 2, "-": DOT_MAX_S * 2, "x": ERASE_S}
+
+    def __init__(self, stream: TextIO = sys.stdin):
+        self._stream = stream
+        self._pending: List[Optional[float]] = []
+
+    def read_press(self, timeout: Optional[float] = None) -> Optional[float]:
+        while not self._pending:
+            line = self._stream.readline()
+            if not line:
+                raise EOFError("end of input")
+            self._pending = [self._DURATIONS[c] for c in line.strip().lower() if c in self._DURATIONS] + [None]
+        return self._pending.pop(0)
+
+
+class MorseDecoder:
+    """Turns press durations into digits."""
+
+    def __init__(self, dot_max_s: float = DOT_MAX_S):
+        self.dot_max_s = dot_max_s
+        self.symbols = ""           # dots and dashes of the digit being entered
+
+    def add(self, duration_s: float) -> None:
+        """Add a press to the current digit."""
+        self.symbols += "." if duration_s <= self.dot_max_s else "-"
+
+    def finish(self) -> Optional[str]:
+        """
+        End the current digit.
+
+        Returns:
+            The digit, or None if the symbols are not one
+        """
+        symbols, self.symbols = self.symbols, ""
+        return MORSE_DIGITS.get(symbols)
+
+
+def read_morse_code(source: PressSource, length: int,
+                    on_change: Optional[Callable[[str, str, str], None]] = None,
+                    dot_max_s: float = DOT_MAX_S, digit_gap_s: float = DIGIT_GAP_S,
+                    erase_s: float = ERASE_S) -> Optional[str]:
+    """
+    Read a code of length digits tapped in Morse code.
+
+    Args:
+        source: The button
+        length: Number of digits
+        on_change: Called with the digits so far, the symbols of the digit
+                   being entered and a note (e.g. "Not a digit: .-") whenever
+                   one of them changes, to redraw the entry screen
+        dot_max_s: Longest press read as a dot
+        digit_gap_s: Pause ending a digit
+        erase_s: Shortest press erasing the last digit
+
+    Returns:
+        The digits, or None if the input ended first
+    """
+    decoder = MorseDecoder(dot_max_s)
+    code = ""
+    note = ""
+    while len(code) < length:
+        if on_change is not None:
+            on_change(code, decoder.symbols, note)
+        try:
+            duration = source.read_press(digit_gap_s if decoder.symbols else None)
+        except EOFError:
+            return None
+        note = ""
+        if duration is None:
+            symbols = decoder.symbols
+            if not symbols:
+                continue
+            digit = decoder.finish()
+            if digit is None:
+                note = f"Not a digit: {symbols}"
+            else:
+                code += digit
+        elif duration >= erase_s:
+            if decoder.symbols:
+                decoder.symbols = ""
+            else:
+                code = code[:-1]
+        else:
+            decoder.add(duration)
+    return code
+
+
+class SingleButton(ButtonSource):
+    """
+    Drives a menu made for UP/DOWN/SELECT from one button: a short press is
+    DOWN and a long one SELECT. UP is never reported.
+    """
+
+    def __init__(self, presses: PressSource, dot_max_s: float = DOT_MAX_S):
+        """
+        Args:
+            presses: The button; also usable for read_morse_code()
+            dot_max_s: Longest press read as DOWN
+        """
+        self.presses = presses
+        self.dot_max_s = dot_max_s
+
+    def read(self, timeout: Optional[float] = None) -> Optional[Button]:
+        try:
+            duration = self.presses.read_press(timeout)
+        except EOFError:
+            return None
+        if duration is None:
+            return None
+        return Button.DOWN if duration <= self.dot_max_s else Button.SELECT
+
+    def close(self) -> None:
+        self.presses.close()
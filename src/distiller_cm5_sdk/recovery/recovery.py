@@ -0,0 +1,369 @@
+#!/usr/bin/env python3
+"""
+Recovery UI for CM5 SDK.
+
+A self-contained splash screen and menu for the recovery partition, built
+from the e-ink display, graphics and button modules. Offers factory reset,
+software update from a USB drive, a log viewer and display calibration.
+"""
+
+import glob
+import os
+import subprocess
+import sys
+import textwrap
+from dataclasses import dataclass
+from typing import Callable, List, Optional, Sequence
+
+from ..device.factory_reset import RESET_WARNING, FactoryResetError, ResetProgress, factory_reset
+from ..device.lock import ScreenLock
+from ..hardware.eink.display import DisplayMode
+from ..hardware.eink.font import FONT_HEIGHT, FONT_WIDTH
+from ..hardware.eink.graphics import Color, Framebuffer
+from .buttons import Button, ButtonSource
+from .calibration import CalibrationWizard
+from .morse import PressSource, read_morse_code
+
+
+# Where USB drives are mounted, and the update packages looked for on them
+USB_MOUNT_ROOTS = ("/media", "/mnt")
+UPDATE_PATTERN = "distiller_cm5_sdk-*.whl"
+
+# Log sources, tried in order
+LOG_COMMAND = ("journalctl", "-b", "--no-pager", "-o", "cat", "-n", "200")
+LOG_FILE = "/var/log/syslog"
+
+COMMAND_TIMEOUT_S = 600
+
+CHAR_WIDTH = FONT_WIDTH + 1
+LINE_HEIGHT = FONT_HEIGHT + 2
+MARGIN = 4
+
+
+@dataclass
+class MenuItem:
+    """An entry in the recovery menu."""
+    label: str
+    action: Callable[[], None]
+
+
+class RecoveryUI:
+    """
+    Recovery splash screen and menu.
+
+    UP/DOWN move the selection and SELECT runs the highlighted entry. Every
+    screen the menu opens returns to the menu on the next button press. With
+    an enabled ScreenLock, the menu is only shown after the PIN is entered.
+    On single-button devices the PIN is tapped in Morse code (see morse.py).
+    """
+
+    def __init__(self, display, buttons: ButtonSource,
+                 factory_reset_command: Optional[Sequence[str]] = None,
+                 usb_roots: Sequence[str] = USB_MOUNT_ROOTS,
+                 lock: Optional[ScreenLock] = None, morse: Optional[PressSource] = None):
+        """
+        Create the recovery UI.
+
+        Args:
+            display: Initialized Display to draw on
+            buttons: Source of button presses
+            factory_reset_command: Command that performs the factory reset. If None,
+                                   device.factory_reset() is used.
+            usb_roots: Directories under which USB drives are mounted
+            lock: Screen lock gating the menu, or None for no lock
+            morse: Button PINs are tapped on in Morse code, e.g. the one behind
+                   a morse.SingleButton given as buttons; None to pick digits
+                   with UP/DOWN
+        """
+        self.display = display
+        self.buttons = buttons
+        self.factory_reset_command = factory_reset_command
+        self.usb_roots = usb_roots
+        self.lock = lock
+        self.morse = morse
+        self.width, self.height = display.get_dimensions()
+        self.chars_per_line = (self.width - 2 * MARGIN) // CHAR_WIDTH
+        self.items = [
+            MenuItem("Factory reset", self.factory_reset),
+            MenuItem("Update from USB", self.update_from_usb),
+            MenuItem("Show logs", self.show_logs),
+            MenuItem("Calibrate display", self.calibrate_display),
+        ]
+        if lock is not None and lock.enabled:
+            self.items.append(MenuItem("Lock", self.lock_screen))
+        self.selected = 0
+
+    # -- Drawing --------------------------------------------------------------
+
+    def _present(self, fb: Framebuffer, mode: DisplayMode = DisplayMode.PARTIAL) -> None:
+        self.display.display_image(fb.to_bytes(), mode)
+
+    def _title_bar(self, fb: Framebuffer, title: str) -> int:
+        """Draw an inverted title bar and return the y coordinate below it."""
+        fb.draw_rect(0, 0, self.width, LINE_HEIGHT + 2, fill=True)
+        fb.draw_text(MARGIN, 2, title[:self.chars_per_line], Color.WHITE)
+        return LINE_HEIGHT + 2 + MARGIN
+
+    def show_splash(self) -> None:
+        """Show the recovery splash screen with a full refresh."""
+        fb = Framebuffer(self.width, self.height)
+        title = "DISTILLER"
+        x = max(0, (self.width - len(title) * CHAR_WIDTH * 2) // 2)
+        fb.draw_text(x, self.height // 2 - 2 * FONT_HEIGHT, title, scale=2)
+        subtitle = "Recovery"
+        x = max(0, (self.width - len(subtitle) * CHAR_WIDTH) // 2)
+        fb.draw_text(x, self.height // 2 + FONT_HEIGHT, subtitle)
+        self._present(fb, DisplayMode.FULL)
+
+    def render_menu(self, mode: DisplayMode = DisplayMode.PARTIAL) -> None:
+        """Draw the menu with the current selection highlighted."""
+        fb = Framebuffer(self.width, self.height)
+        y = self._title_bar(fb, "Recovery")
+        for index, item in enumerate(self.items):
+            label = item.label[:self.chars_per_line]
+            if index == self.selected:
+                fb.draw_rect(0, y - 1, self.width, LINE_HEIGHT, fill=True)
+                fb.draw_text(MARGIN, y, label, Color.WHITE)
+            else:
+                fb.draw_text(MARGIN, y, label)
+            y += LINE_HEIGHT
+        self._present(fb, mode)
+
+    def show_message(self, title: str, text: str) -> None:
+        """Show a titled, word-wrapped message."""
+        fb = Framebuffer(self.width, self.height)
+        y = self._title_bar(fb, title)
+        for line in textwrap.wrap(text, self.chars_per_line):
+            if y + FONT_HEIGHT > self.height:
+                break
+            fb.draw_text(MARGIN, y, line)
+            y += LINE_HEIGHT
+        self._present(fb)
+
+    def render_pin(self, title: str, entered: int, digit: int, length: int, note: str = "") -> None:
+        """Draw the PIN entry screen: entered digits masked, the current one shown."""
+        fb = Framebuffer(self.width, self.height)
+        y = self._title_bar(fb, title)
+        cell = CHAR_WIDTH * 2 + 4
+        x = max(0, (self.width - cell * length) // 2)
+        y += LINE_HEIGHT
+        for index in range(length):
+            if index < entered:
+                text = "*"
+            elif index == entered:
+                text = str(digit)
+                fb.draw_rect(x, y - 2, cell - 2, FONT_HEIGHT * 2 + 4, fill=True)
+            else:
+                text = "_"
+            color = Color.WHITE if index == entered else Color.BLACK
+            fb.draw_text(x + 2, y, text, color, scale=2)
+            x += cell
+        y += FONT_HEIGHT * 2 + LINE_HEIGHT
+        for line in textwrap.wrap(note, self.chars_per_line):
+            fb.draw_text(MARGIN, y, line)
+            y += LINE_HEIGHT
+        self._present(fb)
+
+    def render_morse(self, title: str, code: str, symbols: str, length: int, note: str = "") -> None:
+        """Draw the Morse code entry screen: entered digits masked, the current digit's symbols below."""
+        fb = Framebuffer(self.width, self.height)
+        y = self._title_bar(fb, title)
+        cell = CHAR_WIDTH * 2 + 4
+        x = max(0, (self.width - cell * length) // 2)
+        y += LINE_HEIGHT
+        for index in range(length):
+            fb.draw_text(x + 2, y, "*" if index < len(code) else "_", scale=2)
+            x += cell
+        y += FONT_HEIGHT * 2 + LINE_HEIGHT
+        x = max(0, (self.width - len(symbols) * CHAR_WIDTH * 2) // 2)
+        fb.draw_text(x, y, symbols, scale=2)
+        y += FONT_HEIGHT * 2 + LINE_HEIGHT
+        help_text = "Short press . long press -, pause ends a digit. Hold to erase."
+        for line in textwrap.wrap(f"{note} {help_text}" if note else help_text, self.chars_per_line):
+            if y + FONT_HEIGHT > self.height:
+                break
+            fb.draw_text(MARGIN, y, line)
+            y += LINE_HEIGHT
+        self._present(fb)
+
+    def _wait(self) -> Optional[Button]:
+        return self.buttons.read()
+
+    # -- Menu loop ------------------------------------------------------------
+
+    def run(self) -> None:
+        """Show the splash and run the menu until the button source closes."""
+        self.show_splash()
+        if self.lock is not None and self.lock.locked and not self.unlock(self.lock):
+            return
+        self.render_menu(DisplayMode.FULL)
+        while True:
+            button = self._wait()
+            if button is None:
+                return
+            if button == Button.UP:
+                self.selected = (self.selected - 1) % len(self.items)
+            elif button == Button.DOWN:
+                self.selected = (self.selected + 1) % len(self.items)
+            else:
+                self.items[self.selected].action()
+            self.render_menu()
+
+    def enter_pin(self, title: str, length: int, note: str = "") -> Optional[str]:
+        """
+        Read a PIN digit by digit. UP/DOWN change the digit and SELECT accepts
+        it, or with a Morse button each digit is tapped in Morse code.
+
+        Returns:
+            The entered digits, or None if the button source closed
+        """
+        if self.morse is not None:
+            return read_morse_code(self.morse, length, lambda code, symbols, morse_note: self.render_morse(
+                title, code, symbols, length, morse_note or note))
+        pin = ""
+        digit = 0
+        while len(pin) < length:
+            self.render_pin(title, len(pin), digit, length, note)
+            button = self._wait()
+            if button is None:
+                return None
+            if button == Button.UP:
+                digit = (digit + 1) % 10
+            elif button == Button.DOWN:
+                digit = (digit - 1) % 10
+            else:
+                pin += str(digit)
+                digit = 0
+        return pin
+
+    def unlock(self, lock: ScreenLock) -> bool:
+        """Ask for the PIN until the lock opens. Returns False if input ends first."""
+        note = ""
+        while lock.locked:
+            remaining = lock.lockout_remaining()
+            if remaining > 0:
+                self.show_message("Locked", f"Too many attempts. Try again in {int(remaining) + 1} s.")
+                if self._wait() is None:
+                    return False
+                continue
+            pin = self.enter_pin("Enter PIN", lock.pin_length, note)
+            if pin is None:
+                return False
+            if not lock.unlock(pin):
+                note = "Wrong PIN"
+        return True
+
+    def confirm(self, title: str, text: str) -> bool:
+        """Ask for confirmation; SELECT confirms, any other button cancels."""
+        self.show_message(title, f"{text} SELECT to confirm, UP/DOWN to cancel.")
+        return self._wait() == Button.SELECT
+
+    def _run_command(self, title: str, command: Sequence[str]) -> None:
+        self.show_message(title, "Working, do not power off...")
+        try:
+            result = subprocess.run(list(command), capture_output=True, text=True,
+                                    timeout=COMMAND_TIMEOUT_S)
+        except (OSError, subprocess.TimeoutExpired) as e:
+            self.show_message(title, f"Failed: {e}")
+        else:
+            if result.returncode == 0:
+                self.show_message(title, "Done. Press any button.")
+            else:
+                detail = (result.stderr or result.stdout).strip().splitlines()
+                reason = detail[-1] if detail else f"exit code {result.returncode}"
+                self.show_message(title, f"Failed: {reason}")
+        self._wait()
+
+    # -- Actions --------------------------------------------------------------
+
+    def factory_reset(self) -> None:
+        """Erase user data after confirmation."""
+        title = "Factory reset"
+        if self.factory_reset_command is not None:
+            if self.confirm(title, RESET_WARNING):
+                self._run_command(title, self.factory_reset_command)
+            return
+
+        def show_progress(event: ResetProgress) -> None:
+            self.show_message(title, f"{event.index}/{event.total} {event.message}...")
+
+        try:
+            if not factory_reset(confirm=lambda: self.confirm(title, RESET_WARNING), progress=show_progress):
+                return
+            self.show_message(title, "Done. Press any button.")
+        except FactoryResetError as e:
+            self.show_message(title, f"Failed: {str(e).splitlines()[-1].strip(' -')}")
+        self._wait()
+
+    def lock_screen(self) -> None:
+        """Lock the menu and wait for the PIN again."""
+        self.lock.lock()
+        self.unlock(self.lock)
+
+    def calibrate_display(self) -> None:
+        """Run the calibration wizard, then lay the menu out for the chosen rotation."""
+        if CalibrationWizard(self.display, self.buttons).run() is not None:
+            self.show_message("Calibration", "Saved. Press any button.")
+            self._wait()
+        self.width, self.height = self.display.get_dimensions()
+        self.chars_per_line = (self.width - 2 * MARGIN) // CHAR_WIDTH
+
+    def find_update_packages(self) -> List[str]:
+        """Find SDK update packages on mounted USB drives, highest file name first."""
+        found = []
+        for root in self.usb_roots:
+            for depth in ("*", os.path.join("*", "*")):
+                found.extend(glob.glob(os.path.join(root, depth, UPDATE_PATTERN)))
+        return sorted(set(found), reverse=True)
+
+    def update_from_usb(self) -> None:
+        """Install the newest SDK package found on a USB drive."""
+        title = "USB update"
+        packages = self.find_update_packages()
+        if not packages:
+            self.show_message(title, f"No {UPDATE_PATTERN} found on USB. Press any button.")
+            self._wait()
+            return
+        package = packages[0]
+        if not self.confirm(title, f"Install {os.path.basename(package)}?"):
+            return
+        self._run_command(title, [sys.executable, "-m", "pip", "install",
+                                  "--force-reinstall", "--no-deps", package])
+
+    def read_logs(self) -> List[str]:
+        """Read recent system log lines from journald, falling back to syslog."""
+        try:
+            result = subprocess.run(list(LOG_COMMAND), capture_output=True, text=True, timeout=10)
+            if result.returncode == 0 and result.stdout:
+                return result.stdout.splitlines()
+        except (OSError, subprocess.TimeoutExpired):
+            pass
+        try:
+            with open(LOG_FILE, "r", errors="replace") as f:
+                return f.readlines()[-200:]
+        except OSError as e:
+            return [f"No logs available: {e}"]
+
+    def show_logs(self) -> None:
+        """Page through recent logs, newest last. UP/DOWN scroll, SELECT returns."""
+        lines = []
+        for entry in self.read_logs():
+            lines.extend(textwrap.wrap(entry.rstrip(), self.chars_per_line) or [""])
+
+        rows = (self.height - (LINE_HEIGHT + 2 + MARGIN)) // LINE_HEIGHT
+        top = max(0, len(lines) - rows)
+        while True:
+            fb = Framebuffer(self.width, self.height)
+            y = self._title_bar(fb, f"Logs {top + 1}-{min(top + rows, len(lines))}/{len(lines)}")
+            for line in lines[top:top + rows]:
+                fb.draw_text(MARGIN, y, line)
+                y += LINE_HEIGHT
+            self._present(fb)
+
+            button = self._wait()
+            if button == Button.UP:
+                top = max(0, top - rows)
+            elif button == Button.DOWN:
+                top = min(max(0, len(lines) - rows), top + rows)
+            else:
+                return
@@ -0,0 +1,2 @@
+from .api import ScriptApi, ScriptError
+from .runtime import ScriptRuntime
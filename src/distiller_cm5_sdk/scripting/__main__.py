@@ -0,0 +1,82 @@
+#!/usr/bin/env python3
+"""
+Automation scripts: python3 -m distiller_cm5_sdk.scripting run <script.lua> [options]
+"""
+
+import argparse
+import signal
+import sys
+
+from .api import ScriptError
+
+
+def _run(args) -> int:
+    from ..events.sources import publish_buttons
+    from ..hardware.eink.display import Display, DisplayError
+    from ..recovery.buttons import ButtonError, GpioButtons, parse_pins
+    from ..smarthome.endpoints import DeviceBridge
+    from .lua import LuaScript
+
+    display = audio = led = buttons = None
+    try:
+        if args.gpio_pins:
+            buttons = GpioButtons(parse_pins(args.gpio_pins), chip_name=args.gpio_chip)
+        if not args.no_display:
+            display = Display(backend=args.backend)
+    except (ButtonError, DisplayError) as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    if not args.no_audio:
+        try:
+            from ..hardware.audio.audio import Audio
+            audio = Audio(auto_check_config=False)
+        except Exception as e:
+            print(f"Warning: No audio for scripts: {e}")
+    if not args.no_led:
+        try:
+            from ..hardware.sam.led import LED
+            led = LED()
+        except Exception as e:
+            print(f"Warning: No LED for scripts: {e}")
+
+    bridge = DeviceBridge.from_device(buttons=buttons, temperature_path=None)
+    try:
+        script = LuaScript(args.script, display=display, audio=audio, led=led)
+        publish_buttons(bridge)
+        signal.signal(signal.SIGTERM, lambda signum, frame: script.stop())
+        bridge.start()
+        script.run()
+    except ScriptError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    except KeyboardInterrupt:
+        pass
+    finally:
+        bridge.stop()
+        if buttons is not None:
+            buttons.close()
+        if display is not None:
+            display.close()
+    return 0
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Run on-device automation scripts")
+    commands = parser.add_subparsers(dest="command_name", required=True)
+
+    run = commands.add_parser("run", help="Run a Lua script until interrupted")
+    run.add_argument("script", help="Script file; the images and sounds it uses are looked up next to it")
+    run.add_argument("--backend", help="Display backend (default: from eink.toml)")
+    run.add_argument("--gpio-pins", metavar="UP,DOWN,SELECT", help="GPIO line offsets of the buttons")
+    run.add_argument("--gpio-chip", default="gpiochip0", help="GPIO chip of the button lines")
+    run.add_argument("--no-display", action="store_true", help="Leave the display to other applications")
+    run.add_argument("--no-audio", action="store_true", help="Do not give scripts audio")
+    run.add_argument("--no-led", action="store_true", help="Do not give scripts the LED")
+    run.set_defaults(handler=_run)
+
+    args = parser.parse_args(argv)
+    return args.handler(args)
+
+
+if __name__ == "__main__":
+    sys.exit(main())
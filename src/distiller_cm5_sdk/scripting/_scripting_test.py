@@ -0,0 +1,128 @@
+#!/usr/bin/env python3
+"""
+Scripting API and runtime unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+from unittest.mock import Mock
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.events.bus import EventBus
+from distiller_cm5_sdk.hardware.eink.backends.mock import get_mock_transport
+from distiller_cm5_sdk.hardware.eink.display import Display
+from distiller_cm5_sdk.scripting.api import ScriptApi, ScriptError
+from distiller_cm5_sdk.scripting.runtime import ScriptRuntime
+
+
+class TestScriptApi(unittest.TestCase):
+    """Test cases for the device functions given to scripts."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.script_dir = os.path.join(self.tmpdir.name, "scripts")
+        os.mkdir(self.script_dir)
+        Path(self.script_dir, "chime.wav").write_bytes(b"RIFF")
+        Path(self.tmpdir.name, "secret.wav").write_bytes(b"RIFF")
+
+    def tearDown(self):
+        self.tmpdir.cleanup()
+
+    def test_devices(self):
+        """Test files stay inside the script directory and values are clamped."""
+        audio, led = Mock(), Mock()
+        api = ScriptApi(self.script_dir, audio=audio, led=led, bus=EventBus())
+        api.play("chime.wav")
+        audio.play.assert_called_once_with(os.path.join(os.path.realpath(self.script_dir), "chime.wav"))
+        for name in ("../secret.wav", os.path.join(self.tmpdir.name, "secret.wav"), "missing.wav"):
+            with self.subTest(name=name):
+                with self.assertRaises(ScriptError):
+                    api.play(name)
+
+        api.set_volume(250)
+        audio.set_speaker_volume.assert_called_once_with(100)
+        api.set_led(300, -5, 128.7, 2)
+        led.set_led_color.assert_called_once_with(255, 0, 128, brightness=1.0)
+        with self.assertRaises(ScriptError):
+            api.clear()         # no display
+
+    def test_display_text(self):
+        """Test scripts can show text on the display."""
+        display = Display(backend="mock")
+        try:
+            ScriptApi(self.script_dir, display=display, bus=EventBus()).show_text(21.5)
+            self.assertNotEqual(set(get_mock_transport(display).last_frame), {0xFF})
+        finally:
+            display.close()
+
+    def test_events(self):
+        """Test event callbacks go through dispatch and end with close()."""
+        bus = EventBus()
+        dispatched, seen = [], []
+        api = ScriptApi(self.script_dir, bus=bus, dispatch=lambda callback, args: dispatched.append(args))
+        api.on("button.*", lambda name, data: None)
+        bus.subscribe("weather.*", seen.append)
+        bus.publish("button.double_press", button="select")
+        api.emit("weather.updated", {"temperature": 21})
+        self.assertEqual(dispatched, [("button.double_press", {"button": "select"})])
+        self.assertEqual(seen[0].data, {"temperature": 21})
+
+        api.close()
+        bus.publish("button.long_press", button="up")
+        self.assertEqual(len(dispatched), 1)
+
+
+class TestScriptRuntime(unittest.TestCase):
+    """Test cases for callback and timer scheduling."""
+
+    def test_callbacks_and_timers(self):
+        """Test callbacks run in order, timers when due, and a failing callback does not stop the rest."""
+        now = [0.0]
+        calls = []
+        runtime = ScriptRuntime(clock=lambda: now[0])
+
+        def fail():
+            raise RuntimeError("boom")
+
+        runtime.dispatch(calls.append, ("a",))
+        runtime.dispatch(fail)
+        runtime.dispatch(calls.append, ("b",))
+        once = runtime.after(5, lambda: calls.append("once"))
+        tick = runtime.every(2, lambda: calls.append("tick"))
+        self.assertTrue(runtime.run_pending())
+        self.assertEqual(calls, ["a", "b"])
+
+        now[0] = 2.0
+        runtime.run_pending()
+        now[0] = 5.0
+        runtime.run_pending()
+        self.assertEqual(calls, ["a", "b", "tick", "tick", "once"])  # due at 2, 4 and 5
+        runtime.cancel(tick)
+        runtime.cancel(once)    # already fired: no effect
+        now[0] = 20.0
+        runtime.run_pending()
+        self.assertEqual(len(calls), 5)
+        with self.assertRaises(ScriptError):
+            runtime.every(0, lambda: None)
+
+        runtime.stop()
+        self.assertFalse(runtime.run_pending())
+
+    def test_run_until_stopped(self):
+        """Test run() serves callbacks queued from other threads until stop()."""
+        runtime = ScriptRuntime()
+        calls = []
+        runtime.after(0, lambda: calls.append("timer"))
+        runtime.dispatch(calls.append, ("event",))
+        runtime.dispatch(runtime.stop)
+        runtime.run()
+        self.assertEqual(calls, ["timer", "event"])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,114 @@
+#!/usr/bin/env python3
+"""
+Functions available to automation scripts for CM5 SDK.
+
+ScriptApi is what a script may touch, independent of the language it is
+written in: the display, audio, the LED, and the event bus (which carries the
+button presses). Everything is checked here, since scripts are untrusted:
+files are confined to the script directory and values to their ranges.
+"""
+
+import os
+from typing import Any, Callable, Dict, List, Optional, Tuple
+
+from ..events.bus import Event, EventBus, get_bus
+
+
+class ScriptError(Exception):
+    """Custom exception for scripting errors."""
+    pass
+
+
+class ScriptApi:
+    """Device functions bound into scripts; callbacks are handed to the runtime's dispatch."""
+
+    def __init__(self, script_dir: str, display=None, audio=None, led=None,
+                 bus: Optional[EventBus] = None,
+                 dispatch: Optional[Callable[[Callable, Tuple], None]] = None):
+        """
+        Args:
+            script_dir: Directory scripts may read images and sounds from
+            display: hardware.eink Display, or None
+            audio: hardware.audio Audio, or None
+            led: hardware.sam LED, or None
+            bus: Bus for on() and emit(); defaults to get_bus()
+            dispatch: Runs a script callback with its arguments on the script
+                      thread; defaults to calling it directly
+        """
+        self.script_dir = os.path.realpath(script_dir)
+        self.display = display
+        self.audio = audio
+        self.led = led
+        self.bus = bus or get_bus()
+        self.dispatch = dispatch or (lambda callback, args: callback(*args))
+        self._subscriptions: List[Tuple[str, Callable[[Event], None]]] = []
+
+    def _path(self, name: str) -> str:
+        """Resolve a file name inside the script directory."""
+        path = os.path.realpath(os.path.join(self.script_dir, str(name)))
+        if os.path.commonpath([path, self.script_dir]) != self.script_dir:
+            raise ScriptError(f"'{name}' is outside the script directory")
+        if not os.path.isfile(path):
+            raise ScriptError(f"File not found: {name}")
+        return path
+
+    def _require(self, device: Any, name: str) -> Any:
+        if device is None:
+            raise ScriptError(f"No {name} available to scripts")
+        return device
+
+    # -- display --
+
+    def show_text(self, text: Any) -> None:
+        """Show text wrapped and centered on the display."""
+        from ..smarthome.endpoints import _show_message
+        _show_message(self._require(self.display, "display"), str(text))
+
+    def show_image(self, name: str) -> None:
+        """Show a PNG from the script directory."""
+        self._require(self.display, "display").display_image(self._path(name))
+
+    def clear(self) -> None:
+        """Clear the display to white."""
+        self._require(self.display, "display").clear()
+
+    # -- audio --
+
+    def play(self, name: str) -> None:
+        """Play a WAV file from the script directory, in the background."""
+        self._require(self.audio, "audio").play(self._path(name))
+
+    def set_volume(self, percent: Any) -> None:
+        """Set the speaker volume, 0-100."""
+        self._require(self.audio, "audio").set_speaker_volume(max(0, min(100, int(percent))))
+
+    # -- LED --
+
+    def set_led(self, r: Any, g: Any, b: Any, brightness: Any = 0.5) -> None:
+        """Set the LED color, components 0-255 and brightness 0.0-1.0."""
+        r, g, b = (max(0, min(255, int(c))) for c in (r, g, b))
+        self._require(self.led, "LED").set_led_color(r, g, b, brightness=max(0.0, min(1.0, float(brightness))))
+
+    # -- events --
+
+    def on(self, pattern: str, callback: Callable) -> None:
+        """
+        Call callback(name, data) for each event matching pattern, e.g.
+        "button.double_press", on the script thread.
+        """
+        def handler(event: Event) -> None:
+            self.dispatch(callback, (event.name, dict(event.data)))
+
+        pattern = str(pattern)
+        self.bus.subscribe(pattern, handler)
+        self._subscriptions.append((pattern, handler))
+
+    def emit(self, name: str, data: Optional[Dict[str, Any]] = None) -> None:
+        """Publish an event, e.g. for other scripts or webhooks."""
+        self.bus.publish(str(name), **{str(key): value for key, value in (data or {}).items()})
+
+    def close(self) -> None:
+        """Drop the script's event subscriptions."""
+        for pattern, handler in self._subscriptions:
+            self.bus.unsubscribe(pattern, handler)
+        self._subscriptions.clear()
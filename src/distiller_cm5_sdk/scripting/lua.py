@@ -0,0 +1,184 @@
+#!/usr/bin/env python3
+"""
+Lua scripting for CM5 SDK.
+
+Runs Lua 5.x automation scripts through lupa, sandboxed: a script sees only
+the safe parts of the standard library (no io, os.execute, require, load or
+debug), the device functions of ScriptApi and no Python objects, runs a
+limited number of instructions per callback and allocates at most
+max_memory bytes. Needs lupa (pip install "distiller-cm5-sdk[scripting]").
+
+    -- /opt/distiller-cm5-sdk/scripts/weather.lua
+    on("button.double_press", function(name, data)
+        display.image("weather.png")
+        after(60, display.clear)
+    end)
+"""
+
+import os
+from typing import Any, Callable, Tuple
+
+from .api import ScriptApi, ScriptError
+from .runtime import ScriptRuntime
+
+
+INSTRUCTION_LIMIT = 10_000_000      # per callback, a few seconds of Lua on a CM5
+MAX_MEMORY = 32 * 1024 * 1024
+
+# Builds the sandbox: compile() loads text chunks into a script environment,
+# call() runs a function under an instruction budget that clear_budget() lifts
+# again. The budget hook keeps firing once tripped, so a script cannot pcall
+# its way past it; call() tail-calls pcall so none of its own code runs after.
+_PRELUDE = """
+local sethook, pcall, load, error, unpack = debug.sethook, pcall, load, error, table.unpack
+
+local function exceeded()
+    sethook(exceeded, "", 1)
+    error("instruction limit exceeded", 2)
+end
+
+local function call(budget, fn, ...)
+    sethook(exceeded, "", budget)
+    return pcall(fn, ...)
+end
+
+local function clear_budget()
+    sethook()
+end
+
+local function copy(t)
+    local c = {}
+    for k, v in pairs(t) do c[k] = v end
+    return c
+end
+
+local function environment(bindings)
+    local env = {
+        assert = assert, error = error, ipairs = ipairs, next = next, pairs = pairs, pcall = pcall,
+        select = select, tonumber = tonumber, tostring = tostring, type = type, unpack = unpack,
+        string = copy(string), table = copy(table), math = copy(math),
+        os = {time = os.time, clock = os.clock, date = os.date, difftime = os.difftime},
+    }
+    for k, v in pairs(bindings) do env[k] = v end
+    env._G = env
+    return env
+end
+
+local function compile(source, name, env)
+    return load(source, "=" .. name, "t", env)
+end
+
+return compile, call, clear_budget, environment
+"""
+
+
+def _deny_attributes(obj: Any, name: Any, setting: bool) -> Any:
+    raise AttributeError("Python attributes are not accessible from scripts")
+
+
+class LuaScript:
+    """One Lua script with its sandbox, bound to a ScriptApi."""
+
+    def __init__(self, path: str, display=None, audio=None, led=None, bus=None,
+                 instruction_limit: int = INSTRUCTION_LIMIT, max_memory: int = MAX_MEMORY):
+        """
+        Args:
+            path: Script file; the files it uses are looked up next to it
+            display: hardware.eink Display for the display functions, or None
+            audio: hardware.audio Audio for the audio functions, or None
+            led: hardware.sam LED for led.set(), or None
+            bus: Event bus; defaults to get_bus()
+            instruction_limit: Lua instructions per callback before it is aborted
+            max_memory: Bytes the script may allocate
+
+        Raises:
+            ScriptError: If lupa is missing or the script does not compile
+        """
+        try:
+            import lupa.lua54 as lupa
+        except ImportError:
+            raise ScriptError('Scripting needs lupa; install with pip install "distiller-cm5-sdk[scripting]"')
+        self.path = path
+        self.name = os.path.basename(path)
+        self.instruction_limit = instruction_limit
+        self.runtime = ScriptRuntime(call=self._call)
+        self.api = ScriptApi(os.path.dirname(os.path.abspath(path)), display=display, audio=audio, led=led,
+                             bus=bus, dispatch=self._dispatch)
+
+        self._lupa = lupa
+        self._lua = lupa.LuaRuntime(register_eval=False, register_builtins=False, unpack_returned_tuples=True,
+                                    attribute_filter=_deny_attributes, max_memory=max_memory)
+        compile_chunk, self._lua_call, self._clear_budget, environment = self._lua.execute(_PRELUDE)
+        env = environment(self._bindings())
+
+        try:
+            with open(path, "r") as f:
+                source = f.read()
+        except OSError as e:
+            raise ScriptError(f"Cannot read script {path}: {e}")
+        chunk, error = compile_chunk(source, self.name, env)
+        if chunk is None:
+            raise ScriptError(f"Script does not compile: {error}")
+        self._chunk = chunk
+
+    def _table(self, **functions: Callable) -> Any:
+        return self._lua.table_from(functions)
+
+    def _bindings(self) -> Any:
+        api, runtime = self.api, self.runtime
+        return self._table(
+            display=self._table(text=api.show_text, image=api.show_image, clear=api.clear),
+            audio=self._table(play=api.play, volume=api.set_volume),
+            led=self._table(set=api.set_led),
+            on=api.on,
+            emit=lambda name, data=None: api.emit(name, self._to_python(data)),
+            after=runtime.after,
+            every=runtime.every,
+            cancel=runtime.cancel,
+            print=lambda *values: print(f"[{self.name}]", *(str(value) for value in values)),
+        )
+
+    def _to_python(self, value: Any) -> Any:
+        """Lua tables to dicts, recursively, for event data."""
+        if self._lupa.lua_type(value) == "table":
+            return {key: self._to_python(item) for key, item in value.items()}
+        return value
+
+    def _to_lua(self, value: Any) -> Any:
+        if isinstance(value, (dict, list, tuple)):
+            return self._lua.table_from(value, recursive=True)
+        return value
+
+    def _dispatch(self, callback: Callable, args: Tuple) -> None:
+        self.runtime.dispatch(callback, args)
+
+    def _call(self, callback: Callable, args: Tuple) -> None:
+        try:
+            result = self._lua_call(self.instruction_limit, callback, *(self._to_lua(a) for a in args))
+        except self._lupa.LuaMemoryError:
+            raise ScriptError(f"{self.name}: out of memory")
+        except self._lupa.LuaError as e:
+            result = (False, e)
+        finally:
+            self._clear_budget()
+        if not isinstance(result, tuple):
+            result = (result,)      # a lone return value is not unpacked into a tuple
+        if not result[0]:
+            raise ScriptError(f"{self.name}: {result[1] if len(result) > 1 else 'error'}")
+
+    def run(self) -> None:
+        """
+        Run the script's top level, then its callbacks until stop().
+
+        Raises:
+            ScriptError: If the top level fails
+        """
+        try:
+            self._call(self._chunk, ())
+            self.runtime.run()
+        finally:
+            self.api.close()
+
+    def stop(self) -> None:
+        """End run(); safe from any thread or signal handler."""
+        self.runtime.stop()
@@ -0,0 +1,130 @@
+#!/usr/bin/env python3
+"""
+Script runtime for CM5 SDK.
+
+Scripts run on one thread: their top level first, then every callback
+(events, timers) in turn, so a script never sees two of its callbacks at
+once. ScriptRuntime does the scheduling and is language independent;
+LuaScript runs Lua in a sandbox on top of it.
+"""
+
+import heapq
+import itertools
+import queue
+import threading
+import time
+from typing import Any, Callable, List, Optional, Tuple
+
+from .api import ScriptError
+
+
+_STOP = object()
+
+
+class ScriptRuntime:
+    """Runs script callbacks and timers one at a time on the calling thread of run()."""
+
+    def __init__(self, call: Optional[Callable[[Callable, Tuple], None]] = None,
+                 clock: Callable[[], float] = time.monotonic):
+        """
+        Args:
+            call: Invokes a script callback with its arguments, e.g. under the
+                  engine's limits; defaults to calling it directly
+            clock: Monotonic clock, replaceable for testing
+        """
+        self.call = call or (lambda callback, args: callback(*args))
+        self._clock = clock
+        self._queue: "queue.Queue[Any]" = queue.Queue()
+        self._timers: List[Tuple[float, int, Callable, Optional[float]]] = []   # (due, id, callback, period)
+        self._cancelled = set()
+        self._ids = itertools.count(1)
+        self._lock = threading.Lock()
+
+    def dispatch(self, callback: Callable, args: Tuple = ()) -> None:
+        """Queue a callback for the script thread; safe from any thread."""
+        self._queue.put((callback, args))
+
+    def after(self, seconds: float, callback: Callable) -> int:
+        """Run callback once after seconds. Returns a timer ID for cancel()."""
+        return self._add_timer(seconds, callback, None)
+
+    def every(self, seconds: float, callback: Callable) -> int:
+        """Run callback every seconds, the first time after one period. Returns a timer ID for cancel()."""
+        if float(seconds) <= 0:
+            raise ScriptError("every() needs a positive period")
+        return self._add_timer(seconds, callback, float(seconds))
+
+    def cancel(self, timer_id: int) -> None:
+        """Stop a timer from after() or every()."""
+        with self._lock:
+            self._cancelled.add(int(timer_id))
+
+    def _add_timer(self, seconds: float, callback: Callable, period: Optional[float]) -> int:
+        timer_id = next(self._ids)
+        with self._lock:
+            heapq.heappush(self._timers, (self._clock() + max(0.0, float(seconds)), timer_id, callback, period))
+        self._queue.put(None)       # recompute the wait in run()
+        return timer_id
+
+    def _run_callback(self, callback: Callable, args: Tuple) -> None:
+        try:
+            self.call(callback, args)
+        except Exception as e:
+            print(f"Warning: Script callback failed: {e}")
+
+    def _next_timer(self) -> Optional[Tuple[Callable, Optional[float]]]:
+        """Pop the first due timer, rescheduling periodic ones; None if nothing is due."""
+        with self._lock:
+            while self._timers and self._timers[0][1] in self._cancelled:
+                self._cancelled.discard(heapq.heappop(self._timers)[1])
+            if not self._timers or self._timers[0][0] > self._clock():
+                return None
+            due, timer_id, callback, period = heapq.heappop(self._timers)
+            if period is not None:
+                heapq.heappush(self._timers, (max(due + period, self._clock()), timer_id, callback, period))
+            return callback, period
+
+    def _run_due_timers(self) -> None:
+        while True:
+            timer = self._next_timer()
+            if timer is None:
+                return
+            self._run_callback(timer[0], ())
+
+    def run_pending(self) -> bool:
+        """
+        Run the queued callbacks and due timers, then return.
+
+        Returns:
+            False once stop() was called
+        """
+        while True:
+            try:
+                item = self._queue.get_nowait()
+            except queue.Empty:
+                break
+            if item is _STOP:
+                return False
+            if item is not None:
+                self._run_callback(*item)
+        self._run_due_timers()
+        return True
+
+    def run(self) -> None:
+        """Run callbacks and timers as they come, until stop()."""
+        while True:
+            with self._lock:
+                wait = max(0.0, self._timers[0][0] - self._clock()) if self._timers else None
+            try:
+                item = self._queue.get(timeout=wait)
+            except queue.Empty:
+                item = None
+            if item is _STOP:
+                return
+            if item is not None:
+                self._run_callback(*item)
+            self._run_due_timers()
+
+    def stop(self) -> None:
+        """End run() after the callback in progress; safe from any thread or signal handler."""
+        self._queue.put(_STOP)
@@ -0,0 +1,2 @@
+from .device import KEYMAP, SimulatedBattery, SimulatedButtons, SimulatedDevice
+from .harness import ArtifactRecorder, load_manifest
@@ -0,0 +1,103 @@
+#!/usr/bin/env python3
+"""
+Device simulator: distiller-sim [options] (script.py | -m module) [args ...]
+(or python3 -m distiller_cm5_sdk.sim)
+
+For CI: distiller-sim --headless --artifacts out/ --keys down,return app.py
+records what the app showed and played into out/, listed in out/manifest.json.
+"""
+
+import argparse
+import os
+import runpy
+import sys
+
+from ..hardware.eink.backends.mock import MOCK_DIR_ENV_VAR
+from ..hardware.eink.backends.simulator import SCALE_ENV_VAR, SPEED_ENV_VAR
+from .device import SimulatedBattery, SimulatedDevice
+from .harness import ArtifactRecorder
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(
+        description="Run an SDK application on this computer with a simulated display, buttons, battery "
+                    "and audio",
+        epilog="Keys in the window: arrows, j/k or page up/down move, Enter or space selects; "
+               "-/= discharge/charge the battery by 5%%, c plugs the charger in or out.")
+    parser.add_argument("-m", dest="module", help="Run a module, as python3 -m does")
+    parser.add_argument("--scale", type=int, help="Window pixels per panel pixel (default: 3)")
+    parser.add_argument("--speed", type=float,
+                        help="Multiplier for the panel's refresh time (default: 1.0; 0 refreshes at once)")
+    parser.add_argument("--frames-dir", help="Also write each frame as a PNG into this directory")
+    parser.add_argument("--battery", type=float, default=80.0, metavar="PERCENT", help="Starting charge")
+    parser.add_argument("--drain", type=float, default=10.0, metavar="PERCENT",
+                        help="Charge lost per hour (default: 10)")
+    parser.add_argument("--microphone", metavar="WAV", help="Recordings read this file, looped (default: silence)")
+    parser.add_argument("--mute", action="store_true", help="Do not play audio on the speakers")
+    parser.add_argument("--headless", action="store_true",
+                        help="No window and no sound, e.g. on a CI runner (implies --mute)")
+    parser.add_argument("--artifacts", metavar="DIR",
+                        help="Write every frame and played audio buffer into DIR, listed in DIR/manifest.json")
+    parser.add_argument("--keys", default="", metavar="KEYS",
+                        help="Window keys pressed in order as the app starts, comma-separated (e.g. down,return)")
+    parser.add_argument("script", nargs="?", help="Application script")
+    parser.add_argument("args", nargs=argparse.REMAINDER, help="Arguments passed to the application")
+    args = parser.parse_args(argv)
+
+    if args.module:
+        app_argv = [args.module] + ([args.script] if args.script else []) + args.args
+    elif args.script:
+        app_argv = [args.script] + args.args
+    else:
+        parser.error("an application script or -m module is required")
+    if not args.module and not os.path.isfile(args.script):
+        print(f"Error: Application not found: {args.script}", file=sys.stderr)
+        return 1
+    if args.microphone and not os.path.isfile(args.microphone):
+        print(f"Error: Microphone file not found: {args.microphone}", file=sys.stderr)
+        return 1
+
+    # The simulator backend reads these as the app opens its display
+    if args.scale is not None:
+        os.environ[SCALE_ENV_VAR] = str(args.scale)
+    if args.speed is not None:
+        os.environ[SPEED_ENV_VAR] = str(args.speed)
+    if args.frames_dir:
+        os.makedirs(args.frames_dir, exist_ok=True)
+        os.environ[MOCK_DIR_ENV_VAR] = args.frames_dir
+
+    device = SimulatedDevice(SimulatedBattery(args.battery, args.drain), microphone=args.microphone,
+                             speaker=not args.mute, headless=args.headless)
+    recorder = ArtifactRecorder(args.artifacts, app_argv) if args.artifacts else None
+    device.install()
+    if recorder:
+        recorder.start()
+    for key in filter(None, (key.strip() for key in args.keys.split(","))):
+        device.handle_key(key)
+    sys.argv = app_argv
+    exit_code = 0
+    try:
+        if args.module:
+            runpy.run_module(args.module, run_name="__main__", alter_sys=True)
+        else:
+            sys.path.insert(0, os.path.dirname(os.path.abspath(args.script)))
+            runpy.run_path(args.script, run_name="__main__")
+    except KeyboardInterrupt:
+        pass
+    except SystemExit as e:
+        # Recorded in the manifest, so the app's status is not lost to the harness
+        exit_code = e.code if isinstance(e.code, int) else (0 if e.code is None else 1)
+        if isinstance(e.code, str):
+            print(e.code, file=sys.stderr)
+    except BaseException:
+        exit_code = 1
+        raise
+    finally:
+        if recorder:
+            recorder.stop(exit_code)
+        device.uninstall()
+    return exit_code
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,231 @@
+#!/usr/bin/env python3
+"""
+Device simulator unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import threading
+import time
+import unittest
+import wave
+from pathlib import Path
+from unittest.mock import patch
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk import recovery
+from distiller_cm5_sdk.device.privacy import SoftwareMuteSwitch, set_mute_switch
+from distiller_cm5_sdk.events.bus import EventBus
+from distiller_cm5_sdk.events.sources import BATTERY_LOW, BATTERY_OK
+from distiller_cm5_sdk.hardware.eink.backends import BACKEND_ENV_VAR
+from distiller_cm5_sdk.recovery.buttons import Button, GpioButtons
+from distiller_cm5_sdk.sim.__main__ import main
+from distiller_cm5_sdk.sim.device import SimulatedBattery, SimulatedButtons, SimulatedDevice
+from distiller_cm5_sdk.sim.harness import load_manifest
+
+try:
+    from distiller_cm5_sdk.hardware.audio.audio import MicMutedError
+    from distiller_cm5_sdk.sim.audio import SimulatedAudio
+except ImportError:
+    SimulatedAudio = None       # the audio module needs numpy
+
+
+class TestSimulatedDevice(unittest.TestCase):
+    """Test cases for the simulated buttons and battery."""
+
+    def setUp(self):
+        self.bus = EventBus()
+        self.events = []
+        self.bus.subscribe("battery.*", lambda event: self.events.append((event.name, event.data["percent"])))
+        self.device = SimulatedDevice(SimulatedBattery(17.0, drain_per_hour=0), speaker=False, bus=self.bus)
+        self.device.install()
+
+    def tearDown(self):
+        self.device.uninstall()
+
+    def test_install(self):
+        """Test the hardware classes and display backend are replaced, then restored."""
+        self.assertEqual(os.environ[BACKEND_ENV_VAR], "simulator")
+        from distiller_cm5_sdk.recovery.buttons import EvdevButtons
+        self.assertIs(EvdevButtons, SimulatedButtons)
+        self.assertIs(recovery.GpioButtons, SimulatedButtons)
+        if SimulatedAudio is not None:
+            from distiller_cm5_sdk.hardware.audio import Audio
+            self.assertIs(Audio, SimulatedAudio)
+
+        with patch.dict(os.environ, {BACKEND_ENV_VAR: "mock"}):
+            device = SimulatedDevice()
+            device.install()
+            device.uninstall()
+            self.assertEqual(os.environ[BACKEND_ENV_VAR], "mock")
+        self.device.uninstall()
+        self.assertIsNot(recovery.GpioButtons, SimulatedButtons)
+        from distiller_cm5_sdk.recovery import buttons
+        self.assertIs(buttons.GpioButtons, GpioButtons)
+
+    def test_buttons(self):
+        """Test window keys press buttons for button sources created as on the device."""
+        buttons = recovery.GpioButtons({Button.UP: 16, Button.DOWN: 20, Button.SELECT: 26}, chip_name="gpiochip0")
+        for key in ("down", "k", "return", "x"):
+            self.device.handle_key(key)
+        self.assertEqual([buttons.read(0.1) for _ in range(3)], [Button.DOWN, Button.UP, Button.SELECT])
+        start = time.monotonic()
+        self.assertIsNone(buttons.read(0.05))
+        self.assertGreaterEqual(time.monotonic() - start, 0.05)
+
+    def test_battery(self):
+        """Test battery keys and the charger move the charge and publish battery events."""
+        self.device.handle_key("-")
+        self.device.handle_key("=")
+        self.assertEqual(self.events, [(BATTERY_LOW, 12.0)])
+        self.device.handle_key("=")
+        self.assertEqual(self.events[-1], (BATTERY_OK, 22.0))
+
+        battery = SimulatedBattery(50.0, drain_per_hour=3600.0, charge_per_hour=7200.0)
+        battery._since -= 2.0                               # two seconds unplugged
+        self.assertAlmostEqual(battery.read_percent(), 48.0, delta=0.2)
+        battery.set_charging(True)
+        battery._since -= 1.0
+        self.assertAlmostEqual(battery.read_percent(), 50.0, delta=0.2)
+        battery.set_percent(120)
+        self.assertEqual(battery.read_percent(), 100.0)
+
+
+@unittest.skipIf(SimulatedAudio is None, "the audio module needs numpy")
+class TestSimulatedAudio(unittest.TestCase):
+    """Test cases for the virtual audio."""
+
+    def setUp(self):
+        self.tmpdir = tempfile.TemporaryDirectory()
+        self.microphone = os.path.join(self.tmpdir.name, "mic.wav")
+        with wave.open(self.microphone, "wb") as wav:
+            wav.setnchannels(1)
+            wav.setsampwidth(2)
+            wav.setframerate(8000)
+            wav.writeframes(bytes(range(1, 201)))
+        SimulatedAudio.MICROPHONE = self.microphone
+        SimulatedAudio.SPEAKER = False
+        self.audio = SimulatedAudio(sample_rate=8000, channels=1)
+
+    def tearDown(self):
+        self.audio.close()
+        SimulatedAudio.MICROPHONE = None
+        SimulatedAudio.SPEAKER = True
+        self.tmpdir.cleanup()
+
+    def test_record(self):
+        """Test recordings loop the microphone file for as long as they run."""
+        path = os.path.join(self.tmpdir.name, "out", "rec.wav")
+        self.audio.record(path)
+        time.sleep(0.1)
+        self.audio.stop_recording()
+        with wave.open(path, "rb") as wav:
+            self.assertEqual((wav.getnchannels(), wav.getframerate()), (1, 8000))
+            frames = wav.readframes(wav.getnframes())
+        self.assertGreaterEqual(len(frames), 1600)
+        self.assertEqual(frames[:400], bytes(range(1, 201)) * 2)
+
+        buffers = []
+        stop = threading.Event()
+        thread = self.audio.stream_record(buffers.append, buffer_size=160, stop_event=stop)
+        time.sleep(0.1)
+        stop.set()
+        thread.join(1)
+        self.assertGreater(len(buffers), 0)
+        self.assertEqual(buffers[0], bytes(range(1, 161)))
+
+    def test_mute(self):
+        """Test recordings are refused while muted, and a running stream stops when muted."""
+        switch = SoftwareMuteSwitch(True)
+        set_mute_switch(switch)
+        try:
+            with self.assertRaises(MicMutedError):
+                self.audio.record(os.path.join(self.tmpdir.name, "rec.wav"))
+            with self.assertRaises(MicMutedError):
+                self.audio.stream_record(lambda data: None)
+
+            switch.set_muted(False)
+            buffers = []
+            thread = self.audio.stream_record(buffers.append, buffer_size=160)
+            time.sleep(0.1)
+            switch.set_muted(True)
+            thread.join(1)
+            self.assertFalse(thread.is_alive())
+            self.assertGreater(len(buffers), 0)
+        finally:
+            set_mute_switch(None)
+
+    def test_play(self):
+        """Test playback takes as long as the audio and can be stopped."""
+        self.audio.play(self.microphone)
+        self.assertTrue(self.audio.is_playing())
+        time.sleep(0.2)
+        self.assertFalse(self.audio.is_playing())
+
+        self.audio.stream_play(bytes(8000 * 2 * 10))           # ten seconds
+        self.audio.stop_playback()
+        self.assertFalse(self.audio.is_playing())
+        self.assertEqual(self.audio.played, [self.microphone, "stream"])
+
+
+class TestCommandLine(unittest.TestCase):
+    """Test cases for distiller-sim."""
+
+    def test_runs_script(self):
+        """Test a script runs as __main__ with its arguments on the simulated device."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            script = os.path.join(tmpdir, "app.py")
+            result = os.path.join(tmpdir, "result.txt")
+            Path(script).write_text(
+                "import os, sys\n"
+                "from distiller_cm5_sdk.recovery.buttons import GpioButtons\n"
+                "if __name__ == '__main__':\n"
+                "    with open(sys.argv[1], 'w') as f:\n"
+                "        f.write(' '.join([GpioButtons.__name__, os.environ['DISTILLER_EINK_BACKEND']] + sys.argv[2:]))\n")
+            with patch.object(sys, "argv", ["distiller-sim"]):
+                self.assertEqual(main(["--mute", "--battery", "50", script, result, "--flag"]), 0)
+            self.assertEqual(Path(result).read_text(), "SimulatedButtons simulator --flag")
+            self.assertIs(recovery.GpioButtons, GpioButtons)
+
+    def test_artifacts(self):
+        """Test a headless run records the frames shown and audio played, with the app's exit code."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            script = os.path.join(tmpdir, "app.py")
+            artifacts = os.path.join(tmpdir, "artifacts")
+            Path(script).write_text(
+                "import sys\n"
+                "from distiller_cm5_sdk.hardware.eink import Display, DisplayMode\n"
+                "from distiller_cm5_sdk.recovery.buttons import Button, GpioButtons\n"
+                "with Display() as display:\n"
+                "    display.clear()\n"
+                "    if GpioButtons({}).read(1) == Button.DOWN:\n"
+                "        display.display_image(bytes(display.ARRAY_SIZE), DisplayMode.PARTIAL)\n"
+                "if len(sys.argv) > 1:\n"
+                "    from distiller_cm5_sdk.hardware.audio import Audio\n"
+                "    Audio(sample_rate=8000, channels=1).stream_play(bytes(1600))\n"
+                "sys.exit(3)\n")
+            app_args = ["audio"] if SimulatedAudio is not None else []
+            with patch.object(sys, "argv", ["distiller-sim"]):
+                self.assertEqual(main(["--headless", "--artifacts", artifacts, "--keys", "j",
+                                       script] + app_args), 3)
+
+            manifest = load_manifest(artifacts)
+            self.assertEqual(manifest["exit_code"], 3)
+            self.assertEqual(manifest["app"], [script] + app_args)
+            self.assertIsNotNone(manifest["finished"])
+            self.assertEqual([entry["type"] for entry in manifest["artifacts"]],
+                             ["frame", "frame"] + ["audio"] * len(app_args))
+            frame = manifest["artifacts"][1]
+            self.assertEqual((frame["file"], frame["width"], frame["height"]), ("frame_0002.png", 128, 250))
+            for entry in manifest["artifacts"]:
+                self.assertTrue(os.path.isfile(os.path.join(artifacts, entry["file"])))
+            if app_args:
+                audio = manifest["artifacts"][2]
+                self.assertEqual((audio["source"], audio["sample_rate"], audio["duration_s"]), ("stream", 8000, 0.1))
+
+
+if __name__ == "__main__":
+    unittest.main()
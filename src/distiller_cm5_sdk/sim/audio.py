@@ -0,0 +1,226 @@
+#!/usr/bin/env python3
+"""
+Virtual audio for the device simulator.
+
+SimulatedAudio stands in for hardware.audio.Audio on a development machine:
+playback goes to the computer's speakers through pygame's mixer, when it is
+installed, and recording reads a WAV file given as the microphone, or
+silence. Both take as long as on the device, so apps keep their timing.
+"""
+
+import os
+import tempfile
+import threading
+import time
+import wave
+from typing import BinaryIO, Callable, List, Optional, Union
+
+from ..diagnostics.audit import AuditCategory, audit
+from ..diagnostics.retention import DataCategory, get_retention_policy, purge
+from ..device.privacy import capture_allowed, is_mic_muted
+from ..hardware.audio.audio import Audio, AudioError, MicMutedError
+
+
+# Bytes per sample of the ALSA formats apps use
+SAMPLE_BYTES = {"S16_LE": 2, "S24_LE": 3, "S32_LE": 4}
+
+# Callbacks for everything SimulatedAudio plays
+_playback_listeners: List[Callable[[str, str], None]] = []
+
+
+def add_playback_listener(callback: Callable[[str, str], None]) -> None:
+    """
+    Call callback(wav_path, source) as playback starts; source is the file
+    played, or "stream" for stream_play(), whose WAV is deleted afterwards.
+    """
+    _playback_listeners.append(callback)
+
+
+def remove_playback_listener(callback: Callable[[str, str], None]) -> None:
+    """Stop calling a playback listener."""
+    _playback_listeners.remove(callback)
+
+
+class SimulatedAudio(Audio):
+    """
+    Audio played on the computer's speakers and recorded from a WAV file.
+
+    Class attributes configure every instance, since apps create their own:
+    MICROPHONE is the WAV file recordings read, looped, and SPEAKER turns
+    playback through pygame off (playback is then only timed).
+    """
+
+    MICROPHONE: Optional[str] = None
+    SPEAKER = True
+
+    def __init__(self, *args, **kwargs):
+        kwargs["auto_check_config"] = False     # no ALSA tools or device paths needed
+        super().__init__(*args, **kwargs)
+        self.played: List[str] = []             # files and "stream", in order of playback
+        self._mixer = None
+        self._microphone: Optional[bytes] = None
+
+    # -- Microphone ---------------------------------------------------------
+
+    @property
+    def _frame_bytes(self) -> int:
+        return SAMPLE_BYTES.get(self.format_type, 2) * self.channels
+
+    def _capture(self, length: int, offset: int = 0) -> bytes:
+        """length bytes of microphone input from offset, looping the microphone file."""
+        if self._microphone is None:
+            self._microphone = b""
+            if self.MICROPHONE:
+                try:
+                    with wave.open(self.MICROPHONE, "rb") as wav:
+                        self._microphone = wav.readframes(wav.getnframes())
+                except (OSError, wave.Error) as e:
+                    print(f"Warning: Cannot read simulated microphone {self.MICROPHONE}: {e}")
+        source = self._microphone
+        if not source:
+            return bytes(length)
+        out = bytearray()
+        while len(out) < length:
+            start = (offset + len(out)) % len(source)
+            out += source[start:start + length - len(out)]
+        return bytes(out)
+
+    def _write_recording(self, filepath: str, seconds: float) -> None:
+        frames = int(seconds * self.sample_rate)
+        with wave.open(filepath, "wb") as wav:
+            wav.setnchannels(self.channels)
+            wav.setsampwidth(SAMPLE_BYTES.get(self.format_type, 2))
+            wav.setframerate(self.sample_rate)
+            wav.writeframes(self._capture(frames * self._frame_bytes))
+        purge(DataCategory.RECORDINGS)
+
+    def record(self, filepath: str, duration: Optional[float] = None) -> str:
+        if self._is_recording:
+            raise AudioError("Recording already in progress")
+        if duration is not None and not isinstance(duration, (int, float)):
+            raise AudioError(f"Invalid duration: {duration}. Must be a number or None.")
+        if not get_retention_policy(DataCategory.RECORDINGS).persist:
+            raise AudioError("Recordings are not kept by the retention policy; use stream_record()")
+        if not capture_allowed("audio"):
+            raise MicMutedError("Microphone is muted by the privacy switch")
+        os.makedirs(os.path.dirname(os.path.abspath(filepath)), exist_ok=True)
+
+        self._is_recording = True
+        self._stop_recording.clear()
+        if duration is not None:
+            # arecord takes whole seconds
+            time.sleep(int(duration))
+            self._write_recording(filepath, int(duration))
+            self._is_recording = False
+            return filepath
+
+        start = time.monotonic()
+
+        def record_thread():
+            self._stop_recording.wait()
+            self._write_recording(filepath, time.monotonic() - start)
+
+        self._record_thread = threading.Thread(target=record_thread, daemon=True)
+        self._record_thread.start()
+        return filepath
+
+    def stream_record(self, callback: Callable[[bytes], None], buffer_size: int = 4096,
+                      stop_event: Optional[threading.Event] = None) -> threading.Thread:
+        if self._is_recording:
+            raise AudioError("Recording already in progress")
+        if not callable(callback):
+            raise AudioError("Callback must be a callable function")
+        if not capture_allowed("audio"):
+            raise MicMutedError("Microphone is muted by the privacy switch")
+        self._stop_recording = stop_event if stop_event is not None else threading.Event()
+        buffer_s = buffer_size / (self._frame_bytes * self.sample_rate)
+
+        def stream_thread():
+            self._is_recording = True
+            offset = 0
+            try:
+                # Buffers arrive at the rate the microphone would fill them
+                while not self._stop_recording.wait(buffer_s) and not is_mic_muted():
+                    callback(self._capture(buffer_size, offset))
+                    offset += buffer_size
+            except Exception as e:
+                print(f"Stream recording error: {str(e)}")
+            self._is_recording = False
+
+        self._record_thread = threading.Thread(target=stream_thread, daemon=True)
+        self._record_thread.start()
+        return self._record_thread
+
+    # -- Speaker ------------------------------------------------------------
+
+    def _start_sound(self, filepath: str):
+        """Start playing a WAV file on the computer's speakers; returns the pygame channel or None."""
+        if not self.SPEAKER:
+            return None
+        try:
+            if self._mixer is None:
+                import pygame
+                pygame.mixer.init()
+                self._mixer = pygame.mixer
+            return self._mixer.Sound(filepath).play()
+        except Exception as e:
+            print(f"Warning: Simulated speaker unavailable, playback is silent: {e}")
+            self.SPEAKER = False
+            return None
+
+    def _play_for(self, filepath: str, seconds: float, cleanup: Optional[str] = None) -> None:
+        for listener in list(_playback_listeners):
+            listener(filepath, "stream" if cleanup else filepath)
+        with self._lock:
+            self._is_playing = True
+            self._stop_playback.clear()
+
+        def play_thread():
+            try:
+                channel = self._start_sound(filepath)
+                self._stop_playback.wait(seconds)
+                if channel is not None:
+                    channel.stop()
+            finally:
+                if cleanup:
+                    os.unlink(cleanup)
+                # Not under _lock: stop_playback() holds it while joining this thread
+                self._is_playing = False
+
+        self._play_thread = threading.Thread(target=play_thread, daemon=True)
+        self._play_thread.start()
+
+    def play(self, filepath: str) -> None:
+        if self._is_playing:
+            raise AudioError("Playback already in progress")
+        if not os.path.exists(filepath):
+            raise AudioError(f"Audio file not found: {filepath}")
+        try:
+            with wave.open(filepath, "rb") as wav:
+                seconds = wav.getnframes() / float(wav.getframerate())
+        except (OSError, wave.Error) as e:
+            raise AudioError(f"Playback failed: {e}")
+        self.played.append(filepath)
+        self._play_for(filepath, seconds)
+        audit(AuditCategory.AUDIO, "play", filepath, device="simulator")
+
+    def stream_play(self, audio_data: Union[bytes, BinaryIO], format_type: Optional[str] = None,
+                    sample_rate: Optional[int] = None, channels: Optional[int] = None) -> None:
+        if self._is_playing:
+            raise AudioError("Playback already in progress")
+        fmt = format_type or self.format_type
+        rate = sample_rate or self.sample_rate
+        chans = channels or self.channels
+        data = audio_data if isinstance(audio_data, bytes) else audio_data.read()
+
+        # The mixer plays files, so the stream becomes a temporary WAV
+        fd, path = tempfile.mkstemp(suffix=".wav", prefix="distiller-sim-")
+        with os.fdopen(fd, "wb") as f, wave.open(f, "wb") as wav:
+            wav.setnchannels(chans)
+            wav.setsampwidth(SAMPLE_BYTES.get(fmt, 2))
+            wav.setframerate(rate)
+            wav.writeframes(data)
+        self.played.append("stream")
+        self._play_for(path, len(data) / float(SAMPLE_BYTES.get(fmt, 2) * chans * rate), cleanup=path)
+        audit(AuditCategory.AUDIO, "play", "stream", device="simulator", format=fmt, sample_rate=rate,
+              channels=chans)
@@ -0,0 +1,224 @@
+#!/usr/bin/env python3
+"""
+Simulated Distiller device for CM5 SDK.
+
+Puts stand-ins in place of the hardware an app uses, so it runs unmodified
+on a development machine:
+
+    display   the simulator backend's desktop window (DISTILLER_EINK_BACKEND=simulator)
+    buttons   keys pressed in that window, for GpioButtons and EvdevButtons
+    battery   a charge that drains over time, watched by a BatteryMonitor on get_bus()
+    audio     SimulatedAudio in place of Audio: the computer's speakers and a WAV microphone
+
+    device = SimulatedDevice()
+    device.install()
+    ...                     # the app creates Display, GpioButtons, Audio as on the device
+    device.uninstall()
+
+Classes are replaced where the SDK defines and exports them, so install()
+must run before the app imports them by name. A headless device, for CI,
+uses the mock backend instead of a window; buttons are then pressed with
+press().
+"""
+
+import os
+import queue
+import threading
+import time
+from typing import Dict, List, Optional, Tuple
+
+from ..events.bus import EventBus
+from ..events.sources import BatteryMonitor
+from ..hardware.eink.backends import BACKEND_ENV_VAR, MOCK_BACKEND, SIMULATOR_BACKEND
+from ..hardware.eink.backends.simulator import add_key_listener, pump_windows, remove_key_listener
+from ..recovery import buttons as buttons_module
+from ..recovery.buttons import Button, ButtonError, ButtonSource
+
+
+# Window keys and the buttons they press; the same keys as the device's presentation remotes
+KEYMAP = {
+    "up": Button.UP, "left": Button.UP, "page up": Button.UP, "k": Button.UP,
+    "down": Button.DOWN, "right": Button.DOWN, "page down": Button.DOWN, "j": Button.DOWN,
+    "return": Button.SELECT, "enter": Button.SELECT, "space": Button.SELECT,
+}
+
+# Window keys for the battery: discharge or charge by BATTERY_STEP, or plug the charger in and out
+BATTERY_DOWN_KEY = "-"
+BATTERY_UP_KEY = "="
+CHARGER_KEY = "c"
+BATTERY_STEP = 5.0
+
+# How often the simulated battery is checked for battery.low and battery.ok
+BATTERY_INTERVAL_S = 5.0
+
+# Longest wait for a key press between pumps of the simulator windows
+_PUMP_INTERVAL_S = 0.02
+
+
+class SimulatedBattery:
+    """A battery that drains while unplugged and charges while plugged in."""
+
+    def __init__(self, percent: float = 80.0, drain_per_hour: float = 10.0, charge_per_hour: float = 60.0):
+        """
+        Args:
+            percent: Charge to start from
+            drain_per_hour: Percent lost per hour unplugged (0 holds the charge)
+            charge_per_hour: Percent gained per hour plugged in
+        """
+        self.drain_per_hour = drain_per_hour
+        self.charge_per_hour = charge_per_hour
+        self.charging = False
+        self._percent = float(percent)
+        self._since = time.monotonic()
+        self._lock = threading.Lock()
+
+    def _advance(self) -> None:
+        now = time.monotonic()
+        hours = (now - self._since) / 3600.0
+        self._since = now
+        rate = self.charge_per_hour if self.charging else -self.drain_per_hour
+        self._percent = min(100.0, max(0.0, self._percent + rate * hours))
+
+    def read_percent(self) -> float:
+        """Current charge in percent; a BatteryMonitor's read_percent."""
+        with self._lock:
+            self._advance()
+            return round(self._percent, 1)
+
+    def set_percent(self, percent: float) -> None:
+        """Set the charge, e.g. to try an app's low battery handling."""
+        with self._lock:
+            self._advance()
+            self._percent = min(100.0, max(0.0, float(percent)))
+
+    def set_charging(self, charging: bool) -> None:
+        """Plug the charger in or out."""
+        with self._lock:
+            self._advance()
+            self.charging = charging
+
+
+class SimulatedButtons(ButtonSource):
+    """
+    Buttons pressed with keys in the simulator window (see KEYMAP).
+
+    Accepts and ignores the arguments of the button sources it replaces, so
+    GpioButtons(pins) and EvdevButtons() create one unchanged. Presses are
+    shared by all instances: each goes to whichever reads first.
+    """
+
+    device: Optional["SimulatedDevice"] = None      # set by SimulatedDevice.install()
+
+    def __init__(self, *args, **kwargs):
+        if self.device is None:
+            raise ButtonError("No simulated device installed")
+        self._presses = self.device.presses
+
+    def read(self, timeout: Optional[float] = None) -> Optional[Button]:
+        deadline = None if timeout is None else time.monotonic() + timeout
+        while True:
+            # Key presses only arrive while the window's events are processed
+            pump_windows()
+            wait_s = _PUMP_INTERVAL_S if deadline is None else min(_PUMP_INTERVAL_S,
+                                                                  max(0.0, deadline - time.monotonic()))
+            try:
+                return self._presses.get(timeout=wait_s)
+            except queue.Empty:
+                pass
+            if deadline is not None and time.monotonic() >= deadline:
+                return None
+
+
+class SimulatedDevice:
+    """The display, buttons, battery and audio of a Distiller, simulated on a development machine."""
+
+    def __init__(self, battery: Optional[SimulatedBattery] = None, microphone: Optional[str] = None,
+                 speaker: bool = True, bus: Optional[EventBus] = None,
+                 keymap: Optional[Dict[str, Button]] = None, headless: bool = False):
+        """
+        Args:
+            battery: Battery to simulate; defaults to 80% draining 10% an hour
+            microphone: WAV file recordings read, looped; None records silence
+            speaker: Play audio on the computer's speakers (needs pygame); False only times playback
+            bus: Bus battery events are published on; defaults to get_bus()
+            keymap: Window key name to button; defaults to KEYMAP
+            headless: Show frames nowhere (the mock backend) and play no audio, e.g. on a CI runner
+        """
+        self.battery = battery or SimulatedBattery()
+        self.microphone = microphone
+        self.speaker = speaker and not headless
+        self.headless = headless
+        self.keymap = dict(KEYMAP if keymap is None else keymap)
+        self.presses: "queue.Queue[Button]" = queue.Queue()
+        self.monitor = BatteryMonitor(self.battery.read_percent, bus, interval_s=BATTERY_INTERVAL_S)
+        self.installed = False
+        self._patches: List[Tuple[object, str, object]] = []
+        self._env: Optional[str] = None
+
+    def press(self, button: Button) -> None:
+        """Press a button, as a key in the window does."""
+        self.presses.put(button)
+
+    def handle_key(self, key: str) -> None:
+        """Act on a key pressed in the simulator window."""
+        button = self.keymap.get(key)
+        if button is not None:
+            self.press(button)
+        elif key == BATTERY_DOWN_KEY:
+            self.battery.set_percent(self.battery.read_percent() - BATTERY_STEP)
+            self.monitor.check()
+        elif key == BATTERY_UP_KEY:
+            self.battery.set_percent(self.battery.read_percent() + BATTERY_STEP)
+            self.monitor.check()
+        elif key == CHARGER_KEY:
+            self.battery.set_charging(not self.battery.charging)
+            print(f"Simulator: charger {'plugged in' if self.battery.charging else 'unplugged'}")
+
+    def _patch(self, target, name: str, value) -> None:
+        self._patches.append((target, name, getattr(target, name)))
+        setattr(target, name, value)
+
+    def install(self) -> None:
+        """Replace the hardware with the simulation for the rest of the process, until uninstall()."""
+        if self.installed:
+            return
+        self._env = os.environ.get(BACKEND_ENV_VAR)
+        os.environ[BACKEND_ENV_VAR] = MOCK_BACKEND if self.headless else SIMULATOR_BACKEND
+
+        from .. import recovery
+        SimulatedButtons.device = self
+        for module in (buttons_module, recovery):
+            self._patch(module, "GpioButtons", SimulatedButtons)
+            self._patch(module, "EvdevButtons", SimulatedButtons)
+        add_key_listener(self.handle_key)
+
+        try:
+            from ..hardware import audio
+            from ..hardware.audio import audio as audio_module
+            from .audio import SimulatedAudio
+        except ImportError as e:
+            print(f"Warning: No virtual audio, the audio module cannot be imported: {e}")
+        else:
+            SimulatedAudio.MICROPHONE = self.microphone
+            SimulatedAudio.SPEAKER = self.speaker
+            self._patch(audio_module, "Audio", SimulatedAudio)
+            self._patch(audio, "Audio", SimulatedAudio)
+
+        self.monitor.start()
+        self.installed = True
+
+    def uninstall(self) -> None:
+        """Put the hardware classes and display backend back."""
+        if not self.installed:
+            return
+        self.monitor.stop()
+        remove_key_listener(self.handle_key)
+        for target, name, original in reversed(self._patches):
+            setattr(target, name, original)
+        self._patches.clear()
+        SimulatedButtons.device = None
+        if self._env is None:
+            os.environ.pop(BACKEND_ENV_VAR, None)
+        else:
+            os.environ[BACKEND_ENV_VAR] = self._env
+        self.installed = False
@@ -0,0 +1,155 @@
+#!/usr/bin/env python3
+"""
+Artifact recording for end-to-end tests of SDK apps.
+
+ArtifactRecorder saves every frame a mock or simulator display shows as a
+PNG and every buffer SimulatedAudio plays as a WAV, and lists them in
+manifest.json in the order they happened, so a test can run an app on the
+simulated device and assert on what it actually rendered and played:
+
+    with ArtifactRecorder("artifacts"):
+        ...                 # run the app on a SimulatedDevice
+    manifest = load_manifest("artifacts")
+
+The manifest is rewritten after each artifact, so it stays valid if the
+app is killed.
+"""
+
+import json
+import os
+import shutil
+import threading
+import time
+import wave
+from typing import Any, Dict, List, Optional
+
+from ..hardware.eink.backends.mock import MockTransport, add_frame_listener, remove_frame_listener
+from ..hardware.eink.pngcodec import encode_png_1bit
+
+
+MANIFEST_FILE = "manifest.json"
+MANIFEST_VERSION = 1
+
+
+class ArtifactRecorder:
+    """Writes displayed frames and played audio into a directory, with a manifest."""
+
+    def __init__(self, directory: str, app: Optional[List[str]] = None):
+        """
+        Args:
+            directory: Artifact directory, created if missing
+            app: Command line of the app being recorded, for the manifest
+        """
+        self.directory = directory
+        self.app = list(app or [])
+        self.artifacts: List[Dict[str, Any]] = []
+        self.started: Optional[str] = None
+        self.finished: Optional[str] = None
+        self.exit_code: Optional[int] = None
+        self._start = 0.0
+        self._lock = threading.Lock()
+        self._recording = False
+
+    def start(self) -> None:
+        """Start recording; artifacts from an earlier run in the directory are overwritten."""
+        if self._recording:
+            return
+        os.makedirs(self.directory, exist_ok=True)
+        self.artifacts = []
+        self.started = _timestamp()
+        self.finished = None
+        self.exit_code = None
+        self._start = time.monotonic()
+        add_frame_listener(self._on_frame)
+        try:
+            from .audio import add_playback_listener
+        except ImportError:
+            pass                # no audio module, nothing to play
+        else:
+            add_playback_listener(self._on_playback)
+        self._recording = True
+        self._write_manifest()
+
+    def stop(self, exit_code: Optional[int] = None) -> None:
+        """Stop recording and write the final manifest."""
+        if not self._recording:
+            return
+        remove_frame_listener(self._on_frame)
+        try:
+            from .audio import remove_playback_listener
+        except ImportError:
+            pass
+        else:
+            remove_playback_listener(self._on_playback)
+        self._recording = False
+        with self._lock:
+            self.finished = _timestamp()
+            self.exit_code = exit_code
+            self._write_manifest()
+
+    def __enter__(self) -> "ArtifactRecorder":
+        self.start()
+        return self
+
+    def __exit__(self, exc_type, exc_val, exc_tb) -> None:
+        self.stop(1 if exc_type is not None else 0)
+
+    def _add(self, kind: str, extension: str, entry: Dict[str, Any]) -> str:
+        """Append a manifest entry; returns the path its artifact is written to. Call with _lock held."""
+        index = len(self.artifacts) + 1
+        name = f"{kind}_{index:04d}.{extension}"
+        self.artifacts.append(dict({"index": index, "type": kind, "file": name,
+                                    "time_s": round(time.monotonic() - self._start, 3)}, **entry))
+        return os.path.join(self.directory, name)
+
+    def _on_frame(self, transport: MockTransport, frame: bytes) -> None:
+        panel = transport.panel
+        with self._lock:
+            path = self._add("frame", "png", {"width": panel.width, "height": panel.height,
+                                              "frame": transport.frame_count})
+            with open(path, "wb") as f:
+                f.write(encode_png_1bit(frame, panel.width, panel.height))
+            self._write_manifest()
+
+    def _on_playback(self, wav_path: str, source: str) -> None:
+        try:
+            with wave.open(wav_path, "rb") as wav:
+                params = wav.getparams()
+        except (OSError, wave.Error) as e:
+            print(f"Warning: Cannot record played audio {source}: {e}")
+            return
+        with self._lock:
+            path = self._add("audio", "wav", {
+                "source": source,
+                "duration_s": round(params.nframes / float(params.framerate), 3),
+                "sample_rate": params.framerate,
+                "channels": params.nchannels,
+                "sample_width": params.sampwidth,
+            })
+            shutil.copyfile(wav_path, path)
+            self._write_manifest()
+
+    def _write_manifest(self) -> None:
+        manifest = {
+            "version": MANIFEST_VERSION,
+            "app": self.app,
+            "started": self.started,
+            "finished": self.finished,
+            "exit_code": self.exit_code,
+            "artifacts": self.artifacts,
+        }
+        path = os.path.join(self.directory, MANIFEST_FILE)
+        tmp_path = path + ".tmp"
+        with open(tmp_path, "w") as f:
+            json.dump(manifest, f, indent=2)
+        os.replace(tmp_path, path)
+
+
+def load_manifest(directory: str) -> Dict[str, Any]:
+    """Read the manifest an ArtifactRecorder wrote into directory."""
+    with open(os.path.join(directory, MANIFEST_FILE)) as f:
+        return json.load(f)
+
+
+def _timestamp() -> str:
+    return time.strftime("%Y-%m-%dT%H:%M:%S%z")
@@ -0,0 +1,4 @@
+from .endpoints import (
+    ButtonEndpoint, DeviceBridge, LightEndpoint, LightState, MessageEndpoint, PressType, SensorEndpoint, SensorKind,
+    SmartHomeError,
+)
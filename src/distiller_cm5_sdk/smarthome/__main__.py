@@ -0,0 +1,116 @@
+#!/usr/bin/env python3
+"""
+Smart-home bridge: python3 -m distiller_cm5_sdk.smarthome {homekit,homeassistant} [options]
+"""
+
+import argparse
+import signal
+import sys
+
+from .endpoints import DeviceBridge, SmartHomeError
+
+
+def _parse_message(text: str):
+    name, sep, message = text.partition("=")
+    if not sep or not name or not message:
+        raise argparse.ArgumentTypeError(f"Expected NAME=TEXT, got '{text}'")
+    return name, message
+
+
+def _serve(args, open_display: bool, publish) -> int:
+    """Open the device endpoints, build the bridge and hand it to a backend's publish(bridge)."""
+    from ..hardware.eink.display import Display, DisplayError
+    from ..recovery.buttons import ButtonError, GpioButtons, parse_pins
+
+    display = led = buttons = None
+    try:
+        if args.gpio_pins:
+            buttons = GpioButtons(parse_pins(args.gpio_pins), chip_name=args.gpio_chip)
+        if open_display:
+            display = Display(backend=args.backend)
+    except (ButtonError, DisplayError) as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    if not args.no_led:
+        try:
+            from ..hardware.sam.led import LED
+            led = LED()
+        except Exception as e:
+            print(f"Warning: No LED endpoint: {e}")
+
+    try:
+        bridge = DeviceBridge.from_device(args.name, display=display, led=led, buttons=buttons)
+        for name, text in args.message:
+            bridge.add_message(name, text)
+        publish(bridge)
+    except SmartHomeError as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    finally:
+        if buttons is not None:
+            buttons.close()
+        if display is not None:
+            display.close()
+    return 0
+
+
+def _homekit(args) -> int:
+    from .homekit import DEFAULT_PORT, HomeKitBridge
+
+    # The display is only needed for messages; leave it to other applications otherwise
+    return _serve(args, bool(args.message), lambda bridge: HomeKitBridge(bridge, port=args.port or DEFAULT_PORT).run())
+
+
+def _homeassistant(args) -> int:
+    from .homeassistant import DEFAULT_PORT, HomeAssistantBridge
+
+    host, _, port = args.broker.partition(":")
+
+    def publish(bridge):
+        ha = HomeAssistantBridge(bridge, host, port=int(port) if port else DEFAULT_PORT,
+                                 username=args.username, password=args.password,
+                                 node_id=args.node_id, discovery_prefix=args.discovery_prefix)
+        signal.signal(signal.SIGTERM, lambda signum, frame: ha.stop())
+        ha.run()
+
+    return _serve(args, bool(args.message) or not args.no_display, publish)
+
+
+def _add_device_arguments(parser) -> None:
+    parser.add_argument("--message", type=_parse_message, action="append", default=[], metavar="NAME=TEXT",
+                        help="Display message switch; repeat for more messages")
+    parser.add_argument("--backend", help="Display backend (default: from eink.toml)")
+    parser.add_argument("--gpio-pins", metavar="UP,DOWN,SELECT", help="GPIO line offsets of the buttons")
+    parser.add_argument("--gpio-chip", default="gpiochip0", help="GPIO chip of the button lines")
+    parser.add_argument("--no-led", action="store_true", help="Do not expose the LED")
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Expose the device to smart-home ecosystems")
+    parser.add_argument("--name", default="Distiller", help="Bridge name shown in the app (default: Distiller)")
+    commands = parser.add_subparsers(dest="command_name", required=True)
+
+    homekit = commands.add_parser("homekit", help="Run a HomeKit bridge")
+    homekit.add_argument("--port", type=int, help="Accessory server port (default: 51826)")
+    _add_device_arguments(homekit)
+    homekit.set_defaults(handler=_homekit)
+
+    homeassistant = commands.add_parser("homeassistant", help="Publish to Home Assistant via MQTT discovery")
+    homeassistant.add_argument("--broker", required=True, metavar="HOST[:PORT]",
+                               help="MQTT broker Home Assistant uses (default port: 1883)")
+    homeassistant.add_argument("--username", help="Broker user")
+    homeassistant.add_argument("--password", help="Broker password")
+    homeassistant.add_argument("--node-id", help="Device ID in Home Assistant (default: host name)")
+    homeassistant.add_argument("--discovery-prefix", default="homeassistant",
+                               help="Discovery prefix configured in Home Assistant (default: homeassistant)")
+    homeassistant.add_argument("--no-display", action="store_true",
+                               help="Do not expose the display as a text entity")
+    _add_device_arguments(homeassistant)
+    homeassistant.set_defaults(handler=_homeassistant)
+
+    args = parser.parse_args(argv)
+    return args.handler(args)
+
+
+if __name__ == "__main__":
+    sys.exit(main())
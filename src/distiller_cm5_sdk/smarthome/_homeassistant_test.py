@@ -0,0 +1,111 @@
+#!/usr/bin/env python3
+"""
+Home Assistant MQTT discovery unit tests for CM5 SDK.
+"""
+
+import json
+import sys
+import unittest
+from pathlib import Path
+from types import SimpleNamespace
+from unittest.mock import Mock
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.smarthome.endpoints import DeviceBridge, PressType, SensorKind
+from distiller_cm5_sdk.smarthome.homeassistant import HomeAssistantBridge
+
+
+class FakeClient:
+    """Records what a paho-mqtt client would send."""
+
+    def __init__(self):
+        self.published = []
+        self.subscribed = []
+        self.will = None
+
+    def username_pw_set(self, username, password):
+        self.credentials = (username, password)
+
+    def will_set(self, topic, payload, retain=False):
+        self.will = (topic, payload, retain)
+
+    def publish(self, topic, payload, qos=0, retain=False):
+        self.published.append((topic, payload, retain))
+
+    def subscribe(self, topic, qos=0):
+        self.subscribed.append(topic)
+
+    def receive(self, topic, payload):
+        self.on_message(self, None, SimpleNamespace(topic=topic, payload=payload.encode()))
+
+    def last(self, topic):
+        return [payload for t, payload, _ in self.published if t == topic][-1]
+
+
+class TestHomeAssistantBridge(unittest.TestCase):
+    """Test cases for the Home Assistant backend."""
+
+    def setUp(self):
+        self.shown = []
+        self.led = Mock()
+        self.bridge = DeviceBridge.from_device("Kitchen", led=self.led, temperature_path=None)
+        self.bridge.add_sensor("Battery", SensorKind.BATTERY, lambda: 87.0, interval_s=60)
+        self.bridge.add_button("select")
+        self.bridge.set_message_display(self.shown.append)
+        self.bridge.add_message("Dinner", "Dinner is ready")
+        self.client = FakeClient()
+        self.ha = HomeAssistantBridge(self.bridge, "broker", username="user", password="secret",
+                                      node_id="Kitchen Pi", client=self.client)
+        self.client.on_connect(self.client, None, {}, 0, None)
+
+    def test_discovery(self):
+        """Test every entity is announced with the device metadata, and the device goes online."""
+        configs = {topic: json.loads(payload) for topic, payload, retain in self.client.published
+                   if topic.endswith("/config") and retain}
+        self.assertEqual(sorted(configs), [
+            "homeassistant/event/kitchen_pi/select_button/config",
+            "homeassistant/light/kitchen_pi/led/config",
+            "homeassistant/sensor/kitchen_pi/battery/config",
+            "homeassistant/switch/kitchen_pi/dinner/config",
+            "homeassistant/text/kitchen_pi/display_message/config",
+        ])
+        battery = configs["homeassistant/sensor/kitchen_pi/battery/config"]
+        self.assertEqual((battery["device_class"], battery["unit_of_measurement"]), ("battery", "%"))
+        self.assertEqual(battery["unique_id"], "distiller_kitchen_pi_battery")
+        self.assertEqual(battery["device"]["identifiers"], ["distiller_kitchen_pi"])
+        self.assertEqual(battery["device"]["name"], "Kitchen")
+        self.assertEqual(configs["homeassistant/event/kitchen_pi/select_button/config"]["event_types"],
+                         ["single", "double", "long"])
+        self.assertEqual(self.client.will, ("distiller/kitchen_pi/status", "offline", True))
+        self.assertEqual(self.client.last("distiller/kitchen_pi/status"), "online")
+        self.assertEqual(len(self.client.subscribed), 3)
+
+        self.ha.poll_sensors(now=0)
+        self.ha.poll_sensors(now=30)    # not due yet
+        self.assertEqual([p for t, p, _ in self.client.published if t == "distiller/kitchen_pi/sensor/battery"],
+                         ["87.0"])
+
+    def test_commands(self):
+        """Test light, text and switch commands reach the device and their state is published back."""
+        self.client.receive("distiller/kitchen_pi/light/led/set",
+                            json.dumps({"state": "ON", "brightness": 50, "color": {"h": 120, "s": 100}}))
+        self.led.set_led_color.assert_called_with(0, 255, 0, brightness=0.5)
+        self.assertEqual(json.loads(self.client.last("distiller/kitchen_pi/light/led"))["state"], "ON")
+
+        self.client.receive("distiller/kitchen_pi/display/set", "Back soon")
+        self.client.receive("distiller/kitchen_pi/message/dinner/set", "ON")
+        self.assertEqual(self.client.last("distiller/kitchen_pi/display"), "")
+        self.assertEqual(self.client.last("distiller/kitchen_pi/message/dinner"), "ON")
+        self.client.receive("distiller/kitchen_pi/message/dinner/set", "OFF")
+        self.assertEqual(self.shown, ["Back soon", "Dinner is ready", None])
+
+        self.client.receive("distiller/kitchen_pi/light/led/set", "not json")     # ignored with a warning
+
+        self.bridge.buttons["select"].press(PressType.DOUBLE)
+        self.assertEqual(self.client.last("distiller/kitchen_pi/button/select"), '{"event_type": "double"}')
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,107 @@
+#!/usr/bin/env python3
+"""
+Smart-home endpoint unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import time
+import unittest
+from pathlib import Path
+from unittest.mock import Mock
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.hardware.eink.backends.mock import get_mock_transport
+from distiller_cm5_sdk.hardware.eink.display import Display
+from distiller_cm5_sdk.recovery.buttons import Button
+from distiller_cm5_sdk.smarthome.endpoints import DeviceBridge, PressType, SensorKind, SmartHomeError
+
+
+class FakeButtons:
+    def __init__(self, presses):
+        self.presses = list(presses)
+
+    def read(self, timeout=None):
+        if self.presses:
+            return self.presses.pop(0)
+        time.sleep(0.01)
+        return None
+
+
+class TestDeviceBridge(unittest.TestCase):
+    """Test cases for the backend-neutral endpoint model."""
+
+    def test_device_endpoints(self):
+        """Test the temperature sensor, LED light and button forwarding of a device."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "temp")
+            Path(path).write_text("48250\n")
+            led = Mock()
+            bridge = DeviceBridge.from_device(led=led, buttons=FakeButtons([Button.SELECT, Button.UP]),
+                                              temperature_path=path)
+            try:
+                sensor, = bridge.sensors
+                self.assertEqual(sensor.kind, SensorKind.TEMPERATURE)
+                self.assertEqual(sensor.read(), 48.25)
+                os.remove(path)
+                self.assertIsNone(sensor.read())
+
+                light, = bridge.lights
+                light.set_state(on=True, hue=120, saturation=100, brightness=50)
+                led.set_led_color.assert_called_with(0, 255, 0, brightness=0.5)
+                light.set_state(on=False)
+                led.set_led_color.assert_called_with(0, 255, 0, brightness=0.0)
+                with self.assertRaises(SmartHomeError):
+                    light.set_state(colour=1)
+
+                presses = []
+                bridge.buttons["select"].add_listener(lambda press: presses.append(("select", press)))
+                bridge.buttons["up"].add_listener(lambda press: presses.append(("up", press)))
+                bridge.start()
+                for _ in range(100):
+                    if len(presses) == 2:
+                        break
+                    time.sleep(0.01)
+                self.assertEqual(presses, [("select", PressType.SINGLE), ("up", PressType.SINGLE)])
+            finally:
+                bridge.stop()
+
+    def test_messages(self):
+        """Test only one message is shown at a time and switching it off clears it."""
+        shown = []
+        bridge = DeviceBridge()
+        with self.assertRaises(SmartHomeError):
+            bridge.add_message("dinner", "Dinner is ready")
+        bridge.set_message_display(shown.append)
+        bridge.add_message("dinner", "Dinner is ready")
+        bridge.add_message("away", "Back at 5")
+
+        bridge.set_message("dinner", True)
+        bridge.set_message("away", True)
+        self.assertFalse(bridge.messages["dinner"].on)
+        bridge.set_message("dinner", False)     # already off: display untouched
+        bridge.set_message("away", False)
+        self.assertEqual(shown, ["Dinner is ready", "Back at 5", None])
+        with self.assertRaises(SmartHomeError):
+            bridge.set_message("bogus", True)
+
+    def test_message_rendering(self):
+        """Test messages are drawn on the display and cleared again."""
+        display = Display(backend="mock")
+        try:
+            bridge = DeviceBridge.from_device(display=display, temperature_path=None)
+            bridge.add_message("long", "A message long enough to be wrapped over several lines " * 3)
+            bridge.set_message("long", True)
+            transport = get_mock_transport(display)
+            self.assertNotEqual(set(transport.last_frame), {0xFF})     # text drawn on white
+            bridge.set_message("long", False)
+            self.assertEqual(set(transport.last_frame), {0xFF})
+        finally:
+            display.close()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,294 @@
+#!/usr/bin/env python3
+"""
+Smart-home endpoints of a Distiller device.
+
+DeviceBridge describes what the device offers a smart-home ecosystem,
+independent of the protocol used to expose it:
+
+- sensors (the CPU temperature, or any value read by a callback)
+- buttons, whose presses become events that trigger automations
+- the RGB LED as a dimmable color light
+- display messages: preset texts that are shown on the e-ink panel when
+  switched on, since neither HomeKit nor Matter can send free text, and
+  free text for ecosystems that can (Home Assistant)
+
+A protocol backend (homekit.py, homeassistant.py) publishes the endpoints and calls
+their setters; the endpoints call back into the SDK's hardware modules.
+
+    bridge = DeviceBridge.from_device(display=display, led=LED(), buttons=GpioButtons(...))
+    bridge.add_message("dinner", "Dinner is ready")
+"""
+
+import colorsys
+import textwrap
+import threading
+from dataclasses import dataclass, field
+from enum import Enum
+from typing import Callable, Dict, List, Optional
+
+from ..diagnostics.audit import AuditCategory, audit
+
+
+CPU_TEMPERATURE_PATH = "/sys/class/thermal/thermal_zone0/temp"
+
+
+class SmartHomeError(Exception):
+    """Custom exception for smart-home bridge errors."""
+    pass
+
+
+class SensorKind(Enum):
+    """Quantities a sensor endpoint can report."""
+    TEMPERATURE = "temperature"     # degrees Celsius
+    HUMIDITY = "humidity"           # percent relative humidity
+    BATTERY = "battery"             # percent charge
+    LIGHT_LEVEL = "light_level"     # lux
+
+
+class PressType(Enum):
+    """Button events, as both HomeKit and Matter distinguish them."""
+    SINGLE = 0
+    DOUBLE = 1
+    LONG = 2
+
+
+@dataclass
+class SensorEndpoint:
+    """A value read on demand, e.g. every interval_s by the backend."""
+    name: str
+    kind: SensorKind
+    read: Callable[[], Optional[float]]     # None while unavailable
+    interval_s: float = 30.0
+
+
+@dataclass
+class ButtonEndpoint:
+    """A button whose presses are forwarded as events."""
+    name: str
+    _listeners: List[Callable[[PressType], None]] = field(default_factory=list, repr=False)
+
+    def add_listener(self, callback: Callable[[PressType], None]) -> None:
+        self._listeners.append(callback)
+
+    def press(self, press_type: PressType = PressType.SINGLE) -> None:
+        for callback in list(self._listeners):
+            try:
+                callback(press_type)
+            except Exception as e:
+                print(f"Warning: Button listener for '{self.name}' failed: {e}")
+
+
+@dataclass
+class LightState:
+    """State of a color light in HomeKit/Matter terms."""
+    on: bool = False
+    brightness: int = 100       # percent
+    hue: float = 0.0            # degrees
+    saturation: float = 0.0     # percent
+
+    def rgb(self) -> tuple:
+        """The color at full brightness as 0-255 components."""
+        r, g, b = colorsys.hsv_to_rgb(self.hue / 360.0, self.saturation / 100.0, 1.0)
+        return round(r * 255), round(g * 255), round(b * 255)
+
+
+@dataclass
+class LightEndpoint:
+    """A light the ecosystem switches, dims and colors."""
+    name: str
+    apply: Callable[[LightState], None]
+    state: LightState = field(default_factory=LightState)
+
+    def set_state(self, **changes) -> LightState:
+        """Change on, brightness, hue or saturation and apply the result."""
+        for key, value in changes.items():
+            if not hasattr(self.state, key):
+                raise SmartHomeError(f"Unknown light attribute '{key}'")
+            setattr(self.state, key, value)
+        self.apply(self.state)
+        return self.state
+
+
+@dataclass
+class MessageEndpoint:
+    """A preset text, shown on the display while switched on."""
+    name: str
+    text: str
+    on: bool = False
+
+
+class DeviceBridge:
+    """The endpoints a device exposes, and their wiring to the SDK."""
+
+    def __init__(self, name: str = "Distiller"):
+        self.name = name
+        self.sensors: List[SensorEndpoint] = []
+        self.buttons: Dict[str, ButtonEndpoint] = {}
+        self.lights: List[LightEndpoint] = []
+        self.messages: Dict[str, MessageEndpoint] = {}
+        self.button_source = None
+        self._show: Optional[Callable[[Optional[str]], None]] = None
+        self._button_thread: Optional[threading.Thread] = None
+        self._stop = threading.Event()
+
+    @classmethod
+    def from_device(cls, name: str = "Distiller", display=None, led=None, buttons=None,
+                    temperature_path: Optional[str] = CPU_TEMPERATURE_PATH) -> "DeviceBridge":
+        """
+        The standard endpoints of a device: CPU temperature, and each of the
+        display, LED and buttons that is given.
+
+        Args:
+            name: Bridge name shown in the ecosystem's app
+            display: Initialized Display for message endpoints
+            led: LED from hardware.sam.led
+            buttons: recovery.buttons.ButtonSource whose presses become button
+                     events once start() is called
+            temperature_path: sysfs file in millidegrees, or None for no sensor
+        """
+        bridge = cls(name)
+        if temperature_path:
+            bridge.add_sensor("CPU temperature", SensorKind.TEMPERATURE,
+                              lambda: _read_millidegrees(temperature_path))
+        if led is not None:
+            bridge.add_light("LED", lambda state: _apply_led(led, state))
+        if display is not None:
+            bridge.set_message_display(lambda text: _show_message(display, text))
+        if buttons is not None:
+            from ..recovery.buttons import Button
+            for button in Button:
+                bridge.add_button(button.value)
+            bridge.button_source = buttons
+        return bridge
+
+    def add_sensor(self, name: str, kind: SensorKind, read: Callable[[], Optional[float]],
+                   interval_s: float = 30.0) -> SensorEndpoint:
+        sensor = SensorEndpoint(name, kind, read, interval_s)
+        self.sensors.append(sensor)
+        return sensor
+
+    def add_button(self, name: str) -> ButtonEndpoint:
+        button = self.buttons[name] = ButtonEndpoint(name)
+        return button
+
+    def add_light(self, name: str, apply: Callable[[LightState], None]) -> LightEndpoint:
+        light = LightEndpoint(name, apply)
+        self.lights.append(light)
+        return light
+
+    def set_message_display(self, show: Callable[[Optional[str]], None]) -> None:
+        """Set how messages are shown; show(None) clears the last one."""
+        self._show = show
+
+    def add_message(self, name: str, text: str) -> MessageEndpoint:
+        """
+        Raises:
+            SmartHomeError: If no message display is set
+        """
+        if self._show is None:
+            raise SmartHomeError("Messages need a display; pass one to from_device() or set_message_display()")
+        message = self.messages[name] = MessageEndpoint(name, text)
+        return message
+
+    def set_message(self, name: str, on: bool) -> None:
+        """
+        Show a message, or clear it. Only one message is on at a time.
+
+        Raises:
+            SmartHomeError: If the message does not exist
+        """
+        message = self.messages.get(name)
+        if message is None:
+            raise SmartHomeError(f"Unknown message '{name}'")
+        if on:
+            for other in self.messages.values():
+                other.on = False
+        elif not message.on:
+            return
+        message.on = on
+        self._show(message.text if on else None)
+        audit(AuditCategory.DISPLAY, "smarthome_message", name, on=on)
+
+    @property
+    def can_show_text(self) -> bool:
+        return self._show is not None
+
+    def show_text(self, text: Optional[str]) -> None:
+        """
+        Show free text on the display, for ecosystems that can send it, or
+        clear the display for None or "". Any message is turned off.
+
+        Raises:
+            SmartHomeError: If no message display is set
+        """
+        if self._show is None:
+            raise SmartHomeError("Text needs a display; pass one to from_device() or set_message_display()")
+        for message in self.messages.values():
+            message.on = False
+        self._show(text or None)
+        audit(AuditCategory.DISPLAY, "smarthome_text", "text", length=len(text or ""))
+
+    def start(self) -> None:
+        """
+        Forward presses from button_source to the button endpoints in a
+        background thread. Called by the backend once its listeners are in place.
+        """
+        if self._button_thread is not None or self.button_source is None:
+            return
+        self._stop.clear()
+        source = self.button_source
+
+        def run() -> None:
+            while not self._stop.is_set():
+                button = source.read(timeout=0.5)
+                if button is not None and button.value in self.buttons:
+                    self.buttons[button.value].press()
+
+        self._button_thread = threading.Thread(target=run, name="smarthome-buttons", daemon=True)
+        self._button_thread.start()
+
+    def stop(self) -> None:
+        self._stop.set()
+        if self._button_thread is not None:
+            self._button_thread.join(timeout=2)
+            self._button_thread = None
+
+
+def _read_millidegrees(path: str) -> Optional[float]:
+    try:
+        with open(path) as f:
+            return int(f.read().strip()) / 1000.0
+    except (OSError, ValueError):
+        return None
+
+
+def _apply_led(led, state: LightState) -> None:
+    r, g, b = state.rgb()
+    led.set_led_color(r, g, b, brightness=state.brightness / 100.0 if state.on else 0.0)
+
+
+def _show_message(display, text: Optional[str]) -> None:
+    """Draw text wrapped and centered, or clear the panel for None."""
+    from ..hardware.eink.display import DisplayMode
+    from ..hardware.eink.font import FONT_HEIGHT, FONT_WIDTH
+    from ..hardware.eink.graphics import Framebuffer
+
+    if text is None:
+        display.clear()
+        return
+    width, height = display.get_dimensions()
+    # Largest scale at which the wrapped text fits
+    for scale in range(4, 0, -1):
+        chars = max(1, (width - 8) // ((FONT_WIDTH + 1) * scale))
+        lines = textwrap.wrap(text, chars) or [""]
+        line_height = (FONT_HEIGHT + 2) * scale
+        if len(lines) * line_height <= height - 8:
+            break
+    lines = lines[:max(1, (height - 8) // line_height)]
+    fb = Framebuffer(width, height)
+    y = (height - len(lines) * line_height) // 2
+    for line in lines:
+        text_width = len(line) * (FONT_WIDTH + 1) * scale - scale
+        fb.draw_text((width - text_width) // 2, y, line, scale=scale)
+        y += line_height
+    display.display_image(fb.to_bytes(), DisplayMode.FULL)
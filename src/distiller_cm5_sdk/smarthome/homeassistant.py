@@ -0,0 +1,285 @@
+#!/usr/bin/env python3
+"""
+Home Assistant integration for CM5 SDK.
+
+Publishes a DeviceBridge over MQTT using Home Assistant's discovery
+protocol, so the device and its entities appear in Home Assistant as soon
+as the bridge connects to HA's broker, without any YAML:
+
+- sensors become sensor entities with their device class and unit
+- buttons become event entities, usable as automation triggers
+- the LED becomes a light with brightness and color
+- the display becomes a text entity showing whatever is typed into it, and
+  each preset message a switch
+
+Needs paho-mqtt (pip install "distiller-cm5-sdk[mqtt]").
+
+    HomeAssistantBridge(bridge, "homeassistant.local", username="distiller", password="...").run()
+"""
+
+import json
+import re
+import socket
+import threading
+import time
+from typing import Callable, Dict, List, Optional, Tuple
+
+from .endpoints import DeviceBridge, LightEndpoint, PressType, SensorEndpoint, SensorKind, SmartHomeError
+
+
+DISCOVERY_PREFIX = "homeassistant"
+TOPIC_PREFIX = "distiller"
+DEFAULT_PORT = 1883
+TEXT_MAX_LENGTH = 255       # longest state Home Assistant accepts
+
+# Home Assistant device class and unit of each sensor kind
+SENSOR_CLASSES = {
+    SensorKind.TEMPERATURE: ("temperature", "°C"),
+    SensorKind.HUMIDITY: ("humidity", "%"),
+    SensorKind.BATTERY: ("battery", "%"),
+    SensorKind.LIGHT_LEVEL: ("illuminance", "lx"),
+}
+
+
+def slugify(name: str) -> str:
+    """Topic- and ID-safe form of a name, e.g. "CPU temperature" -> "cpu_temperature"."""
+    return re.sub(r"[^a-z0-9]+", "_", name.lower()).strip("_") or "entity"
+
+
+def default_node_id() -> str:
+    """Node ID from the host name, unique on most networks."""
+    return slugify(socket.gethostname())
+
+
+def _sdk_version() -> Optional[str]:
+    try:
+        from importlib.metadata import version
+        return version("distiller-cm5-sdk")
+    except Exception:
+        return None
+
+
+def _create_client(client_id: str):
+    try:
+        import paho.mqtt.client as mqtt
+    except ImportError:
+        raise SmartHomeError('Home Assistant needs paho-mqtt; install with pip install "distiller-cm5-sdk[mqtt]"')
+    return mqtt.Client(mqtt.CallbackAPIVersion.VERSION2, client_id=client_id)
+
+
+class HomeAssistantBridge:
+    """Publishes a DeviceBridge to Home Assistant through MQTT discovery."""
+
+    def __init__(self, bridge: DeviceBridge, host: str, port: int = DEFAULT_PORT,
+                 username: Optional[str] = None, password: Optional[str] = None,
+                 node_id: Optional[str] = None, discovery_prefix: str = DISCOVERY_PREFIX,
+                 client=None, clock: Callable[[], float] = time.monotonic):
+        """
+        Args:
+            bridge: Endpoints to publish
+            host: MQTT broker Home Assistant is connected to
+            port: Broker port
+            username: Broker user, if it requires login
+            password: Broker password
+            node_id: Unique ID of this device in Home Assistant; defaults to the host name
+            discovery_prefix: Discovery prefix configured in Home Assistant
+            client: paho-mqtt Client, replaceable for testing
+            clock: Monotonic clock, replaceable for testing
+
+        Raises:
+            SmartHomeError: If paho-mqtt is not installed
+        """
+        self.bridge = bridge
+        self.host = host
+        self.port = port
+        self.node_id = slugify(node_id) if node_id else default_node_id()
+        self.discovery_prefix = discovery_prefix
+        self.base_topic = f"{TOPIC_PREFIX}/{self.node_id}"
+        self.availability_topic = f"{self.base_topic}/status"
+        self._clock = clock
+        self._stop = threading.Event()
+        self._next_read: Dict[str, float] = {}
+        self._text = ""
+        self._commands: Dict[str, Callable[[str], None]] = {}
+
+        self.client = client if client is not None else _create_client(f"distiller-{self.node_id}")
+        if username:
+            self.client.username_pw_set(username, password)
+        self.client.will_set(self.availability_topic, "offline", retain=True)
+        self.client.on_connect = self._on_connect
+        self.client.on_message = self._on_message
+
+        for name, button in bridge.buttons.items():
+            topic = f"{self.base_topic}/button/{slugify(name)}"
+            button.add_listener(lambda press, topic=topic: self._publish(
+                topic, json.dumps({"event_type": press.name.lower()})))
+
+    @property
+    def device(self) -> dict:
+        """Device registry entry shared by all entities."""
+        device = {
+            "identifiers": [f"distiller_{self.node_id}"],
+            "name": self.bridge.name,
+            "manufacturer": "Pamir AI",
+            "model": "Distiller CM5",
+        }
+        version = _sdk_version()
+        if version:
+            device["sw_version"] = version
+        return device
+
+    def _entity(self, component: str, name: str, **fields) -> Tuple[str, dict]:
+        object_id = slugify(name)
+        config = {
+            "name": name,
+            "unique_id": f"distiller_{self.node_id}_{object_id}",
+            "availability_topic": self.availability_topic,
+            "device": self.device,
+        }
+        config.update(fields)
+        return f"{self.discovery_prefix}/{component}/{self.node_id}/{object_id}/config", config
+
+    def discovery(self) -> List[Tuple[str, dict]]:
+        """Discovery topics and configs of every entity."""
+        entities = []
+        for sensor in self.bridge.sensors:
+            device_class, unit = SENSOR_CLASSES[sensor.kind]
+            entities.append(self._entity(
+                "sensor", sensor.name, state_topic=self._sensor_topic(sensor), device_class=device_class,
+                unit_of_measurement=unit, state_class="measurement",
+                expire_after=int(sensor.interval_s * 3)))
+        for name in self.bridge.buttons:
+            entities.append(self._entity(
+                "event", f"{name.capitalize()} button", state_topic=f"{self.base_topic}/button/{slugify(name)}",
+                event_types=[press.name.lower() for press in PressType], device_class="button"))
+        for light in self.bridge.lights:
+            topic = self._light_topic(light)
+            entities.append(self._entity(
+                "light", light.name, schema="json", state_topic=topic, command_topic=f"{topic}/set",
+                brightness=True, brightness_scale=100, supported_color_modes=["hs"]))
+        if self.bridge.can_show_text:
+            topic = f"{self.base_topic}/display"
+            entities.append(self._entity(
+                "text", "Display message", state_topic=topic, command_topic=f"{topic}/set",
+                max=TEXT_MAX_LENGTH, icon="mdi:message-text"))
+        for name in self.bridge.messages:
+            topic = f"{self.base_topic}/message/{slugify(name)}"
+            entities.append(self._entity(
+                "switch", name, state_topic=topic, command_topic=f"{topic}/set", icon="mdi:message-badge"))
+        return entities
+
+    def _sensor_topic(self, sensor: SensorEndpoint) -> str:
+        return f"{self.base_topic}/sensor/{slugify(sensor.name)}"
+
+    def _light_topic(self, light: LightEndpoint) -> str:
+        return f"{self.base_topic}/light/{slugify(light.name)}"
+
+    def _publish(self, topic: str, payload: str, retain: bool = False) -> None:
+        self.client.publish(topic, payload, qos=1, retain=retain)
+
+    def _publish_light(self, light: LightEndpoint) -> None:
+        state = light.state
+        self._publish(self._light_topic(light), json.dumps({
+            "state": "ON" if state.on else "OFF",
+            "brightness": state.brightness,
+            "color_mode": "hs",
+            "color": {"h": state.hue, "s": state.saturation},
+        }), retain=True)
+
+    def _publish_messages(self) -> None:
+        for name, message in self.bridge.messages.items():
+            self._publish(f"{self.base_topic}/message/{slugify(name)}", "ON" if message.on else "OFF", retain=True)
+        if self.bridge.can_show_text:
+            self._publish(f"{self.base_topic}/display", self._text, retain=True)
+
+    def _on_connect(self, client, userdata, flags, reason_code=0, properties=None) -> None:
+        if reason_code != 0:
+            print(f"Warning: MQTT broker {self.host} refused the connection: {reason_code}")
+            return
+        self._commands = {}
+        for topic, config in self.discovery():
+            self._publish(topic, json.dumps(config), retain=True)
+            if "command_topic" in config:
+                self._commands[config["command_topic"]] = self._command_handler(topic.split("/")[1], config)
+                client.subscribe(config["command_topic"], qos=1)
+        self._publish(self.availability_topic, "online", retain=True)
+        for light in self.bridge.lights:
+            self._publish_light(light)
+        self._publish_messages()
+        self._next_read.clear()     # publish sensor values again after a reconnect
+
+    def _command_handler(self, component: str, config: dict) -> Callable[[str], None]:
+        if component == "light":
+            light = next(l for l in self.bridge.lights if self._light_topic(l) == config["state_topic"])
+            return lambda payload: self._set_light(light, payload)
+        if component == "text":
+            return self._set_text
+        name = next(n for n in self.bridge.messages if config["state_topic"].endswith("/" + slugify(n)))
+        return lambda payload: self._set_message(name, payload)
+
+    def _set_light(self, light: LightEndpoint, payload: str) -> None:
+        command = json.loads(payload)
+        changes = {}
+        if "state" in command:
+            changes["on"] = command["state"] == "ON"
+        if "brightness" in command:
+            changes["brightness"] = int(command["brightness"])
+        if "color" in command:
+            changes["hue"] = float(command["color"].get("h", light.state.hue))
+            changes["saturation"] = float(command["color"].get("s", light.state.saturation))
+        light.set_state(**changes)
+        self._publish_light(light)
+
+    def _set_text(self, payload: str) -> None:
+        self._text = payload[:TEXT_MAX_LENGTH]
+        self.bridge.show_text(self._text)
+        self._publish_messages()
+
+    def _set_message(self, name: str, payload: str) -> None:
+        self.bridge.set_message(name, payload == "ON")
+        if payload == "ON":
+            self._text = ""
+        self._publish_messages()
+
+    def _on_message(self, client, userdata, message) -> None:
+        handler = self._commands.get(message.topic)
+        if handler is None:
+            return
+        try:
+            handler(message.payload.decode("utf-8"))
+        except Exception as e:
+            print(f"Warning: Failed to handle {message.topic}: {e}")
+
+    def poll_sensors(self, now: Optional[float] = None) -> None:
+        """Publish each sensor whose interval has passed."""
+        now = self._clock() if now is None else now
+        for sensor in self.bridge.sensors:
+            topic = self._sensor_topic(sensor)
+            if now < self._next_read.get(topic, 0.0):
+                continue
+            self._next_read[topic] = now + sensor.interval_s
+            value = sensor.read()
+            if value is not None:
+                self._publish(topic, str(round(value, 2)))
+
+    def run(self) -> None:
+        """Connect, reconnecting as needed, and serve until stop() or Ctrl+C."""
+        self.client.connect_async(self.host, self.port)
+        self.client.loop_start()
+        self.bridge.start()
+        print(f"Publishing '{self.bridge.name}' to Home Assistant via {self.host}:{self.port} as {self.node_id}")
+        try:
+            while not self._stop.wait(1.0):
+                if self.client.is_connected():
+                    self.poll_sensors()
+        except KeyboardInterrupt:
+            pass
+        finally:
+            self.bridge.stop()
+            self.client.publish(self.availability_topic, "offline", qos=1, retain=True).wait_for_publish(2)
+            self.client.disconnect()
+            self.client.loop_stop()
+
+    def stop(self) -> None:
+        """End run() from another thread or a signal handler."""
+        self._stop.set()
@@ -0,0 +1,146 @@
+#!/usr/bin/env python3
+"""
+HomeKit bridge for CM5 SDK.
+
+Publishes a DeviceBridge as a HomeKit bridge accessory using HAP-python
+(pip install "distiller-cm5-sdk[homekit]"). Sensors become HomeKit sensors,
+buttons stateless programmable switches, the LED a color light and each
+display message a switch. The bridge can be paired from Apple Home, and
+Home Assistant's HomeKit Controller integration. This is HomeKit, not
+Matter; the SDK has no Matter backend (see the module README).
+
+    HomeKitBridge(bridge).run()    # blocks; prints the pairing code
+"""
+
+import os
+from typing import Dict
+
+from .endpoints import DeviceBridge, LightEndpoint, MessageEndpoint, SensorEndpoint, SensorKind, SmartHomeError
+
+
+STATE_PATH = "/var/lib/distiller-cm5-sdk/homekit.state"
+DEFAULT_PORT = 51826
+
+# HAP service and characteristic of each sensor kind
+SENSOR_SERVICES = {
+    SensorKind.TEMPERATURE: ("TemperatureSensor", "CurrentTemperature"),
+    SensorKind.HUMIDITY: ("HumiditySensor", "CurrentRelativeHumidity"),
+    SensorKind.BATTERY: ("BatteryService", "BatteryLevel"),
+    SensorKind.LIGHT_LEVEL: ("LightSensor", "CurrentAmbientLightLevel"),
+}
+
+LIGHT_CHARACTERISTICS = {"On": "on", "Brightness": "brightness", "Hue": "hue", "Saturation": "saturation"}
+
+
+def _import_pyhap():
+    try:
+        from pyhap import accessory, accessory_driver, const, util
+    except ImportError:
+        raise SmartHomeError('HomeKit needs HAP-python; install with pip install "distiller-cm5-sdk[homekit]"')
+    return accessory, accessory_driver, const, util
+
+
+class HomeKitBridge:
+    """Publishes a DeviceBridge over HomeKit Accessory Protocol."""
+
+    def __init__(self, bridge: DeviceBridge, port: int = DEFAULT_PORT, state_path: str = STATE_PATH):
+        """
+        Args:
+            bridge: Endpoints to publish
+            port: TCP port of the accessory server
+            state_path: File keeping the pairing and accessory IDs across restarts
+
+        Raises:
+            SmartHomeError: If HAP-python is not installed
+        """
+        accessory, accessory_driver, const, util = _import_pyhap()
+        self._accessory, self._const, self._util = accessory, const, util
+        self.bridge = bridge
+
+        os.makedirs(os.path.dirname(state_path), exist_ok=True)
+        self.driver = accessory_driver.AccessoryDriver(port=port, persist_file=state_path)
+        self._hap_bridge = accessory.Bridge(self.driver, bridge.name)
+        self._message_chars: Dict[str, object] = {}
+
+        for sensor in bridge.sensors:
+            self._add_sensor(sensor)
+        for name, button in bridge.buttons.items():
+            self._add_button(name, button)
+        for light in bridge.lights:
+            self._add_light(light)
+        for message in bridge.messages.values():
+            self._add_message(message)
+        self.driver.add_accessory(self._hap_bridge)
+
+    def _new_accessory(self, name: str, category: int):
+        acc = self._accessory.Accessory(self.driver, name)
+        acc.category = category
+        self._hap_bridge.add_accessory(acc)
+        return acc
+
+    def _add_sensor(self, sensor: SensorEndpoint) -> None:
+        service_name, char_name = SENSOR_SERVICES[sensor.kind]
+        acc = self._new_accessory(sensor.name, self._const.CATEGORY_SENSOR)
+        char = acc.add_preload_service(service_name).configure_char(char_name)
+        stop_event, event_wait = self.driver.aio_stop_event, self._util.event_wait
+
+        async def poll() -> None:
+            while True:
+                value = sensor.read()
+                if value is not None:
+                    char.set_value(value)
+                if await event_wait(stop_event, sensor.interval_s):
+                    break
+
+        acc.run = poll
+
+    def _add_button(self, name: str, button) -> None:
+        acc = self._new_accessory(f"{name.capitalize()} button", self._const.CATEGORY_PROGRAMMABLE_SWITCH)
+        char = acc.add_preload_service("StatelessProgrammableSwitch").configure_char("ProgrammableSwitchEvent")
+        loop = self.driver.loop
+
+        # Presses arrive on the bridge's button thread; HAP notifications belong on the driver loop
+        button.add_listener(lambda press: loop.call_soon_threadsafe(char.set_value, press.value))
+
+    def _add_light(self, light: LightEndpoint) -> None:
+        acc = self._new_accessory(light.name, self._const.CATEGORY_LIGHTBULB)
+        service = acc.add_preload_service("Lightbulb", chars=list(LIGHT_CHARACTERISTICS))
+
+        def on_set(values: Dict[str, object]) -> None:
+            light.set_state(**{LIGHT_CHARACTERISTICS[key]: value for key, value in values.items()
+                               if key in LIGHT_CHARACTERISTICS})
+
+        service.setter_callback = on_set
+
+    def _add_message(self, message: MessageEndpoint) -> None:
+        acc = self._new_accessory(message.name, self._const.CATEGORY_SWITCH)
+        char = acc.add_preload_service("Switch").configure_char("On")
+        self._message_chars[message.name] = char
+
+        def on_set(value: bool) -> None:
+            try:
+                self.bridge.set_message(message.name, bool(value))
+            except Exception as e:
+                print(f"Warning: Failed to show message '{message.name}': {e}")
+            # Turning one message on turns the others off
+            for name, other in self.bridge.messages.items():
+                self._message_chars[name].set_value(other.on)
+
+        char.setter_callback = on_set
+
+    @property
+    def pairing_code(self) -> str:
+        """Setup code to enter in the Home app, e.g. 123-45-678."""
+        return self.driver.state.pincode.decode()
+
+    def run(self) -> None:
+        """Serve until SIGINT or SIGTERM."""
+        print(f"HomeKit bridge '{self.bridge.name}', pairing code {self.pairing_code}")
+        self.bridge.start()
+        try:
+            self.driver.start()
+        finally:
+            self.bridge.stop()
+
+    def stop(self) -> None:
+        self.driver.stop()
@@ -0,0 +1,2 @@
+from .pomodoro import Phase, PomodoroConfig, PomodoroTimer, TimerError, TimerState, render_timer
+from .service import TimerService, send_command
@@ -0,0 +1,90 @@
+#!/usr/bin/env python3
+"""
+Pomodoro timer: python3 -m distiller_cm5_sdk.timer run | ctl <command>
+"""
+
+import argparse
+import json
+import sys
+
+from .pomodoro import PomodoroConfig, TimerError
+from .service import COMMANDS, TimerService, send_command
+
+
+def _run(args) -> int:
+    from ..hardware.eink.display import Display, DisplayError
+    from ..recovery.buttons import ButtonError, GpioButtons, parse_pins
+
+    config = PomodoroConfig(work_s=int(args.work * 60), short_break_s=int(args.short_break * 60),
+                            long_break_s=int(args.long_break * 60), long_break_every=args.long_break_every,
+                            auto_start=args.auto_start)
+    try:
+        config.validate()
+        buttons = GpioButtons(parse_pins(args.gpio_pins), chip_name=args.gpio_chip) if args.gpio_pins else None
+    except (TimerError, ButtonError) as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+
+    audio = led = None
+    if not args.quiet:
+        try:
+            from ..hardware.audio.audio import Audio
+            audio = Audio(auto_check_config=False)
+        except Exception as e:
+            print(f"Warning: No end-of-interval tone: {e}")
+        try:
+            from ..hardware.sam.led import LED
+            led = LED()
+        except Exception as e:
+            print(f"Warning: No LED alert: {e}")
+
+    try:
+        with Display(backend=args.backend) as display:
+            TimerService(display, config, audio=audio, led=led, buttons=buttons, socket_path=args.socket).run()
+    except (DisplayError, OSError) as e:
+        print(f"Error: {e}", file=sys.stderr)
+        return 1
+    finally:
+        if buttons is not None:
+            buttons.close()
+    return 0
+
+
+def _ctl(args) -> int:
+    try:
+        reply = send_command(args.command, args.socket)
+    except (OSError, ValueError) as e:
+        print(f"Error: Timer service not reachable: {e}", file=sys.stderr)
+        return 1
+    print(json.dumps(reply))
+    return 0 if reply.get("ok") else 1
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(description="Distiller CM5 pomodoro timer")
+    parser.add_argument("--socket", help="Control socket (default: DISTILLER_TIMER_SOCKET or "
+                                         "/run/distiller-cm5-sdk/timer.sock)")
+    commands = parser.add_subparsers(dest="command_name", required=True)
+
+    run = commands.add_parser("run", help="Run the timer on the e-ink display")
+    run.add_argument("--work", type=float, default=25, help="Work interval in minutes (default: 25)")
+    run.add_argument("--short-break", type=float, default=5, help="Short break in minutes (default: 5)")
+    run.add_argument("--long-break", type=float, default=15, help="Long break in minutes (default: 15)")
+    run.add_argument("--long-break-every", type=int, default=4, help="Work intervals per long break (default: 4)")
+    run.add_argument("--auto-start", action="store_true", help="Start each interval without a button press")
+    run.add_argument("--backend", help="Display backend (default: from eink.toml)")
+    run.add_argument("--gpio-pins", metavar="UP,DOWN,SELECT", help="GPIO line offsets of the buttons")
+    run.add_argument("--gpio-chip", default="gpiochip0", help="GPIO chip of the button lines")
+    run.add_argument("--quiet", action="store_true", help="No tone or LED when an interval ends")
+    run.set_defaults(handler=_run)
+
+    ctl = commands.add_parser("ctl", help="Control a running timer")
+    ctl.add_argument("command", choices=COMMANDS)
+    ctl.set_defaults(handler=_ctl)
+
+    args = parser.parse_args(argv)
+    return args.handler(args)
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,120 @@
+#!/usr/bin/env python3
+"""
+Pomodoro timer unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import threading
+import time
+import unittest
+from pathlib import Path
+from unittest.mock import Mock
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.hardware.eink.display import Display
+from distiller_cm5_sdk.timer.pomodoro import (
+    Phase, PomodoroConfig, PomodoroTimer, TimerError, TimerState, format_remaining, render_timer, tone_pcm,
+)
+from distiller_cm5_sdk.timer.service import TimerService, send_command
+
+
+class FakeClock:
+    def __init__(self):
+        self.now = 1000.0
+
+    def __call__(self):
+        return self.now
+
+
+class TestPomodoroTimer(unittest.TestCase):
+    """Test cases for the timer logic."""
+
+    def setUp(self):
+        self.clock = FakeClock()
+        config = PomodoroConfig(work_s=100, short_break_s=10, long_break_s=30, long_break_every=2)
+        self.timer = PomodoroTimer(config, clock=self.clock)
+
+    def test_countdown_pause_resume(self):
+        """Test the countdown stops while paused."""
+        self.timer.start()
+        self.clock.now += 30
+        self.timer.pause()
+        self.clock.now += 500
+        self.assertEqual(self.timer.remaining(), 70)
+        self.timer.toggle()
+        self.clock.now += 20
+        self.assertEqual(self.timer.remaining(), 50)
+        self.assertIsNone(self.timer.tick())
+
+    def test_cycle(self):
+        """Test work alternates with short breaks and every second break is long."""
+        finished, phases = [], []
+        for _ in range(4):
+            self.timer.start()
+            self.clock.now += self.timer.remaining()
+            finished.append(self.timer.tick())
+            phases.append(self.timer.phase)
+        self.assertEqual(phases, [Phase.SHORT_BREAK, Phase.WORK, Phase.LONG_BREAK, Phase.WORK])
+        self.assertEqual(finished, [Phase.WORK, Phase.SHORT_BREAK, Phase.WORK, Phase.LONG_BREAK])
+        self.assertEqual(self.timer.state, TimerState.IDLE)
+        self.assertEqual(self.timer.completed, 2)
+        self.timer.reset()
+        self.assertEqual((self.timer.phase, self.timer.completed, self.timer.remaining()), (Phase.WORK, 0, 100))
+
+    def test_auto_start_and_skip(self):
+        """Test auto_start runs the next interval and skip ends one early."""
+        timer = PomodoroTimer(PomodoroConfig(work_s=100, auto_start=True), clock=self.clock)
+        timer.start()
+        self.assertEqual(timer.skip(), Phase.SHORT_BREAK)
+        self.assertEqual(timer.state, TimerState.RUNNING)
+        with self.assertRaises(TimerError):
+            PomodoroTimer(PomodoroConfig(work_s=0))
+
+    def test_render_and_tone(self):
+        """Test the face fits the frame, the countdown rounds up, and the tone has the expected length."""
+        fb = render_timer(self.timer, 250, 128)
+        self.assertEqual((fb.width, fb.height), (250, 128))
+        self.assertEqual(format_remaining(59.2), "01:00")
+        self.assertEqual(len(tone_pcm(duration_s=0.1, repeats=2, sample_rate=8000, channels=1)), 2 * (800 + 800) * 2)
+
+
+class TestTimerService(unittest.TestCase):
+    """Test cases for the service's IPC and alerts."""
+
+    def test_ipc_commands(self):
+        """Test commands over the control socket drive the timer and quit ends the service."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "timer.sock")
+            display = Display(backend="mock")
+            service = TimerService(display, PomodoroConfig(work_s=1, short_break_s=60), led=Mock(),
+                                   socket_path=path)
+            thread = threading.Thread(target=service.run)
+            thread.start()
+            for _ in range(100):
+                if os.path.exists(path):
+                    break
+                time.sleep(0.01)
+
+            self.assertEqual(send_command("status", path)["state"], "idle")
+            self.assertFalse(send_command("bogus", path)["ok"])
+            self.assertEqual(send_command("start", path)["state"], "running")
+            for _ in range(300):
+                if service.timer.phase == Phase.SHORT_BREAK:
+                    break
+                time.sleep(0.01)
+            self.assertEqual(service.timer.phase, Phase.SHORT_BREAK)
+            service.led.blink_led.assert_called_once()
+
+            send_command("quit", path)
+            thread.join(timeout=5)
+            self.assertFalse(thread.is_alive())
+            self.assertFalse(os.path.exists(path))
+            display.close()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,219 @@
+#!/usr/bin/env python3
+"""
+Pomodoro timer logic and rendering for CM5 SDK.
+
+PomodoroTimer cycles through work intervals and short breaks, with a long
+break after every few work intervals. It holds no threads: the owner calls
+tick() regularly and acts on the phase changes it returns, so the same logic
+drives the on-device service and tests with a fake clock.
+"""
+
+import math
+import struct
+import time
+from dataclasses import dataclass
+from enum import Enum
+from typing import Callable, Optional
+
+from ..hardware.eink.font import FONT_HEIGHT, FONT_WIDTH
+from ..hardware.eink.graphics import Framebuffer
+
+
+class TimerError(Exception):
+    """Custom exception for timer errors."""
+    pass
+
+
+class Phase(Enum):
+    """Interval kinds of a pomodoro cycle."""
+    WORK = "work"
+    SHORT_BREAK = "short_break"
+    LONG_BREAK = "long_break"
+
+    @property
+    def label(self) -> str:
+        return {Phase.WORK: "Focus", Phase.SHORT_BREAK: "Break", Phase.LONG_BREAK: "Long break"}[self]
+
+
+class TimerState(Enum):
+    """Whether the current interval is counting down."""
+    IDLE = "idle"           # interval not started
+    RUNNING = "running"
+    PAUSED = "paused"
+
+
+@dataclass(frozen=True)
+class PomodoroConfig:
+    """Interval lengths in seconds."""
+    work_s: int = 25 * 60
+    short_break_s: int = 5 * 60
+    long_break_s: int = 15 * 60
+    long_break_every: int = 4           # work intervals per long break
+    auto_start: bool = False            # start the next interval without a button press
+
+    def validate(self) -> None:
+        """
+        Raises:
+            TimerError: If an interval is not positive
+        """
+        if min(self.work_s, self.short_break_s, self.long_break_s, self.long_break_every) <= 0:
+            raise TimerError("Pomodoro intervals and long_break_every must be positive")
+
+    def duration(self, phase: Phase) -> int:
+        return {Phase.WORK: self.work_s, Phase.SHORT_BREAK: self.short_break_s,
+                Phase.LONG_BREAK: self.long_break_s}[phase]
+
+
+class PomodoroTimer:
+    """Countdown through work and break intervals."""
+
+    def __init__(self, config: Optional[PomodoroConfig] = None, clock: Callable[[], float] = time.monotonic):
+        """
+        Args:
+            config: Interval lengths; None uses the defaults (25/5/15 minutes)
+            clock: Monotonic clock in seconds, replaceable for testing
+        """
+        self.config = config or PomodoroConfig()
+        self.config.validate()
+        self._clock = clock
+        self.phase = Phase.WORK
+        self.state = TimerState.IDLE
+        self.completed = 0              # work intervals finished
+        self._remaining = float(self.config.work_s)
+        self._started_at = 0.0
+
+    def remaining(self) -> float:
+        """Seconds left in the current interval."""
+        if self.state == TimerState.RUNNING:
+            return max(self._remaining - (self._clock() - self._started_at), 0.0)
+        return self._remaining
+
+    def start(self) -> None:
+        """Start or resume the current interval."""
+        if self.state != TimerState.RUNNING:
+            self._started_at = self._clock()
+            self.state = TimerState.RUNNING
+
+    def pause(self) -> None:
+        if self.state == TimerState.RUNNING:
+            self._remaining = self.remaining()
+            self.state = TimerState.PAUSED
+
+    def toggle(self) -> None:
+        """Start, pause or resume, as the single button does."""
+        if self.state == TimerState.RUNNING:
+            self.pause()
+        else:
+            self.start()
+
+    def reset(self) -> None:
+        """Return to a stopped work interval and clear the cycle."""
+        self.completed = 0
+        self._enter(Phase.WORK, running=False)
+
+    def skip(self) -> Phase:
+        """End the current interval early; returns the next phase."""
+        return self._advance(self.state == TimerState.RUNNING and self.config.auto_start)
+
+    def tick(self) -> Optional[Phase]:
+        """
+        Advance the timer.
+
+        Returns:
+            The phase that just finished, or None
+        """
+        if self.state != TimerState.RUNNING or self.remaining() > 0:
+            return None
+        finished = self.phase
+        self._advance(self.config.auto_start)
+        return finished
+
+    def _advance(self, running: bool) -> Phase:
+        if self.phase == Phase.WORK:
+            self.completed += 1
+            long_break = self.completed % self.config.long_break_every == 0
+            self._enter(Phase.LONG_BREAK if long_break else Phase.SHORT_BREAK, running)
+        else:
+            self._enter(Phase.WORK, running)
+        return self.phase
+
+    def _enter(self, phase: Phase, running: bool) -> None:
+        self.phase = phase
+        self._remaining = float(self.config.duration(phase))
+        self.state = TimerState.IDLE
+        if running:
+            self.start()
+
+    def status(self) -> dict:
+        """State for IPC clients and logs."""
+        return {"phase": self.phase.value, "state": self.state.value,
+                "remaining_s": round(self.remaining(), 1), "completed": self.completed}
+
+
+def format_remaining(seconds: float) -> str:
+    """Countdown text, e.g. "24:59"; rounds up so 0:00 only shows when the interval ends."""
+    seconds = int(math.ceil(seconds))
+    return f"{seconds // 60:02d}:{seconds % 60:02d}"
+
+
+def _text_width(text: str, scale: int) -> int:
+    return max(len(text) * (FONT_WIDTH + 1) * scale - scale, 0)
+
+
+def render_timer(timer: PomodoroTimer, width: int, height: int) -> Framebuffer:
+    """
+    Draw the timer: phase label, countdown, a progress bar and the cycle count.
+
+    Args:
+        timer: Timer to draw
+        width: Frame width in pixels
+        height: Frame height in pixels
+    """
+    fb = Framebuffer(width, height)
+    margin = max(width, height) // 25
+
+    label = timer.phase.label + (" (paused)" if timer.state == TimerState.PAUSED else "")
+    fb.draw_text(margin, margin, label, scale=2 if _text_width(label, 2) <= width - 2 * margin else 1)
+
+    countdown = format_remaining(timer.remaining())
+    scale = 1
+    while (_text_width(countdown, scale + 1) <= width - 2 * margin
+           and FONT_HEIGHT * (scale + 1) <= height // 2):
+        scale += 1
+    fb.draw_text((width - _text_width(countdown, scale)) // 2, (height - FONT_HEIGHT * scale) // 2,
+                 countdown, scale=scale)
+
+    bar_height = max(height // 16, 4)
+    bar_y = height - margin - bar_height - FONT_HEIGHT - 4
+    duration = timer.config.duration(timer.phase)
+    done = 1.0 - timer.remaining() / duration
+    fb.draw_rect(margin, bar_y, width - 2 * margin, bar_height)
+    fb.draw_rect(margin, bar_y, int((width - 2 * margin) * done), bar_height, fill=True)
+
+    cycle = f"{timer.completed % timer.config.long_break_every}/{timer.config.long_break_every}"
+    fb.draw_text(width - margin - _text_width(cycle, 1), height - margin - FONT_HEIGHT, cycle)
+    return fb
+
+
+def refresh_interval(remaining: float) -> float:
+    """Seconds between countdown redraws: each minute, then every 10 s in the last minute."""
+    return 10.0 if remaining <= 60 else 60.0
+
+
+def tone_pcm(frequency: float = 880.0, duration_s: float = 0.25, repeats: int = 3,
+             sample_rate: int = 48000, channels: int = 2, volume: float = 0.5) -> bytes:
+    """
+    Generate an alert as S16_LE PCM: repeats beeps of duration_s, each followed by an equal gap.
+
+    The beeps fade in and out over 5 ms to avoid clicks.
+    """
+    beep = int(sample_rate * duration_s)
+    fade = max(int(sample_rate * 0.005), 1)
+    amplitude = int(32767 * max(0.0, min(volume, 1.0)))
+    samples = []
+    for n in range(beep):
+        envelope = min(1.0, n / fade, (beep - n) / fade)
+        samples.append(int(amplitude * envelope * math.sin(2 * math.pi * frequency * n / sample_rate)))
+    beep_frame = b"".join(struct.pack("<" + "h" * channels, *([s] * channels)) for s in samples)
+    silence = bytes(len(beep_frame))
+    return (beep_frame + silence) * repeats
@@ -0,0 +1,224 @@
+#!/usr/bin/env python3
+"""
+Pomodoro timer service for CM5 SDK.
+
+Runs a PomodoroTimer on the e-ink display, plays a tone and blinks the LED
+when an interval ends, and takes commands from buttons and from other
+processes over a Unix socket.
+
+Buttons: SELECT starts/pauses, UP skips to the next interval, DOWN resets.
+
+IPC: one command per connection, a line of text answered with a line of
+JSON holding the timer status:
+
+    $ python3 -m distiller_cm5_sdk.timer ctl pause
+    {"ok": true, "phase": "work", "state": "paused", "remaining_s": 1312.4, "completed": 1}
+"""
+
+import json
+import os
+import socket
+import threading
+from typing import Optional
+
+from ..diagnostics.log_levels import get_logger
+from ..hardware.eink.display import DisplayMode
+from ..recovery.buttons import Button, ButtonSource
+from .pomodoro import Phase, PomodoroConfig, PomodoroTimer, TimerState, refresh_interval, render_timer, tone_pcm
+
+
+SOCKET_PATH = "/run/distiller-cm5-sdk/timer.sock"
+SOCKET_PATH_ENV_VAR = "DISTILLER_TIMER_SOCKET"
+
+COMMANDS = ("start", "pause", "toggle", "skip", "reset", "status", "quit")
+
+# LED colors (r, g, b) announcing the interval that starts next
+_LED_COLORS = {Phase.WORK: (255, 40, 0), Phase.SHORT_BREAK: (0, 200, 60), Phase.LONG_BREAK: (0, 80, 255)}
+
+
+def default_socket_path() -> str:
+    """Path of the control socket (DISTILLER_TIMER_SOCKET overrides the default)."""
+    return os.environ.get(SOCKET_PATH_ENV_VAR, SOCKET_PATH)
+
+
+class TimerService:
+    """Pomodoro timer bound to the display, speaker, LED, buttons and a control socket."""
+
+    def __init__(self, display, config: Optional[PomodoroConfig] = None, audio=None, led=None,
+                 buttons: Optional[ButtonSource] = None, socket_path: Optional[str] = None):
+        """
+        Args:
+            display: Initialized Display
+            config: Interval lengths; None uses the defaults
+            audio: Optional Audio used for the end-of-interval tone
+            led: Optional LED blinked when an interval ends
+            buttons: Optional button source
+            socket_path: Control socket path; None uses DISTILLER_TIMER_SOCKET or
+                         /run/distiller-cm5-sdk/timer.sock, "" disables IPC
+        """
+        self.display = display
+        self.timer = PomodoroTimer(config)
+        self.audio = audio
+        self.led = led
+        self.buttons = buttons
+        self.socket_path = default_socket_path() if socket_path is None else socket_path
+        self._log = get_logger("timer")
+        self._lock = threading.Lock()
+        self._wake = threading.Event()
+        self._stop = threading.Event()
+        self._server: Optional[socket.socket] = None
+        self._shown: Optional[tuple] = None
+
+    # -- commands ---------------------------------------------------------------
+
+    def command(self, name: str) -> dict:
+        """
+        Apply a command from a button or IPC client.
+
+        Returns:
+            The timer status, with "ok" False for an unknown command
+        """
+        name = name.strip().lower()
+        with self._lock:
+            if name not in COMMANDS:
+                return {"ok": False, "error": f"unknown command '{name}'", **self.timer.status()}
+            if name == "start":
+                self.timer.start()
+            elif name == "pause":
+                self.timer.pause()
+            elif name == "toggle":
+                self.timer.toggle()
+            elif name == "skip":
+                self.timer.skip()
+            elif name == "reset":
+                self.timer.reset()
+            elif name == "quit":
+                self._stop.set()
+            status = self.timer.status()
+        if name != "status":
+            self._wake.set()
+        return {"ok": True, **status}
+
+    def stop(self) -> None:
+        """End run() from another thread."""
+        self._stop.set()
+        self._wake.set()
+
+    # -- outputs ----------------------------------------------------------------
+
+    def _alert(self, next_phase: Phase) -> None:
+        if self.audio is not None:
+            try:
+                self.audio.stream_play(tone_pcm(sample_rate=self.audio.sample_rate, channels=self.audio.channels),
+                                       format_type="S16_LE")
+            except Exception as e:
+                print(f"Warning: Timer tone failed: {e}")
+        if self.led is not None:
+            self.led.blink_led(*_LED_COLORS[next_phase], count=3, on_time=0.3, off_time=0.3)
+
+    def _redraw(self, force_full: bool = False) -> None:
+        with self._lock:
+            width, height = self.display.get_dimensions()
+            frame = render_timer(self.timer, width, height).to_bytes()
+            key = (self.timer.phase, self.timer.state)
+        # A new phase or pause state is redrawn fully, countdown steps partially
+        full = force_full or key != self._shown
+        self.display.display_image(frame, DisplayMode.FULL if full else DisplayMode.PARTIAL)
+        self._shown = key
+
+    # -- inputs -----------------------------------------------------------------
+
+    def _serve(self) -> None:
+        while not self._stop.is_set():
+            try:
+                conn, _ = self._server.accept()
+            except OSError:
+                return  # socket closed by run()
+            with conn:
+                conn.settimeout(2.0)
+                try:
+                    request = conn.makefile("r").readline()
+                    conn.sendall((json.dumps(self.command(request)) + "\n").encode())
+                except OSError as e:
+                    self._log.info("control connection failed: %s", e)
+
+    def _read_buttons(self) -> None:
+        actions = {Button.SELECT: "toggle", Button.UP: "skip", Button.DOWN: "reset"}
+        while not self._stop.is_set():
+            button = self.buttons.read(timeout=0.5)
+            if button is not None:
+                self.command(actions[button])
+
+    def _open_socket(self) -> None:
+        directory = os.path.dirname(self.socket_path)
+        if directory:
+            os.makedirs(directory, exist_ok=True)
+        if os.path.exists(self.socket_path):
+            os.unlink(self.socket_path)  # left over from a previous run
+        self._server = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        self._server.bind(self.socket_path)
+        self._server.listen(4)
+
+    # -- main loop --------------------------------------------------------------
+
+    def run(self) -> None:
+        """Run until stop() or a "quit" command."""
+        threads = []
+        if self.socket_path:
+            self._open_socket()
+            threads.append(threading.Thread(target=self._serve, name="timer-ipc", daemon=True))
+        if self.buttons is not None:
+            threads.append(threading.Thread(target=self._read_buttons, name="timer-buttons", daemon=True))
+        for thread in threads:
+            thread.start()
+
+        try:
+            self._redraw(force_full=True)
+            while not self._stop.is_set():
+                with self._lock:
+                    remaining = self.timer.remaining()
+                    running = self.timer.state == TimerState.RUNNING
+                # Sleep to the next redraw step of the countdown, or until a command arrives
+                if running:
+                    step = refresh_interval(remaining)
+                    timeout = remaining % step
+                    if timeout < 0.5:
+                        timeout += step  # just redrawn at this step
+                    timeout = min(remaining, timeout)
+                else:
+                    timeout = None
+                woken = self._wake.wait(timeout)
+                self._wake.clear()
+                if self._stop.is_set():
+                    break
+                with self._lock:
+                    finished = self.timer.tick()
+                if finished is not None:
+                    self._log.info("%s finished", finished.value)
+                    self._alert(self.timer.phase)
+                if finished is not None or woken or running:
+                    self._redraw()
+        finally:
+            if self._server is not None:
+                self._server.close()
+                try:
+                    os.unlink(self.socket_path)
+                except OSError:
+                    pass
+
+
+def send_command(command: str, path: Optional[str] = None, timeout: float = 5.0) -> dict:
+    """
+    Send a command to a running timer service.
+
+    Returns:
+        The service's reply
+
+    Raises:
+        OSError: If the service is not running or does not answer
+    """
+    with socket.socket(socket.AF_UNIX, socket.SOCK_STREAM) as conn:
+        conn.settimeout(timeout)
+        conn.connect(path or default_socket_path())
+        conn.sendall((command + "\n").encode())
+        return json.loads(conn.makefile("r").readline())
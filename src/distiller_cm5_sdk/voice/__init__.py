@@ -0,0 +1,2 @@
+from .bridge import BridgeError, BridgeResult, PiperSpeaker, SentenceChunker, SpeechBridge
+from .intents import INTENT_EVENT_PREFIX, INTENT_UNRECOGNIZED, IntentError, IntentMatch, IntentMatcher, load_grammar
@@ -0,0 +1,146 @@
+#!/usr/bin/env python3
+"""
+Token stream to speech and display bridge unit tests for CM5 SDK.
+"""
+
+import sys
+import threading
+import time
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.diagnostics.tracing import interaction
+from distiller_cm5_sdk.hardware.eink import Display, StreamingTextRenderer
+from distiller_cm5_sdk.hardware.eink.backends.mock import add_frame_listener, remove_frame_listener
+from distiller_cm5_sdk.voice import BridgeError, SentenceChunker, SpeechBridge
+
+
+class FakeSpeaker:
+    """Records what it speaks; blocks in speak() until released when gated."""
+
+    def __init__(self, gated=False, fail_on=None):
+        self.spoken = []
+        self.stopped = 0
+        self.fail_on = fail_on
+        self.gate = threading.Semaphore(0) if gated else None
+        self.speaking = threading.Event()
+
+    def speak(self, text):
+        self.speaking.set()
+        if text == self.fail_on:
+            raise RuntimeError("no audio device")
+        if self.gate is not None:
+            self.gate.acquire()
+        self.spoken.append(text)
+
+    def stop(self):
+        self.stopped += 1
+        if self.gate is not None:
+            self.gate.release()
+
+
+class TestSentenceChunker(unittest.TestCase):
+    """Test cases for cutting streamed text into sentences."""
+
+    def test_sentences(self):
+        """Test sentences end at punctuation and whitespace, not at abbreviations or decimals."""
+        chunker = SentenceChunker()
+        text = "Hello there! It is 3.5 degrees, e.g. cold. Dr. Who said \"Run.\" Then\nnew line"
+        out = []
+        for i in range(0, len(text), 3):
+            out += chunker.feed(text[i:i + 3])
+        out.append(chunker.flush())
+        self.assertEqual(out, ["Hello there! ", "It is 3.5 degrees, e.g. cold. ", "Dr. Who said \"Run.\" ",
+                               "Then\n", "new line"])
+        self.assertEqual("".join(out), text)
+        self.assertIsNone(chunker.flush())
+
+    def test_long_sentence(self):
+        """Test a long sentence is cut at its last clause or word break."""
+        chunker = SentenceChunker(max_chars=15)
+        self.assertEqual(chunker.feed("first part, second part and more"), ["first part, ", "second part "])
+        self.assertEqual(chunker.flush(), "and more")
+
+
+class TestSpeechBridge(unittest.TestCase):
+    """Test cases for SpeechBridge."""
+
+    def test_speak_and_show(self):
+        """Test sentences are spoken in order and the whole text ends up on the display."""
+        frames = []
+        on_frame = lambda transport, frame: frames.append(frame)
+        add_frame_listener(on_frame)
+        self.addCleanup(remove_frame_listener, on_frame)
+        speaker = FakeSpeaker()
+        with Display(backend="mock") as display:
+            renderer = StreamingTextRenderer(display, min_interval_s=0.05)
+            bridge = SpeechBridge(speaker, renderer)
+            with interaction("voice") as trace:
+                result = bridge.run(iter(["Hi", " there.", " How are", " you?"]))
+            renderer.close()
+            self.assertEqual(renderer.text, "Hi there. How are you?")
+            self.assertEqual(frames[-1], renderer.render(renderer.text).to_bytes())
+        self.assertEqual(speaker.spoken, ["Hi there.", "How are you?"])
+        self.assertEqual(result.text, "Hi there. How are you?")
+        self.assertEqual(result.sentences, speaker.spoken)
+        self.assertFalse(result.cancelled)
+        self.assertEqual(trace.stages[0].name, "first_token")
+
+    def test_backpressure_and_cancel(self):
+        """Test the stream is paused while the speaker is behind, and cancel() stops both."""
+        pulled = []
+        closed = threading.Event()
+
+        def tokens():
+            try:
+                for i in range(100):
+                    pulled.append(i)
+                    yield f"Sentence {i}. "
+            finally:
+                closed.set()
+
+        speaker = FakeSpeaker(gated=True)
+        bridge = SpeechBridge(speaker, max_pending=2)
+        results = []
+        thread = threading.Thread(target=lambda: results.append(bridge.run(tokens())))
+        thread.start()
+        speaker.speaking.wait(1)
+        time.sleep(0.2)
+        # One sentence being spoken, two waiting, one blocked trying to get in line
+        self.assertLessEqual(len(pulled), 5)
+
+        speaker.gate.release()
+        time.sleep(0.1)
+        bridge.cancel()
+        thread.join(2)
+        self.assertFalse(thread.is_alive())
+        self.assertTrue(closed.is_set())
+        self.assertTrue(results[0].cancelled)
+        self.assertEqual(speaker.stopped, 1)
+        self.assertEqual(results[0].sentences, ["Sentence 0."])
+        self.assertLess(len(pulled), 10)
+
+    def test_sync_and_errors(self):
+        """Test sync mode shows sentences as they are spoken, and a speaker failure is raised after the text is shown."""
+        speaker = FakeSpeaker(fail_on="Two.")
+        shown = []
+
+        class Renderer:
+            def append(self, text):
+                shown.append((text, list(speaker.spoken)))
+
+            def flush(self):
+                pass
+
+        bridge = SpeechBridge(speaker, Renderer(), sync=True)
+        with self.assertRaises(BridgeError):
+            bridge.run(["One. ", "Two. ", "Three. "])
+        self.assertEqual(speaker.spoken, ["One."])
+        self.assertEqual(shown[:2], [("One. ", []), ("Two. ", ["One."])])
+
+
+if __name__ == "__main__":
+    unittest.main()
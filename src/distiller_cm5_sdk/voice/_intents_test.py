@@ -0,0 +1,103 @@
+#!/usr/bin/env python3
+"""
+Offline voice command matcher unit tests for CM5 SDK.
+"""
+
+import os
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+
+# Make the distiller_cm5_sdk package importable when run from this directory
+sys.path.insert(0, str(Path(__file__).resolve().parents[2]))
+
+from distiller_cm5_sdk.events import EventBus
+from distiller_cm5_sdk.voice import IntentError, IntentMatcher, load_grammar
+from distiller_cm5_sdk.voice.intents import parse_number
+
+
+class TestIntentMatcher(unittest.TestCase):
+    """Test cases for matching utterances against a grammar."""
+
+    def setUp(self):
+        self.matcher = IntentMatcher()
+        self.matcher.add_slot("direction", {"up": ["up", "louder"], "down": ["down", "quieter"]})
+        self.matcher.add("next_page", "[go to the] next page", "page (forward|down)")
+        self.matcher.add("volume", "[turn] [the] volume {direction}", "(make it|speak) {direction}")
+        self.matcher.add("timer", "set [a] timer for {minutes:number} minutes")
+        self.matcher.add("play", "play {song:text}")
+
+    def match(self, text):
+        result = self.matcher.match(text)
+        return None if result is None else (result.intent, result.slots)
+
+    def test_match(self):
+        """Test alternatives, optional words, fillers, slots and synonyms."""
+        self.assertEqual(self.match("Next page."), ("next_page", {}))
+        self.assertEqual(self.match("Um, go to the next page, please"), ("next_page", {}))
+        self.assertEqual(self.match("page down"), ("next_page", {}))
+        self.assertEqual(self.match("Turn the volume up!"), ("volume", {"direction": "up"}))
+        self.assertEqual(self.match("make it quieter"), ("volume", {"direction": "down"}))
+        self.assertEqual(self.match("Set a timer for twenty-five minutes"), ("timer", {"minutes": 25}))
+        self.assertEqual(self.match("set timer for 5 minutes"), ("timer", {"minutes": 5}))
+        self.assertEqual(self.match("play the next page"), ("play", {"song": "the next page"}))
+        self.assertIsNone(self.match("next page now"))
+        self.assertIsNone(self.match("what time is it"))
+        self.assertIsNone(self.match("please"))
+
+    def test_fuzzy(self):
+        """Test slightly misrecognized words match at a lower score, unless fuzzy matching is off."""
+        result = self.matcher.match("turn the volum up")
+        self.assertEqual(result.intent, "volume")
+        self.assertLess(result.score, 1.0)
+        self.assertIsNone(self.matcher.match("turn the valley up"))
+        self.matcher.fuzzy = False
+        self.assertIsNone(self.matcher.match("turn the volum up"))
+
+    def test_numbers(self):
+        """Test spelled-out numbers."""
+        self.assertEqual(parse_number("one hundred and five".split()), 105)
+        self.assertEqual(parse_number("two thousand twenty".split()), 2020)
+        self.assertIsNone(parse_number("five six".split()))
+        self.assertIsNone(parse_number(["hundred"]))
+
+    def test_publish(self):
+        """Test recognized and unrecognized utterances are published as intent events."""
+        bus = EventBus()
+        events = []
+        bus.subscribe("intent.*", events.append)
+        self.matcher.publish("volume louder", bus)
+        self.matcher.publish("sing a song", bus)
+        self.assertEqual([e.name for e in events], ["intent.volume", "intent.unrecognized"])
+        self.assertEqual(events[0].data["slots"], {"direction": "up"})
+        self.assertEqual(events[1].data, {"text": "sing a song"})
+
+    def test_grammar_errors(self):
+        """Test malformed patterns and unknown slot types are reported."""
+        for pattern in ("(next|previous page", "next ] page", "[]", "{} page"):
+            with self.assertRaises(IntentError, msg=pattern):
+                self.matcher.add("broken", pattern)
+        self.matcher.add("color", "make it {color}")
+        with self.assertRaisesRegex(IntentError, "color"):
+            self.matcher.match("make it red")
+
+    def test_load_grammar(self):
+        """Test intents and slot types load from a grammar file."""
+        with tempfile.TemporaryDirectory() as tmpdir:
+            path = os.path.join(tmpdir, "commands.grammar")
+            Path(path).write_text("# Reader commands\n"
+                                  "next_page: [go to the] next page\n"
+                                  "light: (turn|switch) {state} [the] light\n"
+                                  "$state: on\n"
+                                  "$state: off = off, out\n")
+            matcher = load_grammar(path)
+            self.assertEqual(matcher.intents, ["next_page", "light"])
+            self.assertEqual(matcher.match("switch out the light").slots, {"state": "off"})
+            Path(path).write_text("next page\n")
+            with self.assertRaisesRegex(IntentError, ":1:"):
+                load_grammar(path)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,272 @@
+#!/usr/bin/env python3
+"""
+Bridge from a language model's token stream to speech and the display.
+
+    bridge = SpeechBridge(speaker=PiperSpeaker(Piper()), renderer=StreamingTextRenderer(display, scale=2))
+    bus.subscribe(WAKE_WORD, lambda event: bridge.cancel())     # barge-in
+    result = bridge.run(llm.stream(prompt))
+
+run() pulls tokens from the stream, shows them on the display as they
+arrive and cuts them into sentences, each spoken as soon as it is
+complete while the next ones are still generated. Speech is the slow part:
+when max_pending sentences are waiting to be spoken, run() stops pulling
+tokens until one has been spoken, so the model is paused rather than its
+output piling up. The display never holds the stream back; the renderer
+coalesces text that arrives faster than the panel refreshes.
+
+With sync=True each sentence appears on the display only when it starts
+being spoken, so the text does not run ahead of the voice.
+
+cancel(), e.g. when the user starts talking again, stops the speech in
+progress, drops the sentences waiting for it and closes the token stream.
+"""
+
+import queue
+import re
+import threading
+from dataclasses import dataclass
+from typing import Any, Iterable, List, Optional
+
+from ..diagnostics.tracing import current_interaction, record_stage
+
+
+# Stage recorded, in an active trace interaction, when the first token arrives
+STAGE_FIRST_TOKEN = "first_token"
+
+# End of a sentence: terminal punctuation, optionally closing quotes or
+# brackets, then whitespace
+_SENTENCE_END = re.compile(r"[.!?…]+[\"')\]]*\s+|\n+")
+_CLAUSE_END = re.compile(r"[,;:]\s+")
+# Followed by a space, these do not end a sentence
+_ABBREVIATIONS = {"mr.", "mrs.", "ms.", "dr.", "prof.", "st.", "vs.", "etc.", "e.g.", "i.e.", "no."}
+
+
+class BridgeError(Exception):
+    """Custom exception for speech bridge errors."""
+    pass
+
+
+class SentenceChunker:
+    """Cuts streamed text into sentences for speech."""
+
+    def __init__(self, max_chars: int = 200):
+        """
+        Args:
+            max_chars: A sentence longer than this is cut at its last clause
+                       or word break, so speech starts without waiting for its end
+        """
+        self.max_chars = max_chars
+        self._buffer = ""
+
+    def feed(self, text: str) -> List[str]:
+        """
+        Add streamed text.
+
+        Returns:
+            Sentences completed by it, each with its trailing whitespace, so
+            joining everything returned reproduces the text
+        """
+        self._buffer += text
+        sentences = []
+        start = 0
+        for match in _SENTENCE_END.finditer(self._buffer):
+            words = self._buffer[start:match.start() + 1].split()
+            if match.group()[0] == "." and words and words[-1].lower() in _ABBREVIATIONS:
+                continue
+            sentences.append(self._buffer[start:match.end()])
+            start = match.end()
+        self._buffer = self._buffer[start:]
+
+        while len(self._buffer) > self.max_chars:
+            head = self._buffer[:self.max_chars]
+            breaks = list(_CLAUSE_END.finditer(head)) or list(re.finditer(r"\s+", head))
+            cut = breaks[-1].end() if breaks else self.max_chars
+            sentences.append(self._buffer[:cut])
+            self._buffer = self._buffer[cut:]
+        return sentences
+
+    def flush(self) -> Optional[str]:
+        """The unfinished last sentence, or None if there is none."""
+        rest, self._buffer = self._buffer, ""
+        return rest if rest.strip() else None
+
+
+class PiperSpeaker:
+    """Speaks through a piper.Piper voice, stoppable from another thread."""
+
+    def __init__(self, piper, volume: int = 50, sound_card_name: Optional[str] = None):
+        self.piper = piper
+        self.volume = volume
+        self.sound_card_name = sound_card_name
+
+    def speak(self, text: str) -> None:
+        self.piper.speak_stream(text, self.volume, self.sound_card_name)
+
+    def stop(self) -> None:
+        self.piper.stop()
+
+
+@dataclass
+class BridgeResult:
+    """What a SpeechBridge.run() call got through."""
+    text: str                   # all text received from the stream
+    sentences: List[str]        # sentences spoken in full
+    cancelled: bool
+
+
+class SpeechBridge:
+    """
+    Feeds a token stream to a speaker, sentence by sentence, and to a
+    StreamingTextRenderer.
+
+    The speaker is any object with speak(text), which blocks until the text
+    has been spoken, and optionally stop(), which makes a speak() in
+    progress return early from another thread.
+    """
+
+    def __init__(self, speaker: Any = None, renderer: Any = None, max_pending: int = 2, sync: bool = False,
+                 chunker: Optional[SentenceChunker] = None):
+        """
+        Args:
+            speaker: Speaks each sentence, e.g. PiperSpeaker; None for text only
+            renderer: hardware.eink.StreamingTextRenderer, or None for speech only
+            max_pending: Sentences allowed to wait for the speaker before the
+                         token stream is paused
+            sync: Show each sentence only as it starts being spoken (needs a speaker)
+            chunker: Sentence chunker; a new SentenceChunker() if None
+        """
+        if max_pending < 1:
+            raise ValueError("max_pending must be at least 1")
+        self.speaker = speaker
+        self.renderer = renderer
+        self.max_pending = max_pending
+        self.sync = sync and speaker is not None
+        self.chunker = chunker or SentenceChunker()
+        self._cancel = threading.Event()
+        self._running = threading.Lock()
+
+    def run(self, tokens: Iterable[str]) -> BridgeResult:
+        """
+        Speak and show a token stream; blocks until it has been spoken and shown in full, or cancelled.
+
+        Args:
+            tokens: Text fragments in order, e.g. a generator streaming a model's output
+
+        Returns:
+            BridgeResult
+
+        Raises:
+            BridgeError: If a run is already in progress, or the speaker failed;
+                         the display is still brought up to date
+        """
+        if not self._running.acquire(blocking=False):
+            raise BridgeError("SpeechBridge is already running")
+        try:
+            self._cancel.clear()
+            return self._run(tokens)
+        finally:
+            self._running.release()
+
+    def cancel(self) -> None:
+        """Stop speaking and stop reading tokens, e.g. on barge-in; run() returns with cancelled set."""
+        self._cancel.set()
+        stop = getattr(self.speaker, "stop", None)
+        if stop is not None:
+            try:
+                stop()
+            except Exception as e:
+                print(f"Warning: Failed to stop speaking: {e}")
+
+    @property
+    def cancelled(self) -> bool:
+        return self._cancel.is_set()
+
+    def _run(self, tokens: Iterable[str]) -> BridgeResult:
+        sentences: "queue.Queue[Optional[str]]" = queue.Queue(self.max_pending)
+        spoken: List[str] = []
+        errors: List[Exception] = []
+        worker = None
+        if self.speaker is not None:
+            worker = threading.Thread(target=self._speak_sentences, args=(sentences, spoken, errors,
+                                                                          current_interaction()),
+                                      name="speech-bridge", daemon=True)
+            worker.start()
+
+        received = []
+        iterator = iter(tokens)
+        try:
+            for token in iterator:
+                if self.cancelled or errors:
+                    break
+                if not received:
+                    record_stage(STAGE_FIRST_TOKEN)
+                received.append(token)
+                if self.renderer is not None and not self.sync:
+                    self.renderer.append(token)
+                for sentence in self.chunker.feed(token):
+                    self._put(sentences, sentence)
+            rest = self.chunker.flush()
+            if rest is not None:
+                self._put(sentences, rest)
+        finally:
+            self.chunker.flush()
+            close = getattr(iterator, "close", None)
+            if close is not None and (self.cancelled or errors):
+                close()
+            if worker is not None:
+                self._put(sentences, None, force=True)
+                worker.join()
+            if self.renderer is not None:
+                self.renderer.flush()
+
+        if errors and not self.cancelled:
+            raise BridgeError(f"Speaking failed: {errors[0]}") from errors[0]
+        return BridgeResult("".join(received), spoken, self.cancelled)
+
+    def _put(self, sentences: "queue.Queue[Optional[str]]", sentence: Optional[str], force: bool = False) -> None:
+        """Queue a sentence, waiting while the speaker is max_pending behind; gives up when cancelled."""
+        if self.speaker is None:
+            return
+        while not self.cancelled or force:
+            try:
+                sentences.put(sentence, timeout=0.1)
+                return
+            except queue.Full:
+                if self.cancelled:
+                    # Make room for the end marker; the sentences are dropped anyway
+                    self._drain(sentences)
+
+    @staticmethod
+    def _drain(sentences: "queue.Queue[Optional[str]]") -> None:
+        try:
+            while True:
+                sentences.get_nowait()
+        except queue.Empty:
+            pass
+
+    def _speak_sentences(self, sentences, spoken, errors, trace) -> None:
+        if trace is not None:
+            with trace.attach():
+                self._speak_loop(sentences, spoken, errors)
+        else:
+            self._speak_loop(sentences, spoken, errors)
+
+    def _speak_loop(self, sentences, spoken, errors) -> None:
+        while True:
+            sentence = sentences.get()
+            if sentence is None:
+                return
+            if self.cancelled:
+                continue
+            if self.sync and self.renderer is not None:
+                self.renderer.append(sentence)
+            text = sentence.strip()
+            if not text or errors:
+                continue
+            try:
+                self.speaker.speak(text)
+            except Exception as e:
+                errors.append(e)
+                continue
+            if not self.cancelled:
+                spoken.append(text)
@@ -0,0 +1,371 @@
+#!/usr/bin/env python3
+"""
+Offline voice commands for CM5 SDK.
+
+Matches transcribed speech against a small grammar of commands, with no
+language model or network, and publishes the result on the event bus:
+
+    matcher = IntentMatcher()
+    matcher.add_slot("direction", {"up": ["up", "louder"], "down": ["down", "quieter"]})
+    matcher.add("next_page", "[go to the] next page", "page (forward|down)")
+    matcher.add("volume", "[turn] [the] volume {direction}", "(make it|speak) {direction}")
+    matcher.add("timer", "set [a] timer for {minutes:number} minutes")
+
+    for text in parakeet.transcribe_buffer(audio):
+        matcher.publish(text)       # e.g. intent.volume with slots {"direction": "up"}
+
+Pattern syntax, over whole words, case and punctuation ignored:
+
+    word            the word itself
+    (a|b c)         one of the alternatives
+    [a|b c]         optional, or one of the alternatives
+    {slot}          a value of the slot type of that name
+    {name:type}     a value of slot type type, reported as name
+
+Slot types are added with add_slot(); "number" (digits or words such as
+"twenty five") and "text" (one or more words of anything) are built in. A
+pattern must match the whole utterance; filler words such as "um" and
+"please" are ignored. With fuzzy matching, a word that speech recognition
+got slightly wrong ("volum") still matches, at a lower score.
+"""
+
+import difflib
+import re
+from dataclasses import dataclass, field
+from typing import Any, Dict, Iterable, List, Optional, Sequence, Tuple, Union
+
+from ..events.bus import EventBus, get_bus
+
+
+INTENT_EVENT_PREFIX = "intent."
+INTENT_UNRECOGNIZED = "intent.unrecognized"
+
+DEFAULT_FILLERS = ("um", "uh", "erm", "please", "hey")
+
+# Lowest similarity at which a misrecognized word still matches a literal
+FUZZY_RATIO = 0.8
+# Score of each word captured by a {name:text} slot
+TEXT_SLOT_SCORE = 0.5
+
+_UNITS = {word: value for value, word in enumerate(
+    "zero one two three four five six seven eight nine ten eleven twelve thirteen fourteen fifteen "
+    "sixteen seventeen eighteen nineteen".split())}
+_TENS = {word: value * 10 for value, word in enumerate(
+    "twenty thirty forty fifty sixty seventy eighty ninety".split(), start=2)}
+
+
+class IntentError(Exception):
+    """Custom exception for intent grammar errors."""
+    pass
+
+
+@dataclass
+class IntentMatch:
+    """An utterance recognized as a command."""
+    intent: str
+    slots: Dict[str, Any] = field(default_factory=dict)
+    text: str = ""
+    score: float = 1.0          # 1.0 when every word matched exactly; lower for fuzzy words and text slots
+
+
+def normalize(text: str) -> List[str]:
+    """Lowercase words of text, without punctuation."""
+    return re.findall(r"[a-z0-9']+", text.lower().replace("-", " "))
+
+
+def parse_number(words: Sequence[str]) -> Optional[int]:
+    """The number spelled by words, e.g. ["twenty", "five"] or ["25"], or None."""
+    if len(words) == 1 and words[0].isdigit():
+        return int(words[0])
+    total = current = 0
+    seen = False
+    for word in words:
+        if word in _UNITS:
+            if current % 10 or (current and current < 20):
+                return None         # "five six"
+            current += _UNITS[word]
+        elif word in _TENS:
+            if current % 100:
+                return None
+            current += _TENS[word]
+        elif word == "hundred" and seen and 0 < current < 10:
+            current *= 100
+        elif word == "thousand" and seen and 0 < current < 1000:
+            total += current * 1000
+            current = 0
+        elif word == "and" and seen:
+            continue
+        else:
+            return None
+        seen = True
+    return total + current if seen and words[-1] != "and" else None
+
+
+# -- grammar ----------------------------------------------------------------
+
+# A compiled pattern is a list of nodes:
+#   ("word", w)                      a literal word
+#   ("choice", [seq, ...], optional) alternatives, each a list of nodes
+#   ("slot", name, type)
+
+def _parse(pattern: str) -> list:
+    tokens = re.findall(r"\(|\)|\[|\]|\||\{[^}]*\}|[^\s()\[\]|{}]+", pattern.lower())
+    nodes, pos = _parse_sequence(tokens, 0, pattern)
+    if pos != len(tokens):
+        raise IntentError(f"Unbalanced '{tokens[pos]}' in pattern '{pattern}'")
+    if not nodes:
+        raise IntentError("Empty pattern")
+    return nodes
+
+
+def _parse_sequence(tokens: List[str], pos: int, pattern: str) -> Tuple[list, int]:
+    nodes = []
+    while pos < len(tokens) and tokens[pos] not in (")", "]", "|"):
+        token = tokens[pos]
+        if token in ("(", "["):
+            close = ")" if token == "(" else "]"
+            alternatives = []
+            pos += 1
+            while True:
+                sequence, pos = _parse_sequence(tokens, pos, pattern)
+                if not sequence:
+                    raise IntentError(f"Empty alternative in pattern '{pattern}'")
+                alternatives.append(sequence)
+                if pos >= len(tokens):
+                    raise IntentError(f"Missing '{close}' in pattern '{pattern}'")
+                if tokens[pos] == "|":
+                    pos += 1
+                    continue
+                if tokens[pos] != close:
+                    raise IntentError(f"Unexpected '{tokens[pos]}' in pattern '{pattern}'")
+                pos += 1
+                break
+            nodes.append(("choice", alternatives, token == "["))
+        elif token.startswith("{"):
+            name, _, slot_type = token[1:-1].strip().partition(":")
+            if not name:
+                raise IntentError(f"Empty slot in pattern '{pattern}'")
+            nodes.append(("slot", name.strip(), (slot_type or name).strip()))
+            pos += 1
+        else:
+            nodes.extend(("word", word) for word in normalize(token))
+            pos += 1
+    return nodes, pos
+
+
+class IntentMatcher:
+    """Recognizes commands in transcribed text."""
+
+    def __init__(self, fuzzy: bool = True, fillers: Iterable[str] = DEFAULT_FILLERS):
+        """
+        Args:
+            fuzzy: Let slightly misrecognized words match, at a lower score
+            fillers: Words ignored in utterances and patterns
+        """
+        self.fuzzy = fuzzy
+        self.fillers = frozenset(fillers)
+        self._intents: List[Tuple[str, list, str]] = []
+        # slot type -> [(phrase words, value)]
+        self._slots: Dict[str, List[Tuple[List[str], str]]] = {}
+
+    @property
+    def intents(self) -> List[str]:
+        """Names of the intents, in the order they were added."""
+        return list(dict.fromkeys(name for name, _, _ in self._intents))
+
+    def add_slot(self, slot_type: str, values: Union[Iterable[str], Dict[str, Iterable[str]]]) -> None:
+        """
+        Define a slot type.
+
+        Args:
+            slot_type: Name used in patterns as {slot_type} or {name:slot_type}
+            values: Phrases, each reported as itself, or a dict from the value
+                    reported to the phrases that mean it
+        """
+        if slot_type in ("number", "text"):
+            raise IntentError(f"'{slot_type}' is a built-in slot type")
+        pairs = values.items() if isinstance(values, dict) else ((value, [value]) for value in values)
+        phrases = []
+        for value, synonyms in pairs:
+            for synonym in ([synonyms] if isinstance(synonyms, str) else synonyms):
+                words = [w for w in normalize(synonym) if w not in self.fillers]
+                if words:
+                    phrases.append((words, value))
+        if not phrases:
+            raise IntentError(f"Slot type '{slot_type}' has no values")
+        self._slots[slot_type] = phrases
+
+    def add(self, intent: str, *patterns: str) -> None:
+        """
+        Add patterns recognized as intent; earlier intents win ties.
+
+        Raises:
+            IntentError: If a pattern is malformed
+        """
+        if not patterns:
+            raise IntentError(f"Intent '{intent}' needs at least one pattern")
+        for pattern in patterns:
+            nodes = self._strip_fillers(_parse(pattern))
+            self._intents.append((intent, nodes, pattern))
+
+    def _strip_fillers(self, nodes: list) -> list:
+        out = []
+        for node in nodes:
+            if node[0] == "word" and node[1] in self.fillers:
+                continue
+            if node[0] == "choice":
+                node = ("choice", [self._strip_fillers(seq) for seq in node[1]], node[2])
+            out.append(node)
+        return out
+
+    def match(self, text: str) -> Optional[IntentMatch]:
+        """
+        Recognize an utterance.
+
+        Returns:
+            The best-scoring match, or None if no pattern matches
+
+        Raises:
+            IntentError: If a pattern uses a slot type that was never added
+        """
+        words = [word for word in normalize(text) if word not in self.fillers]
+        if not words:
+            return None
+        best = None
+        for intent, nodes, _ in self._intents:
+            for end, gained, slots in self._match_seq(nodes, words, 0):
+                score = gained / len(words)
+                if end == len(words) and (best is None or score > best.score):
+                    best = IntentMatch(intent, slots, text, score)
+        return best
+
+    def publish(self, text: str, bus: Optional[EventBus] = None) -> Optional[IntentMatch]:
+        """
+        Recognize an utterance and publish intent.<name> with data text,
+        slots and score, or intent.unrecognized with text.
+
+        Args:
+            text: Transcribed speech
+            bus: Bus to publish to; defaults to get_bus()
+
+        Returns:
+            The match, or None
+        """
+        bus = bus or get_bus()
+        result = self.match(text)
+        if result is None:
+            bus.publish(INTENT_UNRECOGNIZED, text=text)
+        else:
+            bus.publish(INTENT_EVENT_PREFIX + result.intent, text=text, slots=result.slots, score=result.score)
+        return result
+
+    # Backtracking over the word list: yields (end, gained, slots) for every
+    # way the nodes match words from pos on, gained being the summed score of
+    # the words consumed
+
+    def _match_seq(self, nodes: list, words: List[str], pos: int):
+        if not nodes:
+            yield pos, 0.0, {}
+            return
+        node, rest = nodes[0], nodes[1:]
+        for end, gained, slots in self._match_node(node, words, pos):
+            for final, more, rest_slots in self._match_seq(rest, words, end):
+                yield final, gained + more, {**slots, **rest_slots}
+
+    def _match_node(self, node, words: List[str], pos: int):
+        kind = node[0]
+        if kind == "word":
+            if pos < len(words):
+                similarity = self._similarity(node[1], words[pos])
+                if similarity:
+                    yield pos + 1, similarity, {}
+        elif kind == "choice":
+            for sequence in node[1]:
+                yield from self._match_seq(sequence, words, pos)
+            if node[2]:
+                yield pos, 0.0, {}
+        else:
+            _, name, slot_type = node
+            yield from self._match_slot(name, slot_type, words, pos)
+
+    def _match_slot(self, name: str, slot_type: str, words: List[str], pos: int):
+        if slot_type == "text":
+            # Anything matches, so it scores lower than words matched by name
+            for end in range(pos + 1, len(words) + 1):
+                yield end, TEXT_SLOT_SCORE * (end - pos), {name: " ".join(words[pos:end])}
+        elif slot_type == "number":
+            for end in range(len(words), pos, -1):
+                value = parse_number(words[pos:end])
+                if value is not None:
+                    yield end, float(end - pos), {name: value}
+        else:
+            if slot_type not in self._slots:
+                raise IntentError(f"Unknown slot type '{slot_type}'")
+            for phrase, value in self._slots[slot_type]:
+                end = pos + len(phrase)
+                if end > len(words):
+                    continue
+                similarities = [self._similarity(a, b) for a, b in zip(phrase, words[pos:end])]
+                if all(similarities):
+                    yield end, sum(similarities), {name: value}
+
+    def _similarity(self, expected: str, heard: str) -> float:
+        if expected == heard:
+            return 1.0
+        if not self.fuzzy or min(len(expected), len(heard)) < 4:
+            return 0.0
+        ratio = difflib.SequenceMatcher(None, expected, heard).ratio()
+        return ratio if ratio >= FUZZY_RATIO else 0.0
+
+
+def load_grammar(path: str, matcher: Optional[IntentMatcher] = None) -> IntentMatcher:
+    """
+    Load intents and slot types from a grammar file:
+
+        # comment
+        next_page: [go to the] next page
+        next_page: page (forward|down)
+        volume: [turn] [the] volume {direction}
+        $direction: up = up, louder
+        $direction: down = down, quieter
+
+    One pattern per line; a slot value without "=" stands for itself.
+
+    Args:
+        path: Grammar file
+        matcher: Matcher to add to; a new IntentMatcher() if None
+
+    Raises:
+        IntentError: If the file cannot be read or a line is malformed
+    """
+    matcher = matcher or IntentMatcher()
+    slots: Dict[str, Dict[str, List[str]]] = {}
+    patterns: List[Tuple[str, str]] = []
+    try:
+        with open(path) as f:
+            lines = f.readlines()
+    except OSError as e:
+        raise IntentError(f"Cannot read grammar {path}: {e}")
+    for number, line in enumerate(lines, 1):
+        line = line.strip()
+        if not line or line.startswith("#"):
+            continue
+        name, sep, rest = line.partition(":")
+        name, rest = name.strip(), rest.strip()
+        if not sep or not name or not rest:
+            raise IntentError(f"{path}:{number}: expected 'intent: pattern' or '$slot: value'")
+        if name.startswith("$"):
+            value, _, synonyms = rest.partition("=")
+            value = value.strip()
+            phrases = [s.strip() for s in synonyms.split(",") if s.strip()] if synonyms else [value]
+            slots.setdefault(name[1:], {}).setdefault(value, []).extend(phrases)
+        else:
+            patterns.append((name, rest))
+    for slot_type, values in slots.items():
+        matcher.add_slot(slot_type, values)
+    for name, pattern in patterns:
+        try:
+            matcher.add(name, pattern)
+        except IntentError as e:
+            raise IntentError(f"{path}: {e}")
+    return matcher
@@ -9,6 +9,8 @@ import wave
 import threading
 import time
 
+from distiller_cm5_sdk.device.privacy import capture_allowed, is_mic_muted
+from distiller_cm5_sdk.diagnostics.tracing import STAGE_STT, record_stage
 from distiller_cm5_sdk.hardware.audio.audio import Audio
 
 logging.basicConfig(level=logging.INFO,
@@ -74,18 +76,32 @@ class Whisper:
             raise ValueError("Load Whisper Model error")
         return model
 
-    def transcribe(self, audio_path: str = None) -> Generator[str, None, None]:
+    def _decode(self, audio) -> Generator[str, None, None]:
+        # Segments are decoded as they are iterated, so only the time spent
+        # decoding counts towards the traced stage, not the caller's
+        start = time.monotonic()
         segments, info = self.model.transcribe(
-            audio_path,
+            audio,
             beam_size=self.model_config["beam_size"],
             language=self.model_config["language"]
         )
+        decode_s = time.monotonic() - start
 
         logging.info(f"Detected language '{info.language}' with probability {info.language_probability}")
 
-        for segment in segments:
+        segments = iter(segments)
+        while True:
+            start = time.monotonic()
+            segment = next(segments, None)
+            decode_s += time.monotonic() - start
+            if segment is None:
+                break
             logging.info(f"[{segment.start:.2f}s -> {segment.end:.2f}s] {segment.text}")
             yield segment.text
+        record_stage(STAGE_STT, int(decode_s * 1000), model="whisper")
+
+    def transcribe(self, audio_path: str = None) -> Generator[str, None, None]:
+        yield from self._decode(audio_path)
             
     def transcribe_buffer(self, audio_data: bytes) -> Generator[str, None, None]:
         """
@@ -99,18 +115,7 @@ class Whisper:
         """
         # Create in-memory file-like object
         buffer = io.BytesIO(audio_data)
-        
-        segments, info = self.model.transcribe(
-            buffer,
-            beam_size=self.model_config["beam_size"],
-            language=self.model_config["language"]
-        )
-
-        logging.info(f"Detected language '{info.language}' with probability {info.language_probability}")
-
-        for segment in segments:
-            logging.info(f"[{segment.start:.2f}s -> {segment.end:.2f}s] {segment.text}")
-            yield segment.text
+        yield from self._decode(buffer)
     
     def _init_audio(self):
         """Initialize PyAudio instance and get device info if needed"""
@@ -134,6 +139,9 @@ class Whisper:
     def _recording_thread(self):
         """Thread function for audio recording"""
         while self._is_recording:
+            if is_mic_muted():
+                logging.warning("Microphone muted, recording stopped")
+                break
             try:
                 data = self._stream.read(self.audio_config["chunk"])
                 self._audio_frames.append(data)
@@ -152,6 +160,10 @@ class Whisper:
             logging.warning("Already recording")
             return False
             
+        if not capture_allowed(self.__class__.__name__.lower()):
+            logging.warning("Microphone is muted by the privacy switch")
+            return False
+            
         try:
             self._init_audio()
             